@@ -0,0 +1,606 @@
+//! Decoding key presses into what they ask for. Nothing here looks at the DB
+//! or changes state; `apply_action` in `main` carries the actions out.
+
+use crate::batch::Assignment;
+use crate::clipboard::YankFormat;
+use crate::keys;
+use crate::palette::ExportFormat;
+use crate::reorder::Side;
+use crate::search::Query;
+use crate::theme::Theme;
+use crate::view::Sort;
+use crate::MenuItem;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::path::PathBuf;
+
+/// What the keys mean outside of prompts, editors and confirmations.
+pub struct InputMode {
+    pub tab: MenuItem,
+    /// Whether a count like the `5` of `5a` is being typed.
+    pub counting: bool,
+    /// Whether the key follows `keys::FOLD`.
+    pub folding: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Quit,
+    OpenQuickOpen,
+    /// Groups the Pets list by category, or stops; see `grouping`.
+    ToggleGrouping,
+    /// Folds or unfolds the selected group of the grouped Pets list.
+    ToggleGroup,
+    /// Go back and forward through the jump list; see `jumps`.
+    JumpBack,
+    JumpForward,
+    OpenPalette,
+    /// Checks the DB again and shows what is wrong with it.
+    ShowIntegrity,
+    /// Opens what is selected on the active tab: the pet of a reminder on
+    /// Home, the pets of a category on Stats.
+    Open,
+    /// Sorts the Stats tab by count or by name.
+    CycleStatsSort,
+    /// Starts recording a macro, once a register is given, or stops it.
+    ToggleRecording,
+    /// Replays a macro, once a register is given.
+    ReplayMacro,
+    ShowTab(MenuItem),
+    /// The tab at this place of the menu, counting from 0, for `1` to `9`.
+    ShowNthTab(usize),
+    NextTab,
+    PreviousTab,
+    /// Another digit of the count for the next command.
+    CountDigit(usize),
+    /// Moves the selection of the active tab's list, wrapping around at the ends.
+    Next,
+    Previous,
+    /// Selects the first or last row of the active tab's list.
+    First,
+    Last,
+    AddPets,
+    /// Asks for the name of a pet to add, taking the rest from the config.
+    QuickAdd,
+    /// Moves the marked pets, or else the selected one, to the Trash.
+    Delete,
+    /// Moves the pet with this id to the Trash.
+    DeletePet(usize),
+    CycleCategory,
+    CycleStatusFilter,
+    /// Sorts the Pets tab by the next key, or back in the DB's order.
+    CycleSort,
+    ReverseSort,
+    Search,
+    /// Writes the Pets tab as shown to a Markdown report.
+    Export,
+    // Set from the command palette, which can give the value directly.
+    SetSort(Option<Sort>),
+    SetCategory(Option<String>),
+    SetQuery(Option<Query>),
+    ExportTo(ExportFormat, PathBuf),
+    SetTheme(Theme),
+    /// Writes the selected pet to a file of its own.
+    ExportPet,
+    ImportPets,
+    MergeDb,
+    /// Opens the comparison of the DB with its backups.
+    PreviewRestore,
+    /// Asks for `reset` to be typed, then moves the DB file aside and starts
+    /// an empty one.
+    ResetDb,
+    ToggleMark,
+    ToggleVisual,
+    ClearMarks,
+    EditNotes,
+    EditBirthdate,
+    EditAge,
+    AssignOwner,
+    AddRecord,
+    AddWeight,
+    EditMicrochip,
+    EditBreed,
+    /// Turns the selected pet's row into an input for its name.
+    Rename,
+    /// Moves the selected pet on to the next adoption status.
+    CycleStatus,
+    EditPhoto,
+    EditAttribute,
+    CopyPet,
+    Yank(YankFormat),
+    /// Lists the pets yanked or cut this session, to add one again.
+    ShowYankRing,
+    ScrollNotesDown,
+    ScrollNotesUp,
+    /// Shows or hides how often the screen is redrawn, for debugging.
+    ToggleDrawRate,
+    /// Shows or hides frame times, DB timings and queue depths over the UI,
+    /// for debugging.
+    ToggleDiagnostics,
+    /// Moves the split between the pet list and the detail panes.
+    WidenList,
+    NarrowList,
+    ChooseColumns,
+    /// Lists the categories to rename or merge them.
+    ManageCategories,
+    /// Opens the overlay with the session's messages, wrapped.
+    ShowMessages,
+    PickWorkspace,
+    PickView,
+    /// From `:save-view`.
+    SaveView(String),
+    CycleAgeBuckets,
+    /// Changes how many months of pets added the Stats tab shows.
+    WidenGrowth,
+    NarrowGrowth,
+    ToggleGrowthTotal,
+    CutPet,
+    BatchEdit,
+    /// From `:set`.
+    BatchSet(Assignment),
+    PastePet(Side),
+    ScrollDetailDown,
+    ScrollDetailUp,
+    ScrollRecordsDown,
+    ScrollRecordsUp,
+    AddOwner,
+    DeleteOwner,
+    MergeDuplicates,
+    RestoreFromTrash,
+    PurgeFromTrash,
+}
+
+impl Action {
+    /// Whether the action moves a list's selection by a count of rows, so
+    /// that a run of them can be carried out as one move.
+    pub fn is_navigation(&self) -> bool {
+        matches!(self, Action::Next | Action::Previous)
+    }
+
+    /// Whether carrying out the action writes to the DB, possibly after a
+    /// prompt or confirmation.
+    pub fn changes_db(&self) -> bool {
+        matches!(
+            self,
+            Action::AddPets
+                | Action::QuickAdd
+                | Action::Delete
+                | Action::DeletePet(_)
+                | Action::EditNotes
+                | Action::EditBirthdate
+                | Action::EditAge
+                | Action::AssignOwner
+                | Action::AddRecord
+                | Action::AddWeight
+                | Action::EditMicrochip
+                | Action::EditBreed
+                | Action::Rename
+                | Action::CycleStatus
+                | Action::EditPhoto
+                | Action::EditAttribute
+                | Action::ImportPets
+                | Action::MergeDb
+                | Action::PreviewRestore
+                | Action::ResetDb
+                | Action::CopyPet
+                | Action::CutPet
+                | Action::BatchEdit
+                | Action::BatchSet(_)
+                | Action::ManageCategories
+                | Action::PastePet(_)
+                | Action::AddOwner
+                | Action::DeleteOwner
+                | Action::MergeDuplicates
+                | Action::RestoreFromTrash
+                | Action::PurgeFromTrash
+        )
+    }
+}
+
+/// The action `event` stands for in `mode`, or `None` if the key isn't bound.
+/// The bindings of the active tab are matched before the global ones, as
+/// `keys::Context` describes; questions are answered before this is asked.
+pub fn map_key(event: KeyEvent, mode: &InputMode) -> Option<Action> {
+    let KeyEvent { code, modifiers } = event;
+    if modifiers == KeyModifiers::CONTROL {
+        return match code {
+            KeyCode::Char('c') => Some(Action::Quit),
+            KeyCode::Char(keys::QUICK_OPEN) => Some(Action::OpenQuickOpen),
+            KeyCode::Char(keys::JUMP_BACK) => Some(Action::JumpBack),
+            KeyCode::Char(keys::JUMP_FORWARD) => Some(Action::JumpForward),
+            KeyCode::Char(keys::QUICK_ADD) => Some(Action::QuickAdd),
+            KeyCode::Char(keys::NEXT) => Some(Action::ScrollNotesDown),
+            KeyCode::Char(keys::PREVIOUS) => Some(Action::ScrollNotesUp),
+            KeyCode::Char(keys::NARROW_LIST_CTRL) => Some(Action::NarrowList),
+            KeyCode::Char(keys::WIDEN_LIST_CTRL) => Some(Action::WidenList),
+            KeyCode::Char(keys::DRAW_RATE) => Some(Action::ToggleDrawRate),
+            KeyCode::Char(keys::RECORD_MACRO) => Some(Action::ToggleRecording),
+            _ => None,
+        };
+    }
+    if modifiers == KeyModifiers::ALT {
+        return match code {
+            KeyCode::Char(keys::NEXT) => Some(Action::ScrollDetailDown),
+            KeyCode::Char(keys::PREVIOUS) => Some(Action::ScrollDetailUp),
+            _ => None,
+        };
+    }
+    if mode.folding {
+        return match code {
+            KeyCode::Char(keys::GROUP) if mode.tab == MenuItem::Pets => {
+                Some(Action::ToggleGrouping)
+            }
+            KeyCode::Char(keys::TOGGLE_FOLD) if mode.tab == MenuItem::Pets => {
+                Some(Action::ToggleGroup)
+            }
+            _ => None,
+        };
+    }
+    let key = match code {
+        KeyCode::Char(key) => key,
+        KeyCode::Tab => return Some(Action::NextTab),
+        KeyCode::BackTab => return Some(Action::PreviousTab),
+        KeyCode::F(keys::DIAGNOSTICS) => return Some(Action::ToggleDiagnostics),
+        KeyCode::Esc => return Some(Action::ClearMarks),
+        KeyCode::Enter if matches!(mode.tab, MenuItem::Home | MenuItem::Stats | MenuItem::Pets) => {
+            return Some(Action::Open)
+        }
+        _ => return None,
+    };
+    let tab = mode.tab;
+    let action = match key {
+        keys::QUIT => Action::Quit,
+        keys::PALETTE => Action::OpenPalette,
+        keys::INTEGRITY => Action::ShowIntegrity,
+        keys::REPLAY_MACRO => Action::ReplayMacro,
+        keys::HOME => Action::ShowTab(MenuItem::Home),
+        keys::PETS => Action::ShowTab(MenuItem::Pets),
+        keys::OWNERS => Action::ShowTab(MenuItem::Owners),
+        keys::DUPLICATES => Action::ShowTab(MenuItem::Duplicates),
+        keys::TRASH => Action::ShowTab(MenuItem::Trash),
+        keys::LOG => Action::ShowTab(MenuItem::Log),
+        keys::MESSAGES => Action::ShowMessages,
+        keys::WORKSPACES => Action::PickWorkspace,
+        keys::SAVED_VIEWS => Action::PickView,
+        keys::STATS => Action::ShowTab(MenuItem::Stats),
+        '0'..='9' if tab == MenuItem::Pets && (key != '0' || mode.counting) => {
+            Action::CountDigit(key.to_digit(10).expect("is a digit") as usize)
+        }
+        '1'..='9' => Action::ShowNthTab(key as usize - '1' as usize),
+        keys::MERGE if tab == MenuItem::Duplicates => Action::MergeDuplicates,
+        keys::RESTORE if tab == MenuItem::Trash => Action::RestoreFromTrash,
+        keys::PURGE | keys::DELETE if tab == MenuItem::Trash => Action::PurgeFromTrash,
+        keys::RENAME if tab == MenuItem::Pets => Action::Rename,
+        keys::ADD if tab == MenuItem::Owners => Action::AddOwner,
+        keys::DELETE if tab == MenuItem::Owners => Action::DeleteOwner,
+        keys::WIDEN_LIST if tab == MenuItem::Pets => Action::WidenList,
+        keys::NARROW_LIST if tab == MenuItem::Pets => Action::NarrowList,
+        keys::CHOOSE_COLUMNS if tab == MenuItem::Pets => Action::ChooseColumns,
+        keys::CUT if tab == MenuItem::Pets => Action::CutPet,
+        keys::BATCH_EDIT if tab == MenuItem::Pets => Action::BatchEdit,
+        keys::PASTE_AFTER if tab == MenuItem::Pets => Action::PastePet(Side::After),
+        keys::PASTE_BEFORE if tab == MenuItem::Pets => Action::PastePet(Side::Before),
+        keys::YANK if tab == MenuItem::Pets => Action::Yank(YankFormat::Json),
+        keys::YANK_SUMMARY if tab == MenuItem::Pets => Action::Yank(YankFormat::Summary),
+        keys::YANK_RING => Action::ShowYankRing,
+        keys::ADD => Action::AddPets,
+        keys::DELETE => Action::Delete,
+        keys::ASSIGN_OWNER => Action::AssignOwner,
+        keys::COPY_PET => Action::CopyPet,
+        keys::EDIT_NOTES => Action::EditNotes,
+        keys::EDIT_BIRTHDATE => Action::EditBirthdate,
+        keys::EDIT_AGE => Action::EditAge,
+        keys::SCROLL_RECORDS_DOWN => Action::ScrollRecordsDown,
+        keys::SCROLL_RECORDS_UP => Action::ScrollRecordsUp,
+        keys::ADD_RECORD => Action::AddRecord,
+        keys::ADD_WEIGHT => Action::AddWeight,
+        keys::EDIT_MICROCHIP => Action::EditMicrochip,
+        keys::EDIT_BREED => Action::EditBreed,
+        keys::CYCLE_STATUS => Action::CycleStatus,
+        keys::EDIT_PHOTO => Action::EditPhoto,
+        keys::EDIT_ATTRIBUTE => Action::EditAttribute,
+        keys::CYCLE_CATEGORY => Action::CycleCategory,
+        keys::CYCLE_STATUS_FILTER => Action::CycleStatusFilter,
+        keys::CYCLE_SORT if tab == MenuItem::Stats => Action::CycleStatsSort,
+        keys::AGE_BUCKETS if tab == MenuItem::Stats => Action::CycleAgeBuckets,
+        keys::WIDEN_GROWTH if tab == MenuItem::Stats => Action::WidenGrowth,
+        keys::NARROW_GROWTH if tab == MenuItem::Stats => Action::NarrowGrowth,
+        keys::GROWTH_TOTAL if tab == MenuItem::Stats => Action::ToggleGrowthTotal,
+        keys::CATEGORIES if matches!(tab, MenuItem::Pets | MenuItem::Stats) => {
+            Action::ManageCategories
+        }
+        keys::CYCLE_SORT => Action::CycleSort,
+        keys::REVERSE_SORT => Action::ReverseSort,
+        keys::SEARCH => Action::Search,
+        keys::EXPORT => Action::Export,
+        keys::EXPORT_PET => Action::ExportPet,
+        keys::IMPORT_PETS => Action::ImportPets,
+        keys::MERGE_DB => Action::MergeDb,
+        keys::RESTORE_BACKUP => Action::PreviewRestore,
+        keys::TOGGLE_MARK => Action::ToggleMark,
+        keys::VISUAL => Action::ToggleVisual,
+        keys::FIRST => Action::First,
+        keys::LAST => Action::Last,
+        keys::NEXT => Action::Next,
+        keys::PREVIOUS => Action::Previous,
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// Merges each run of the same navigation action into one with the number of
+/// times it came, e.g. a held-down `j` into one move of that many rows. Other
+/// actions are kept once each and in order, and nothing is merged across them.
+pub fn coalesce(actions: Vec<Action>) -> Vec<(Action, usize)> {
+    let mut coalesced: Vec<(Action, usize)> = Vec::new();
+    for action in actions {
+        match coalesced.last_mut() {
+            Some((last, times)) if action.is_navigation() && *last == action => *times += 1,
+            _ => coalesced.push((action, 1)),
+        }
+    }
+    coalesced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers, tab: MenuItem) -> Option<Action> {
+        let mode = InputMode {
+            tab,
+            counting: false,
+            folding: false,
+        };
+        map_key(KeyEvent { code, modifiers }, &mode)
+    }
+
+    fn plain(key_char: char, tab: MenuItem) -> Option<Action> {
+        key(KeyCode::Char(key_char), KeyModifiers::NONE, tab)
+    }
+
+    /// Every binding but answering a question is one that `map_key` knows
+    /// in its context. Global ones are tried on the Pets tab.
+    #[test]
+    fn every_binding_is_dispatched_in_its_context() {
+        for (context, modifier, key_char, description) in keys::BINDINGS {
+            let tab = match context {
+                keys::Context::Question => continue,
+                keys::Context::Tab(tab) => tab,
+                keys::Context::Global => MenuItem::Pets,
+            };
+            let code = match key_char {
+                keys::TAB => KeyCode::Tab,
+                keys::ESC => KeyCode::Esc,
+                keys::ENTER => KeyCode::Enter,
+                key_char => KeyCode::Char(key_char),
+            };
+            let modifiers = match modifier {
+                "Ctrl+" => KeyModifiers::CONTROL,
+                "Alt+" => KeyModifiers::ALT,
+                _ => KeyModifiers::NONE,
+            };
+            let mode = InputMode {
+                tab,
+                counting: false,
+                folding: modifier == keys::FOLD.to_string(),
+            };
+            assert!(
+                map_key(KeyEvent { code, modifiers }, &mode).is_some(),
+                "{} does nothing {}, though it should '{}'",
+                keys::key_name(modifier, key_char),
+                context.describe(),
+                description
+            );
+        }
+    }
+
+    #[test]
+    fn a_tabs_bindings_come_before_the_global_ones() {
+        assert_eq!(plain(keys::DELETE, MenuItem::Pets), Some(Action::Delete));
+        assert_eq!(
+            plain(keys::DELETE, MenuItem::Owners),
+            Some(Action::DeleteOwner)
+        );
+        assert_eq!(
+            plain(keys::DELETE, MenuItem::Trash),
+            Some(Action::PurgeFromTrash)
+        );
+        assert_eq!(plain(keys::ADD, MenuItem::Pets), Some(Action::AddPets));
+        assert_eq!(plain(keys::ADD, MenuItem::Owners), Some(Action::AddOwner));
+        assert_eq!(
+            plain(keys::CYCLE_SORT, MenuItem::Pets),
+            Some(Action::CycleSort)
+        );
+        assert_eq!(
+            plain(keys::CYCLE_SORT, MenuItem::Stats),
+            Some(Action::CycleStatsSort)
+        );
+    }
+
+    #[test]
+    fn a_tabs_bindings_do_nothing_elsewhere() {
+        assert_eq!(plain(keys::MERGE, MenuItem::Trash), None);
+        assert_eq!(
+            plain(keys::MERGE, MenuItem::Duplicates),
+            Some(Action::MergeDuplicates)
+        );
+        assert_eq!(plain(keys::AGE_BUCKETS, MenuItem::Pets), None);
+        assert_eq!(
+            plain(keys::CATEGORIES, MenuItem::Stats),
+            Some(Action::ManageCategories)
+        );
+        assert_eq!(
+            key(KeyCode::Enter, KeyModifiers::NONE, MenuItem::Owners),
+            None
+        );
+    }
+
+    #[test]
+    fn digits_count_on_the_pets_tab_and_pick_a_tab_elsewhere() {
+        assert_eq!(plain('3', MenuItem::Pets), Some(Action::CountDigit(3)));
+        assert_eq!(plain('3', MenuItem::Home), Some(Action::ShowNthTab(2)));
+        assert_eq!(plain('0', MenuItem::Pets), None);
+        let counting = InputMode {
+            tab: MenuItem::Pets,
+            counting: true,
+            folding: false,
+        };
+        assert_eq!(
+            map_key(
+                KeyEvent {
+                    code: KeyCode::Char('0'),
+                    modifiers: KeyModifiers::NONE
+                },
+                &counting
+            ),
+            Some(Action::CountDigit(0))
+        );
+    }
+
+    #[test]
+    fn modifiers_and_folding_come_before_the_plain_keys() {
+        assert_eq!(plain(keys::NEXT, MenuItem::Pets), Some(Action::Next));
+        assert_eq!(
+            key(
+                KeyCode::Char(keys::NEXT),
+                KeyModifiers::CONTROL,
+                MenuItem::Pets
+            ),
+            Some(Action::ScrollNotesDown)
+        );
+        assert_eq!(
+            key(KeyCode::Char(keys::NEXT), KeyModifiers::ALT, MenuItem::Pets),
+            Some(Action::ScrollDetailDown)
+        );
+        assert_eq!(
+            key(KeyCode::Char(keys::ADD), KeyModifiers::ALT, MenuItem::Pets),
+            None
+        );
+        let folding = |tab| InputMode {
+            tab,
+            counting: false,
+            folding: true,
+        };
+        let fold = KeyEvent {
+            code: KeyCode::Char(keys::TOGGLE_FOLD),
+            modifiers: KeyModifiers::NONE,
+        };
+        assert_eq!(
+            map_key(fold, &folding(MenuItem::Pets)),
+            Some(Action::ToggleGroup)
+        );
+        assert_eq!(map_key(fold, &folding(MenuItem::Owners)), None);
+    }
+
+    #[test]
+    fn coalesce_merges_a_run_of_the_same_move() {
+        let actions = vec![Action::Next, Action::Next, Action::Next];
+        assert_eq!(coalesce(actions), vec![(Action::Next, 3)]);
+    }
+
+    #[test]
+    fn coalesce_keeps_the_order_of_different_moves() {
+        let actions = vec![Action::Next, Action::Next, Action::Previous, Action::Next];
+        assert_eq!(
+            coalesce(actions),
+            vec![(Action::Next, 2), (Action::Previous, 1), (Action::Next, 1)]
+        );
+    }
+
+    #[test]
+    fn coalesce_does_not_merge_across_other_actions() {
+        let actions = vec![Action::Next, Action::Delete, Action::Next, Action::Next];
+        assert_eq!(
+            coalesce(actions),
+            vec![(Action::Next, 1), (Action::Delete, 1), (Action::Next, 2)]
+        );
+    }
+
+    #[test]
+    fn coalesce_keeps_repeated_other_actions_apart() {
+        let actions = vec![Action::Delete, Action::Delete, Action::Last];
+        assert_eq!(
+            coalesce(actions),
+            vec![(Action::Delete, 1), (Action::Delete, 1), (Action::Last, 1)]
+        );
+    }
+
+    #[test]
+    fn coalesce_of_nothing_is_nothing() {
+        assert!(coalesce(Vec::new()).is_empty());
+    }
+
+    fn on(tab: MenuItem, code: KeyCode) -> Option<Action> {
+        let mode = InputMode {
+            tab,
+            counting: false,
+            folding: false,
+        };
+        map_key(KeyEvent::new(code, KeyModifiers::NONE), &mode)
+    }
+
+    #[test]
+    fn the_sort_key_sorts_what_the_tab_shows() {
+        let sort = KeyCode::Char(keys::CYCLE_SORT);
+        assert_eq!(on(MenuItem::Stats, sort), Some(Action::CycleStatsSort));
+        assert_eq!(on(MenuItem::Pets, sort), Some(Action::CycleSort));
+        assert_eq!(on(MenuItem::Home, sort), Some(Action::CycleSort));
+    }
+
+    #[test]
+    fn enter_opens_only_on_tabs_with_something_to_open() {
+        for tab in [MenuItem::Home, MenuItem::Stats, MenuItem::Pets] {
+            assert_eq!(on(tab, KeyCode::Enter), Some(Action::Open), "{:?}", tab);
+        }
+        for tab in [MenuItem::Owners, MenuItem::Trash, MenuItem::Log] {
+            assert_eq!(on(tab, KeyCode::Enter), None, "{:?}", tab);
+        }
+    }
+
+    #[test]
+    fn deleting_in_the_trash_is_deleting_for_good() {
+        let delete = KeyCode::Char(keys::DELETE);
+        let purge = KeyCode::Char(keys::PURGE);
+        assert_eq!(on(MenuItem::Trash, delete), Some(Action::PurgeFromTrash));
+        assert_eq!(on(MenuItem::Trash, purge), Some(Action::PurgeFromTrash));
+        assert_eq!(
+            on(MenuItem::Trash, KeyCode::Char(keys::RESTORE)),
+            Some(Action::RestoreFromTrash)
+        );
+        assert_eq!(on(MenuItem::Pets, delete), Some(Action::Delete));
+    }
+
+    #[test]
+    fn stats_keys_are_bound_only_on_the_stats_tab() {
+        let keys = [
+            (keys::AGE_BUCKETS, Action::CycleAgeBuckets),
+            (keys::WIDEN_GROWTH, Action::WidenGrowth),
+            (keys::NARROW_GROWTH, Action::NarrowGrowth),
+            (keys::GROWTH_TOTAL, Action::ToggleGrowthTotal),
+        ];
+        for (key, action) in keys {
+            assert_eq!(on(MenuItem::Stats, KeyCode::Char(key)), Some(action));
+            assert_ne!(
+                on(MenuItem::Owners, KeyCode::Char(key)),
+                on(MenuItem::Stats, KeyCode::Char(key))
+            );
+        }
+    }
+
+    #[test]
+    fn moves_are_the_same_on_every_tab() {
+        for tab in [
+            MenuItem::Home,
+            MenuItem::Pets,
+            MenuItem::Stats,
+            MenuItem::Owners,
+        ] {
+            assert_eq!(on(tab, KeyCode::Char(keys::NEXT)), Some(Action::Next));
+            assert_eq!(
+                on(tab, KeyCode::Char(keys::PREVIOUS)),
+                Some(Action::Previous)
+            );
+            assert_eq!(on(tab, KeyCode::Char(keys::LAST)), Some(Action::Last));
+        }
+    }
+}
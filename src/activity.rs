@@ -0,0 +1,104 @@
+use crate::status::StatusLevel;
+use chrono::prelude::*;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Older entries are dropped once the log holds this many.
+const CAPACITY: usize = 300;
+
+pub struct ActivityEntry {
+    pub at: DateTime<Local>,
+    pub text: String,
+    pub level: StatusLevel,
+}
+
+/// What happened during this session, shown on the Log tab and optionally
+/// appended to a file.
+#[derive(Default)]
+pub struct ActivityLog {
+    entries: VecDeque<ActivityEntry>,
+    file: Option<File>,
+}
+
+impl ActivityLog {
+    /// A log that also appends every entry to the file at `path`.
+    pub fn with_file(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            entries: VecDeque::new(),
+            file: Some(file),
+        })
+    }
+
+    pub fn record(&mut self, level: StatusLevel, text: impl Into<String>) {
+        let entry = ActivityEntry {
+            at: Local::now(),
+            text: text.into(),
+            level,
+        };
+        if let Some(file) = &mut self.file {
+            let level = match entry.level {
+                StatusLevel::Info => "INFO",
+                StatusLevel::Error => "ERROR",
+            };
+            // A log file that can't be written must not take the UI down.
+            let _ = writeln!(file, "{} {} {}", entry.at.to_rfc3339(), level, entry.text);
+        }
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Newest first.
+    pub fn entries(&self) -> impl Iterator<Item = &ActivityEntry> {
+        self.entries.iter().rev()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn the_oldest_entries_go_once_it_is_full() {
+        let mut log = ActivityLog::default();
+        for n in 0..CAPACITY + 2 {
+            log.record(StatusLevel::Info, format!("entry {}", n));
+        }
+        assert_eq!(log.len(), CAPACITY);
+        let texts: Vec<_> = log.entries().map(|entry| entry.text.as_str()).collect();
+        assert_eq!(texts[0], format!("entry {}", CAPACITY + 1));
+        assert_eq!(texts[CAPACITY - 1], "entry 2");
+    }
+
+    #[test]
+    fn entries_are_appended_to_the_file_too() {
+        let path =
+            std::env::temp_dir().join(format!("pet-cli-activity-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut log = ActivityLog::with_file(&path).unwrap();
+        log.record(StatusLevel::Info, "added Rex");
+        log.record(StatusLevel::Error, "could not save: disk full");
+        let mut entries = log.entries();
+        assert_eq!(entries.next().unwrap().level, StatusLevel::Error);
+        assert_eq!(entries.next().unwrap().text, "added Rex");
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<_> = written.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" INFO added Rex"), "{}", lines[0]);
+        assert!(
+            lines[1].ends_with(" ERROR could not save: disk full"),
+            "{}",
+            lines[1]
+        );
+    }
+}
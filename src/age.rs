@@ -0,0 +1,363 @@
+//! Ages of pets without a birthdate. They are kept in months so a kitten
+//! isn't stored as 0 years old, and written `3 y 2 m` for people.
+//!
+//! The youngest animals, like foster kittens, are counted in days and
+//! weeks. Those ages are kept as a birthdate instead, and shown as `12 d`
+//! or `3 w` while the animal is young; see `YoungAges`.
+
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::sync::OnceLock;
+
+/// An age in months. In the DB it is the pet's `age_months`; pets written
+/// before months were stored have whole years in `age`, which are converted
+/// when read.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "StoredAge", into = "StoredAge")]
+pub struct Age {
+    pub months: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredAge {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    age_months: Option<usize>,
+    #[serde(default, skip_serializing)]
+    age: Option<usize>,
+}
+
+impl TryFrom<StoredAge> for Age {
+    type Error = String;
+
+    fn try_from(stored: StoredAge) -> Result<Self, Self::Error> {
+        let months = match (stored.age_months, stored.age) {
+            (Some(months), _) => months,
+            (None, Some(years)) => years
+                .checked_mul(12)
+                .ok_or_else(|| format!("age {} is too large", years))?,
+            (None, None) => return Err("missing field `age_months`".to_owned()),
+        };
+        Ok(Age { months })
+    }
+}
+
+impl From<Age> for StoredAge {
+    fn from(age: Age) -> Self {
+        StoredAge {
+            age_months: Some(age.months),
+            age: None,
+        }
+    }
+}
+
+/// `8 m` under a year, otherwise `3 y` or `3 y 2 m`.
+pub fn format(months: usize) -> String {
+    let (years, months) = (months / 12, months % 12);
+    match (years, months) {
+        (0, months) => format!("{} m", months),
+        (years, 0) => format!("{} y", years),
+        (years, months) => format!("{} y {} m", years, months),
+    }
+}
+
+/// `months` the way `parse` reads it, e.g. `1y6m`.
+pub fn format_input(months: usize) -> String {
+    format(months).replace(' ', "")
+}
+
+/// Reads an age typed as `3y`, `8m`, `1y6m` or `1y 6m` into months. A bare
+/// number is refused since it could be either unit. Whether the age is
+/// plausible is up to validation.
+pub fn parse(input: &str) -> Result<usize, String> {
+    let invalid = || {
+        format!(
+            "invalid age '{}', expected years and/or months like 3y, 8m or 1y6m",
+            input
+        )
+    };
+    let mut rest = input.trim().to_lowercase();
+    rest.retain(|c| !c.is_whitespace());
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut total: usize = 0;
+    let mut seen_years = false;
+    let mut seen_months = false;
+    let mut rest = rest.as_str();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let number: usize = rest[..digits].parse().map_err(|_| invalid())?;
+        let unit = rest[digits..].chars().next().ok_or_else(invalid)?;
+        let months = match unit {
+            'y' if !seen_years && !seen_months => {
+                seen_years = true;
+                number.checked_mul(12)
+            }
+            'm' if !seen_months => {
+                seen_months = true;
+                Some(number)
+            }
+            _ => return Err(invalid()),
+        };
+        total = months
+            .and_then(|months| total.checked_add(months))
+            .ok_or_else(invalid)?;
+        rest = &rest[digits + unit.len_utf8()..];
+    }
+    Ok(total)
+}
+
+/// Up to what age an age from a birthdate is shown in days, and then in
+/// weeks, rather than in months: the `age_in_days_below` and
+/// `age_in_weeks_below` config keys, both in days.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct YoungAges {
+    pub days_below: usize,
+    pub weeks_below: usize,
+}
+
+impl Default for YoungAges {
+    /// Days for the first 8 weeks, then weeks up to 3 months.
+    fn default() -> Self {
+        YoungAges {
+            days_below: 56,
+            weeks_below: 91,
+        }
+    }
+}
+
+static YOUNG_AGES: OnceLock<YoungAges> = OnceLock::new();
+
+/// Sets the thresholds from the config, once at startup.
+pub fn set_young_ages(young_ages: YoungAges) {
+    let _ = YOUNG_AGES.set(young_ages);
+}
+
+pub fn young_ages() -> YoungAges {
+    YOUNG_AGES.get().copied().unwrap_or_default()
+}
+
+/// `12 d` or `3 w` for an animal `days` old that `young_ages` counts in
+/// days or weeks, `None` for an older one.
+pub fn format_young(days: usize, young_ages: YoungAges) -> Option<String> {
+    if days < young_ages.days_below {
+        Some(format!("{} d", days))
+    } else if days < young_ages.weeks_below {
+        Some(format!("{} w", days / 7))
+    } else {
+        None
+    }
+}
+
+/// An age as typed where a birthdate can be set: in months as `parse`
+/// reads them, or in days as `12d` or `3w`, which are kept as a birthdate.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Typed {
+    Months(usize),
+    Days(usize),
+}
+
+/// Reads `12d` or `3w` as days, or anything `parse` reads as months. Days
+/// and weeks aren't combined with other units.
+pub fn parse_typed(input: &str) -> Result<Typed, String> {
+    let mut text = input.trim().to_lowercase();
+    text.retain(|c| !c.is_whitespace());
+    let days = text
+        .strip_suffix('d')
+        .map(|number| (number, 1))
+        .or_else(|| text.strip_suffix('w').map(|number| (number, 7)));
+    match days {
+        Some((number, unit)) => number
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_mul(unit))
+            .map(Typed::Days)
+            .ok_or_else(|| {
+                format!(
+                    "invalid age '{}', expected days or weeks like 12d or 3w",
+                    input
+                )
+            }),
+        None => parse(input).map(Typed::Months).map_err(|_| {
+            format!(
+                "invalid age '{}', expected years and/or months like 3y, 8m or 1y6m, or days or weeks like 12d or 3w",
+                input
+            )
+        }),
+    }
+}
+
+/// An age threshold of the config, like `8w` or `3m`, in days. A month is
+/// taken as a twelfth of a year.
+pub fn parse_threshold(input: &str) -> Result<usize, String> {
+    match parse_typed(input)? {
+        Typed::Days(days) => Ok(days),
+        Typed::Months(months) => months
+            .checked_mul(365)
+            .map(|days| days / 12)
+            .ok_or_else(|| format!("age {} is too large", input.trim())),
+    }
+}
+
+/// `days` the way `parse_threshold` reads it: in weeks where they are
+/// whole, e.g. `13w`.
+pub fn format_threshold(days: usize) -> String {
+    if days.is_multiple_of(7) {
+        format!("{}w", days / 7)
+    } else {
+        format!("{}d", days)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use crate::validation::{self, ValidationIssue, AGE_RANGE};
+
+    #[test]
+    fn formats_months_and_years() {
+        assert_eq!(format(0), "0 m");
+        assert_eq!(format(11), "11 m");
+        assert_eq!(format(12), "1 y");
+        assert_eq!(format(13), "1 y 1 m");
+        assert_eq!(format(38), "3 y 2 m");
+        assert_eq!(format(720), "60 y");
+        assert_eq!(format_input(18), "1y6m");
+        assert_eq!(format_input(0), "0m");
+    }
+
+    #[test]
+    fn parses_years_and_months() {
+        assert_eq!(parse("0m"), Ok(0));
+        assert_eq!(parse("0y"), Ok(0));
+        assert_eq!(parse("12m"), Ok(12));
+        assert_eq!(parse("1y"), Ok(12));
+        assert_eq!(parse("1y6m"), Ok(18));
+        assert_eq!(parse(" 1Y 6M "), Ok(18));
+        assert_eq!(parse("30m"), Ok(30));
+    }
+
+    #[test]
+    fn reads_back_what_it_formats() {
+        for months in [0, 1, 11, 12, 13, 24, 719, 720] {
+            assert_eq!(parse(&format_input(months)), Ok(months));
+            assert_eq!(parse(&format(months)), Ok(months));
+        }
+    }
+
+    #[test]
+    fn refuses_what_isnt_an_age() {
+        for input in [
+            "", " ", "3", "y", "3x", "6m1y", "1y1y", "2m3m", "-1y", "1.5y", "3 years",
+        ] {
+            assert!(parse(input).is_err(), "{:?}", input);
+        }
+        let err = parse("3").unwrap_err();
+        assert!(err.contains("3y, 8m or 1y6m"), "{}", err);
+    }
+
+    #[test]
+    fn refuses_ages_that_overflow() {
+        assert!(parse(&format!("{}y", usize::MAX)).is_err());
+        assert!(parse(&format!("{}m1m", usize::MAX)).is_err());
+        assert!(parse("99999999999999999999999m").is_err());
+    }
+
+    #[test]
+    fn absurd_ages_parse_but_fail_validation() {
+        let mut pet = pet(1, "Old", "tortoises");
+        pet.age.months = parse("200y").unwrap();
+        assert!(validation::validate(&pet).contains(&ValidationIssue::AgeOutOfRange(2400)));
+        pet.age.months = *AGE_RANGE.end();
+        assert!(validation::validate(&pet).is_empty());
+        pet.age.months = 0;
+        assert!(validation::validate(&pet).is_empty());
+    }
+
+    #[test]
+    fn young_ages_are_in_days_then_weeks_up_to_the_thresholds() {
+        let young_ages = YoungAges::default();
+        assert_eq!(format_young(0, young_ages).as_deref(), Some("0 d"));
+        assert_eq!(format_young(55, young_ages).as_deref(), Some("55 d"));
+        assert_eq!(format_young(56, young_ages).as_deref(), Some("8 w"));
+        assert_eq!(format_young(62, young_ages).as_deref(), Some("8 w"));
+        assert_eq!(format_young(90, young_ages).as_deref(), Some("12 w"));
+        assert_eq!(format_young(91, young_ages), None);
+    }
+
+    #[test]
+    fn the_thresholds_can_be_moved_or_turned_off() {
+        let weeks_only = YoungAges {
+            days_below: 0,
+            weeks_below: 28,
+        };
+        assert_eq!(format_young(0, weeks_only).as_deref(), Some("0 w"));
+        assert_eq!(format_young(27, weeks_only).as_deref(), Some("3 w"));
+        assert_eq!(format_young(28, weeks_only), None);
+        let days_only = YoungAges {
+            days_below: 30,
+            weeks_below: 0,
+        };
+        assert_eq!(format_young(29, days_only).as_deref(), Some("29 d"));
+        assert_eq!(format_young(30, days_only), None);
+        let neither = YoungAges {
+            days_below: 0,
+            weeks_below: 0,
+        };
+        assert_eq!(format_young(0, neither), None);
+    }
+
+    #[test]
+    fn typed_ages_can_be_in_days_or_weeks() {
+        assert_eq!(parse_typed("12d"), Ok(Typed::Days(12)));
+        assert_eq!(parse_typed(" 3 W "), Ok(Typed::Days(21)));
+        assert_eq!(parse_typed("0d"), Ok(Typed::Days(0)));
+        assert_eq!(parse_typed("1y6m"), Ok(Typed::Months(18)));
+        assert_eq!(
+            parse_typed("1w2d").unwrap_err(),
+            "invalid age '1w2d', expected days or weeks like 12d or 3w"
+        );
+        assert!(parse_typed(&format!("{}w", usize::MAX)).is_err());
+        assert_eq!(
+            parse_typed("3").unwrap_err(),
+            "invalid age '3', expected years and/or months like 3y, 8m or 1y6m, \
+             or days or weeks like 12d or 3w"
+        );
+    }
+
+    #[test]
+    fn thresholds_are_read_in_days_and_written_in_weeks_where_whole() {
+        assert_eq!(parse_threshold("8w"), Ok(56));
+        assert_eq!(parse_threshold("10d"), Ok(10));
+        assert_eq!(parse_threshold("3m"), Ok(91));
+        assert_eq!(parse_threshold("1y"), Ok(365));
+        assert!(parse_threshold(&format!("{}y", usize::MAX / 12)).is_err());
+        assert_eq!(format_threshold(56), "8w");
+        assert_eq!(format_threshold(91), "13w");
+        assert_eq!(format_threshold(10), "10d");
+        assert_eq!(format_threshold(0), "0w");
+        for days in [0, 10, 56, 91, 365] {
+            assert_eq!(parse_threshold(&format_threshold(days)), Ok(days));
+        }
+    }
+
+    #[test]
+    fn reads_ages_in_years_from_older_dbs() {
+        let age = |json| serde_json::from_str::<Age>(json);
+        assert_eq!(age(r#"{"age_months": 8}"#).unwrap(), Age { months: 8 });
+        assert_eq!(age(r#"{"age": 3}"#).unwrap(), Age { months: 36 });
+        assert_eq!(
+            age(r#"{"age": 3, "age_months": 5}"#).unwrap(),
+            Age { months: 5 }
+        );
+        assert!(age("{}").is_err());
+        assert!(age(&format!(r#"{{"age": {}}}"#, usize::MAX)).is_err());
+        assert_eq!(
+            serde_json::to_string(&Age { months: 36 }).unwrap(),
+            r#"{"age_months":36}"#
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! The moving parts of the UI: the spinner of the loading screen and the
+//! status message that scrolls when it is too long for the status bar. Each
+//! draw registers the animations it showed, and a tick asks for another draw
+//! only once one of those is due its next frame, so a UI without any draws
+//! nothing on ticks and one with them draws at most once a tick. tui sends
+//! only the cells that changed since the last frame to the terminal, so such
+//! a draw writes little more than the spinner or the status bar.
+
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Animation {
+    Spinner,
+    Marquee,
+}
+
+impl Animation {
+    /// How long a frame is shown. Ticks further apart skip frames rather
+    /// than draw more often.
+    fn interval(self) -> Duration {
+        match self {
+            Animation::Spinner => Duration::from_millis(100),
+            Animation::Marquee => Duration::from_millis(250),
+        }
+    }
+}
+
+struct Running {
+    animation: Animation,
+    /// What it animates, e.g. the message scrolling; it starts over from
+    /// the first frame for another one.
+    subject: String,
+    started: Instant,
+    /// The frame the last tick asked to be drawn.
+    due: u64,
+    /// Whether the last draw showed it.
+    drawn: bool,
+}
+
+pub struct Animations {
+    /// `false` keeps everything still: the spinner on its first frame and
+    /// long messages cut off.
+    enabled: bool,
+    running: Vec<Running>,
+}
+
+impl Animations {
+    pub fn new(enabled: bool) -> Animations {
+        Animations {
+            enabled,
+            running: Vec::new(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Called as a draw starts; whatever it doesn't `run` stops afterwards.
+    pub fn begin_draw(&mut self) {
+        self.running.retain(|running| running.drawn);
+        for running in &mut self.running {
+            running.drawn = false;
+        }
+    }
+
+    /// Keeps `animation` going about `subject` for as long as it is drawn.
+    pub fn run(&mut self, animation: Animation, subject: &str, now: Instant) {
+        if !self.enabled {
+            return;
+        }
+        match self
+            .running
+            .iter_mut()
+            .find(|running| running.animation == animation)
+        {
+            Some(running) if running.subject == subject => running.drawn = true,
+            Some(running) => {
+                *running = Running::new(animation, subject, now);
+            }
+            None => self.running.push(Running::new(animation, subject, now)),
+        }
+    }
+
+    /// The frame of `animation` to draw at `now`: 0 until it runs.
+    pub fn frame(&self, animation: Animation, now: Instant) -> u64 {
+        self.running
+            .iter()
+            .find(|running| running.animation == animation)
+            .map_or(0, |running| running.frame(now))
+    }
+
+    /// Whether an animation the last draw showed is due its next frame.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let mut due = false;
+        for running in self.running.iter_mut().filter(|running| running.drawn) {
+            let frame = running.frame(now);
+            due |= frame != running.due;
+            running.due = frame;
+        }
+        due
+    }
+}
+
+impl Running {
+    fn new(animation: Animation, subject: &str, now: Instant) -> Running {
+        Running {
+            animation,
+            subject: subject.to_owned(),
+            started: now,
+            due: 0,
+            drawn: true,
+        }
+    }
+
+    fn frame(&self, now: Instant) -> u64 {
+        let elapsed = now.saturating_duration_since(self.started);
+        (elapsed.as_millis() / self.animation.interval().as_millis()) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// Runs the animations drawn at `at`, as a draw does.
+    fn draw(animations: &mut Animations, running: &[(Animation, &str)], at: Instant) {
+        animations.begin_draw();
+        for (animation, subject) in running {
+            animations.run(*animation, subject, at);
+        }
+    }
+
+    #[test]
+    fn without_animations_ticks_ask_for_no_draws() {
+        let start = Instant::now();
+        let mut animations = Animations::new(true);
+        for millis in [0, 100, 250, 1000, 60_000] {
+            draw(&mut animations, &[], start + ms(millis));
+            assert!(!animations.tick(start + ms(millis)));
+        }
+    }
+
+    #[test]
+    fn frames_count_intervals_since_the_start() {
+        let start = Instant::now();
+        let mut animations = Animations::new(true);
+        draw(&mut animations, &[(Animation::Spinner, "")], start);
+        assert_eq!(animations.frame(Animation::Spinner, start), 0);
+        assert_eq!(animations.frame(Animation::Spinner, start + ms(99)), 0);
+        assert_eq!(animations.frame(Animation::Spinner, start + ms(100)), 1);
+        assert_eq!(animations.frame(Animation::Spinner, start + ms(1050)), 10);
+        assert_eq!(animations.frame(Animation::Marquee, start + ms(1050)), 0);
+        // A clock that went back stays on the first frame.
+        assert_eq!(animations.frame(Animation::Spinner, start - ms(10)), 0);
+    }
+
+    #[test]
+    fn a_tick_asks_for_a_draw_once_per_frame() {
+        let start = Instant::now();
+        let mut animations = Animations::new(true);
+        draw(&mut animations, &[(Animation::Marquee, "long")], start);
+        assert!(!animations.tick(start + ms(100)));
+        assert!(animations.tick(start + ms(250)));
+        assert!(!animations.tick(start + ms(260)));
+        assert!(!animations.tick(start + ms(499)));
+        // Ticks further apart skip frames, still asking for one draw.
+        assert!(animations.tick(start + ms(1600)));
+        assert_eq!(animations.frame(Animation::Marquee, start + ms(1600)), 6);
+    }
+
+    #[test]
+    fn an_animation_not_drawn_again_stops() {
+        let start = Instant::now();
+        let mut animations = Animations::new(true);
+        draw(&mut animations, &[(Animation::Spinner, "")], start);
+        draw(&mut animations, &[], start + ms(50));
+        assert!(!animations.tick(start + ms(200)));
+        draw(&mut animations, &[], start + ms(300));
+        assert_eq!(animations.frame(Animation::Spinner, start + ms(300)), 0);
+    }
+
+    #[test]
+    fn another_subject_starts_over() {
+        let start = Instant::now();
+        let mut animations = Animations::new(true);
+        draw(&mut animations, &[(Animation::Marquee, "one")], start);
+        draw(
+            &mut animations,
+            &[(Animation::Marquee, "one")],
+            start + ms(500),
+        );
+        assert_eq!(animations.frame(Animation::Marquee, start + ms(500)), 2);
+        draw(
+            &mut animations,
+            &[(Animation::Marquee, "two")],
+            start + ms(500),
+        );
+        assert_eq!(animations.frame(Animation::Marquee, start + ms(500)), 0);
+        assert_eq!(animations.frame(Animation::Marquee, start + ms(750)), 1);
+    }
+
+    #[test]
+    fn disabled_animations_stay_on_their_first_frame() {
+        let start = Instant::now();
+        let mut animations = Animations::new(false);
+        assert!(!animations.enabled());
+        draw(&mut animations, &[(Animation::Spinner, "")], start);
+        assert!(!animations.tick(start + ms(1000)));
+        assert_eq!(animations.frame(Animation::Spinner, start + ms(1000)), 0);
+    }
+}
@@ -0,0 +1,85 @@
+//! Custom attributes of a pet: keys and values for whatever the fixed fields
+//! don't cover, like `diet=grain-free`.
+
+use crate::Pet;
+use std::collections::BTreeSet;
+
+/// Whether `key` can name an attribute: not blank and without control
+/// characters, which would break the detail pane and CSV exports.
+pub fn valid_key(key: &str) -> bool {
+    !key.trim().is_empty() && !key.chars().any(char::is_control)
+}
+
+/// Reads `key=value` as typed into the prompt. An empty value stands for
+/// removing the attribute.
+pub fn parse_assignment(text: &str) -> Result<(String, Option<String>), String> {
+    let (key, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got '{}'", text))?;
+    let (key, value) = (key.trim(), value.trim());
+    if !valid_key(key) {
+        return Err("an attribute key can't be empty or have control characters".to_owned());
+    }
+    let value = Some(value.to_owned()).filter(|value| !value.is_empty());
+    Ok((key.to_owned(), value))
+}
+
+/// Every attribute key of `pets`, sorted, for the columns of an export.
+pub fn keys<'p>(pets: &[&'p Pet]) -> Vec<&'p str> {
+    let keys: BTreeSet<&str> = pets
+        .iter()
+        .flat_map(|pet| pet.attributes.keys().map(String::as_str))
+        .collect();
+    keys.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+
+    #[test]
+    fn keys_must_have_text_and_no_control_characters() {
+        assert!(valid_key("diet"));
+        assert!(valid_key("vet's name"));
+        assert!(valid_key("Größe"));
+        for key in ["", "  ", "di\tet", "diet\n", "\u{7f}"] {
+            assert!(!valid_key(key), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn assignments_are_trimmed_and_empty_values_remove() {
+        assert_eq!(
+            parse_assignment(" diet = grain-free ").unwrap(),
+            ("diet".to_owned(), Some("grain-free".to_owned()))
+        );
+        assert_eq!(
+            parse_assignment("note=a=b").unwrap(),
+            ("note".to_owned(), Some("a=b".to_owned()))
+        );
+        assert_eq!(
+            parse_assignment("diet=").unwrap(),
+            ("diet".to_owned(), None)
+        );
+        assert_eq!(
+            parse_assignment("diet").unwrap_err(),
+            "expected key=value, got 'diet'"
+        );
+        assert!(parse_assignment(" =x").is_err());
+    }
+
+    #[test]
+    fn keys_are_the_union_of_every_pets_sorted() {
+        let mut rex = pet(1, "Rex", "dogs");
+        rex.attributes.insert("vet".to_owned(), "Dr. Ng".to_owned());
+        rex.attributes.insert("diet".to_owned(), "raw".to_owned());
+        let mut coco = pet(2, "Coco", "cats");
+        coco.attributes
+            .insert("indoor".to_owned(), "yes".to_owned());
+        coco.attributes.insert("diet".to_owned(), "fish".to_owned());
+        let bun = pet(3, "Bun", "rabbits");
+        assert_eq!(keys(&[&rex, &coco, &bun]), ["diet", "indoor", "vet"]);
+        assert!(keys(&[&bun]).is_empty());
+    }
+}
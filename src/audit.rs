@@ -0,0 +1,443 @@
+//! `--audit`: a record of who changed which pet and how, for rescues that
+//! have to account for their animals. Every write of the DB appends a line
+//! of JSON per pet it added, deleted or changed to `audit.jsonl` next to the
+//! DB, with the pet before and after, and `pet-cli audit` prints them. Lines
+//! are only ever appended, except that the oldest are dropped once the file
+//! outgrows `audit_max_mb`.
+
+use crate::diff;
+use crate::text;
+use crate::Pet;
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "audit.jsonl";
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Add,
+    /// Moved to the Trash.
+    Trash,
+    Restore,
+    Edit,
+    /// Deleted for good.
+    Delete,
+}
+
+impl AuditAction {
+    fn name(self) -> &'static str {
+        match self {
+            AuditAction::Add => "add",
+            AuditAction::Trash => "trash",
+            AuditAction::Restore => "restore",
+            AuditAction::Edit => "edit",
+            AuditAction::Delete => "delete",
+        }
+    }
+}
+
+/// One line of the audit file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub at: DateTime<Utc>,
+    /// The login of whoever ran pet-cli, if the system says.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    pub action: AuditAction,
+    pub pet_id: usize,
+    /// The pet before the write; none for adds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Pet>,
+    /// The pet after the write; none for deletes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Pet>,
+}
+
+impl AuditRecord {
+    /// The fields that changed, for edits, trashing and restoring.
+    fn changed_fields(&self) -> Vec<String> {
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => {
+                let diff =
+                    diff::diff_pets(std::slice::from_ref(before), std::slice::from_ref(after));
+                diff.changed
+                    .into_iter()
+                    .flat_map(|change| change.fields)
+                    .map(|field| field.field)
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// How `pet-cli audit` prints the records.
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum AuditFormat {
+    #[default]
+    Table,
+    /// The records as they are in the file, one per line.
+    Json,
+}
+
+/// Which records `pet-cli audit` prints.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AuditFilter {
+    /// Only records from this day on, in UTC.
+    pub since: Option<NaiveDate>,
+    pub pet_id: Option<usize>,
+}
+
+impl AuditFilter {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        self.since
+            .is_none_or(|since| record.at.date_naive() >= since)
+            && self.pet_id.is_none_or(|pet_id| record.pet_id == pet_id)
+    }
+}
+
+/// The audit file of the DB at `db_path`.
+pub fn path_for(db_path: &Path) -> PathBuf {
+    db_path.with_file_name(FILE_NAME)
+}
+
+/// The records of a write that changed the pets from `before` to `after`.
+pub fn records(before: &[Pet], after: &[Pet], at: DateTime<Utc>) -> Vec<AuditRecord> {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok();
+    let record = |action, pet_id, before: Option<&Pet>, after: Option<&Pet>| AuditRecord {
+        at,
+        user: user.clone(),
+        action,
+        pet_id,
+        before: before.cloned(),
+        after: after.cloned(),
+    };
+    let diff = diff::diff_pets(before, after);
+    let mut records = Vec::new();
+    for pet in &diff.removed {
+        records.push(record(AuditAction::Delete, pet.id, Some(pet), None));
+    }
+    for change in &diff.changed {
+        let old = before.iter().find(|pet| pet.id == change.id);
+        let new = after.iter().find(|pet| pet.id == change.id);
+        let action = match (
+            old.is_some_and(|pet| pet.is_archived()),
+            new.is_some_and(|pet| pet.is_archived()),
+        ) {
+            (false, true) => AuditAction::Trash,
+            (true, false) => AuditAction::Restore,
+            _ => AuditAction::Edit,
+        };
+        records.push(record(action, change.id, old, new));
+    }
+    for pet in &diff.added {
+        records.push(record(AuditAction::Add, pet.id, None, Some(pet)));
+    }
+    records
+}
+
+/// Appends `records` to the file at `path`, then drops its oldest lines if
+/// it is over `max_bytes`.
+pub fn append(path: &Path, records: &[AuditRecord], max_bytes: u64) -> io::Result<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for record in records {
+        lines.push_str(&serde_json::to_string(record)?);
+        lines.push('\n');
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())?;
+    if file.metadata()?.len() > max_bytes {
+        rotate(path, max_bytes)?;
+    }
+    Ok(())
+}
+
+/// Keeps the newest whole lines of the file at `path` that fit in
+/// `max_bytes`.
+fn rotate(path: &Path, max_bytes: u64) -> io::Result<()> {
+    let content = fs::read_to_string(path)?;
+    let mut start = 0;
+    while (content.len() - start) as u64 > max_bytes {
+        match content[start..].find('\n') {
+            Some(end) => start += end + 1,
+            None => start = content.len(),
+        }
+    }
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    fs::write(&temp_path, &content[start..])?;
+    fs::rename(&temp_path, path)
+}
+
+/// The records in the file at `path` that `filter` lets through, oldest
+/// first, and a warning for each line that isn't a record.
+pub fn read(path: &Path, filter: &AuditFilter) -> io::Result<(Vec<AuditRecord>, Vec<String>)> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    let mut records = Vec::new();
+    let mut warnings = Vec::new();
+    for (number, line) in (1..).zip(content.lines()) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<AuditRecord>(line) {
+            Ok(record) if filter.matches(&record) => records.push(record),
+            Ok(_) => {}
+            Err(err) => warnings.push(format!(
+                "{} line {}: skipped, {}",
+                path.display(),
+                number,
+                err
+            )),
+        }
+    }
+    Ok((records, warnings))
+}
+
+/// `records` as `pet-cli audit` prints them.
+pub fn render(records: &[AuditRecord], format: AuditFormat) -> serde_json::Result<String> {
+    let mut out = String::new();
+    match format {
+        AuditFormat::Json => {
+            for record in records {
+                out.push_str(&serde_json::to_string(record)?);
+                out.push('\n');
+            }
+        }
+        AuditFormat::Table => {
+            let rows: Vec<[String; 5]> = records
+                .iter()
+                .map(|record| {
+                    let name = record
+                        .after
+                        .as_ref()
+                        .or(record.before.as_ref())
+                        .map_or("", |pet| pet.name.as_str());
+                    [
+                        record.at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                        record.user.clone().unwrap_or_default(),
+                        record.action.name().to_owned(),
+                        format!("#{} {}", record.pet_id, name),
+                        record.changed_fields().join(", "),
+                    ]
+                })
+                .collect();
+            let header = ["Time (UTC)", "User", "Action", "Pet", "Changed"].map(str::to_owned);
+            let mut widths = [0; 5];
+            for row in std::iter::once(&header).chain(&rows) {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(text::width(cell));
+                }
+            }
+            for row in std::iter::once(&header).chain(&rows) {
+                let cells: Vec<String> = row
+                    .iter()
+                    .zip(widths)
+                    .map(|(cell, width)| text::pad(cell, width))
+                    .collect();
+                out.push_str(cells.join("  ").trim_end());
+                out.push('\n');
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use chrono::TimeZone;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "pet-cli-audit-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = path_for(&dir.join("db.json"));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, 9, 30, 0).unwrap()
+    }
+
+    fn record(action: AuditAction, pet_id: usize, day: u32) -> AuditRecord {
+        AuditRecord {
+            at: at(day),
+            user: Some("sam".to_owned()),
+            action,
+            pet_id,
+            before: None,
+            after: Some(pet(pet_id, "Coco", "cats")),
+        }
+    }
+
+    fn actions(records: &[AuditRecord]) -> Vec<(AuditAction, usize)> {
+        records
+            .iter()
+            .map(|record| (record.action, record.pet_id))
+            .collect()
+    }
+
+    #[test]
+    fn a_write_gives_a_record_per_pet_it_changed() {
+        let before = vec![
+            pet(1, "Coco", "cats"),
+            pet(2, "Rex", "dogs"),
+            pet(3, "Bun", "rabbits"),
+            pet(4, "Max", "dogs"),
+        ];
+        let mut after = before.clone();
+        after[0].name = "Coco Chanel".to_owned();
+        after[1].archived_at = Some(at(2));
+        after.remove(2);
+        after.push(pet(5, "Pip", "birds"));
+        let written = records(&before, &after, at(2));
+        assert_eq!(
+            actions(&written),
+            [
+                (AuditAction::Delete, 3),
+                (AuditAction::Edit, 1),
+                (AuditAction::Trash, 2),
+                (AuditAction::Add, 5)
+            ]
+        );
+        assert_eq!(written[1].changed_fields(), ["name"]);
+        assert_eq!(written[1].before.as_ref().unwrap().name, "Coco");
+        assert_eq!(written[1].after.as_ref().unwrap().name, "Coco Chanel");
+        assert!(written[0].after.is_none() && written[3].before.is_none());
+        let restored = records(&after[..2], &before[..2], at(3));
+        assert_eq!(
+            actions(&restored),
+            [(AuditAction::Edit, 1), (AuditAction::Restore, 2)]
+        );
+    }
+
+    #[test]
+    fn records_are_appended_and_read_back_filtered() {
+        let path = temp_file("filter");
+        append(&path, &[record(AuditAction::Add, 42, 1)], u64::MAX).unwrap();
+        append(
+            &path,
+            &[
+                record(AuditAction::Edit, 42, 5),
+                record(AuditAction::Add, 7, 5),
+            ],
+            u64::MAX,
+        )
+        .unwrap();
+        append(&path, &[], u64::MAX).unwrap();
+        let all = read(&path, &AuditFilter::default()).unwrap().0;
+        assert_eq!(all.len(), 3);
+        let filter = AuditFilter {
+            since: NaiveDate::from_ymd_opt(2024, 3, 2),
+            pet_id: Some(42),
+        };
+        let (records, warnings) = read(&path, &filter).unwrap();
+        assert_eq!(actions(&records), [(AuditAction::Edit, 42)]);
+        assert!(warnings.is_empty());
+        let filter = AuditFilter {
+            since: NaiveDate::from_ymd_opt(2024, 3, 5),
+            pet_id: None,
+        };
+        assert_eq!(read(&path, &filter).unwrap().0.len(), 2);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn a_line_that_isnt_a_record_is_skipped_with_a_warning() {
+        let path = temp_file("corrupt");
+        append(&path, &[record(AuditAction::Add, 1, 1)], u64::MAX).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"at\": \"2024-03-0\n\n").unwrap();
+        append(&path, &[record(AuditAction::Edit, 1, 2)], u64::MAX).unwrap();
+        let (records, warnings) = read(&path, &AuditFilter::default()).unwrap();
+        assert_eq!(
+            actions(&records),
+            [(AuditAction::Add, 1), (AuditAction::Edit, 1)]
+        );
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(
+            warnings[0].contains("audit.jsonl line 2: skipped"),
+            "{}",
+            warnings[0]
+        );
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn a_missing_file_has_no_records() {
+        let path = temp_file("missing");
+        assert!(read(&path, &AuditFilter::default()).unwrap().0.is_empty());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn rotation_keeps_the_newest_whole_lines() {
+        let path = temp_file("rotate");
+        let line_len = serde_json::to_string(&record(AuditAction::Add, 1, 1))
+            .unwrap()
+            .len() as u64
+            + 1;
+        for day in 1..=9 {
+            append(
+                &path,
+                &[record(AuditAction::Add, day as usize, day)],
+                line_len * 3 + 5,
+            )
+            .unwrap();
+        }
+        let (records, warnings) = read(&path, &AuditFilter::default()).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let ids: Vec<usize> = records.iter().map(|record| record.pet_id).collect();
+        assert_eq!(ids, [7, 8, 9]);
+        assert!(fs::metadata(&path).unwrap().len() <= line_len * 3 + 5);
+        // A limit smaller than one line keeps nothing rather than half a line.
+        append(&path, &[record(AuditAction::Add, 10, 10)], 10).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn the_table_has_a_row_per_record() {
+        let mut edit = record(AuditAction::Edit, 42, 5);
+        edit.before = Some(pet(42, "Rex", "dogs"));
+        edit.after = Some(pet(42, "Rex", "cats"));
+        let mut delete = record(AuditAction::Delete, 7, 6);
+        delete.user = None;
+        delete.before = Some(pet(7, "Bun", "rabbits"));
+        delete.after = None;
+        let out = render(&[edit, delete], AuditFormat::Table).unwrap();
+        assert_eq!(
+            out,
+            "Time (UTC)           User  Action  Pet      Changed\n\
+             2024-03-05 09:30:00  sam   edit    #42 Rex  category\n\
+             2024-03-06 09:30:00        delete  #7 Bun\n"
+        );
+        let json = render(&[record(AuditAction::Add, 1, 1)], AuditFormat::Json).unwrap();
+        assert!(
+            json.starts_with("{\"at\":\"2024-03-01T09:30:00Z\""),
+            "{}",
+            json
+        );
+        assert_eq!(json.lines().count(), 1);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{compression, db_format};
+use chrono::prelude::*;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const FILE_PREFIX: &str = "db-";
+/// Basic ISO 8601, which sorts chronologically and is safe in file names.
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+pub struct Backup {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Backup {
+    /// How many pets the backup holds, `None` if it can't be parsed. Reads
+    /// the whole backup, so only for showing it.
+    pub fn pet_count(&self) -> Option<usize> {
+        compression::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| db_format::parse(&content).ok())
+            .map(|database| database.pets.len())
+    }
+}
+
+/// Backups live in `backups/<stem>/` next to the DB file, so that the DBs of
+/// workspaces in one directory each keep their own.
+pub fn backup_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("backups")
+        .join(stem(db_path))
+}
+
+/// The file name of the DB without `.json` and any compression suffix.
+fn stem(db_path: &Path) -> String {
+    let name = db_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let name = [".gz", ".zst"]
+        .iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+        .unwrap_or(&name);
+    name.strip_suffix(".json").unwrap_or(name).to_owned()
+}
+
+/// Copies the DB into a new timestamped backup and then prunes the oldest
+/// backups so at most `keep` remain. The new backup is never pruned.
+pub fn create(db_path: &Path, keep: usize) -> io::Result<PathBuf> {
+    let dir = backup_dir(db_path);
+    fs::create_dir_all(&dir)?;
+    let name = format!(
+        "{}{}.json",
+        FILE_PREFIX,
+        Utc::now().format(TIMESTAMP_FORMAT)
+    );
+    let path = dir.join(name);
+    fs::copy(db_path, &path)?;
+
+    let backups = list(db_path)?;
+    let excess = backups.len().saturating_sub(keep.max(1));
+    for old in backups
+        .iter()
+        .filter(|backup| backup.path != path)
+        .take(excess)
+    {
+        fs::remove_file(&old.path)?;
+    }
+    Ok(path)
+}
+
+/// Every backup of the DB, oldest first. Only the file names are read.
+pub fn list(db_path: &Path) -> io::Result<Vec<Backup>> {
+    let dir = backup_dir(db_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(created_at) = parse_timestamp(&path) else {
+            continue;
+        };
+        backups.push(Backup { path, created_at });
+    }
+    backups.sort_by_key(|backup| backup.created_at);
+    Ok(backups)
+}
+
+fn parse_timestamp(path: &Path) -> Option<DateTime<Utc>> {
+    let name = path.file_name()?.to_str()?;
+    let timestamp = name.strip_prefix(FILE_PREFIX)?.strip_suffix(".json")?;
+    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+/// Replaces the DB with `backup`, backing up the current DB first so the
+/// restore itself can be undone.
+pub fn restore(db_path: &Path, backup: &Path, keep: usize) -> io::Result<()> {
+    create(db_path, keep)?;
+    let mut temp_name = db_path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = db_path.with_file_name(temp_name);
+    fs::copy(backup, &temp_path)?;
+    fs::rename(&temp_path, db_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A DB of `content` in a directory of its own.
+    fn db_file(name: &str, content: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pet-cli-backup-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("db.json");
+        fs::write(&db, content).unwrap();
+        db
+    }
+
+    /// Backups of `db` from `count` minutes on 2020-01-01, oldest first.
+    fn old_backups(db: &Path, count: u32) -> Vec<PathBuf> {
+        let dir = backup_dir(db);
+        fs::create_dir_all(&dir).unwrap();
+        (0..count)
+            .map(|minute| {
+                let at = Utc.with_ymd_and_hms(2020, 1, 1, 0, minute, 0).unwrap();
+                let path = dir.join(format!(
+                    "{}{}.json",
+                    FILE_PREFIX,
+                    at.format(TIMESTAMP_FORMAT)
+                ));
+                fs::write(&path, format!("[] // {}", minute)).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    fn paths(db: &Path) -> Vec<PathBuf> {
+        list(db)
+            .unwrap()
+            .into_iter()
+            .map(|backup| backup.path)
+            .collect()
+    }
+
+    #[test]
+    fn creating_a_backup_prunes_down_to_keep() {
+        let db = db_file("prune", "[]");
+        let old = old_backups(&db, 5);
+        let new = create(&db, 3).unwrap();
+        assert_eq!(paths(&db), [old[3].clone(), old[4].clone(), new.clone()]);
+        assert_eq!(fs::read_to_string(&new).unwrap(), "[]");
+        fs::remove_dir_all(db.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn the_new_backup_survives_a_keep_of_0_or_1() {
+        for keep in [0, 1] {
+            let db = db_file(&format!("keep-{}", keep), "[]");
+            old_backups(&db, 3);
+            let new = create(&db, keep).unwrap();
+            assert_eq!(paths(&db), [new], "keep {}", keep);
+            fs::remove_dir_all(db.parent().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn restoring_backs_up_the_current_db_first() {
+        let current = r#"[{"name": "Rex"}]"#;
+        let db = db_file("restore", current);
+        let old = old_backups(&db, 1);
+        restore(&db, &old[0], 5).unwrap();
+        assert_eq!(fs::read_to_string(&db).unwrap(), "[] // 0");
+        let backups = paths(&db);
+        assert_eq!(backups.len(), 2);
+        assert_eq!(fs::read_to_string(&backups[1]).unwrap(), current);
+        fs::remove_dir_all(db.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn dbs_in_one_directory_keep_their_own_backups() {
+        let db = db_file("stems", "[]");
+        let other = db.with_file_name("garden.json.gz");
+        fs::write(&other, "").unwrap();
+        let mine = create(&db, 1).unwrap();
+        let theirs = create(&other, 1).unwrap();
+        assert_eq!(backup_dir(&other), db.with_file_name("backups/garden"));
+        assert_eq!(paths(&db), [mine]);
+        assert_eq!(paths(&other), [theirs]);
+        fs::remove_dir_all(db.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn pets_are_only_counted_when_asked() {
+        let db = db_file("count", "[]");
+        old_backups(&db, 1);
+        let new = create(&db, 5).unwrap();
+        fs::write(&new, include_str!("../tests/fixtures/migrations/v0.json")).unwrap();
+        let counts: Vec<_> = list(&db).unwrap().iter().map(Backup::pet_count).collect();
+        assert_eq!(counts, [None, Some(2)]);
+        fs::remove_dir_all(db.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+//! Setting one field to the same value on many pets at once, for `B` and
+//! `:set`. The change is worked out on a copy of the pets so that it is
+//! written for all of them or, if any would become invalid, for none.
+
+use crate::pet_status::PetStatus;
+use crate::validation::{self, ValidationIssue};
+use crate::{age, attributes, Pet};
+use clap::ValueEnum;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The fields `:set` takes, with their arguments, for help and errors.
+pub const FIELDS: &str =
+    "category NAME, status intake|available|adopted, age AGE or attr KEY=VALUE";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Assignment {
+    Category(String),
+    Status(PetStatus),
+    /// In months.
+    Age(usize),
+    /// Removes the attribute if the value is `None`.
+    Attribute(String, Option<String>),
+}
+
+impl Assignment {
+    /// Reads `FIELD VALUE`, like `category cats` or `attr diet=grain-free`.
+    pub fn parse(text: &str) -> Result<Assignment, String> {
+        let text = text.trim();
+        let (field, value) = text
+            .split_once(char::is_whitespace)
+            .map_or((text, ""), |(field, value)| (field, value.trim()));
+        if value.is_empty() {
+            return Err(format!("expected a field and a value: {}", FIELDS));
+        }
+        match field {
+            "category" => Ok(Assignment::Category(value.to_owned())),
+            "status" => PetStatus::from_str(value, true)
+                .map(Assignment::Status)
+                .map_err(|_| format!("unknown status '{}'", value)),
+            "age" => age::parse(value).map(Assignment::Age),
+            "attr" => attributes::parse_assignment(value)
+                .map(|(key, value)| Assignment::Attribute(key, value)),
+            field => Err(format!("can't set '{}', only {}", field, FIELDS)),
+        }
+    }
+
+    fn set(&self, pet: &mut Pet) {
+        match self {
+            Assignment::Category(category) => pet.category = category.clone(),
+            Assignment::Status(status) => pet.status = *status,
+            Assignment::Age(months) => pet.age.months = *months,
+            Assignment::Attribute(key, Some(value)) => {
+                pet.attributes.insert(key.clone(), value.clone());
+            }
+            Assignment::Attribute(key, None) => {
+                pet.attributes.remove(key);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Assignment::Category(category) => write!(f, "category = {}", category),
+            Assignment::Status(status) => write!(f, "status = {}", status),
+            Assignment::Age(months) => write!(f, "age = {}", age::format(*months)),
+            Assignment::Attribute(key, Some(value)) => write!(f, "{} = {}", key, value),
+            Assignment::Attribute(key, None) => write!(f, "no {}", key),
+        }
+    }
+}
+
+/// A pet the assignment would make invalid, with what would be wrong that
+/// wasn't before.
+#[derive(Debug)]
+pub struct Failure {
+    pub pet_id: usize,
+    pub name: String,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (#{}): {}",
+            self.name,
+            self.pet_id,
+            validation::describe(&self.issues)
+        )
+    }
+}
+
+/// `pets` with `assignment` made on those in `ids`, or every pet it would
+/// make invalid. Issues a pet already had don't count.
+pub fn apply(
+    mut pets: Vec<Pet>,
+    ids: &HashSet<usize>,
+    assignment: &Assignment,
+) -> Result<Vec<Pet>, Vec<Failure>> {
+    let mut failures = Vec::new();
+    for pet in pets.iter_mut().filter(|pet| ids.contains(&pet.id)) {
+        let before = validation::validate(pet);
+        assignment.set(pet);
+        let issues: Vec<_> = validation::validate(pet)
+            .into_iter()
+            .filter(|issue| !before.contains(issue))
+            .collect();
+        if !issues.is_empty() {
+            failures.push(Failure {
+                pet_id: pet.id,
+                name: pet.name.clone(),
+                issues,
+            });
+        }
+    }
+    if failures.is_empty() {
+        Ok(pets)
+    } else {
+        Err(failures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+
+    fn shelter() -> Vec<Pet> {
+        vec![
+            pet(1, "Coco", "cats"),
+            pet(2, "Rex", "dogs"),
+            pet(3, "Bun", "rabbits"),
+        ]
+    }
+
+    fn ids(ids: &[usize]) -> HashSet<usize> {
+        ids.iter().copied().collect()
+    }
+
+    #[test]
+    fn assignments_are_read_and_shown() {
+        for (text, assignment, shown) in [
+            (
+                "category  big cats ",
+                Assignment::Category("big cats".to_owned()),
+                "category = big cats",
+            ),
+            (
+                "status Adopted",
+                Assignment::Status(PetStatus::Adopted),
+                "status = adopted",
+            ),
+            ("age 1y6m", Assignment::Age(18), "age = 1 y 6 m"),
+            (
+                "attr diet=raw",
+                Assignment::Attribute("diet".to_owned(), Some("raw".to_owned())),
+                "diet = raw",
+            ),
+            (
+                "attr diet=",
+                Assignment::Attribute("diet".to_owned(), None),
+                "no diet",
+            ),
+        ] {
+            let parsed = Assignment::parse(text).unwrap();
+            assert_eq!(parsed, assignment, "{}", text);
+            assert_eq!(parsed.to_string(), shown);
+        }
+    }
+
+    #[test]
+    fn unknown_fields_and_missing_values_are_refused() {
+        assert_eq!(
+            Assignment::parse("name Rex").unwrap_err(),
+            format!("can't set 'name', only {}", FIELDS)
+        );
+        assert_eq!(
+            Assignment::parse("category").unwrap_err(),
+            format!("expected a field and a value: {}", FIELDS)
+        );
+        assert_eq!(
+            Assignment::parse("status lost").unwrap_err(),
+            "unknown status 'lost'"
+        );
+        assert!(Assignment::parse("age old").is_err());
+        assert!(Assignment::parse("attr =raw").is_err());
+    }
+
+    #[test]
+    fn only_the_chosen_pets_change() {
+        let assignment = Assignment::Category("cats".to_owned());
+        let pets = apply(shelter(), &ids(&[2, 3]), &assignment).unwrap();
+        let categories: Vec<&str> = pets.iter().map(|pet| pet.category.as_str()).collect();
+        assert_eq!(categories, ["cats", "cats", "cats"]);
+        let pets = apply(shelter(), &ids(&[2]), &Assignment::Age(30)).unwrap();
+        let ages: Vec<usize> = pets.iter().map(|pet| pet.age.months).collect();
+        assert_eq!(ages, [12, 30, 12]);
+    }
+
+    #[test]
+    fn attributes_are_set_and_removed() {
+        let set = Assignment::Attribute("diet".to_owned(), Some("raw".to_owned()));
+        let pets = apply(shelter(), &ids(&[1, 2]), &set).unwrap();
+        assert_eq!(pets[0].attributes["diet"], "raw");
+        assert!(pets[2].attributes.is_empty());
+        let remove = Assignment::Attribute("diet".to_owned(), None);
+        let pets = apply(pets, &ids(&[1]), &remove).unwrap();
+        assert!(pets[0].attributes.is_empty());
+        assert_eq!(pets[1].attributes["diet"], "raw");
+    }
+
+    #[test]
+    fn one_invalid_pet_fails_the_whole_batch_naming_each() {
+        let mut pets = shelter();
+        pets[0].age.months = 0;
+        let too_old = Assignment::Age(61 * 12);
+        let failures = apply(pets, &ids(&[1, 3]), &too_old).unwrap_err();
+        let failed: Vec<usize> = failures.iter().map(|failure| failure.pet_id).collect();
+        assert_eq!(failed, [1, 3]);
+        assert_eq!(
+            failures[1].to_string(),
+            "Bun (#3): age 61 y is outside 0 m..=60 y"
+        );
+        let blank = Assignment::Category(" ".to_owned());
+        let failures = apply(shelter(), &ids(&[2]), &blank).unwrap_err();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].issues, [ValidationIssue::EmptyCategory]);
+    }
+
+    #[test]
+    fn issues_a_pet_already_had_dont_count() {
+        let mut pets = shelter();
+        pets[1].name = String::new();
+        let assignment = Assignment::Status(PetStatus::Adopted);
+        let pets = apply(pets, &ids(&[1, 2]), &assignment).unwrap();
+        assert_eq!(pets[1].status, PetStatus::Adopted);
+    }
+}
@@ -0,0 +1,163 @@
+//! The breeds the breed prompt suggests for dogs and cats. Any breed can be
+//! typed, and for other species there are no suggestions, only free text.
+
+use crate::fuzzy::fuzzy_rank;
+
+const DOGS: [&str; 30] = [
+    "Labrador Retriever",
+    "German Shepherd",
+    "Golden Retriever",
+    "French Bulldog",
+    "Bulldog",
+    "Poodle",
+    "Beagle",
+    "Rottweiler",
+    "Dachshund",
+    "Yorkshire Terrier",
+    "Boxer",
+    "Border Collie",
+    "Siberian Husky",
+    "Cavalier King Charles Spaniel",
+    "Shih Tzu",
+    "Chihuahua",
+    "Pug",
+    "Great Dane",
+    "Doberman Pinscher",
+    "Australian Shepherd",
+    "Cocker Spaniel",
+    "Bernese Mountain Dog",
+    "Jack Russell Terrier",
+    "Maltese",
+    "Staffordshire Bull Terrier",
+    "Whippet",
+    "Greyhound",
+    "Pomeranian",
+    "Schnauzer",
+    "Mixed breed",
+];
+
+const CATS: [&str; 20] = [
+    "Domestic Shorthair",
+    "Domestic Longhair",
+    "Maine Coon",
+    "Persian",
+    "Siamese",
+    "Ragdoll",
+    "British Shorthair",
+    "Bengal",
+    "Sphynx",
+    "Abyssinian",
+    "Scottish Fold",
+    "Russian Blue",
+    "Norwegian Forest Cat",
+    "Birman",
+    "Burmese",
+    "Devon Rex",
+    "Cornish Rex",
+    "Oriental Shorthair",
+    "Savannah",
+    "Mixed breed",
+];
+
+/// The breeds known for pets of `category`, matched ignoring case and a
+/// plural `s`; empty for species without a list.
+pub fn known(category: &str) -> &'static [&'static str] {
+    match category.trim().to_lowercase().trim_end_matches('s') {
+        "dog" => &DOGS,
+        "cat" => &CATS,
+        _ => &[],
+    }
+}
+
+/// The known breeds for `category` that fuzzily match `typed`, best first,
+/// or all of them in order if nothing is typed.
+pub fn suggestions(category: &str, typed: &str) -> Vec<&'static str> {
+    let breeds = known(category);
+    let typed = typed.trim();
+    if typed.is_empty() {
+        return breeds.to_vec();
+    }
+    fuzzy_rank(typed, breeds)
+        .into_iter()
+        .map(|(index, _)| breeds[index])
+        .collect()
+}
+
+/// A breed as typed, trimmed, with `None` for nothing typed. A known breed
+/// typed in another case is written as listed.
+pub fn normalize(category: &str, typed: &str) -> Option<String> {
+    let typed = typed.trim();
+    if typed.is_empty() {
+        return None;
+    }
+    let known = known(category)
+        .iter()
+        .find(|breed| breed.eq_ignore_ascii_case(typed));
+    Some(known.map_or_else(|| typed.to_owned(), |breed| breed.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dogs_and_cats_have_lists_whatever_the_spelling() {
+        for category in ["dogs", "dog", "Dogs", " DOG "] {
+            assert_eq!(known(category), DOGS, "{}", category);
+        }
+        assert_eq!(known("cats"), CATS);
+        assert!(known("rabbits").is_empty());
+        assert!(known("").is_empty());
+    }
+
+    #[test]
+    fn suggestions_come_from_the_pets_species_only() {
+        assert_eq!(
+            suggestions("dogs", "")[..2],
+            ["Labrador Retriever", "German Shepherd"]
+        );
+        assert_eq!(suggestions("cats", "  ").len(), CATS.len());
+        assert_eq!(suggestions("dogs", "labr")[0], "Labrador Retriever");
+        assert_eq!(suggestions("cats", "coon")[0], "Maine Coon");
+        assert!(!suggestions("cats", "labr").contains(&"Labrador Retriever"));
+        assert!(suggestions("dogs", "husky").contains(&"Siberian Husky"));
+        assert!(!suggestions("dogs", "husky").contains(&"Maine Coon"));
+    }
+
+    #[test]
+    fn other_species_get_no_suggestions() {
+        assert!(suggestions("rabbits", "").is_empty());
+        assert!(suggestions("rabbits", "lop").is_empty());
+        assert!(suggestions("rabbits", "labr").is_empty());
+    }
+
+    #[test]
+    fn any_breed_can_be_typed() {
+        assert_eq!(
+            normalize("rabbits", " Holland Lop "),
+            Some("Holland Lop".to_owned())
+        );
+        assert_eq!(
+            normalize("dogs", "Labradoodle"),
+            Some("Labradoodle".to_owned())
+        );
+        assert_eq!(normalize("dogs", "  "), None);
+        assert_eq!(normalize("rabbits", ""), None);
+    }
+
+    #[test]
+    fn known_breeds_are_written_as_listed() {
+        assert_eq!(
+            normalize("dogs", "border collie"),
+            Some("Border Collie".to_owned())
+        );
+        assert_eq!(
+            normalize("cats", "MAINE COON"),
+            Some("Maine Coon".to_owned())
+        );
+        assert_eq!(
+            normalize("cats", "border collie"),
+            Some("border collie".to_owned())
+        );
+    }
+}
@@ -0,0 +1,378 @@
+//! What the terminal can show, guessed at startup from `TERM`, `COLORTERM`
+//! and the locale, and set by hand with `capabilities` in the config or
+//! `--ascii`. The UI is drawn as usual and [`Fallback`], rendered over it
+//! last, swaps whatever the terminal can't show for something it can: ASCII
+//! for box drawing, the nearest of the 16 basic colors for RGB ones. Braille
+//! charts and photos are drawn differently up front instead.
+
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::Color;
+use tui::widgets::Widget;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    /// Box drawing and block characters, for borders, gauges and bars.
+    pub unicode_borders: bool,
+    /// 24-bit colors, like those `#ffaa00` in a theme picks.
+    pub truecolor: bool,
+    /// Braille dots, for the lines of charts.
+    pub braille: bool,
+    /// Photos as colored half blocks rather than as ASCII shades.
+    pub images: bool,
+}
+
+/// The keys of the `capabilities` setting, in the order of the fields.
+pub const NAMES: [&str; 4] = ["unicode_borders", "truecolor", "braille", "images"];
+
+/// What the terminal says of itself.
+#[derive(Clone, Debug, Default)]
+pub struct Environment {
+    pub term: Option<String>,
+    pub colorterm: Option<String>,
+    /// The first of `LC_ALL`, `LC_CTYPE` and `LANG` that is set.
+    pub locale: Option<String>,
+    /// Set in Windows Terminal, which has all of them.
+    pub windows_terminal: bool,
+}
+
+impl Environment {
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Environment {
+            term: var("TERM"),
+            colorterm: var("COLORTERM"),
+            locale: var("LC_ALL")
+                .or_else(|| var("LC_CTYPE"))
+                .or_else(|| var("LANG")),
+            windows_terminal: var("WT_SESSION").is_some(),
+        }
+    }
+}
+
+impl Capabilities {
+    /// What `--ascii` draws with, which any terminal shows.
+    pub const ASCII: Capabilities = Capabilities {
+        unicode_borders: false,
+        truecolor: false,
+        braille: false,
+        images: false,
+    };
+
+    /// What a terminal in `env` most likely shows. A locale naming another
+    /// charset than UTF-8 rules out everything but ASCII, and the consoles
+    /// of Linux and of old terminals have no braille and only 16 colors.
+    pub fn detect(env: &Environment) -> Self {
+        if env.windows_terminal {
+            return Capabilities {
+                unicode_borders: true,
+                truecolor: true,
+                braille: true,
+                images: true,
+            };
+        }
+        let utf8 = env.locale.as_deref().is_none_or(|locale| {
+            // C and POSIX name no charset; terminals are UTF-8 regardless.
+            match locale.split_once('.') {
+                Some((_, charset)) => {
+                    let charset = charset.split('@').next().unwrap_or(charset);
+                    charset.eq_ignore_ascii_case("utf-8") || charset.eq_ignore_ascii_case("utf8")
+                }
+                None => true,
+            }
+        });
+        let term = env.term.as_deref().unwrap_or("dumb");
+        let plain_term = term == "dumb" || term.starts_with("vt");
+        let console = plain_term || term == "linux";
+        let truecolor = env
+            .colorterm
+            .as_deref()
+            .is_some_and(|colorterm| colorterm == "truecolor" || colorterm == "24bit");
+        Capabilities {
+            unicode_borders: utf8 && !plain_term,
+            truecolor: truecolor && !console,
+            braille: utf8 && !console,
+            images: utf8 && !console,
+        }
+    }
+
+    /// These capabilities with the `capabilities` setting applied.
+    pub fn with_overrides(mut self, overrides: &[(usize, bool)]) -> Self {
+        for &(index, on) in overrides {
+            match index {
+                0 => self.unicode_borders = on,
+                1 => self.truecolor = on,
+                2 => self.braille = on,
+                _ => self.images = on,
+            }
+        }
+        self
+    }
+
+    /// The ones there are, like `unicode_borders, braille`, for the
+    /// diagnostics overlay.
+    pub fn describe(&self) -> String {
+        let on = [
+            self.unicode_borders,
+            self.truecolor,
+            self.braille,
+            self.images,
+        ];
+        let names: Vec<&str> = NAMES
+            .iter()
+            .zip(on)
+            .filter(|(_, on)| *on)
+            .map(|(name, _)| *name)
+            .collect();
+        if names.is_empty() {
+            "ascii only".to_owned()
+        } else {
+            names.join(", ")
+        }
+    }
+}
+
+/// Parses the `capabilities` setting, like `braille=off,truecolor=on`, into
+/// the index in [`NAMES`] of each key and its value.
+pub fn parse_overrides(value: &str) -> Result<Vec<(usize, bool)>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, on) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected name=on or name=off, got '{}'", entry))?;
+            let index = NAMES
+                .iter()
+                .position(|known| *known == name.trim())
+                .ok_or_else(|| {
+                    format!(
+                        "unknown capability '{}', expected one of: {}",
+                        name.trim(),
+                        NAMES.join(", ")
+                    )
+                })?;
+            let on = match on.trim() {
+                "on" | "true" => true,
+                "off" | "false" => false,
+                other => return Err(format!("expected on or off, got '{}'", other)),
+            };
+            Ok((index, on))
+        })
+        .collect()
+}
+
+/// Rendered over the whole frame after everything else, replacing what the
+/// terminal can't show.
+pub struct Fallback(pub Capabilities);
+
+impl Widget for Fallback {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let Fallback(capabilities) = self;
+        if capabilities.unicode_borders && capabilities.truecolor {
+            return;
+        }
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                if !capabilities.unicode_borders {
+                    let mut chars = cell.symbol.chars();
+                    if let (Some(symbol), None) = (chars.next(), chars.next()) {
+                        if let Some(ascii) = ascii_for(symbol) {
+                            cell.set_char(ascii);
+                        }
+                    }
+                }
+                if !capabilities.truecolor {
+                    cell.fg = basic_color(cell.fg);
+                    cell.bg = basic_color(cell.bg);
+                }
+            }
+        }
+    }
+}
+
+/// The ASCII stand-in for a box drawing, block or braille character.
+fn ascii_for(symbol: char) -> Option<char> {
+    match symbol {
+        '─' | '━' | '═' | '╌' | '╍' | '┄' | '┅' | '┈' | '┉' | '╴' | '╶' | '╸' | '╺' => {
+            Some('-')
+        }
+        '│' | '┃' | '║' | '╎' | '╏' | '┆' | '┇' | '┊' | '┋' | '╵' | '╷' | '╹' | '╻' => {
+            Some('|')
+        }
+        '\u{2500}'..='\u{257f}' => Some('+'),
+        // Upper and lower halves and eighths, shades and full blocks.
+        '\u{2580}'..='\u{259f}' => Some('#'),
+        '\u{2800}' => Some(' '),
+        '\u{2801}'..='\u{28ff}' | '•' => Some('*'),
+        '…' => Some('.'),
+        '▾' => Some('v'),
+        _ => None,
+    }
+}
+
+/// The basic colors as xterm shows them.
+const BASIC_COLORS: [(Color, [u8; 3]); 16] = [
+    (Color::Black, [0, 0, 0]),
+    (Color::Red, [205, 0, 0]),
+    (Color::Green, [0, 205, 0]),
+    (Color::Yellow, [205, 205, 0]),
+    (Color::Blue, [0, 0, 238]),
+    (Color::Magenta, [205, 0, 205]),
+    (Color::Cyan, [0, 205, 205]),
+    (Color::Gray, [229, 229, 229]),
+    (Color::DarkGray, [127, 127, 127]),
+    (Color::LightRed, [255, 0, 0]),
+    (Color::LightGreen, [0, 255, 0]),
+    (Color::LightYellow, [255, 255, 0]),
+    (Color::LightBlue, [92, 92, 255]),
+    (Color::LightMagenta, [255, 0, 255]),
+    (Color::LightCyan, [0, 255, 255]),
+    (Color::White, [255, 255, 255]),
+];
+
+/// The basic color nearest to an RGB one; others are left alone.
+fn basic_color(color: Color) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    let distance = |[r2, g2, b2]: [u8; 3]| {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(r, r2) + d(g, g2) + d(b, b2)
+    };
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map_or(color, |(basic, _)| *basic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::Style;
+
+    fn env(term: &str, colorterm: &str, locale: &str) -> Environment {
+        let set = |value: &str| (!value.is_empty()).then(|| value.to_owned());
+        Environment {
+            term: set(term),
+            colorterm: set(colorterm),
+            locale: set(locale),
+            windows_terminal: false,
+        }
+    }
+
+    const ALL: Capabilities = Capabilities {
+        unicode_borders: true,
+        truecolor: true,
+        braille: true,
+        images: true,
+    };
+
+    #[test]
+    fn detects_from_term_colorterm_and_locale() {
+        let cases = [
+            (
+                env("xterm-256color", "truecolor", "en_IE.UTF-8"),
+                "unicode_borders, truecolor, braille, images",
+            ),
+            (
+                env("xterm-256color", "", "en_IE.utf8"),
+                "unicode_borders, braille, images",
+            ),
+            (
+                env("xterm-256color", "24bit", "C"),
+                "unicode_borders, truecolor, braille, images",
+            ),
+            (env("xterm", "", "en_IE.ISO-8859-1"), "ascii only"),
+            (
+                env("xterm", "", "de_DE.UTF-8@euro"),
+                "unicode_borders, braille, images",
+            ),
+            (env("linux", "truecolor", "en_US.UTF-8"), "unicode_borders"),
+            (env("vt100", "", ""), "ascii only"),
+            (env("dumb", "truecolor", "en_US.UTF-8"), "ascii only"),
+            (env("", "", ""), "ascii only"),
+        ];
+        for (env, expected) in cases {
+            assert_eq!(Capabilities::detect(&env).describe(), expected, "{:?}", env);
+        }
+        let windows = Environment {
+            windows_terminal: true,
+            ..env("", "", "")
+        };
+        assert_eq!(Capabilities::detect(&windows), ALL);
+    }
+
+    #[test]
+    fn the_setting_turns_capabilities_on_and_off() {
+        let overrides = parse_overrides(" braille=off, truecolor = on ,").unwrap();
+        assert_eq!(overrides, [(2, false), (1, true)]);
+        let capabilities = Capabilities::ASCII.with_overrides(&overrides);
+        assert_eq!(capabilities.describe(), "truecolor");
+        assert_eq!(
+            ALL.with_overrides(&overrides).describe(),
+            "unicode_borders, truecolor, images"
+        );
+        assert_eq!(parse_overrides("").unwrap(), []);
+        assert_eq!(
+            parse_overrides("sixel=on").unwrap_err(),
+            "unknown capability 'sixel', expected one of: unicode_borders, truecolor, braille, images"
+        );
+        assert_eq!(
+            parse_overrides("images").unwrap_err(),
+            "expected name=on or name=off, got 'images'"
+        );
+        assert_eq!(
+            parse_overrides("images=maybe").unwrap_err(),
+            "expected on or off, got 'maybe'"
+        );
+    }
+
+    fn buffer(symbols: &[&str], color: Color) -> Buffer {
+        let mut buf = Buffer::empty(Rect::new(0, 0, symbols.len() as u16, 1));
+        for (x, symbol) in (0..).zip(symbols) {
+            buf.get_mut(x, 0)
+                .set_symbol(symbol)
+                .set_style(Style::default().fg(color).bg(Color::Rgb(10, 10, 10)));
+        }
+        buf
+    }
+
+    fn symbols(buf: &Buffer) -> String {
+        buf.content
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn ascii_stands_in_for_what_the_terminal_cant_show() {
+        let drawn = [
+            "┌", "─", "┐", "│", "█", "⣿", "\u{2800}", "…", "▾", "a", "ね",
+        ];
+        let mut buf = buffer(&drawn, Color::Rgb(250, 10, 10));
+        Fallback(Capabilities::ASCII).render(buf.area, &mut buf);
+        assert_eq!(symbols(&buf), "+-+|#* .vaね");
+        assert!(buf.content.iter().all(|cell| cell.fg == Color::LightRed));
+        assert!(buf.content.iter().all(|cell| cell.bg == Color::Black));
+        let mut buf = buffer(&drawn, Color::Rgb(250, 10, 10));
+        Fallback(ALL).render(buf.area, &mut buf);
+        assert_eq!(symbols(&buf), drawn.concat());
+        assert!(buf
+            .content
+            .iter()
+            .all(|cell| cell.fg == Color::Rgb(250, 10, 10)));
+    }
+
+    #[test]
+    fn rgb_colors_become_the_nearest_basic_ones() {
+        assert_eq!(basic_color(Color::Rgb(0, 0, 0)), Color::Black);
+        assert_eq!(basic_color(Color::Rgb(255, 170, 0)), Color::Yellow);
+        assert_eq!(basic_color(Color::Rgb(100, 100, 255)), Color::LightBlue);
+        assert_eq!(basic_color(Color::Rgb(130, 125, 120)), Color::DarkGray);
+        assert_eq!(basic_color(Color::Cyan), Color::Cyan);
+        assert_eq!(basic_color(Color::Reset), Color::Reset);
+    }
+}
@@ -0,0 +1,286 @@
+//! Renaming categories across the DB: `=` on the Pets or Stats tab lists
+//! them to rename one or merge several into one, and `pet-cli category`
+//! does the same from the shell. A rename is a single write of every pet it
+//! changes, and renaming to the name the pets have already changes none, so
+//! running it twice is harmless.
+
+use crate::Pet;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Every category with how many pets it has, the most common first, ties by
+/// name. Pets in the Trash count too, as a rename changes them as well.
+pub fn counts(pets: &[Pet]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for pet in pets {
+        match counts
+            .iter_mut()
+            .find(|(category, _)| *category == pet.category)
+        {
+            Some((_, count)) => *count += 1,
+            None => counts.push((pet.category.clone(), 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Gives the pets of the categories `from` the category `to`. With several
+/// categories, or one that is `to` but for case, it merges them.
+#[derive(Clone, Debug)]
+pub struct Rename {
+    pub from: Vec<String>,
+    pub to: String,
+    /// Whether `from` also matches categories that differ only in case.
+    pub ignore_case: bool,
+}
+
+impl Rename {
+    pub fn new(from: Vec<String>, to: &str, ignore_case: bool) -> Result<Rename, String> {
+        let to = to.trim();
+        if to.is_empty() {
+            return Err("the new category is empty".to_owned());
+        }
+        if from.is_empty() {
+            return Err("no category to rename".to_owned());
+        }
+        Ok(Rename {
+            from,
+            to: to.to_owned(),
+            ignore_case,
+        })
+    }
+
+    pub fn matches(&self, category: &str) -> bool {
+        self.from.iter().any(|from| {
+            if self.ignore_case {
+                from.to_lowercase() == category.to_lowercase()
+            } else {
+                from == category
+            }
+        })
+    }
+
+    /// Renames the matching pets and returns how many it changed, which
+    /// leaves out those that had the new category already.
+    pub fn apply(&self, pets: &mut [Pet]) -> usize {
+        let mut changed = 0;
+        for pet in pets.iter_mut() {
+            if self.matches(&pet.category) && pet.category != self.to {
+                pet.category = self.to.clone();
+                changed += 1;
+            }
+        }
+        changed
+    }
+}
+
+impl fmt::Display for Rename {
+    /// Like `category Dogs to dogs` or `categories Dogs, Hounds into dogs`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.from.as_slice() {
+            [from] => write!(f, "category {} to {}", from, self.to),
+            from => write!(f, "categories {} into {}", from.join(", "), self.to),
+        }
+    }
+}
+
+/// The `=` overlay listing the categories with their pet counts.
+pub struct CategoryManager {
+    categories: Vec<(String, usize)>,
+    selected: usize,
+    marked: BTreeSet<usize>,
+}
+
+pub enum ManagerOutcome {
+    Open,
+    Closed,
+    /// Ask for the name to give the pets of these categories.
+    Rename(Vec<String>),
+}
+
+impl CategoryManager {
+    pub fn new(pets: &[Pet]) -> CategoryManager {
+        CategoryManager {
+            categories: counts(pets),
+            selected: 0,
+            marked: BTreeSet::new(),
+        }
+    }
+
+    pub fn categories(&self) -> &[(String, usize)] {
+        &self.categories
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn is_marked(&self, index: usize) -> bool {
+        self.marked.contains(&index)
+    }
+
+    /// The highlighted category, to start the new name from.
+    pub fn current(&self) -> Option<&str> {
+        self.categories
+            .get(self.selected)
+            .map(|(category, _)| category.as_str())
+    }
+
+    /// `j`/`k` move the highlight, Space marks, `r` renames the highlighted
+    /// category and `m` merges the marked ones and the highlighted one.
+    pub fn handle_key(&mut self, key: KeyEvent) -> ManagerOutcome {
+        let last = self.categories.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return ManagerOutcome::Closed,
+            KeyCode::Down | KeyCode::Char('j') if self.selected < last => self.selected += 1,
+            KeyCode::Up | KeyCode::Char('k') if self.selected > 0 => self.selected -= 1,
+            KeyCode::Char(' ') if !self.categories.is_empty() => {
+                if !self.marked.remove(&self.selected) {
+                    self.marked.insert(self.selected);
+                }
+                if self.selected < last {
+                    self.selected += 1;
+                }
+            }
+            KeyCode::Char('r') if !self.categories.is_empty() => {
+                return ManagerOutcome::Rename(vec![self.categories[self.selected].0.clone()])
+            }
+            KeyCode::Char('m') if !self.categories.is_empty() => {
+                let mut chosen = self.marked.clone();
+                chosen.insert(self.selected);
+                return ManagerOutcome::Rename(
+                    chosen
+                        .into_iter()
+                        .map(|index| self.categories[index].0.clone())
+                        .collect(),
+                );
+            }
+            _ => {}
+        }
+        ManagerOutcome::Open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use crossterm::event::KeyModifiers;
+
+    fn pets() -> Vec<Pet> {
+        vec![
+            pet(1, "Coco", "cats"),
+            pet(2, "Rex", "Dogs"),
+            pet(3, "Bun", "rabbits"),
+            pet(4, "Max", "dogs"),
+            pet(5, "Fido", "hounds"),
+            pet(6, "Tom", "cats"),
+        ]
+    }
+
+    fn categories(pets: &[Pet]) -> Vec<&str> {
+        pets.iter().map(|pet| pet.category.as_str()).collect()
+    }
+
+    fn rename(from: &[&str], to: &str, ignore_case: bool) -> Rename {
+        let from = from.iter().map(|from| from.to_string()).collect();
+        Rename::new(from, to, ignore_case).unwrap()
+    }
+
+    fn press(manager: &mut CategoryManager, key: char) -> ManagerOutcome {
+        manager.handle_key(KeyEvent::new(KeyCode::Char(key), KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn counts_are_the_most_common_first_then_by_name() {
+        let mut pets = pets();
+        pets[2].archived_at = Some(chrono::Utc::now());
+        assert_eq!(
+            counts(&pets),
+            [
+                ("cats".to_owned(), 2),
+                ("Dogs".to_owned(), 1),
+                ("dogs".to_owned(), 1),
+                ("hounds".to_owned(), 1),
+                ("rabbits".to_owned(), 1),
+            ]
+        );
+        assert!(counts(&[]).is_empty());
+    }
+
+    #[test]
+    fn an_exact_rename_leaves_other_cases_alone() {
+        let mut pets = pets();
+        assert_eq!(rename(&["dogs"], "canines", false).apply(&mut pets), 1);
+        assert_eq!(
+            categories(&pets),
+            ["cats", "Dogs", "rabbits", "canines", "hounds", "cats"]
+        );
+    }
+
+    #[test]
+    fn ignoring_case_merges_the_spellings() {
+        let mut pets = pets();
+        assert_eq!(rename(&["DOGS"], "dogs", true).apply(&mut pets), 1);
+        assert_eq!(
+            categories(&pets),
+            ["cats", "dogs", "rabbits", "dogs", "hounds", "cats"]
+        );
+    }
+
+    #[test]
+    fn renaming_to_an_existing_category_merges_into_it() {
+        let mut pets = pets();
+        let merge = rename(&["Dogs", "hounds"], "dogs", false);
+        assert_eq!(merge.to_string(), "categories Dogs, hounds into dogs");
+        assert_eq!(merge.apply(&mut pets), 2);
+        assert_eq!(
+            categories(&pets),
+            ["cats", "dogs", "rabbits", "dogs", "dogs", "cats"]
+        );
+        assert_eq!(counts(&pets)[0], ("dogs".to_owned(), 3));
+    }
+
+    #[test]
+    fn a_second_run_changes_nothing() {
+        let mut pets = pets();
+        let rename = rename(&["cats"], "felines", false);
+        assert_eq!(rename.to_string(), "category cats to felines");
+        assert_eq!(rename.apply(&mut pets), 2);
+        let once = categories(&pets).join(",");
+        assert_eq!(rename.apply(&mut pets), 0);
+        assert_eq!(categories(&pets).join(","), once);
+    }
+
+    #[test]
+    fn the_new_name_is_trimmed_and_can_not_be_empty() {
+        assert_eq!(rename(&["cats"], "  felines ", false).to, "felines");
+        assert_eq!(
+            Rename::new(vec!["cats".to_owned()], "  ", false).unwrap_err(),
+            "the new category is empty"
+        );
+        assert_eq!(
+            Rename::new(Vec::new(), "cats", false).unwrap_err(),
+            "no category to rename"
+        );
+    }
+
+    #[test]
+    fn the_manager_renames_the_highlighted_or_merges_the_marked() {
+        let mut manager = CategoryManager::new(&pets());
+        assert_eq!(manager.current(), Some("cats"));
+        assert!(matches!(press(&mut manager, 'r'),
+            ManagerOutcome::Rename(from) if from == ["cats"]));
+        press(&mut manager, 'j');
+        press(&mut manager, ' ');
+        assert!(manager.is_marked(1));
+        assert_eq!(manager.selected(), 2);
+        assert!(matches!(press(&mut manager, 'm'),
+            ManagerOutcome::Rename(from) if from == ["Dogs", "dogs"]));
+        assert!(matches!(press(&mut manager, 'q'), ManagerOutcome::Closed));
+        let mut empty = CategoryManager::new(&[]);
+        assert!(matches!(press(&mut empty, 'r'), ManagerOutcome::Open));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::keys;
+use std::time::{Duration, Instant};
+
+/// How long the second key of a chord may take before the first is dropped.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Two-key commands in the style of vim, like `dd` and `gg`. The first key of
+/// a chord is held back until the same key follows; any other key, or waiting
+/// longer than `TIMEOUT`, drops it again. Expiry is checked on every tick.
+/// `keys::FOLD` is followed by any key, as in `za`.
+#[derive(Default)]
+pub struct Chord {
+    pending: Option<(char, Instant)>,
+}
+
+impl Chord {
+    /// Feeds a key press through the chord. Returns whether `key` should be
+    /// acted on now: `false` while it waits for its second half, `true` once a
+    /// chord is complete or for keys that aren't chords at all.
+    pub fn press(&mut self, key: char, now: Instant) -> bool {
+        self.expire(now);
+        match self.pending.take() {
+            Some((first, _)) if first == key || first == keys::FOLD => true,
+            _ if keys::CHORDS.contains(&key) => {
+                self.pending = Some((key, now));
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Drops the first half of a chord, e.g. on `Esc` or a key with modifiers.
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    pub fn expire(&mut self, now: Instant) {
+        if let Some((_, pressed_at)) = self.pending {
+            if now.duration_since(pressed_at) >= TIMEOUT {
+                self.pending = None;
+            }
+        }
+    }
+
+    /// The key waiting for its second half, shown in the status bar.
+    pub fn pending(&self) -> Option<char> {
+        self.pending.map(|(key, _)| key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_doubled_key_completes_the_chord() {
+        let mut chord = Chord::default();
+        let now = Instant::now();
+        assert!(!chord.press(keys::DELETE, now));
+        assert_eq!(chord.pending(), Some(keys::DELETE));
+        assert!(chord.press(keys::DELETE, now + Duration::from_millis(500)));
+        assert_eq!(chord.pending(), None);
+    }
+
+    #[test]
+    fn keys_outside_chords_act_at_once() {
+        let mut chord = Chord::default();
+        assert!(chord.press(keys::NEXT, Instant::now()));
+        assert_eq!(chord.pending(), None);
+    }
+
+    #[test]
+    fn a_different_second_key_drops_the_first() {
+        let mut chord = Chord::default();
+        let now = Instant::now();
+        assert!(!chord.press(keys::DELETE, now));
+        assert!(chord.press(keys::NEXT, now));
+        assert_eq!(chord.pending(), None);
+        assert!(!chord.press(keys::DELETE, now));
+        assert!(!chord.press(keys::FIRST, now));
+        assert_eq!(chord.pending(), Some(keys::FIRST));
+    }
+
+    #[test]
+    fn the_first_key_times_out() {
+        let mut chord = Chord::default();
+        let now = Instant::now();
+        assert!(!chord.press(keys::FIRST, now));
+        chord.expire(now + TIMEOUT - Duration::from_millis(1));
+        assert_eq!(chord.pending(), Some(keys::FIRST));
+        chord.expire(now + TIMEOUT);
+        assert_eq!(chord.pending(), None);
+        assert!(!chord.press(keys::FIRST, now + TIMEOUT));
+        assert!(!chord.press(keys::FIRST, now + TIMEOUT * 3));
+    }
+
+    #[test]
+    fn fold_is_followed_by_any_key() {
+        let mut chord = Chord::default();
+        let now = Instant::now();
+        assert!(!chord.press(keys::FOLD, now));
+        assert!(chord.press(keys::TOGGLE_FOLD, now));
+        assert_eq!(chord.pending(), None);
+        assert!(!chord.press(keys::FOLD, now));
+        assert!(chord.press(keys::DELETE, now));
+        assert_eq!(chord.pending(), None);
+    }
+
+    #[test]
+    fn cancel_drops_the_first_key() {
+        let mut chord = Chord::default();
+        let now = Instant::now();
+        assert!(!chord.press(keys::DELETE, now));
+        chord.cancel();
+        assert_eq!(chord.pending(), None);
+        assert!(!chord.press(keys::DELETE, now));
+    }
+}
@@ -0,0 +1,914 @@
+use crate::audit::AuditFormat;
+use crate::completions::Shell;
+use crate::compression::Compress;
+use crate::config::{Config, Layer, Source};
+use crate::db_format::DbFormat;
+use crate::fields::PetField;
+use crate::filter::CategoryFilter;
+use crate::list::ListFormat;
+use crate::merge::Strategy;
+use crate::pet_status::PetStatus;
+use crate::report::ReportFormat;
+use crate::search::Query;
+use crate::strings::Lang;
+use crate::table_import::ImportFrom;
+use crate::theme::{Theme, ThemeError, THEME_NAMES};
+use crate::view::{Sort, ViewParams};
+use crate::{MenuItem, WriteOptions};
+use chrono::NaiveDate;
+use clap::builder::{PossibleValuesParser, ValueHint};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+#[cfg(feature = "server")]
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Parser, Debug)]
+#[command(name = "pet-cli", about = "A small terminal UI for managing pets")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Don't print what the session changed on quitting
+    #[arg(long)]
+    pub quiet: bool,
+
+    /// Start on the Home tab with the first pet selected instead of where the last session ended
+    #[arg(long)]
+    pub no_restore: bool,
+
+    /// Tab to start on, instead of where the last session ended [default: home]
+    #[arg(long, value_enum)]
+    pub tab: Option<MenuItem>,
+
+    /// Start with the pet with this id selected on the Pets tab
+    #[arg(long, value_name = "ID")]
+    pub select: Option<usize>,
+
+    /// Lock the DB for the whole session so no other instance can write to it
+    #[arg(long)]
+    pub exclusive: bool,
+
+    /// Never write to the DB; commands that would change it fail instead
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Start a read-only UI for visitors to browse: only the Pets and Stats
+    /// tabs, no keys that change, export or quit, and back to the start
+    /// after `kiosk_reset` minutes idle. Typing `kiosk_unlock` leaves it
+    #[arg(long, conflicts_with_all = ["dry_run", "plain"])]
+    pub kiosk: bool,
+
+    /// Append what every write changes to audit.jsonl next to the DB; see `pet-cli audit`
+    #[arg(long, global = true)]
+    pub audit: bool,
+
+    /// Refuse DB files whose pets have fields pet-cli doesn't know, instead
+    /// of keeping them as they are
+    #[arg(long, global = true)]
+    pub strict_schema: bool,
+
+    /// Print the pets as lines of text and read commands like `sort age desc`
+    /// from stdin instead of drawing the full-screen UI, e.g. for a screen
+    /// reader
+    #[arg(long)]
+    pub plain: bool,
+
+    /// Run on made-up pets in a new temporary directory, without reading the
+    /// config file, and remove the directory on quitting
+    #[arg(long, conflicts_with_all = ["workspace", "config", "dry_run"])]
+    pub demo: bool,
+
+    /// With --demo, leave the directory behind and print where it is
+    #[arg(long, requires = "demo")]
+    pub keep: bool,
+
+    /// Learn the keys step by step on made-up pets, like --demo; Esc ends it
+    #[arg(long, conflicts_with_all = ["workspace", "config", "dry_run", "demo", "kiosk", "plain"])]
+    pub tutorial: bool,
+
+    /// Start with the built-in settings, for when something saved keeps
+    /// pet-cli from starting: the config file, the last session's UI state,
+    /// saved views and drafts are neither read nor written, and no hooks run
+    #[arg(long, global = true, conflicts_with_all = ["workspace", "config"])]
+    pub safe_mode: bool,
+
+    /// Work on a scratch copy of the DB and, on quitting, show what changed
+    /// and ask whether to save it
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Use the DB of this workspace from the config file instead of ./data/db.json
+    #[arg(long, value_name = "NAME", global = true)]
+    pub workspace: Option<String>,
+
+    /// Config file to read instead of ~/.config/pet-cli/config.toml
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// How to write the DB file; any of the formats can be read [default: pretty]
+    #[arg(long, value_enum, global = true)]
+    pub db_format: Option<DbFormat>,
+
+    /// Whether to write the DB gzip-compressed; auto for a `.gz` name or a compressed DB [default: auto]
+    #[arg(long, value_enum, value_name = "HOW", global = true)]
+    pub compress: Option<Compress>,
+
+    /// How many DB backups to keep from before deletes and edits [default: 10]
+    #[arg(long, value_name = "N", global = true)]
+    pub keep_backups: Option<usize>,
+
+    /// Also append the session's activity log to this file
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub activity_log: Option<PathBuf>,
+
+    /// Write debug logs to this file (`RUST_LOG` sets the level and, without this flag, logs to pet-cli.log)
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub log_file: Option<PathBuf>,
+
+    /// Color theme [default: dark]
+    #[arg(long, value_parser = PossibleValuesParser::new(THEME_NAMES))]
+    pub theme: Option<String>,
+
+    /// Draw with bold, underlined and reversed text only, no colors (same as `--theme mono`)
+    #[arg(long, conflicts_with_all = ["theme", "colors"])]
+    pub no_color: bool,
+
+    /// Draw with ASCII and the 16 basic colors only, for terminals that show other characters wrongly
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Override a single theme color, e.g. `--color highlight=#ffaa00` (repeatable)
+    #[arg(long = "color", value_name = "NAME=COLOR")]
+    pub colors: Vec<String>,
+
+    /// Title of the menu bar
+    #[arg(long, value_name = "TEXT")]
+    pub title: Option<String>,
+
+    /// Text of the footer (defaults to "pet-CLI" and the current year)
+    #[arg(long, value_name = "TEXT")]
+    pub footer: Option<String>,
+
+    /// Leave out the footer to make room for the lists
+    #[arg(long, conflicts_with = "footer")]
+    pub no_footer: bool,
+
+    /// Share of the Pets tab's width given to the list, in percent (10-90)
+    #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u16).range(10..=90))]
+    pub list_width: Option<u16>,
+
+    /// How often the UI checks for changes and expires messages, in milliseconds [default: 200]
+    #[arg(long, value_name = "MS")]
+    pub tick_rate: Option<u64>,
+
+    /// Language of the UI [default: from $LANG, else en]
+    #[arg(long, value_enum)]
+    pub lang: Option<Lang>,
+
+    /// Give generated pets names no pet in the DB has yet, numbering them if needed
+    #[arg(long, global = true)]
+    pub unique_names: bool,
+
+    /// Locale of generated pets' categories and names, e.g. de or ja [default: en]
+    #[arg(long, value_name = "CODE", global = true)]
+    pub locale: Option<String>,
+
+    /// Show how long the previous frame took to draw in the status bar
+    #[arg(long)]
+    pub show_frame_time: bool,
+
+    /// Write a Chrome trace of the session to this file (open it in chrome://tracing or Perfetto)
+    #[cfg(feature = "profiling")]
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub profile_out: Option<PathBuf>,
+}
+
+/// Which pets `list` and `report` show, in which order; the same view the
+/// TUI's Pets tab can be set up to show.
+#[derive(Args, Debug)]
+pub struct ViewArgs {
+    /// Only include pets of this category
+    #[arg(long)]
+    pub category: Option<String>,
+
+    /// Only include pets with this adoption status
+    #[arg(long, value_enum)]
+    pub status: Option<PetStatus>,
+
+    /// Order by id, name, category, age or created, ascending unless followed by `:desc`
+    #[arg(long, value_name = "KEY[:desc]")]
+    pub sort: Option<Sort>,
+
+    /// Only include pets matching this search, e.g. 'category:cats age:>5 rex' (see the README)
+    #[arg(long, value_name = "QUERY")]
+    pub query: Option<Query>,
+
+    /// Include the pets in the Trash
+    #[arg(long)]
+    pub include_archived: bool,
+
+    /// Only include pets added on this day (UTC) or later
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    pub since: Option<NaiveDate>,
+}
+
+impl ViewArgs {
+    pub fn params(&self) -> ViewParams {
+        ViewParams {
+            category: CategoryFilter::only(self.category.clone()),
+            status: self.status,
+            sort: self.sort,
+            query: self.query.clone(),
+            include_archived: self.include_archived,
+            since: self.since,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Render the pets as a Markdown table or an HTML page
+    Report {
+        #[arg(long, value_enum, default_value_t = ReportFormat::Markdown)]
+        format: ReportFormat,
+
+        /// File to write the report to instead of stdout
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        out: Option<PathBuf>,
+
+        #[command(flatten)]
+        view: ViewArgs,
+    },
+    /// Print the pets outside the Trash, or all of them with --include-archived
+    List {
+        /// [default: table on a terminal, tsv when piped]
+        #[arg(long, value_enum)]
+        format: Option<ListFormat>,
+
+        /// Cut table cells wider than this many columns, ending them in …
+        #[arg(long, value_name = "COLUMNS")]
+        max_width: Option<usize>,
+
+        /// The columns to print, in this order, instead of the usual ones (not with weights-csv)
+        #[arg(long, value_enum, value_delimiter = ',', value_name = "FIELD,...")]
+        fields: Vec<PetField>,
+
+        #[command(flatten)]
+        view: ViewArgs,
+
+        /// Keep running and print the pets again whenever the DB changes
+        #[arg(long)]
+        watch: bool,
+
+        /// How often to check the DB for changes with --watch, in seconds
+        #[arg(long, value_name = "SECONDS", default_value_t = 2.0, value_parser = parse_interval)]
+        interval: f64,
+
+        /// With --watch, print below the previous output instead of clearing the screen
+        #[arg(long)]
+        no_clear: bool,
+
+        /// With json and csv, also print the fields of pets pet-cli doesn't know
+        #[arg(long)]
+        extra_fields: bool,
+    },
+    /// Print how many pets there are by category and adoption status, their
+    /// average ages and an age histogram, as on the Stats tab
+    Stats {
+        /// Print JSON instead of text, to graph it elsewhere
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the categories, or rename or merge them on every pet in one write
+    Category {
+        #[command(subcommand)]
+        command: CategoryCommand,
+    },
+    /// Merge pets with the same name (ignoring case and surrounding spaces) into the first of them
+    Dedupe {
+        /// Only print what would be merged
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Add the pets of a file written with `E` in the terminal UI, under new ids
+    ImportPet {
+        /// JSON file with one pet or a list of them
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+    },
+    /// Add the rows of another program's table as pets, leaving out those
+    /// with the same name, category and birthdate as one here
+    Import {
+        /// What the file is
+        #[arg(long, value_enum)]
+        from: ImportFrom,
+
+        /// The file to read
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+
+        /// The table with the pets
+        #[arg(long)]
+        table: String,
+
+        /// Which column goes into which field, like name=name,species=category,dob=birthdate;
+        /// the fields are name, category, birthdate, notes, breed and microchip
+        #[arg(long)]
+        map: String,
+
+        /// Only print what would be imported
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Merge the pets and owners of another DB file into this one. Pets with
+    /// the same name, category and birthdate as one here are left out; pets
+    /// with the id of a different one here are asked about
+    Merge {
+        /// The DB file to merge in
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+
+        /// Decide every conflicting pet this way instead of asking: keep mine, theirs or both
+        #[arg(long, value_enum)]
+        strategy: Option<Strategy>,
+
+        /// Only print what would be merged
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print the pets added, removed and changed between two DB files
+    Diff {
+        /// The earlier version
+        #[arg(value_hint = ValueHint::FilePath)]
+        old: PathBuf,
+        /// The later version
+        #[arg(value_hint = ValueHint::FilePath)]
+        new: PathBuf,
+    },
+    /// Bring the DB to the current schema version, after backing it up; this
+    /// also happens whenever the terminal UI starts
+    Migrate {
+        /// Only print the steps that would run
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check the DB for broken, invalid or unknown entries and exit with
+    /// status 1 if there are errors
+    Check,
+    /// Choose a pet in a searchable list and print it, for shell pipelines.
+    /// The list is drawn on the terminal, so that only the pet goes to
+    /// stdout; Esc prints nothing and exits with status 1
+    Pick {
+        /// What to print: id, json or another field, like name
+        #[arg(long, value_name = "FIELD", default_value = "id", value_parser = parse_pick_output)]
+        output: PickOutput,
+
+        #[command(flatten)]
+        view: ViewArgs,
+    },
+    /// Permanently delete pets that have been in the Trash for a while
+    Purge {
+        /// Only pets archived longer ago than this, e.g. `90d` or `2w`
+        #[arg(long, value_parser = parse_age)]
+        older_than: chrono::Duration,
+    },
+    /// Append randomly generated pets to the DB
+    Generate {
+        /// How many pets to add
+        #[arg(long, default_value_t = 10)]
+        count: usize,
+
+        /// Seed for the generator; the same seed always makes the same pets
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Keep sending the reminders of the Home tab, like birthdays and
+    /// checkups due, to the channels `notify_desktop`, `notify_command` and
+    /// `notify_file` turn on, each no more often than every `notify_snooze`
+    /// hours. SIGTERM or Ctrl+C stops it
+    NotifyDaemon {
+        /// How often to check for reminders, like 30m, 1h or 1d
+        #[arg(long, value_name = "PERIOD", default_value = "1h", value_parser = parse_period)]
+        interval: Duration,
+
+        /// Check once, send what is due and exit, for running from cron
+        #[arg(long)]
+        once: bool,
+
+        /// Show reminders as desktop notifications
+        #[arg(long, conflicts_with = "no_desktop")]
+        desktop: bool,
+
+        /// Don't show desktop notifications, whatever the config says
+        #[arg(long)]
+        no_desktop: bool,
+
+        /// Run this shell command for every reminder; empty for none
+        #[arg(long, value_name = "COMMAND")]
+        command: Option<String>,
+
+        /// Append every reminder to this file; empty for none
+        #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+        file: Option<String>,
+    },
+    /// Serve the DB as a JSON API over HTTP
+    #[cfg(feature = "server")]
+    Serve {
+        /// Address to listen on; use 0.0.0.0 to reach it from other devices
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: SocketAddr,
+    },
+    /// Print a completion script for the shell
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page, including the keys of the terminal UI
+    Man,
+    /// Print a JSON Schema of the DB file, with the limits pet-cli enforces
+    /// on edits
+    Schema,
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Move the DB file aside, renamed with the time, and start an empty one
+    Nuke {
+        /// Reset without asking for the word `reset` to be typed
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Print what writes changed, from the file kept with --audit
+    Audit {
+        /// Only changes from this day on, like 2024-01-31
+        #[arg(long, value_name = "DATE")]
+        since: Option<NaiveDate>,
+
+        /// Only changes to the pet with this id
+        #[arg(long, value_name = "ID")]
+        pet: Option<usize>,
+
+        #[arg(long, value_enum, default_value_t = AuditFormat::Table)]
+        format: AuditFormat,
+    },
+    /// List the DB backups, or restore one of them
+    Restore {
+        /// Number of the backup to restore, as shown in the list
+        backup: Option<usize>,
+
+        /// Restore without asking for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+/// What `pick` prints of the chosen pet.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PickOutput {
+    /// The whole pet on one line.
+    Json,
+    Field(PetField),
+}
+
+fn parse_pick_output(input: &str) -> Result<PickOutput, String> {
+    if input == "json" {
+        return Ok(PickOutput::Json);
+    }
+    PetField::from_str(input, false)
+        .map(PickOutput::Field)
+        .map_err(|_| {
+            let fields: Vec<_> = PetField::ALL.iter().map(|field| field.key()).collect();
+            format!(
+                "invalid output '{}', expected json or one of: {}",
+                input,
+                fields.join(", ")
+            )
+        })
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CategoryCommand {
+    /// Print every category with its number of pets, the Trash included
+    List,
+    /// Give the pets of a category another one; running it again changes
+    /// nothing
+    Rename {
+        /// The category to rename
+        #[arg(long)]
+        from: String,
+
+        /// The new category
+        #[arg(long)]
+        to: String,
+
+        /// Also rename categories that differ from --from only in case
+        #[arg(long)]
+        ignore_case: bool,
+    },
+    /// Give the pets of several categories the same one
+    Merge {
+        /// The categories to merge
+        #[arg(required = true)]
+        categories: Vec<String>,
+
+        /// The category they all get, which may be one of them
+        #[arg(long)]
+        into: String,
+
+        /// Also merge categories that differ from those given only in case
+        #[arg(long)]
+        ignore_case: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the effective configuration and where each value comes from
+    Show,
+}
+
+impl Cli {
+    /// Whether the UI runs on the made-up pets of `--demo`, which
+    /// `--tutorial` uses too.
+    pub fn made_up_db(&self) -> bool {
+        self.demo || self.tutorial
+    }
+
+    /// The settings given as flags, as the top layer of the configuration.
+    pub fn config_layer(&self) -> Layer {
+        let mut layer = Layer::default();
+        let mut set = |key, value: String| layer.set(key, value, Source::Flag);
+        if let Some(theme) = &self.theme {
+            set("theme", theme.clone());
+        }
+        if self.no_color {
+            set("theme", "mono".to_owned());
+        }
+        if let Some(format) = self.db_format {
+            let format = format.to_possible_value().expect("no skipped formats");
+            set("db_format", format.get_name().to_owned());
+        }
+        if let Some(compress) = self.compress {
+            set("compress", compress.name().to_owned());
+        }
+        if let Some(keep_backups) = self.keep_backups {
+            set("keep_backups", keep_backups.to_string());
+        }
+        if let Some(title) = &self.title {
+            set("title", title.clone());
+        }
+        if self.no_footer {
+            set("footer", String::new());
+        } else if let Some(footer) = &self.footer {
+            set("footer", footer.clone());
+        }
+        if let Some(list_width) = self.list_width {
+            set("list_width", list_width.to_string());
+        }
+        if let Some(tick_rate) = self.tick_rate {
+            set("tick_rate", tick_rate.to_string());
+        }
+        if let Some(lang) = self.lang {
+            let lang = lang.to_possible_value().expect("no skipped languages");
+            set("lang", lang.get_name().to_owned());
+        }
+        if self.unique_names {
+            set("unique_names", "true".to_owned());
+        }
+        if self.audit {
+            set("audit", "true".to_owned());
+        }
+        if let Some(locale) = &self.locale {
+            set("generator_locale", locale.clone());
+        }
+        if let Some(tab) = self.tab {
+            let tab = tab.to_possible_value().expect("no skipped tabs");
+            set("tab", tab.get_name().to_owned());
+        }
+        if let Some(Command::NotifyDaemon {
+            desktop,
+            no_desktop,
+            command,
+            file,
+            ..
+        }) = &self.command
+        {
+            if *desktop || *no_desktop {
+                set("notify_desktop", desktop.to_string());
+            }
+            if let Some(command) = command {
+                set("notify_command", command.clone());
+            }
+            if let Some(file) = file {
+                set("notify_file", file.clone());
+            }
+        }
+        layer
+    }
+
+    pub fn write_options(&self, config: &Config) -> WriteOptions {
+        WriteOptions {
+            format: config.db_format.value,
+            keep_backups: config.keep_backups.value,
+            read_only: self.read_only,
+            durability: config.write_durability.value,
+            compress: config.compress.value,
+            compress_level: config.compress_level.value,
+            audit_max_bytes: config
+                .audit
+                .value
+                .then(|| config.audit_max_mb.value * 1024 * 1024),
+        }
+    }
+
+    /// The configured theme with all `--color` overrides applied.
+    pub fn theme(&self, config: &Config) -> Result<Theme, ThemeError> {
+        let mut theme = Theme::named(&config.theme.value)?;
+        theme.set_category_colors(crate::theme::parse_category_colors(
+            &config.category_colors.value,
+        )?);
+        for assignment in &self.colors {
+            theme.apply_override(assignment)?;
+        }
+        Ok(theme)
+    }
+}
+
+fn parse_interval(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Ok(seconds),
+        _ => Err(format!(
+            "invalid interval '{}', expected a positive number of seconds",
+            input
+        )),
+    }
+}
+
+fn parse_date(input: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date '{}', expected YYYY-MM-DD", input))
+}
+
+/// The longest period `parse_period` takes, a year.
+const MAX_PERIOD_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Parses a period like `30s`, `15m`, `1h` or `1d`, up to a year.
+fn parse_period(input: &str) -> Result<Duration, String> {
+    let error = || {
+        format!(
+            "invalid interval '{}', expected e.g. 30s, 15m, 1h or 1d, up to 365d",
+            input
+        )
+    };
+    let split = input.len().saturating_sub(1);
+    let (amount, unit) = (input.get(..split).ok_or_else(error)?, &input[split..]);
+    let amount: u64 = amount.parse().map_err(|_| error())?;
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(error()),
+    };
+    match amount.checked_mul(unit) {
+        Some(seconds) if seconds > 0 && seconds <= MAX_PERIOD_SECS => {
+            Ok(Duration::from_secs(seconds))
+        }
+        _ => Err(error()),
+    }
+}
+
+/// Parses an age like `90d`, `2w` or `12h`.
+fn parse_age(input: &str) -> Result<chrono::Duration, String> {
+    let error = || format!("invalid age '{}', expected e.g. 90d, 2w or 12h", input);
+    let split = input.len().saturating_sub(1);
+    let (amount, unit) = (input.get(..split).ok_or_else(error)?, &input[split..]);
+    let amount: i64 = amount.parse().map_err(|_| error())?;
+    match unit {
+        "h" => Ok(chrono::Duration::hours(amount)),
+        "d" => Ok(chrono::Duration::days(amount)),
+        "w" => Ok(chrono::Duration::weeks(amount)),
+        _ => Err(error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("pet-cli").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn tab_and_select_are_read() {
+        let cli = parse(&["--tab", "stats", "--select", "12"]).unwrap();
+        assert_eq!(cli.tab, Some(MenuItem::Stats));
+        assert_eq!(cli.select, Some(12));
+        let config = crate::config::resolve(&[cli.config_layer()], String::new()).unwrap();
+        assert_eq!(config.tab.value, MenuItem::Stats);
+        assert_eq!(config.tab.source, Source::Flag);
+    }
+
+    #[test]
+    fn without_tab_the_config_decides() {
+        let cli = parse(&[]).unwrap();
+        assert_eq!((cli.tab, cli.select), (None, None));
+        let config = crate::config::resolve(&[cli.config_layer()], String::new()).unwrap();
+        assert_eq!(config.tab.source, Source::Default);
+    }
+
+    #[test]
+    fn an_unknown_tab_lists_the_valid_ones() {
+        let err = parse(&["--tab", "kennel"]).unwrap_err().to_string();
+        assert!(err.contains("invalid value 'kennel'"), "{}", err);
+        for tab in [
+            "home",
+            "pets",
+            "owners",
+            "duplicates",
+            "trash",
+            "log",
+            "stats",
+        ] {
+            assert!(err.contains(tab), "{} in {}", tab, err);
+        }
+    }
+
+    #[test]
+    fn fields_keep_their_order() {
+        let cli = parse(&["list", "--fields", "name,age,category", "--format", "csv"]).unwrap();
+        match cli.command {
+            Some(Command::List { fields, .. }) => {
+                assert_eq!(fields, [PetField::Name, PetField::Age, PetField::Category])
+            }
+            command => panic!("{:?}", command),
+        }
+    }
+
+    #[test]
+    fn an_unknown_field_lists_the_valid_ones() {
+        let err = parse(&["list", "--fields", "name,colour"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid value 'colour'"), "{}", err);
+        for field in PetField::ALL {
+            assert!(err.contains(field.key()), "{} in {}", field.key(), err);
+        }
+    }
+
+    #[test]
+    fn since_takes_a_date() {
+        let cli = parse(&["list", "--since", "2023-01-01", "--include-archived"]).unwrap();
+        let Some(Command::List { view, .. }) = cli.command else {
+            panic!("not list");
+        };
+        let params = view.params();
+        assert_eq!(params.since, NaiveDate::from_ymd_opt(2023, 1, 1));
+        assert!(params.include_archived);
+        let err = parse(&["list", "--since", "2023-13-01"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected YYYY-MM-DD"), "{}", err);
+    }
+
+    #[test]
+    fn query_takes_the_search_syntax() {
+        let cli = parse(&["list", "--query", "category:cats age:>5 rex"]).unwrap();
+        let Some(Command::List { view, .. }) = cli.command else {
+            panic!("not list");
+        };
+        let query = view.params().query.unwrap();
+        assert_eq!(query.text(), "category:cats age:>5 rex");
+        let err = parse(&["report", "--query", "colour:red"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown field 'colour'"), "{}", err);
+    }
+
+    #[test]
+    fn audit_takes_a_date_a_pet_and_a_format() {
+        let cli = parse(&[
+            "audit",
+            "--since",
+            "2024-01-01",
+            "--pet",
+            "42",
+            "--format",
+            "json",
+        ])
+        .unwrap();
+        let Some(Command::Audit { since, pet, format }) = cli.command else {
+            panic!("not audit");
+        };
+        assert_eq!(since, NaiveDate::from_ymd_opt(2024, 1, 1));
+        assert_eq!(pet, Some(42));
+        assert_eq!(format, AuditFormat::Json);
+        assert!(parse(&["audit", "--since", "yesterday"]).is_err());
+    }
+
+    #[test]
+    fn the_locale_flag_sets_the_generator_locale() {
+        let cli = parse(&["generate", "--locale", "ja"]).unwrap();
+        let config = crate::config::resolve(&[cli.config_layer()], String::new()).unwrap();
+        assert_eq!(config.generator_locale.value, "ja");
+        assert_eq!(config.generator_locale.source, Source::Flag);
+    }
+
+    #[test]
+    fn pick_prints_the_id_unless_told_otherwise() {
+        let output = |args: &[&str]| match parse(args).unwrap().command {
+            Some(Command::Pick { output, .. }) => output,
+            command => panic!("{:?}", command),
+        };
+        assert_eq!(output(&["pick"]), PickOutput::Field(PetField::Id));
+        assert_eq!(output(&["pick", "--output", "json"]), PickOutput::Json);
+        assert_eq!(
+            output(&["pick", "--output", "name"]),
+            PickOutput::Field(PetField::Name)
+        );
+        let err = parse(&["pick", "--output", "yaml"])
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("invalid output 'yaml', expected json or one of: id"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn the_notify_daemon_checks_hourly_unless_told_otherwise() {
+        let interval = |args: &[&str]| match parse(args).unwrap().command {
+            Some(Command::NotifyDaemon { interval, .. }) => interval,
+            command => panic!("{:?}", command),
+        };
+        assert_eq!(interval(&["notify-daemon"]), Duration::from_secs(3600));
+        assert_eq!(
+            interval(&["notify-daemon", "--interval", "15m"]),
+            Duration::from_secs(900)
+        );
+        assert_eq!(
+            interval(&["notify-daemon", "--interval", "2d"]),
+            Duration::from_secs(2 * 86400)
+        );
+        for bad in ["0h", "1w", "h", "1.5h", "366d", "99999999999999999d"] {
+            let err = parse(&["notify-daemon", "--interval", bad])
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("expected e.g. 30s, 15m, 1h or 1d"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn notify_daemon_flags_turn_channels_on_and_off() {
+        let config = |args: &[&str]| {
+            let cli = parse(args).unwrap();
+            crate::config::resolve(&[cli.config_layer()], String::new()).unwrap()
+        };
+        let defaults = config(&["notify-daemon"]);
+        assert!(!defaults.notify_desktop.value);
+        assert_eq!(defaults.notify_desktop.source, Source::Default);
+        let on = config(&[
+            "notify-daemon",
+            "--desktop",
+            "--command",
+            "mail -s pets me",
+            "--file",
+            "reminders.log",
+        ]);
+        assert!(on.notify_desktop.value);
+        assert_eq!(on.notify_command.value.as_deref(), Some("mail -s pets me"));
+        assert_eq!(on.notify_file.value, Some(PathBuf::from("reminders.log")));
+        let off = crate::config::resolve(
+            &[
+                on_file(),
+                parse(&["notify-daemon", "--no-desktop", "--command", ""])
+                    .unwrap()
+                    .config_layer(),
+            ],
+            String::new(),
+        )
+        .unwrap();
+        assert!(!off.notify_desktop.value);
+        assert_eq!(off.notify_desktop.source, Source::Flag);
+        assert_eq!(off.notify_command.value, None);
+        assert!(parse(&["notify-daemon", "--desktop", "--no-desktop"]).is_err());
+    }
+
+    fn on_file() -> Layer {
+        let file = Source::File(PathBuf::from("config.toml"));
+        let mut layer = Layer::default();
+        layer.set("notify_desktop", "true", file.clone());
+        layer.set("notify_command", "notify", file);
+        layer
+    }
+
+    #[test]
+    fn select_takes_a_pet_id() {
+        assert!(parse(&["--select", "Rex"]).is_err());
+        assert!(parse(&["--select", "-1"]).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Copying pets to the system clipboard. The clipboard itself is only
+//! available with the `clipboard` feature; without it every copy fails with
+//! [`ClipboardError::Unsupported`], so headless builds need no display libraries.
+
+use crate::Pet;
+use chrono::NaiveDate;
+use thiserror::Error;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum YankFormat {
+    /// The pet as it is stored in the DB, pretty-printed.
+    Json,
+    /// `Name (category, age N)`.
+    Summary,
+}
+
+#[derive(Error, Debug)]
+pub enum ClipboardError {
+    #[cfg(not(feature = "clipboard"))]
+    #[error("clipboard support is not built in (enable the `clipboard` feature)")]
+    Unsupported,
+    #[cfg(feature = "clipboard")]
+    #[error("no clipboard available: {0}")]
+    Unavailable(String),
+}
+
+pub fn yank_text(pet: &Pet, format: YankFormat, today: NaiveDate) -> String {
+    match format {
+        YankFormat::Json => serde_json::to_string_pretty(pet).expect("pets serialize"),
+        YankFormat::Summary => format!(
+            "{} ({}, age {})",
+            pet.name,
+            pet.category,
+            pet.display_age(today)
+        ),
+    }
+}
+
+/// Opened on first use and kept for the session: on X11 the copied text is
+/// only served for as long as the clipboard handle lives.
+#[derive(Default)]
+pub struct Clipboard {
+    #[cfg(feature = "clipboard")]
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    #[cfg(feature = "clipboard")]
+    pub fn copy(&mut self, text: String) -> Result<(), ClipboardError> {
+        let unavailable = |err: arboard::Error| ClipboardError::Unavailable(err.to_string());
+        let clipboard = match &mut self.inner {
+            Some(clipboard) => clipboard,
+            None => self
+                .inner
+                .insert(arboard::Clipboard::new().map_err(unavailable)?),
+        };
+        clipboard.set_text(text).map_err(unavailable)
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    pub fn copy(&mut self, _text: String) -> Result<(), ClipboardError> {
+        Err(ClipboardError::Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Born on a leap day, with a note and a record.
+    fn coco() -> Pet {
+        serde_json::from_value(serde_json::json!({
+            "id": 7,
+            "name": "Coco",
+            "category": "cats",
+            "age_months": 0,
+            "birthdate": "2020-02-29",
+            "created_at": "2024-01-01T00:00:00Z",
+            "notes": "likes \"tuna\"\non the sofa",
+            "records": [{"date": "2024-03-01", "kind": "vaccination", "note": "FVRCP"}],
+        }))
+        .expect("a valid pet")
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+    }
+
+    #[test]
+    fn json_is_the_pet_as_stored_pretty_printed() {
+        let expected = r#"{
+  "id": 7,
+  "name": "Coco",
+  "category": "cats",
+  "age_months": 0,
+  "created_at": "2024-01-01T00:00:00Z",
+  "notes": "likes \"tuna\"\non the sofa",
+  "birthdate": "2020-02-29",
+  "records": [
+    {
+      "date": "2024-03-01",
+      "kind": "vaccination",
+      "note": "FVRCP"
+    }
+  ],
+  "status": "available"
+}"#;
+        assert_eq!(yank_text(&coco(), YankFormat::Json, today()), expected);
+    }
+
+    #[test]
+    fn the_summary_is_one_line() {
+        assert_eq!(
+            yank_text(&coco(), YankFormat::Summary, today()),
+            "Coco (cats, age 4 y 3 m)"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::fields::{PetField, LIST_COLUMNS};
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// The `|` overlay for choosing the columns of the pet list and their order.
+/// The name column can be moved but not hidden.
+pub struct ColumnChooser {
+    /// Every column the list can show, in order, and whether it is shown.
+    columns: Vec<(PetField, bool)>,
+    selected: usize,
+}
+
+pub enum ChooserOutcome {
+    Open,
+    Cancelled,
+    /// The columns to show, in order.
+    Chosen(Vec<PetField>),
+}
+
+impl ColumnChooser {
+    /// Starts with the `shown` columns in their order, then the hidden ones.
+    pub fn new(shown: &[PetField]) -> Self {
+        let hidden = LIST_COLUMNS.iter().filter(|column| !shown.contains(column));
+        let columns = shown
+            .iter()
+            .map(|column| (*column, true))
+            .chain(hidden.map(|column| (*column, false)))
+            .collect();
+        ColumnChooser {
+            columns,
+            selected: 0,
+        }
+    }
+
+    pub fn columns(&self) -> &[(PetField, bool)] {
+        &self.columns
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// `j`/`k` move the highlight, `J`/`K` move the highlighted column down
+    /// and up, Space shows or hides it and Enter applies.
+    pub fn handle_key(&mut self, key: KeyEvent) -> ChooserOutcome {
+        let last = self.columns.len() - 1;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return ChooserOutcome::Cancelled,
+            KeyCode::Enter => {
+                return ChooserOutcome::Chosen(
+                    self.columns
+                        .iter()
+                        .filter(|(_, shown)| *shown)
+                        .map(|(column, _)| *column)
+                        .collect(),
+                )
+            }
+            KeyCode::Down | KeyCode::Char('j') if self.selected < last => self.selected += 1,
+            KeyCode::Up | KeyCode::Char('k') if self.selected > 0 => self.selected -= 1,
+            KeyCode::Char('J') if self.selected < last => {
+                self.columns.swap(self.selected, self.selected + 1);
+                self.selected += 1;
+            }
+            KeyCode::Char('K') if self.selected > 0 => {
+                self.columns.swap(self.selected, self.selected - 1);
+                self.selected -= 1;
+            }
+            KeyCode::Char(' ') => {
+                let (column, shown) = &mut self.columns[self.selected];
+                if *column != PetField::Name {
+                    *shown = !*shown;
+                }
+            }
+            _ => {}
+        }
+        ChooserOutcome::Open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(chooser: &mut ColumnChooser, keys: &str) -> ChooserOutcome {
+        let mut outcome = ChooserOutcome::Open;
+        for key in keys.chars() {
+            let code = match key {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                c => KeyCode::Char(c),
+            };
+            outcome = chooser.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        outcome
+    }
+
+    fn chosen(outcome: ChooserOutcome) -> Vec<PetField> {
+        match outcome {
+            ChooserOutcome::Chosen(columns) => columns,
+            ChooserOutcome::Open => panic!("still open"),
+            ChooserOutcome::Cancelled => panic!("cancelled"),
+        }
+    }
+
+    #[test]
+    fn starts_with_the_shown_columns_then_the_hidden_ones() {
+        let chooser = ColumnChooser::new(&[PetField::Status, PetField::Name]);
+        assert_eq!(
+            chooser.columns(),
+            [
+                (PetField::Status, true),
+                (PetField::Name, true),
+                (PetField::Category, false),
+                (PetField::Age, false),
+                (PetField::Owner, false),
+                (PetField::Created, false),
+            ]
+        );
+        assert_eq!(chooser.selected(), 0);
+    }
+
+    #[test]
+    fn space_shows_and_hides_columns() {
+        let mut chooser = ColumnChooser::new(&[PetField::Name, PetField::Status]);
+        let columns = chosen(press(&mut chooser, "j j j \n"));
+        assert_eq!(columns, [PetField::Name, PetField::Category, PetField::Age]);
+    }
+
+    #[test]
+    fn the_name_cant_be_hidden() {
+        let mut chooser = ColumnChooser::new(&[PetField::Name, PetField::Status]);
+        let columns = chosen(press(&mut chooser, " j \n"));
+        assert_eq!(columns, [PetField::Name]);
+    }
+
+    #[test]
+    fn shift_j_and_k_move_the_highlighted_column() {
+        let mut chooser = ColumnChooser::new(&[PetField::Name, PetField::Status]);
+        press(&mut chooser, "JJ");
+        assert_eq!(chooser.selected(), 2);
+        assert_eq!(chooser.columns()[2], (PetField::Name, true));
+        press(&mut chooser, "jjjK");
+        assert_eq!(chooser.selected(), 4);
+        let columns = chosen(press(&mut chooser, " \n"));
+        assert_eq!(
+            columns,
+            [PetField::Status, PetField::Name, PetField::Created]
+        );
+    }
+
+    #[test]
+    fn moving_stops_at_the_ends() {
+        let mut chooser = ColumnChooser::new(&[PetField::Name]);
+        press(&mut chooser, "kK");
+        assert_eq!(chooser.selected(), 0);
+        assert_eq!(chooser.columns()[0].0, PetField::Name);
+        press(&mut chooser, "jjjjjjjjJ");
+        assert_eq!(chooser.selected(), 5);
+        assert_eq!(chooser.columns()[5].0, PetField::Created);
+    }
+
+    #[test]
+    fn esc_and_q_cancel() {
+        let mut chooser = ColumnChooser::new(&[PetField::Name]);
+        assert!(matches!(
+            press(&mut chooser, "j \x1b"),
+            ChooserOutcome::Cancelled
+        ));
+        assert!(matches!(
+            press(&mut chooser, "q"),
+            ChooserOutcome::Cancelled
+        ));
+    }
+}
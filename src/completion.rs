@@ -0,0 +1,231 @@
+//! Tab completion for the search prompt and the arguments of the `:` command
+//! line. What can be completed comes from a `Candidates` source; the
+//! `Completer` matches the word typed against it, ignoring case, and goes
+//! through the matches on each further Tab.
+
+use crate::Pet;
+
+pub trait Candidates {
+    /// Everything a word can be completed to. Duplicates are offered once.
+    fn candidates(&self) -> Vec<String>;
+}
+
+/// A fixed list of words, like the sort keys.
+pub struct Words(pub &'static [&'static str]);
+
+impl Candidates for Words {
+    fn candidates(&self) -> Vec<String> {
+        self.0.iter().map(|word| (*word).to_owned()).collect()
+    }
+}
+
+pub struct PetNames<'a>(pub &'a [Pet]);
+
+impl Candidates for PetNames<'_> {
+    fn candidates(&self) -> Vec<String> {
+        self.0.iter().map(|pet| pet.name.clone()).collect()
+    }
+}
+
+pub struct Categories<'a>(pub &'a [Pet]);
+
+impl Candidates for Categories<'_> {
+    fn candidates(&self) -> Vec<String> {
+        self.0.iter().map(|pet| pet.category.clone()).collect()
+    }
+}
+
+/// The candidates of several sources together.
+pub struct Both<A, B>(pub A, pub B);
+
+impl<A: Candidates, B: Candidates> Candidates for Both<A, B> {
+    fn candidates(&self) -> Vec<String> {
+        let mut candidates = self.0.candidates();
+        candidates.extend(self.1.candidates());
+        candidates
+    }
+}
+
+/// The matches of the word last completed and which of them is shown.
+#[derive(Default)]
+pub struct Completer {
+    matches: Vec<String>,
+    index: usize,
+}
+
+impl Completer {
+    /// The first candidate of `source` that starts with `word`, in any case,
+    /// or the next one if `word` is the match offered by the last Tab.
+    pub fn complete(&mut self, word: &str, source: &dyn Candidates) -> Option<String> {
+        if let Some(next) = self.next(word) {
+            return Some(next);
+        }
+        let typed = word.to_lowercase();
+        let mut matches: Vec<String> = source
+            .candidates()
+            .into_iter()
+            .filter(|candidate| candidate.to_lowercase().starts_with(&typed))
+            .collect();
+        matches.sort_by_key(|candidate| candidate.to_lowercase());
+        matches.dedup();
+        self.matches = matches;
+        self.index = 0;
+        self.matches.first().cloned()
+    }
+
+    /// Like `complete`, for matches the caller found and ranked itself, best
+    /// first. `rank` is only called when `word` isn't the match shown.
+    pub fn complete_ranked(
+        &mut self,
+        word: &str,
+        rank: impl FnOnce() -> Vec<String>,
+    ) -> Option<String> {
+        if let Some(next) = self.next(word) {
+            return Some(next);
+        }
+        self.matches = rank();
+        self.index = 0;
+        self.matches.first().cloned()
+    }
+
+    /// The match after `word` if it is the one shown.
+    fn next(&mut self, word: &str) -> Option<String> {
+        if self
+            .matches
+            .get(self.index)
+            .is_none_or(|shown| shown != word)
+        {
+            return None;
+        }
+        self.index = (self.index + 1) % self.matches.len();
+        Some(self.matches[self.index].clone())
+    }
+
+    /// Forgets the matches, for when something other than Tab is pressed.
+    pub fn reset(&mut self) {
+        self.matches.clear();
+        self.index = 0;
+    }
+
+    /// Which match is shown and how many there are, counting from 1, while
+    /// going through them.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        (!self.matches.is_empty()).then_some((self.index + 1, self.matches.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+
+    const FRUIT: Words = Words(&["banana", "Apple", "apricot", "avocado", "apple"]);
+
+    #[test]
+    fn completes_ignoring_case() {
+        let mut completer = Completer::default();
+        assert_eq!(completer.complete("AP", &FRUIT), Some("Apple".to_owned()));
+        completer.reset();
+        assert_eq!(completer.complete("b", &FRUIT), Some("banana".to_owned()));
+        completer.reset();
+        assert_eq!(completer.complete("cherry", &FRUIT), None);
+        assert_eq!(completer.position(), None);
+    }
+
+    #[test]
+    fn repeated_tabs_go_round_the_matches() {
+        let mut completer = Completer::default();
+        let mut word = "ap".to_owned();
+        let mut shown = Vec::new();
+        for _ in 0..4 {
+            word = completer.complete(&word, &FRUIT).unwrap();
+            shown.push((word.clone(), completer.position().unwrap()));
+        }
+        assert_eq!(
+            shown,
+            vec![
+                ("Apple".to_owned(), (1, 3)),
+                ("apple".to_owned(), (2, 3)),
+                ("apricot".to_owned(), (3, 3)),
+                ("Apple".to_owned(), (1, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_changed_word_starts_over() {
+        let mut completer = Completer::default();
+        assert_eq!(completer.complete("ap", &FRUIT), Some("Apple".to_owned()));
+        assert_eq!(completer.complete("av", &FRUIT), Some("avocado".to_owned()));
+        assert_eq!(completer.position(), Some((1, 1)));
+        completer.reset();
+        assert_eq!(completer.position(), None);
+        assert_eq!(
+            completer.complete("avocado", &FRUIT),
+            Some("avocado".to_owned())
+        );
+    }
+
+    #[test]
+    fn offers_duplicates_once() {
+        let pets = vec![
+            pet(1, "Rex", "dogs"),
+            pet(2, "Max", "dogs"),
+            pet(3, "Rex", "dogs"),
+        ];
+        let mut completer = Completer::default();
+        assert_eq!(
+            completer.complete("r", &PetNames(&pets)),
+            Some("Rex".to_owned())
+        );
+        assert_eq!(completer.position(), Some((1, 1)));
+        assert_eq!(
+            completer.complete("d", &Categories(&pets)),
+            Some("dogs".to_owned())
+        );
+        assert_eq!(completer.position(), Some((1, 1)));
+    }
+
+    #[test]
+    fn both_offers_the_candidates_of_each_source() {
+        let pets = vec![pet(1, "Coco", "cats"), pet(2, "Fluffy", "cats")];
+        let mut completer = Completer::default();
+        let source = Both(PetNames(&pets), Categories(&pets));
+        assert_eq!(
+            completer.complete("flu", &source),
+            Some("Fluffy".to_owned())
+        );
+        completer.reset();
+        let mut word = "c".to_owned();
+        let mut shown = Vec::new();
+        for _ in 0..2 {
+            word = completer.complete(&word, &source).unwrap();
+            shown.push(word.clone());
+        }
+        assert_eq!(shown, vec!["cats", "Coco"]);
+    }
+
+    #[test]
+    fn ranked_matches_keep_their_order_and_are_ranked_once() {
+        let mut completer = Completer::default();
+        let mut ranked = 0;
+        let mut rank = || {
+            ranked += 1;
+            vec!["zebra".to_owned(), "aardvark".to_owned()]
+        };
+        assert_eq!(
+            completer.complete_ranked("a", &mut rank),
+            Some("zebra".to_owned())
+        );
+        assert_eq!(
+            completer.complete_ranked("zebra", &mut rank),
+            Some("aardvark".to_owned())
+        );
+        assert_eq!(
+            completer.complete_ranked("aardvark", &mut rank),
+            Some("zebra".to_owned())
+        );
+        assert_eq!(ranked, 1);
+        assert_eq!(completer.complete_ranked("q", Vec::new), None);
+    }
+}
@@ -0,0 +1,291 @@
+//! `pet-cli completions`: shell completion scripts generated from the clap
+//! definition of the CLI, so new flags and subcommands complete without
+//! touching the scripts.
+
+use clap::builder::ValueHint;
+use clap::{Arg, Command, ValueEnum};
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// What can follow an option on the command line.
+enum Value {
+    /// The option is a flag and takes no value.
+    None,
+    Any,
+    Path,
+    Choices(Vec<String>),
+}
+
+/// An option of a command, in the terms all three shells need.
+struct Opt {
+    long: String,
+    short: Option<char>,
+    help: String,
+    value: Value,
+}
+
+pub fn generate(shell: Shell, cli: &mut Command) -> String {
+    cli.build();
+    match shell {
+        Shell::Bash => bash(cli),
+        Shell::Zsh => zsh(cli),
+        Shell::Fish => fish(cli),
+    }
+}
+
+fn options(command: &Command) -> Vec<Opt> {
+    command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set() && !arg.is_positional())
+        .filter_map(|arg| {
+            Some(Opt {
+                long: arg.get_long()?.to_owned(),
+                short: arg.get_short(),
+                help: first_line(arg),
+                value: value(arg),
+            })
+        })
+        .collect()
+}
+
+fn value(arg: &Arg) -> Value {
+    if !arg.get_action().takes_values() {
+        return Value::None;
+    }
+    let choices: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_owned())
+        .collect();
+    if !choices.is_empty() {
+        return Value::Choices(choices);
+    }
+    match arg.get_value_hint() {
+        ValueHint::AnyPath | ValueHint::FilePath | ValueHint::DirPath => Value::Path,
+        _ => Value::Any,
+    }
+}
+
+fn first_line(arg: &Arg) -> String {
+    arg.get_help()
+        .map(|help| help.to_string())
+        .unwrap_or_default()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn subcommands(cli: &Command) -> impl Iterator<Item = &Command> {
+    cli.get_subcommands()
+        .filter(|command| !command.is_hide_set() && command.get_name() != "help")
+}
+
+fn about(command: &Command) -> String {
+    command
+        .get_about()
+        .map(|about| about.to_string())
+        .unwrap_or_default()
+}
+
+fn bash(cli: &Command) -> String {
+    let name = cli.get_name();
+    let function = format!("_{}", name.replace('-', "_"));
+    let names: Vec<&str> = subcommands(cli).map(Command::get_name).collect();
+    let mut out = format!(
+        "{function}() {{
+    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"
+    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"
+    local command=\"\"
+    local word
+    for word in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do
+        case \"$word\" in
+            {subcommands})
+                command=\"$word\"
+                break
+                ;;
+        esac
+    done
+    case \"$command:$prev\" in
+",
+        function = function,
+        subcommands = names.join("|"),
+    );
+    let commands = std::iter::once(("", cli))
+        .chain(subcommands(cli).map(|command| (command.get_name(), command)));
+    let mut words = Vec::new();
+    for (command_name, command) in commands {
+        let options = options(command);
+        for opt in &options {
+            let reply = match &opt.value {
+                Value::None => continue,
+                Value::Any => "COMPREPLY=()".to_owned(),
+                Value::Path => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_owned(),
+                Value::Choices(choices) => format!(
+                    "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                    choices.join(" ")
+                ),
+            };
+            out.push_str(&format!(
+                "        {}:--{})\n            {}\n            return\n            ;;\n",
+                command_name, opt.long, reply
+            ));
+        }
+        let mut command_words: Vec<String> = options
+            .iter()
+            .map(|opt| format!("--{}", opt.long))
+            .collect();
+        if command_name.is_empty() {
+            command_words.extend(names.iter().map(|name| name.to_string()));
+        }
+        words.push((command_name, command_words));
+    }
+    out.push_str("    esac\n    case \"$command\" in\n");
+    for (command_name, command_words) in words {
+        out.push_str(&format!(
+            "        \"{}\")\n            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            ;;\n",
+            command_name,
+            command_words.join(" ")
+        ));
+    }
+    out.push_str(&format!(
+        "    esac\n}}\n\ncomplete -F {} {}\n",
+        function, name
+    ));
+    out
+}
+
+/// Escapes text for the inside of a single-quoted `_arguments` spec.
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_specs(command: &Command) -> Vec<String> {
+    options(command)
+        .iter()
+        .map(|opt| {
+            let help = zsh_escape(&opt.help);
+            let names = match opt.short {
+                Some(short) => format!(
+                    "'(-{short} --{long})'{{-{short},--{long}}}",
+                    short = short,
+                    long = opt.long
+                ),
+                None => format!("--{}", opt.long),
+            };
+            let value = match &opt.value {
+                Value::None => String::new(),
+                Value::Any => format!(":{}: ", opt.long),
+                Value::Path => format!(":{}:_files", opt.long),
+                Value::Choices(choices) => format!(":{}:({})", opt.long, choices.join(" ")),
+            };
+            format!("{}'[{}]{}'", names, help, value)
+        })
+        .collect()
+}
+
+fn zsh(cli: &Command) -> String {
+    let name = cli.get_name();
+    let function = format!("_{}", name.replace('-', "_"));
+    let indent = " \\\n        ";
+    let mut out = format!(
+        "#compdef {name}
+
+{function}() {{
+    local line state
+    _arguments -C{indent}{specs}{indent}'1: :->command'{indent}'*:: :->args'
+    case $state in
+        command)
+            local -a commands
+            commands=(\n",
+        name = name,
+        function = function,
+        indent = indent,
+        specs = zsh_specs(cli).join(indent),
+    );
+    for command in subcommands(cli) {
+        out.push_str(&format!(
+            "                '{}:{}'\n",
+            command.get_name(),
+            about(command).replace('\'', "'\\''")
+        ));
+    }
+    out.push_str(
+        "            )
+            _describe 'command' commands
+            ;;
+        args)
+            case $line[1] in\n",
+    );
+    let indent = " \\\n                        ";
+    for command in subcommands(cli) {
+        let specs = zsh_specs(command);
+        out.push_str(&format!(
+            "                {})\n                    _arguments{}{}\n                    ;;\n",
+            command.get_name(),
+            indent,
+            specs.join(indent)
+        ));
+    }
+    out.push_str(&format!(
+        "            esac
+            ;;
+    esac
+}}
+
+{function} \"$@\"\n",
+        function = function
+    ));
+    out
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn fish(cli: &Command) -> String {
+    let name = cli.get_name();
+    let mut out = String::new();
+    let top_level = "__fish_use_subcommand".to_owned();
+    for command in subcommands(cli) {
+        out.push_str(&format!(
+            "complete -c {} -n {} -f -a {} -d '{}'\n",
+            name,
+            top_level,
+            command.get_name(),
+            fish_escape(&about(command))
+        ));
+    }
+    let commands = std::iter::once((top_level, cli)).chain(subcommands(cli).map(|command| {
+        let condition = format!("'__fish_seen_subcommand_from {}'", command.get_name());
+        (condition, command)
+    }));
+    for (condition, command) in commands {
+        for opt in options(command) {
+            let mut line = format!("complete -c {} -n {} -l {}", name, condition, opt.long);
+            if let Some(short) = opt.short {
+                line.push_str(&format!(" -s {}", short));
+            }
+            match &opt.value {
+                Value::None => {}
+                Value::Any => line.push_str(" -x"),
+                Value::Path => line.push_str(" -r -F"),
+                Value::Choices(choices) => {
+                    line.push_str(&format!(" -x -a '{}'", choices.join(" ")))
+                }
+            }
+            line.push_str(&format!(" -d '{}'\n", fish_escape(&opt.help)));
+            out.push_str(&line);
+        }
+    }
+    out
+}
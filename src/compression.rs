@@ -0,0 +1,820 @@
+//! Compressed DB files. A DB named like `pets.json.gz`, or written with
+//! `compress = "gzip"`, is gzip; reading goes by the first bytes of the file
+//! rather than its name, so a renamed file still opens. zstd files, and
+//! writes to a name ending in `.zst`, are recognized but this build can't read
+//! or write them.
+//!
+//! The codec is written here rather than taken from a crate: inflate for
+//! reading anything gzip writes, and for writing, LZ77 matches in one block
+//! of the fixed Huffman codes, which is simple and still shrinks the DB's
+//! repetitive JSON several times over.
+
+use clap::ValueEnum;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// How the DB is compressed when written.
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum Compress {
+    /// Gzip for a name ending in `.gz`, otherwise as the file is now.
+    #[default]
+    Auto,
+    /// Plain JSON.
+    None,
+    Gzip,
+}
+
+impl Compress {
+    pub fn name(self) -> &'static str {
+        match self {
+            Compress::Auto => "auto",
+            Compress::None => "none",
+            Compress::Gzip => "gzip",
+        }
+    }
+}
+
+/// The most a gzip stream is inflated to, far beyond any DB, so that a small
+/// crafted file can't take all memory.
+const MAX_DECODED_LEN: usize = 1 << 30;
+
+/// The levels `compress_level` takes, from fastest to smallest, as gzip's.
+pub const LEVELS: std::ops::RangeInclusive<u32> = 1..=9;
+
+/// What a file's bytes are compressed with.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Codec {
+    Plain,
+    Gzip,
+    Zstd,
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+pub fn detect(bytes: &[u8]) -> Codec {
+    if bytes.starts_with(GZIP_MAGIC) {
+        Codec::Gzip
+    } else if bytes.starts_with(ZSTD_MAGIC) {
+        Codec::Zstd
+    } else {
+        Codec::Plain
+    }
+}
+
+/// The codec to write `path` with: the one `compress` asks for, else the one
+/// its name suggests, else the one the file has now, so a compressed DB
+/// renamed to `.json` stays compressed.
+pub fn for_write(path: &Path, compress: Compress, on_disk: Option<Codec>) -> Codec {
+    match (compress, by_name(path), on_disk) {
+        (Compress::None, _, _) => Codec::Plain,
+        (Compress::Gzip, _, _) | (Compress::Auto, Codec::Gzip, _) => Codec::Gzip,
+        (Compress::Auto, Codec::Zstd, _) => Codec::Zstd,
+        (Compress::Auto, _, Some(Codec::Gzip)) => Codec::Gzip,
+        (Compress::Auto, _, _) => Codec::Plain,
+    }
+}
+
+/// Gzip for a path ending in `.gz`, zstd for one ending in `.zst`.
+pub fn by_name(path: &Path) -> Codec {
+    match path.extension() {
+        Some(extension) if extension.eq_ignore_ascii_case("gz") => Codec::Gzip,
+        Some(extension) if extension.eq_ignore_ascii_case("zst") => Codec::Zstd,
+        _ => Codec::Plain,
+    }
+}
+
+/// The bytes of `content` as `codec` has them. zstd is refused rather than
+/// leaving plain JSON under a zstd name.
+pub fn encode(content: &[u8], codec: Codec, level: u32) -> io::Result<Vec<u8>> {
+    match codec {
+        Codec::Plain => Ok(content.to_vec()),
+        Codec::Gzip => Ok(gzip(content, level)),
+        Codec::Zstd => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "zstd output isn't supported by this build of pet-cli; \
+             use a name ending in `.gz` or `--compress gzip` instead",
+        )),
+    }
+}
+
+/// The content of `bytes` in whichever codec they are. Broken streams fail
+/// with [`io::ErrorKind::InvalidData`].
+pub fn decode(bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+    match detect(&bytes) {
+        Codec::Plain => Ok(bytes),
+        Codec::Gzip => gunzip(&bytes, MAX_DECODED_LEN).map_err(invalid),
+        Codec::Zstd => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the file is zstd-compressed, which this build of pet-cli can't read; \
+             decompress it with `zstd -d` or use gzip",
+        )),
+    }
+}
+
+/// The text of the file at `path`, decompressed.
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    String::from_utf8(decode(fs::read(path)?)?).map_err(invalid)
+}
+
+fn invalid(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, byte| {
+        CRC_TABLE[((crc ^ u32::from(*byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+const WINDOW: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+
+fn gzip(content: &[u8], level: u32) -> Vec<u8> {
+    // No name or time, so the same DB always compresses to the same bytes;
+    // the OS is "unknown".
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255];
+    let mut bits = BitWriter {
+        out: &mut out,
+        bits: 0,
+        count: 0,
+    };
+    deflate(content, level, &mut bits);
+    bits.flush();
+    out.extend_from_slice(&crc32(content).to_le_bytes());
+    out.extend_from_slice(&(content.len() as u32).to_le_bytes());
+    out
+}
+
+/// Writes `content` as one final block of the fixed Huffman codes, matching
+/// against the last 32K greedily. Higher levels follow longer chains of
+/// earlier places with the same three bytes.
+fn deflate(content: &[u8], level: u32, bits: &mut BitWriter) {
+    let chain_limit = 1 << (level.clamp(1, 9) + 2);
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut previous = vec![usize::MAX; WINDOW];
+    let insert = |at: usize, head: &mut [usize], previous: &mut [usize]| {
+        if at + MIN_MATCH <= content.len() {
+            let key = hash(&content[at..]);
+            previous[at % WINDOW] = head[key];
+            head[key] = at;
+        }
+    };
+    bits.write(1, 1);
+    bits.write(1, 2);
+    let mut at = 0;
+    while at < content.len() {
+        let (mut best_length, mut best_distance) = (0, 0);
+        if at + MIN_MATCH <= content.len() {
+            let longest = MAX_MATCH.min(content.len() - at);
+            let mut candidate = head[hash(&content[at..])];
+            let mut chain = chain_limit;
+            while candidate != usize::MAX && at - candidate <= WINDOW && chain > 0 {
+                let length = content[candidate..]
+                    .iter()
+                    .zip(&content[at..at + longest])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best_length {
+                    best_length = length;
+                    best_distance = at - candidate;
+                    if length == longest {
+                        break;
+                    }
+                }
+                let next = previous[candidate % WINDOW];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain -= 1;
+            }
+        }
+        if best_length >= MIN_MATCH {
+            write_match(bits, best_length, best_distance);
+            for position in at..at + best_length {
+                insert(position, &mut head, &mut previous);
+            }
+            at += best_length;
+        } else {
+            write_symbol(bits, u16::from(content[at]));
+            insert(at, &mut head, &mut previous);
+            at += 1;
+        }
+    }
+    write_symbol(bits, 256);
+}
+
+/// Where to look for earlier places with the same first three bytes.
+fn hash(bytes: &[u8]) -> usize {
+    let three = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+    (three.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+}
+
+fn write_match(bits: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE
+        .iter()
+        .rposition(|base| usize::from(*base) <= length)
+        .expect("matches are at least 3 long");
+    write_symbol(bits, 257 + code as u16);
+    bits.write(
+        (length - usize::from(LENGTH_BASE[code])) as u32,
+        LENGTH_EXTRA[code].into(),
+    );
+    let code = DISTANCE_BASE
+        .iter()
+        .rposition(|base| usize::from(*base) <= distance)
+        .expect("distances are at least 1");
+    bits.write_reversed(code as u32, 5);
+    bits.write(
+        (distance - usize::from(DISTANCE_BASE[code])) as u32,
+        DISTANCE_EXTRA[code].into(),
+    );
+}
+
+/// A literal, length or the end of the block in the fixed Huffman code.
+fn write_symbol(bits: &mut BitWriter, symbol: u16) {
+    let symbol = u32::from(symbol);
+    match symbol {
+        0..=143 => bits.write_reversed(0x30 + symbol, 8),
+        144..=255 => bits.write_reversed(0x190 + symbol - 144, 9),
+        256..=279 => bits.write_reversed(symbol - 256, 7),
+        _ => bits.write_reversed(0xc0 + symbol - 280, 8),
+    }
+}
+
+struct BitWriter<'a> {
+    out: &'a mut Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter<'_> {
+    /// The low `count` bits of `value`, least significant first.
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= u64::from(value) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// A Huffman code, which goes most significant bit first.
+    fn write_reversed(&mut self, code: u32, count: u32) {
+        self.write(code.reverse_bits() >> (32 - count), count);
+    }
+
+    fn flush(&mut self) {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.bits = 0;
+        self.count = 0;
+    }
+}
+
+/// The content of the gzip stream `bytes`, failing if it would be longer than
+/// `limit`.
+fn gunzip(bytes: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let truncated = || "the gzip stream is cut short".to_owned();
+    if bytes.len() < 18 || bytes[2] != 8 {
+        return Err("not a gzip stream pet-cli can read".to_owned());
+    }
+    let flags = bytes[3];
+    let mut at = 10;
+    if flags & 4 != 0 {
+        let extra = bytes.get(at..at + 2).ok_or_else(truncated)?;
+        at += 2 + usize::from(u16::from_le_bytes([extra[0], extra[1]]));
+    }
+    for flag in [8, 16] {
+        if flags & flag != 0 {
+            let end = bytes
+                .get(at..)
+                .and_then(|rest| rest.iter().position(|byte| *byte == 0))
+                .ok_or_else(truncated)?;
+            at += end + 1;
+        }
+    }
+    if flags & 2 != 0 {
+        at += 2;
+    }
+    let mut reader = BitReader {
+        bytes: bytes.get(at..).ok_or_else(truncated)?,
+        at: 0,
+        bits: 0,
+        count: 0,
+    };
+    let content = inflate(&mut reader, limit)?;
+    let trailer = bytes
+        .get(at + reader.at..at + reader.at + 8)
+        .ok_or_else(truncated)?;
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if crc != crc32(&content) || size != content.len() as u32 {
+        return Err("the gzip stream is corrupt: its checksum doesn't match".to_owned());
+    }
+    Ok(content)
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    /// The bytes taken so far, whole once the bits left are dropped.
+    at: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        while self.count < count {
+            let byte = *self
+                .bytes
+                .get(self.at)
+                .ok_or("the gzip stream is cut short")?;
+            self.bits |= u32::from(byte) << self.count;
+            self.at += 1;
+            self.count += 8;
+        }
+        let value = self.bits & ((1u64 << count) - 1) as u32;
+        self.bits = self.bits.checked_shr(count).unwrap_or(0);
+        self.count -= count;
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+    }
+}
+
+/// A canonical Huffman code as the number of codes of each length and the
+/// symbols in code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for length in lengths {
+            counts[usize::from(*length)] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length != 0 {
+                symbols[usize::from(offsets[usize::from(*length)])] = symbol as u16;
+                offsets[usize::from(*length)] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(self.counts[length]);
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("the gzip stream is corrupt: a bad Huffman code".to_owned())
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    for (symbol, length) in lengths.iter_mut().enumerate() {
+        *length = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+/// The order the code length codes of a dynamic block come in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let corrupt = || "the gzip stream is corrupt: bad code lengths".to_owned();
+    let literals = reader.bits(5)? as usize + 257;
+    let distances = reader.bits(5)? as usize + 1;
+    let code_lengths = reader.bits(4)? as usize + 4;
+    let mut lengths = [0u8; 19];
+    for index in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[*index] = reader.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&lengths);
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let symbol = code_length_code.decode(reader)?;
+        let (length, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last().ok_or_else(corrupt)?, 3 + reader.bits(2)?),
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(length, repeat as usize));
+    }
+    if lengths.len() > literals + distances {
+        return Err(corrupt());
+    }
+    Ok((
+        Huffman::new(&lengths[..literals]),
+        Huffman::new(&lengths[literals..]),
+    ))
+}
+
+fn inflate(reader: &mut BitReader, limit: usize) -> Result<Vec<u8>, String> {
+    let corrupt = |what: &str| format!("the gzip stream is corrupt: {}", what);
+    let too_long = || format!("the gzip stream inflates to more than {} bytes", limit);
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = reader
+                    .bytes
+                    .get(reader.at..reader.at + 4)
+                    .ok_or("the gzip stream is cut short")?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err(corrupt("a bad stored block length"));
+                }
+                let start = reader.at + 4;
+                let stored = reader
+                    .bytes
+                    .get(start..start + usize::from(length))
+                    .ok_or("the gzip stream is cut short")?;
+                if out.len() + stored.len() > limit {
+                    return Err(too_long());
+                }
+                out.extend_from_slice(stored);
+                reader.at = start + usize::from(length);
+            }
+            kind @ (1 | 2) => {
+                let (literals, distances) = match kind {
+                    1 => fixed_codes(),
+                    _ => dynamic_codes(reader)?,
+                };
+                loop {
+                    let symbol = usize::from(literals.decode(reader)?);
+                    if symbol < 256 {
+                        if out.len() >= limit {
+                            return Err(too_long());
+                        }
+                        out.push(symbol as u8);
+                        continue;
+                    }
+                    if symbol == 256 {
+                        break;
+                    }
+                    let code = symbol - 257;
+                    let length = usize::from(
+                        *LENGTH_BASE
+                            .get(code)
+                            .ok_or_else(|| corrupt("a bad length"))?,
+                    ) + reader.bits(LENGTH_EXTRA[code].into())? as usize;
+                    let code = usize::from(distances.decode(reader)?);
+                    let distance = usize::from(
+                        *DISTANCE_BASE
+                            .get(code)
+                            .ok_or_else(|| corrupt("a bad distance"))?,
+                    ) + reader.bits(DISTANCE_EXTRA[code].into())? as usize;
+                    if distance > out.len() {
+                        return Err(corrupt("a match before the start"));
+                    }
+                    if out.len() + length > limit {
+                        return Err(too_long());
+                    }
+                    let start = out.len() - distance;
+                    for index in start..start + length {
+                        out.push(out[index]);
+                    }
+                }
+            }
+            _ => return Err(corrupt("a bad block type")),
+        }
+        if last {
+            reader.align();
+            return Ok(out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What `gzip -9 -n` made of `pets.json`: one dynamic Huffman block.
+    const GZIP_9: &[u8] = include_bytes!("../tests/fixtures/pets.json.gz");
+    const GZIP_9_CONTENT: &[u8] = include_bytes!("../tests/fixtures/pets.json");
+
+    /// Bytes that don't repeat, from a fixed seed.
+    fn noise(length: usize) -> Vec<u8> {
+        let mut state: u32 = 0x1234_5678;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    fn samples() -> Vec<Vec<u8>> {
+        vec![
+            Vec::new(),
+            b"a".to_vec(),
+            b"abc".to_vec(),
+            GZIP_9_CONTENT.to_vec(),
+            // Matches of the longest length, and runs past the window.
+            vec![b'a'; 100_000],
+            GZIP_9_CONTENT.repeat(60),
+            noise(70_000),
+        ]
+    }
+
+    /// A gzip stream of `blocks`, already deflated, with the trailer for
+    /// `content`.
+    fn gzip_with(flags: u8, extra: &[u8], blocks: &[u8], content: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0x1f, 0x8b, 8, flags, 0, 0, 0, 0, 0, 255];
+        bytes.extend_from_slice(extra);
+        bytes.extend_from_slice(blocks);
+        bytes.extend_from_slice(&crc32(content).to_le_bytes());
+        bytes.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        bytes
+    }
+
+    /// `content` in stored blocks of at most `size` bytes.
+    fn stored_blocks(content: &[u8], size: usize) -> Vec<u8> {
+        let mut blocks = Vec::new();
+        let mut chunks: Vec<&[u8]> = content.chunks(size).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        for (index, chunk) in chunks.iter().enumerate() {
+            blocks.push(u8::from(index + 1 == chunks.len()));
+            let length = chunk.len() as u16;
+            blocks.extend_from_slice(&length.to_le_bytes());
+            blocks.extend_from_slice(&(!length).to_le_bytes());
+            blocks.extend_from_slice(chunk);
+        }
+        blocks
+    }
+
+    #[test]
+    fn round_trips_at_every_level() {
+        for content in samples() {
+            for level in LEVELS {
+                let encoded = encode(&content, Codec::Gzip, level).unwrap();
+                assert_eq!(detect(&encoded), Codec::Gzip);
+                let decoded = decode(encoded).expect("our own gzip reads back");
+                assert!(
+                    decoded == content,
+                    "{} bytes at level {}",
+                    content.len(),
+                    level
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn compresses_repetitive_json() {
+        let content = GZIP_9_CONTENT.repeat(10);
+        for level in LEVELS {
+            assert!(encode(&content, Codec::Gzip, level).unwrap().len() < content.len() / 4);
+        }
+    }
+
+    #[test]
+    fn writes_the_same_bytes_for_the_same_content() {
+        assert_eq!(
+            encode(GZIP_9_CONTENT, Codec::Gzip, 6).unwrap(),
+            encode(GZIP_9_CONTENT, Codec::Gzip, 6).unwrap()
+        );
+    }
+
+    #[test]
+    fn reads_gzip_9() {
+        assert_eq!((GZIP_9[10] >> 1) & 3, 2, "the vector is a dynamic block");
+        assert_eq!(decode(GZIP_9.to_vec()).unwrap(), GZIP_9_CONTENT);
+    }
+
+    #[test]
+    fn reads_stored_blocks() {
+        for content in [Vec::new(), b"hello".to_vec(), noise(70_000)] {
+            let stream = gzip_with(0, &[], &stored_blocks(&content, 65_535), &content);
+            assert_eq!(decode(stream).unwrap(), content);
+        }
+        // Several blocks in a row.
+        let content = noise(1000);
+        let stream = gzip_with(0, &[], &stored_blocks(&content, 300), &content);
+        assert_eq!(decode(stream).unwrap(), content);
+    }
+
+    #[test]
+    fn reads_streams_with_a_name_comment_and_extra_field() {
+        let content = b"named";
+        let encoded = encode(content, Codec::Gzip, 6).unwrap();
+        let blocks = &encoded[10..encoded.len() - 8];
+        let mut extra = vec![3, 0, b'x', b'y', b'z'];
+        extra.extend_from_slice(b"pets.json\0a comment\0");
+        extra.extend_from_slice(&[0xaa, 0xbb]);
+        let stream = gzip_with(4 | 8 | 16 | 2, &extra, blocks, content);
+        assert_eq!(decode(stream).unwrap(), content);
+    }
+
+    #[test]
+    fn plain_files_read_as_they_are() {
+        assert_eq!(decode(b"[]".to_vec()).unwrap(), b"[]");
+        assert_eq!(decode(Vec::new()).unwrap(), b"");
+        assert_eq!(encode(b"[]", Codec::Plain, 9).unwrap(), b"[]");
+    }
+
+    #[test]
+    fn zstd_is_recognized_but_not_read() {
+        let err = decode([ZSTD_MAGIC, b"rest"].concat()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("zstd"));
+    }
+
+    #[test]
+    fn zst_names_are_refused() {
+        let zst = Path::new("pets.json.ZST");
+        assert_eq!(by_name(zst), Codec::Zstd);
+        assert_eq!(for_write(zst, Compress::Auto, None), Codec::Zstd);
+        assert_eq!(
+            for_write(zst, Compress::Auto, Some(Codec::Gzip)),
+            Codec::Zstd
+        );
+        let write_err = encode(b"[]", Codec::Zstd, 6).unwrap_err();
+        assert_eq!(write_err.kind(), io::ErrorKind::InvalidInput);
+        assert!(
+            write_err
+                .to_string()
+                .starts_with("zstd output isn't supported"),
+            "{}",
+            write_err
+        );
+        let read_err = decode([ZSTD_MAGIC, b"rest"].concat()).unwrap_err();
+        assert_eq!(read_err.kind(), io::ErrorKind::InvalidData);
+        assert!(read_err.to_string().contains("can't read"), "{}", read_err);
+        // Asked for outright, a codec this build has is written instead.
+        assert_eq!(for_write(zst, Compress::None, None), Codec::Plain);
+        assert_eq!(for_write(zst, Compress::Gzip, None), Codec::Gzip);
+    }
+
+    #[test]
+    fn streams_that_inflate_past_the_limit_fail() {
+        let content = vec![b'x'; 100_000];
+        let encoded = encode(&content, Codec::Gzip, 9).unwrap();
+        assert!(encoded.len() < 1_000);
+        assert_eq!(gunzip(&encoded, content.len()).unwrap(), content);
+        assert_eq!(
+            gunzip(&encoded, content.len() - 1).unwrap_err(),
+            "the gzip stream inflates to more than 99999 bytes"
+        );
+        let stored = gzip_with(0, &[], &stored_blocks(&content, 4_096), &content);
+        assert!(gunzip(&stored, 1_000).is_err());
+        assert_eq!(gunzip(&stored, content.len()).unwrap(), content);
+    }
+
+    #[test]
+    fn truncated_streams_fail() {
+        let encoded = encode(&GZIP_9_CONTENT.repeat(3), Codec::Gzip, 9).unwrap();
+        for streams in [&encoded, GZIP_9] {
+            for length in 2..streams.len() {
+                let err = decode(streams[..length].to_vec()).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{} bytes", length);
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_streams_fail_or_read_the_same() {
+        // Any one byte changed is an error, or a byte of the header that
+        // doesn't matter; never a panic or different content.
+        for (stream, content) in [
+            (GZIP_9.to_vec(), GZIP_9_CONTENT.to_vec()),
+            (
+                encode(GZIP_9_CONTENT, Codec::Gzip, 1).unwrap(),
+                GZIP_9_CONTENT.to_vec(),
+            ),
+        ] {
+            for at in 2..stream.len() {
+                for flip in [0x01, 0x10, 0x80, 0xff] {
+                    let mut corrupt = stream.clone();
+                    corrupt[at] ^= flip;
+                    match decode(corrupt) {
+                        Ok(decoded) => assert!(decoded == content, "byte {} ^ {:#x}", at, flip),
+                        Err(err) => assert_eq!(err.kind(), io::ErrorKind::InvalidData),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bad_checksums_and_sizes_fail() {
+        let mut stream = encode(b"content", Codec::Gzip, 6).unwrap();
+        let crc_at = stream.len() - 8;
+        stream[crc_at] ^= 1;
+        let err = decode(stream.clone()).unwrap_err();
+        assert!(err.to_string().contains("checksum"));
+        stream[crc_at] ^= 1;
+        let size_at = stream.len() - 4;
+        stream[size_at] ^= 1;
+        assert!(decode(stream).is_err());
+    }
+
+    #[test]
+    fn bad_stored_lengths_and_block_types_fail() {
+        let mut blocks = stored_blocks(b"hello", 100);
+        blocks[3] ^= 1;
+        let err = decode(gzip_with(0, &[], &blocks, b"hello")).unwrap_err();
+        assert!(err.to_string().contains("stored block length"));
+        let err = decode(gzip_with(0, &[], &[0b111, 0, 0], b"")).unwrap_err();
+        assert!(err.to_string().contains("block type"));
+    }
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn picks_the_codec_to_write() {
+        let (json, gz) = (Path::new("pets.json"), Path::new("pets.json.GZ"));
+        assert_eq!(for_write(json, Compress::Auto, None), Codec::Plain);
+        assert_eq!(for_write(gz, Compress::Auto, None), Codec::Gzip);
+        assert_eq!(
+            for_write(json, Compress::Auto, Some(Codec::Gzip)),
+            Codec::Gzip
+        );
+        assert_eq!(
+            for_write(gz, Compress::None, Some(Codec::Gzip)),
+            Codec::Plain
+        );
+        assert_eq!(for_write(json, Compress::Gzip, None), Codec::Gzip);
+    }
+}
@@ -0,0 +1,1313 @@
+//! Settings that can come from the config file, the environment or flags.
+//! Each source is read into a [`Layer`] of raw strings; [`resolve`] stacks
+//! them so later layers win (defaults < file < env < flags) and only then
+//! parses the values, so every source is checked the same way.
+
+use crate::age;
+use crate::capabilities;
+use crate::compression::{self, Compress};
+use crate::dates::DateOrder;
+use crate::db_format::DbFormat;
+use crate::durability::Durability;
+use crate::fields::{self, PetField};
+use crate::locales;
+use crate::menu;
+use crate::palette;
+use crate::stats::AgeBucketWidth;
+use crate::strings::Lang;
+use crate::theme::{self, THEME_NAMES};
+use crate::workspace::{self, Workspace};
+use crate::MenuItem;
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+const ENV_PREFIX: &str = "PET_CLI_";
+
+/// The keys of the config file, in the order `config show` prints them, with
+/// their defaults as they would be written in the file.
+pub const DEFAULTS: [(&str, &str); 45] = [
+    ("theme", "dark"),
+    // Colors for categories, like "cats=magenta,dogs=#ffaa00". Other
+    // categories get one by their name.
+    ("category_colors", ""),
+    // What the terminal can show, when guessing from TERM, COLORTERM and the
+    // locale gets it wrong, like "braille=off,truecolor=on". The keys are
+    // unicode_borders, truecolor, braille and images.
+    ("capabilities", ""),
+    ("db_format", "pretty"),
+    ("keep_backups", "10"),
+    // How hard writes of the DB try to survive a power cut: none, flush or
+    // fsync, from fastest to safest.
+    ("write_durability", "flush"),
+    // Whether the DB is written gzip-compressed: auto for when its name ends
+    // in `.gz` or it is already. The level is gzip's, from 1, fastest, to 9,
+    // smallest.
+    ("compress", "auto"),
+    ("compress_level", "1"),
+    // Whether every write appends what it changed to audit.jsonl next to the
+    // DB, as with --audit, and the megabytes of it to keep.
+    ("audit", "false"),
+    ("audit_max_mb", "10"),
+    ("title", "Menu"),
+    // Empty for no footer; unset for "pet-CLI" and the current year.
+    ("footer", ""),
+    ("list_width", "20"),
+    // The columns of the pet list, in order. Only used when the last
+    // session's aren't restored, unless it is set.
+    ("list_columns", "name,status"),
+    // Widths for columns of the pet list, like "category=8,created=10";
+    // the others fit their cells.
+    ("list_column_widths", ""),
+    ("tick_rate", "200"),
+    // Whether the loading spinner turns and status messages too long for
+    // the status bar scroll; false keeps the screen still.
+    ("animations", "true"),
+    // Minutes without a key press before the screen is blanked until the
+    // next one; 0 for never.
+    ("idle_lock", "0"),
+    // With --kiosk: minutes without a key press after which the Pets tab is
+    // shown as it started, 0 for never, and the characters to type to leave
+    // kiosk mode, empty for no way out.
+    ("kiosk_reset", "2"),
+    ("kiosk_unlock", ""),
+    ("lang", "en"),
+    // A JSON file of generated pet names by species; empty for the built-in
+    // ones.
+    ("name_pools", ""),
+    ("unique_names", "false"),
+    // The locale of generated pets' categories and names, built in or from
+    // the `locales` directory next to this file.
+    ("generator_locale", "en"),
+    // The tab to start on. Only used when the last session's isn't restored,
+    // unless it is set.
+    ("tab", "home"),
+    // The tabs of the menu, in order, like ["pets", "stats", "home"]. Those
+    // left out are hidden and their keys do nothing.
+    ("tabs", "home,pets,owners,duplicates,trash,log,stats"),
+    // Whether the pet list starts grouped by category, as after zg.
+    ("group_by_category", "false"),
+    // The years each bar of the Stats tab's age histogram covers: 1y, 2y or
+    // 5y.
+    ("age_buckets", "1y"),
+    // Ages from a birthdate below these are shown in days, then in weeks,
+    // rather than in months, like 12d or 3m; 0d for never.
+    ("age_in_days_below", "8w"),
+    ("age_in_weeks_below", "3m"),
+    // Whether CSV and JSON exports have the fields of pets pet-cli doesn't
+    // know, like ones added to the DB file by hand.
+    ("export_extra_fields", "false"),
+    // How dates like 01/03/2024 typed into the birthdate and medical record
+    // prompts are read: dmy for 1 March, mdy for January 3. Empty accepts
+    // them only where just one reading is a date.
+    ("date_order", ""),
+    // What Ctrl+a fills in for a pet typed by name alone: the category, the
+    // age like 1y or 8m, and a prefix for the name; empty for none.
+    ("add_category", ""),
+    ("add_age", ""),
+    ("add_name_prefix", ""),
+    // Shell commands run after the UI writes the DB, with PET_CLI_EVENT
+    // (add, delete or update), PET_CLI_PET_ID and PET_CLI_DB set; empty for
+    // none. on_any_write runs after every write, after the others.
+    ("on_add", ""),
+    ("on_delete", ""),
+    ("on_update", ""),
+    ("on_any_write", ""),
+    // Seconds after which a hook is killed.
+    ("hook_timeout", "5"),
+    // Where `pet-cli notify-daemon` sends reminders: desktop notifications,
+    // a shell command run for each with PET_CLI_REMINDER,
+    // PET_CLI_REMINDER_KIND, PET_CLI_PET_ID and PET_CLI_DB set, and a file
+    // each is appended to; empty for none. A reminder sent is sent again
+    // after notify_snooze hours.
+    ("notify_desktop", "false"),
+    ("notify_command", ""),
+    ("notify_file", ""),
+    ("notify_snooze", "24"),
+    // Named DBs to switch between with W or --workspace, like
+    // "home=./data/db.json,foster=../foster/db.json"; relative to where
+    // pet-cli is started.
+    ("workspaces", ""),
+];
+
+/// The function keys a palette command can be bound to, like
+/// `F2 = ":set status adopted"`. F12 is left for the diagnostics overlay.
+pub const QUICK_ACTION_KEYS: [&str; 11] = [
+    "F1", "F2", "F3", "F4", "F5", "F6", "F7", "F8", "F9", "F10", "F11",
+];
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("could not read {0}: {1}")]
+    Read(PathBuf, io::Error),
+    #[error("{path}:{line}: {message}")]
+    Syntax {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    #[error("invalid {key} '{value}' from {from}: {message}")]
+    Invalid {
+        key: &'static str,
+        value: String,
+        from: Source,
+        message: String,
+    },
+}
+
+impl ConfigError {
+    /// Whether the config file is to blame, which `--safe-mode` starts
+    /// without.
+    pub fn is_from_file(&self) -> bool {
+        match self {
+            ConfigError::Read(..) | ConfigError::Syntax { .. } => true,
+            ConfigError::Invalid { from, .. } => matches!(from, Source::File(_)),
+        }
+    }
+
+    /// What to change to get rid of the error.
+    pub fn fix(&self) -> String {
+        let safe_mode = "or start with --safe-mode to leave the file out";
+        match self {
+            ConfigError::Read(path, _) => {
+                format!("make {} readable, {}", path.display(), safe_mode)
+            }
+            ConfigError::Syntax { path, line, .. } => {
+                format!("correct line {} of {}, {}", line, path.display(), safe_mode)
+            }
+            ConfigError::Invalid { key, from, .. } => match from {
+                Source::File(path) => {
+                    format!("correct `{}` in {}, {}", key, path.display(), safe_mode)
+                }
+                Source::Env(name) => format!("correct or unset ${}", name),
+                Source::Flag | Source::Default => format!("correct the flag for `{}`", key),
+            },
+        }
+    }
+}
+
+/// Where a setting's value came from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    /// The name of the environment variable.
+    Env(String),
+    Flag,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Env(name) => write!(f, "${}", name),
+            Source::Flag => write!(f, "command line"),
+        }
+    }
+}
+
+/// Raw values from one source, keyed like the config file. The source of a
+/// value is kept per key since the environment layer mixes variables.
+#[derive(Default, Debug)]
+pub struct Layer {
+    values: BTreeMap<String, (String, Source)>,
+}
+
+impl Layer {
+    pub fn set(&mut self, key: &str, value: impl Into<String>, source: Source) {
+        self.values.insert(key.to_owned(), (value.into(), source));
+    }
+
+    /// The layer of a config file's contents. Supports the flat part of TOML
+    /// the settings need: `key = value` lines with strings, integers and
+    /// booleans, and `#` comments.
+    pub fn from_toml(text: &str, path: &Path) -> Result<Self, ConfigError> {
+        let mut layer = Layer::default();
+        for (index, line) in text.lines().enumerate() {
+            let syntax = |message: &str| ConfigError::Syntax {
+                path: path.to_owned(),
+                line: index + 1,
+                message: message.to_owned(),
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(syntax(
+                    "tables aren't supported, settings go at the top level",
+                ));
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| syntax("expected `key = value`"))?;
+            let value = parse_toml_value(value.trim()).map_err(|message| syntax(&message))?;
+            layer.set(key.trim(), value, Source::File(path.to_owned()));
+        }
+        Ok(layer)
+    }
+
+    /// The layer of the `PET_CLI_` variables among `vars`, e.g. `PET_CLI_THEME`
+    /// for `theme`.
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut layer = Layer::default();
+        for (name, value) in vars {
+            if let Some(key) = name.strip_prefix(ENV_PREFIX) {
+                layer.set(&key.to_lowercase(), value, Source::Env(name.clone()));
+            }
+        }
+        layer
+    }
+
+    /// The UI language of the locale, from the first of `LC_ALL`,
+    /// `LC_MESSAGES` and `LANG` that is set. Empty if the UI doesn't speak
+    /// that language.
+    pub fn from_locale(vars: &[(String, String)]) -> Self {
+        let mut layer = Layer::default();
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"].iter().find_map(|name| {
+            vars.iter()
+                .find(|(var, value)| var == name && !value.is_empty())
+        });
+        if let Some((name, value)) = locale {
+            if let Some(lang) = Lang::from_locale(value) {
+                let lang = lang.to_possible_value().expect("no skipped languages");
+                layer.set("lang", lang.get_name(), Source::Env(name.clone()));
+            }
+        }
+        layer
+    }
+
+    /// The mono theme if `NO_COLOR` is set to anything but the empty string.
+    /// It sits below the config file, so a theme chosen there still wins, as
+    /// <https://no-color.org> asks.
+    pub fn from_no_color(vars: &[(String, String)]) -> Self {
+        let mut layer = Layer::default();
+        if vars
+            .iter()
+            .any(|(name, value)| name == "NO_COLOR" && !value.is_empty())
+        {
+            layer.set("theme", "mono", Source::Env("NO_COLOR".to_owned()));
+        }
+        layer
+    }
+}
+
+/// A string, integer or boolean, returned as the string it stands for, or an
+/// array of them, returned as the strings joined with commas.
+fn parse_toml_value(value: &str) -> Result<String, String> {
+    if let Some(rest) = value.strip_prefix('[') {
+        let (items, after) = split_array(rest)?;
+        let after = after.trim();
+        if !after.is_empty() && !after.starts_with('#') {
+            return Err(format!("unexpected '{}' after the array", after));
+        }
+        let items: Vec<String> = items
+            .into_iter()
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(parse_toml_value)
+            .collect::<Result<_, _>>()?;
+        // The settings read arrays back as lists split on commas.
+        if let Some(item) = items.iter().find(|item| item.contains(',')) {
+            return Err(format!("'{}' has a comma, which array items can't", item));
+        }
+        return Ok(items.join(","));
+    }
+    let value = match value.find(" #") {
+        Some(comment) if !value.starts_with('"') => value[..comment].trim_end(),
+        _ => value,
+    };
+    if let Some(quoted) = value.strip_prefix('"') {
+        let mut text = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    let rest = chars.as_str().trim();
+                    if !rest.is_empty() && !rest.starts_with('#') {
+                        return Err(format!("unexpected '{}' after the string", rest));
+                    }
+                    return Ok(text);
+                }
+                '\\' => match chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('"') => text.push('"'),
+                    Some('\\') => text.push('\\'),
+                    _ => return Err("unsupported escape in string".to_owned()),
+                },
+                c => text.push(c),
+            }
+        }
+        return Err("unterminated string".to_owned());
+    }
+    if value == "true" || value == "false" || value.parse::<i64>().is_ok() {
+        return Ok(value.to_owned());
+    }
+    Err(format!(
+        "invalid value '{}', expected a quoted string, number or boolean",
+        value
+    ))
+}
+
+/// The items of the array that `rest` is the text of after its `[`, and what
+/// follows its `]`. Commas and brackets in strings are part of them, and a
+/// `#` outside one starts a comment.
+fn split_array(rest: &str) -> Result<(Vec<&str>, &str), String> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut in_string = false;
+    let mut chars = rest.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                items.push(&rest[start..index]);
+                start = index + 1;
+            }
+            ']' if !in_string => {
+                items.push(&rest[start..index]);
+                return Ok((items, &rest[index + 1..]));
+            }
+            '#' if !in_string => break,
+            _ => {}
+        }
+    }
+    Err("unterminated array".to_owned())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+/// The effective settings after all layers are applied.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub theme: Setting<String>,
+    /// As written; `theme::parse_category_colors` accepts it.
+    pub category_colors: Setting<String>,
+    /// As written; `capabilities::parse_overrides` accepts it.
+    pub capabilities: Setting<String>,
+    pub db_format: Setting<DbFormat>,
+    pub keep_backups: Setting<usize>,
+    pub write_durability: Setting<Durability>,
+    pub compress: Setting<Compress>,
+    pub compress_level: Setting<u32>,
+    pub audit: Setting<bool>,
+    pub audit_max_mb: Setting<u64>,
+    pub title: Setting<String>,
+    /// `None` when the footer is turned off.
+    pub footer: Setting<Option<String>>,
+    pub list_width: Setting<u16>,
+    pub list_columns: Setting<Vec<PetField>>,
+    pub list_column_widths: Setting<Vec<(PetField, usize)>>,
+    pub tick_rate: Setting<Duration>,
+    pub animations: Setting<bool>,
+    /// `None` when the screen is never blanked.
+    pub idle_lock: Setting<Option<Duration>>,
+    /// `None` when a kiosk is never reset.
+    pub kiosk_reset: Setting<Option<Duration>>,
+    pub kiosk_unlock: Setting<String>,
+    pub lang: Setting<Lang>,
+    pub name_pools: Setting<Option<PathBuf>>,
+    pub unique_names: Setting<bool>,
+    pub generator_locale: Setting<String>,
+    pub tab: Setting<MenuItem>,
+    pub tabs: Setting<Vec<MenuItem>>,
+    pub group_by_category: Setting<bool>,
+    pub age_buckets: Setting<AgeBucketWidth>,
+    /// In days.
+    pub age_in_days_below: Setting<usize>,
+    /// In days.
+    pub age_in_weeks_below: Setting<usize>,
+    pub export_extra_fields: Setting<bool>,
+    pub date_order: Setting<Option<DateOrder>>,
+    pub add_category: Setting<Option<String>>,
+    /// In months.
+    pub add_age: Setting<Option<usize>>,
+    pub add_name_prefix: Setting<String>,
+    pub on_add: Setting<Option<String>>,
+    pub on_delete: Setting<Option<String>>,
+    pub on_update: Setting<Option<String>>,
+    pub on_any_write: Setting<Option<String>>,
+    pub hook_timeout: Setting<Duration>,
+    pub notify_desktop: Setting<bool>,
+    pub notify_command: Setting<Option<String>>,
+    pub notify_file: Setting<Option<PathBuf>>,
+    pub notify_snooze: Setting<Duration>,
+    pub workspaces: Setting<Vec<Workspace>>,
+    /// By function key, the ones bound in `QUICK_ACTION_KEYS`.
+    pub quick_actions: Vec<QuickAction>,
+    /// Keys no setting has, with where they came from.
+    pub unknown_keys: Vec<(String, Source)>,
+}
+
+/// A palette command bound to a function key.
+#[derive(Clone, Debug)]
+pub struct QuickAction {
+    /// The number of the function key.
+    pub key: u8,
+    /// The command as written, for the Home tab and `config show`.
+    pub line: String,
+    pub command: palette::Command,
+    pub source: Source,
+}
+
+impl QuickAction {
+    /// `F2`, as in the config file.
+    pub fn key_name(&self) -> &'static str {
+        QUICK_ACTION_KEYS[usize::from(self.key) - 1]
+    }
+}
+
+/// Stacks `layers`, later ones winning, on top of the defaults and parses the
+/// result. `default_footer` stands in for a footer nobody set.
+pub fn resolve(layers: &[Layer], default_footer: String) -> Result<Config, ConfigError> {
+    let mut merged: BTreeMap<&str, (String, Source)> = DEFAULTS
+        .iter()
+        .map(|(key, value)| (*key, (value.to_string(), Source::Default)))
+        .collect();
+    let mut quick_actions: BTreeMap<usize, (String, Source)> = BTreeMap::new();
+    let mut unknown_keys = Vec::new();
+    for layer in layers {
+        for (key, (value, source)) in &layer.values {
+            let quick_action = QUICK_ACTION_KEYS
+                .iter()
+                .position(|known| known.eq_ignore_ascii_case(key));
+            match DEFAULTS.iter().find(|(known, _)| known == key) {
+                Some((known, _)) => {
+                    merged.insert(known, (value.clone(), source.clone()));
+                }
+                None => match quick_action {
+                    Some(index) => {
+                        quick_actions.insert(index, (value.clone(), source.clone()));
+                    }
+                    None => unknown_keys.push((key.clone(), source.clone())),
+                },
+            }
+        }
+    }
+    // An empty command unbinds the key, say from the environment.
+    let quick_actions = quick_actions
+        .into_iter()
+        .filter(|(_, (line, _))| !line.trim().is_empty())
+        .map(|(index, (line, source))| {
+            let key = QUICK_ACTION_KEYS[index];
+            let Setting {
+                value: command,
+                source,
+            } = parse(key, (line.clone(), source), palette::parse)?;
+            Ok(QuickAction {
+                key: index as u8 + 1,
+                line,
+                command,
+                source,
+            })
+        })
+        .collect::<Result<_, ConfigError>>()?;
+    let setting = |key: &'static str| merged[key].clone();
+    let footer = match setting("footer") {
+        (_, Source::Default) => Setting {
+            value: Some(default_footer),
+            source: Source::Default,
+        },
+        (value, source) => Setting {
+            value: Some(value).filter(|value| !value.is_empty()),
+            source,
+        },
+    };
+    let title = setting("title");
+    Ok(Config {
+        theme: parse("theme", setting("theme"), |value| {
+            if THEME_NAMES.contains(&value) {
+                Ok(value.to_owned())
+            } else {
+                Err(format!("valid themes are: {}", THEME_NAMES.join(", ")))
+            }
+        })?,
+        category_colors: parse("category_colors", setting("category_colors"), |value| {
+            theme::parse_category_colors(value)
+                .map(|_| value.to_owned())
+                .map_err(|err| err.to_string())
+        })?,
+        capabilities: parse("capabilities", setting("capabilities"), |value| {
+            capabilities::parse_overrides(value).map(|_| value.to_owned())
+        })?,
+        db_format: parse("db_format", setting("db_format"), |value| {
+            DbFormat::from_str(value, true)
+        })?,
+        keep_backups: parse("keep_backups", setting("keep_backups"), |value| {
+            value.parse().map_err(|_| "expected a number".to_owned())
+        })?,
+        write_durability: parse("write_durability", setting("write_durability"), |value| {
+            Durability::from_str(value, true)
+        })?,
+        compress: parse("compress", setting("compress"), |value| {
+            if value.eq_ignore_ascii_case("zstd") {
+                return Err("zstd isn't supported by this build, use gzip".to_owned());
+            }
+            Compress::from_str(value, true)
+        })?,
+        compress_level: parse(
+            "compress_level",
+            setting("compress_level"),
+            |value| match value.parse() {
+                Ok(level) if compression::LEVELS.contains(&level) => Ok(level),
+                _ => Err("expected a level from 1 to 9".to_owned()),
+            },
+        )?,
+        audit: parse("audit", setting("audit"), |value| {
+            value
+                .parse()
+                .map_err(|_| "expected true or false".to_owned())
+        })?,
+        audit_max_mb: parse(
+            "audit_max_mb",
+            setting("audit_max_mb"),
+            |value| match value.parse() {
+                Ok(megabytes) if megabytes > 0 => Ok(megabytes),
+                _ => Err("expected a positive number".to_owned()),
+            },
+        )?,
+        title: Setting {
+            value: title.0,
+            source: title.1,
+        },
+        footer,
+        list_width: parse("list_width", setting("list_width"), |value| {
+            match value.parse() {
+                Ok(percent) if (10..=90).contains(&percent) => Ok(percent),
+                _ => Err("expected a percentage from 10 to 90".to_owned()),
+            }
+        })?,
+        list_columns: parse(
+            "list_columns",
+            setting("list_columns"),
+            fields::parse_list_columns,
+        )?,
+        list_column_widths: parse(
+            "list_column_widths",
+            setting("list_column_widths"),
+            fields::parse_column_widths,
+        )?,
+        tick_rate: parse("tick_rate", setting("tick_rate"), |value| {
+            match value.parse() {
+                Ok(millis) if millis >= 10 => Ok(Duration::from_millis(millis)),
+                _ => Err("expected a number of milliseconds, at least 10".to_owned()),
+            }
+        })?,
+        animations: parse("animations", setting("animations"), |value| {
+            value
+                .parse()
+                .map_err(|_| "expected true or false".to_owned())
+        })?,
+        idle_lock: parse("idle_lock", setting("idle_lock"), parse_minutes)?,
+        kiosk_reset: parse("kiosk_reset", setting("kiosk_reset"), parse_minutes)?,
+        kiosk_unlock: parse("kiosk_unlock", setting("kiosk_unlock"), |value| {
+            Ok(value.to_owned())
+        })?,
+        lang: parse("lang", setting("lang"), |value| Lang::from_str(value, true))?,
+        name_pools: parse("name_pools", setting("name_pools"), |value| {
+            Ok(Some(PathBuf::from(value)).filter(|_| !value.is_empty()))
+        })?,
+        unique_names: parse("unique_names", setting("unique_names"), |value| {
+            value
+                .parse()
+                .map_err(|_| "expected true or false".to_owned())
+        })?,
+        generator_locale: parse("generator_locale", setting("generator_locale"), |value| {
+            if locales::valid_code(value) {
+                Ok(value.to_owned())
+            } else {
+                Err("expected a locale like de, of letters, digits, - and _".to_owned())
+            }
+        })?,
+        tab: parse("tab", setting("tab"), |value| {
+            MenuItem::from_str(value, true).map_err(|_| {
+                let names: Vec<_> = MenuItem::value_variants()
+                    .iter()
+                    .filter_map(|tab| tab.to_possible_value())
+                    .map(|tab| tab.get_name().to_owned())
+                    .collect();
+                format!("valid tabs are: {}", names.join(", "))
+            })
+        })?,
+        tabs: parse("tabs", setting("tabs"), menu::parse_tabs)?,
+        group_by_category: parse("group_by_category", setting("group_by_category"), |value| {
+            value
+                .parse()
+                .map_err(|_| "expected true or false".to_owned())
+        })?,
+        age_buckets: parse("age_buckets", setting("age_buckets"), AgeBucketWidth::parse)?,
+        age_in_days_below: parse(
+            "age_in_days_below",
+            setting("age_in_days_below"),
+            age::parse_threshold,
+        )?,
+        age_in_weeks_below: parse(
+            "age_in_weeks_below",
+            setting("age_in_weeks_below"),
+            age::parse_threshold,
+        )?,
+        export_extra_fields: parse(
+            "export_extra_fields",
+            setting("export_extra_fields"),
+            |value| {
+                value
+                    .parse()
+                    .map_err(|_| "expected true or false".to_owned())
+            },
+        )?,
+        date_order: parse("date_order", setting("date_order"), DateOrder::parse)?,
+        add_category: parse("add_category", setting("add_category"), |value| {
+            Ok(Some(value.trim().to_owned()).filter(|category| !category.is_empty()))
+        })?,
+        add_age: parse("add_age", setting("add_age"), |value| {
+            if value.trim().is_empty() {
+                Ok(None)
+            } else {
+                age::parse(value).map(Some)
+            }
+        })?,
+        add_name_prefix: parse("add_name_prefix", setting("add_name_prefix"), |value| {
+            Ok(value.to_owned())
+        })?,
+        on_add: parse("on_add", setting("on_add"), hook_command)?,
+        on_delete: parse("on_delete", setting("on_delete"), hook_command)?,
+        on_update: parse("on_update", setting("on_update"), hook_command)?,
+        on_any_write: parse("on_any_write", setting("on_any_write"), hook_command)?,
+        hook_timeout: parse("hook_timeout", setting("hook_timeout"), |value| match value
+            .parse::<f64>()
+        {
+            Ok(seconds) if seconds > 0.0 && seconds.is_finite() => {
+                Ok(Duration::from_secs_f64(seconds))
+            }
+            _ => Err("expected a positive number of seconds".to_owned()),
+        })?,
+        notify_desktop: parse("notify_desktop", setting("notify_desktop"), |value| {
+            value
+                .parse()
+                .map_err(|_| "expected true or false".to_owned())
+        })?,
+        notify_command: parse("notify_command", setting("notify_command"), hook_command)?,
+        notify_file: parse("notify_file", setting("notify_file"), |value| {
+            Ok(Some(PathBuf::from(value)).filter(|_| !value.is_empty()))
+        })?,
+        notify_snooze: parse(
+            "notify_snooze",
+            setting("notify_snooze"),
+            |value| match value.parse::<f64>() {
+                Ok(hours) if hours > 0.0 && hours.is_finite() => {
+                    Ok(Duration::from_secs_f64(hours * 3600.0))
+                }
+                _ => Err("expected a positive number of hours".to_owned()),
+            },
+        )?,
+        workspaces: parse("workspaces", setting("workspaces"), workspace::parse)?,
+        quick_actions,
+        unknown_keys,
+    })
+}
+
+fn hook_command(value: &str) -> Result<Option<String>, String> {
+    Ok(Some(value.trim().to_owned()).filter(|command| !command.is_empty()))
+}
+
+/// A number of minutes, or `None` for 0.
+fn parse_minutes(value: &str) -> Result<Option<Duration>, String> {
+    match value.parse::<f64>() {
+        Ok(0.0) => Ok(None),
+        Ok(minutes) if minutes > 0.0 && minutes.is_finite() => {
+            Ok(Some(Duration::from_secs_f64(minutes * 60.0)))
+        }
+        _ => Err("expected a number of minutes, 0 for never".to_owned()),
+    }
+}
+
+fn parse<T>(
+    key: &'static str,
+    (value, source): (String, Source),
+    parse_value: impl FnOnce(&str) -> Result<T, String>,
+) -> Result<Setting<T>, ConfigError> {
+    match parse_value(&value) {
+        Ok(parsed) => Ok(Setting {
+            value: parsed,
+            source,
+        }),
+        Err(message) => Err(ConfigError::Invalid {
+            key,
+            value,
+            from: source,
+            message,
+        }),
+    }
+}
+
+/// The `locales` directory next to the config file, where generator
+/// locales can be added.
+pub fn locales_dir(config_path: &Path) -> Option<PathBuf> {
+    Some(config_path.parent()?.join("locales"))
+}
+
+/// `$XDG_CONFIG_HOME/pet-cli/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("pet-cli").join("config.toml"))
+}
+
+/// Creates the config file at `path` with string `settings`, for the
+/// first-run wizard. Fails if there already is one.
+pub fn create_file(path: &Path, settings: &[(&str, &str)]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut text =
+        "# Written by the pet-cli setup; `pet-cli config show` lists every setting.\n".to_owned();
+    for (key, value) in settings {
+        text.push_str(&format!("{} = {}\n", key, quote(value)));
+    }
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    io::Write::write_all(&mut file, text.as_bytes())
+}
+
+/// Whether `layers` reads the config file. `--demo` and `--safe-mode` leave
+/// it out, whatever is in it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ConfigFiles {
+    Read,
+    Skip,
+}
+
+/// The layers to `resolve` below the flags', lowest precedence first: the
+/// locale and NO_COLOR, the config file and the `PET_CLI_` variables in
+/// `vars`. The file is `explicit`, which must exist, or else the default one
+/// if there is one.
+pub fn layers(
+    explicit: Option<&Path>,
+    files: ConfigFiles,
+    vars: Vec<(String, String)>,
+) -> Result<Vec<Layer>, ConfigError> {
+    let file = match (files, explicit, default_path()) {
+        (ConfigFiles::Skip, _, _) => Layer::default(),
+        (ConfigFiles::Read, Some(path), _) => load_file(path, true)?,
+        (ConfigFiles::Read, None, Some(path)) => load_file(&path, false)?,
+        (ConfigFiles::Read, None, None) => Layer::default(),
+    };
+    Ok(vec![
+        Layer::from_locale(&vars),
+        Layer::from_no_color(&vars),
+        file,
+        Layer::from_env(vars),
+    ])
+}
+
+/// The file's layer. A missing file is an empty layer unless it was asked
+/// for with `--config`.
+pub fn load_file(path: &Path, required: bool) -> Result<Layer, ConfigError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Layer::from_toml(&text, path),
+        Err(err) if err.kind() == io::ErrorKind::NotFound && !required => Ok(Layer::default()),
+        Err(err) => Err(ConfigError::Read(path.to_owned(), err)),
+    }
+}
+
+impl Config {
+    /// One `key = value  # source` line per setting, as `config show` prints.
+    pub fn show(&self) -> String {
+        let footer = self.footer.value.clone().unwrap_or_default();
+        let lines = [
+            ("theme", quote(&self.theme.value), &self.theme.source),
+            (
+                "category_colors",
+                quote(&self.category_colors.value),
+                &self.category_colors.source,
+            ),
+            (
+                "capabilities",
+                quote(&self.capabilities.value),
+                &self.capabilities.source,
+            ),
+            (
+                "db_format",
+                quote(
+                    self.db_format
+                        .value
+                        .to_possible_value()
+                        .expect("no skipped formats")
+                        .get_name(),
+                ),
+                &self.db_format.source,
+            ),
+            (
+                "keep_backups",
+                self.keep_backups.value.to_string(),
+                &self.keep_backups.source,
+            ),
+            (
+                "write_durability",
+                quote(self.write_durability.value.name()),
+                &self.write_durability.source,
+            ),
+            (
+                "compress",
+                quote(self.compress.value.name()),
+                &self.compress.source,
+            ),
+            (
+                "compress_level",
+                self.compress_level.value.to_string(),
+                &self.compress_level.source,
+            ),
+            ("audit", self.audit.value.to_string(), &self.audit.source),
+            (
+                "audit_max_mb",
+                self.audit_max_mb.value.to_string(),
+                &self.audit_max_mb.source,
+            ),
+            ("title", quote(&self.title.value), &self.title.source),
+            ("footer", quote(&footer), &self.footer.source),
+            (
+                "list_width",
+                self.list_width.value.to_string(),
+                &self.list_width.source,
+            ),
+            (
+                "list_columns",
+                quote(&fields::format_list_columns(&self.list_columns.value)),
+                &self.list_columns.source,
+            ),
+            (
+                "list_column_widths",
+                quote(&fields::format_column_widths(
+                    &self.list_column_widths.value,
+                )),
+                &self.list_column_widths.source,
+            ),
+            (
+                "tick_rate",
+                self.tick_rate.value.as_millis().to_string(),
+                &self.tick_rate.source,
+            ),
+            (
+                "animations",
+                self.animations.value.to_string(),
+                &self.animations.source,
+            ),
+            (
+                "idle_lock",
+                self.idle_lock
+                    .value
+                    .map_or(0.0, |after| after.as_secs_f64() / 60.0)
+                    .to_string(),
+                &self.idle_lock.source,
+            ),
+            (
+                "kiosk_reset",
+                self.kiosk_reset
+                    .value
+                    .map_or(0.0, |after| after.as_secs_f64() / 60.0)
+                    .to_string(),
+                &self.kiosk_reset.source,
+            ),
+            (
+                "kiosk_unlock",
+                quote(&self.kiosk_unlock.value),
+                &self.kiosk_unlock.source,
+            ),
+            (
+                "lang",
+                quote(
+                    self.lang
+                        .value
+                        .to_possible_value()
+                        .expect("no skipped languages")
+                        .get_name(),
+                ),
+                &self.lang.source,
+            ),
+            (
+                "name_pools",
+                quote(
+                    &self
+                        .name_pools
+                        .value
+                        .as_deref()
+                        .map(|path| path.display().to_string())
+                        .unwrap_or_default(),
+                ),
+                &self.name_pools.source,
+            ),
+            (
+                "unique_names",
+                self.unique_names.value.to_string(),
+                &self.unique_names.source,
+            ),
+            (
+                "generator_locale",
+                quote(&self.generator_locale.value),
+                &self.generator_locale.source,
+            ),
+            (
+                "tab",
+                quote(
+                    self.tab
+                        .value
+                        .to_possible_value()
+                        .expect("no skipped tabs")
+                        .get_name(),
+                ),
+                &self.tab.source,
+            ),
+            (
+                "tabs",
+                quote(&menu::format_tabs(&self.tabs.value)),
+                &self.tabs.source,
+            ),
+            (
+                "group_by_category",
+                self.group_by_category.value.to_string(),
+                &self.group_by_category.source,
+            ),
+            (
+                "age_buckets",
+                quote(&self.age_buckets.value.to_string()),
+                &self.age_buckets.source,
+            ),
+            (
+                "age_in_days_below",
+                quote(&age::format_threshold(self.age_in_days_below.value)),
+                &self.age_in_days_below.source,
+            ),
+            (
+                "age_in_weeks_below",
+                quote(&age::format_threshold(self.age_in_weeks_below.value)),
+                &self.age_in_weeks_below.source,
+            ),
+            (
+                "export_extra_fields",
+                self.export_extra_fields.value.to_string(),
+                &self.export_extra_fields.source,
+            ),
+            (
+                "date_order",
+                quote(
+                    &self
+                        .date_order
+                        .value
+                        .map(|order| order.to_string())
+                        .unwrap_or_default(),
+                ),
+                &self.date_order.source,
+            ),
+            (
+                "add_category",
+                quote(self.add_category.value.as_deref().unwrap_or_default()),
+                &self.add_category.source,
+            ),
+            (
+                "add_age",
+                quote(
+                    &self
+                        .add_age
+                        .value
+                        .map(age::format_input)
+                        .unwrap_or_default(),
+                ),
+                &self.add_age.source,
+            ),
+            (
+                "add_name_prefix",
+                quote(&self.add_name_prefix.value),
+                &self.add_name_prefix.source,
+            ),
+            hook_line("on_add", &self.on_add),
+            hook_line("on_delete", &self.on_delete),
+            hook_line("on_update", &self.on_update),
+            hook_line("on_any_write", &self.on_any_write),
+            (
+                "hook_timeout",
+                self.hook_timeout.value.as_secs_f64().to_string(),
+                &self.hook_timeout.source,
+            ),
+            (
+                "notify_desktop",
+                self.notify_desktop.value.to_string(),
+                &self.notify_desktop.source,
+            ),
+            hook_line("notify_command", &self.notify_command),
+            (
+                "notify_file",
+                quote(
+                    &self
+                        .notify_file
+                        .value
+                        .as_deref()
+                        .map(|path| path.display().to_string())
+                        .unwrap_or_default(),
+                ),
+                &self.notify_file.source,
+            ),
+            (
+                "notify_snooze",
+                (self.notify_snooze.value.as_secs_f64() / 3600.0).to_string(),
+                &self.notify_snooze.source,
+            ),
+            (
+                "workspaces",
+                quote(&workspace::format(&self.workspaces.value)),
+                &self.workspaces.source,
+            ),
+        ];
+        let quick_actions = self
+            .quick_actions
+            .iter()
+            .map(|action| (action.key_name(), quote(&action.line), &action.source));
+        IntoIterator::into_iter(lines)
+            .chain(quick_actions)
+            .map(|(key, value, source)| format!("{} = {}  # {}\n", key, value, source))
+            .collect()
+    }
+}
+
+fn hook_line<'a>(
+    key: &'static str,
+    setting: &'a Setting<Option<String>>,
+) -> (&'static str, String, &'a Source) {
+    let command = setting.value.as_deref().unwrap_or_default();
+    (key, quote(command), &setting.source)
+}
+
+fn quote(text: &str) -> String {
+    format!("{:?}", text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(key: &str, value: &str, source: Source) -> Layer {
+        let mut layer = Layer::default();
+        layer.set(key, value, source);
+        layer
+    }
+
+    #[test]
+    fn array_items_are_split_outside_strings() {
+        assert_eq!(
+            parse_toml_value(r#"["pets", "home" , "x]"]"#).unwrap(),
+            "pets,home,x]"
+        );
+        assert_eq!(
+            parse_toml_value(r#"["a\"b", "]"] # done"#).unwrap(),
+            "a\"b,]"
+        );
+        assert_eq!(
+            parse_toml_value(r#"["a,b"]"#).unwrap_err(),
+            "'a,b' has a comma, which array items can't"
+        );
+        assert_eq!(
+            parse_toml_value(r#"["a", "b"#).unwrap_err(),
+            "unterminated array"
+        );
+    }
+
+    #[test]
+    fn a_bracket_in_a_comment_does_not_close_the_array() {
+        assert_eq!(
+            parse_toml_value(r#"["pets", "home"] # not ["stats"]"#).unwrap(),
+            "pets,home"
+        );
+        assert_eq!(
+            parse_toml_value(r#"["pets", "home" # not ["stats"]"#).unwrap_err(),
+            "unterminated array"
+        );
+        assert_eq!(
+            parse_toml_value(r#"["pets"] "home""#).unwrap_err(),
+            "unexpected '\"home\"' after the array"
+        );
+    }
+
+    #[test]
+    fn the_tab_defaults_to_home() {
+        let config = resolve(&[], String::new()).unwrap();
+        assert_eq!(config.tab.value, MenuItem::Home);
+        assert_eq!(config.tab.source, Source::Default);
+    }
+
+    #[test]
+    fn the_tab_is_read_ignoring_case() {
+        let file = Source::File(PathBuf::from("config.toml"));
+        let config = resolve(&[layer("tab", "Stats", file.clone())], String::new()).unwrap();
+        assert_eq!(
+            config.tab,
+            Setting {
+                value: MenuItem::Stats,
+                source: file
+            }
+        );
+    }
+
+    #[test]
+    fn the_flag_beats_the_file_and_environment() {
+        let layers = [
+            layer("tab", "pets", Source::File(PathBuf::from("config.toml"))),
+            layer("tab", "trash", Source::Env("PET_CLI_TAB".to_owned())),
+            layer("tab", "stats", Source::Flag),
+        ];
+        let config = resolve(&layers, String::new()).unwrap();
+        assert_eq!(config.tab.value, MenuItem::Stats);
+        assert_eq!(config.tab.source, Source::Flag);
+    }
+
+    #[test]
+    fn the_generator_locale_must_be_a_code() {
+        let config = resolve(
+            &[layer("generator_locale", "de", Source::Flag)],
+            String::new(),
+        );
+        assert_eq!(config.unwrap().generator_locale.value, "de");
+        let err = resolve(
+            &[layer("generator_locale", "../de", Source::Flag)],
+            String::new(),
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("expected a locale like de, of letters, digits, - and _"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn age_buckets_default_to_a_year() {
+        let config = resolve(&[], String::new()).unwrap();
+        assert_eq!(config.age_buckets.value, AgeBucketWidth::One);
+        let env = Source::Env("PET_CLI_AGE_BUCKETS".to_owned());
+        let config = resolve(&[layer("age_buckets", "5y", env.clone())], String::new());
+        assert_eq!(config.unwrap().age_buckets.value, AgeBucketWidth::Five);
+        let err = resolve(&[layer("age_buckets", "3y", env)], String::new()).unwrap_err();
+        assert!(err.to_string().contains("expected 1y, 2y or 5y"), "{}", err);
+    }
+
+    #[test]
+    fn category_colors_are_checked_when_read() {
+        let file = Source::File(PathBuf::from("config.toml"));
+        let config = resolve(
+            &[layer("category_colors", "cats=magenta", file.clone())],
+            String::new(),
+        );
+        assert_eq!(config.unwrap().category_colors.value, "cats=magenta");
+        let err = resolve(
+            &[layer("category_colors", "cats=mauve", file)],
+            String::new(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown color 'mauve'"), "{}", err);
+    }
+
+    #[test]
+    fn an_unknown_tab_lists_the_valid_ones() {
+        let env = Source::Env("PET_CLI_TAB".to_owned());
+        let err = resolve(&[layer("tab", "kennel", env)], String::new()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid tab 'kennel' from $PET_CLI_TAB: valid tabs are: \
+             home, pets, owners, duplicates, trash, log, stats"
+        );
+        assert_eq!(err.fix(), "correct or unset $PET_CLI_TAB");
+    }
+
+    #[test]
+    fn function_keys_take_palette_commands() {
+        let file = Source::File(PathBuf::from("config.toml"));
+        let env = Source::Env("PET_CLI_F3".to_owned());
+        let layers = [
+            layer("F2", ":set status adopted", file.clone()),
+            layer("F3", "filter cats", file.clone()),
+            layer("f3", "", env),
+            layer("F11", "add 3", file.clone()),
+        ];
+        let config = resolve(&layers, String::new()).unwrap();
+        let bound: Vec<(&str, &str)> = config
+            .quick_actions
+            .iter()
+            .map(|quick_action| (quick_action.key_name(), quick_action.line.as_str()))
+            .collect();
+        // The empty value from the environment unbinds F3.
+        assert_eq!(bound, [("F2", ":set status adopted"), ("F11", "add 3")]);
+        assert_eq!(config.quick_actions[1].command.count, Some(3));
+        assert_eq!(config.quick_actions[1].source, file);
+        assert!(config.unknown_keys.is_empty());
+        let config = resolve(&[layer("F12", "add", file)], String::new()).unwrap();
+        assert!(config.quick_actions.is_empty());
+        assert_eq!(config.unknown_keys[0].0, "F12");
+    }
+
+    #[test]
+    fn a_function_key_bound_to_a_bad_command_is_an_error_naming_the_key() {
+        let env = Source::Env("PET_CLI_F2".to_owned());
+        let err = resolve(&[layer("F2", ":frobnicate", env)], String::new()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("F2"), "{}", message);
+        assert!(message.contains("$PET_CLI_F2"), "{}", message);
+        assert!(message.contains("frobnicate"), "{}", message);
+        assert_eq!(err.fix(), "correct or unset $PET_CLI_F2");
+    }
+
+    #[test]
+    fn young_age_thresholds_are_read_as_ages() {
+        let config = resolve(&[], String::new()).unwrap();
+        assert_eq!(config.age_in_days_below.value, 56);
+        assert_eq!(config.age_in_weeks_below.value, 91);
+        let file = Source::File(PathBuf::from("config.toml"));
+        let layers = [
+            layer("age_in_days_below", "3w", file.clone()),
+            layer("age_in_weeks_below", "10d", file.clone()),
+        ];
+        let config = resolve(&layers, String::new()).unwrap();
+        assert_eq!(config.age_in_days_below.value, 21);
+        assert_eq!(config.age_in_weeks_below.value, 10);
+        let err = resolve(&[layer("age_in_days_below", "soon", file)], String::new())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("age_in_days_below"), "{}", err);
+    }
+
+    #[test]
+    fn quick_add_defaults_are_read_from_the_add_keys() {
+        let config = resolve(&[], String::new()).unwrap();
+        assert_eq!(config.add_category.value, None);
+        assert_eq!(config.add_age.value, None);
+        assert_eq!(config.add_name_prefix.value, "");
+        let file = Source::File(PathBuf::from("config.toml"));
+        let layers = [
+            layer("add_category", " cats ", file.clone()),
+            layer("add_age", "1y6m", file.clone()),
+            layer("add_name_prefix", "Foster-", file.clone()),
+        ];
+        let config = resolve(&layers, String::new()).unwrap();
+        assert_eq!(config.add_category.value.as_deref(), Some("cats"));
+        assert_eq!(config.add_age.value, Some(18));
+        assert_eq!(config.add_name_prefix.value, "Foster-");
+        let err = resolve(&[layer("add_age", "12", file)], String::new()).unwrap_err();
+        assert!(err.to_string().contains("add_age"), "{}", err);
+    }
+}
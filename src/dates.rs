@@ -0,0 +1,629 @@
+use crate::age;
+use chrono::prelude::*;
+use chrono::Duration;
+use std::fmt;
+use thiserror::Error;
+
+/// `dt` in the local timezone as `YYYY-MM-DD HH:MM`.
+pub fn format_local(dt: DateTime<Utc>) -> String {
+    dt.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+/// How long before `now` `dt` was, in the largest whole unit ("3 days ago").
+/// Timestamps in the future are treated as "just now".
+pub fn format_relative(dt: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let elapsed = now.signed_duration_since(dt);
+    let (count, unit) = if elapsed.num_minutes() < 1 {
+        return "just now".to_owned();
+    } else if elapsed.num_hours() < 1 {
+        (elapsed.num_minutes(), "minute")
+    } else if elapsed.num_days() < 1 {
+        (elapsed.num_hours(), "hour")
+    } else if elapsed.num_days() < 30 {
+        (elapsed.num_days(), "day")
+    } else if elapsed.num_days() < 365 {
+        (elapsed.num_days() / 30, "month")
+    } else {
+        (elapsed.num_days() / 365, "year")
+    };
+    if count == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", count, unit)
+    }
+}
+
+/// When `format_relative(dt, now)` will next read differently.
+pub fn next_change(dt: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+    let elapsed = now.signed_duration_since(dt);
+    let after = if elapsed.num_hours() < 1 {
+        Duration::minutes(elapsed.num_minutes().max(0) + 1)
+    } else if elapsed.num_days() < 1 {
+        Duration::hours(elapsed.num_hours() + 1)
+    } else if elapsed.num_days() < 30 {
+        Duration::days(elapsed.num_days() + 1)
+    } else if elapsed.num_days() < 365 {
+        Duration::days(((elapsed.num_days() / 30 + 1) * 30).min(365))
+    } else {
+        Duration::days((elapsed.num_days() / 365 + 1) * 365)
+    };
+    dt + after
+}
+
+/// Whole months between `birthdate` and `today`, counting a month only once
+/// its day has been reached. Someone born on Feb 29 turns a year older on
+/// Mar 1 in non-leap years.
+pub fn months_between(birthdate: NaiveDate, today: NaiveDate) -> i32 {
+    let months =
+        (today.year() - birthdate.year()) * 12 + today.month() as i32 - birthdate.month() as i32;
+    if today.day() < birthdate.day() {
+        months - 1
+    } else {
+        months
+    }
+}
+
+/// An age computed from a birthdate, written like a stored age, or in days
+/// or weeks while the animal is young enough; see `age::YoungAges`.
+pub fn format_age(birthdate: NaiveDate, today: NaiveDate) -> String {
+    let days = today.signed_duration_since(birthdate).num_days().max(0);
+    if let Some(young) = age::format_young(days as usize, age::young_ages()) {
+        return young;
+    }
+    let months = months_between(birthdate, today).max(0);
+    age::format(months as usize)
+}
+
+/// The birthdate of an animal `days` old on `today`, for an age typed in
+/// days or weeks.
+pub fn born_days_ago(days: usize, today: NaiveDate) -> Result<NaiveDate, String> {
+    today
+        .checked_sub_days(chrono::Days::new(days as u64))
+        .ok_or_else(|| format!("{} days is too old", days))
+}
+
+/// How a date with slashes, like `01/03/2024`, is read: from the
+/// `date_order` config key.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DateOrder {
+    /// Day first, so `01/03/2024` is 1 March.
+    Dmy,
+    /// Month first, so `01/03/2024` is January 3.
+    Mdy,
+}
+
+impl DateOrder {
+    /// Parses `dmy` or `mdy`, or empty for neither.
+    pub fn parse(value: &str) -> Result<Option<DateOrder>, String> {
+        match value.trim().to_lowercase().as_str() {
+            "" => Ok(None),
+            "dmy" => Ok(Some(DateOrder::Dmy)),
+            "mdy" => Ok(Some(DateOrder::Mdy)),
+            _ => Err("expected dmy, mdy or nothing".to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for DateOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateOrder::Dmy => write!(f, "dmy"),
+            DateOrder::Mdy => write!(f, "mdy"),
+        }
+    }
+}
+
+/// Why a typed date was refused, worded to be shown under the field.
+#[derive(Error, Debug, PartialEq)]
+pub enum DateParseError {
+    #[error("type a date")]
+    Empty,
+    #[error("'{0}' is not a date; try 2024-03-01, 01/03/2024, today, yesterday, -3d or -2w")]
+    Unrecognized(String),
+    #[error(
+        "'{input}' could be {dmy} or {mdy}; type it as YYYY-MM-DD or set date_order to dmy or mdy"
+    )]
+    Ambiguous {
+        input: String,
+        dmy: NaiveDate,
+        mdy: NaiveDate,
+    },
+    #[error("'{0}' needs a year of four digits")]
+    ShortYear(String),
+    #[error("there is no month {month}{}", .order.map_or(String::new(), |order| format!(" (date_order is {})", order)))]
+    NoSuchMonth {
+        month: u32,
+        order: Option<DateOrder>,
+    },
+    #[error("{month} {year} has no day {day}, only {days}")]
+    NoSuchDay {
+        day: u32,
+        month: &'static str,
+        year: i32,
+        days: u32,
+    },
+    #[error("'{0}' is too far back")]
+    TooFarBack(String),
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Reads a date as typed into a prompt: `2024-03-01`, `01/03/2024` in
+/// `order`, `today`, `yesterday`, or days or weeks before `today` as `-3d`
+/// or `-2w`. Without an order, a date with slashes is only accepted when
+/// just one of its readings is a date.
+pub fn parse_user_date(
+    input: &str,
+    today: NaiveDate,
+    order: Option<DateOrder>,
+) -> Result<NaiveDate, DateParseError> {
+    let input = input.trim();
+    let lower = input.to_lowercase();
+    match lower.as_str() {
+        "" => return Err(DateParseError::Empty),
+        "today" => return Ok(today),
+        "yesterday" => return days_before(today, 1, input),
+        _ => {}
+    }
+    let unrecognized = || DateParseError::Unrecognized(input.to_owned());
+    if let Some(relative) = lower.strip_prefix('-') {
+        let (count, days_each) = match relative.char_indices().last() {
+            Some((at, 'd')) => (&relative[..at], 1),
+            Some((at, 'w')) => (&relative[..at], 7),
+            _ => return Err(unrecognized()),
+        };
+        if count.is_empty() || !count.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(unrecognized());
+        }
+        let count: i64 = count
+            .parse()
+            .map_err(|_| DateParseError::TooFarBack(input.to_owned()))?;
+        return days_before(today, count.saturating_mul(days_each), input);
+    }
+    if let Some([year, month, day]) = numbers(input, '-') {
+        if year.len() != 4 {
+            return Err(DateParseError::ShortYear(input.to_owned()));
+        }
+        return date(year, month, day, None);
+    }
+    let [first, second, year] = numbers(input, '/').ok_or_else(unrecognized)?;
+    if year.len() != 4 {
+        return Err(DateParseError::ShortYear(input.to_owned()));
+    }
+    match order {
+        Some(DateOrder::Dmy) => date(year, second, first, order),
+        Some(DateOrder::Mdy) => date(year, first, second, order),
+        None => match (
+            date(year, second, first, None),
+            date(year, first, second, None),
+        ) {
+            (Ok(dmy), Ok(mdy)) if dmy == mdy => Ok(dmy),
+            (Ok(dmy), Ok(mdy)) => Err(DateParseError::Ambiguous {
+                input: input.to_owned(),
+                dmy,
+                mdy,
+            }),
+            (Ok(dmy), Err(_)) => Ok(dmy),
+            (Err(_), Ok(mdy)) => Ok(mdy),
+            // Blame the day rather than the month where one reading has a
+            // month, as in 02/30/2024.
+            (Err(dmy @ DateParseError::NoSuchDay { .. }), Err(_)) => Err(dmy),
+            (Err(_), Err(mdy)) => Err(mdy),
+        },
+    }
+}
+
+/// The three runs of at most four digits `input` has between `separator`s.
+fn numbers(input: &str, separator: char) -> Option<[&str; 3]> {
+    let mut parts = input.split(separator);
+    let mut next = || {
+        parts
+            .next()
+            .filter(|part| (1..=4).contains(&part.len()))
+            .filter(|part| part.bytes().all(|byte| byte.is_ascii_digit()))
+    };
+    let numbers = [next()?, next()?, next()?];
+    match parts.next() {
+        Some(_) => None,
+        None => Some(numbers),
+    }
+}
+
+/// The date of digit runs checked by `numbers`.
+fn date(
+    year: &str,
+    month: &str,
+    day: &str,
+    order: Option<DateOrder>,
+) -> Result<NaiveDate, DateParseError> {
+    let [year, month, day] = [year, month, day].map(|part| part.parse::<u32>().unwrap_or(0));
+    if !(1..=12).contains(&month) {
+        return Err(DateParseError::NoSuchMonth { month, order });
+    }
+    let year = year as i32;
+    NaiveDate::from_ymd_opt(year, month, day).ok_or_else(|| {
+        let first_of_next = match month {
+            12 => NaiveDate::from_ymd_opt(year + 1, 1, 1),
+            _ => NaiveDate::from_ymd_opt(year, month + 1, 1),
+        };
+        DateParseError::NoSuchDay {
+            day,
+            month: MONTH_NAMES[month as usize - 1],
+            year,
+            days: first_of_next
+                .and_then(|first| first.pred_opt())
+                .map_or(31, |last| last.day()),
+        }
+    })
+}
+
+fn days_before(today: NaiveDate, days: i64, input: &str) -> Result<NaiveDate, DateParseError> {
+    Duration::try_days(days)
+        .and_then(|duration| today.checked_sub_signed(duration))
+        .ok_or_else(|| DateParseError::TooFarBack(input.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::seconds(seconds)
+    }
+
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+
+    #[test]
+    fn relative_times_use_the_largest_whole_unit() {
+        let dt = at(0);
+        for (elapsed, text) in [
+            (-DAY, "just now"),
+            (0, "just now"),
+            (59, "just now"),
+            (MINUTE, "1 minute ago"),
+            (2 * MINUTE, "2 minutes ago"),
+            (HOUR - 1, "59 minutes ago"),
+            (HOUR, "1 hour ago"),
+            (DAY - 1, "23 hours ago"),
+            (DAY, "1 day ago"),
+            (30 * DAY - 1, "29 days ago"),
+            (30 * DAY, "1 month ago"),
+            (364 * DAY, "12 months ago"),
+            (365 * DAY, "1 year ago"),
+            (3 * 365 * DAY, "3 years ago"),
+        ] {
+            assert_eq!(format_relative(dt, at(elapsed)), text, "{}s", elapsed);
+        }
+    }
+
+    #[test]
+    fn the_next_change_crosses_each_unit_boundary() {
+        let dt = at(0);
+        for (elapsed, next) in [
+            (0, MINUTE),
+            (59, MINUTE),
+            (MINUTE, 2 * MINUTE),
+            (HOUR - 1, HOUR),
+            (HOUR, 2 * HOUR),
+            (DAY - 1, DAY),
+            (DAY, 2 * DAY),
+            (30 * DAY - 1, 30 * DAY),
+            (30 * DAY, 60 * DAY),
+            (359 * DAY, 360 * DAY),
+            (360 * DAY, 365 * DAY),
+            (365 * DAY, 730 * DAY),
+        ] {
+            assert_eq!(next_change(dt, at(elapsed)), at(next), "{}s", elapsed);
+        }
+    }
+
+    #[test]
+    fn a_time_in_the_future_changes_a_minute_after_it() {
+        assert_eq!(next_change(at(DAY), at(0)), at(DAY + MINUTE));
+    }
+
+    /// The text stays the same up to the next change and differs at it, so
+    /// the UI redraws exactly when it has to.
+    #[test]
+    fn the_next_change_is_exactly_when_the_text_changes() {
+        let dt = at(0);
+        let mut elapsed = 0;
+        let mut changes = 0;
+        while elapsed < 3 * 365 * DAY {
+            let now = at(elapsed);
+            let next = next_change(dt, now);
+            assert!(next > now, "{}s", elapsed);
+            let text = format_relative(dt, now);
+            let before = next - Duration::seconds(1);
+            assert_eq!(format_relative(dt, before), text, "{}s", elapsed);
+            assert_ne!(format_relative(dt, next), text, "{}s", elapsed);
+            elapsed = next.signed_duration_since(dt).num_seconds();
+            changes += 1;
+        }
+        // 60 in the first hour, then one per hour, day, month and year.
+        assert_eq!(changes, 60 + 23 + 29 + 12 + 2);
+    }
+
+    #[test]
+    fn an_age_from_a_birthdate_goes_from_days_to_weeks_to_months_and_years() {
+        let today = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let born = |days: u64| today.checked_sub_days(chrono::Days::new(days)).unwrap();
+        for (days, text) in [
+            (0, "0 d"),
+            (55, "55 d"),
+            (56, "8 w"),
+            (90, "12 w"),
+            (91, "2 m"),
+            (92, "3 m"),
+            (364, "11 m"),
+            (365, "1 y"),
+            (800, "2 y 2 m"),
+        ] {
+            assert_eq!(format_age(born(days), today), text, "{} days", days);
+        }
+        let tomorrow = today.succ_opt().unwrap();
+        assert_eq!(format_age(tomorrow, today), "0 d");
+    }
+
+    #[test]
+    fn a_leap_day_birthday_comes_on_march_1_in_other_years() {
+        let born = ymd(2020, 2, 29);
+        for (today, months, text) in [
+            (ymd(2021, 2, 28), 11, "11 m"),
+            (ymd(2021, 3, 1), 12, "1 y"),
+            (ymd(2023, 2, 28), 35, "2 y 11 m"),
+            (ymd(2023, 3, 1), 36, "3 y"),
+            (ymd(2024, 2, 28), 47, "3 y 11 m"),
+            (ymd(2024, 2, 29), 48, "4 y"),
+        ] {
+            assert_eq!(months_between(born, today), months, "{}", today);
+            assert_eq!(format_age(born, today), text, "{}", today);
+        }
+    }
+
+    #[test]
+    fn a_birthday_not_yet_reached_this_year_is_a_year_short() {
+        let born = ymd(2019, 9, 15);
+        for (today, months, text) in [
+            (ymd(2025, 6, 1), 68, "5 y 8 m"),
+            (ymd(2025, 9, 14), 71, "5 y 11 m"),
+            (ymd(2025, 9, 15), 72, "6 y"),
+            (ymd(2026, 1, 1), 75, "6 y 3 m"),
+        ] {
+            assert_eq!(months_between(born, today), months, "{}", today);
+            assert_eq!(format_age(born, today), text, "{}", today);
+        }
+        // Each month only counts once its day comes round.
+        assert_eq!(months_between(ymd(2024, 1, 31), ymd(2024, 2, 29)), 0);
+        assert_eq!(months_between(ymd(2024, 1, 31), ymd(2024, 3, 31)), 2);
+    }
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn parse(input: &str, order: Option<DateOrder>) -> Result<NaiveDate, DateParseError> {
+        parse_user_date(input, ymd(2024, 3, 10), order)
+    }
+
+    fn message(input: &str, order: Option<DateOrder>) -> String {
+        parse(input, order).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn words_and_relative_dates_count_back_from_today() {
+        for (input, date) in [
+            ("today", ymd(2024, 3, 10)),
+            (" Today ", ymd(2024, 3, 10)),
+            ("yesterday", ymd(2024, 3, 9)),
+            ("-0d", ymd(2024, 3, 10)),
+            ("-10d", ymd(2024, 2, 29)),
+            ("-2w", ymd(2024, 2, 25)),
+            ("-2W", ymd(2024, 2, 25)),
+            ("-52w", ymd(2023, 3, 12)),
+        ] {
+            assert_eq!(parse(input, None), Ok(date), "{}", input);
+        }
+        assert_eq!(
+            parse_user_date("yesterday", ymd(2024, 1, 1), None),
+            Ok(ymd(2023, 12, 31))
+        );
+    }
+
+    #[test]
+    fn relative_dates_need_a_count_and_a_unit() {
+        for input in ["-d", "-3", "-3m", "-3x", "--3d", "-3 d", "+3d", "-3.5w"] {
+            assert_eq!(
+                parse(input, None),
+                Err(DateParseError::Unrecognized(input.to_owned())),
+                "{}",
+                input
+            );
+        }
+        for input in ["-99999999999999999999d", "-9999999999999w", "-999999999d"] {
+            assert_eq!(
+                parse(input, None),
+                Err(DateParseError::TooFarBack(input.to_owned())),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn iso_dates_ignore_the_date_order() {
+        for order in [None, Some(DateOrder::Dmy), Some(DateOrder::Mdy)] {
+            assert_eq!(parse("2024-03-01", order), Ok(ymd(2024, 3, 1)));
+            assert_eq!(parse("2024-3-1", order), Ok(ymd(2024, 3, 1)));
+        }
+        assert_eq!(
+            parse("24-03-01", None),
+            Err(DateParseError::ShortYear("24-03-01".to_owned()))
+        );
+        assert_eq!(
+            parse("2024-03", None),
+            Err(DateParseError::Unrecognized("2024-03".to_owned()))
+        );
+        assert_eq!(
+            parse("2024-03-01-02", None),
+            Err(DateParseError::Unrecognized("2024-03-01-02".to_owned()))
+        );
+    }
+
+    #[test]
+    fn leap_days_exist_only_in_leap_years() {
+        assert_eq!(parse("2024-02-29", None), Ok(ymd(2024, 2, 29)));
+        assert_eq!(parse("2000-02-29", None), Ok(ymd(2000, 2, 29)));
+        assert_eq!(
+            message("2023-02-29", None),
+            "February 2023 has no day 29, only 28"
+        );
+        assert_eq!(
+            message("1900-02-29", None),
+            "February 1900 has no day 29, only 28"
+        );
+        assert_eq!(
+            message("2024-02-30", None),
+            "February 2024 has no day 30, only 29"
+        );
+        assert_eq!(
+            parse("29/02/2024", Some(DateOrder::Dmy)),
+            Ok(ymd(2024, 2, 29))
+        );
+        assert_eq!(
+            message("02/29/2023", Some(DateOrder::Mdy)),
+            "February 2023 has no day 29, only 28"
+        );
+    }
+
+    #[test]
+    fn the_31st_of_a_short_month_is_refused() {
+        for (input, text) in [
+            ("2024-04-31", "April 2024 has no day 31, only 30"),
+            ("2024-06-31", "June 2024 has no day 31, only 30"),
+            ("2024-09-31", "September 2024 has no day 31, only 30"),
+            ("2024-11-31", "November 2024 has no day 31, only 30"),
+            ("2024-01-32", "January 2024 has no day 32, only 31"),
+            ("2024-12-32", "December 2024 has no day 32, only 31"),
+            ("2024-05-00", "May 2024 has no day 0, only 31"),
+        ] {
+            assert_eq!(message(input, None), text, "{}", input);
+        }
+        assert_eq!(parse("2024-12-31", None), Ok(ymd(2024, 12, 31)));
+        assert_eq!(message("2024-13-01", None), "there is no month 13");
+        assert_eq!(message("2024-00-01", None), "there is no month 0");
+    }
+
+    #[test]
+    fn the_date_order_says_how_slashed_dates_are_read() {
+        assert_eq!(
+            parse("01/03/2024", Some(DateOrder::Dmy)),
+            Ok(ymd(2024, 3, 1))
+        );
+        assert_eq!(
+            parse("01/03/2024", Some(DateOrder::Mdy)),
+            Ok(ymd(2024, 1, 3))
+        );
+        assert_eq!(
+            message("03/13/2024", Some(DateOrder::Dmy)),
+            "there is no month 13 (date_order is dmy)"
+        );
+        assert_eq!(
+            message("13/03/2024", Some(DateOrder::Mdy)),
+            "there is no month 13 (date_order is mdy)"
+        );
+        assert_eq!(
+            message("31/04/2024", Some(DateOrder::Dmy)),
+            "April 2024 has no day 31, only 30"
+        );
+    }
+
+    #[test]
+    fn without_an_order_a_slashed_date_needs_a_single_reading() {
+        assert_eq!(
+            parse("01/03/2024", None),
+            Err(DateParseError::Ambiguous {
+                input: "01/03/2024".to_owned(),
+                dmy: ymd(2024, 3, 1),
+                mdy: ymd(2024, 1, 3),
+            })
+        );
+        assert_eq!(
+            message("01/03/2024", None),
+            "'01/03/2024' could be 2024-03-01 or 2024-01-03; \
+             type it as YYYY-MM-DD or set date_order to dmy or mdy"
+        );
+        assert_eq!(parse("05/05/2024", None), Ok(ymd(2024, 5, 5)));
+        assert_eq!(parse("13/03/2024", None), Ok(ymd(2024, 3, 13)));
+        assert_eq!(parse("03/13/2024", None), Ok(ymd(2024, 3, 13)));
+        assert_eq!(
+            parse("31/04/2024", None),
+            Err(DateParseError::NoSuchDay {
+                day: 31,
+                month: "April",
+                year: 2024,
+                days: 30,
+            })
+        );
+        assert_eq!(
+            message("02/30/2024", None),
+            "February 2024 has no day 30, only 29"
+        );
+        assert_eq!(message("13/13/2024", None), "there is no month 13");
+        assert_eq!(
+            parse("01/03/24", None),
+            Err(DateParseError::ShortYear("01/03/24".to_owned()))
+        );
+    }
+
+    #[test]
+    fn other_input_is_refused_with_the_forms_that_work() {
+        assert_eq!(parse("   ", None), Err(DateParseError::Empty));
+        for input in [
+            "tomorrow",
+            "1 March 2024",
+            "2024.03.01",
+            "01/03",
+            "2024-03-01x",
+        ] {
+            assert_eq!(
+                parse(input, None),
+                Err(DateParseError::Unrecognized(input.to_owned())),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            message("soon", None),
+            "'soon' is not a date; try 2024-03-01, 01/03/2024, today, yesterday, -3d or -2w"
+        );
+    }
+
+    #[test]
+    fn date_orders_are_read_ignoring_case() {
+        assert_eq!(DateOrder::parse(""), Ok(None));
+        assert_eq!(DateOrder::parse(" DMY "), Ok(Some(DateOrder::Dmy)));
+        assert_eq!(DateOrder::parse("mdy"), Ok(Some(DateOrder::Mdy)));
+        assert_eq!(
+            DateOrder::parse("ymd"),
+            Err("expected dmy, mdy or nothing".to_owned())
+        );
+    }
+}
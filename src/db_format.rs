@@ -0,0 +1,414 @@
+use crate::ids::IdAllocator;
+use crate::migrations::{self, CURRENT_VERSION};
+use crate::{Database, Error, Owner, Pet};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, BufReader, Read};
+
+/// How the DB file is written. Reading accepts any of them.
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum DbFormat {
+    /// A JSON object on a single line.
+    Compact,
+    /// A JSON object with one field per line.
+    #[default]
+    Pretty,
+    /// One JSON object per line: the revision, schema version and next id
+    /// as `{"revision": ..., "schema_version": ..., "next_id": ...}`, owners
+    /// wrapped as
+    /// `{"owner": ...}`, then one line per pet.
+    #[value(name = "jsonl")]
+    JsonLines,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Owner {
+        owner: Owner,
+    },
+    Pet(Box<Pet>),
+    Header {
+        revision: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        schema_version: Option<u64>,
+        #[serde(default)]
+        next_id: IdAllocator,
+    },
+}
+
+/// The revision and schema version of a `Database` object, or the first
+/// line of a JSON Lines file.
+#[derive(Deserialize)]
+struct Header {
+    #[serde(default)]
+    revision: u64,
+    #[serde(default)]
+    schema_version: Option<u64>,
+}
+
+/// The start of an encrypted DB file, which is followed by the ciphertext
+/// rather than JSON.
+pub const ENCRYPTED_MAGIC: &[u8] = b"pet-cli encrypted v1\n";
+
+pub fn is_encrypted(content: &[u8]) -> bool {
+    content.starts_with(ENCRYPTED_MAGIC)
+}
+
+/// Whether `content` is a DB from before owners existed: a bare JSON array of
+/// pets instead of an object.
+pub fn is_legacy(content: &str) -> bool {
+    content.trim_start().starts_with('[')
+}
+
+/// Parses a DB file in any of the `DbFormat`s and any schema version up to
+/// the current one, migrating older ones in memory. Anything that isn't a
+/// JSON array or a `Database` object is read as JSON Lines.
+pub fn parse(content: &str) -> Result<Database, Error> {
+    if schema_version(content) != CURRENT_VERSION {
+        let db = migrations::migrate(to_value(content)?)?;
+        return Ok(serde_json::from_value(db)?);
+    }
+    let whole = serde_json::from_str(content);
+    if whole.is_ok() || !is_json_lines(content) {
+        return Ok(whole?);
+    }
+    let mut database = Database::default();
+    for line in content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        match serde_json::from_str(line)? {
+            Line::Owner { owner } => database.owners.push(owner),
+            Line::Pet(pet) => database.pets.push(*pet),
+            Line::Header {
+                revision,
+                schema_version,
+                next_id,
+            } => {
+                database.revision = revision;
+                database.schema_version = schema_version.unwrap_or(1);
+                database.next_id = next_id;
+            }
+        }
+    }
+    Ok(database)
+}
+
+/// How many bytes [`stream`] reads between reports of how far it is.
+pub const PROGRESS_BYTES: u64 = 1 << 20;
+
+/// Reads a pretty or compact DB of the current schema version straight from
+/// `reader`, building the pets as it goes rather than holding the whole text
+/// of the file next to them, and calling `progress` with the bytes read so
+/// far every [`PROGRESS_BYTES`]. Returns the bytes read, and `None` for
+/// anything else, which [`parse`] reads.
+pub fn stream(reader: impl Read, progress: impl FnMut(u64)) -> (Option<Database>, u64) {
+    let mut counting = Counting {
+        inner: reader,
+        read: 0,
+        reported: 0,
+        progress,
+    };
+    // serde_json reads a byte at a time, which the buffer serves, so only
+    // whole chunks of the file are counted.
+    let mut buffered = BufReader::with_capacity(64 * 1024, &mut counting);
+    let database = serde_json::from_reader::<_, Database>(&mut buffered)
+        .ok()
+        .filter(|database| database.schema_version == CURRENT_VERSION);
+    drop(buffered);
+    (database, counting.read)
+}
+
+/// Counts the bytes read through it.
+struct Counting<R, F> {
+    inner: R,
+    read: u64,
+    reported: u64,
+    progress: F,
+}
+
+impl<R: Read, F: FnMut(u64)> Read for Counting<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read += read as u64;
+        if self.read - self.reported >= PROGRESS_BYTES {
+            self.reported = self.read;
+            (self.progress)(self.read);
+        }
+        Ok(read)
+    }
+}
+
+/// A DB file in any of the `DbFormat`s as JSON, with JSON Lines files
+/// turned into the object the other formats have.
+fn to_value(content: &str) -> serde_json::Result<Value> {
+    match serde_json::from_str(content) {
+        Ok(Value::Object(db)) if db.contains_key("pets") => return Ok(Value::Object(db)),
+        Ok(Value::Array(pets)) => return Ok(Value::Array(pets)),
+        Err(err) if !is_json_lines(content) => return Err(err),
+        _ => {}
+    }
+    let mut db = serde_json::Map::new();
+    let (mut pets, mut owners) = (Vec::new(), Vec::new());
+    for line in content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        match serde_json::from_str(line)? {
+            Value::Object(mut fields) if fields.len() == 1 && fields.contains_key("owner") => {
+                owners.extend(fields.remove("owner"))
+            }
+            Value::Object(fields) if is_header(&fields) => db.extend(fields),
+            entry => pets.push(entry),
+        }
+    }
+    db.insert("pets".to_owned(), Value::Array(pets));
+    db.insert("owners".to_owned(), Value::Array(owners));
+    Ok(Value::Object(db))
+}
+
+/// Whether the first line of `content` is JSON by itself, as in JSON Lines.
+/// A broken pretty file fails on its first line, `{`, so that the error of
+/// parsing it whole, with the line where it broke, is the one to report.
+fn is_json_lines(content: &str) -> bool {
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| serde_json::from_str::<Value>(line).is_ok())
+}
+
+fn is_header(fields: &serde_json::Map<String, Value>) -> bool {
+    fields.contains_key("revision")
+        && fields
+            .keys()
+            .all(|key| ["revision", "schema_version", "next_id"].contains(&key.as_str()))
+}
+
+/// The header of a DB file in any of the `DbFormat`s, without reading its
+/// pets. JSON Lines files have it on the first line, if at all.
+fn header(content: &str) -> Option<Header> {
+    if let Ok(header) = serde_json::from_str::<Header>(content) {
+        return Some(header);
+    }
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .and_then(|line| serde_json::from_str::<Header>(line).ok())
+}
+
+/// The revision of a DB file, 0 for files from before there were revisions.
+pub fn revision(content: &str) -> u64 {
+    if is_legacy(content) {
+        return 0;
+    }
+    header(content).map_or(0, |header| header.revision)
+}
+
+/// The schema version of a DB file, as `migrations::version` would read it.
+pub fn schema_version(content: &str) -> u64 {
+    if is_legacy(content) {
+        return 0;
+    }
+    header(content)
+        .and_then(|header| header.schema_version)
+        .unwrap_or(1)
+}
+
+pub fn serialize(database: &Database, format: DbFormat) -> serde_json::Result<Vec<u8>> {
+    match format {
+        DbFormat::Compact => serde_json::to_vec(database),
+        DbFormat::Pretty => {
+            let mut content = serde_json::to_vec_pretty(database)?;
+            content.push(b'\n');
+            Ok(content)
+        }
+        DbFormat::JsonLines => {
+            let mut content = Vec::new();
+            serde_json::to_writer(
+                &mut content,
+                &Line::Header {
+                    revision: database.revision,
+                    schema_version: Some(database.schema_version),
+                    next_id: database.next_id,
+                },
+            )?;
+            content.push(b'\n');
+            for owner in &database.owners {
+                serde_json::to_writer(
+                    &mut content,
+                    &Line::Owner {
+                        owner: owner.clone(),
+                    },
+                )?;
+                content.push(b'\n');
+            }
+            for pet in &database.pets {
+                serde_json::to_writer(&mut content, pet)?;
+                content.push(b'\n');
+            }
+            Ok(content)
+        }
+    }
+}
+
+/// The pets and owners of a DB file as plain JSON, for looking at what
+/// `parse` would reject or drop.
+pub struct RawDatabase {
+    pub pets: Vec<Value>,
+    pub owners: Vec<Value>,
+}
+
+/// Splits a DB file in any of the `DbFormat`s into its entries without
+/// reading them as pets and owners.
+pub fn parse_raw(content: &str) -> serde_json::Result<RawDatabase> {
+    if is_legacy(content) {
+        return Ok(RawDatabase {
+            pets: serde_json::from_str(content)?,
+            owners: Vec::new(),
+        });
+    }
+    if let Ok(Value::Object(mut fields)) = serde_json::from_str(content) {
+        if fields.contains_key("pets") {
+            let mut entries = |key: &str| match fields.remove(key) {
+                Some(Value::Array(entries)) => entries,
+                _ => Vec::new(),
+            };
+            return Ok(RawDatabase {
+                pets: entries("pets"),
+                owners: entries("owners"),
+            });
+        }
+    }
+    let mut database = RawDatabase {
+        pets: Vec::new(),
+        owners: Vec::new(),
+    };
+    for line in content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        match serde_json::from_str(line)? {
+            Value::Object(mut fields) if fields.len() == 1 && fields.contains_key("owner") => {
+                database.owners.extend(fields.remove("owner"))
+            }
+            Value::Object(fields) if is_header(&fields) => {}
+            entry => database.pets.push(entry),
+        }
+    }
+    Ok(database)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_encrypted_files_by_their_header() {
+        let mut encrypted = ENCRYPTED_MAGIC.to_vec();
+        encrypted.extend([0x8f, 0x00, 0xff, b'{']);
+        assert!(is_encrypted(&encrypted));
+        assert!(is_encrypted(ENCRYPTED_MAGIC));
+    }
+
+    #[test]
+    fn plain_and_compressed_files_are_not_encrypted() {
+        let database = Database::default();
+        for format in [DbFormat::Compact, DbFormat::Pretty, DbFormat::JsonLines] {
+            assert!(!is_encrypted(&serialize(&database, format).unwrap()));
+        }
+        assert!(!is_encrypted(b""));
+        assert!(!is_encrypted(&ENCRYPTED_MAGIC[..ENCRYPTED_MAGIC.len() - 1]));
+        assert!(!is_encrypted(&[&b" "[..], ENCRYPTED_MAGIC].concat()));
+        assert!(!is_encrypted(&[0x1f, 0x8b, 0x08, 0x00]));
+    }
+
+    fn shelter(count: usize) -> Database {
+        let mut database = Database {
+            pets: (1..=count)
+                .map(|id| crate::ui_tests::pet(id, &format!("Pet {}", id), "cats"))
+                .collect(),
+            schema_version: CURRENT_VERSION,
+            ..Database::default()
+        };
+        database.next_id.take(&database.pets, 0);
+        database
+    }
+
+    fn names(database: &Database) -> Vec<&str> {
+        database.pets.iter().map(|pet| pet.name.as_str()).collect()
+    }
+
+    #[test]
+    fn streams_the_formats_of_the_current_version() {
+        let database = shelter(3);
+        for format in [DbFormat::Compact, DbFormat::Pretty] {
+            let content = serialize(&database, format).unwrap();
+            let (streamed, read) = stream(content.as_slice(), |_| {});
+            let streamed = streamed.unwrap_or_else(|| panic!("{:?} streams", format));
+            assert_eq!(names(&streamed), ["Pet 1", "Pet 2", "Pet 3"]);
+            assert_eq!(streamed.next_id, database.next_id);
+            assert_eq!(read, content.len() as u64);
+        }
+    }
+
+    #[test]
+    fn leaves_other_files_to_parse() {
+        let json_lines = serialize(&shelter(2), DbFormat::JsonLines).unwrap();
+        let json_lines = String::from_utf8(json_lines).unwrap();
+        let mut old = serde_json::to_value(shelter(2)).unwrap();
+        old["schema_version"] = serde_json::json!(CURRENT_VERSION - 1);
+        let legacy = r#"[{"id": 1, "name": "Coco", "category": "cats", "age": 2}]"#.to_owned();
+        assert_eq!(
+            parse(&json_lines).map(|database| database.pets.len()).ok(),
+            Some(2)
+        );
+        for content in [
+            json_lines,
+            old.to_string(),
+            legacy,
+            "{\"pets\": [".to_owned(),
+        ] {
+            assert!(
+                stream(content.as_bytes(), |_| {}).0.is_none(),
+                "{}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn a_broken_file_says_where_it_broke() {
+        let content = "{\n  \"pets\": [\n    {\"id\": 1,, }\n  ]\n}";
+        assert!(stream(content.as_bytes(), |_| {}).0.is_none());
+        let err = parse(content).unwrap_err().to_string();
+        assert!(err.contains("line 3"), "{}", err);
+        // Files of older versions are read another way, with the same errors.
+        let old = "{\n  \"schema_version\": 1,\n  \"pets\": [\n    {\"id\": 1,, }\n  ]\n}";
+        let err = parse(old).unwrap_err().to_string();
+        assert!(err.contains("line 4"), "{}", err);
+        // A broken line of JSON Lines is still reported as its own.
+        let json_lines = "{\"revision\": 1}\n{\"id\": 1,, }\n";
+        assert!(parse(json_lines).is_err());
+    }
+
+    #[test]
+    fn progress_is_reported_every_megabyte_read() {
+        let content = serialize(&shelter(20_000), DbFormat::Pretty).unwrap();
+        assert!(content.len() as u64 > 3 * PROGRESS_BYTES);
+        let mut reports = Vec::new();
+        let (streamed, read) = stream(content.as_slice(), |read| reports.push(read));
+        assert_eq!(streamed.map(|database| database.pets.len()), Some(20_000));
+        assert_eq!(reports.len() as u64, read / PROGRESS_BYTES, "{:?}", reports);
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[1] - pair[0] >= PROGRESS_BYTES));
+        assert!(reports.iter().all(|report| *report <= read));
+    }
+}
@@ -0,0 +1,90 @@
+use fs2::FileExt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// How long to wait for another process to release the DB before giving up.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+const RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Set while this process holds the lock for its whole run (`--exclusive`),
+/// in which case individual writes don't lock again.
+static HELD_FOR_SESSION: AtomicBool = AtomicBool::new(false);
+
+#[derive(Error, Debug)]
+pub enum LockError {
+    #[error("db locked by another process")]
+    Contended,
+    #[error("could not lock the DB: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// An advisory lock on the sidecar `<db>.lock` file, released on drop.
+pub struct DbLock {
+    file: Option<File>,
+    session: bool,
+}
+
+impl Drop for DbLock {
+    fn drop(&mut self) {
+        if let Some(file) = &self.file {
+            let _ = file.unlock();
+        }
+        if self.session {
+            HELD_FOR_SESSION.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn lock_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_owned();
+    name.push(".lock");
+    db_path.with_file_name(name)
+}
+
+/// Locks the DB for a read-modify-write. Plain reads don't lock, since writes
+/// replace the file atomically.
+pub fn exclusive(db_path: &Path) -> Result<DbLock, LockError> {
+    acquire(db_path)
+}
+
+/// Locks the DB until the returned guard is dropped, keeping other instances
+/// from writing it in the meantime.
+pub fn exclusive_for_session(db_path: &Path) -> Result<DbLock, LockError> {
+    let mut lock = exclusive(db_path)?;
+    lock.session = true;
+    HELD_FOR_SESSION.store(true, Ordering::Relaxed);
+    Ok(lock)
+}
+
+fn acquire(db_path: &Path) -> Result<DbLock, LockError> {
+    if HELD_FOR_SESSION.load(Ordering::Relaxed) {
+        return Ok(DbLock {
+            file: None,
+            session: false,
+        });
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(lock_path(db_path))?;
+    let deadline = Instant::now() + LOCK_TIMEOUT;
+    loop {
+        match file.try_lock_exclusive() {
+            Ok(()) => {
+                return Ok(DbLock {
+                    file: Some(file),
+                    session: false,
+                })
+            }
+            Err(err) if err.kind() != fs2::lock_contended_error().kind() => return Err(err.into()),
+            Err(_) if Instant::now() >= deadline => return Err(LockError::Contended),
+            Err(_) => thread::sleep(RETRY_INTERVAL),
+        }
+    }
+}
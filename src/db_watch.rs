@@ -0,0 +1,37 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Remembers the DB file's modification time as of the last time the TUI
+/// looked at it, so edits made by other programs can be noticed.
+pub struct DbWatch {
+    path: PathBuf,
+    seen: Option<SystemTime>,
+}
+
+impl DbWatch {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let seen = modified(&path);
+        Self { path, seen }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file was modified since it was last marked as seen.
+    pub fn changed(&self) -> bool {
+        modified(&self.path) != self.seen
+    }
+
+    pub fn mark_seen(&mut self) {
+        self.seen = modified(&self.path);
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -0,0 +1,106 @@
+//! `pet-cli --demo`: the UI on made-up pets, for showing pet-cli to people.
+//! The DB is created in a new temporary directory, the config file isn't
+//! read, and the directory is removed again on quitting unless `--keep` is
+//! given, so nothing of the real setup is touched.
+
+use crate::generator::{self, Names};
+use crate::pet_status::PetStatus;
+use crate::Pet;
+use chrono::{DateTime, Duration, Utc};
+use rand::prelude::*;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// The demo always shows the same pets.
+const SEED: u64 = 2024;
+const PET_COUNT: usize = 30;
+/// The pets were created over the two years before the demo starts.
+const CREATED_OVER_MINUTES: i64 = 2 * 365 * 24 * 60;
+
+/// A new directory for the demo's DB under the system's temporary one.
+pub fn create_dir() -> io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("pet-cli-demo-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// The demo's pets, named from `names`, as of `now`: generated like `pet-cli
+/// generate` does, then given a status and a creation time at random, with
+/// ids in the order they were created. The same `now` always gives the same
+/// pets.
+pub fn pets(names: &Names, now: DateTime<Utc>) -> Vec<Pet> {
+    let mut rng = generator::seeded_rng(Some(SEED));
+    let mut pets = generator::generate(&mut rng, PET_COUNT, 1, now, names, &[]);
+    for pet in &mut pets {
+        pet.created_at = now - Duration::minutes(rng.gen_range(0, CREATED_OVER_MINUTES));
+        pet.status = *PetStatus::ALL.choose(&mut rng).expect("there are statuses");
+    }
+    pets.sort_by_key(|pet| pet.created_at);
+    for (id, pet) in (1..).zip(&mut pets) {
+        pet.id = id;
+    }
+    pets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn now() -> DateTime<Utc> {
+        "2025-06-01T12:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn the_same_moment_gives_the_same_pets() {
+        let names = Names::default();
+        let first = pets(&names, now());
+        let again = pets(&names, now());
+        assert_eq!(first.len(), PET_COUNT);
+        let summary = |pets: &[Pet]| -> Vec<String> {
+            pets.iter()
+                .map(|pet| serde_json::to_string(pet).unwrap())
+                .collect()
+        };
+        assert_eq!(summary(&first), summary(&again));
+    }
+
+    #[test]
+    fn pets_are_varied_and_numbered_in_the_order_they_were_created() {
+        let pets = pets(&Names::default(), now());
+        let ids: Vec<usize> = pets.iter().map(|pet| pet.id).collect();
+        assert_eq!(ids, (1..=PET_COUNT).collect::<Vec<_>>());
+        assert!(pets.windows(2).all(|w| w[0].created_at <= w[1].created_at));
+        let earliest = now() - Duration::minutes(CREATED_OVER_MINUTES);
+        assert!(pets
+            .iter()
+            .all(|pet| pet.created_at > earliest && pet.created_at <= now()));
+        // Spread over the two years, not bunched at one end.
+        assert!(pets[0].created_at < now() - Duration::days(365));
+        assert!(pets[PET_COUNT - 1].created_at > now() - Duration::days(365));
+        let categories: HashSet<&str> = pets.iter().map(|pet| pet.category.as_str()).collect();
+        assert!(categories.len() > 2, "{:?}", categories);
+        let statuses: HashSet<PetStatus> = pets.iter().map(|pet| pet.status).collect();
+        assert!(statuses.len() > 1, "{:?}", statuses);
+        assert!(pets.iter().all(|pet| !pet.name.is_empty()));
+    }
+
+    #[test]
+    fn names_come_from_the_given_pools() {
+        let dir = std::env::temp_dir().join(format!("pet-cli-demo-names-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("names.json");
+        fs::write(&path, r#"{"cats": ["Tom"], "dogs": ["Rex"]}"#).unwrap();
+        let names = Names::load(&path).unwrap();
+        let pets = pets(&names, now());
+        for pet in &pets {
+            match pet.category.as_str() {
+                "cats" => assert_eq!(pet.name, "Tom"),
+                "dogs" => assert_eq!(pet.name, "Rex"),
+                _ => {}
+            }
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
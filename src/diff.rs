@@ -0,0 +1,309 @@
+//! What changed between two versions of the pets, for previewing a restore
+//! and for `pet-cli diff`. Pets are matched by id; a pet whose id was given
+//! to another pet, which shows as a different creation time, counts as
+//! removed and added rather than changed.
+
+use crate::Pet;
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PetChange {
+    pub id: usize,
+    /// The name in the newer version.
+    pub name: String,
+    pub fields: Vec<FieldChange>,
+}
+
+/// Each list is ordered by id.
+#[derive(Debug, Default)]
+pub struct PetDiff {
+    pub added: Vec<Pet>,
+    pub removed: Vec<Pet>,
+    pub changed: Vec<PetChange>,
+}
+
+impl PetDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Like `2 added, 1 removed, 3 changed`.
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return "no differences".to_owned();
+        }
+        format!(
+            "{} added, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )
+    }
+}
+
+pub fn diff_pets(old: &[Pet], new: &[Pet]) -> PetDiff {
+    let old_by_id: HashMap<usize, &Pet> = old.iter().map(|pet| (pet.id, pet)).collect();
+    let new_by_id: HashMap<usize, &Pet> = new.iter().map(|pet| (pet.id, pet)).collect();
+    let mut diff = PetDiff::default();
+    for pet in old {
+        match new_by_id.get(&pet.id) {
+            Some(newer) if newer.created_at == pet.created_at => {
+                let fields = diff_fields(pet, newer);
+                if !fields.is_empty() {
+                    diff.changed.push(PetChange {
+                        id: pet.id,
+                        name: newer.name.clone(),
+                        fields,
+                    });
+                }
+            }
+            _ => diff.removed.push(pet.clone()),
+        }
+    }
+    for pet in new {
+        let same_pet = old_by_id
+            .get(&pet.id)
+            .is_some_and(|older| older.created_at == pet.created_at);
+        if !same_pet {
+            diff.added.push(pet.clone());
+        }
+    }
+    diff.added.sort_by_key(|pet| pet.id);
+    diff.removed.sort_by_key(|pet| pet.id);
+    diff.changed.sort_by_key(|change| change.id);
+    diff
+}
+
+/// The fields of the stored pets that differ, in alphabetical order.
+pub fn diff_fields(old: &Pet, new: &Pet) -> Vec<FieldChange> {
+    let fields = |pet: &Pet| match serde_json::to_value(pet).expect("pets serialize") {
+        Value::Object(fields) => fields,
+        _ => Map::new(),
+    };
+    let (old, new) = (fields(old), fields(new));
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    names
+        .into_iter()
+        .filter(|name| old.get(*name) != new.get(*name))
+        .map(|name| {
+            let (old, new) = (old.get(name), new.get(name));
+            let (old_text, mut new_text) = (describe(old), describe(new));
+            if old_text == new_text {
+                new_text.push_str(", edited");
+            }
+            FieldChange {
+                field: name.clone(),
+                old: old_text,
+                new: new_text,
+            }
+        })
+        .collect()
+}
+
+/// Values as JSON, except that lists like the medical records are only
+/// counted, as they would not fit on a line.
+fn describe(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "none".to_owned(),
+        Some(Value::Array(entries)) if entries.len() == 1 => "1 entry".to_owned(),
+        Some(Value::Array(entries)) => format!("{} entries", entries.len()),
+        Some(value) => value.to_string(),
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LineKind {
+    Added,
+    Removed,
+    Changed,
+    Field,
+}
+
+pub struct Line {
+    pub kind: LineKind,
+    pub text: String,
+}
+
+/// The diff as a unified summary: `+` for added pets, `-` for removed ones and
+/// `~` for changed ones, followed by a line per changed field.
+pub fn lines(diff: &PetDiff) -> Vec<Line> {
+    let pet_line = |kind, sign, pet: &Pet| Line {
+        kind,
+        text: format!("{} #{} {} ({})", sign, pet.id, pet.name, pet.category),
+    };
+    let mut lines: Vec<Line> = diff
+        .removed
+        .iter()
+        .map(|pet| pet_line(LineKind::Removed, '-', pet))
+        .chain(
+            diff.added
+                .iter()
+                .map(|pet| pet_line(LineKind::Added, '+', pet)),
+        )
+        .collect();
+    for change in &diff.changed {
+        lines.push(Line {
+            kind: LineKind::Changed,
+            text: format!("~ #{} {}", change.id, change.name),
+        });
+        lines.extend(change.fields.iter().map(|field| Line {
+            kind: LineKind::Field,
+            text: format!("    {}: {} -> {}", field.field, field.old, field.new),
+        }));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use chrono::{TimeZone, Utc};
+
+    fn ids(pets: &[Pet]) -> Vec<usize> {
+        pets.iter().map(|pet| pet.id).collect()
+    }
+
+    fn texts(diff: &PetDiff) -> Vec<String> {
+        lines(diff).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn the_same_pets_have_no_differences() {
+        let pets = vec![pet(1, "Coco", "cats"), pet(2, "Rex", "dogs")];
+        let diff = diff_pets(&pets, &pets);
+        assert!(diff.is_empty());
+        assert_eq!(diff.summary(), "no differences");
+        assert!(lines(&diff).is_empty());
+    }
+
+    #[test]
+    fn finds_added_pets() {
+        let old = vec![pet(2, "Rex", "dogs")];
+        let new = vec![
+            pet(3, "Bun", "rabbits"),
+            pet(2, "Rex", "dogs"),
+            pet(1, "Coco", "cats"),
+        ];
+        let diff = diff_pets(&old, &new);
+        assert_eq!(ids(&diff.added), vec![1, 3]);
+        assert!(diff.removed.is_empty() && diff.changed.is_empty());
+        assert_eq!(diff.summary(), "2 added, 0 removed, 0 changed");
+        assert_eq!(texts(&diff), vec!["+ #1 Coco (cats)", "+ #3 Bun (rabbits)"]);
+    }
+
+    #[test]
+    fn finds_removed_pets() {
+        let old = vec![pet(4, "Max", "dogs"), pet(2, "Rex", "dogs")];
+        let diff = diff_pets(&old, &[]);
+        assert_eq!(ids(&diff.removed), vec![2, 4]);
+        assert_eq!(diff.summary(), "0 added, 2 removed, 0 changed");
+        assert_eq!(texts(&diff), vec!["- #2 Rex (dogs)", "- #4 Max (dogs)"]);
+    }
+
+    #[test]
+    fn lists_changed_fields_alphabetically() {
+        let old = pet(1, "Coco", "cats");
+        let mut new = pet(1, "Cocoa", "cats");
+        new.age.months = 14;
+        new.notes = Some("shy".to_owned());
+        let diff = diff_pets(&[old], &[new]);
+        assert_eq!(
+            diff.changed,
+            vec![PetChange {
+                id: 1,
+                name: "Cocoa".to_owned(),
+                fields: vec![
+                    FieldChange {
+                        field: "age_months".to_owned(),
+                        old: "12".to_owned(),
+                        new: "14".to_owned(),
+                    },
+                    FieldChange {
+                        field: "name".to_owned(),
+                        old: "\"Coco\"".to_owned(),
+                        new: "\"Cocoa\"".to_owned(),
+                    },
+                    FieldChange {
+                        field: "notes".to_owned(),
+                        old: "none".to_owned(),
+                        new: "\"shy\"".to_owned(),
+                    },
+                ],
+            }]
+        );
+        assert_eq!(
+            texts(&diff),
+            vec![
+                "~ #1 Cocoa",
+                "    age_months: 12 -> 14",
+                "    name: \"Coco\" -> \"Cocoa\"",
+                "    notes: none -> \"shy\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_are_counted_and_edits_to_them_marked() {
+        let with_records = |notes: &[&str]| {
+            let mut pet = serde_json::to_value(pet(1, "Coco", "cats")).unwrap();
+            pet["records"] = notes
+                .iter()
+                .map(|note| serde_json::json!({"date": "2024-02-01", "kind": "checkup", "note": note}))
+                .collect();
+            serde_json::from_value::<Pet>(pet).unwrap()
+        };
+        let diff = diff_fields(&with_records(&["fine"]), &with_records(&["fine", "ill"]));
+        assert_eq!(
+            (diff[0].old.as_str(), diff[0].new.as_str()),
+            ("1 entry", "2 entries")
+        );
+        let diff = diff_fields(&with_records(&["fine"]), &with_records(&["ill"]));
+        assert_eq!(
+            (diff[0].old.as_str(), diff[0].new.as_str()),
+            ("1 entry", "1 entry, edited")
+        );
+        let diff = diff_fields(&pet(1, "Coco", "cats"), &with_records(&[]));
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn a_reused_id_is_a_removal_and_an_addition() {
+        let old = vec![pet(1, "Coco", "cats"), pet(2, "Rex", "dogs")];
+        let mut reused = pet(2, "Pip", "birds");
+        reused.created_at = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let new = vec![pet(1, "Coco", "cats"), reused];
+        let diff = diff_pets(&old, &new);
+        assert_eq!(ids(&diff.removed), vec![2]);
+        assert_eq!(ids(&diff.added), vec![2]);
+        assert!(diff.changed.is_empty());
+        assert_eq!(texts(&diff), vec!["- #2 Rex (dogs)", "+ #2 Pip (birds)"]);
+    }
+
+    #[test]
+    fn removals_come_before_additions_and_changes() {
+        let old = vec![pet(1, "Coco", "cats"), pet(3, "Bun", "rabbits")];
+        let new = vec![pet(1, "Coco", "dogs"), pet(2, "Rex", "dogs")];
+        let kinds: Vec<_> = lines(&diff_pets(&old, &new))
+            .into_iter()
+            .map(|line| line.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LineKind::Removed,
+                LineKind::Added,
+                LineKind::Changed,
+                LineKind::Field
+            ]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! What was being typed into the notes editor or a prompt, saved while typing
+//! so it survives the terminal being closed. The draft is offered back on the
+//! next start with the same DB and deleted once the form is saved or
+//! cancelled.
+
+use crate::PromptKind;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Drafts are saved on every this many ticks, if they changed.
+pub const SAVE_EVERY_TICKS: u32 = 4;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "form", rename_all = "snake_case")]
+pub enum Form {
+    Notes { pet_id: usize, text: String },
+    Prompt { kind: PromptKind, text: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Draft {
+    /// The resolved path of the DB the form was editing.
+    pub db_path: String,
+    pub saved_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub form: Form,
+}
+
+impl Draft {
+    /// What the draft was, for asking whether to resume it.
+    pub fn describe(&self) -> String {
+        let what = match &self.form {
+            Form::Notes { pet_id, .. } => format!("notes of pet #{}", pet_id),
+            Form::Prompt { kind, .. } => format!("answer to \"{}\"", kind.label()),
+        };
+        format!(
+            "unsaved {} from {}",
+            what,
+            crate::dates::format_local(self.saved_at)
+        )
+    }
+}
+
+/// `$XDG_CACHE_HOME/pet-cli/draft.json`, falling back to `~/.cache`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+    Some(base.join("pet-cli").join("draft.json"))
+}
+
+/// Returns `None` for a missing or unreadable draft, which is then simply not
+/// offered.
+pub fn load(path: &Path) -> Option<Draft> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn save(path: &Path, draft: &Draft) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec(draft)?)
+}
+
+pub fn delete(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// The draft to offer when opening the DB at `db_path`. Drafts of another DB
+/// are left alone for when that one is opened again, and empty ones aren't
+/// worth asking about.
+pub fn resumable(draft: Option<Draft>, db_path: &str) -> Option<Draft> {
+    let draft = draft?;
+    let text = match &draft.form {
+        Form::Notes { text, .. } | Form::Prompt { text, .. } => text,
+    };
+    (draft.db_path == db_path && !text.trim().is_empty()).then_some(draft)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn notes_draft(db_path: &str, text: &str) -> Draft {
+        Draft {
+            db_path: db_path.to_owned(),
+            saved_at: Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap(),
+            form: Form::Notes {
+                pet_id: 7,
+                text: text.to_owned(),
+            },
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("pet-cli-draft-{}-{}", std::process::id(), name))
+            .join("draft.json")
+    }
+
+    #[test]
+    fn a_saved_draft_loads_back_the_same() {
+        let path = temp_path("round-trip");
+        let drafts = vec![
+            notes_draft("/pets/db.json", "line one\nline \"two\"\n"),
+            Draft {
+                form: Form::Prompt {
+                    kind: PromptKind::Age { pet_id: 3 },
+                    text: "1y6m".to_owned(),
+                },
+                ..notes_draft("/pets/db.json", "")
+            },
+        ];
+        for draft in drafts {
+            save(&path, &draft).unwrap();
+            assert_eq!(load(&path), Some(draft));
+        }
+        delete(&path).unwrap();
+        fs::remove_dir(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn a_missing_or_garbled_draft_is_not_offered() {
+        let path = temp_path("garbled");
+        assert_eq!(load(&path), None);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{\"db_path\": \"/pets/db.json\", \"form\": \"no").unwrap();
+        assert_eq!(load(&path), None);
+        fs::write(
+            &path,
+            "{\"db_path\": \"/pets/db.json\", \"form\": \"other\"}",
+        )
+        .unwrap();
+        assert_eq!(load(&path), None);
+        delete(&path).unwrap();
+        fs::remove_dir(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn deleting_a_missing_draft_is_fine() {
+        let path = temp_path("missing");
+        assert!(delete(&path).is_ok());
+        save(&path, &notes_draft("/pets/db.json", "x")).unwrap();
+        delete(&path).unwrap();
+        assert!(!path.exists());
+        fs::remove_dir(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn only_drafts_of_the_same_db_are_resumed() {
+        let draft = notes_draft("/pets/db.json", "half a note");
+        assert_eq!(
+            resumable(Some(draft.clone()), "/pets/db.json"),
+            Some(draft.clone())
+        );
+        assert_eq!(resumable(Some(draft), "/other/db.json"), None);
+        assert_eq!(resumable(None, "/pets/db.json"), None);
+    }
+
+    #[test]
+    fn blank_drafts_are_not_resumed() {
+        for text in ["", "   ", "\n\t\n"] {
+            let draft = notes_draft("/pets/db.json", text);
+            assert_eq!(resumable(Some(draft), "/pets/db.json"), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn describes_what_was_being_typed() {
+        let described = notes_draft("/pets/db.json", "x").describe();
+        assert!(
+            described.starts_with("unsaved notes of pet #7 from "),
+            "{}",
+            described
+        );
+        let draft = Draft {
+            form: Form::Prompt {
+                kind: PromptKind::Age { pet_id: 3 },
+                text: "1y".to_owned(),
+            },
+            ..notes_draft("/pets/db.json", "")
+        };
+        assert!(draft.describe().starts_with("unsaved answer to \"Age"));
+    }
+}
@@ -0,0 +1,34 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// The times of the frames drawn in the last minute, for checking that an
+/// idle UI really stops drawing.
+#[derive(Default)]
+pub struct DrawRate {
+    frames: VecDeque<Instant>,
+}
+
+impl DrawRate {
+    pub fn record(&mut self, now: Instant) {
+        self.frames.push_back(now);
+        self.forget_old(now);
+    }
+
+    pub fn per_minute(&self, now: Instant) -> usize {
+        self.frames
+            .iter()
+            .filter(|&&frame| now.duration_since(frame) < WINDOW)
+            .count()
+    }
+
+    fn forget_old(&mut self, now: Instant) {
+        while let Some(&oldest) = self.frames.front() {
+            if now.duration_since(oldest) < WINDOW {
+                break;
+            }
+            self.frames.pop_front();
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! `--dry-run`: the UI works on a scratch copy of the DB, so that everything
+//! can be tried without the DB changing. On quit the changes are shown and
+//! can be saved to the DB after all, if nobody else changed it meanwhile.
+
+use crate::diff::{self, PetDiff};
+use crate::durability::{self, Durability};
+use crate::{backup, compression, db_format, db_lock, Database, Owner};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub struct DryRun {
+    /// The DB as it was when the dry run started, byte for byte; empty if
+    /// there was none.
+    original: Vec<u8>,
+    /// Where the copy is, with its lock file and backups.
+    dir: PathBuf,
+    /// The revision of the copy once it was set up, from which its writes
+    /// are counted.
+    base_revision: u64,
+}
+
+impl DryRun {
+    /// Copies the DB at `db` to a new directory under the system's temporary
+    /// one and returns the copy's path.
+    pub fn start(db: &Path) -> io::Result<(DryRun, PathBuf)> {
+        let original = match fs::read(db) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        static STARTED: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "pet-cli-dry-run-{}-{}",
+            std::process::id(),
+            STARTED.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir)?;
+        // Named as the DB is, so a new `.json.gz` DB is compressed in the copy
+        // too.
+        let copy = dir.join(db.file_name().unwrap_or_else(|| "db.json".as_ref()));
+        if !original.is_empty() {
+            fs::write(&copy, &original)?;
+        }
+        let dry_run = DryRun {
+            original,
+            dir,
+            base_revision: 0,
+        };
+        Ok((dry_run, copy))
+    }
+
+    /// Counts the copy's writes from its current revision on, once setting
+    /// it up, e.g. migrating it, is done.
+    pub fn set_base(&mut self, revision: u64) {
+        self.base_revision = revision;
+    }
+
+    /// How many writes there were to the copy at `revision`.
+    pub fn unsaved(&self, revision: u64) -> u64 {
+        revision.saturating_sub(self.base_revision)
+    }
+
+    /// The DB as it was when the dry run started.
+    pub fn original(&self) -> Result<Database, String> {
+        parse(&self.original)
+    }
+
+    /// The DB at `db` as it is now, if it changed since the dry run started
+    /// or since `rebase`.
+    pub fn changed(&self, db: &Path) -> io::Result<Option<(Vec<u8>, Database)>> {
+        let on_disk = match fs::read(db) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        if on_disk == self.original {
+            return Ok(None);
+        }
+        let database =
+            parse(&on_disk).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(Some((on_disk, database)))
+    }
+
+    /// Takes `on_disk` as the DB the copy is saved over, once the copy has
+    /// the changes made to it merged in.
+    pub fn rebase(&mut self, on_disk: Vec<u8>) {
+        self.original = on_disk;
+    }
+
+    /// The copy's changes against the DB as it was.
+    pub fn summary(&self, copy: &Path) -> Result<Summary, String> {
+        let original = self.original()?;
+        let content = compression::read_to_string(copy).map_err(|err| err.to_string())?;
+        let current = db_format::parse(&content).map_err(|err| err.to_string())?;
+        Ok(Summary {
+            diff: diff::diff_pets(&original.pets, &current.pets),
+            owners_changed: !same_owners(&original.owners, &current.owners),
+            scroll: 0,
+        })
+    }
+
+    /// Replaces the DB at `db` with the copy, after backing it up, unless it
+    /// changed since the dry run started.
+    pub fn save(
+        &self,
+        copy: &Path,
+        db: &Path,
+        keep_backups: usize,
+        durability: Durability,
+    ) -> Result<(), String> {
+        let content = fs::read(copy).map_err(|err| err.to_string())?;
+        self.save_content(&content, db, keep_backups, durability)
+    }
+
+    /// Replaces the DB at `db` with `content` as `save` does, for the dry run
+    /// merged with changes made to the DB meanwhile.
+    pub fn save_content(
+        &self,
+        content: &[u8],
+        db: &Path,
+        keep_backups: usize,
+        durability: Durability,
+    ) -> Result<(), String> {
+        let _lock = db_lock::exclusive(db).map_err(|err| err.to_string())?;
+        let on_disk = match fs::read(db) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.to_string()),
+        };
+        if on_disk != self.original {
+            return Err(format!(
+                "{} changed since the dry run started, not saving",
+                db.display()
+            ));
+        }
+        if !on_disk.is_empty() {
+            backup::create(db, keep_backups).map_err(|err| err.to_string())?;
+        }
+        let temp_path = db.with_extension("json.tmp");
+        durability::write_atomic(db, &temp_path, content, durability).map_err(|err| err.to_string())
+    }
+
+    /// Deletes the copy.
+    pub fn finish(self) {
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            log::warn!("could not remove {}: {}", self.dir.display(), err);
+        }
+    }
+}
+
+/// A DB file's bytes as a DB; an empty one for no file.
+fn parse(content: &[u8]) -> Result<Database, String> {
+    if content.is_empty() {
+        return Ok(Database::default());
+    }
+    let content = compression::decode(content.to_vec()).map_err(|err| err.to_string())?;
+    db_format::parse(&String::from_utf8_lossy(&content)).map_err(|err| err.to_string())
+}
+
+fn same_owners(a: &[Owner], b: &[Owner]) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// What quitting a dry run would throw away, shown before it does.
+pub struct Summary {
+    diff: PetDiff,
+    owners_changed: bool,
+    scroll: u16,
+}
+
+pub enum SummaryOutcome {
+    Open,
+    /// Back to the UI, without quitting.
+    Cancelled,
+    Discard,
+    Save,
+}
+
+impl Summary {
+    pub fn diff(&self) -> &PetDiff {
+        &self.diff
+    }
+
+    pub fn owners_changed(&self) -> bool {
+        self.owners_changed
+    }
+
+    pub fn scroll(&self) -> u16 {
+        self.scroll
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> SummaryOutcome {
+        match key.code {
+            KeyCode::Esc => return SummaryOutcome::Cancelled,
+            KeyCode::Char('y') => return SummaryOutcome::Save,
+            KeyCode::Char('n') => return SummaryOutcome::Discard,
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        SummaryOutcome::Open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_format::DbFormat;
+    use crate::ui_tests::pet;
+    use crossterm::event::KeyModifiers;
+
+    fn db_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "pet-cli-dry-run-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn content(names: &[&str]) -> Vec<u8> {
+        let database = Database {
+            pets: names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| pet(i + 1, name, "dogs"))
+                .collect(),
+            schema_version: crate::migrations::CURRENT_VERSION,
+            ..Database::default()
+        };
+        db_format::serialize(&database, DbFormat::Pretty).unwrap()
+    }
+
+    #[test]
+    fn writes_go_to_the_copy_and_leave_the_db_as_it_was() {
+        let dir = db_dir("copy");
+        let db = dir.join("db.json");
+        let original = content(&["Rex", "Max"]);
+        fs::write(&db, &original).unwrap();
+        let (dry_run, copy) = DryRun::start(&db).unwrap();
+        assert_ne!(copy, db);
+        assert_eq!(fs::read(&copy).unwrap(), original);
+        fs::write(&copy, content(&["Rex"])).unwrap();
+        assert!(dry_run.changed(&db).unwrap().is_none());
+        dry_run.finish();
+        assert!(!copy.exists());
+        assert_eq!(fs::read(&db).unwrap(), original);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_missing_db_starts_an_empty_copy() {
+        let dir = db_dir("missing");
+        let db = dir.join("db.json");
+        let (dry_run, copy) = DryRun::start(&db).unwrap();
+        assert!(!copy.exists());
+        assert!(dry_run.original().unwrap().pets.is_empty());
+        dry_run.finish();
+        assert!(!db.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unsaved_changes_are_counted_from_the_base() {
+        let dir = db_dir("unsaved");
+        let (mut dry_run, _) = DryRun::start(&dir.join("db.json")).unwrap();
+        dry_run.set_base(4);
+        assert_eq!(dry_run.unsaved(4), 0);
+        assert_eq!(dry_run.unsaved(7), 3);
+        assert_eq!(dry_run.unsaved(2), 0);
+        dry_run.finish();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn the_summary_lists_the_copys_changes() {
+        let dir = db_dir("summary");
+        let db = dir.join("db.json");
+        fs::write(&db, content(&["Rex", "Max"])).unwrap();
+        let (dry_run, copy) = DryRun::start(&db).unwrap();
+        fs::write(&copy, content(&["Rex", "Maxi", "Bun"])).unwrap();
+        let summary = dry_run.summary(&copy).unwrap();
+        assert_eq!(summary.diff().added.len(), 1);
+        assert_eq!(summary.diff().changed.len(), 1);
+        assert!(summary.diff().removed.is_empty());
+        assert!(!summary.owners_changed());
+        dry_run.finish();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn saving_backs_the_db_up_unless_it_changed_meanwhile() {
+        let dir = db_dir("save");
+        let db = dir.join("db.json");
+        let original = content(&["Rex"]);
+        fs::write(&db, &original).unwrap();
+        let (mut dry_run, copy) = DryRun::start(&db).unwrap();
+        let edited = content(&["Rex", "Max"]);
+        fs::write(&copy, &edited).unwrap();
+
+        let elsewhere = content(&["Bun"]);
+        fs::write(&db, &elsewhere).unwrap();
+        let err = dry_run.save(&copy, &db, 3, Durability::None).unwrap_err();
+        assert!(
+            err.ends_with("changed since the dry run started, not saving"),
+            "{}",
+            err
+        );
+        assert_eq!(fs::read(&db).unwrap(), elsewhere);
+        let (on_disk, theirs) = dry_run.changed(&db).unwrap().unwrap();
+        assert_eq!(theirs.pets[0].name, "Bun");
+
+        dry_run.rebase(on_disk);
+        dry_run.save(&copy, &db, 3, Durability::None).unwrap();
+        assert_eq!(fs::read(&db).unwrap(), edited);
+        assert_eq!(backup::list(&db).unwrap().len(), 1);
+        dry_run.finish();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn the_summary_asks_whether_to_save() {
+        let mut summary = Summary {
+            diff: PetDiff::default(),
+            owners_changed: false,
+            scroll: 0,
+        };
+        let mut press = |code| summary.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        assert!(matches!(press(KeyCode::Char('y')), SummaryOutcome::Save));
+        assert!(matches!(press(KeyCode::Char('n')), SummaryOutcome::Discard));
+        assert!(matches!(press(KeyCode::Esc), SummaryOutcome::Cancelled));
+        assert!(matches!(press(KeyCode::Char('j')), SummaryOutcome::Open));
+        press(KeyCode::Down);
+        press(KeyCode::Up);
+        assert_eq!(summary.scroll(), 1);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::Pet;
+use unicode_normalization::UnicodeNormalization;
+
+/// The form names are compared in: trimmed, compatibility-normalized (so
+/// composed and decomposed accents match) and case-folded.
+pub fn normalize_name(name: &str) -> String {
+    name.trim().nfkc().flat_map(char::to_lowercase).collect()
+}
+
+/// Groups of pets whose normalized names match, each with more than one pet.
+/// Groups and the pets in them keep the order of `pets`.
+pub fn group_duplicates<'p>(pets: &[&'p Pet]) -> Vec<Vec<&'p Pet>> {
+    let mut groups: Vec<(String, Vec<&Pet>)> = Vec::new();
+    for pet in pets {
+        let key = normalize_name(&pet.name);
+        match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+            Some((_, group)) => group.push(pet),
+            None => groups.push((key, vec![pet])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+/// `survivor` with anything it lacks filled in from `others`: notes,
+/// birthdate, owner, microchip, breed and photo come from the first of them
+/// that has one, medical records and weights are combined, and custom
+/// attributes and unknown fields the survivor doesn't have are added.
+pub fn merge(survivor: &Pet, others: &[&Pet]) -> Pet {
+    let mut merged = survivor.clone();
+    for other in others {
+        fill(&mut merged.notes, &other.notes);
+        fill(&mut merged.birthdate, &other.birthdate);
+        fill(&mut merged.owner_id, &other.owner_id);
+        fill(&mut merged.microchip, &other.microchip);
+        fill(&mut merged.breed, &other.breed);
+        fill(&mut merged.photo_path, &other.photo_path);
+        merged.records.extend(other.records.iter().cloned());
+        merged.weights.extend(other.weights.iter().cloned());
+        for (name, value) in &other.attributes {
+            merged
+                .attributes
+                .entry(name.clone())
+                .or_insert_with(|| value.clone());
+        }
+        for (name, value) in &other.extra {
+            merged
+                .extra
+                .entry(name.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+    merged
+}
+
+fn fill<T: Clone>(field: &mut Option<T>, other: &Option<T>) {
+    if field.is_none() {
+        *field = other.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use crate::weight::WeightEntry;
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    fn names(groups: &[Vec<&Pet>]) -> Vec<Vec<usize>> {
+        groups
+            .iter()
+            .map(|group| group.iter().map(|pet| pet.id).collect())
+            .collect()
+    }
+
+    #[test]
+    fn names_match_across_whitespace_and_case() {
+        assert_eq!(normalize_name("  Coco "), "coco");
+        assert_eq!(normalize_name("REX"), normalize_name("rex"));
+        let pets = [
+            pet(1, "Coco", "cats"),
+            pet(2, "Rex", "dogs"),
+            pet(3, " coco", "cats"),
+            pet(4, "Bun", "rabbits"),
+            pet(5, "COCO  ", "cats"),
+            pet(6, "rex", "dogs"),
+        ];
+        let refs: Vec<&Pet> = pets.iter().collect();
+        assert_eq!(names(&group_duplicates(&refs)), [vec![1, 3, 5], vec![2, 6]]);
+    }
+
+    #[test]
+    fn composed_and_decomposed_names_match() {
+        let composed = pet(1, "Ren\u{e9}e", "cats");
+        let decomposed = pet(2, "Rene\u{301}e", "cats");
+        let wide = pet(3, "\u{ff32}\u{ff45}\u{ff4e}\u{e9}\u{ff45}", "cats");
+        assert_eq!(
+            normalize_name(&composed.name),
+            normalize_name(&decomposed.name)
+        );
+        assert_eq!(
+            names(&group_duplicates(&[&composed, &decomposed, &wide])),
+            [vec![1, 2, 3]]
+        );
+    }
+
+    #[test]
+    fn pets_with_different_names_are_not_grouped() {
+        let pets = [pet(1, "Coco", "cats"), pet(2, "Cocoa", "cats")];
+        let refs: Vec<&Pet> = pets.iter().collect();
+        assert!(group_duplicates(&refs).is_empty());
+        assert!(group_duplicates(&[]).is_empty());
+    }
+
+    #[test]
+    fn merging_keeps_every_field_of_the_duplicates() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let mut survivor = pet(1, "Coco", "cats");
+        survivor.notes = Some("shy".to_owned());
+        survivor.weights = vec![WeightEntry {
+            date: date(1),
+            grams: 4000,
+        }];
+        survivor
+            .attributes
+            .insert("diet".to_owned(), "wet food".to_owned());
+        survivor.extra.insert("vet".to_owned(), json!("Dr. Smith"));
+        let mut other = pet(2, "coco", "cats");
+        other.notes = Some("bites".to_owned());
+        other.microchip = Some("985112345678901".to_owned());
+        other.breed = Some("Siamese".to_owned());
+        other.weights = vec![WeightEntry {
+            date: date(8),
+            grams: 4200,
+        }];
+        other
+            .attributes
+            .insert("diet".to_owned(), "dry food".to_owned());
+        other
+            .attributes
+            .insert("toy".to_owned(), "mouse".to_owned());
+        other.extra.insert("vet".to_owned(), json!("Dr. Ng"));
+        other
+            .extra
+            .insert("insurance".to_owned(), json!({"provider": "PetSure"}));
+
+        let merged = merge(&survivor, &[&other]);
+        assert_eq!(merged.id, 1);
+        assert_eq!(merged.notes.as_deref(), Some("shy"));
+        assert_eq!(merged.microchip.as_deref(), Some("985112345678901"));
+        assert_eq!(merged.breed.as_deref(), Some("Siamese"));
+        let grams: Vec<u32> = merged.weights.iter().map(|entry| entry.grams).collect();
+        assert_eq!(grams, [4000, 4200]);
+        assert_eq!(merged.attributes["diet"], "wet food");
+        assert_eq!(merged.attributes["toy"], "mouse");
+        assert_eq!(merged.extra["vet"], "Dr. Smith");
+        assert_eq!(merged.extra["insurance"], json!({"provider": "PetSure"}));
+    }
+
+    #[test]
+    fn empty_fields_come_from_the_first_duplicate_that_has_them() {
+        let survivor = pet(1, "Coco", "cats");
+        let mut first = pet(2, "Coco", "cats");
+        first.breed = Some("Siamese".to_owned());
+        let mut second = pet(3, "Coco", "cats");
+        second.breed = Some("Persian".to_owned());
+        second.owner_id = Some(4);
+        let merged = merge(&survivor, &[&first, &second]);
+        assert_eq!(merged.breed.as_deref(), Some("Siamese"));
+        assert_eq!(merged.owner_id, Some(4));
+    }
+}
@@ -0,0 +1,325 @@
+//! How hard a write of the DB file tries to survive a crash or a power cut,
+//! set by `write_durability` in the config. Each write goes to a temporary
+//! file next to the DB that is then renamed over it, so a crash leaves the
+//! old DB or the new one, never half of one; what it can still lose is the
+//! write itself, if the system goes down before the data reaches the disk.
+
+use clap::ValueEnum;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum Durability {
+    /// Hands the data to the system and renames, leaving when it reaches
+    /// the disk to the system. Fastest; a power cut can lose the write.
+    None,
+    /// Also waits for the data of the temporary file to be on the disk
+    /// before renaming, but not for its metadata or the rename.
+    #[default]
+    Flush,
+    /// Waits for the whole temporary file to be on the disk before renaming,
+    /// and for the rename after. Slowest, but a write that succeeded is kept
+    /// through a power cut.
+    Fsync,
+}
+
+impl Durability {
+    pub fn name(self) -> &'static str {
+        match self {
+            Durability::None => "none",
+            Durability::Flush => "flush",
+            Durability::Fsync => "fsync",
+        }
+    }
+}
+
+/// Writes `content` to `temp_path` and renames it to `path`, as durably as
+/// `durability` asks. Every level reports an error writing the data, and
+/// leaves the DB at `path` as it was and no temporary file behind then.
+pub fn write_atomic(
+    path: &Path,
+    temp_path: &Path,
+    content: &[u8],
+    durability: Durability,
+) -> io::Result<()> {
+    write_atomic_with(&mut SystemFs, path, temp_path, content, durability)
+}
+
+/// The file system as `write_atomic` uses it, so tests can see each step and
+/// fail any of them.
+trait AtomicFs {
+    type File: Write + SyncFile;
+
+    fn create(&mut self, path: &Path) -> io::Result<Self::File>;
+    fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()>;
+    fn remove_file(&mut self, path: &Path) -> io::Result<()>;
+    fn sync_dir(&mut self, path: &Path) -> io::Result<()>;
+}
+
+trait SyncFile {
+    fn sync_data(&mut self) -> io::Result<()>;
+    fn sync_all(&mut self) -> io::Result<()>;
+}
+
+struct SystemFs;
+
+impl AtomicFs for SystemFs {
+    type File = File;
+
+    fn create(&mut self, path: &Path) -> io::Result<File> {
+        File::create(path)
+    }
+
+    fn rename(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&mut self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn sync_dir(&mut self, path: &Path) -> io::Result<()> {
+        sync_dir(path)
+    }
+}
+
+impl SyncFile for File {
+    fn sync_data(&mut self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+
+    fn sync_all(&mut self) -> io::Result<()> {
+        File::sync_all(self)
+    }
+}
+
+fn write_atomic_with<F: AtomicFs>(
+    fs: &mut F,
+    path: &Path,
+    temp_path: &Path,
+    content: &[u8],
+    durability: Durability,
+) -> io::Result<()> {
+    let written =
+        write_temp(fs, temp_path, content, durability).and_then(|()| fs.rename(temp_path, path));
+    if let Err(err) = written {
+        if let Err(remove_err) = fs.remove_file(temp_path) {
+            log::warn!("could not remove {}: {}", temp_path.display(), remove_err);
+        }
+        return Err(err);
+    }
+    if durability == Durability::Fsync {
+        fs.sync_dir(path)?;
+    }
+    Ok(())
+}
+
+/// Writes the temporary file. The content is written in one go, unbuffered,
+/// so no error can be left for a drop to swallow.
+fn write_temp<F: AtomicFs>(
+    fs: &mut F,
+    temp_path: &Path,
+    content: &[u8],
+    durability: Durability,
+) -> io::Result<()> {
+    let mut file = fs.create(temp_path)?;
+    file.write_all(content)?;
+    match durability {
+        Durability::None => Ok(()),
+        Durability::Flush => file.sync_data(),
+        Durability::Fsync => file.sync_all(),
+    }
+}
+
+/// Waits for the directory entry of the file at `path` to be on the disk.
+/// On Unix a file's data and its name in the directory are synced apart, so
+/// without this the rename can be lost even though the data isn't.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+/// Windows can't open directories as files, and syncs the entry with the
+/// file.
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Records each step as a word, and fails the step named `fail`.
+    #[derive(Clone, Default)]
+    struct MockFs {
+        steps: Rc<RefCell<Vec<&'static str>>>,
+        fail: Option<&'static str>,
+    }
+
+    impl MockFs {
+        fn failing(step: &'static str) -> MockFs {
+            MockFs {
+                fail: Some(step),
+                ..MockFs::default()
+            }
+        }
+
+        fn step(&self, step: &'static str) -> io::Result<()> {
+            self.steps.borrow_mut().push(step);
+            if self.fail == Some(step) {
+                Err(io::Error::other(step))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn steps(&self) -> Vec<&'static str> {
+            self.steps.borrow().clone()
+        }
+    }
+
+    impl Write for MockFs {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.step("write").map(|()| buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SyncFile for MockFs {
+        fn sync_data(&mut self) -> io::Result<()> {
+            self.step("sync_data")
+        }
+
+        fn sync_all(&mut self) -> io::Result<()> {
+            self.step("sync_all")
+        }
+    }
+
+    impl AtomicFs for MockFs {
+        type File = MockFs;
+
+        fn create(&mut self, _path: &Path) -> io::Result<MockFs> {
+            self.step("create").map(|()| self.clone())
+        }
+
+        fn rename(&mut self, _from: &Path, _to: &Path) -> io::Result<()> {
+            self.step("rename")
+        }
+
+        fn remove_file(&mut self, _path: &Path) -> io::Result<()> {
+            self.step("remove")
+        }
+
+        fn sync_dir(&mut self, _path: &Path) -> io::Result<()> {
+            self.step("sync_dir")
+        }
+    }
+
+    fn write(fs: &mut MockFs, durability: Durability) -> io::Result<()> {
+        let path = Path::new("db.json");
+        write_atomic_with(fs, path, Path::new("db.json.tmp"), b"{}", durability)
+    }
+
+    #[test]
+    fn none_writes_and_renames() {
+        let mut fs = MockFs::default();
+        write(&mut fs, Durability::None).unwrap();
+        assert_eq!(fs.steps(), ["create", "write", "rename"]);
+    }
+
+    #[test]
+    fn flush_syncs_the_data_before_renaming() {
+        let mut fs = MockFs::default();
+        write(&mut fs, Durability::Flush).unwrap();
+        assert_eq!(fs.steps(), ["create", "write", "sync_data", "rename"]);
+    }
+
+    #[test]
+    fn fsync_syncs_the_file_and_then_the_directory() {
+        let mut fs = MockFs::default();
+        write(&mut fs, Durability::Fsync).unwrap();
+        assert_eq!(
+            fs.steps(),
+            ["create", "write", "sync_all", "rename", "sync_dir"]
+        );
+    }
+
+    #[test]
+    fn a_failed_write_keeps_the_db_at_every_level() {
+        for durability in [Durability::None, Durability::Flush, Durability::Fsync] {
+            let mut fs = MockFs::failing("write");
+            assert!(write(&mut fs, durability).is_err(), "{:?}", durability);
+            assert_eq!(
+                fs.steps(),
+                ["create", "write", "remove"],
+                "{:?}",
+                durability
+            );
+        }
+    }
+
+    #[test]
+    fn a_failed_sync_keeps_the_db() {
+        let mut fs = MockFs::failing("sync_data");
+        assert!(write(&mut fs, Durability::Flush).is_err());
+        assert_eq!(fs.steps(), ["create", "write", "sync_data", "remove"]);
+        let mut fs = MockFs::failing("sync_all");
+        assert!(write(&mut fs, Durability::Fsync).is_err());
+        assert_eq!(fs.steps(), ["create", "write", "sync_all", "remove"]);
+    }
+
+    #[test]
+    fn a_failed_rename_removes_the_temporary_file() {
+        let mut fs = MockFs::failing("rename");
+        assert!(write(&mut fs, Durability::Fsync).is_err());
+        assert_eq!(
+            fs.steps(),
+            ["create", "write", "sync_all", "rename", "remove"]
+        );
+    }
+
+    #[test]
+    fn a_failed_directory_sync_is_reported() {
+        let mut fs = MockFs::failing("sync_dir");
+        assert!(write(&mut fs, Durability::Fsync).is_err());
+        assert!(!fs.steps().contains(&"remove"));
+    }
+
+    #[test]
+    fn a_failed_create_leaves_nothing_to_remove() {
+        let mut fs = MockFs::failing("create");
+        assert!(write(&mut fs, Durability::None).is_err());
+        assert_eq!(fs.steps(), ["create", "remove"]);
+    }
+
+    #[test]
+    fn writes_to_disk_at_every_level() {
+        let dir = std::env::temp_dir().join(format!("pet-cli-durability-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("db.json");
+        let temp_path = dir.join("db.json.tmp");
+        for durability in [Durability::None, Durability::Flush, Durability::Fsync] {
+            let content = durability.name().as_bytes();
+            write_atomic(&path, &temp_path, content, durability).unwrap();
+            assert_eq!(fs::read(&path).unwrap(), content);
+            assert!(!temp_path.exists());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn syncs_the_working_directory_for_a_bare_file_name() {
+        sync_dir(Path::new("db.json")).unwrap();
+    }
+}
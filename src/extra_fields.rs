@@ -0,0 +1,88 @@
+//! Fields of a pet in the DB file that pet-cli doesn't know, like a
+//! `"vet": "Dr. Smith"` added by hand. They are kept as they are when the pet
+//! is written back, shown read-only in the detail pane and exported with
+//! `export_extra_fields`, unless `--strict-schema` refuses them.
+
+use crate::Pet;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Every extra field name of `pets`, sorted, for the columns of an export.
+pub fn keys<'p>(pets: &[&'p Pet]) -> Vec<&'p str> {
+    let keys: BTreeSet<&str> = pets
+        .iter()
+        .flat_map(|pet| pet.extra.keys().map(String::as_str))
+        .collect();
+    keys.into_iter().collect()
+}
+
+/// A value as one line of text: strings without their quotes, anything else
+/// as compact JSON.
+pub fn display(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// The pets with their extra fields, for `--strict-schema` to refuse, as
+/// `pet #3: vet, color; pet #8: tattoo`. `None` if no pet has any.
+pub fn describe(pets: &[Pet]) -> Option<String> {
+    let records: Vec<String> = pets
+        .iter()
+        .filter(|pet| !pet.extra.is_empty())
+        .map(|pet| {
+            let names: Vec<&str> = pet.extra.keys().map(String::as_str).collect();
+            format!("pet #{}: {}", pet.id, names.join(", "))
+        })
+        .collect();
+    (!records.is_empty()).then(|| records.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use serde_json::json;
+
+    fn with_extra(id: usize, extra: Value) -> Pet {
+        let mut pet = pet(id, &format!("Pet{}", id), "dogs");
+        pet.extra = extra.as_object().unwrap().clone();
+        pet
+    }
+
+    #[test]
+    fn keys_are_every_pets_sorted_once() {
+        let coco = with_extra(1, json!({"vet": "Dr. Smith", "color": "black"}));
+        let rex = with_extra(2, json!({"vet": "Dr. Ng", "tattoo": 12}));
+        let bun = pet(3, "Bun", "rabbits");
+        assert_eq!(keys(&[&coco, &rex, &bun]), ["color", "tattoo", "vet"]);
+        assert!(keys(&[&bun]).is_empty());
+    }
+
+    #[test]
+    fn values_are_shown_on_one_line() {
+        assert_eq!(display(&json!("Dr. Smith")), "Dr. Smith");
+        assert_eq!(display(&json!(2500.5)), "2500.5");
+        assert_eq!(display(&json!(null)), "null");
+        assert_eq!(
+            display(&json!({"provider": "PetSure", "doses": [1, 2]})),
+            "{\"doses\":[1,2],\"provider\":\"PetSure\"}"
+        );
+    }
+
+    #[test]
+    fn describe_lists_the_fields_per_pet() {
+        let pets = [
+            with_extra(3, json!({"vet": "Dr. Smith", "color": "black"})),
+            pet(5, "Rex", "dogs"),
+            with_extra(8, json!({"tattoo": "A12"})),
+        ];
+        assert_eq!(
+            describe(&pets).unwrap(),
+            "pet #3: color, vet; pet #8: tattoo"
+        );
+        assert_eq!(describe(&pets[1..2]), None);
+        assert_eq!(describe(&[]), None);
+    }
+}
@@ -0,0 +1,294 @@
+//! The fields of a pet as the detail pane shows them, in its order. `pet-cli
+//! list --fields` exports the same fields and the pet list can show some of
+//! them as columns, so whatever the detail pane gains can be exported too.
+
+use crate::strings::{self, Lang, Msg};
+use crate::{dates, weight, Owner, Pet};
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// The columns the pet list can show. The name is always among them.
+pub const LIST_COLUMNS: [PetField; 6] = [
+    PetField::Name,
+    PetField::Category,
+    PetField::Age,
+    PetField::Status,
+    PetField::Owner,
+    PetField::Created,
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PetField {
+    Id,
+    Name,
+    Category,
+    Breed,
+    Status,
+    Age,
+    Birthdate,
+    Owner,
+    Created,
+    Records,
+    Microchip,
+    Photo,
+    Weight,
+}
+
+impl PetField {
+    pub const ALL: [PetField; 13] = [
+        PetField::Id,
+        PetField::Name,
+        PetField::Category,
+        PetField::Breed,
+        PetField::Status,
+        PetField::Age,
+        PetField::Birthdate,
+        PetField::Owner,
+        PetField::Created,
+        PetField::Records,
+        PetField::Microchip,
+        PetField::Photo,
+        PetField::Weight,
+    ];
+
+    pub fn label(self) -> Msg {
+        match self {
+            PetField::Id => Msg::FieldId,
+            PetField::Name => Msg::FieldName,
+            PetField::Category => Msg::FieldCategory,
+            PetField::Breed => Msg::FieldBreed,
+            PetField::Status => Msg::FieldStatus,
+            PetField::Age => Msg::FieldAge,
+            PetField::Birthdate => Msg::FieldBirthdate,
+            PetField::Owner => Msg::FieldOwner,
+            PetField::Created => Msg::FieldCreated,
+            PetField::Records => Msg::FieldRecords,
+            PetField::Microchip => Msg::FieldMicrochip,
+            PetField::Photo => Msg::FieldPhoto,
+            PetField::Weight => Msg::FieldWeight,
+        }
+    }
+
+    /// Whether the field's column is aligned to the right, so that numbers
+    /// of different lengths line up. Ages count as numbers.
+    pub fn right_aligned(self) -> bool {
+        matches!(
+            self,
+            PetField::Id | PetField::Age | PetField::Records | PetField::Weight
+        )
+    }
+
+    /// The name `--fields` takes, and the key in JSON exports.
+    pub fn key(self) -> &'static str {
+        match self {
+            PetField::Id => "id",
+            PetField::Name => "name",
+            PetField::Category => "category",
+            PetField::Breed => "breed",
+            PetField::Status => "status",
+            PetField::Age => "age",
+            PetField::Birthdate => "birthdate",
+            PetField::Owner => "owner",
+            PetField::Created => "created",
+            PetField::Records => "records",
+            PetField::Microchip => "microchip",
+            PetField::Photo => "photo",
+            PetField::Weight => "weight",
+        }
+    }
+
+    /// What the detail pane shows for `pet`, or `None` if the pet doesn't
+    /// have the field. A pet without medical records has none.
+    pub fn value(
+        self,
+        pet: &Pet,
+        owners: &[Owner],
+        now: DateTime<Utc>,
+        lang: Lang,
+    ) -> Option<String> {
+        match self {
+            PetField::Id => Some(pet.id.to_string()),
+            PetField::Name => Some(pet.name.clone()),
+            PetField::Category => Some(pet.category.clone()),
+            PetField::Breed => pet.breed.clone(),
+            PetField::Status => Some(pet.status.to_string()),
+            PetField::Age => Some(pet.display_age(now.with_timezone(&Local).date_naive())),
+            PetField::Birthdate => pet
+                .birthdate
+                .map(|birthdate| birthdate.format("%Y-%m-%d").to_string()),
+            PetField::Owner => pet.owner_id.map(|owner_id| {
+                match owners.iter().find(|owner| owner.id == owner_id) {
+                    Some(owner) => owner.name.clone(),
+                    None => format!("#{} ({})", owner_id, strings::text(lang, Msg::UnknownOwner)),
+                }
+            }),
+            PetField::Created => Some(dates::format_local(pet.created_at)),
+            PetField::Records => (!pet.records.is_empty()).then(|| pet.records.len().to_string()),
+            PetField::Microchip => pet.microchip.clone(),
+            PetField::Photo => pet
+                .photo_path
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            PetField::Weight => pet
+                .weights
+                .iter()
+                .max_by_key(|entry| entry.date)
+                .map(|entry| {
+                    format!(
+                        "{} on {}",
+                        weight::format(entry.grams),
+                        entry.date.format("%Y-%m-%d")
+                    )
+                }),
+        }
+    }
+}
+
+/// Columns written like `name,status`, as in the config. The name must be
+/// among them.
+pub fn parse_list_columns(text: &str) -> Result<Vec<PetField>, String> {
+    let mut columns = Vec::new();
+    for key in text.split(',').map(str::trim) {
+        let column = list_column(key)?;
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+    if !columns.contains(&PetField::Name) {
+        return Err("the name column can't be left out".to_owned());
+    }
+    Ok(columns)
+}
+
+fn list_column(key: &str) -> Result<PetField, String> {
+    LIST_COLUMNS
+        .iter()
+        .find(|field| field.key() == key)
+        .copied()
+        .ok_or_else(|| {
+            let keys: Vec<_> = LIST_COLUMNS.iter().map(|field| field.key()).collect();
+            format!("valid columns are: {}", keys.join(", "))
+        })
+}
+
+pub fn format_list_columns(columns: &[PetField]) -> String {
+    let keys: Vec<_> = columns.iter().map(|field| field.key()).collect();
+    keys.join(",")
+}
+
+/// Widths written like `category=8,created=10`, as in the config; empty for
+/// none.
+pub fn parse_column_widths(text: &str) -> Result<Vec<(PetField, usize)>, String> {
+    let mut widths: Vec<(PetField, usize)> = Vec::new();
+    for entry in text
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (key, width) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("expected column=width, not '{}'", entry))?;
+        let column = list_column(key.trim())?;
+        let width = match width.trim().parse() {
+            Ok(width) if width > 0 => width,
+            _ => return Err(format!("expected a width of at least 1 for {}", key.trim())),
+        };
+        widths.retain(|(other, _)| *other != column);
+        widths.push((column, width));
+    }
+    Ok(widths)
+}
+
+pub fn format_column_widths(widths: &[(PetField, usize)]) -> String {
+    let entries: Vec<_> = widths
+        .iter()
+        .map(|(column, width)| format!("{}={}", column.key(), width))
+        .collect();
+    entries.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_columns_keep_their_order_once_each() {
+        assert_eq!(
+            parse_list_columns("status, name,status,owner").unwrap(),
+            [PetField::Status, PetField::Name, PetField::Owner]
+        );
+        let columns = [PetField::Created, PetField::Name, PetField::Age];
+        assert_eq!(
+            parse_list_columns(&format_list_columns(&columns)).unwrap(),
+            columns
+        );
+    }
+
+    #[test]
+    fn list_columns_need_the_name() {
+        assert_eq!(
+            parse_list_columns("status,age").unwrap_err(),
+            "the name column can't be left out"
+        );
+    }
+
+    #[test]
+    fn an_unknown_list_column_lists_the_valid_ones() {
+        // Breed is a field but not a column of the list.
+        for text in ["name,colour", "name,breed"] {
+            assert_eq!(
+                parse_list_columns(text).unwrap_err(),
+                "valid columns are: name, category, age, status, owner, created"
+            );
+        }
+    }
+
+    #[test]
+    fn column_widths_are_read_and_written_back() {
+        assert_eq!(parse_column_widths(" ").unwrap(), []);
+        let widths = parse_column_widths("category = 8, created=10,").unwrap();
+        assert_eq!(widths, [(PetField::Category, 8), (PetField::Created, 10)]);
+        assert_eq!(format_column_widths(&widths), "category=8,created=10");
+        assert_eq!(
+            parse_column_widths("name=5,name=7").unwrap(),
+            [(PetField::Name, 7)]
+        );
+    }
+
+    #[test]
+    fn invalid_column_widths_say_what_is_wrong() {
+        assert_eq!(
+            parse_column_widths("category").unwrap_err(),
+            "expected column=width, not 'category'"
+        );
+        assert_eq!(
+            parse_column_widths("colour=4").unwrap_err(),
+            "valid columns are: name, category, age, status, owner, created"
+        );
+        for text in ["age=0", "age=-1", "age=wide", "age="] {
+            assert_eq!(
+                parse_column_widths(text).unwrap_err(),
+                "expected a width of at least 1 for age",
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn every_field_has_its_own_key() {
+        for (index, field) in PetField::ALL.iter().enumerate() {
+            assert!(PetField::ALL[..index]
+                .iter()
+                .all(|other| other.key() != field.key()));
+            assert_eq!(
+                PetField::from_str(field.key(), false).as_ref(),
+                Ok(field),
+                "{}",
+                field.key()
+            );
+        }
+    }
+}
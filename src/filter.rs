@@ -0,0 +1,51 @@
+use crate::Pet;
+use std::collections::BTreeSet;
+
+/// Restricts the Pets view to a single category. `None` shows every pet.
+#[derive(Default)]
+pub struct CategoryFilter {
+    category: Option<String>,
+}
+
+impl CategoryFilter {
+    /// Moves to the next category present in `pets`, wrapping around through
+    /// "All" after the last one.
+    pub fn cycle(&mut self, pets: &[Pet]) {
+        let categories = categories(pets);
+        self.category = match &self.category {
+            None => categories.into_iter().next(),
+            Some(current) => categories
+                .into_iter()
+                .skip_while(|category| category != current)
+                .nth(1),
+        };
+    }
+
+    /// Shows only `category`, or every pet for `None`.
+    pub fn only(category: Option<String>) -> Self {
+        CategoryFilter { category }
+    }
+
+    pub fn category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+
+    pub fn matches(&self, pet: &Pet) -> bool {
+        self.category
+            .as_ref()
+            .is_none_or(|category| &pet.category == category)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.category.is_some()
+    }
+
+    pub fn label(&self) -> &str {
+        self.category.as_deref().unwrap_or("All")
+    }
+}
+
+/// The distinct categories in `pets`, sorted.
+pub fn categories(pets: &[Pet]) -> BTreeSet<String> {
+    pets.iter().map(|pet| pet.category.clone()).collect()
+}
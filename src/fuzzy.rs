@@ -0,0 +1,100 @@
+/// Ranks `candidates` against `query`, best match first. A candidate matches
+/// if the query's characters appear in it in order, ignoring case; runs of
+/// consecutive characters and matches at the start of words score higher.
+/// Returns `(index into candidates, score)` pairs, ties broken by index.
+pub fn fuzzy_rank(query: &str, candidates: &[&str]) -> Vec<(usize, i64)> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let mut ranked: Vec<(usize, i64)> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| score(&query, candidate).map(|score| (index, score)))
+        .collect();
+    ranked.sort_by(|(a_index, a_score), (b_index, b_score)| {
+        b_score.cmp(a_score).then(a_index.cmp(b_index))
+    });
+    ranked
+}
+
+fn score(query: &[char], candidate: &str) -> Option<i64> {
+    let mut query_chars = query.iter().peekable();
+    let mut score = 0;
+    let mut previous_matched = false;
+    let mut previous_char: Option<char> = None;
+    for c in candidate.chars() {
+        let Some(&&wanted) = query_chars.peek() else {
+            break;
+        };
+        let matched = c.to_lowercase().eq(std::iter::once(wanted));
+        if matched {
+            query_chars.next();
+            score += 1;
+            if previous_matched {
+                score += 5;
+            }
+            if previous_char.is_none_or(|previous| !previous.is_alphanumeric()) {
+                score += 10;
+            }
+        } else {
+            score -= 1;
+        }
+        previous_matched = matched;
+        previous_char = Some(c);
+    }
+    if query_chars.peek().is_some() {
+        None
+    } else {
+        Some(score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranked<'a>(query: &str, candidates: &[&'a str]) -> Vec<&'a str> {
+        fuzzy_rank(query, candidates)
+            .into_iter()
+            .map(|(index, _)| candidates[index])
+            .collect()
+    }
+
+    #[test]
+    fn runs_and_word_starts_rank_first() {
+        let candidates = ["Mr Biscuits", "Bella", "Bob", "Rabbit"];
+        assert_eq!(ranked("bi", &candidates), ["Mr Biscuits", "Rabbit"]);
+        assert_eq!(
+            ranked("b", &candidates),
+            ["Bella", "Bob", "Mr Biscuits", "Rabbit"]
+        );
+    }
+
+    #[test]
+    fn ties_keep_candidate_order() {
+        assert_eq!(
+            fuzzy_rank("rex", &["Rex", "rex", "REX"]),
+            [(0, 23), (1, 23), (2, 23)]
+        );
+    }
+
+    #[test]
+    fn case_is_ignored_on_both_sides() {
+        assert_eq!(
+            fuzzy_rank("FLUF", &["fluffy"]),
+            fuzzy_rank("fluf", &["FLUFFY"])
+        );
+        assert_eq!(ranked("ÉCLAIR", &["éclair"]), ["éclair"]);
+    }
+
+    #[test]
+    fn queries_out_of_order_or_absent_match_nothing() {
+        assert!(fuzzy_rank("xz", &["Rex", "Fluffy"]).is_empty());
+        assert!(fuzzy_rank("xe", &["Rex"]).is_empty());
+        assert!(fuzzy_rank("rexy", &["Rex"]).is_empty());
+        assert!(fuzzy_rank("a", &[]).is_empty());
+    }
+
+    #[test]
+    fn an_empty_query_matches_everything() {
+        assert_eq!(ranked("", &["Rex", "Fluffy"]), ["Rex", "Fluffy"]);
+    }
+}
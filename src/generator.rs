@@ -0,0 +1,279 @@
+use crate::age::Age;
+use crate::locales::Locale;
+use crate::pet_status::PetStatus;
+use crate::Pet;
+use chrono::prelude::*;
+use rand::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// How generated pets are named: from a pool per species, those of the
+/// locale or of a file, and optionally never like a pet already in the DB.
+#[derive(Clone, Debug, Default)]
+pub struct Names {
+    /// Pools from a file, which replace the locale's pool of their species.
+    custom: HashMap<String, Vec<String>>,
+    /// Where the categories come from, and the names of those without a
+    /// pool from a file.
+    pub locale: Locale,
+    /// Whether a taken name gets a number appended, like `Rex 2`.
+    pub unique: bool,
+}
+
+impl Names {
+    /// Reads a JSON object of name lists by species, like
+    /// `{"cats": ["Whiskers", "Tom"]}`.
+    pub fn load(path: &Path) -> Result<Names, String> {
+        let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let custom: HashMap<String, Vec<String>> =
+            serde_json::from_str(&content).map_err(|err| err.to_string())?;
+        if let Some((species, _)) = custom.iter().find(|(_, names)| names.is_empty()) {
+            return Err(format!("the names of {} are empty", species));
+        }
+        Ok(Names {
+            custom,
+            ..Names::default()
+        })
+    }
+
+    /// The pool of `species` from the file, matched ignoring case and a
+    /// plural `s`, or else the locale's.
+    fn pool(&self, species: &str) -> &[String] {
+        let key = |species: &str| species.to_lowercase().trim_end_matches('s').to_owned();
+        let wanted = key(species);
+        match self.custom.iter().find(|(name, _)| key(name) == wanted) {
+            Some((_, names)) => names,
+            None => self.locale.pool(species),
+        }
+    }
+
+    /// A random name for a pet of `species`. A name in `existing` gets the
+    /// lowest number from 2 on that makes it new.
+    pub fn generate_name(
+        &self,
+        rng: &mut impl Rng,
+        species: &str,
+        existing: &HashSet<String>,
+    ) -> String {
+        let name = self.pool(species).choose(rng).expect("pools are not empty");
+        if !existing.contains(name) {
+            return name.to_owned();
+        }
+        (2..)
+            .map(|number| format!("{} {}", name, number))
+            .find(|numbered| !existing.contains(numbered))
+            .expect("some number is free")
+    }
+}
+
+/// Makes `count` fake pets with ids counting up from `first_id`, named
+/// unlike the pets in `existing` if `names` are unique. The same rng state
+/// and arguments always give the same pets.
+pub fn generate(
+    rng: &mut impl Rng,
+    count: usize,
+    first_id: usize,
+    created_at: DateTime<Utc>,
+    names: &Names,
+    existing: &[Pet],
+) -> Vec<Pet> {
+    let mut taken: HashSet<String> = if names.unique {
+        existing.iter().map(|pet| pet.name.clone()).collect()
+    } else {
+        HashSet::new()
+    };
+    (first_id..first_id + count)
+        .map(|id| {
+            let category = &names
+                .locale
+                .categories
+                .choose(rng)
+                .expect("categories are not empty")
+                .name;
+            let name = names.generate_name(rng, category, &taken);
+            if names.unique {
+                taken.insert(name.clone());
+            }
+            Pet {
+                id,
+                name,
+                category: category.clone(),
+                age: Age {
+                    months: rng.gen_range(1, 180),
+                },
+                created_at,
+                notes: None,
+                birthdate: None,
+                owner_id: None,
+                records: Vec::new(),
+                weights: Vec::new(),
+                microchip: None,
+                breed: None,
+                status: PetStatus::default(),
+                photo_path: None,
+                attributes: BTreeMap::new(),
+                archived_at: None,
+                extra: Default::default(),
+            }
+        })
+        .collect()
+}
+
+pub fn seeded_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locales::Category;
+
+    fn names(custom: &[(&str, &[&str])]) -> Names {
+        Names {
+            custom: custom
+                .iter()
+                .map(|(species, names)| {
+                    let names = names.iter().map(|name| (*name).to_owned()).collect();
+                    ((*species).to_owned(), names)
+                })
+                .collect(),
+            ..Names::default()
+        }
+    }
+
+    fn taken(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| (*name).to_owned()).collect()
+    }
+
+    #[test]
+    fn a_free_name_is_used_as_it_is() {
+        let names = names(&[("cats", &["Whiskers"])]);
+        let mut rng = seeded_rng(Some(1));
+        let name = names.generate_name(&mut rng, "cats", &taken(&["Rex", "Whiskers 2"]));
+        assert_eq!(name, "Whiskers");
+    }
+
+    #[test]
+    fn a_taken_name_gets_the_lowest_free_number() {
+        let names = names(&[("cats", &["Whiskers"])]);
+        let mut rng = seeded_rng(Some(1));
+        let existing = taken(&["Whiskers"]);
+        assert_eq!(
+            names.generate_name(&mut rng, "cats", &existing),
+            "Whiskers 2"
+        );
+        let existing = taken(&["Whiskers", "Whiskers 2", "Whiskers 4"]);
+        assert_eq!(
+            names.generate_name(&mut rng, "cats", &existing),
+            "Whiskers 3"
+        );
+    }
+
+    #[test]
+    fn a_species_without_a_pool_gets_the_general_names() {
+        let mut names = names(&[("cats", &["Whiskers"])]);
+        names.locale = Locale {
+            names: vec!["Luna".to_owned()],
+            categories: vec![Category {
+                name: "dogs".to_owned(),
+                names: Vec::new(),
+            }],
+        };
+        let mut rng = seeded_rng(Some(1));
+        for species in ["dogs", "axolotls"] {
+            assert_eq!(
+                names.generate_name(&mut rng, species, &HashSet::new()),
+                "Luna"
+            );
+        }
+    }
+
+    #[test]
+    fn the_built_in_pools_have_names_for_each_species() {
+        let names = Names::default();
+        let mut rng = seeded_rng(Some(7));
+        for category in &names.locale.categories {
+            let name = names.generate_name(&mut rng, &category.name, &HashSet::new());
+            assert!(names.pool(&category.name).contains(&name));
+            assert!(!name.is_empty());
+        }
+    }
+
+    #[test]
+    fn pools_from_a_file_match_ignoring_case_and_plurals() {
+        let names = names(&[("Dog", &["Biscuit"])]);
+        let mut rng = seeded_rng(Some(1));
+        for species in ["dogs", "DOGS", "dog"] {
+            assert_eq!(
+                names.generate_name(&mut rng, species, &HashSet::new()),
+                "Biscuit"
+            );
+        }
+        assert_ne!(names.pool("cats"), ["Biscuit".to_owned()]);
+    }
+
+    #[test]
+    fn loading_a_file_rejects_empty_pools() {
+        let path = std::env::temp_dir().join(format!("pet-cli-names-{}.json", std::process::id()));
+        fs::write(&path, r#"{"cats": ["Tom"], "dogs": []}"#).unwrap();
+        assert_eq!(
+            Names::load(&path).map(|_| ()),
+            Err("the names of dogs are empty".to_owned())
+        );
+        fs::write(&path, r#"{"cats": ["Tom"]}"#).unwrap();
+        let names = Names::load(&path).unwrap();
+        assert_eq!(names.pool("cats"), ["Tom".to_owned()]);
+        fs::remove_file(&path).unwrap();
+        assert!(Names::load(&path).is_err());
+    }
+
+    #[test]
+    fn unique_names_are_never_repeated_or_taken() {
+        let names = Names {
+            unique: true,
+            ..names(&[
+                ("cats", &["Tom"]),
+                ("dogs", &["Rex"]),
+                ("rabbits", &["Bun"]),
+            ])
+        };
+        let existing = vec![crate::ui_tests::pet(1, "Rex", "dogs")];
+        let created_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let pets = generate(
+            &mut seeded_rng(Some(3)),
+            40,
+            2,
+            created_at,
+            &names,
+            &existing,
+        );
+        let generated: HashSet<&str> = pets.iter().map(|pet| pet.name.as_str()).collect();
+        assert_eq!(generated.len(), 40);
+        assert!(!generated.contains("Rex"));
+        assert_eq!(pets.first().map(|pet| pet.id), Some(2));
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_pets() {
+        let created_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let pets = |seed| {
+            generate(
+                &mut seeded_rng(Some(seed)),
+                5,
+                1,
+                created_at,
+                &Names::default(),
+                &[],
+            )
+            .into_iter()
+            .map(|pet| (pet.name, pet.category, pet.age.months))
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(pets(9), pets(9));
+        assert_ne!(pets(9), pets(10));
+    }
+}
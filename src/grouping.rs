@@ -0,0 +1,280 @@
+//! The grouped Pets list (`zg`): the shown pets under a header row per
+//! category, like `— cats (12) —`. `za` folds the group of the selected pet
+//! into its header and unfolds it again, as does `Enter` on a folded header.
+//!
+//! The selection only ever rests on a pet or on a folded header, which
+//! stands in for its pets; the header of an unfolded group is skipped.
+
+use crate::Pet;
+use std::collections::HashSet;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ListRow {
+    /// `count` is of the group's shown pets, folded or not.
+    Header {
+        category: String,
+        count: usize,
+        folded: bool,
+    },
+    /// The pet at this index of the shown pets.
+    Pet(usize),
+}
+
+impl ListRow {
+    pub fn selectable(&self) -> bool {
+        matches!(self, ListRow::Pet(_) | ListRow::Header { folded: true, .. })
+    }
+}
+
+/// Puts the pets of each category together, ordered by category, keeping
+/// the order of the pets within a category.
+pub fn order(pets: &mut [&Pet]) {
+    pets.sort_by(|a, b| a.category.cmp(&b.category));
+}
+
+/// One pet row per pet, for the list when it isn't grouped.
+pub fn flat(len: usize) -> Vec<ListRow> {
+    (0..len).map(ListRow::Pet).collect()
+}
+
+/// The rows of the grouped list, given the categories of the shown pets in
+/// the order `order` put them in: a header before each category's pets,
+/// which are left out if the category is in `folded`.
+pub fn rows(categories: &[&str], folded: &HashSet<String>) -> Vec<ListRow> {
+    let mut rows = Vec::with_capacity(categories.len());
+    let mut start = 0;
+    while start < categories.len() {
+        let category = categories[start];
+        let count = categories[start..]
+            .iter()
+            .take_while(|other| **other == category)
+            .count();
+        let is_folded = folded.contains(category);
+        rows.push(ListRow::Header {
+            category: category.to_owned(),
+            count,
+            folded: is_folded,
+        });
+        if !is_folded {
+            rows.extend((start..start + count).map(ListRow::Pet));
+        }
+        start += count;
+    }
+    rows
+}
+
+/// The row of the pet at `index` of the shown pets, or of its folded
+/// header.
+pub fn row_of_pet(rows: &[ListRow], categories: &[&str], index: usize) -> Option<usize> {
+    rows.iter()
+        .position(|row| *row == ListRow::Pet(index))
+        .or_else(|| {
+            let category = categories.get(index)?;
+            row_of_header(rows, category)
+        })
+}
+
+pub fn row_of_header(rows: &[ListRow], category: &str) -> Option<usize> {
+    rows.iter().position(
+        |row| matches!(row, ListRow::Header { category: header, .. } if header == category),
+    )
+}
+
+/// The selectable row `steps` selectable rows after (or before) `from`,
+/// stopping at the ends, or past them to the other end with `wrap`. From
+/// no row, the first selectable one.
+pub fn step(
+    rows: &[ListRow],
+    from: Option<usize>,
+    forward: bool,
+    steps: usize,
+    wrap: bool,
+) -> Option<usize> {
+    let selectable: Vec<usize> = (0..rows.len())
+        .filter(|&row| rows[row].selectable())
+        .collect();
+    let last = selectable.len().checked_sub(1)?;
+    let Some(from) = from else {
+        return Some(selectable[0]);
+    };
+    // Where `from` is, or would go, among the selectable rows.
+    let at = selectable.partition_point(|&row| row < from);
+    let on_selectable = selectable.get(at) == Some(&from);
+    let next = if forward {
+        let skipped = if on_selectable { 0 } else { 1 };
+        match (at + steps).checked_sub(skipped) {
+            Some(next) if next <= last => next,
+            _ if wrap && on_selectable => 0,
+            _ => last,
+        }
+    } else {
+        match at.checked_sub(steps) {
+            Some(next) => next,
+            None if wrap && on_selectable => last,
+            None => 0,
+        }
+    };
+    Some(selectable[next])
+}
+
+/// The first or last selectable row.
+pub fn end(rows: &[ListRow], last: bool) -> Option<usize> {
+    let mut selectable = (0..rows.len()).filter(|&row| rows[row].selectable());
+    if last {
+        selectable.next_back()
+    } else {
+        selectable.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(category: &str, count: usize, folded: bool) -> ListRow {
+        ListRow::Header {
+            category: category.to_owned(),
+            count,
+            folded,
+        }
+    }
+
+    fn folded(categories: &[&str]) -> HashSet<String> {
+        categories
+            .iter()
+            .map(|category| category.to_string())
+            .collect()
+    }
+
+    const CATEGORIES: [&str; 4] = ["cats", "cats", "dogs", "rabbits"];
+
+    /// cats unfolded, dogs folded, rabbits unfolded; the selectable rows
+    /// are 1, 2, 3 and 5.
+    fn dogs_folded() -> Vec<ListRow> {
+        rows(&CATEGORIES, &folded(&["dogs"]))
+    }
+
+    #[test]
+    fn order_groups_categories_and_keeps_the_order_within_them() {
+        let pets = [
+            crate::ui_tests::pet(1, "Rex", "dogs"),
+            crate::ui_tests::pet(2, "Coco", "cats"),
+            crate::ui_tests::pet(3, "Max", "dogs"),
+            crate::ui_tests::pet(4, "Ace", "cats"),
+        ];
+        let mut shown: Vec<&Pet> = pets.iter().collect();
+        order(&mut shown);
+        let ids: Vec<usize> = shown.iter().map(|pet| pet.id).collect();
+        assert_eq!(ids, [2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn each_category_gets_a_header_before_its_pets() {
+        assert_eq!(
+            rows(&CATEGORIES, &HashSet::new()),
+            [
+                header("cats", 2, false),
+                ListRow::Pet(0),
+                ListRow::Pet(1),
+                header("dogs", 1, false),
+                ListRow::Pet(2),
+                header("rabbits", 1, false),
+                ListRow::Pet(3),
+            ]
+        );
+        assert_eq!(rows(&[], &HashSet::new()), []);
+        assert_eq!(flat(3), [ListRow::Pet(0), ListRow::Pet(1), ListRow::Pet(2)]);
+    }
+
+    #[test]
+    fn a_folded_group_keeps_only_its_header_and_count() {
+        assert_eq!(
+            dogs_folded(),
+            [
+                header("cats", 2, false),
+                ListRow::Pet(0),
+                ListRow::Pet(1),
+                header("dogs", 1, true),
+                header("rabbits", 1, false),
+                ListRow::Pet(3),
+            ]
+        );
+        assert_eq!(
+            rows(&CATEGORIES, &folded(&["cats", "dogs", "rabbits", "birds"])),
+            [
+                header("cats", 2, true),
+                header("dogs", 1, true),
+                header("rabbits", 1, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_pets_and_folded_headers_are_selectable() {
+        let selectable: Vec<bool> = dogs_folded().iter().map(ListRow::selectable).collect();
+        assert_eq!(selectable, [false, true, true, true, false, true]);
+    }
+
+    #[test]
+    fn a_pet_in_a_folded_group_is_found_at_its_header() {
+        let rows = dogs_folded();
+        assert_eq!(row_of_pet(&rows, &CATEGORIES, 0), Some(1));
+        assert_eq!(row_of_pet(&rows, &CATEGORIES, 2), Some(3));
+        assert_eq!(row_of_pet(&rows, &CATEGORIES, 3), Some(5));
+        assert_eq!(row_of_pet(&rows, &CATEGORIES, 4), None);
+        assert_eq!(row_of_header(&rows, "rabbits"), Some(4));
+        assert_eq!(row_of_header(&rows, "birds"), None);
+    }
+
+    #[test]
+    fn steps_skip_unfolded_headers() {
+        let rows = dogs_folded();
+        assert_eq!(step(&rows, None, true, 1, false), Some(1));
+        assert_eq!(step(&rows, Some(1), true, 1, false), Some(2));
+        assert_eq!(step(&rows, Some(2), true, 1, false), Some(3));
+        assert_eq!(step(&rows, Some(3), true, 1, false), Some(5));
+        assert_eq!(step(&rows, Some(5), false, 1, false), Some(3));
+        assert_eq!(step(&rows, Some(1), true, 2, false), Some(3));
+        assert_eq!(step(&rows, Some(5), false, 3, false), Some(1));
+    }
+
+    #[test]
+    fn steps_stop_at_the_ends_or_wrap() {
+        let rows = dogs_folded();
+        assert_eq!(step(&rows, Some(5), true, 1, false), Some(5));
+        assert_eq!(step(&rows, Some(5), true, 1, true), Some(1));
+        assert_eq!(step(&rows, Some(1), false, 1, false), Some(1));
+        assert_eq!(step(&rows, Some(1), false, 1, true), Some(5));
+        assert_eq!(step(&rows, Some(2), true, 10, false), Some(5));
+        assert_eq!(step(&rows, Some(2), false, 10, false), Some(1));
+    }
+
+    /// After a fold or a filter the selection can be left on a row that
+    /// isn't selectable; a step then counts from where it would be.
+    #[test]
+    fn steps_from_an_unselectable_row_land_on_its_neighbours() {
+        let rows = dogs_folded();
+        assert_eq!(step(&rows, Some(4), true, 1, false), Some(5));
+        assert_eq!(step(&rows, Some(4), false, 1, false), Some(3));
+        assert_eq!(step(&rows, Some(0), true, 1, true), Some(1));
+        assert_eq!(step(&rows, Some(0), false, 1, true), Some(1));
+        assert_eq!(step(&rows, Some(9), false, 1, false), Some(5));
+    }
+
+    #[test]
+    fn the_ends_are_the_first_and_last_selectable_rows() {
+        let dogs_folded = dogs_folded();
+        assert_eq!(end(&dogs_folded, false), Some(1));
+        assert_eq!(end(&dogs_folded, true), Some(5));
+        let all_folded = rows(&CATEGORIES, &folded(&["cats", "dogs", "rabbits"]));
+        assert_eq!(end(&all_folded, false), Some(0));
+        assert_eq!(step(&all_folded, Some(0), true, 1, false), Some(1));
+    }
+
+    #[test]
+    fn nothing_is_selected_without_rows() {
+        assert_eq!(step(&[], None, true, 1, true), None);
+        assert_eq!(step(&[], Some(0), false, 1, true), None);
+        assert_eq!(end(&[], true), None);
+    }
+}
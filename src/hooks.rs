@@ -0,0 +1,313 @@
+//! Shell commands from the config file that run after the UI writes the DB,
+//! like `on_any_write = "git -C data commit -qam pets"`. They run in the
+//! background with the event in the environment; what they print goes to the
+//! Log tab, and a failure is reported but leaves the write in place.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a running hook is checked on.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HookEvent {
+    Add,
+    Delete,
+    Update,
+}
+
+impl HookEvent {
+    /// The value of `PET_CLI_EVENT`.
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::Add => "add",
+            HookEvent::Delete => "delete",
+            HookEvent::Update => "update",
+        }
+    }
+}
+
+/// The configured commands, `None` where there is none.
+#[derive(Clone, Debug, Default)]
+pub struct Hooks {
+    pub on_add: Option<String>,
+    pub on_delete: Option<String>,
+    pub on_update: Option<String>,
+    pub on_any_write: Option<String>,
+    /// After which a hook is killed.
+    pub timeout: Duration,
+}
+
+impl Hooks {
+    /// The hooks for `event` by their config key, the one for the event
+    /// before `on_any_write`.
+    pub fn commands(&self, event: HookEvent) -> Vec<(&'static str, &str)> {
+        let specific = match event {
+            HookEvent::Add => ("on_add", &self.on_add),
+            HookEvent::Delete => ("on_delete", &self.on_delete),
+            HookEvent::Update => ("on_update", &self.on_update),
+        };
+        [specific, ("on_any_write", &self.on_any_write)]
+            .iter()
+            .filter_map(|(key, command)| Some((*key, command.as_deref()?)))
+            .collect()
+    }
+}
+
+/// What a write was, for its hooks.
+#[derive(Clone, Debug)]
+pub struct HookContext {
+    pub event: HookEvent,
+    /// The pet written, if it was just one.
+    pub pet_id: Option<usize>,
+    pub db_path: String,
+}
+
+/// A hook that ran, with what it printed or why it failed.
+#[derive(Debug)]
+pub struct HookOutcome {
+    pub key: &'static str,
+    pub result: Result<String, HookError>,
+}
+
+#[derive(Debug)]
+pub enum HookError {
+    Spawn(io::Error),
+    Failed { status: ExitStatus, output: String },
+    TimedOut(Duration),
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookError::Spawn(err) => write!(f, "could not start: {}", err),
+            HookError::Failed { status, output } if output.is_empty() => write!(f, "{}", status),
+            HookError::Failed { status, output } => write!(f, "{}: {}", status, output),
+            HookError::TimedOut(timeout) => write!(f, "killed after {:?}", timeout),
+        }
+    }
+}
+
+/// Runs the hooks for `context` one after the other on a new thread, handing
+/// each outcome to `done`.
+pub fn spawn(hooks: &Hooks, context: HookContext, done: impl Fn(HookOutcome) + Send + 'static) {
+    let commands: Vec<(&'static str, String)> = hooks
+        .commands(context.event)
+        .into_iter()
+        .map(|(key, command)| (key, command.to_owned()))
+        .collect();
+    if commands.is_empty() {
+        return;
+    }
+    let timeout = hooks.timeout;
+    thread::spawn(move || {
+        for (key, command) in commands {
+            log::info!("running {} hook: {}", key, command);
+            let result = run(&command, &context, timeout);
+            done(HookOutcome { key, result });
+        }
+    });
+}
+
+/// Runs `command` with the shell and the event in its environment.
+fn run(command: &str, context: &HookContext, timeout: Duration) -> Result<String, HookError> {
+    let mut command = shell(command);
+    command
+        .env("PET_CLI_EVENT", context.event.name())
+        .env(
+            "PET_CLI_PET_ID",
+            context.pet_id.map(|id| id.to_string()).unwrap_or_default(),
+        )
+        .env("PET_CLI_DB", &context.db_path);
+    run_command(command, timeout)
+}
+
+/// Runs `command` and waits for it, for at most `timeout`, returning what it
+/// printed. Its output goes to a temporary file rather than a pipe, so that
+/// nothing it leaves running can keep this waiting.
+pub fn run_command(mut command: Command, timeout: Duration) -> Result<String, HookError> {
+    let output_path = output_path();
+    let output = File::create(&output_path).map_err(HookError::Spawn)?;
+    let errors = output.try_clone().map_err(HookError::Spawn)?;
+    let spawned = command
+        .stdin(Stdio::null())
+        .stdout(output)
+        .stderr(errors)
+        .spawn();
+    let result = match spawned {
+        Ok(mut child) => wait(&mut child, timeout),
+        Err(err) => Err(HookError::Spawn(err)),
+    };
+    let printed = fs::read_to_string(&output_path).unwrap_or_default();
+    let _ = fs::remove_file(&output_path);
+    let printed = printed.trim().to_owned();
+    match result {
+        Ok(status) if status.success() => Ok(printed),
+        Ok(status) => Err(HookError::Failed {
+            status,
+            output: printed,
+        }),
+        Err(err) => Err(err),
+    }
+}
+
+fn wait(child: &mut std::process::Child, timeout: Duration) -> Result<ExitStatus, HookError> {
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().map_err(HookError::Spawn)? {
+            return Ok(status);
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(HookError::TimedOut(timeout));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(unix)]
+pub fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+pub fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// A file of its own for every hook this process runs.
+fn output_path() -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("pet-cli-hook-{}-{}.log", std::process::id(), n))
+}
+
+/// The DB as hooks see it, absolute if it can be resolved.
+pub fn db_path(db: &Path) -> String {
+    fs::canonicalize(db)
+        .unwrap_or_else(|_| db.to_owned())
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn context(event: HookEvent, pet_id: Option<usize>) -> HookContext {
+        HookContext {
+            event,
+            pet_id,
+            db_path: "/pets/db.json".to_owned(),
+        }
+    }
+
+    fn marker(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pet-cli-hook-test-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn the_events_hook_runs_before_on_any_write() {
+        let hooks = Hooks {
+            on_add: Some("add".to_owned()),
+            on_update: Some("update".to_owned()),
+            on_any_write: Some("any".to_owned()),
+            ..Hooks::default()
+        };
+        assert_eq!(
+            hooks.commands(HookEvent::Add),
+            [("on_add", "add"), ("on_any_write", "any")]
+        );
+        assert_eq!(hooks.commands(HookEvent::Delete), [("on_any_write", "any")]);
+        assert!(Hooks::default().commands(HookEvent::Update).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_hook_gets_the_event_in_its_environment() {
+        let marker = marker("env");
+        let command = format!(
+            "echo \"$PET_CLI_EVENT $PET_CLI_PET_ID $PET_CLI_DB\" > '{}'",
+            marker.display()
+        );
+        let printed = run(
+            &command,
+            &context(HookEvent::Update, Some(7)),
+            Duration::from_secs(5),
+        );
+        assert_eq!(printed.unwrap(), "");
+        let written = fs::read_to_string(&marker).unwrap();
+        fs::remove_file(&marker).unwrap();
+        assert_eq!(written, "update 7 /pets/db.json\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn what_a_hook_prints_is_kept_and_a_failure_reported() {
+        let timeout = Duration::from_secs(5);
+        let add = context(HookEvent::Add, None);
+        assert_eq!(
+            run("echo out; echo err >&2", &add, timeout).unwrap(),
+            "out\nerr"
+        );
+        let err = run("echo nope; exit 3", &add, timeout).unwrap_err();
+        assert!(matches!(&err, HookError::Failed { output, .. } if output == "nope"));
+        assert!(err.to_string().ends_with(": nope"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_hook_running_past_the_timeout_is_killed() {
+        let marker = marker("late");
+        let command = format!("sleep 5; touch '{}'", marker.display());
+        let started = Instant::now();
+        let result = run(
+            &command,
+            &context(HookEvent::Delete, Some(1)),
+            Duration::from_millis(200),
+        );
+        assert!(started.elapsed() < Duration::from_secs(3));
+        let err = result.unwrap_err();
+        assert!(matches!(err, HookError::TimedOut(_)));
+        assert_eq!(err.to_string(), "killed after 200ms");
+        assert!(!marker.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn spawn_runs_every_hook_off_the_calling_thread() {
+        let marker = marker("spawn");
+        let hooks = Hooks {
+            on_delete: Some(format!("touch '{}'", marker.display())),
+            on_any_write: Some("exit 1".to_owned()),
+            timeout: Duration::from_secs(5),
+            ..Hooks::default()
+        };
+        let (tx, rx) = mpsc::channel();
+        spawn(
+            &hooks,
+            context(HookEvent::Delete, Some(2)),
+            move |outcome| tx.send(outcome).unwrap(),
+        );
+        let first = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let second = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(first.key, "on_delete");
+        assert_eq!(first.result.unwrap(), "");
+        assert!(marker.exists());
+        fs::remove_file(&marker).unwrap();
+        assert_eq!(second.key, "on_any_write");
+        assert!(second.result.is_err());
+    }
+}
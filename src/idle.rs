@@ -0,0 +1,45 @@
+use std::time::{Duration, Instant};
+
+/// Blanks the screen behind a "press any key" overlay once no key has been
+/// pressed for the `idle_lock` minutes, for a session left open on a shared
+/// machine. Idling is checked on every tick, so writes, hooks and watching
+/// the DB go on underneath. The key that dismisses the overlay does nothing
+/// else.
+pub struct IdleLock {
+    after: Option<Duration>,
+    last_input: Instant,
+    locked: bool,
+}
+
+impl IdleLock {
+    /// Locks after `after` without input; never for `None`.
+    pub fn new(after: Option<Duration>, now: Instant) -> IdleLock {
+        IdleLock {
+            after,
+            last_input: now,
+            locked: false,
+        }
+    }
+
+    /// Feeds a key press or paste through the lock. Returns whether it
+    /// should be acted on: `false` for the one that dismisses the overlay.
+    pub fn input(&mut self, now: Instant) -> bool {
+        self.last_input = now;
+        !std::mem::replace(&mut self.locked, false)
+    }
+
+    /// Locks once the session has been idle long enough. Returns whether it
+    /// just did, so the overlay is drawn.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let idle = self
+            .after
+            .is_some_and(|after| now.duration_since(self.last_input) >= after);
+        let locks = idle && !self.locked;
+        self.locked |= idle;
+        locks
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
@@ -0,0 +1,91 @@
+//! Pet ids. They are never given out twice, even once the pet that had one
+//! is purged, so a script or another system that kept an id can't end up
+//! pointing at a different pet. The DB keeps the next id as `next_id`.
+
+use crate::Pet;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// The DB's `next_id`. Every way of adding pets takes their ids from here.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IdAllocator {
+    next: usize,
+}
+
+impl IdAllocator {
+    /// Ids for `count` new pets. They start after the highest id in `pets`
+    /// as well, for files without `next_id` or with pets added by hand.
+    pub fn take(&mut self, pets: &[Pet], count: usize) -> Range<usize> {
+        let after_pets = pets.iter().map(|pet| pet.id + 1).max().unwrap_or(1);
+        let first = self.next.max(after_pets);
+        self.next = first + count;
+        first..self.next
+    }
+
+    pub fn next(&mut self, pets: &[Pet]) -> usize {
+        self.take(pets, 1).start
+    }
+
+    /// Whichever of two versions of the DB's `next_id` is further along, so
+    /// that neither one's ids are given out again.
+    pub fn later(self, other: IdAllocator) -> IdAllocator {
+        IdAllocator {
+            next: self.next.max(other.next),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+
+    #[test]
+    fn ids_follow_on_from_each_other() {
+        let pets = [pet(1, "Coco", "cats"), pet(2, "Rex", "dogs")];
+        let mut ids = IdAllocator::default();
+        assert_eq!(ids.take(&pets, 3), 3..6);
+        assert_eq!(ids.next(&pets), 6);
+        assert_eq!(ids.take(&pets, 0), 7..7);
+        assert_eq!(ids.next(&pets), 7);
+    }
+
+    #[test]
+    fn the_first_id_is_one() {
+        assert_eq!(IdAllocator::default().next(&[]), 1);
+    }
+
+    #[test]
+    fn ids_of_removed_pets_are_not_given_out_again() {
+        let mut pets = vec![pet(1, "Coco", "cats"), pet(2, "Rex", "dogs")];
+        let mut ids = IdAllocator::default();
+        ids.take(&pets, 0);
+        pets.pop();
+        assert_eq!(ids.next(&pets), 3);
+        pets.clear();
+        assert_eq!(ids.next(&pets), 4);
+    }
+
+    #[test]
+    fn pets_added_past_next_id_move_it_on() {
+        let mut ids = IdAllocator { next: 3 };
+        let pets = [pet(9, "Rex", "dogs")];
+        assert_eq!(ids.next(&pets), 10);
+        assert_eq!(ids.next(&[]), 11);
+    }
+
+    #[test]
+    fn the_later_next_id_wins() {
+        let (ours, theirs) = (IdAllocator { next: 5 }, IdAllocator { next: 8 });
+        assert_eq!(ours.later(theirs), theirs);
+        assert_eq!(theirs.later(ours), theirs);
+    }
+
+    #[test]
+    fn next_id_is_a_plain_number_in_the_db() {
+        let ids = IdAllocator { next: 42 };
+        assert_eq!(serde_json::to_string(&ids).unwrap(), "42");
+        assert_eq!(serde_json::from_str::<IdAllocator>("42").unwrap(), ids);
+    }
+}
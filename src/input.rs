@@ -0,0 +1,268 @@
+use crate::text;
+use crossterm::event::KeyCode;
+
+/// A single line of text typed into a prompt, with a cursor that Left, Right,
+/// Home and End move.
+#[derive(Default)]
+pub struct TextInput {
+    text: String,
+    /// A byte offset into `text`, always between grapheme clusters, so that
+    /// an emoji or a letter with an accent is moved over and deleted whole.
+    cursor: usize,
+}
+
+pub enum InputOutcome {
+    Editing,
+    Submitted,
+    Cancelled,
+}
+
+impl TextInput {
+    /// With the cursor after `initial`.
+    pub fn new(initial: &str) -> Self {
+        Self {
+            text: initial.to_owned(),
+            cursor: initial.len(),
+        }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> InputOutcome {
+        match code {
+            KeyCode::Enter => return InputOutcome::Submitted,
+            KeyCode::Esc => return InputOutcome::Cancelled,
+            KeyCode::Backspace => {
+                if let Some(previous) = self.previous_boundary() {
+                    self.text.replace_range(previous..self.cursor, "");
+                    self.cursor = previous;
+                }
+            }
+            KeyCode::Delete => {
+                if let Some(next) = self.next_boundary() {
+                    self.text.replace_range(self.cursor..next, "");
+                }
+            }
+            KeyCode::Left => self.cursor = self.previous_boundary().unwrap_or(0),
+            KeyCode::Right => self.cursor = self.next_boundary().unwrap_or(self.text.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Char(c) => {
+                self.text.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+            }
+            _ => {}
+        }
+        InputOutcome::Editing
+    }
+
+    /// Puts `text` at the cursor, with the cursor after it.
+    pub fn insert(&mut self, text: &str) {
+        self.text.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The text before and from the cursor, for drawing it.
+    pub fn split_at_cursor(&self) -> (&str, &str) {
+        self.text.split_at(self.cursor)
+    }
+
+    fn previous_boundary(&self) -> Option<usize> {
+        text::previous_boundary(&self.text, self.cursor)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        text::next_boundary(&self.text, self.cursor)
+    }
+}
+
+/// How many entries a history keeps for Up and Down.
+const HISTORY_LENGTH: usize = 100;
+
+/// Adds `line` as the newest entry of `history`, unless it repeats the one
+/// before.
+pub fn remember(history: &mut Vec<String>, line: &str) {
+    if line.trim().is_empty() || history.last().is_some_and(|last| last == line) {
+        return;
+    }
+    if history.len() == HISTORY_LENGTH {
+        history.remove(0);
+    }
+    history.push(line.to_owned());
+}
+
+/// While going back through a history with Up and Down, how many entries
+/// back. Histories have the oldest entry first.
+#[derive(Default)]
+pub struct HistoryCursor {
+    position: Option<usize>,
+}
+
+impl HistoryCursor {
+    /// The entry before the one shown, or `None` past the oldest.
+    pub fn older<'h>(&mut self, history: &'h [String]) -> Option<&'h str> {
+        let position = self.position.map_or(0, |position| position + 1);
+        let line = history.iter().rev().nth(position)?;
+        self.position = Some(position);
+        Some(line)
+    }
+
+    /// The entry after the one shown, or nothing past the newest.
+    pub fn newer<'h>(&mut self, history: &'h [String]) -> &'h str {
+        match self.position {
+            Some(0) | None => {
+                self.position = None;
+                ""
+            }
+            Some(position) => {
+                self.position = Some(position - 1);
+                &history[history.len() - position]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(initial: &str, keys: &[KeyCode]) -> TextInput {
+        let mut input = TextInput::new(initial);
+        for key in keys {
+            input.handle_key(*key);
+        }
+        input
+    }
+
+    #[test]
+    fn starts_with_the_cursor_at_the_end() {
+        let input = TextInput::new("ねこ");
+        assert_eq!(input.split_at_cursor(), ("ねこ", ""));
+        assert_eq!(TextInput::default().split_at_cursor(), ("", ""));
+    }
+
+    #[test]
+    fn typing_inserts_at_the_cursor() {
+        let input = typed(
+            "ねこ",
+            &[KeyCode::Left, KeyCode::Char('の'), KeyCode::Char('!')],
+        );
+        assert_eq!(input.text(), "ねの!こ");
+        assert_eq!(input.split_at_cursor(), ("ねの!", "こ"));
+    }
+
+    #[test]
+    fn left_and_right_move_over_whole_graphemes() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let name = format!("a{}e\u{301}", family);
+        let mut input = TextInput::new(&name);
+        input.handle_key(KeyCode::Left);
+        assert_eq!(input.split_at_cursor().1, "e\u{301}");
+        input.handle_key(KeyCode::Left);
+        assert_eq!(input.split_at_cursor().1, format!("{}e\u{301}", family));
+        input.handle_key(KeyCode::Left);
+        input.handle_key(KeyCode::Left);
+        assert_eq!(input.split_at_cursor().0, "");
+        input.handle_key(KeyCode::Right);
+        input.handle_key(KeyCode::Right);
+        assert_eq!(input.split_at_cursor().0, format!("a{}", family));
+        for _ in 0..3 {
+            input.handle_key(KeyCode::Right);
+        }
+        assert_eq!(input.split_at_cursor(), (name.as_str(), ""));
+    }
+
+    #[test]
+    fn home_and_end_go_to_either_end() {
+        let mut input = TextInput::new("🐱🐱🐱");
+        input.handle_key(KeyCode::Home);
+        assert_eq!(input.split_at_cursor(), ("", "🐱🐱🐱"));
+        input.handle_key(KeyCode::End);
+        assert_eq!(input.split_at_cursor(), ("🐱🐱🐱", ""));
+    }
+
+    #[test]
+    fn backspace_and_delete_remove_whole_graphemes() {
+        let input = typed("🐱e\u{301}ね", &[KeyCode::Left, KeyCode::Backspace]);
+        assert_eq!(input.text(), "🐱ね");
+        assert_eq!(input.split_at_cursor(), ("🐱", "ね"));
+        let input = typed(
+            "🐱e\u{301}ね",
+            &[KeyCode::Home, KeyCode::Delete, KeyCode::Delete],
+        );
+        assert_eq!(input.text(), "ね");
+        assert_eq!(input.split_at_cursor(), ("", "ね"));
+    }
+
+    #[test]
+    fn editing_past_either_end_does_nothing() {
+        let input = typed("ab", &[KeyCode::Delete, KeyCode::Right, KeyCode::Right]);
+        assert_eq!(input.split_at_cursor(), ("ab", ""));
+        let input = typed(
+            "ab",
+            &[
+                KeyCode::Home,
+                KeyCode::Backspace,
+                KeyCode::Left,
+                KeyCode::Left,
+            ],
+        );
+        assert_eq!(input.split_at_cursor(), ("", "ab"));
+        let input = typed("", &[KeyCode::Backspace, KeyCode::Delete]);
+        assert_eq!(input.text(), "");
+    }
+
+    #[test]
+    fn insert_puts_text_at_the_cursor() {
+        let mut input = typed("Rex", &[KeyCode::Left]);
+        input.insert("🐶 ");
+        assert_eq!(input.split_at_cursor(), ("Re🐶 ", "x"));
+    }
+
+    #[test]
+    fn enter_and_esc_end_the_editing() {
+        let mut input = TextInput::new("Rex");
+        assert!(matches!(
+            input.handle_key(KeyCode::Char('y')),
+            InputOutcome::Editing
+        ));
+        assert!(matches!(
+            input.handle_key(KeyCode::Enter),
+            InputOutcome::Submitted
+        ));
+        assert!(matches!(
+            input.handle_key(KeyCode::Esc),
+            InputOutcome::Cancelled
+        ));
+        assert_eq!(input.text(), "Rexy");
+    }
+
+    #[test]
+    fn a_history_keeps_no_blanks_or_repeats() {
+        let mut history = Vec::new();
+        for line in ["rex", "rex", " ", "max", "rex"] {
+            remember(&mut history, line);
+        }
+        assert_eq!(history, vec!["rex", "max", "rex"]);
+        for number in 0..HISTORY_LENGTH {
+            remember(&mut history, &number.to_string());
+        }
+        assert_eq!(history.len(), HISTORY_LENGTH);
+        assert_eq!(history[0], "0");
+    }
+
+    #[test]
+    fn the_history_cursor_walks_back_and_forth() {
+        let history = vec!["old".to_owned(), "new".to_owned()];
+        let mut cursor = HistoryCursor::default();
+        assert_eq!(cursor.newer(&history), "");
+        assert_eq!(cursor.older(&history), Some("new"));
+        assert_eq!(cursor.older(&history), Some("old"));
+        assert_eq!(cursor.older(&history), None);
+        assert_eq!(cursor.newer(&history), "new");
+        assert_eq!(cursor.newer(&history), "");
+        assert_eq!(cursor.older(&[]), None);
+    }
+}
@@ -0,0 +1,551 @@
+//! `pet-cli check` and the check of the DB at startup. The DB is read as
+//! plain JSON first, so that one broken pet is reported along with everything
+//! else instead of failing the whole file. Each check is a function in
+//! `CHECKS`; adding one there is enough for both the command and the TUI.
+
+use crate::db_format::{self, RawDatabase};
+use crate::{text, validation, Pet};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finding {
+    pub severity: Severity,
+    /// The name of the check in `CHECKS` that found it, filled in by `check`.
+    pub check: &'static str,
+    /// What it is about, like `pet #3`.
+    pub subject: String,
+    pub message: String,
+}
+
+type Check = fn(&RawDatabase) -> Vec<Finding>;
+
+/// Every check, by name, in the order their findings are listed.
+pub const CHECKS: [(&str, Check); 5] = [
+    ("ids", duplicate_ids),
+    ("timestamps", timestamps),
+    ("pets", invalid_pets),
+    ("fields", unknown_fields),
+    ("owners", dangling_owners),
+];
+
+/// The fields `Pet` reads, including `age` from before ages were stored in
+/// months. Kept in step with `Pet`.
+const PET_FIELDS: [&str; 17] = [
+    "id",
+    "name",
+    "category",
+    "breed",
+    "age_months",
+    "age",
+    "created_at",
+    "notes",
+    "birthdate",
+    "owner_id",
+    "records",
+    "weights",
+    "microchip",
+    "status",
+    "photo_path",
+    "attributes",
+    "archived_at",
+];
+
+const OWNER_FIELDS: [&str; 3] = ["id", "name", "email"];
+
+const TIMESTAMP_FIELDS: [&str; 2] = ["created_at", "archived_at"];
+
+/// The findings of all checks and how big the DB is.
+pub struct Report {
+    pub findings: Vec<Finding>,
+    pub bytes: usize,
+    pub pets: usize,
+    pub archived: usize,
+    pub owners: usize,
+}
+
+impl Report {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    /// Like `3 errors, 1 warning`.
+    pub fn describe(&self) -> String {
+        let count = |severity: Severity| {
+            let count = self.count(severity);
+            format!(
+                "{} {}{}",
+                count,
+                severity,
+                if count == 1 { "" } else { "s" }
+            )
+        };
+        format!("{}, {}", count(Severity::Error), count(Severity::Warning))
+    }
+}
+
+/// Runs every check on the content of a DB file. A file that isn't JSON in
+/// any of the DB formats is a single error.
+pub fn check(content: &str) -> Report {
+    let mut report = Report {
+        findings: Vec::new(),
+        bytes: content.len(),
+        pets: 0,
+        archived: 0,
+        owners: 0,
+    };
+    let database = match db_format::parse_raw(content) {
+        Ok(database) => database,
+        Err(err) => {
+            report.findings.push(Finding {
+                severity: Severity::Error,
+                check: "format",
+                subject: "file".to_owned(),
+                message: format!("not a DB file: {}", err),
+            });
+            return report;
+        }
+    };
+    report.pets = database.pets.len();
+    report.archived = database
+        .pets
+        .iter()
+        .filter(|pet| !pet["archived_at"].is_null())
+        .count();
+    report.owners = database.owners.len();
+    for (name, check) in CHECKS {
+        report
+            .findings
+            .extend(check(&database).into_iter().map(|finding| Finding {
+                check: name,
+                ..finding
+            }));
+    }
+    report
+}
+
+/// The findings as a table with a header, one line each.
+pub fn render_table(findings: &[Finding]) -> Vec<String> {
+    const COLUMNS: [&str; 4] = ["SEVERITY", "CHECK", "SUBJECT", "MESSAGE"];
+    let rows: Vec<[String; 4]> = findings
+        .iter()
+        .map(|finding| {
+            [
+                finding.severity.to_string(),
+                finding.check.to_owned(),
+                finding.subject.clone(),
+                finding.message.clone(),
+            ]
+        })
+        .collect();
+    let mut widths = COLUMNS.map(text::width);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(text::width(cell));
+        }
+    }
+    let header = COLUMNS.map(str::to_owned);
+    std::iter::once(&header)
+        .chain(&rows)
+        .map(|row| {
+            let cells: Vec<_> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| text::pad(cell, width))
+                .collect();
+            cells.join("  ").trim_end().to_owned()
+        })
+        .collect()
+}
+
+/// `pet #3`, or the position in the file for an entry without a usable id.
+fn subject(kind: &str, index: usize, entry: &Value) -> String {
+    match entry["id"].as_u64() {
+        Some(id) => format!("{} #{}", kind, id),
+        None => format!("{} entry {}", kind, index + 1),
+    }
+}
+
+fn error(subject: String, message: String) -> Finding {
+    Finding {
+        severity: Severity::Error,
+        check: "",
+        subject,
+        message,
+    }
+}
+
+fn warning(subject: String, message: String) -> Finding {
+    Finding {
+        severity: Severity::Warning,
+        check: "",
+        subject,
+        message,
+    }
+}
+
+fn duplicate_ids(database: &RawDatabase) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (kind, entries) in [("pet", &database.pets), ("owner", &database.owners)] {
+        let mut counts: HashMap<u64, usize> = HashMap::new();
+        for (index, entry) in entries.iter().enumerate() {
+            match entry["id"].as_u64() {
+                Some(id) => *counts.entry(id).or_default() += 1,
+                None => findings.push(error(
+                    subject(kind, index, entry),
+                    "has no numeric id".to_owned(),
+                )),
+            }
+        }
+        let mut duplicates: Vec<_> = counts.into_iter().filter(|(_, count)| *count > 1).collect();
+        duplicates.sort_unstable();
+        for (id, count) in duplicates {
+            findings.push(error(
+                format!("{} #{}", kind, id),
+                format!("id {} is used by {} {}s", id, count, kind),
+            ));
+        }
+    }
+    findings
+}
+
+/// The timestamp fields of `pet` that are set but can't be read.
+fn bad_timestamps(pet: &Value) -> Vec<(&'static str, &Value)> {
+    TIMESTAMP_FIELDS
+        .iter()
+        .map(|&field| (field, &pet[field]))
+        .filter(|(field, value)| {
+            let optional = *field != "created_at";
+            !(optional && value.is_null())
+                && value
+                    .as_str()
+                    .and_then(|text| text.parse::<DateTime<Utc>>().ok())
+                    .is_none()
+        })
+        .collect()
+}
+
+fn timestamps(database: &RawDatabase) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (index, pet) in database.pets.iter().enumerate() {
+        for (field, value) in bad_timestamps(pet) {
+            let message = match value {
+                Value::Null => format!("{} is missing", field),
+                value => format!("{} {} is not a timestamp", field, value),
+            };
+            findings.push(error(subject("pet", index, pet), message));
+        }
+    }
+    findings
+}
+
+/// Pets that can't be read or fail validation. Bad timestamps are left to
+/// `timestamps`, so they aren't reported twice.
+fn invalid_pets(database: &RawDatabase) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (index, entry) in database.pets.iter().enumerate() {
+        if !entry.is_object() {
+            findings.push(error(
+                subject("pet", index, entry),
+                "is not an object".to_owned(),
+            ));
+            continue;
+        }
+        if !bad_timestamps(entry).is_empty() {
+            continue;
+        }
+        match serde_json::from_value::<Pet>(entry.clone()) {
+            Ok(pet) => {
+                let issues = validation::validate(&pet);
+                if !issues.is_empty() {
+                    findings.push(error(
+                        subject("pet", index, entry),
+                        validation::describe(&issues),
+                    ));
+                }
+            }
+            Err(err) => findings.push(error(
+                subject("pet", index, entry),
+                format!("cannot be read: {}", err),
+            )),
+        }
+    }
+    findings
+}
+
+/// Fields nothing reads, which are dropped the next time the DB is written.
+fn unknown_fields(database: &RawDatabase) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let entries = [
+        ("pet", &database.pets, &PET_FIELDS[..]),
+        ("owner", &database.owners, &OWNER_FIELDS[..]),
+    ];
+    for (kind, entries, known) in entries {
+        for (index, entry) in entries.iter().enumerate() {
+            let Value::Object(fields) = entry else {
+                continue;
+            };
+            let unknown: Vec<&str> = fields
+                .keys()
+                .map(String::as_str)
+                .filter(|field| !known.contains(field))
+                .collect();
+            if !unknown.is_empty() {
+                findings.push(warning(
+                    subject(kind, index, entry),
+                    format!("unknown fields: {}", unknown.join(", ")),
+                ));
+            }
+        }
+    }
+    findings
+}
+
+fn dangling_owners(database: &RawDatabase) -> Vec<Finding> {
+    let owners: HashSet<u64> = database
+        .owners
+        .iter()
+        .filter_map(|owner| owner["id"].as_u64())
+        .collect();
+    database
+        .pets
+        .iter()
+        .enumerate()
+        .filter_map(|(index, pet)| {
+            let owner_id = pet["owner_id"].as_u64()?;
+            (!owners.contains(&owner_id)).then(|| {
+                warning(
+                    subject("pet", index, pet),
+                    format!("owner #{} does not exist", owner_id),
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn raw(pets: Value, owners: Value) -> RawDatabase {
+        let entries = |value: Value| match value {
+            Value::Array(entries) => entries,
+            _ => panic!("entries are an array"),
+        };
+        RawDatabase {
+            pets: entries(pets),
+            owners: entries(owners),
+        }
+    }
+
+    fn pet(id: u64) -> Value {
+        serde_json::to_value(crate::ui_tests::pet(id as usize, "Coco", "cats")).unwrap()
+    }
+
+    fn subjects(findings: &[Finding]) -> Vec<(Severity, &str)> {
+        findings
+            .iter()
+            .map(|finding| (finding.severity, finding.subject.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn a_healthy_db_has_no_findings() {
+        let mut owned = pet(2);
+        owned["owner_id"] = json!(1);
+        let content = json!({
+            "pets": [pet(1), owned],
+            "owners": [{"id": 1, "name": "Ann", "email": "ann@example.com"}],
+        })
+        .to_string();
+        let report = check(&content);
+        assert_eq!(report.findings, vec![]);
+        assert_eq!((report.pets, report.archived, report.owners), (2, 0, 1));
+        assert_eq!(report.bytes, content.len());
+        assert_eq!(report.describe(), "0 errors, 0 warnings");
+    }
+
+    #[test]
+    fn every_field_a_pet_writes_is_known() {
+        let mut pet = crate::ui_tests::pet(1, "Coco", "cats");
+        pet.archived_at = Some(pet.created_at);
+        pet.notes = Some("notes".to_owned());
+        let Value::Object(fields) = serde_json::to_value(pet).unwrap() else {
+            panic!("a pet is an object");
+        };
+        for field in fields.keys() {
+            assert!(PET_FIELDS.contains(&field.as_str()), "{}", field);
+        }
+    }
+
+    #[test]
+    fn finds_duplicate_and_missing_ids() {
+        let mut no_id = pet(0);
+        no_id["id"] = json!("three");
+        let database = raw(
+            json!([pet(1), pet(2), pet(1), no_id, pet(1)]),
+            json!([{"id": 4, "name": "Ann"}, {"id": 4, "name": "Bo"}]),
+        );
+        let findings = duplicate_ids(&database);
+        assert_eq!(
+            subjects(&findings),
+            vec![
+                (Severity::Error, "pet entry 4"),
+                (Severity::Error, "pet #1"),
+                (Severity::Error, "owner #4"),
+            ]
+        );
+        assert_eq!(findings[1].message, "id 1 is used by 3 pets");
+    }
+
+    #[test]
+    fn finds_unreadable_timestamps() {
+        let mut missing = pet(1);
+        missing.as_object_mut().unwrap().remove("created_at");
+        let mut garbled = pet(2);
+        garbled["created_at"] = json!("yesterday");
+        let mut archived = pet(3);
+        archived["archived_at"] = json!(12);
+        let mut fine = pet(4);
+        fine["archived_at"] = json!("2024-02-01T00:00:00Z");
+        let findings = timestamps(&raw(json!([missing, garbled, archived, fine]), json!([])));
+        let messages: Vec<_> = findings
+            .iter()
+            .map(|finding| finding.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "created_at is missing",
+                "created_at \"yesterday\" is not a timestamp",
+                "archived_at 12 is not a timestamp",
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_pets_that_cannot_be_read_or_are_invalid() {
+        let mut empty_name = pet(1);
+        empty_name["name"] = json!(" ");
+        let mut bad_age = pet(2);
+        bad_age["age_months"] = json!(-3);
+        let mut bad_timestamp = pet(4);
+        bad_timestamp["created_at"] = json!("never");
+        let database = raw(
+            json!([empty_name, bad_age, "Rex", bad_timestamp, pet(5)]),
+            json!([]),
+        );
+        let findings = invalid_pets(&database);
+        assert_eq!(
+            subjects(&findings),
+            vec![
+                (Severity::Error, "pet #1"),
+                (Severity::Error, "pet #2"),
+                (Severity::Error, "pet entry 3"),
+            ]
+        );
+        assert!(findings[1].message.starts_with("cannot be read"));
+        assert_eq!(findings[2].message, "is not an object");
+    }
+
+    #[test]
+    fn warns_about_unknown_fields() {
+        let mut extra = pet(1);
+        extra["colour"] = json!("ginger");
+        extra["legs"] = json!(4);
+        let database = raw(
+            json!([extra, pet(2)]),
+            json!([{"id": 1, "name": "Ann", "phone": "555"}]),
+        );
+        let findings = unknown_fields(&database);
+        assert_eq!(
+            subjects(&findings),
+            vec![
+                (Severity::Warning, "pet #1"),
+                (Severity::Warning, "owner #1")
+            ]
+        );
+        assert_eq!(findings[0].message, "unknown fields: colour, legs");
+        assert_eq!(findings[1].message, "unknown fields: phone");
+    }
+
+    #[test]
+    fn warns_about_owners_that_do_not_exist() {
+        let mut owned = pet(1);
+        owned["owner_id"] = json!(1);
+        let mut dangling = pet(2);
+        dangling["owner_id"] = json!(9);
+        let database = raw(
+            json!([owned, dangling, pet(3)]),
+            json!([{"id": 1, "name": "Ann"}]),
+        );
+        let findings = dangling_owners(&database);
+        assert_eq!(subjects(&findings), vec![(Severity::Warning, "pet #2")]);
+        assert_eq!(findings[0].message, "owner #9 does not exist");
+    }
+
+    #[test]
+    fn check_names_each_finding_after_its_check() {
+        let mut extra = pet(1);
+        extra["colour"] = json!("ginger");
+        let report = check(&json!({"pets": [extra, pet(1)]}).to_string());
+        let checks: Vec<_> = report
+            .findings
+            .iter()
+            .map(|finding| finding.check)
+            .collect();
+        assert_eq!(checks, vec!["ids", "fields"]);
+        assert_eq!(report.describe(), "1 error, 1 warning");
+    }
+
+    #[test]
+    fn a_file_that_is_not_a_db_is_one_error() {
+        let report = check("{\"pets\": [");
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].check, "format");
+        assert_eq!(report.count(Severity::Error), 1);
+    }
+
+    #[test]
+    fn renders_the_findings_as_aligned_columns() {
+        let findings = vec![
+            Finding {
+                check: "ids",
+                ..error("pet #1".to_owned(), "id 1 is used by 2 pets".to_owned())
+            },
+            Finding {
+                check: "owners",
+                ..warning("pet #12".to_owned(), "owner #9 does not exist".to_owned())
+            },
+        ];
+        assert_eq!(
+            render_table(&findings),
+            vec![
+                "SEVERITY  CHECK   SUBJECT  MESSAGE",
+                "error     ids     pet #1   id 1 is used by 2 pets",
+                "warning   owners  pet #12  owner #9 does not exist",
+            ]
+        );
+    }
+}
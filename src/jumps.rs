@@ -0,0 +1,163 @@
+//! The jump list, like vim's: where the selection was before each jump to a
+//! far-away pet, from quick open, a reminder, a search or a category on the
+//! Stats tab. `Ctrl+o` goes back through it and `Ctrl+n` forward again;
+//! vim's `Ctrl+i` can't be told apart from `Tab` in a terminal.
+
+use crate::MenuItem;
+
+/// The most jumps kept; older ones are forgotten.
+pub const CAPACITY: usize = 100;
+
+/// A place to jump back to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Jump {
+    pub tab: MenuItem,
+    /// The pet selected on the Pets tab, if any.
+    pub pet_id: Option<usize>,
+}
+
+#[derive(Default)]
+pub struct JumpList {
+    entries: Vec<Jump>,
+    /// The entry last jumped to with `back` or `forward`, or the length of
+    /// `entries` when not moving through them.
+    position: usize,
+}
+
+impl JumpList {
+    /// Records `from` as the place a jump is leaving. The entries ahead of
+    /// the current one are dropped, as they are no longer where forward
+    /// leads.
+    pub fn push(&mut self, from: Jump) {
+        self.entries.truncate(self.position);
+        if self.entries.last() != Some(&from) {
+            self.entries.push(from);
+        }
+        if self.entries.len() > CAPACITY {
+            self.entries.remove(0);
+        }
+        self.position = self.entries.len();
+    }
+
+    /// The entry before the current one that `valid` accepts, skipping the
+    /// others. `current` is remembered first when not moving through the
+    /// list yet, so that `forward` can return to it.
+    pub fn back(&mut self, current: Jump, valid: impl Fn(&Jump) -> bool) -> Option<Jump> {
+        let appended = self.position == self.entries.len();
+        if appended {
+            self.entries.push(current);
+        }
+        match (0..self.position)
+            .rev()
+            .find(|&index| valid(&self.entries[index]))
+        {
+            Some(index) => {
+                self.position = index;
+                Some(self.entries[index])
+            }
+            None => {
+                if appended {
+                    self.entries.pop();
+                }
+                None
+            }
+        }
+    }
+
+    /// The entry after the current one that `valid` accepts, skipping the
+    /// others.
+    pub fn forward(&mut self, valid: impl Fn(&Jump) -> bool) -> Option<Jump> {
+        let index =
+            (self.position + 1..self.entries.len()).find(|&index| valid(&self.entries[index]))?;
+        self.position = index;
+        Some(self.entries[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn on_pet(pet_id: usize) -> Jump {
+        Jump {
+            tab: MenuItem::Pets,
+            pet_id: Some(pet_id),
+        }
+    }
+
+    fn any(_: &Jump) -> bool {
+        true
+    }
+
+    #[test]
+    fn back_and_forward_walk_the_list_and_return_to_the_start() {
+        let mut jumps = JumpList::default();
+        jumps.push(on_pet(1));
+        jumps.push(on_pet(2));
+        assert_eq!(jumps.back(on_pet(3), any), Some(on_pet(2)));
+        assert_eq!(jumps.back(on_pet(2), any), Some(on_pet(1)));
+        assert_eq!(jumps.back(on_pet(1), any), None);
+        assert_eq!(jumps.forward(any), Some(on_pet(2)));
+        assert_eq!(jumps.forward(any), Some(on_pet(3)));
+        assert_eq!(jumps.forward(any), None);
+    }
+
+    #[test]
+    fn a_new_jump_drops_the_forward_history() {
+        let mut jumps = JumpList::default();
+        jumps.push(on_pet(1));
+        jumps.push(on_pet(2));
+        jumps.back(on_pet(3), any);
+        jumps.back(on_pet(2), any);
+        jumps.push(on_pet(1));
+        assert_eq!(jumps.forward(any), None);
+        assert_eq!(jumps.back(on_pet(4), any), Some(on_pet(1)));
+        assert_eq!(jumps.back(on_pet(1), any), None);
+    }
+
+    #[test]
+    fn jumping_twice_from_the_same_place_records_it_once() {
+        let mut jumps = JumpList::default();
+        jumps.push(on_pet(1));
+        jumps.push(on_pet(1));
+        assert_eq!(jumps.back(on_pet(2), any), Some(on_pet(1)));
+        assert_eq!(jumps.back(on_pet(1), any), None);
+    }
+
+    #[test]
+    fn entries_that_are_gone_are_skipped() {
+        let mut jumps = JumpList::default();
+        jumps.push(on_pet(1));
+        jumps.push(on_pet(2));
+        jumps.push(on_pet(3));
+        let not_two = |jump: &Jump| jump.pet_id != Some(2);
+        assert_eq!(jumps.back(on_pet(4), not_two), Some(on_pet(3)));
+        assert_eq!(jumps.back(on_pet(3), not_two), Some(on_pet(1)));
+        assert_eq!(jumps.forward(not_two), Some(on_pet(3)));
+    }
+
+    #[test]
+    fn going_back_with_nothing_to_go_to_changes_nothing() {
+        let mut jumps = JumpList::default();
+        assert_eq!(jumps.back(on_pet(1), any), None);
+        assert_eq!(jumps.forward(any), None);
+        jumps.push(on_pet(2));
+        assert_eq!(jumps.back(on_pet(3), |_| false), None);
+        assert_eq!(jumps.back(on_pet(3), any), Some(on_pet(2)));
+    }
+
+    #[test]
+    fn the_oldest_jumps_are_forgotten() {
+        let mut jumps = JumpList::default();
+        for pet_id in 0..=CAPACITY {
+            jumps.push(on_pet(pet_id));
+        }
+        let mut oldest = None;
+        let mut current = on_pet(CAPACITY + 1);
+        while let Some(jump) = jumps.back(current, any) {
+            oldest = Some(jump);
+            current = jump;
+        }
+        assert_eq!(oldest, Some(on_pet(1)));
+    }
+}
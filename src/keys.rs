@@ -0,0 +1,336 @@
+//! The single-key bindings of the app. Both the input handler and the menu
+//! refer to these, so the underlined hotkeys in the menu can't drift from what
+//! the keys actually do.
+
+use crate::MenuItem;
+
+pub const QUIT: char = 'q';
+pub const HOME: char = 'h';
+pub const PETS: char = 'p';
+pub const OWNERS: char = 'o';
+pub const DUPLICATES: char = 'u';
+pub const TRASH: char = 't';
+pub const LOG: char = 'l';
+pub const STATS: char = '%';
+pub const ADD: char = 'a';
+pub const DELETE: char = 'd';
+pub const NEXT: char = 'j';
+pub const PREVIOUS: char = 'k';
+/// Pressed twice.
+pub const FIRST: char = 'g';
+pub const LAST: char = 'G';
+pub const CYCLE_CATEGORY: char = 'c';
+pub const CYCLE_STATUS_FILTER: char = 'f';
+pub const CYCLE_SORT: char = 's';
+pub const REVERSE_SORT: char = 'S';
+pub const SEARCH: char = '/';
+pub const PALETTE: char = ':';
+pub const INTEGRITY: char = '!';
+/// Followed by a register letter.
+pub const REPLAY_MACRO: char = '@';
+pub const EXPORT: char = 'x';
+pub const EDIT_NOTES: char = 'n';
+pub const RENAME: char = 'r';
+pub const EDIT_BIRTHDATE: char = 'b';
+pub const EDIT_AGE: char = 'A';
+pub const ASSIGN_OWNER: char = 'O';
+pub const ADD_RECORD: char = 'm';
+pub const ADD_WEIGHT: char = 'w';
+pub const EDIT_MICROCHIP: char = 'i';
+pub const EDIT_BREED: char = 'N';
+pub const CYCLE_STATUS: char = 'T';
+pub const EDIT_PHOTO: char = 'P';
+pub const EDIT_ATTRIBUTE: char = 'F';
+pub const EXPORT_PET: char = 'E';
+pub const IMPORT_PETS: char = 'I';
+pub const RESTORE_BACKUP: char = 'R';
+pub const COPY_PET: char = 'C';
+pub const SCROLL_RECORDS_DOWN: char = 'J';
+pub const SCROLL_RECORDS_UP: char = 'K';
+pub const TOGGLE_MARK: char = ' ';
+pub const VISUAL: char = 'v';
+pub const CONFIRM: char = 'y';
+pub const RESTORE: char = 'r';
+pub const PURGE: char = 'D';
+pub const MERGE: char = 'M';
+pub const YANK: char = 'y';
+pub const YANK_SUMMARY: char = 'Y';
+pub const YANK_RING: char = '"';
+pub const WIDEN_LIST: char = '>';
+pub const NARROW_LIST: char = '<';
+pub const CHOOSE_COLUMNS: char = '|';
+pub const CUT: char = 'X';
+pub const BATCH_EDIT: char = 'B';
+pub const PASTE_AFTER: char = ']';
+pub const PASTE_BEFORE: char = '[';
+pub const MERGE_DB: char = 'e';
+pub const MESSAGES: char = 'L';
+pub const WORKSPACES: char = 'W';
+pub const SAVED_VIEWS: char = 'V';
+pub const AGE_BUCKETS: char = 'H';
+pub const WIDEN_GROWTH: char = '+';
+pub const NARROW_GROWTH: char = '-';
+pub const GROWTH_TOTAL: char = '#';
+/// On the Pets and Stats tabs.
+pub const CATEGORIES: char = '=';
+/// Starts a two-key command about the groups of the Pets list, like vim's
+/// fold commands.
+pub const FOLD: char = 'z';
+/// After `FOLD`.
+pub const GROUP: char = 'g';
+/// After `FOLD`.
+pub const TOGGLE_FOLD: char = 'a';
+/// The keys that only count when pressed twice in a row, like vim's `dd`,
+/// and `FOLD`, which counts with whatever key follows.
+pub const CHORDS: [char; 3] = [DELETE, FIRST, FOLD];
+/// The bindings the Home tab reminds of: a modifier prefix, the key and what
+/// it does.
+pub const ESSENTIALS: [(&str, char, &str); 7] = [
+    ("", PETS, "show the pets"),
+    ("", ADD, "add a pet (with a count first to add several)"),
+    ("", DELETE, "(twice) move the selected pet to the Trash"),
+    ("", EDIT_NOTES, "edit the notes"),
+    ("", CYCLE_CATEGORY, "cycle the category filter"),
+    ("Ctrl+", QUICK_OPEN, "jump to a pet by name"),
+    ("", QUIT, "quit"),
+];
+/// Stands for `Tab` in the binding tables.
+pub const TAB: char = '\t';
+/// Stands for `Esc` in the binding tables.
+pub const ESC: char = '\u{1b}';
+/// Stands for `Enter` in the binding tables.
+pub const ENTER: char = '\r';
+/// Where a binding applies. A key may mean something else in each, and
+/// `action::map_key` tries them in the order here: a pending question takes
+/// any key, then the active tab has its own bindings, and only then do the
+/// global ones apply.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Context {
+    Question,
+    Tab(MenuItem),
+    Global,
+}
+
+impl Context {
+    /// Like `on the Pets tab`.
+    pub fn describe(self) -> String {
+        match self {
+            Context::Question => "while asked a question".to_owned(),
+            Context::Tab(tab) => format!("on the {:?} tab", tab),
+            Context::Global => "everywhere".to_owned(),
+        }
+    }
+
+    /// The heading of the context's keys in the man page.
+    pub fn heading(self) -> String {
+        let describe = self.describe();
+        let mut chars = describe.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Every binding outside of prompts and editors, as shown in the man page:
+/// where it applies, a modifier prefix, the key and what it does. No two
+/// have the same key in the same context.
+pub const BINDINGS: [(Context, &str, char, &str); 81] = [
+    (Context::Global, "", HOME, "show the Home tab"),
+    (Context::Global, "", PETS, "show the Pets tab"),
+    (Context::Global, "", OWNERS, "show the Owners tab"),
+    (Context::Global, "", DUPLICATES, "show the Duplicates tab"),
+    (Context::Global, "", TRASH, "show the Trash tab"),
+    (Context::Global, "", LOG, "show the activity log"),
+    (Context::Global, "", MESSAGES, "show this session's messages in full, newest first, for ones cut off in the status bar"),
+    (Context::Global, "", STATS, "show the pets per category and adoption status"),
+    (Context::Global, "", TAB, "show the next tab (Shift+Tab the previous one)"),
+    (Context::Global, "", NEXT, "select the next row; after a count, move that many rows"),
+    (Context::Global, "", PREVIOUS, "select the previous row"),
+    (Context::Global, "", FIRST, "(twice) select the first row"),
+    (Context::Global, "", LAST, "select the last row"),
+    (Context::Tab(MenuItem::Pets), "z", GROUP, "group the pet list by category, or stop"),
+    (Context::Tab(MenuItem::Pets), "z", TOGGLE_FOLD, "fold the selected pet's group into its header, or unfold the folded group selected (also Enter)"),
+    (Context::Global, "", ENTER, "on the Home tab, show the pet of the selected reminder; on the Stats tab, the pets of the selected category"),
+    (Context::Global, "", ADD, "add a random pet, after a count that many; on the Owners tab add an owner"),
+    (Context::Global, "Ctrl+", QUICK_ADD, "add a pet by name, its category and age from add_category and add_age unless typed after it"),
+    (Context::Global, "", DELETE, "(twice) move the selected or marked pets to the Trash, after a count that many, asking about deleting those already there for good; on the Owners tab delete the owner"),
+    (Context::Global, "", TOGGLE_MARK, "mark or unmark the selected pet"),
+    (Context::Global, "", VISUAL, "start or end marking a range of pets"),
+    (Context::Global, "", ESC, "clear the marks"),
+    (Context::Global, "", CYCLE_CATEGORY, "cycle the category filter"),
+    (Context::Global, "", CYCLE_STATUS_FILTER, "cycle the adoption status filter"),
+    (Context::Global, "", CYCLE_SORT, "sort the pets by the next field, then back in the DB's order; on the Stats tab, sort by count or by name"),
+    (Context::Global, "", REVERSE_SORT, "reverse the sort order"),
+    (Context::Tab(MenuItem::Stats), "", AGE_BUCKETS, "make the age histogram's bars 1, 2 or 5 years wide"),
+    (Context::Tab(MenuItem::Stats), "", WIDEN_GROWTH, "show another year of pets added per month"),
+    (Context::Tab(MenuItem::Stats), "", NARROW_GROWTH, "show a year less of pets added per month"),
+    (Context::Tab(MenuItem::Stats), "", GROWTH_TOTAL, "switch between pets added per month and the running total"),
+    (Context::Tab(MenuItem::Stats), "", CATEGORIES, "rename a category or merge several, on every pet"),
+    (Context::Global, "", SEARCH, "show only the pets matching a search, like category:cats age:>5 rex"),
+    (Context::Global, "", SAVED_VIEWS, "list the views saved with :save-view, to show, rename or delete one"),
+    (Context::Global, "", EXPORT, "write the pets as shown to pet-report.md"),
+    (Context::Global, "", EXPORT_PET, "write the selected pet to exports/ to share it"),
+    (Context::Global, "", IMPORT_PETS, "add the pets of a file written with E"),
+    (Context::Global, "", MERGE_DB, "merge another DB file into this one, deciding about pets that conflict"),
+    (Context::Global, "", WORKSPACES, "switch to the DB of another workspace from the config file"),
+    (Context::Global, "", RESTORE_BACKUP, "compare the DB with its backups and restore one"),
+    (Context::Global, "", EDIT_NOTES, "edit the notes of the selected pet"),
+    (Context::Tab(MenuItem::Pets), "", RENAME, "rename the selected pet in the list; Enter saves, Esc cancels"),
+    (Context::Global, "", EDIT_BIRTHDATE, "edit the birthdate"),
+    (Context::Global, "", EDIT_AGE, "edit the age, as years and months like 1y6m"),
+    (Context::Global, "", ASSIGN_OWNER, "set the owner"),
+    (Context::Global, "", ADD_RECORD, "add a medical record"),
+    (Context::Global, "", ADD_WEIGHT, "record the weight of the pet today"),
+    (Context::Global, "", EDIT_MICROCHIP, "set the 15-digit microchip number"),
+    (Context::Global, "", EDIT_BREED, "set the breed, with Tab suggesting dog and cat breeds"),
+    (Context::Global, "", CYCLE_STATUS, "move the pet to the next adoption status"),
+    (Context::Global, "", EDIT_PHOTO, "set the path of the pet's photo"),
+    (Context::Global, "", EDIT_ATTRIBUTE, "set or remove a custom attribute, like diet=grain-free"),
+    (Context::Global, "", COPY_PET, "copy the pet under a new name"),
+    (Context::Tab(MenuItem::Pets), "", YANK, "copy the pet to the clipboard as JSON"),
+    (Context::Tab(MenuItem::Pets), "", YANK_SUMMARY, "copy a one-line summary of the pet to the clipboard"),
+    (Context::Global, "", YANK_RING, "list the last 10 pets yanked or cut, to add one again as a new pet"),
+    (Context::Global, "", SCROLL_RECORDS_DOWN, "scroll the medical records down"),
+    (Context::Global, "", SCROLL_RECORDS_UP, "scroll the medical records up"),
+    (Context::Global, "Ctrl+", NEXT, "scroll the notes down"),
+    (Context::Global, "Ctrl+", PREVIOUS, "scroll the notes up"),
+    (Context::Global, "Alt+", NEXT, "scroll the detail pane down"),
+    (Context::Global, "Alt+", PREVIOUS, "scroll the detail pane up"),
+    (Context::Tab(MenuItem::Pets), "", NARROW_LIST, "narrow the pet list (also Ctrl+h)"),
+    (Context::Tab(MenuItem::Pets), "", WIDEN_LIST, "widen the pet list (also Ctrl+l)"),
+    (Context::Tab(MenuItem::Pets), "", CHOOSE_COLUMNS, "choose the columns of the pet list"),
+    (Context::Tab(MenuItem::Pets), "", CATEGORIES, "rename a category or merge several, on every pet"),
+    (Context::Tab(MenuItem::Pets), "", CUT, "cut the selected pet, to move it elsewhere in the DB's order"),
+    (Context::Tab(MenuItem::Pets), "", PASTE_AFTER, "move the cut pet after the selected one (not while sorted)"),
+    (Context::Tab(MenuItem::Pets), "", PASTE_BEFORE, "move the cut pet before the selected one (not while sorted)"),
+    (Context::Tab(MenuItem::Pets), "", BATCH_EDIT, "set a field on every marked pet, or every shown pet if filtered"),
+    (Context::Global, "Ctrl+", QUICK_OPEN, "jump to a pet by name"),
+    (Context::Global, "Ctrl+", JUMP_BACK, "go back to where the selection was before the last jump (quick open, a reminder, a search or a category)"),
+    (Context::Global, "Ctrl+", JUMP_FORWARD, "go forward again after going back"),
+    (Context::Global, "Ctrl+", RECORD_MACRO, "followed by a letter, record what you do into that register; again to stop"),
+    (Context::Global, "", REPLAY_MACRO, "followed by a letter, replay that register; @@ replays the last one again"),
+    (Context::Global, "", PALETTE, "type a command such as `sort age desc`; Tab completes, Up and Down go through earlier ones"),
+    (Context::Global, "", INTEGRITY, "show the problems found in the DB at startup, checking it again"),
+    (Context::Tab(MenuItem::Duplicates), "", MERGE, "merge the selected group"),
+    (Context::Tab(MenuItem::Trash), "", RESTORE, "restore the selected pet"),
+    (Context::Tab(MenuItem::Trash), "", PURGE, "delete the selected pet for good (also dd)"),
+    (Context::Question, "", CONFIRM, "answer a question with yes; any other key cancels"),
+    (Context::Global, "", QUIT, "quit (also Ctrl+c)"),
+];
+
+/// How a binding is written for people: `Ctrl+p`, `Space`, `Tab`.
+pub fn key_name(modifier: &str, key: char) -> String {
+    let key = match key {
+        ' ' => "Space".to_owned(),
+        TAB => "Tab".to_owned(),
+        ESC => "Esc".to_owned(),
+        ENTER => "Enter".to_owned(),
+        key => key.to_string(),
+    };
+    format!("{}{}", modifier, key)
+}
+
+/// The keys that change the DB, disabled in read-only mode.
+pub const EDITS: [char; 26] = [
+    ADD,
+    DELETE,
+    EDIT_NOTES,
+    RENAME,
+    EDIT_BIRTHDATE,
+    EDIT_AGE,
+    ASSIGN_OWNER,
+    ADD_RECORD,
+    ADD_WEIGHT,
+    EDIT_MICROCHIP,
+    EDIT_BREED,
+    CYCLE_STATUS,
+    EDIT_PHOTO,
+    EDIT_ATTRIBUTE,
+    IMPORT_PETS,
+    MERGE_DB,
+    RESTORE_BACKUP,
+    COPY_PET,
+    CUT,
+    PASTE_AFTER,
+    PASTE_BEFORE,
+    BATCH_EDIT,
+    CATEGORIES,
+    RESTORE,
+    PURGE,
+    MERGE,
+];
+/// Used with Ctrl.
+pub const QUICK_OPEN: char = 'p';
+/// Used with Ctrl.
+pub const JUMP_BACK: char = 'o';
+/// Used with Ctrl. Not `i` as in vim, which terminals send as Tab.
+pub const JUMP_FORWARD: char = 'n';
+/// Used with Ctrl.
+pub const RECORD_MACRO: char = 'r';
+/// Used with Ctrl.
+pub const QUICK_ADD: char = 'a';
+/// Used with Ctrl.
+pub const NARROW_LIST_CTRL: char = 'h';
+/// Used with Ctrl.
+pub const WIDEN_LIST_CTRL: char = 'l';
+/// Used with Ctrl. Left out of `BINDINGS` as it is only for debugging.
+pub const DRAW_RATE: char = 'g';
+/// The number of the function key. Left out of `BINDINGS` as it is only for
+/// debugging.
+pub const DIAGNOSTICS: u8 = 12;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two of `bindings` with the same key in the same context, as an error
+    /// naming what both do.
+    fn duplicate(bindings: &[(Context, &str, char, &str)]) -> Option<String> {
+        bindings.iter().enumerate().find_map(|(index, first)| {
+            let (context, modifier, key, description) = *first;
+            bindings[index + 1..]
+                .iter()
+                .find(|other| other.0 == context && other.1 == modifier && other.2 == key)
+                .map(|other| {
+                    format!(
+                        "{} is bound twice {}: '{}' and '{}'",
+                        key_name(modifier, key),
+                        context.describe(),
+                        description,
+                        other.3
+                    )
+                })
+        })
+    }
+    #[test]
+    fn no_key_is_bound_twice_in_the_same_context() {
+        assert_eq!(duplicate(&BINDINGS), None);
+    }
+
+    #[test]
+    fn a_key_bound_twice_in_a_context_names_both() {
+        let bindings = [
+            (Context::Global, "", 'a', "add"),
+            (Context::Tab(MenuItem::Pets), "", 'a', "archive"),
+            (Context::Global, "Ctrl+", 'a', "quick add"),
+            (Context::Tab(MenuItem::Pets), "", 'b', "batch"),
+            (Context::Tab(MenuItem::Pets), "", 'a', "adopt"),
+        ];
+        assert_eq!(
+            duplicate(&bindings).as_deref(),
+            Some("a is bound twice on the Pets tab: 'archive' and 'adopt'")
+        );
+        assert_eq!(duplicate(&bindings[..4]), None);
+    }
+
+    #[test]
+    fn keys_are_named_as_written() {
+        assert_eq!(key_name("Ctrl+", 'p'), "Ctrl+p");
+        assert_eq!(key_name("", ' '), "Space");
+        assert_eq!(key_name("", TAB), "Tab");
+        assert_eq!(key_name("", ESC), "Esc");
+        assert_eq!(key_name("", ENTER), "Enter");
+        assert_eq!(key_name("z", GROUP), format!("z{}", GROUP));
+    }
+}
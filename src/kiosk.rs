@@ -0,0 +1,114 @@
+use crate::action::Action;
+use crate::idle::IdleLock;
+use crate::MenuItem;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::time::{Duration, Instant};
+
+/// The tabs a kiosk shows, of those the config turns on.
+pub const TABS: [MenuItem; 2] = [MenuItem::Pets, MenuItem::Stats];
+
+/// `--kiosk`: a read-only UI for visitors to browse on a shared terminal.
+/// Only browsing keys work, the Pets tab shows the list and the pet without
+/// records and weights, and a while without input puts the Pets tab back as
+/// it started. Typing the `kiosk_unlock` characters leaves kiosk mode for
+/// the rest of the session.
+pub struct Kiosk {
+    /// Empty if kiosk mode can't be left.
+    unlock: String,
+    /// The last characters typed, as many as `unlock` has.
+    typed: String,
+    /// Counts the time without input like the idle lock, to reset instead.
+    idle: IdleLock,
+    /// Whether the session was read-only before, and its tabs, to go back
+    /// to on unlocking.
+    pub read_only: bool,
+    pub tabs: Vec<MenuItem>,
+}
+
+impl Kiosk {
+    pub fn new(
+        unlock: &str,
+        reset_after: Option<Duration>,
+        read_only: bool,
+        tabs: Vec<MenuItem>,
+        now: Instant,
+    ) -> Kiosk {
+        Kiosk {
+            unlock: unlock.to_owned(),
+            typed: String::new(),
+            idle: IdleLock::new(reset_after, now),
+            read_only,
+            tabs,
+        }
+    }
+
+    /// Feeds a key press through. Returns whether it completed the unlock
+    /// characters.
+    pub fn input(&mut self, key: KeyEvent, now: Instant) -> bool {
+        self.idle.input(now);
+        match key.code {
+            KeyCode::Char(typed) if (key.modifiers - KeyModifiers::SHIFT).is_empty() => {
+                self.typed.push(typed)
+            }
+            _ => {
+                self.typed.clear();
+                return false;
+            }
+        }
+        let excess = self
+            .typed
+            .chars()
+            .count()
+            .saturating_sub(self.unlock.chars().count());
+        self.typed = self.typed.chars().skip(excess).collect();
+        !self.unlock.is_empty() && self.typed == self.unlock
+    }
+
+    /// Whether the UI was left alone long enough to be reset. It then
+    /// counts again from now, so an untouched kiosk is reset once.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let idle = self.idle.tick(now);
+        if idle {
+            self.idle.input(now);
+        }
+        idle
+    }
+}
+
+/// Whether `action` only looks around, and so works in a kiosk: moving
+/// through lists and tabs, searching, filtering and sorting. Changing the DB,
+/// exporting, copying, quitting and the tools for the staff don't.
+pub fn allows(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::OpenQuickOpen
+            | Action::ToggleGrouping
+            | Action::ToggleGroup
+            | Action::JumpBack
+            | Action::JumpForward
+            | Action::Open
+            | Action::CycleStatsSort
+            | Action::ShowTab(_)
+            | Action::ShowNthTab(_)
+            | Action::NextTab
+            | Action::PreviousTab
+            | Action::CountDigit(_)
+            | Action::Next
+            | Action::Previous
+            | Action::First
+            | Action::Last
+            | Action::CycleCategory
+            | Action::CycleStatusFilter
+            | Action::CycleSort
+            | Action::ReverseSort
+            | Action::Search
+            | Action::ScrollNotesDown
+            | Action::ScrollNotesUp
+            | Action::ScrollDetailDown
+            | Action::ScrollDetailUp
+            | Action::CycleAgeBuckets
+            | Action::WidenGrowth
+            | Action::NarrowGrowth
+            | Action::ToggleGrowthTotal
+    )
+}
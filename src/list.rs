@@ -0,0 +1,569 @@
+use crate::fields::PetField;
+use crate::strings::{self, Lang};
+use crate::{attributes, extra_fields, text, Owner, Pet};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum ListFormat {
+    /// Aligned columns for reading. Tabs and line breaks in values are
+    /// shown as `\t` and `\n`, so each pet stays on one line.
+    #[default]
+    Table,
+    /// The columns of the table separated by tabs, without padding, for awk
+    /// and cut. Tabs, line breaks and backslashes in values are written as
+    /// `\t`, `\n`, `\r` and `\\`.
+    Tsv,
+    /// The pets as stored in the DB, as a JSON array on one line; extra
+    /// fields only if they are exported.
+    Json,
+    /// One row per pet, with the columns of the table, the breed and then
+    /// one per attribute key, and per extra field if they are exported.
+    Csv,
+    /// One row per weigh-in: the pet's id and name, the date and the weight
+    /// in grams.
+    #[value(name = "weights-csv")]
+    WeightsCsv,
+}
+
+const COLUMNS: [PetField; 5] = [
+    PetField::Id,
+    PetField::Name,
+    PetField::Category,
+    PetField::Age,
+    PetField::Created,
+];
+
+/// `extra_fields` is whether JSON and CSV have the fields pet-cli doesn't
+/// know; see `extra_fields`. `max_width` cuts table cells wider than that,
+/// ending them in `…`.
+pub fn render(
+    format: ListFormat,
+    pets: &[&Pet],
+    now: DateTime<Utc>,
+    extra_fields: bool,
+    max_width: Option<usize>,
+) -> String {
+    match format {
+        ListFormat::Table => render_table(pets, now, max_width),
+        ListFormat::Tsv => {
+            let header = COLUMNS.map(|column| strings::text(Lang::En, column.label()).to_owned());
+            let mut out = tsv_line(&header);
+            for pet in pets {
+                out.push_str(&tsv_line(&row(pet, now)));
+            }
+            out
+        }
+        ListFormat::Json => {
+            let mut out = if extra_fields || pets.iter().all(|pet| pet.extra.is_empty()) {
+                serde_json::to_string(pets)
+            } else {
+                let known: Vec<Pet> = pets
+                    .iter()
+                    .map(|pet| Pet {
+                        extra: Default::default(),
+                        ..(*pet).clone()
+                    })
+                    .collect();
+                serde_json::to_string(&known)
+            }
+            .expect("pets serialize");
+            out.push('\n');
+            out
+        }
+        ListFormat::Csv => render_csv(pets, now, extra_fields),
+        ListFormat::WeightsCsv => render_weights_csv(pets),
+    }
+}
+
+/// Only `fields` of each pet, in that order, as the detail pane shows them.
+/// Fields a pet doesn't have are empty, or `null` in JSON. Not for
+/// `WeightsCsv`, which has columns of its own.
+pub fn render_fields(
+    format: ListFormat,
+    pets: &[&Pet],
+    fields: &[PetField],
+    owners: &[Owner],
+    now: DateTime<Utc>,
+    max_width: Option<usize>,
+) -> String {
+    let values = |pet: &Pet| -> Vec<Option<String>> {
+        fields
+            .iter()
+            .map(|field| field.value(pet, owners, now, Lang::En))
+            .collect()
+    };
+    let header: Vec<String> = fields
+        .iter()
+        .map(|field| strings::text(Lang::En, field.label()).to_owned())
+        .collect();
+    let rows = || {
+        pets.iter().map(|pet| {
+            values(pet)
+                .into_iter()
+                .map(Option::unwrap_or_default)
+                .collect::<Vec<_>>()
+        })
+    };
+    match format {
+        ListFormat::Table => {
+            render_table_rows(fields, &header, &rows().collect::<Vec<_>>(), max_width)
+        }
+        ListFormat::Tsv => {
+            let mut out = tsv_line(&header);
+            for row in rows() {
+                out.push_str(&tsv_line(&row));
+            }
+            out
+        }
+        ListFormat::Json => {
+            let objects: Vec<String> = pets
+                .iter()
+                .map(|pet| json_object(fields, values(pet)))
+                .collect();
+            format!("[{}]\n", objects.join(","))
+        }
+        ListFormat::Csv | ListFormat::WeightsCsv => {
+            let mut out = csv_line(&header);
+            for row in rows() {
+                out.push_str(&csv_line(&row));
+            }
+            out
+        }
+    }
+}
+
+/// The keys in the order of `fields`, which a `serde_json::Map` would sort.
+fn json_object(fields: &[PetField], values: Vec<Option<String>>) -> String {
+    let entries: Vec<String> = fields
+        .iter()
+        .zip(values)
+        .map(|(field, value)| {
+            format!(
+                "{}:{}",
+                serde_json::to_string(field.key()).expect("strings serialize"),
+                serde_json::to_string(&value).expect("strings serialize")
+            )
+        })
+        .collect();
+    format!("{{{}}}", entries.join(","))
+}
+
+/// The columns of the table and the breed, followed by a column per
+/// attribute key any of `pets` has, sorted, empty for the pets without it,
+/// and then the same for extra fields if `extra_fields`.
+fn render_csv(pets: &[&Pet], now: DateTime<Utc>, extra_fields: bool) -> String {
+    let keys = attributes::keys(pets);
+    let extra_keys = if extra_fields {
+        extra_fields::keys(pets)
+    } else {
+        Vec::new()
+    };
+    let header: Vec<String> = COLUMNS
+        .iter()
+        .map(|column| strings::text(Lang::En, column.label()))
+        .chain(["Breed"])
+        .chain(keys.iter().copied())
+        .chain(extra_keys.iter().copied())
+        .map(|column| column.to_string())
+        .collect();
+    let mut out = csv_line(&header);
+    for pet in pets {
+        let mut cells = Vec::from(row(pet, now));
+        cells.push(pet.breed.clone().unwrap_or_default());
+        cells.extend(
+            keys.iter()
+                .map(|key| pet.attributes.get(*key).cloned().unwrap_or_default()),
+        );
+        cells.extend(extra_keys.iter().map(|key| {
+            pet.extra
+                .get(*key)
+                .map(extra_fields::display)
+                .unwrap_or_default()
+        }));
+        out.push_str(&csv_line(&cells));
+    }
+    out
+}
+
+fn render_weights_csv(pets: &[&Pet]) -> String {
+    let mut out = csv_line(&["ID", "Name", "Date", "Grams"].map(str::to_owned));
+    for pet in pets {
+        let mut weights: Vec<_> = pet.weights.iter().collect();
+        weights.sort_by_key(|entry| entry.date);
+        for entry in weights {
+            out.push_str(&csv_line(&[
+                pet.id.to_string(),
+                pet.name.clone(),
+                entry.date.format("%Y-%m-%d").to_string(),
+                entry.grams.to_string(),
+            ]));
+        }
+    }
+    out
+}
+
+/// Quotes the cells that need it, as RFC 4180 describes.
+fn csv_line(cells: &[String]) -> String {
+    let cells: Vec<String> = cells
+        .iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect();
+    format!("{}\n", cells.join(","))
+}
+
+fn tsv_line(cells: &[String]) -> String {
+    let cells: Vec<String> = cells.iter().map(|cell| escape(cell, true)).collect();
+    format!("{}\n", cells.join("\t"))
+}
+
+/// `value` on one line, with tabs and line breaks written as `\t`, `\n` and
+/// `\r`, and backslashes doubled if `backslashes`, so that the escapes can be
+/// told from the text.
+fn escape(value: &str, backslashes: bool) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' if backslashes => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn row(pet: &Pet, now: DateTime<Utc>) -> [String; 5] {
+    [
+        pet.id.to_string(),
+        pet.name.clone(),
+        pet.category.clone(),
+        pet.display_age(now.date_naive()),
+        pet.created_at.format("%Y-%m-%d").to_string(),
+    ]
+}
+
+fn render_table(pets: &[&Pet], now: DateTime<Utc>, max_width: Option<usize>) -> String {
+    let rows: Vec<Vec<String>> = pets.iter().map(|pet| row(pet, now).to_vec()).collect();
+    let header = COLUMNS.map(|column| strings::text(Lang::En, column.label()).to_owned());
+    render_table_rows(&COLUMNS, &header, &rows, max_width)
+}
+
+/// Cells of numeric `columns` are aligned to the right, the rest to the
+/// left. Only the cells are cut to `max_width`, not the header.
+fn render_table_rows(
+    columns: &[PetField],
+    header: &[String],
+    rows: &[Vec<String>],
+    max_width: Option<usize>,
+) -> String {
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| {
+                    let cell = escape(cell, false);
+                    match max_width {
+                        Some(max) => text::truncate(&cell, max),
+                        None => cell,
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let mut widths: Vec<usize> = header.iter().map(|cell| text::width(cell)).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(text::width(cell));
+        }
+    }
+    let mut out = String::new();
+    for row in std::iter::once(header).chain(rows.iter().map(Vec::as_slice)) {
+        let cells: Vec<_> = row
+            .iter()
+            .zip(&widths)
+            .zip(columns)
+            .map(|((cell, width), column)| {
+                if column.right_aligned() {
+                    text::pad_left(cell, *width)
+                } else {
+                    text::pad(cell, *width)
+                }
+            })
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use chrono::TimeZone;
+
+    const FIELDS: [PetField; 4] = [
+        PetField::Name,
+        PetField::Breed,
+        PetField::Category,
+        PetField::Id,
+    ];
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap()
+    }
+
+    fn shelter() -> Vec<Pet> {
+        let mut rex = pet(4, "Rex", "dogs");
+        rex.breed = Some("Collie, rough".to_owned());
+        vec![rex, pet(2, "Coco", "cats")]
+    }
+
+    #[test]
+    fn csv_and_tsv_keep_the_order_of_the_fields() {
+        let pets = shelter();
+        let pets: Vec<&Pet> = pets.iter().collect();
+        assert_eq!(
+            render_fields(ListFormat::Csv, &pets, &FIELDS, &[], now(), None),
+            "Name,Breed,Category,ID\nRex,\"Collie, rough\",dogs,4\nCoco,,cats,2\n"
+        );
+        assert_eq!(
+            render_fields(ListFormat::Tsv, &pets, &FIELDS, &[], now(), None),
+            "Name\tBreed\tCategory\tID\nRex\tCollie, rough\tdogs\t4\nCoco\t\tcats\t2\n"
+        );
+    }
+
+    #[test]
+    fn json_keeps_the_order_of_the_fields_and_has_null_for_missing_ones() {
+        let pets = shelter();
+        let pets: Vec<&Pet> = pets.iter().collect();
+        let out = render_fields(ListFormat::Json, &pets, &FIELDS, &[], now(), None);
+        assert_eq!(
+            out,
+            "[{\"name\":\"Rex\",\"breed\":\"Collie, rough\",\"category\":\"dogs\",\"id\":\"4\"},\
+             {\"name\":\"Coco\",\"breed\":null,\"category\":\"cats\",\"id\":\"2\"}]\n"
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(parsed.as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn the_table_has_only_the_fields_in_their_order() {
+        let pets = shelter();
+        let pets: Vec<&Pet> = pets.iter().collect();
+        let fields = [PetField::Age, PetField::Name];
+        let out = render_fields(ListFormat::Table, &pets, &fields, &[], now(), None);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3, "{}", out);
+        let header: Vec<&str> = lines[0].split_whitespace().collect();
+        assert_eq!(header, ["Age", "Name"]);
+        let age = PetField::Age.value(pets[0], &[], now(), Lang::En).unwrap();
+        assert!(lines[1].starts_with(&age), "{}", out);
+        assert!(lines[1].trim_end().ends_with("Rex"), "{}", out);
+        assert!(!out.contains("dogs"), "{}", out);
+    }
+
+    #[test]
+    fn numeric_columns_line_up_on_the_right() {
+        let pets = [pet(7, "Rex", "dogs"), pet(123, "Coco", "cats")];
+        let pets: Vec<&Pet> = pets.iter().collect();
+        let fields = [PetField::Name, PetField::Id];
+        let out = render_fields(ListFormat::Table, &pets, &fields, &[], now(), None);
+        assert_eq!(out, "Name   ID\nRex     7\nCoco  123\n");
+        let out = render(ListFormat::Table, &pets, now(), false, None);
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[1].starts_with("  7  Rex"), "{}", out);
+        assert!(lines[2].starts_with("123  Coco"), "{}", out);
+    }
+
+    /// Names with what each format has to escape or measure.
+    fn awkward() -> Vec<Pet> {
+        let mut pets = vec![
+            pet(3, "Tab\tand\nline", "cats"),
+            pet(12, "ねこ🐱 \"Neko\"", "cats"),
+            pet(7, "Back\\slash, comma", "dogs"),
+        ];
+        pets[1].age.months = 30;
+        pets
+    }
+
+    #[test]
+    fn golden_table() {
+        let pets = awkward();
+        let pets: Vec<&Pet> = pets.iter().collect();
+        assert_eq!(
+            render(ListFormat::Table, &pets, now(), false, None),
+            "\
+ID  Name               Category      Age  Created
+ 3  Tab\\tand\\nline     cats          1 y  2024-01-01
+12  ねこ🐱 \"Neko\"      cats      2 y 6 m  2024-01-01
+ 7  Back\\slash, comma  dogs          1 y  2024-01-01
+"
+        );
+    }
+
+    #[test]
+    fn golden_table_cut_to_a_width() {
+        let pets = awkward();
+        let pets: Vec<&Pet> = pets.iter().collect();
+        assert_eq!(
+            render(ListFormat::Table, &pets, now(), false, Some(6)),
+            "\
+ID  Name    Category     Age  Created
+ 3  Tab\\t…  cats         1 y  2024-…
+12  ねこ…   cats      2 y 6…  2024-…
+ 7  Back\\…  dogs         1 y  2024-…
+"
+        );
+    }
+
+    #[test]
+    fn golden_tsv() {
+        let pets = awkward();
+        let pets: Vec<&Pet> = pets.iter().collect();
+        assert_eq!(
+            render(ListFormat::Tsv, &pets, now(), false, None),
+            "\
+ID\tName\tCategory\tAge\tCreated
+3\tTab\\tand\\nline\tcats\t1 y\t2024-01-01
+12\tねこ🐱 \"Neko\"\tcats\t2 y 6 m\t2024-01-01
+7\tBack\\\\slash, comma\tdogs\t1 y\t2024-01-01
+"
+        );
+    }
+
+    #[test]
+    fn golden_csv() {
+        let pets = awkward();
+        let pets: Vec<&Pet> = pets.iter().collect();
+        let csv = render(ListFormat::Csv, &pets, now(), false, None);
+        let mut lines = csv.lines();
+        let header = lines.next().unwrap();
+        assert_eq!(header, "ID,Name,Category,Age,Created,Breed");
+        assert!(csv.contains("\n3,\"Tab\tand\nline\",cats,"), "{}", csv);
+        assert!(
+            csv.contains("\n12,\"ねこ🐱 \"\"Neko\"\"\",cats,"),
+            "{}",
+            csv
+        );
+        assert!(csv.contains("\n7,\"Back\\slash, comma\",dogs,"), "{}", csv);
+    }
+
+    #[test]
+    fn golden_json() {
+        let pets = awkward();
+        let pets: Vec<&Pet> = pets.iter().collect();
+        let json = render(ListFormat::Json, &pets, now(), false, None);
+        assert!(
+            json.ends_with("}]\n") && json.lines().count() == 1,
+            "{}",
+            json
+        );
+        let parsed: Vec<Pet> = serde_json::from_str(&json).unwrap();
+        let names: Vec<&str> = parsed.iter().map(|pet| pet.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["Tab\tand\nline", "ねこ🐱 \"Neko\"", "Back\\slash, comma"]
+        );
+    }
+
+    #[test]
+    fn no_pets_give_only_the_header() {
+        for (format, expected) in [
+            (ListFormat::Csv, "Name,Breed,Category,ID\n"),
+            (ListFormat::Tsv, "Name\tBreed\tCategory\tID\n"),
+            (ListFormat::Json, "[]\n"),
+        ] {
+            assert_eq!(
+                render_fields(format, &[], &FIELDS, &[], now(), None),
+                expected,
+                "{:?}",
+                format
+            );
+        }
+        let table = render_fields(ListFormat::Table, &[], &FIELDS, &[], now(), None);
+        assert_eq!(table.lines().count(), 1, "{}", table);
+    }
+
+    #[test]
+    fn csv_has_a_column_per_attribute_key_aligned_across_pets() {
+        let mut rex = pet(4, "Rex", "dogs");
+        rex.attributes.insert("vet".to_owned(), "Dr. Ng".to_owned());
+        let mut coco = pet(2, "Coco", "cats");
+        coco.attributes
+            .insert("diet".to_owned(), "fish, dry".to_owned());
+        let bun = pet(7, "Bun", "rabbits");
+        let out = render(ListFormat::Csv, &[&rex, &coco, &bun], now(), false, None);
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].ends_with(",Breed,diet,vet"), "{}", out);
+        assert!(lines[1].starts_with("4,Rex,"), "{}", out);
+        assert!(lines[1].ends_with(",,,Dr. Ng"), "{}", out);
+        assert!(lines[2].ends_with(",,\"fish, dry\","), "{}", out);
+        assert!(lines[3].ends_with(",,,"), "{}", out);
+        let columns = |line: &str| line.matches(',').count();
+        assert_eq!(columns(lines[0]), columns(lines[1]));
+        assert_eq!(columns(lines[0]), columns(lines[3]));
+        assert_eq!(columns(lines[0]) + 1, columns(lines[2]));
+    }
+
+    #[test]
+    fn extra_fields_are_exported_only_when_asked_for() {
+        let mut rex = pet(4, "Rex", "dogs");
+        rex.extra.insert("vet".to_owned(), "Dr. Smith".into());
+        rex.extra
+            .insert("insurance".to_owned(), serde_json::json!({"active": true}));
+        let coco = pet(2, "Coco", "cats");
+        let pets = [&rex, &coco];
+
+        let csv = render(ListFormat::Csv, &pets, now(), true, None);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].ends_with(",Breed,insurance,vet"), "{}", csv);
+        assert!(
+            lines[1].ends_with(",\"{\"\"active\"\":true}\",Dr. Smith"),
+            "{}",
+            csv
+        );
+        assert!(lines[2].ends_with(",,,"), "{}", csv);
+        let csv = render(ListFormat::Csv, &pets, now(), false, None);
+        assert!(!csv.contains("vet"), "{}", csv);
+
+        let json = |extra_fields| -> serde_json::Value {
+            serde_json::from_str(&render(ListFormat::Json, &pets, now(), extra_fields, None))
+                .unwrap()
+        };
+        assert_eq!(json(true)[0]["vet"], "Dr. Smith");
+        assert_eq!(json(true)[0]["insurance"]["active"], true);
+        assert!(json(false)[0].get("vet").is_none());
+        assert_eq!(json(false)[0]["name"], "Rex");
+    }
+
+    #[test]
+    fn owners_are_shown_by_name() {
+        let mut rex = pet(4, "Rex", "dogs");
+        rex.owner_id = Some(1);
+        let mut coco = pet(2, "Coco", "cats");
+        coco.owner_id = Some(9);
+        let owners = [Owner {
+            id: 1,
+            name: "Ann".to_owned(),
+            email: String::new(),
+        }];
+        let out = render_fields(
+            ListFormat::Csv,
+            &[&rex, &coco],
+            &[PetField::Owner],
+            &owners,
+            now(),
+            None,
+        );
+        assert_eq!(out, "Owner\nAnn\n#9 (unknown)\n");
+    }
+}
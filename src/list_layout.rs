@@ -0,0 +1,188 @@
+//! How the columns of the pet list share its width. Each column starts as
+//! wide as its widest cell on screen. When they don't all fit, the text
+//! columns (name, category, owner, created) are cut, widest first, down to
+//! their minimums, and their cells end in `…`. Only when even the minimums
+//! don't fit are columns left out, rightmost first; the name never is.
+
+use crate::fields::PetField;
+
+/// What a column of the list needs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColumnSpec {
+    /// The widest cell of the column among the rows on screen.
+    pub content: usize,
+    /// The narrowest the column may be cut to. The same as `content` for
+    /// columns that are never cut.
+    pub min: usize,
+    /// Kept however narrow the list gets.
+    pub required: bool,
+}
+
+impl ColumnSpec {
+    /// The spec of `column`, with cells up to `content` wide, or exactly
+    /// `fixed` wide when the config sets its width.
+    pub fn of(column: PetField, content: usize, fixed: Option<usize>) -> Self {
+        let required = column == PetField::Name;
+        if let Some(width) = fixed {
+            return ColumnSpec {
+                content: width,
+                min: width,
+                required,
+            };
+        }
+        let min = match column {
+            PetField::Name | PetField::Category | PetField::Owner => 4,
+            PetField::Created => 10,
+            _ => content,
+        };
+        ColumnSpec {
+            content,
+            min: min.min(content),
+            required,
+        }
+    }
+}
+
+/// The width of each of `specs` in a list `width` wide with a space between
+/// columns, or `None` for those left out. The kept columns can still be
+/// wider than `width` when the required ones are.
+pub fn allocate(width: usize, specs: &[ColumnSpec]) -> Vec<Option<usize>> {
+    let mut kept: Vec<usize> = (0..specs.len()).collect();
+    let needed = |kept: &[usize], each: fn(&ColumnSpec) -> usize| -> usize {
+        let cells: usize = kept.iter().map(|index| each(&specs[*index])).sum();
+        cells + kept.len().saturating_sub(1)
+    };
+    while needed(&kept, |spec| spec.min) > width {
+        match kept.iter().rposition(|index| !specs[*index].required) {
+            Some(position) => kept.remove(position),
+            None => break,
+        };
+    }
+    let mut widths: Vec<Option<usize>> = vec![None; specs.len()];
+    for index in &kept {
+        widths[*index] = Some(specs[*index].content);
+    }
+    let mut excess = needed(&kept, |spec| spec.content).saturating_sub(width);
+    while excess > 0 {
+        // The widest column that can still give up a cell; the rightmost of
+        // equally wide ones.
+        let widest = kept
+            .iter()
+            .filter(|index| widths[**index] > Some(specs[**index].min))
+            .max_by_key(|index| widths[**index]);
+        match widest {
+            Some(index) => {
+                widths[*index] = widths[*index].map(|width| width - 1);
+                excess -= 1;
+            }
+            None => break,
+        }
+    }
+    widths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(content: usize, min: usize) -> ColumnSpec {
+        ColumnSpec {
+            content,
+            min,
+            required: false,
+        }
+    }
+
+    /// Name, category, age and created, as the list has them.
+    fn columns() -> [ColumnSpec; 4] {
+        [
+            ColumnSpec {
+                required: true,
+                ..spec(10, 4)
+            },
+            spec(8, 4),
+            spec(3, 3),
+            spec(16, 10),
+        ]
+    }
+
+    #[test]
+    fn columns_that_fit_are_as_wide_as_their_cells() {
+        let all = [Some(10), Some(8), Some(3), Some(16)];
+        assert_eq!(allocate(200, &columns()), all);
+        assert_eq!(allocate(40, &columns()), all);
+    }
+
+    #[test]
+    fn the_widest_column_is_cut_first() {
+        assert_eq!(
+            allocate(39, &columns()),
+            [Some(10), Some(8), Some(3), Some(15)]
+        );
+        assert_eq!(
+            allocate(34, &columns()),
+            [Some(10), Some(8), Some(3), Some(10)]
+        );
+    }
+
+    #[test]
+    fn equally_wide_columns_are_cut_from_the_right() {
+        assert_eq!(
+            allocate(30, &columns()),
+            [Some(7), Some(7), Some(3), Some(10)]
+        );
+        assert_eq!(
+            allocate(31, &columns()),
+            [Some(8), Some(7), Some(3), Some(10)]
+        );
+    }
+
+    #[test]
+    fn columns_are_dropped_from_the_right_once_minimums_dont_fit() {
+        assert_eq!(
+            allocate(24, &columns()),
+            [Some(4), Some(4), Some(3), Some(10)]
+        );
+        assert_eq!(allocate(23, &columns()), [Some(10), Some(8), Some(3), None]);
+        assert_eq!(allocate(12, &columns()), [Some(6), Some(5), None, None]);
+        assert_eq!(allocate(9, &columns()), [Some(4), Some(4), None, None]);
+        assert_eq!(allocate(8, &columns()), [Some(8), None, None, None]);
+    }
+
+    #[test]
+    fn the_required_column_is_kept_even_when_too_wide() {
+        assert_eq!(allocate(3, &columns()), [Some(4), None, None, None]);
+        assert_eq!(allocate(0, &columns()), [Some(4), None, None, None]);
+    }
+
+    #[test]
+    fn without_columns_there_is_nothing_to_allocate() {
+        assert_eq!(allocate(80, &[]), []);
+        assert_eq!(allocate(0, &[spec(5, 5)]), [None]);
+    }
+
+    #[test]
+    fn only_text_columns_can_be_cut() {
+        assert_eq!(
+            ColumnSpec::of(PetField::Name, 20, None),
+            ColumnSpec {
+                content: 20,
+                min: 4,
+                required: true
+            }
+        );
+        assert_eq!(ColumnSpec::of(PetField::Category, 2, None), spec(2, 2));
+        assert_eq!(ColumnSpec::of(PetField::Owner, 12, None), spec(12, 4));
+        assert_eq!(ColumnSpec::of(PetField::Created, 16, None), spec(16, 10));
+        assert_eq!(ColumnSpec::of(PetField::Age, 7, None), spec(7, 7));
+        assert_eq!(ColumnSpec::of(PetField::Status, 11, None), spec(11, 11));
+    }
+
+    #[test]
+    fn a_configured_width_is_fixed() {
+        assert_eq!(ColumnSpec::of(PetField::Created, 16, Some(6)), spec(6, 6));
+        assert_eq!(ColumnSpec::of(PetField::Category, 3, Some(9)), spec(9, 9));
+        let fixed = [spec(20, 4), ColumnSpec::of(PetField::Category, 8, Some(8))];
+        assert_eq!(allocate(20, &fixed), [Some(11), Some(8)]);
+    }
+}
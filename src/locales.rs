@@ -0,0 +1,253 @@
+//! The categories and names generated pets get, by locale. English, German
+//! and Japanese are built in; a `<code>.json` file in the `locales` directory
+//! next to the config file adds a locale or replaces a built-in one.
+
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+const BUILT_IN: [(&str, &str); 3] = [
+    ("en", include_str!("../locales/en.json")),
+    ("de", include_str!("../locales/de.json")),
+    ("ja", include_str!("../locales/ja.json")),
+];
+
+/// A locale file, like `{"names": ["Luna"], "categories": [{"name": "cats",
+/// "names": ["Whiskers"]}]}`. Whatever it leaves out or empty is taken from
+/// English.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Locale {
+    /// For categories without names of their own.
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// In the order the generator picks from.
+    #[serde(default)]
+    pub categories: Vec<Category>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Category {
+    pub name: String,
+    #[serde(default)]
+    pub names: Vec<String>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        built_in(DEFAULT_LOCALE).expect("English is built in")
+    }
+}
+
+impl Locale {
+    /// The names pets of `category` get, or the locale's general ones.
+    pub fn pool(&self, category: &str) -> &[String] {
+        match self.categories.iter().find(|c| c.name == category) {
+            Some(category) if !category.names.is_empty() => &category.names,
+            _ => &self.names,
+        }
+    }
+
+    fn parse(content: &str) -> Result<Locale, String> {
+        let mut locale: Locale = serde_json::from_str(content).map_err(|err| err.to_string())?;
+        if locale.names.is_empty() || locale.categories.is_empty() {
+            let english = Locale::default();
+            if locale.names.is_empty() {
+                locale.names = english.names;
+            }
+            if locale.categories.is_empty() {
+                locale.categories = english.categories;
+            }
+        }
+        Ok(locale)
+    }
+}
+
+fn built_in(code: &str) -> Option<Locale> {
+    let (_, content) = BUILT_IN.iter().find(|(built_in, _)| *built_in == code)?;
+    Some(Locale::parse(content).expect("built-in locales are valid"))
+}
+
+/// Whether `code` can name a locale file: letters, digits, `-` and `_`.
+pub fn valid_code(code: &str) -> bool {
+    !code.is_empty()
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The locale `code`, from `user_dir` if it has a file for it, else built
+/// in, else English. Also returns a warning for a file that couldn't be used
+/// or a locale nobody has, which never stop the generator.
+pub fn load(code: &str, user_dir: Option<&Path>) -> (Locale, Option<String>) {
+    let mut warning = None;
+    if let Some(path) = user_dir.map(|dir| dir.join(format!("{}.json", code))) {
+        match fs::read_to_string(&path) {
+            Ok(content) => match Locale::parse(&content) {
+                Ok(locale) => return (locale, None),
+                Err(err) => warning = Some(format!("ignoring {}: {}", path.display(), err)),
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => warning = Some(format!("ignoring {}: {}", path.display(), err)),
+        }
+    }
+    if let Some(locale) = built_in(code) {
+        return (locale, warning);
+    }
+    let warning = match warning {
+        Some(warning) => format!("{}, using {}", warning, DEFAULT_LOCALE),
+        None => format!(
+            "no generator locale '{}', using {}; there are: {}",
+            code,
+            DEFAULT_LOCALE,
+            available(user_dir).join(", ")
+        ),
+    };
+    (Locale::default(), Some(warning))
+}
+
+/// The codes of the built-in locales and those in `user_dir`, sorted.
+pub fn available(user_dir: Option<&Path>) -> Vec<String> {
+    let mut codes: Vec<String> = BUILT_IN.iter().map(|(code, _)| code.to_string()).collect();
+    let entries = user_dir.and_then(|dir| fs::read_dir(dir).ok());
+    for entry in entries.into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            if let Some(code) = path.file_stem().and_then(|stem| stem.to_str()) {
+                codes.push(code.to_owned());
+            }
+        }
+    }
+    codes.sort();
+    codes.dedup();
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A `locales` directory of its own for each test, removed when dropped.
+    struct UserDir(PathBuf);
+
+    impl UserDir {
+        fn new(name: &str, files: &[(&str, &str)]) -> UserDir {
+            let dir = std::env::temp_dir().join(format!(
+                "pet-cli-locales-{}-{}",
+                name,
+                std::process::id()
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            for (file, content) in files {
+                fs::write(dir.join(file), content).unwrap();
+            }
+            UserDir(dir)
+        }
+    }
+
+    impl Drop for UserDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn category_names(locale: &Locale) -> Vec<&str> {
+        locale
+            .categories
+            .iter()
+            .map(|category| category.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn the_built_in_locales_have_their_own_categories() {
+        for (code, category) in [("en", "cats"), ("de", "Katzen"), ("ja", "猫")] {
+            let (locale, warning) = load(code, None);
+            assert_eq!(warning, None);
+            assert!(category_names(&locale).contains(&category), "{}", code);
+            assert!(!locale.names.is_empty());
+            for category in &locale.categories {
+                assert!(!locale.pool(&category.name).is_empty());
+            }
+        }
+        let (german, _) = load("de", None);
+        assert!(category_names(&german).contains(&"Hunde"));
+    }
+
+    #[test]
+    fn an_unknown_locale_falls_back_to_english_with_a_warning() {
+        let (locale, warning) = load("fr", None);
+        assert_eq!(category_names(&locale), category_names(&Locale::default()));
+        assert_eq!(
+            warning.unwrap(),
+            "no generator locale 'fr', using en; there are: de, en, ja"
+        );
+    }
+
+    #[test]
+    fn a_user_file_adds_a_locale_and_fills_gaps_from_english() {
+        let dir = UserDir::new(
+            "fr",
+            &[(
+                "fr.json",
+                r#"{"categories": [{"name": "chats", "names": ["Minou"]}]}"#,
+            )],
+        );
+        let (locale, warning) = load("fr", Some(&dir.0));
+        assert_eq!(warning, None);
+        assert_eq!(category_names(&locale), ["chats"]);
+        assert_eq!(locale.pool("chats"), ["Minou".to_owned()]);
+        assert_eq!(locale.names, Locale::default().names);
+        assert_eq!(available(Some(&dir.0)), ["de", "en", "fr", "ja"]);
+    }
+
+    #[test]
+    fn a_user_file_replaces_a_built_in_locale() {
+        let dir = UserDir::new("de", &[("de.json", r#"{"names": ["Bello"]}"#)]);
+        let (locale, warning) = load("de", Some(&dir.0));
+        assert_eq!(warning, None);
+        assert_eq!(locale.names, ["Bello".to_owned()]);
+        assert_eq!(available(Some(&dir.0)), ["de", "en", "ja"]);
+    }
+
+    #[test]
+    fn a_malformed_user_file_warns_and_uses_the_built_in_locale() {
+        let dir = UserDir::new("bad", &[("de.json", "{\"names\": [")]);
+        let (locale, warning) = load("de", Some(&dir.0));
+        assert!(category_names(&locale).contains(&"Katzen"));
+        let warning = warning.unwrap();
+        assert!(warning.starts_with("ignoring "), "{}", warning);
+        assert!(warning.contains("de.json"), "{}", warning);
+    }
+
+    #[test]
+    fn a_malformed_user_file_for_an_unknown_locale_falls_back_to_english() {
+        let dir = UserDir::new("bad-fr", &[("fr.json", r#"{"names": "Minou"}"#)]);
+        let (locale, warning) = load("fr", Some(&dir.0));
+        assert_eq!(locale.names, Locale::default().names);
+        assert!(warning.unwrap().ends_with(", using en"));
+    }
+
+    #[test]
+    fn only_json_files_are_locales() {
+        let dir = UserDir::new("other", &[("notes.txt", ""), ("es.json", "{}")]);
+        assert_eq!(available(Some(&dir.0)), ["de", "en", "es", "ja"]);
+        assert_eq!(available(None), ["de", "en", "ja"]);
+    }
+
+    #[test]
+    fn codes_are_letters_digits_dashes_and_underscores() {
+        for code in ["de", "pt-BR", "en_GB", "x1"] {
+            assert!(valid_code(code), "{}", code);
+        }
+        for code in ["", "../de", "de.json", "d e"] {
+            assert!(!valid_code(code), "{}", code);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Debug logging to a file. The terminal belongs to the UI while it runs, so
+//! log records never go to stdout or stderr.
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Used when `RUST_LOG` is set but no `--log-file` was given.
+pub const DEFAULT_LOG_FILE: &str = "pet-cli.log";
+
+struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{} {:<5} {}: {}",
+                chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Starts logging if `log_file` is given or `RUST_LOG` is set. The level comes
+/// from `RUST_LOG` (`error`, `warn`, `info`, `debug` or `trace`) and defaults
+/// to `debug`. Returns the file being logged to.
+pub fn init(log_file: Option<&Path>) -> io::Result<Option<PathBuf>> {
+    let env_level = std::env::var("RUST_LOG").ok();
+    let path = match (log_file, &env_level) {
+        (Some(path), _) => path.to_owned(),
+        (None, Some(_)) => PathBuf::from(DEFAULT_LOG_FILE),
+        (None, None) => return Ok(None),
+    };
+    let level = env_level
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Debug);
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let logger = FileLogger {
+        file: Mutex::new(file),
+    };
+    // Only fails if a logger is already installed, which `main` never does.
+    let _ = log::set_boxed_logger(Box::new(logger));
+    log::set_max_level(level);
+    Ok(Some(path))
+}
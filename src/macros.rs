@@ -0,0 +1,214 @@
+//! Recording what was done into a register and doing it again, like vim's
+//! `q` and `@`. Macros hold the actions the keys resolved to, not the keys,
+//! so they still work after a binding changes, and the answers typed into
+//! prompts, so a macro that edits a field is complete.
+
+use crate::action::Action;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    Action(Action),
+    /// The text submitted to the prompt the previous step opened.
+    Answer(String),
+}
+
+/// Which command is waiting for its register letter.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pending {
+    Record,
+    Replay,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Replayed {
+    Completed,
+    /// The step at this index failed and the ones after it were skipped.
+    StoppedAt(usize),
+}
+
+/// The registers of the session and the recording in progress, if any.
+#[derive(Default)]
+pub struct Macros {
+    registers: HashMap<char, Vec<Step>>,
+    recording: Option<(char, Vec<Step>)>,
+    pending: Option<Pending>,
+    last_replayed: Option<char>,
+}
+
+impl Macros {
+    /// Whether `register` can hold a macro.
+    pub fn is_register(register: char) -> bool {
+        register.is_ascii_lowercase()
+    }
+
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    pub fn wait_for_register(&mut self, pending: Pending) {
+        self.pending = Some(pending);
+    }
+
+    /// Whether a command is waiting for its register letter.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn take_pending(&mut self) -> Option<Pending> {
+        self.pending.take()
+    }
+
+    /// Starts recording into `register`, replacing what it held once stopped.
+    pub fn start(&mut self, register: char) {
+        self.recording = Some((register, Vec::new()));
+    }
+
+    /// Stops recording and stores the macro, returning its register and
+    /// length.
+    pub fn stop(&mut self) -> Option<(char, usize)> {
+        let (register, steps) = self.recording.take()?;
+        let length = steps.len();
+        self.registers.insert(register, steps);
+        Some((register, length))
+    }
+
+    pub fn record(&mut self, step: Step) {
+        if let Some((_, steps)) = &mut self.recording {
+            steps.push(step);
+        }
+    }
+
+    /// The macro in `register`, where `@` stands for the last one replayed.
+    pub fn steps(&mut self, register: char) -> Option<(char, Vec<Step>)> {
+        let register = match register {
+            '@' => self.last_replayed?,
+            register => register,
+        };
+        let steps = self.registers.get(&register)?.clone();
+        self.last_replayed = Some(register);
+        Some((register, steps))
+    }
+}
+
+/// Runs `steps` in order with `run`, which says whether a step succeeded,
+/// stopping at the first that didn't.
+pub fn replay(steps: &[Step], mut run: impl FnMut(&Step) -> bool) -> Replayed {
+    for (index, step) in steps.iter().enumerate() {
+        if !run(step) {
+            return Replayed::StoppedAt(index);
+        }
+    }
+    Replayed::Completed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(register: char, steps: Vec<Step>) -> Macros {
+        let mut macros = Macros::default();
+        macros.start(register);
+        for step in steps {
+            macros.record(step);
+        }
+        macros.stop();
+        macros
+    }
+
+    #[test]
+    fn records_steps_in_order_into_the_register() {
+        let steps = vec![
+            Step::Action(Action::Next),
+            Step::Action(Action::EditAge),
+            Step::Answer("3y".to_owned()),
+        ];
+        let mut macros = Macros::default();
+        macros.record(Step::Action(Action::Last));
+        macros.start('q');
+        assert_eq!(macros.recording(), Some('q'));
+        for step in steps.clone() {
+            macros.record(step);
+        }
+        assert_eq!(macros.stop(), Some(('q', 3)));
+        assert_eq!(macros.recording(), None);
+        assert_eq!(macros.steps('q'), Some(('q', steps)));
+        macros.record(Step::Action(Action::Last));
+        assert_eq!(macros.steps('q').map(|(_, steps)| steps.len()), Some(3));
+    }
+
+    #[test]
+    fn recording_again_replaces_the_register() {
+        let mut macros = recorded('a', vec![Step::Action(Action::Next)]);
+        macros.start('a');
+        macros.record(Step::Action(Action::Previous));
+        macros.record(Step::Action(Action::Previous));
+        macros.stop();
+        assert_eq!(
+            macros.steps('a'),
+            Some(('a', vec![Step::Action(Action::Previous); 2]))
+        );
+    }
+
+    #[test]
+    fn stopping_without_recording_does_nothing() {
+        let mut macros = Macros::default();
+        assert_eq!(macros.stop(), None);
+        assert_eq!(macros.steps('a'), None);
+    }
+
+    #[test]
+    fn at_replays_the_last_register_replayed() {
+        let mut macros = recorded('a', vec![Step::Action(Action::Next)]);
+        assert_eq!(macros.steps('@'), None);
+        macros.steps('a');
+        assert_eq!(
+            macros.steps('@'),
+            Some(('a', vec![Step::Action(Action::Next)]))
+        );
+        assert_eq!(macros.steps('b'), None);
+        assert_eq!(macros.steps('@').map(|(register, _)| register), Some('a'));
+    }
+
+    #[test]
+    fn a_pending_register_is_taken_once() {
+        let mut macros = Macros::default();
+        assert!(!macros.is_pending());
+        macros.wait_for_register(Pending::Replay);
+        assert!(macros.is_pending());
+        assert_eq!(macros.take_pending(), Some(Pending::Replay));
+        assert_eq!(macros.take_pending(), None);
+        assert!(Macros::is_register('z'));
+        assert!(!Macros::is_register('A'));
+        assert!(!Macros::is_register('1'));
+    }
+
+    #[test]
+    fn replay_runs_every_step_in_order() {
+        let steps = vec![
+            Step::Action(Action::Next),
+            Step::Answer("x".to_owned()),
+            Step::Action(Action::Last),
+        ];
+        let mut run = Vec::new();
+        let replayed = replay(&steps, |step| {
+            run.push(step.clone());
+            true
+        });
+        assert_eq!(replayed, Replayed::Completed);
+        assert_eq!(run, steps);
+    }
+
+    #[test]
+    fn replay_stops_at_the_first_failing_step() {
+        let steps = vec![Step::Action(Action::Next); 5];
+        let mut count = 0;
+        let replayed = replay(&steps, |_| {
+            count += 1;
+            count < 3
+        });
+        assert_eq!(replayed, Replayed::StoppedAt(2));
+        assert_eq!(count, 3);
+        assert_eq!(replay(&[], |_| false), Replayed::Completed);
+    }
+}
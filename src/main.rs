@@ -1,458 +1,10836 @@
+#[macro_use]
+mod profiling;
+mod action;
+mod activity;
+mod age;
+mod animation;
+mod attributes;
+mod audit;
+mod backup;
+mod batch;
+mod breeds;
+mod capabilities;
+mod categories;
+mod chord;
+mod cli;
+mod clipboard;
+mod column_chooser;
+mod completion;
+mod completions;
+mod compression;
+mod config;
+mod dates;
+mod db_format;
+mod db_lock;
+mod db_watch;
+mod demo;
+mod diff;
+mod draft;
+mod draw_rate;
+mod dry_run;
+mod duplicates;
+mod durability;
+mod extra_fields;
+mod fields;
+mod filter;
+mod fuzzy;
+mod generator;
+mod grouping;
+mod hooks;
+mod idle;
+mod ids;
+mod input;
+mod integrity;
+mod jumps;
+mod keys;
+mod kiosk;
+mod list;
+mod list_layout;
+mod locales;
+mod logging;
+mod macros;
+mod man;
+mod marks;
+mod menu;
+mod merge;
+mod merge_review;
+mod message_pager;
+mod metrics;
+mod microchip;
+mod migrations;
+mod notes;
+mod notify;
+mod palette;
+mod pet_selection;
+mod pet_status;
+#[cfg(feature = "image-preview")]
+mod photo;
+mod picker;
+mod plain;
+mod progress;
+mod quick_add;
+mod quick_open;
+mod reconcile;
+mod reminders;
+mod reorder;
+mod report;
+mod reset;
+mod restore_preview;
+mod saved_views;
+mod schema;
+mod search;
+#[cfg(feature = "server")]
+mod server;
+mod session_summary;
+mod share;
+mod sqlite;
+mod startup;
+mod stats;
+mod status;
+mod strings;
+mod tab_views;
+mod table_import;
+mod text;
+mod theme;
+mod three_way;
+mod tty;
+mod tutorial;
+mod ui_state;
+#[cfg(test)]
+mod ui_tests;
+mod validation;
+mod view;
+mod viewport;
+mod weight;
+mod wizard;
+mod workspace;
+mod yank_ring;
+
+use action::{Action, InputMode};
+use activity::ActivityLog;
+use age::Age;
+use animation::{Animation, Animations};
+use capabilities::Capabilities;
+use categories::{CategoryManager, ManagerOutcome};
+use chord::Chord;
 use chrono::prelude::*;
+use clap::{CommandFactory, Parser};
+use clipboard::Clipboard;
+use column_chooser::{ChooserOutcome, ColumnChooser};
+use completion::{Both, Categories, Completer, PetNames};
+use compression::Compress;
 use crossterm::{
-    event::{self, Event as CEvent, KeyCode, KeyEvent},
-    terminal,
+    cursor,
+    event::{Event as CEvent, EventStream, KeyCode, KeyEvent, KeyModifiers, MouseEvent},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use rand::{distributions::Alphanumeric, prelude::*};
+use dates::DateOrder;
+use db_format::DbFormat;
+use db_watch::DbWatch;
+use draw_rate::DrawRate;
+use durability::Durability;
+use fields::PetField;
+use filter::CategoryFilter;
+use futures_util::{FutureExt, Stream, StreamExt};
+use grouping::ListRow;
+use idle::IdleLock;
+use ids::IdAllocator;
+use input::{HistoryCursor, InputOutcome, TextInput};
+use jumps::{Jump, JumpList};
+use kiosk::Kiosk;
+use list_layout::ColumnSpec;
+use macros::{Macros, Pending, Replayed, Step};
+use marks::Marks;
+use menu::{MenuCounts, MenuEntry, MenuFit, MenuLabel, MENU_ENTRIES};
+use merge::{Conflict, Merged, Strategy};
+use merge_review::{MergeReview, ReviewOutcome};
+use message_pager::{MessagePager, PagerOutcome};
+use migrations::Migration;
+use notes::{EditorOutcome, NotesEditor};
+use palette::{Palette, PaletteOutcome};
+use pet_status::PetStatus;
+use picker::{Picker, PickerKey};
+use progress::{Cancel, Progress, Reporter};
+use quick_open::{QuickOpen, QuickOpenOutcome};
+use reconcile::{Reconcile, ReconcileOutcome};
+use restore_preview::{PreviewOutcome, RestorePreview};
+use saved_views::{SavedView, SavedViews};
+use search::Query;
 use serde::{Deserialize, Serialize};
-use std::io;
-use std::sync::mpsc;
+use session_summary::SessionStats;
+use startup::Check;
+use stats::{AgeBucketWidth, StatsSort};
+use status::{StatusLevel, StatusLine};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
-use std::{fs, io::Stdout, sync::mpsc::Receiver};
+use strings::{Lang, Msg};
+use tab_views::TabViews;
+use table_import::ImportFrom;
+use theme::Theme;
 use thiserror::Error;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::time::MissedTickBehavior;
 use tui::{
-    backend::CrosstermBackend,
+    backend::{Backend, CrosstermBackend},
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    symbols,
     text::{Span, Spans},
     widgets::{
-        Block, BorderType, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Table, Tabs,
+        Axis, BarChart, Block, BorderType, Borders, Cell, Chart, Clear, Dataset, Gauge, GraphType,
+        List, ListItem, ListState, Paragraph, Row, Table, TableState, Tabs, Wrap,
     },
     Terminal,
 };
+use tutorial::Tutorial;
+use ui_state::PersistedUiState;
+use view::{Sort, ViewParams};
+use viewport::Viewport;
+use weight::WeightEntry;
+use workspace::{PickerOutcome, Workspace, WorkspacePicker};
+use yank_ring::YankRing;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    terminal::enable_raw_mode().expect("can run in raw mode");
+fn main() {
+    let cli = cli::Cli::parse();
+    let result = if cli.made_up_db() {
+        run_demo(&cli)
+    } else {
+        run(&cli)
+    };
+    match result {
+        // The reader, like `head`, has all it wanted.
+        Err(err) if err.is_broken_pipe() => {}
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(err.exit_code());
+        }
+        Ok(()) => {}
+    }
+}
 
-    let (tx, mut rx) = mpsc::channel();
-    thread::spawn(move || accept_user_input(Duration::from_millis(200), tx));
-    let mut app_state = AppState::default();
-    let mut terminal = create_terminal()?;
+/// `--demo`: runs on a DB of made-up pets in a temporary directory, which is
+/// removed afterwards unless `--keep` is given or something went wrong.
+fn run_demo(cli: &cli::Cli) -> Result<(), AppError> {
+    let dir = demo::create_dir().map_err(|source| AppError::File {
+        path: std::env::temp_dir(),
+        source,
+    })?;
+    set_workspace_db(&dir.join("db.json"));
+    let result = run(cli);
+    if cli.keep || result.is_err() {
+        println!("the demo's files are in {}", dir.display());
+    } else if let Err(err) = fs::remove_dir_all(&dir) {
+        eprintln!("could not remove {}: {}", dir.display(), err);
+    }
+    result
+}
 
-    loop {
-        terminal.draw(|rect| {
-            draw(
-                rect,
-                &app_state.menu_titles,
-                app_state.active_menu_item,
-                &mut app_state.pet_list_state,
+/// Runs the command, or the UI without one. The terminal is restored before
+/// an error is returned.
+fn run(cli: &cli::Cli) -> Result<(), AppError> {
+    #[cfg(feature = "profiling")]
+    let _profile_guard = cli.profile_out.as_deref().map(profiling::init);
+    // The UI checks what it needs before taking over the screen; see
+    // `startup`. Commands fail on the first error.
+    let ui = cli.command.is_none();
+    let mut startup = startup::Report::default();
+    let mut config = match load_config(cli) {
+        Ok(config) => config,
+        Err(err) if ui => {
+            startup.fail(Check::Config, err.to_string(), err.fix());
+            check_terminal(cli, &mut startup);
+            return Err(AppError::Startup(startup));
+        }
+        Err(err) => return Err(err.into()),
+    };
+    if is_first_run(cli) {
+        if let Some(config_path) = run_setup(cli, &config)? {
+            writeln!(io::stdout().lock(), "wrote {}", config_path.display())?;
+            config = load_config(cli)?;
+        }
+    }
+    if let Some(name) = &cli.workspace {
+        match workspace::find(&config.workspaces.value, name) {
+            Ok(workspace) => set_workspace_db(&workspace.path),
+            Err(message) if ui => startup.fail(
+                Check::DbPath,
+                message,
+                "add it to `workspaces` in the config file, or leave out --workspace",
+            ),
+            Err(message) => return Err(AppError::Workspace(message)),
+        }
+    }
+    STRICT_SCHEMA.store(cli.strict_schema, Ordering::Relaxed);
+    age::set_young_ages(age::YoungAges {
+        days_below: config.age_in_days_below.value,
+        weeks_below: config.age_in_weeks_below.value,
+    });
+    let mut config_warnings = unknown_key_warnings(&config);
+    let locales_dir = cli
+        .config
+        .clone()
+        .or_else(config::default_path)
+        .filter(|_| config_files(cli) == config::ConfigFiles::Read)
+        .and_then(|path| config::locales_dir(&path));
+    let (locale, locale_warning) =
+        locales::load(&config.generator_locale.value, locales_dir.as_deref());
+    config_warnings.extend(locale_warning);
+    for warning in &config_warnings {
+        eprintln!("warning: {}", warning);
+    }
+    // Restoring a backup or resetting replaces the DB without reading it,
+    // so it still works on an encrypted one.
+    let reads_db = !matches!(
+        cli.command,
+        Some(
+            cli::Command::Restore { .. }
+                | cli::Command::Nuke { .. }
+                | cli::Command::Audit { .. }
+                | cli::Command::Completions { .. }
+                | cli::Command::Man
+                | cli::Command::Schema
+        )
+    );
+    if reads_db
+        && !ui
+        && fs::read(workspace_db()).is_ok_and(|content| db_format::is_encrypted(&content))
+    {
+        return Err(AppError::db_read(Error::EncryptedDBError));
+    }
+    if let Some(cli::Command::Config {
+        command: cli::ConfigCommand::Show,
+    }) = cli.command
+    {
+        write!(io::stdout().lock(), "{}", config.show())?;
+        return Ok(());
+    }
+    let theme = match cli.theme(&config) {
+        Ok(theme) => theme,
+        Err(err) if ui => {
+            startup.fail(
+                Check::Config,
+                err.to_string(),
+                "correct `theme` or `category_colors` in the config, or the --color flags",
             );
-        })?;
+            Theme::default()
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let write_options = cli.write_options(&config);
+    let mut names = match &config.name_pools.value {
+        Some(path) => match generator::Names::load(path) {
+            Ok(names) => names,
+            Err(message) if ui => {
+                startup.fail(
+                    Check::Config,
+                    format!(
+                        "could not read the names in {}: {}",
+                        path.display(),
+                        message
+                    ),
+                    format!(
+                        "correct {}, or empty `name_pools` for the built-in names",
+                        path.display()
+                    ),
+                );
+                generator::Names::default()
+            }
+            Err(message) => {
+                return Err(AppError::Names {
+                    path: path.clone(),
+                    message,
+                })
+            }
+        },
+        None => generator::Names::default(),
+    };
+    names.unique = config.unique_names.value;
+    names.locale = locale;
+    if cli.made_up_db() {
+        write_demo_db(write_options, &names).map_err(AppError::db_write)?;
+    }
 
-        let input_response = handle_user_input(
-            &mut rx,
-            &mut terminal,
-            &mut app_state.active_menu_item,
-            &mut app_state.pet_list_state,
+    if let Some(cli::Command::Generate { count, seed }) = cli.command {
+        let pets = add_random_pets_to_db(write_options, count, seed, &names, &Reporter::none())
+            .map_err(AppError::db_write)?;
+        writeln!(
+            io::stdout().lock(),
+            "added {} pets, {} in total",
+            count,
+            pets.len()
         )?;
-        if input_response == ResponseToUserInput::Stop {
-            break;
+        return Ok(());
+    }
+    if let Some(cli::Command::Report { format, out, view }) = &cli.command {
+        return run_report(*format, out.as_deref(), &view.params());
+    }
+    if let Some(cli::Command::List {
+        format,
+        max_width,
+        fields,
+        view,
+        watch,
+        interval,
+        no_clear,
+        extra_fields,
+    }) = &cli.command
+    {
+        let extra_fields = *extra_fields || config.export_extra_fields.value;
+        // Padded columns are for reading; a pipe gets values awk can split.
+        let format = format.unwrap_or(if io::stdout().is_terminal() {
+            list::ListFormat::Table
+        } else {
+            list::ListFormat::Tsv
+        });
+        if !fields.is_empty() && format == list::ListFormat::WeightsCsv {
+            cli::Cli::command()
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    "--fields can't be used with --format weights-csv",
+                )
+                .exit();
+        }
+        let view = view.params();
+        if !*watch {
+            return print_list(format, fields, &view, extra_fields, *max_width);
+        }
+        return run_list_watch(
+            format,
+            fields,
+            &view,
+            extra_fields,
+            *max_width,
+            Duration::from_secs_f64(*interval),
+            !*no_clear,
+        );
+    }
+    if let Some(cli::Command::Stats { json }) = cli.command {
+        return run_stats(json, config.age_buckets.value);
+    }
+    if let Some(cli::Command::Completions { shell }) = cli.command {
+        write!(
+            io::stdout().lock(),
+            "{}",
+            completions::generate(shell, &mut cli::Cli::command())
+        )?;
+        return Ok(());
+    }
+    if let Some(cli::Command::Man) = cli.command {
+        write!(
+            io::stdout().lock(),
+            "{}",
+            man::render(&mut cli::Cli::command(), DB_PATH)
+        )?;
+        return Ok(());
+    }
+    if let Some(cli::Command::Schema) = cli.command {
+        let schema = serde_json::to_string_pretty(&schema::schema()).expect("schema serializes");
+        writeln!(io::stdout().lock(), "{}", schema)?;
+        return Ok(());
+    }
+    if let Some(cli::Command::Category { command }) = &cli.command {
+        return run_category(write_options, command);
+    }
+    if let Some(cli::Command::Dedupe { dry_run }) = cli.command {
+        return run_dedupe(write_options, dry_run);
+    }
+    if let Some(cli::Command::Check) = cli.command {
+        return run_check();
+    }
+    if let Some(cli::Command::Diff { old, new }) = &cli.command {
+        return run_diff(old, new);
+    }
+    if let Some(cli::Command::Migrate { dry_run }) = cli.command {
+        return run_migrate(write_options, dry_run);
+    }
+    if let Some(cli::Command::ImportPet { file }) = &cli.command {
+        return run_import(write_options, file);
+    }
+    if let Some(cli::Command::Import {
+        from,
+        file,
+        table,
+        map,
+        dry_run,
+    }) = &cli.command
+    {
+        return run_table_import(
+            write_options,
+            *from,
+            file,
+            table,
+            map,
+            config.date_order.value,
+            *dry_run,
+        );
+    }
+    if let Some(cli::Command::Merge {
+        file,
+        strategy,
+        dry_run,
+    }) = &cli.command
+    {
+        return run_merge(write_options, file, *strategy, *dry_run);
+    }
+    if let Some(cli::Command::Pick { output, view }) = &cli.command {
+        return run_pick(*output, &view.params(), &theme, config.lang.value);
+    }
+    if let Some(cli::Command::Purge { older_than }) = cli.command {
+        let purged = purge_archived(write_options, older_than).map_err(AppError::db_write)?;
+        writeln!(
+            io::stdout().lock(),
+            "deleted {} archived pets",
+            purged.len()
+        )?;
+        return Ok(());
+    }
+    if let Some(cli::Command::NotifyDaemon { interval, once, .. }) = cli.command {
+        let mut channels = notify::Channels {
+            desktop: config.notify_desktop.value,
+            command: config.notify_command.value.clone(),
+            file: config.notify_file.value.clone(),
+            timeout: config.hook_timeout.value,
+        };
+        // Like the hooks, safe mode runs no commands.
+        if cli.safe_mode {
+            channels.command = None;
+        }
+        if channels.is_empty() {
+            cli::Cli::command()
+                .error(
+                    clap::error::ErrorKind::MissingRequiredArgument,
+                    "there is nowhere to send reminders; turn on notify_desktop, \
+                     notify_command or notify_file in the config, or pass --desktop, \
+                     --command or --file",
+                )
+                .exit();
         }
+        init_logging(cli)?;
+        return run_notify_daemon(&channels, config.notify_snooze.value, interval, once);
+    }
+    #[cfg(feature = "server")]
+    if let Some(cli::Command::Serve { addr }) = cli.command {
+        init_logging(cli)?;
+        return server::serve(addr, write_options).map_err(AppError::Server);
+    }
+    if let Some(cli::Command::Nuke { yes }) = cli.command {
+        return run_nuke(write_options, yes);
+    }
+    if let Some(cli::Command::Audit { since, pet, format }) = cli.command {
+        let filter = audit::AuditFilter { since, pet_id: pet };
+        return run_audit(&filter, format);
+    }
+    if let Some(cli::Command::Restore { backup, yes }) = cli.command {
+        let lock = lock_db(write_options).map_err(AppError::db_read)?;
+        let result = run_restore(backup, yes, config.keep_backups.value);
+        drop(lock);
+        return result;
     }
 
-    Ok(())
+    let mode = check_terminal(cli, &mut startup);
+    if startup.can_check(Check::DbFile) {
+        check_db_file(write_options, &mut startup);
+    }
+    if startup.can_check(Check::Schema) {
+        check_db_schema(write_options, &mut startup);
+    }
+    let session_lock = if startup.can_check(Check::Lock) {
+        check_db_lock(cli, write_options, &mut startup)
+    } else {
+        None
+    };
+    if startup.is_fatal() {
+        return Err(AppError::Startup(startup));
+    }
+
+    let output = match mode {
+        None => None,
+        Some(tty::Mode::Stdout) => Some(tty::Output::stdout()),
+        Some(tty::Mode::OfferTty) => Some(offer_tty()?),
+        Some(tty::Mode::NoTerminal) => return Err(AppError::NoTerminal),
+    };
+
+    init_logging(cli)?;
+
+    let dry_run = if cli.dry_run {
+        let (dry_run, copy) =
+            dry_run::DryRun::start(Path::new(&workspace_db())).map_err(|source| {
+                AppError::File {
+                    path: PathBuf::from(workspace_db()),
+                    source,
+                }
+            })?;
+        log::info!("dry run on {}", copy.display());
+        set_dry_run_db(Some(&copy));
+        Some(dry_run)
+    } else {
+        None
+    };
+
+    let mut write_options = write_options;
+    if !write_options.read_only && !db_is_writable() {
+        log::info!("{} is not writable, opening it read-only", db_path());
+        startup.warn(
+            Check::DbFile,
+            format!("{} is not writable, so it is opened read-only", db_path()),
+        );
+        write_options.read_only = true;
+    }
+
+    // A DB that can't be parsed isn't migrated; the loader reports it once
+    // the UI is up and offers the backups instead.
+    let migrations = match migrate_db(write_options) {
+        Err(Error::ParseDBError(err)) => {
+            log::info!("not migrating {}: {}", db_path(), err);
+            Vec::new()
+        }
+        migrations => migrations.map_err(AppError::db_read)?,
+    };
+    if write_options.read_only && !migrations.is_empty() {
+        log::info!("not migrating {} in read-only mode", db_path());
+    }
+
+    let mut dry_run = dry_run;
+    if let Some(dry_run) = &mut dry_run {
+        let content = read_db_content().unwrap_or_default();
+        dry_run.set_base(db_format::revision(&content));
+    }
+
+    // A dry run's drafts would be of changes that are never written, plain
+    // mode has no forms to resume them in and a demo and safe mode keep out
+    // of the cache.
+    let draft_path = draft::default_path()
+        .filter(|_| dry_run.is_none() && !cli.plain && !cli.made_up_db() && !cli.safe_mode);
+    let resumed_draft = if write_options.read_only || cli.kiosk {
+        None
+    } else {
+        draft_path
+            .as_deref()
+            .and_then(|path| offer_draft(path, &resolved_db_path()))
+    };
+
+    let activity_log = match &cli.activity_log {
+        Some(path) => ActivityLog::with_file(path).map_err(|source| AppError::File {
+            path: path.clone(),
+            source,
+        })?,
+        None => ActivityLog::default(),
+    };
+    let capabilities = if cli.ascii {
+        Capabilities::ASCII
+    } else {
+        let overrides = capabilities::parse_overrides(&config.capabilities.value)
+            .expect("validated with the config");
+        Capabilities::detect(&capabilities::Environment::from_env()).with_overrides(&overrides)
+    };
+    log::debug!("terminal capabilities: {:?}", capabilities);
+    let mut app_state = AppState {
+        theme,
+        capabilities,
+        lang: config.lang.value,
+        names,
+        activity_log,
+        write_options,
+        ..AppState::default()
+    };
+    app_state.title = match config.title.source {
+        config::Source::Default => strings::text(app_state.lang, Msg::MenuTitle).to_owned(),
+        _ => config.title.value.clone(),
+    };
+    app_state.footer = config.footer.value.clone();
+    app_state.demo = cli.made_up_db();
+    app_state.list_width = config.list_width.value;
+    app_state.list_columns = config.list_columns.value.clone();
+    app_state.list_column_widths = config.list_column_widths.value.clone();
+    app_state.tabs = config.tabs.value.clone();
+    app_state.menu = menu::entries(&app_state.tabs);
+    app_state.active_menu_item = config.tab.value;
+    app_state.age_buckets = config.age_buckets.value;
+    app_state.grouped = config.group_by_category.value;
+    app_state.export_extra_fields = config.export_extra_fields.value;
+    app_state.date_order = config.date_order.value;
+    app_state.add_defaults = quick_add::AddDefaults {
+        category: config.add_category.value.clone(),
+        age_months: config.add_age.value,
+        name_prefix: config.add_name_prefix.value.clone(),
+    };
+    // The status bar ends up showing the last one, the others are left for
+    // the message pager.
+    for warning in config_warnings {
+        app_state.report(StatusLevel::Error, warning);
+    }
+    if dry_run.is_some() {
+        app_state.db_path = fs::canonicalize(workspace_db())
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| workspace_db());
+    }
+    app_state.dry_run = dry_run;
+    app_state.session_lock = session_lock;
+    app_state.workspaces = config.workspaces.value.clone();
+    app_state.workspace = current_workspace(&app_state.workspaces);
+    app_state.quick_actions = config.quick_actions.clone();
+    app_state.idle_lock = IdleLock::new(config.idle_lock.value, Instant::now());
+    app_state.animations = Animations::new(config.animations.value);
+    app_state.db_writer.hooks = hooks::Hooks {
+        on_add: config.on_add.value.clone(),
+        on_delete: config.on_delete.value.clone(),
+        on_update: config.on_update.value.clone(),
+        on_any_write: config.on_any_write.value.clone(),
+        timeout: config.hook_timeout.value,
+    };
+    // Nothing is written in a dry run, so there is nothing to hook, and safe
+    // mode runs nothing it didn't come with.
+    if app_state.dry_run.is_some() || cli.safe_mode {
+        app_state.db_writer.hooks = hooks::Hooks::default();
+    }
+    if write_options.read_only {
+        app_state
+            .menu
+            .retain(|entry| !keys::EDITS.contains(&entry.hotkey));
+    }
+    app_state.safe_mode = cli.safe_mode;
+    if cli.safe_mode {
+        let notice = safe_mode_notice();
+        log::info!("{}", notice);
+        eprintln!("{}", notice);
+        app_state.status_line.info(notice);
+    }
+    for warning in startup.warnings() {
+        log::warn!("{}", warning);
+        app_state.report(StatusLevel::Info, format!("warning: {}", warning));
+    }
+    let state_path = ui_state::state_path(Path::new(&workspace_db()));
+    if let Some(state) = ui_state::load(&state_path).filter(|_| !cli.safe_mode) {
+        if !cli.no_restore && !cli.kiosk {
+            restore_ui_state(&mut app_state, &state);
+        }
+        load_saved_views(&mut app_state, &state);
+    }
+    // A configured split, set of columns, pet or tab beats the one
+    // remembered from the last session.
+    if config.list_width.source != config::Source::Default {
+        app_state.list_width = config.list_width.value;
+    }
+    if config.list_columns.source != config::Source::Default {
+        app_state.list_columns = config.list_columns.value.clone();
+    }
+    if cli.kiosk {
+        start_kiosk(&mut app_state, &config);
+    }
+    if cli.tutorial {
+        app_state.tutorial = Some(Tutorial::new(tutorial::steps(&app_state.tabs)));
+    }
+    // Nor is a tab turned off in the config started on.
+    if !app_state.tabs.contains(&app_state.active_menu_item) {
+        app_state.active_menu_item = app_state.tabs[0];
+    }
+    app_state.draft_path = draft_path;
+    // What needs the pets waits for them; see `finish_loading`.
+    app_state.loading = Some(Loading {
+        started: Instant::now(),
+        select: cli.select,
+        tab: Some(config.tab.value).filter(|tab| {
+            config.tab.source != config::Source::Default && app_state.tabs.contains(tab)
+        }),
+        draft: resumed_draft.map(|draft| draft.form),
+        progress: None,
+    });
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .map_err(AppError::Runtime)?;
+
+    let Some(output) = output else {
+        let result = runtime.block_on(plain::run(&mut app_state));
+        print_session_summary(cli, &app_state);
+        end_session(cli, &mut app_state);
+        return result;
+    };
+    terminal::enable_raw_mode().map_err(|err| AppError::TerminalInit(crossterm_io(err)))?;
+    install_panic_hook(&output);
+    let leave = output.reopen();
+    let mut terminal = match create_terminal(output) {
+        Ok(terminal) => terminal,
+        Err(err) => {
+            let _ = terminal::disable_raw_mode();
+            if let Ok(mut leave) = leave {
+                let _ = execute!(leave, LeaveAlternateScreen);
+            }
+            return Err(err);
+        }
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    load_in_background(tx.clone());
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let watch_thread = {
+        let shutdown = Arc::clone(&shutdown);
+        let tick_rate = config.tick_rate.value;
+        thread::spawn(move || watch_db(tick_rate, tx, &shutdown))
+    };
+    let result = runtime.block_on(run_event_loop(
+        &mut terminal,
+        &mut app_state,
+        rx,
+        config.tick_rate.value,
+        cli.show_frame_time,
+        EventStream::new,
+    ));
+    let restored = restore_terminal(&mut terminal);
+
+    shutdown.store(true, Ordering::Relaxed);
+    if watch_thread.join().is_err() {
+        log::error!("the DB watch thread panicked");
+    }
+    // Printed only now that the alternate screen is left, so that it stays.
+    print_session_summary(cli, &app_state);
+    end_session(cli, &mut app_state);
+    result.and(restored)
 }
 
-const DB_PATH: &str = "./data/db.json";
+/// Prints what the session changed, unless `--quiet`. A dry run has shown
+/// what it changed before quitting, so it prints nothing here.
+fn print_session_summary(cli: &cli::Cli, app_state: &AppState) {
+    if cli.quiet || app_state.dry_run.is_some() {
+        return;
+    }
+    let Ok(pets) = read_db() else {
+        return;
+    };
+    let writes = metrics::snapshot().db_writes - app_state.session_start_writes;
+    let stats = SessionStats::between(&app_state.session_start_pets, &pets, writes);
+    println!("{}", session_summary::line(&stats, &workspace_db()));
+}
 
-#[derive(Error, Debug)]
-pub enum Error {
-    #[error("error reading the DB file: {0}")]
-    ReadDBError(#[from] io::Error),
-    #[error("error parsing the DB file: {0}")]
-    ParseDBError(#[from] serde_json::Error),
+/// Saves the UI state for the next session and settles a dry run.
+fn end_session(cli: &cli::Cli, app_state: &mut AppState) {
+    if !cli.no_restore && !cli.safe_mode {
+        // The workspace's, which may not be the one the session started in.
+        let state_path = ui_state::state_path(Path::new(&workspace_db()));
+        if let Err(err) = ui_state::save(&state_path, &capture_ui_state(app_state)) {
+            eprintln!("could not save UI state: {}", err);
+        }
+    }
+    if let Some(dry_run) = app_state.dry_run.take() {
+        dry_run.finish();
+    }
 }
 
-enum Event<I> {
-    Input(I),
-    Tick,
+fn init_logging(cli: &cli::Cli) -> Result<(), AppError> {
+    logging::init(cli.log_file.as_deref()).map_err(|source| AppError::File {
+        path: cli
+            .log_file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(logging::DEFAULT_LOG_FILE)),
+        source,
+    })?;
+    Ok(())
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-struct Pet {
-    id: usize,
-    name: String,
-    category: String,
-    age: usize,
-    created_at: DateTime<Utc>,
+const DB_PATH: &str = "./data/db.json";
+/// The scratch copy of the DB a dry run works on.
+static DRY_RUN_DB: RwLock<Option<String>> = RwLock::new(None);
+
+/// The DB of the workspace in use, if one was picked.
+static WORKSPACE_DB: RwLock<Option<String>> = RwLock::new(None);
+
+/// The DB file to read and write: the workspace's, or its copy in a dry run.
+fn db_path() -> String {
+    DRY_RUN_DB
+        .read()
+        .ok()
+        .and_then(|copy| copy.clone())
+        .unwrap_or_else(workspace_db)
 }
 
-#[derive(Copy, Clone, Debug)]
-enum MenuItem {
-    Home,
-    Pets,
+/// The DB the session is about: the workspace's, `DB_PATH` without one.
+fn workspace_db() -> String {
+    WORKSPACE_DB
+        .read()
+        .ok()
+        .and_then(|path| path.clone())
+        .unwrap_or_else(|| DB_PATH.to_owned())
 }
 
-impl From<MenuItem> for usize {
-    fn from(input: MenuItem) -> usize {
-        match input {
-            MenuItem::Home => 0,
-            MenuItem::Pets => 1,
-        }
+fn set_workspace_db(path: &Path) {
+    if let Ok(mut db) = WORKSPACE_DB.write() {
+        *db = Some(path.display().to_string());
     }
 }
 
-struct AppState<'a> {
-    menu_titles: Vec<&'a str>,
-    active_menu_item: MenuItem,
-    pet_list_state: ListState,
+fn set_dry_run_db(copy: Option<&Path>) {
+    if let Ok(mut db) = DRY_RUN_DB.write() {
+        *db = copy.map(|copy| copy.display().to_string());
+    }
 }
 
-impl Default for AppState<'_> {
-    fn default() -> Self {
-        let mut pet_list_state = ListState::default();
-        pet_list_state.select(Some(0));
-        Self {
-            menu_titles: vec!["Home", "Pets", "Add", "Delete", "Quit"],
-            active_menu_item: MenuItem::Home,
-            pet_list_state,
-        }
+/// `--strict-schema`: DB files whose pets have fields pet-cli doesn't know
+/// are refused rather than read.
+static STRICT_SCHEMA: AtomicBool = AtomicBool::new(false);
+
+/// The error for `database` under `--strict-schema`, if there is one.
+fn check_schema(database: &Database) -> Result<(), Error> {
+    if !STRICT_SCHEMA.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    match extra_fields::describe(&database.pets) {
+        Some(records) => Err(Error::UnknownFields(records)),
+        None => Ok(()),
     }
 }
+/// Where `x` writes the Pets tab, relative to the working directory.
+const EXPORT_PATH: &str = "pet-report.md";
 
-#[derive(PartialEq)]
-enum ResponseToUserInput {
-    Continue,
-    Stop,
+/// Whether to start with the setup wizard: the UI is about to open for the
+/// first time, with neither a DB nor a config file, and someone is at the
+/// terminal to answer. Asking for a config file with `--config` counts as
+/// being set up.
+fn is_first_run(cli: &cli::Cli) -> bool {
+    cli.command.is_none()
+        && !cli.dry_run
+        && !cli.made_up_db()
+        && !cli.safe_mode
+        && cli.config.is_none()
+        && cli.workspace.is_none()
+        && !Path::new(DB_PATH).exists()
+        && config::default_path().is_some_and(|path| !path.exists())
+        && io::stdin().is_terminal()
+        && io::stdout().is_terminal()
 }
 
-fn handle_user_input(
-    rx: &mut Receiver<Event<KeyEvent>>,
-    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-    active_menu_item: &mut MenuItem,
-    pet_list_state: &mut ListState,
-) -> Result<ResponseToUserInput, Box<dyn std::error::Error>> {
-    let Event::Input(event) = rx.recv()? else {
-        return Ok(ResponseToUserInput::Continue);
-    };
-    match event.code {
-        KeyCode::Char('q') => {
-            terminal::disable_raw_mode()?;
-            terminal.show_cursor()?;
-            return Ok(ResponseToUserInput::Stop);
-        }
-        KeyCode::Char('h') => *active_menu_item = MenuItem::Home,
-        KeyCode::Char('p') => *active_menu_item = MenuItem::Pets,
-        KeyCode::Char('a') => {
-            add_random_pet_to_db().expect("can add new random pet");
+/// Runs the wizard, then creates the DB and, unless it was skipped, adds the
+/// sample pets and writes the config file, whose path is returned.
+fn run_setup(cli: &cli::Cli, config: &config::Config) -> Result<Option<PathBuf>, AppError> {
+    terminal::enable_raw_mode().map_err(|err| AppError::TerminalInit(crossterm_io(err)))?;
+    let mut terminal = match create_terminal(tty::Output::stdout()) {
+        Ok(terminal) => terminal,
+        Err(err) => {
+            let _ = terminal::disable_raw_mode();
+            return Err(err);
         }
-        KeyCode::Char('d') => {
-            remove_pet_at_index(pet_list_state).expect("can remove pet");
-        }
-        KeyCode::Char('j') => {
-            if let Some(selected) = pet_list_state.selected() {
-                let amount_pets = read_db().expect("can fetch pet list").len();
-                if selected >= amount_pets - 1 {
-                    pet_list_state.select(Some(0));
-                } else {
-                    pet_list_state.select(Some(selected + 1));
-                }
-            }
+    };
+    let mut wizard = wizard::Wizard::default();
+    let finished = loop {
+        let drawn = terminal.draw(|rect| draw_wizard(rect, &wizard));
+        if let Err(err) = drawn {
+            let _ = restore_terminal(&mut terminal);
+            return Err(AppError::Terminal(err));
         }
-        KeyCode::Char('k') => {
-            if let Some(selected) = pet_list_state.selected() {
-                let amount_pets = read_db().expect("can fetch pet list").len();
-                if selected > 0 {
-                    pet_list_state.select(Some(selected - 1));
-                } else {
-                    pet_list_state.select(Some(amount_pets - 1));
-                }
+        match crossterm::event::read() {
+            Ok(CEvent::Key(key)) => match wizard.handle_key(key) {
+                wizard::WizardOutcome::Open => {}
+                wizard::WizardOutcome::Skipped => break false,
+                wizard::WizardOutcome::Finished => break true,
+            },
+            Ok(_) => {}
+            Err(err) => {
+                let _ = restore_terminal(&mut terminal);
+                return Err(AppError::terminal(err));
             }
         }
-        _ => {}
+    };
+    restore_terminal(&mut terminal)?;
+
+    let options = cli.write_options(config);
+    let dir = Path::new(DB_PATH)
+        .parent()
+        .expect("the DB is in a directory");
+    fs::create_dir_all(dir).map_err(|source| AppError::File {
+        path: dir.to_owned(),
+        source,
+    })?;
+    write_db(&mut Database::default(), options).map_err(AppError::db_write)?;
+    println!("created {}", DB_PATH);
+    if !finished {
+        return Ok(None);
     }
-    Ok(ResponseToUserInput::Continue)
+    if wizard.samples() {
+        let names = generator::Names::default();
+        add_random_pets_to_db(
+            options,
+            wizard::SAMPLE_PETS,
+            None,
+            &names,
+            &Reporter::none(),
+        )
+        .map_err(AppError::db_write)?;
+    }
+    let Some(path) = config::default_path() else {
+        return Ok(None);
+    };
+    config::create_file(&path, &[("theme", wizard.theme())]).map_err(|source| AppError::File {
+        path: path.clone(),
+        source,
+    })?;
+    Ok(Some(path))
 }
 
-fn create_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>, Box<dyn std::error::Error>> {
-    let stdout = io::stdout();
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    terminal.clear()?;
-    Ok(terminal)
+/// The settings from the config file, the environment and the flags, in
+/// that order of precedence.
+fn load_config(cli: &cli::Cli) -> Result<config::Config, config::ConfigError> {
+    let vars: Vec<(String, String)> = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .collect();
+    let mut layers = config::layers(cli.config.as_deref(), config_files(cli), vars)?;
+    layers.push(cli.config_layer());
+    config::resolve(&layers, default_footer())
 }
 
-fn accept_user_input(tick_rate: Duration, tx: mpsc::Sender<Event<KeyEvent>>) {
-    let mut last_tick = Instant::now();
-    loop {
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
-
-        if event::poll(timeout).expect("poll works") {
-            if let CEvent::Key(key) = event::read().expect("can read events") {
-                tx.send(Event::Input(key)).expect("can send events");
-            }
-        }
-
-        if last_tick.elapsed() >= tick_rate && tx.send(Event::Tick).is_ok() {
-            last_tick = Instant::now();
-        }
+fn config_files(cli: &cli::Cli) -> config::ConfigFiles {
+    if cli.made_up_db() || cli.safe_mode {
+        config::ConfigFiles::Skip
+    } else {
+        config::ConfigFiles::Read
     }
 }
 
-fn draw(
-    total_drawing_rect: &mut tui::Frame<CrosstermBackend<io::Stdout>>,
-    menu_titles: &[&str],
-    active_menu_item: MenuItem,
-    pet_list_state: &mut ListState,
-) {
-    let app_rects = create_app_rects(total_drawing_rect.size());
-    let copyright = create_copyright_paragraph();
-    let tabs = create_tabs(create_menu(menu_titles), active_menu_item);
-    total_drawing_rect.render_widget(tabs, app_rects.menu);
-    render_selected_widget(
-        active_menu_item,
-        total_drawing_rect,
-        &app_rects,
-        pet_list_state,
+/// What `--safe-mode` leaves out, told before the UI starts.
+fn safe_mode_notice() -> String {
+    let config = config::default_path().map_or_else(
+        || "the config file".to_owned(),
+        |path| path.display().to_string(),
     );
-    total_drawing_rect.render_widget(copyright, app_rects.copyright);
+    let state = ui_state::state_path(Path::new(&workspace_db()));
+    format!(
+        "safe mode: not reading {} or the locales next to it, not restoring or saving {}, \
+         not resuming drafts and running no hooks",
+        config,
+        state.display()
+    )
 }
 
-fn render_selected_widget(
-    active_menu_item: MenuItem,
-    rect: &mut tui::Frame<CrosstermBackend<io::Stdout>>,
-    app_rects: &AppRects,
-    pet_list_state: &mut ListState,
-) {
-    match active_menu_item {
-        MenuItem::Home => rect.render_widget(render_home(), app_rects.main_widget),
-        MenuItem::Pets => {
-            let pet_rects = create_pet_rects(&app_rects.main_widget);
-            let (left, right) = create_pet_widgets(pet_list_state);
-            rect.render_stateful_widget(left, pet_rects.names, pet_list_state);
-            rect.render_widget(right, pet_rects.details);
+/// One warning per source that has keys no setting uses.
+fn unknown_key_warnings(config: &config::Config) -> Vec<String> {
+    let mut by_source: Vec<(&config::Source, Vec<&str>)> = Vec::new();
+    for (key, source) in &config.unknown_keys {
+        match by_source.iter_mut().find(|(known, _)| *known == source) {
+            Some((_, keys)) => keys.push(key),
+            None => by_source.push((source, vec![key])),
         }
     }
+    by_source
+        .into_iter()
+        .map(|(source, keys)| format!("unknown settings in {}: {}", source, keys.join(", ")))
+        .collect()
 }
 
-struct PetRects {
-    names: Rect,
-    details: Rect,
+/// The largest count accepted before a command.
+const MAX_COUNT: usize = 1000;
+
+/// How writes to the DB are carried out.
+#[derive(Copy, Clone)]
+struct WriteOptions {
+    format: DbFormat,
+    /// Backups to keep of the DB from before destructive writes.
+    keep_backups: usize,
+    /// Set by `--read-only` or when the DB file isn't writable. Every write
+    /// fails with [`Error::ReadOnly`].
+    read_only: bool,
+    durability: Durability,
+    /// How the DB is compressed and at which level.
+    compress: Compress,
+    compress_level: u32,
+    /// Set by `--audit`: what each write changed goes to the audit file,
+    /// which is kept to this many bytes.
+    audit_max_bytes: Option<u64>,
 }
 
-fn create_pet_rects(parent_rect: &Rect) -> PetRects {
-    let pet_rects = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
-        .split(*parent_rect);
-    PetRects {
-        names: pet_rects[0],
-        details: pet_rects[1],
-    }
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("error reading the DB file: {0}")]
+    ReadDBError(#[from] io::Error),
+    #[error("error parsing the DB file: {0}")]
+    ParseDBError(#[from] serde_json::Error),
+    #[error("invalid pet, nothing was changed: {0}")]
+    InvalidPetError(String),
+    #[error("could not back up the DB, nothing was changed: {0}")]
+    BackupDBError(io::Error),
+    #[error(transparent)]
+    LockDBError(#[from] db_lock::LockError),
+    #[error("the DB is read-only")]
+    ReadOnly,
+    #[error("this DB is encrypted, which this build of pet-cli can't read")]
+    EncryptedDBError,
+    #[error("the DB was changed by another program meanwhile, nothing was changed")]
+    ConflictError,
+    #[error(transparent)]
+    MigrationError(#[from] migrations::MigrationError),
+    #[error("cancelled, nothing was changed")]
+    Cancelled,
+    #[error("fields pet-cli doesn't know, refused by --strict-schema: {0}")]
+    UnknownFields(String),
 }
 
-fn create_tabs<'a>(menu: Vec<Spans<'a>>, active_menu_item: MenuItem) -> Tabs<'a> {
-    Tabs::new(menu)
-        .select(active_menu_item.into())
-        .block(Block::default().title("Menu").borders(Borders::ALL))
-        .style(Style::default().fg(Color::White))
-        .highlight_style(Style::default().fg(Color::Yellow))
-        .divider(Span::raw("|"))
+impl From<progress::Cancelled> for Error {
+    fn from(_: progress::Cancelled) -> Self {
+        Error::Cancelled
+    }
 }
 
-struct AppRects {
-    menu: Rect,
-    main_widget: Rect,
-    copyright: Rect,
+/// Why pet-cli stopped. `main` prints it, once the terminal is back to
+/// normal, and exits with its `exit_code`.
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error(
+        "{0}{}",
+        if .0.is_from_file() { "\n(--safe-mode starts without the config file)" } else { "" }
+    )]
+    Config(#[from] config::ConfigError),
+    #[error(transparent)]
+    Theme(#[from] theme::ThemeError),
+    #[error("could not read the names in {}: {message}", path.display())]
+    Names { path: PathBuf, message: String },
+    #[error("{0}")]
+    Workspace(String),
+    /// What keeps the UI from starting; see `startup`.
+    #[error("{0}")]
+    Startup(startup::Report),
+    #[error("no backup number {0}, run `pet-cli restore` to list them")]
+    NoSuchBackup(usize),
+    /// The DB, or for `pet-cli diff` one of the files compared, can't be
+    /// read, locked or parsed.
+    #[error("{path}: {source}")]
+    DbRead { path: String, source: Error },
+    #[error("{path}: {source}")]
+    DbWrite { path: String, source: Error },
+    #[error("invalid pet, nothing was changed: {0}")]
+    Validation(String),
+    #[error("could not import {}: {message}", path.display())]
+    Import { path: PathBuf, message: String },
+    #[error("{}: {source}", path.display())]
+    File { path: PathBuf, source: io::Error },
+    /// Printing the result or reading an answer failed.
+    #[error("could not write the output: {0}")]
+    Output(#[from] io::Error),
+    #[error("could not set up the terminal: {0}")]
+    TerminalInit(io::Error),
+    #[error("interactive mode requires a terminal; try `pet-cli list`")]
+    NoTerminal,
+    #[error("could not start the event loop: {0}")]
+    Runtime(io::Error),
+    #[error("terminal error: {0}")]
+    Terminal(io::Error),
+    #[error("the terminal stopped sending input, even after reopening it")]
+    EventChannelClosed,
+    #[cfg(feature = "server")]
+    #[error("server error: {0}")]
+    Server(io::Error),
 }
 
-fn create_app_rects(total_drawing_rect: Rect) -> AppRects {
-    let areas = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(2)
-        .constraints(vec![
-            Constraint::Length(3),
-            Constraint::Min(2),
-            Constraint::Length(3),
-        ])
-        .split(total_drawing_rect);
-    AppRects {
-        menu: areas[0],
-        main_widget: areas[1],
-        copyright: areas[2],
+impl AppError {
+    /// An error from reading the DB file.
+    fn db_read(source: Error) -> AppError {
+        AppError::DbRead {
+            path: workspace_db().to_owned(),
+            source,
+        }
+    }
+
+    /// An error from changing the DB file. Changes refused because the pet
+    /// would be invalid are validation errors.
+    fn db_write(source: Error) -> AppError {
+        match source {
+            Error::InvalidPetError(message) => AppError::Validation(message),
+            source => AppError::DbWrite {
+                path: workspace_db().to_owned(),
+                source,
+            },
+        }
+    }
+
+    /// Whether the output went to a pipe that was closed before all of it
+    /// was read, which ends a command normally.
+    fn is_broken_pipe(&self) -> bool {
+        matches!(self, AppError::Output(err) if err.kind() == io::ErrorKind::BrokenPipe)
+    }
+
+    fn terminal(err: crossterm::ErrorKind) -> AppError {
+        AppError::Terminal(crossterm_io(err))
+    }
+
+    /// 2 for a bad command line or config, 3 for a DB that can't be read, 4
+    /// when the terminal fails, that of the first failed check for the UI's
+    /// startup and 1 for everything else.
+    fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Config(_)
+            | AppError::Theme(_)
+            | AppError::Names { .. }
+            | AppError::NoSuchBackup(_) => 2,
+            AppError::DbRead { .. } => 3,
+            AppError::TerminalInit(_)
+            | AppError::Runtime(_)
+            | AppError::Terminal(_)
+            | AppError::EventChannelClosed => 4,
+            AppError::NoTerminal => 5,
+            AppError::Startup(report) => report.exit_code().unwrap_or(1),
+            _ => 1,
+        }
     }
 }
 
-fn create_menu<'a>(menu_titles: &[&'a str]) -> Vec<Spans<'a>> {
-    menu_titles
-        .iter()
-        .map(|t| {
-            let (first, rest) = t.split_at(1);
-            Spans::from(vec![
-                Span::styled(
-                    first,
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::UNDERLINED),
-                ),
-                Span::styled(rest, Style::default().fg(Color::White)),
-            ])
-        })
-        .collect()
+/// Everything the main loop reacts to. The input thread and background
+/// threads all send into the same channel.
+#[derive(Debug)]
+enum AppEvent {
+    Key(KeyEvent),
+    Mouse(MouseEvent),
+    Resize(u16, u16),
+    Tick,
+    /// The DB file's modification time changed. This includes the TUI's own
+    /// writes, which `DbWatch` filters out.
+    DbChanged,
+    /// How far the DB write in flight is.
+    Progress(Progress),
+    /// A hook run after a write finished.
+    Hook(hooks::HookOutcome),
+    /// The first read of the DB, done on a thread of its own so that a large
+    /// file doesn't keep the first frame from being drawn.
+    DbLoaded(Result<Vec<Pet>, Error>),
+    /// Characters that were all waiting at once while a prompt or the notes
+    /// editor was open, which only happens when they were pasted. crossterm
+    /// 0.19 has no bracketed paste, so this is how a paste is told from
+    /// typing; see `take_paste`.
+    Paste(String),
+    Error(String),
 }
 
-fn create_copyright_paragraph<'a>() -> Paragraph<'a> {
-    Paragraph::new("pet-CLI 2020 - all rights reserved")
-        .style(Style::default().fg(Color::LightCyan))
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .style(Style::default().fg(Color::White))
-                .title("Copyright")
-                .border_type(BorderType::Plain),
-        )
+/// Everything stored in the DB file. Files from before owners existed are a
+/// bare array of pets; `db_format` reads both shapes.
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct Database {
+    /// Counts the writes, so a write can tell whether the file changed since
+    /// it was read. Files from before it existed are revision 0.
+    #[serde(default)]
+    revision: u64,
+    /// The shape of the file; see `migrations`. Always the current version
+    /// once read, and written as that.
+    schema_version: u64,
+    /// See `ids`. Files from before it existed have it set by the
+    /// migration to schema version 3.
+    #[serde(default)]
+    next_id: IdAllocator,
+    pets: Vec<Pet>,
+    #[serde(default)]
+    owners: Vec<Owner>,
 }
 
-fn render_home<'a>() -> Paragraph<'a> {
-    let home = Paragraph::new(vec![
-        Spans::from(vec![Span::raw("")]),
-        Spans::from(vec![Span::raw("Welcome")]),
-        Spans::from(vec![Span::raw("")]),
-        Spans::from(vec![Span::raw("to")]),
-        Spans::from(vec![Span::raw("")]),
-        Spans::from(vec![Span::styled(
-            "pet-CLI",
-            Style::default().fg(Color::LightBlue),
-        )]),
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Owner {
+    id: usize,
+    name: String,
+    email: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Pet {
+    id: usize,
+    name: String,
+    category: String,
+    /// Stored as `age_months`; see `Age` for DBs that have years.
+    #[serde(flatten)]
+    age: Age,
+    created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    birthdate: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner_id: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    records: Vec<MedicalRecord>,
+    /// In the order they were entered.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    weights: Vec<WeightEntry>,
+    /// The 15 digits, without spaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    microchip: Option<String>,
+    /// Free text; see `breeds` for the ones suggested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    breed: Option<String>,
+    #[serde(default)]
+    status: PetStatus,
+    /// A photo on disk, relative to the working directory unless absolute.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    photo_path: Option<PathBuf>,
+    /// Custom fields, like `diet` or `vet`; see `attributes`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    attributes: BTreeMap<String, String>,
+    /// When the pet was moved to the Trash. Archived pets are hidden from the
+    /// Pets view until restored or purged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    archived_at: Option<DateTime<Utc>>,
+    /// Fields pet-cli doesn't know, kept for when the pet is written back;
+    /// see `extra_fields`.
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A vaccination, checkup or other medical event in a pet's history.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct MedicalRecord {
+    date: NaiveDate,
+    kind: String,
+    #[serde(default)]
+    note: String,
+}
+
+impl Pet {
+    fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+
+    /// The age in months, from the birthdate when known; `None` for a
+    /// birthdate after `today`.
+    fn age_months(&self, today: NaiveDate) -> Option<usize> {
+        match self.birthdate {
+            Some(birthdate) => usize::try_from(dates::months_between(birthdate, today)).ok(),
+            None => Some(self.age.months),
+        }
+    }
+
+    /// The age to show for this pet, computed from its birthdate when known and
+    /// falling back to the stored `age` for records without one.
+    fn display_age(&self, today: NaiveDate) -> String {
+        match self.birthdate {
+            Some(birthdate) => dates::format_age(birthdate, today),
+            None => age::format(self.age.months),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum MenuItem {
+    Home,
+    Pets,
+    Owners,
+    Duplicates,
+    Trash,
+    Log,
+    Stats,
+}
+
+impl MenuItem {
+    /// Every tab in the default menu order. The `tabs` config key picks and
+    /// orders them; numbered switching and cycling go by that.
+    const ALL: [MenuItem; 7] = [
+        MenuItem::Home,
+        MenuItem::Pets,
+        MenuItem::Owners,
+        MenuItem::Duplicates,
+        MenuItem::Trash,
+        MenuItem::Log,
+        MenuItem::Stats,
+    ];
+
+    /// The tab after this one in `tabs`, wrapping around. From a tab that
+    /// isn't there, the first.
+    fn next(self, tabs: &[MenuItem]) -> MenuItem {
+        match tabs.iter().position(|tab| *tab == self) {
+            Some(index) => tabs[(index + 1) % tabs.len()],
+            None => tabs[0],
+        }
+    }
+
+    fn prev(self, tabs: &[MenuItem]) -> MenuItem {
+        match tabs.iter().position(|tab| *tab == self) {
+            Some(index) => tabs[(index + tabs.len() - 1) % tabs.len()],
+            None => tabs[0],
+        }
+    }
+}
+
+struct AppState {
+    lang: Lang,
+    menu: Vec<MenuEntry>,
+    /// The tabs of the menu, in order; never empty.
+    tabs: Vec<MenuItem>,
+    active_menu_item: MenuItem,
+    pet_list_state: ListState,
+    /// The share of the Pets tab's width taken by the list, in percent.
+    list_width: u16,
+    /// The pet the Pets selection is on, which it follows when the list
+    /// changes; see `pet_selection`.
+    selected_pet_id: Option<usize>,
+    /// Until the first read of the DB arrives, what to do once it has.
+    loading: Option<Loading>,
+    /// Whether the Pets list is grouped by category; see `grouping`.
+    grouped: bool,
+    /// The categories folded into their header, for the session.
+    folded_groups: HashSet<String>,
+    /// The folded header the selection is on, instead of a pet.
+    selected_group: Option<String>,
+    jumps: JumpList,
+    /// What the Pets list shows of each pet, in order. Always has the name.
+    list_columns: Vec<PetField>,
+    /// The columns the config gives a width, which they always have.
+    list_column_widths: Vec<(PetField, usize)>,
+    column_chooser: Option<ColumnChooser>,
+    category_manager: Option<CategoryManager>,
+    /// The workspaces from the config file, and the one open if the DB is
+    /// one of theirs.
+    workspaces: Vec<Workspace>,
+    workspace: Option<String>,
+    workspace_picker: Option<WorkspacePicker>,
+    /// The palette commands bound to function keys in the config.
+    quick_actions: Vec<config::QuickAction>,
+    saved_views: SavedViews,
+    /// The `V` overlay listing `saved_views`.
+    view_picker: Option<Picker>,
+    /// The saved view last shown or saved, named in the status bar while the
+    /// Pets tab still shows it.
+    active_view: Option<String>,
+    /// Held with `--exclusive`.
+    session_lock: Option<db_lock::DbLock>,
+    message_pager: Option<MessagePager>,
+    merge_review: Option<MergeReview>,
+    /// The rows of the Pets list that are on screen.
+    pet_viewport: Viewport,
+    status_line: StatusLine,
+    activity_log: ActivityLog,
+    /// The selections of the Owners, Duplicates, Trash and Log tabs.
+    tab_views: TabViews,
+    reminder_list_state: ListState,
+    stats_table_state: TableState,
+    stats_sort: StatsSort,
+    age_buckets: AgeBucketWidth,
+    /// How many months of pets added the Stats tab shows, and whether as the
+    /// running total.
+    growth_months: usize,
+    growth_total: bool,
+    /// Whether `:export csv` and `:export json` have the pets' extra fields.
+    export_extra_fields: bool,
+    add_defaults: quick_add::AddDefaults,
+    /// How the birthdate and record prompts read `01/03/2024`.
+    date_order: Option<DateOrder>,
+    db_path: String,
+    db_watch: DbWatch,
+    write_options: WriteOptions,
+    /// The category, search and order of the Pets tab.
+    view: ViewParams,
+    marks: Marks,
+    /// The pet cut with `X`, which stays where it is until pasted.
+    cut_pet: Option<usize>,
+    yank_ring: YankRing,
+    /// The `"` overlay listing `yank_ring`.
+    yank_picker: Option<Picker>,
+    pending_confirmation: Option<Confirmation>,
+    notes_editor: Option<NotesEditor>,
+    notes_scroll: u16,
+    records_scroll: usize,
+    detail_scroll: usize,
+    prompt: Option<Prompt>,
+    /// A count typed before a command in the Pets view, as in `5a`.
+    pending_count: Option<usize>,
+    /// The first key of a two-key command like `dd`.
+    chord: Chord,
+    idle_lock: IdleLock,
+    /// Set with `--kiosk` until unlocked.
+    kiosk: Option<Kiosk>,
+    /// Set with `--tutorial`.
+    tutorial: Option<Tutorial>,
+    animations: Animations,
+    quick_open: Option<QuickOpen>,
+    palette: Option<Palette>,
+    restore_preview: Option<RestorePreview>,
+    /// Set with `--dry-run`, when the DB is a scratch copy.
+    dry_run: Option<dry_run::DryRun>,
+    /// On the made-up DB of `--demo`.
+    demo: bool,
+    /// `--safe-mode`: the state file is left alone.
+    safe_mode: bool,
+    /// The changes of the dry run, shown on quitting it.
+    dry_run_summary: Option<dry_run::Summary>,
+    /// Saving the dry run over a DB that changed meanwhile.
+    reconcile: Option<Reconcile>,
+    macros: Macros,
+    /// What the last check of the DB found, if it could be read.
+    integrity: Option<integrity::Report>,
+    /// Whether the findings of `integrity` are shown over the UI.
+    integrity_open: bool,
+    /// Where the form being filled in is saved while typing, if there is a
+    /// cache directory.
+    draft_path: Option<PathBuf>,
+    /// The form as last saved there.
+    saved_draft: Option<draft::Form>,
+    draft_ticks: u32,
+    /// The commands run from the palette this session, oldest first.
+    command_history: Vec<String>,
+    /// The searches of this session, oldest first.
+    search_history: Vec<String>,
+    clipboard: Clipboard,
+    #[cfg(feature = "image-preview")]
+    photos: photo::PhotoCache,
+    db_writer: DbWriter,
+    /// The title of the menu bar.
+    title: String,
+    /// The text of the footer, or `None` to leave the footer out.
+    footer: Option<String>,
+    /// How long drawing the previous frame took, shown with `--show-frame-time`.
+    frame_time: Option<Duration>,
+    /// Whether anything on screen changed since the last frame. Nothing is
+    /// drawn while this is unset.
+    dirty: bool,
+    /// When the earliest "5 minutes ago" on screen will read differently.
+    next_time_change: Option<DateTime<Utc>>,
+    draw_rate: DrawRate,
+    /// Whether the status bar shows `draw_rate`, toggled with a key left out
+    /// of the help.
+    show_draw_rate: bool,
+    /// The pets when the session started, or switched to the workspace it
+    /// is in, and the DB writes up to then, for the summary on quitting.
+    session_start_pets: Vec<Pet>,
+    session_start_writes: u64,
+    /// Whether the diagnostics are shown over the UI, toggled with F12.
+    show_diagnostics: bool,
+    /// How long drawing the previous frame took, for the diagnostics.
+    last_frame_time: Duration,
+    /// How many events were waiting when the main loop last woke up.
+    events_waiting: usize,
+    theme: Theme,
+    /// What the terminal can show; see `capabilities`.
+    capabilities: Capabilities,
+    /// How pets added with `a` are named.
+    names: generator::Names,
+}
+
+/// A single-line question shown over the UI, answered with Enter.
+struct Prompt {
+    kind: PromptKind,
+    input: TextInput,
+    /// Up and Down through earlier searches, in the search prompt.
+    history: HistoryCursor,
+    /// Tab through pet names and categories, in the search prompt.
+    completer: Completer,
+    /// Why the last answer was refused, shown under it until the next key.
+    error: Option<String>,
+}
+
+impl Prompt {
+    fn new(kind: PromptKind, text: &str) -> Prompt {
+        Prompt {
+            kind,
+            input: TextInput::new(text),
+            history: HistoryCursor::default(),
+            completer: Completer::default(),
+            error: None,
+        }
+    }
+
+    /// Keeps the prompt open to correct the answer, with `error` under it.
+    /// The error also goes to the status bar, which is what stops a macro.
+    fn reject(mut self, error: String, app_state: &mut AppState) {
+        app_state.status_line.error(error.clone());
+        self.error = Some(error);
+        app_state.prompt = Some(self);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum PromptKind {
+    Birthdate {
+        pet_id: usize,
+    },
+    NewOwner,
+    Owner {
+        pet_id: usize,
+    },
+    Record {
+        pet_id: usize,
+    },
+    /// The name for a copy of `pet_id`, which is only created once confirmed.
+    Copy {
+        pet_id: usize,
+    },
+    Search,
+    Age {
+        pet_id: usize,
+    },
+    Weight {
+        pet_id: usize,
+    },
+    Microchip {
+        pet_id: usize,
+    },
+    Breed {
+        pet_id: usize,
+    },
+    Photo {
+        pet_id: usize,
+    },
+    Attribute {
+        pet_id: usize,
+    },
+    /// Sets a field on the pets that were marked, or shown, when `B` was
+    /// pressed.
+    BatchEdit {
+        ids: Vec<usize>,
+    },
+    Import,
+    MergeDb,
+    /// The name of a pet to add, with the rest from the `add_` config keys.
+    QuickAdd,
+    /// Typed into the pet's row of the list rather than over the UI.
+    Rename {
+        pet_id: usize,
+    },
+    /// From `r` in the `V` picker.
+    RenameView {
+        name: String,
+    },
+    /// From `r` and `m` in the `=` overlay: the categories whose pets get
+    /// the category typed.
+    CategoryName {
+        from: Vec<String>,
+    },
+    /// Takes `reset::CONFIRM_WORD` typed out before resetting the DB.
+    ResetDb,
+}
+
+impl PromptKind {
+    fn label(&self) -> &'static str {
+        match self {
+            PromptKind::Birthdate { .. } => {
+                "Birthdate (e.g. 2024-03-01, 01/03/2024 or -2w; empty to clear)"
+            }
+            PromptKind::NewOwner => "New owner (name, email)",
+            PromptKind::Owner { .. } => "Owner name or id (empty to clear)",
+            PromptKind::Record { .. } => "Medical record (date, kind, note)",
+            PromptKind::Copy { .. } => "Name of the copy",
+            PromptKind::Search => "Search, e.g. category:cats age:>5 rex (empty to show all)",
+            PromptKind::Age { .. } => "Age (e.g. 3y, 8m or 1y6m; 12d or 3w sets the birthdate)",
+            PromptKind::Weight { .. } => "Weight today (e.g. 4.2kg or 850g)",
+            PromptKind::Microchip { .. } => "Microchip number (15 digits, empty to clear)",
+            PromptKind::Breed { .. } => "Breed (Tab suggests dog and cat breeds, empty to clear)",
+            PromptKind::Photo { .. } => "Path of the photo (empty to clear)",
+            PromptKind::Attribute { .. } => "Attribute (key=value, or key= to remove it)",
+            PromptKind::BatchEdit { .. } => {
+                "Set on every marked or shown pet (category, status, age or attr, and the value)"
+            }
+            PromptKind::Import => "Import pets from (a JSON file of one pet or a list of them)",
+            PromptKind::MergeDb => "Merge the pets and owners of the DB file",
+            PromptKind::QuickAdd => "Name of the pet to add (or name, category, age)",
+            PromptKind::Rename { .. } => "New name",
+            PromptKind::RenameView { .. } => "New name of the view (one word)",
+            PromptKind::CategoryName { .. } => "New category (an existing one merges them)",
+            PromptKind::ResetDb => "Type reset to start an empty DB; the old file is kept",
+        }
+    }
+}
+
+/// Runs on the main loop after a background write, with the write's result.
+type WriteDone = Box<dyn FnOnce(&mut AppState) + Send>;
+
+/// DB changes waiting to be written, and whether one is being written now.
+struct DbWriter {
+    queue: VecDeque<DbChange>,
+    in_flight: bool,
+    done_tx: UnboundedSender<WriteDone>,
+    done_rx: UnboundedReceiver<WriteDone>,
+    progress_tx: UnboundedSender<Progress>,
+    progress_rx: UnboundedReceiver<Progress>,
+    /// How far the write in flight is, once it has said so. Only the long
+    /// ones do.
+    progress: Option<Progress>,
+    /// Stops the write in flight before it writes anything.
+    cancel: Cancel,
+    /// Run after every write that changed the DB.
+    hooks: hooks::Hooks,
+    hook_tx: UnboundedSender<hooks::HookOutcome>,
+    hook_rx: UnboundedReceiver<hooks::HookOutcome>,
+}
+
+impl Default for DbWriter {
+    fn default() -> Self {
+        let (done_tx, done_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (progress_tx, progress_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (hook_tx, hook_rx) = tokio::sync::mpsc::unbounded_channel();
+        Self {
+            queue: VecDeque::new(),
+            in_flight: false,
+            done_tx,
+            done_rx,
+            progress_tx,
+            progress_rx,
+            progress: None,
+            cancel: Cancel::default(),
+            hooks: hooks::Hooks::default(),
+            hook_tx,
+            hook_rx,
+        }
+    }
+}
+
+impl DbWriter {
+    fn is_busy(&self) -> bool {
+        self.in_flight || !self.queue.is_empty()
+    }
+}
+
+/// An action waiting for the user to press `y` before it runs.
+enum Confirmation {
+    ArchiveMarked(HashSet<usize>),
+    /// The pets from `first` to `last` in the list, as picked with a count.
+    ArchiveRange {
+        ids: HashSet<usize>,
+        first: String,
+        last: String,
+    },
+    /// Folds the other pets of a duplicate group into `survivor_id`.
+    Merge {
+        survivor_id: usize,
+        name: String,
+        other_ids: Vec<usize>,
+    },
+    /// Deletes an archived pet for good.
+    Purge {
+        pet_id: usize,
+        name: String,
+    },
+    /// Deletes these archived pets for good.
+    PurgeMarked(HashSet<usize>),
+    /// Deleting an owner who still has pets leaves those pets without one.
+    DeleteOwner {
+        owner_id: usize,
+        name: String,
+        pet_count: usize,
+    },
+    /// Sets a field on many pets at once.
+    BatchEdit {
+        ids: HashSet<usize>,
+        assignment: batch::Assignment,
+    },
+    /// The DB changed on disk since the view was last refreshed.
+    Overwrite(DbChange),
+    /// Asks the first, then the second whatever the answer was.
+    Chain(Box<Confirmation>, Box<Confirmation>),
+}
+
+impl Confirmation {
+    fn prompt(&self) -> String {
+        match self {
+            Confirmation::ArchiveMarked(ids) => {
+                format!("Move {} marked pets to the Trash? (y/n)", ids.len())
+            }
+            Confirmation::ArchiveRange { ids, first, last } => format!(
+                "Move {} pets, {} to {}, to the Trash? (y/n)",
+                ids.len(),
+                first,
+                last
+            ),
+            Confirmation::Merge {
+                name, other_ids, ..
+            } => format!(
+                "Merge {} other pets into {} and delete them? (y/n)",
+                other_ids.len(),
+                name
+            ),
+            Confirmation::Purge { name, .. } => {
+                format!("Delete {} permanently? This can't be undone. (y/n)", name)
+            }
+            Confirmation::PurgeMarked(ids) => format!(
+                "Delete {} pets in the Trash permanently? This can't be undone. (y/n)",
+                ids.len()
+            ),
+            Confirmation::DeleteOwner {
+                name, pet_count, ..
+            } => format!(
+                "{} still owns {} pets. Delete and leave them without an owner? (y/n)",
+                name, pet_count
+            ),
+            Confirmation::BatchEdit { ids, assignment } => {
+                format!("Set {} on {} pets? (y/n)", assignment, ids.len())
+            }
+            Confirmation::Overwrite(_) => "db changed on disk, apply anyway? (y/n)".to_owned(),
+            Confirmation::Chain(first, _) => first.prompt(),
+        }
+    }
+}
+
+/// A write to the DB requested from the UI.
+#[derive(Debug)]
+enum DbChange {
+    /// Adds this many random pets.
+    Add {
+        count: usize,
+        names: generator::Names,
+    },
+    Archive(HashSet<usize>),
+    Restore(usize),
+    /// Deletes for good those of the pets that are in the Trash.
+    Purge(HashSet<usize>),
+    Merge {
+        survivor_id: usize,
+        other_ids: Vec<usize>,
+    },
+    Notes {
+        pet_id: usize,
+        notes: Option<String>,
+    },
+    Birthdate {
+        pet_id: usize,
+        birthdate: Option<NaiveDate>,
+    },
+    Age {
+        pet_id: usize,
+        age: Age,
+    },
+    Owner {
+        pet_id: usize,
+        owner_id: Option<usize>,
+    },
+    AddOwner {
+        name: String,
+        email: String,
+    },
+    DeleteOwner(usize),
+    Copy {
+        pet_id: usize,
+        name: String,
+    },
+    AddRecord {
+        pet_id: usize,
+        record: MedicalRecord,
+    },
+    AddWeight {
+        pet_id: usize,
+        entry: WeightEntry,
+    },
+    Microchip {
+        pet_id: usize,
+        microchip: Option<String>,
+    },
+    Breed {
+        pet_id: usize,
+        breed: Option<String>,
+    },
+    Photo {
+        pet_id: usize,
+        photo_path: Option<PathBuf>,
+    },
+    /// Moves the pet next to `anchor_id` in the DB, or to the end.
+    Move {
+        pet_id: usize,
+        anchor_id: Option<usize>,
+        side: reorder::Side,
+    },
+    /// Sets a field on the pets with `ids`, all of them or none.
+    BatchEdit {
+        ids: HashSet<usize>,
+        assignment: batch::Assignment,
+    },
+    /// Gives every pet of some categories another one, in a single write.
+    RenameCategories(categories::Rename),
+    /// Sets the attribute `key`, or removes it if `value` is `None`.
+    Attribute {
+        pet_id: usize,
+        key: String,
+        value: Option<String>,
+    },
+    Rename {
+        pet_id: usize,
+        name: String,
+    },
+    /// Moves the pet to the status after the one it has in the DB.
+    AdvanceStatus(usize),
+    /// Adds pets read from a shared file, all or none of them.
+    Import(Vec<Pet>),
+    /// Adds a pet typed into the quick add prompt or pasted from the yank
+    /// ring, under a new id.
+    AddPet(Box<Pet>),
+    /// Merges another DB file, deciding conflicts as in `resolutions`, by
+    /// the id of their pet.
+    MergeDb {
+        theirs: Database,
+        resolutions: HashMap<usize, Strategy>,
+    },
+    /// Replaces the DB with a backup, after backing it up in turn.
+    RestoreBackup {
+        path: PathBuf,
+        created_at: DateTime<Utc>,
+    },
+    /// Moves the DB file aside and starts an empty one; see `reset`.
+    Reset,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        let mut pet_list_state = ListState::default();
+        pet_list_state.select(Some(0));
+        let mut reminder_list_state = ListState::default();
+        reminder_list_state.select(Some(0));
+        let mut stats_table_state = TableState::default();
+        stats_table_state.select(Some(0));
+        Self {
+            lang: Lang::default(),
+            menu: MENU_ENTRIES.to_vec(),
+            tabs: MenuItem::ALL.to_vec(),
+            active_menu_item: MenuItem::Home,
+            pet_list_state,
+            status_line: StatusLine::default(),
+            activity_log: ActivityLog::default(),
+            pet_viewport: Viewport::default(),
+            tab_views: TabViews::default(),
+            reminder_list_state,
+            stats_table_state,
+            stats_sort: StatsSort::default(),
+            age_buckets: AgeBucketWidth::default(),
+            growth_months: stats::GROWTH_MONTHS,
+            growth_total: false,
+            export_extra_fields: false,
+            add_defaults: quick_add::AddDefaults::default(),
+            date_order: None,
+            db_path: resolved_db_path(),
+            db_watch: DbWatch::new(db_path()),
+            write_options: WriteOptions {
+                format: DbFormat::default(),
+                keep_backups: 10,
+                read_only: false,
+                durability: Durability::default(),
+                compress: Compress::default(),
+                compress_level: 1,
+                audit_max_bytes: None,
+            },
+            view: ViewParams::default(),
+            marks: Marks::default(),
+            cut_pet: None,
+            yank_ring: YankRing::default(),
+            yank_picker: None,
+            pending_confirmation: None,
+            notes_editor: None,
+            notes_scroll: 0,
+            records_scroll: 0,
+            detail_scroll: 0,
+            prompt: None,
+            pending_count: None,
+            chord: Chord::default(),
+            idle_lock: IdleLock::new(None, Instant::now()),
+            kiosk: None,
+            tutorial: None,
+            animations: Animations::new(true),
+            quick_open: None,
+            palette: None,
+            restore_preview: None,
+            dry_run: None,
+            demo: false,
+            safe_mode: false,
+            dry_run_summary: None,
+            reconcile: None,
+            macros: Macros::default(),
+            integrity: None,
+            integrity_open: false,
+            draft_path: None,
+            saved_draft: None,
+            draft_ticks: 0,
+            command_history: Vec::new(),
+            search_history: Vec::new(),
+            clipboard: Clipboard::default(),
+            #[cfg(feature = "image-preview")]
+            photos: photo::PhotoCache::default(),
+            db_writer: DbWriter::default(),
+            title: "Menu".to_owned(),
+            footer: Some(default_footer()),
+            frame_time: None,
+            dirty: true,
+            next_time_change: None,
+            draw_rate: DrawRate::default(),
+            show_draw_rate: false,
+            session_start_pets: Vec::new(),
+            session_start_writes: 0,
+            show_diagnostics: false,
+            last_frame_time: Duration::ZERO,
+            events_waiting: 0,
+            list_width: DEFAULT_LIST_WIDTH,
+            selected_pet_id: None,
+            loading: None,
+            grouped: false,
+            folded_groups: HashSet::new(),
+            selected_group: None,
+            jumps: JumpList::default(),
+            list_columns: vec![PetField::Name, PetField::Status],
+            list_column_widths: Vec::new(),
+            column_chooser: None,
+            category_manager: None,
+            workspaces: Vec::new(),
+            workspace: None,
+            workspace_picker: None,
+            quick_actions: Vec::new(),
+            saved_views: SavedViews::default(),
+            view_picker: None,
+            active_view: None,
+            session_lock: None,
+            message_pager: None,
+            merge_review: None,
+            theme: Theme::default(),
+            capabilities: Capabilities::ASCII,
+            names: generator::Names::default(),
+        }
+    }
+}
+
+impl AppState {
+    /// The pets currently shown in the Pets view, in display order. Indices in
+    /// `pet_list_state` refer to this list, not to the DB.
+    fn visible_pets<'p>(&self, pets: &'p [Pet]) -> Vec<&'p Pet> {
+        let mut visible = self.view.apply(pets);
+        if self.grouped {
+            grouping::order(&mut visible);
+        }
+        visible
+    }
+
+    /// The rows of the Pets list: a pet each, or with `grouped` the group
+    /// headers too, without the pets of folded groups.
+    fn pet_rows(&self, visible: &[&Pet]) -> Vec<ListRow> {
+        if !self.grouped {
+            return grouping::flat(visible.len());
+        }
+        let categories: Vec<&str> = visible.iter().map(|pet| pet.category.as_str()).collect();
+        grouping::rows(&categories, &self.folded_groups)
+    }
+
+    /// The row of `rows` the selection is on.
+    fn selected_row(&self, visible: &[&Pet], rows: &[ListRow]) -> Option<usize> {
+        if let Some(category) = &self.selected_group {
+            return grouping::row_of_header(rows, category);
+        }
+        let index = self.pet_list_state.selected()?;
+        if !self.grouped {
+            return Some(index);
+        }
+        let categories: Vec<&str> = visible.iter().map(|pet| pet.category.as_str()).collect();
+        grouping::row_of_pet(rows, &categories, index)
+    }
+
+    fn selected_pet<'p>(&self, visible: &[&'p Pet]) -> Option<&'p Pet> {
+        self.pet_list_state
+            .selected()
+            .and_then(|index| visible.get(index).copied())
+    }
+
+    /// Shows the outcome of an action in the status bar and records it in the
+    /// activity log.
+    fn report(&mut self, level: StatusLevel, text: String) {
+        match level {
+            StatusLevel::Info => {
+                log::info!("{}", text);
+                self.status_line.info(text.clone())
+            }
+            StatusLevel::Error => {
+                log::error!("{}", text);
+                self.status_line.error(text.clone())
+            }
+        }
+        self.activity_log.record(level, text);
+    }
+}
+
+fn restore_ui_state(app_state: &mut AppState, state: &PersistedUiState) {
+    app_state.active_menu_item = state.active_menu_item;
+    if let Some(list_width) = state.list_width {
+        app_state.list_width = resize_split(list_width, 0);
+    }
+    // Only columns the list can show, and never without the name, as a state
+    // file may have been edited.
+    if let Some(columns) = &state.list_columns {
+        let usable = columns.contains(&PetField::Name)
+            && columns
+                .iter()
+                .all(|column| fields::LIST_COLUMNS.contains(column));
+        if usable {
+            app_state.list_columns = columns.clone();
+        }
+    }
+    // Followed once the DB is loaded, like any selection.
+    app_state.selected_pet_id = state.selected_pet_id;
+    app_state.tab_views.restore(&state.tab_selections);
+}
+
+/// Selects the pet with `pet_id` if the Pets tab shows it, returning whether
+/// it does.
+fn select_pet(app_state: &mut AppState, pets: &[Pet], pet_id: usize) -> bool {
+    let visible = app_state.visible_pets(pets);
+    match visible.iter().position(|pet| pet.id == pet_id) {
+        Some(index) => {
+            app_state.pet_list_state.select(Some(index));
+            app_state.selected_pet_id = Some(pet_id);
+            app_state.selected_group = None;
+            app_state.folded_groups.remove(&visible[index].category);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Selects `row` of the Pets list, a pet or a folded header.
+fn select_row(app_state: &mut AppState, visible: &[&Pet], rows: &[ListRow], row: Option<usize>) {
+    match row.and_then(|row| rows.get(row)) {
+        Some(ListRow::Pet(index)) => {
+            app_state.pet_list_state.select(Some(*index));
+            app_state.selected_pet_id = Some(visible[*index].id);
+            app_state.selected_group = None;
+        }
+        Some(ListRow::Header { category, .. }) => {
+            app_state.pet_list_state.select(None);
+            app_state.selected_pet_id = None;
+            app_state.selected_group = Some(category.clone());
+        }
+        None => {}
+    }
+}
+
+/// Folds the group of the selected pet into its header, or unfolds the
+/// folded one the selection is on and selects its first pet.
+fn toggle_group(app_state: &mut AppState, pets: &[Pet]) {
+    if !app_state.grouped {
+        app_state.status_line.info(format!(
+            "group the list by category first with z{}",
+            keys::GROUP
+        ));
+        return;
+    }
+    let visible = app_state.visible_pets(pets);
+    if let Some(category) = app_state.selected_group.take() {
+        app_state.folded_groups.remove(&category);
+        let rows = app_state.pet_rows(&visible);
+        let first = grouping::row_of_header(&rows, &category).map(|header| header + 1);
+        select_row(app_state, &visible, &rows, first);
+    } else if let Some(pet) = app_state.selected_pet(&visible) {
+        app_state.folded_groups.insert(pet.category.clone());
+        let rows = app_state.pet_rows(&visible);
+        let header = grouping::row_of_header(&rows, &pet.category);
+        select_row(app_state, &visible, &rows, header);
+    }
+}
+
+fn capture_ui_state(app_state: &AppState) -> PersistedUiState {
+    PersistedUiState {
+        active_menu_item: app_state.active_menu_item,
+        // Also right when quitting before the DB was loaded.
+        selected_pet_id: app_state.selected_pet_id,
+        tab_selections: app_state.tab_views.store(),
+        list_width: Some(app_state.list_width),
+        list_columns: Some(app_state.list_columns.clone()),
+        saved_views: app_state.saved_views.store(),
+    }
+}
+
+/// Takes the saved views of `state`, warning about and leaving out those
+/// that no longer read.
+fn load_saved_views(app_state: &mut AppState, state: &PersistedUiState) {
+    let (saved_views, warnings) = SavedViews::load(&state.saved_views);
+    app_state.saved_views = saved_views;
+    for warning in warnings {
+        log::warn!("{}", warning);
+        app_state.report(StatusLevel::Error, warning);
+    }
+}
+
+/// Writes the saved views to the state file straight away, so they are kept
+/// with `--no-restore` too. The rest of the file stays as the last session
+/// left it.
+fn save_views(app_state: &mut AppState) {
+    if app_state.safe_mode {
+        app_state
+            .status_line
+            .info("views aren't saved in safe mode, only kept for the session");
+        return;
+    }
+    let path = ui_state::state_path(Path::new(&workspace_db()));
+    let mut state = ui_state::load(&path).unwrap_or_else(|| capture_ui_state(app_state));
+    state.saved_views = app_state.saved_views.store();
+    if let Err(err) = ui_state::save(&path, &state) {
+        app_state.report(
+            StatusLevel::Error,
+            format!("could not save the views to {}: {}", path.display(), err),
+        );
+    }
+}
+
+/// Shows the saved view at `index` on the Pets tab, replacing the search,
+/// filters and sort.
+fn apply_view(app_state: &mut AppState, index: usize) {
+    let view = &app_state.saved_views.views[index];
+    app_state.view = view.params();
+    app_state.active_view = Some(view.name.clone());
+    app_state
+        .status_line
+        .info(format!("showing view {}", view.name));
+    app_state.active_menu_item = MenuItem::Pets;
+    app_state.marks.clear();
+}
+
+#[derive(Debug, PartialEq)]
+enum ResponseToUserInput {
+    Continue,
+    Stop,
+}
+
+fn handle_event<B: Backend>(
+    event: AppEvent,
+    terminal: &mut Terminal<B>,
+    app_state: &mut AppState,
+) -> Result<ResponseToUserInput, AppError> {
+    // Ticks only change the screen once something expires, and mouse
+    // events are ignored.
+    if !matches!(event, AppEvent::Tick | AppEvent::Mouse(_)) {
+        app_state.dirty = true;
+    }
+    match event {
+        AppEvent::Key(event) => {
+            log::debug!("key {:?} with {:?}", event.code, event.modifiers);
+            return handle_key(event, app_state);
+        }
+        AppEvent::Mouse(event) => log::trace!("ignoring mouse {:?}", event.kind),
+        AppEvent::Paste(text) => {
+            log::debug!("paste of {} bytes", text.len());
+            if app_state.idle_lock.input(Instant::now()) {
+                paste(&text, app_state);
+            }
+        }
+        AppEvent::Resize(width, height) => {
+            log::debug!("resized to {}x{}", width, height);
+            terminal
+                .resize(Rect::new(0, 0, width, height))
+                .map_err(AppError::Terminal)?;
+            terminal.clear().map_err(AppError::Terminal)?;
+        }
+        AppEvent::Tick => {
+            log::trace!("tick");
+            // The diagnostics are kept up to date.
+            if expire_timed_state(app_state)
+                || app_state.idle_lock.tick(Instant::now())
+                || reset_idle_kiosk(app_state)
+                || app_state.animations.tick(Instant::now())
+                || app_state.show_diagnostics
+            {
+                app_state.dirty = true;
+            }
+        }
+        AppEvent::DbLoaded(loaded) => finish_loading(loaded, app_state),
+        AppEvent::DbChanged => {
+            // Until the first read arrives there is nothing to reload.
+            let loaded = app_state.loading.is_none();
+            if loaded && !app_state.db_writer.is_busy() && app_state.db_watch.changed() {
+                reload_after_external_change(app_state);
+            }
+        }
+        AppEvent::Progress(progress) => {
+            // Reports are of the first read until it is done, then of writes.
+            // A report can arrive after the write it is about has finished.
+            if let Some(loading) = &mut app_state.loading {
+                loading.progress = Some(progress);
+            } else if app_state.db_writer.in_flight {
+                app_state.db_writer.progress = Some(progress);
+            }
+        }
+        AppEvent::Hook(outcome) => match outcome.result {
+            Ok(output) if output.is_empty() => {
+                log::info!("{} hook ran", outcome.key);
+            }
+            Ok(output) => {
+                log::info!("{} hook: {}", outcome.key, output);
+                let text = format!("{} hook: {}", outcome.key, output);
+                app_state.activity_log.record(StatusLevel::Info, text);
+            }
+            Err(err) => app_state.report(
+                StatusLevel::Error,
+                format!("{} hook failed: {}", outcome.key, err),
+            ),
+        },
+        AppEvent::Error(err) => {
+            app_state.report(StatusLevel::Error, format!("input error: {}", err))
+        }
+    }
+    Ok(ResponseToUserInput::Continue)
+}
+
+fn handle_key(event: KeyEvent, app_state: &mut AppState) -> Result<ResponseToUserInput, AppError> {
+    profile_span!("command", key = ?event.code);
+    if !app_state.idle_lock.input(Instant::now()) {
+        log::debug!("key dismissed the idle lock");
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(kiosk) = &mut app_state.kiosk {
+        if kiosk.input(event, Instant::now()) {
+            leave_kiosk(app_state);
+            return Ok(ResponseToUserInput::Continue);
+        }
+    }
+    if app_state.loading.is_some() {
+        let mode = InputMode {
+            tab: app_state.active_menu_item,
+            counting: false,
+            folding: false,
+        };
+        match action::map_key(event, &mode) {
+            // Nothing can have been changed yet, even in a dry run.
+            Some(Action::Quit) if app_state.kiosk.is_none() => {
+                return Ok(ResponseToUserInput::Stop)
+            }
+            Some(action) if action.changes_db() => app_state
+                .status_line
+                .info("still loading the DB, changes can be made once it is read"),
+            _ => {}
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(tutorial) = &mut app_state.tutorial {
+        if tutorial.is_over() {
+            return Ok(ResponseToUserInput::Stop);
+        }
+        if event.code == KeyCode::Esc {
+            log::info!(
+                "tutorial quit at step {} of {}",
+                tutorial.progress().0,
+                tutorial.progress().1
+            );
+            tutorial.quit();
+            return Ok(ResponseToUserInput::Continue);
+        }
+    }
+    if event.code == KeyCode::Esc && app_state.db_writer.progress.is_some() {
+        app_state.db_writer.cancel.cancel();
+        app_state.status_line.info("cancelling…");
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if key_taken_first(app_state) {
+        return handle_key_taken_first(event, app_state);
+    }
+    if let Some(prompt) = &mut app_state.prompt {
+        log::debug!("key goes to prompt '{}'", prompt.kind.label());
+        let search = prompt.kind == PromptKind::Search;
+        let breed_of = match prompt.kind {
+            PromptKind::Breed { pet_id } => Some(pet_id),
+            _ => None,
+        };
+        if search && handle_search_key(event.code, app_state) {
+            return Ok(ResponseToUserInput::Continue);
+        }
+        if breed_of.is_some_and(|pet_id| handle_breed_key(event.code, pet_id, app_state)) {
+            return Ok(ResponseToUserInput::Continue);
+        }
+        let prompt = app_state.prompt.as_mut().expect("prompt is open");
+        prompt.error = None;
+        match prompt.input.handle_key(event.code) {
+            InputOutcome::Editing => {}
+            InputOutcome::Cancelled => app_state.prompt = None,
+            InputOutcome::Submitted => {
+                let prompt = app_state.prompt.take().expect("prompt is open");
+                let answer = prompt.input.text().to_owned();
+                app_state.macros.record(Step::Answer(answer));
+                submit_prompt(prompt, app_state);
+            }
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(editor) = &mut app_state.notes_editor {
+        log::debug!("key goes to notes editor of pet {}", editor.pet_id);
+        if let EditorOutcome::Finished = editor.handle_key(event.code) {
+            let editor = app_state.notes_editor.take().expect("editor is open");
+            let pet_id = editor.pet_id;
+            let notes = editor.into_notes();
+            request_change(DbChange::Notes { pet_id, notes }, app_state);
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(pending) = app_state.macros.take_pending() {
+        return match event.code {
+            KeyCode::Char(register)
+                if pending == Pending::Record && Macros::is_register(register) =>
+            {
+                app_state.macros.start(register);
+                app_state
+                    .status_line
+                    .info(format!("recording @{}", register));
+                Ok(ResponseToUserInput::Continue)
+            }
+            KeyCode::Char(register)
+                if pending == Pending::Replay
+                    && (Macros::is_register(register) || register == keys::REPLAY_MACRO) =>
+            {
+                replay_macro(register, app_state)
+            }
+            _ => {
+                app_state.status_line.info("cancelled");
+                Ok(ResponseToUserInput::Continue)
+            }
+        };
+    }
+    let now = Instant::now();
+    app_state.chord.expire(now);
+    let folding = app_state.chord.pending() == Some(keys::FOLD);
+    match event.code {
+        KeyCode::Char(key) if (event.modifiers - KeyModifiers::SHIFT).is_empty() => {
+            if !app_state.chord.press(key, now) {
+                return Ok(ResponseToUserInput::Continue);
+            }
+        }
+        _ => app_state.chord.cancel(),
+    }
+    if let KeyCode::F(key) = event.code {
+        if let Some(quick_action) = app_state
+            .quick_actions
+            .iter()
+            .find(|quick_action| quick_action.key == key)
+        {
+            let palette::Command { count, action } = quick_action.command.clone();
+            app_state.status_line.info(format!(
+                "{}: {}",
+                quick_action.key_name(),
+                quick_action.line
+            ));
+            // A count typed first counts for commands that have none.
+            app_state.pending_count = count.or(app_state.pending_count);
+            return record_and_apply(action, app_state);
+        }
+    }
+    let mode = InputMode {
+        tab: app_state.active_menu_item,
+        counting: app_state.pending_count.is_some(),
+        folding,
+    };
+    match action::map_key(event, &mode) {
+        Some(action) => record_and_apply(action, app_state),
+        None => {
+            app_state.pending_count = None;
+            Ok(ResponseToUserInput::Continue)
+        }
+    }
+}
+
+/// Whether something in front of the text inputs and the normal keys takes
+/// the next key: the idle lock, the first read of the DB, a confirmation or
+/// one of the overlays. `handle_key`, the batching of navigation keys and
+/// pastes all ask this, so they agree on where a key goes.
+fn key_taken_first(app_state: &AppState) -> bool {
+    app_state.idle_lock.is_locked()
+        || app_state.loading.is_some()
+        || app_state.pending_confirmation.is_some()
+        || app_state.integrity_open
+        || app_state.dry_run_summary.is_some()
+        || app_state.reconcile.is_some()
+        || app_state.restore_preview.is_some()
+        || app_state.merge_review.is_some()
+        || app_state.view_picker.is_some()
+        || app_state.yank_picker.is_some()
+        || app_state.workspace_picker.is_some()
+        || app_state.message_pager.is_some()
+        || app_state.column_chooser.is_some()
+        || app_state.category_manager.is_some()
+        || app_state.quick_open.is_some()
+        || app_state.palette.is_some()
+}
+
+/// Whether each key has to reach `handle_key` on its own, rather than in a
+/// batch of navigation keys or a paste: a kiosk counts every key towards its
+/// unlock characters and idle time, and the tutorial watches for Esc.
+fn keys_watched(app_state: &AppState) -> bool {
+    app_state.kiosk.is_some() || app_state.tutorial.is_some()
+}
+
+/// The key for the confirmation or overlay in front, once `key_taken_first`
+/// says there is one.
+fn handle_key_taken_first(
+    event: KeyEvent,
+    app_state: &mut AppState,
+) -> Result<ResponseToUserInput, AppError> {
+    if let Some(confirmation) = app_state.pending_confirmation.take() {
+        log::debug!("answering confirmation: {}", confirmation.prompt());
+        if event.code == KeyCode::Char(keys::CONFIRM) {
+            confirm(confirmation, app_state);
+        } else {
+            decline(confirmation, app_state);
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if app_state.integrity_open {
+        app_state.integrity_open = false;
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(summary) = &mut app_state.dry_run_summary {
+        log::debug!("key goes to the dry run summary");
+        match summary.handle_key(event) {
+            dry_run::SummaryOutcome::Open => {}
+            dry_run::SummaryOutcome::Cancelled => app_state.dry_run_summary = None,
+            dry_run::SummaryOutcome::Discard => return Ok(ResponseToUserInput::Stop),
+            dry_run::SummaryOutcome::Save => {
+                app_state.dry_run_summary = None;
+                return Ok(save_dry_run(app_state));
+            }
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(reconcile) = &mut app_state.reconcile {
+        log::debug!("key goes to the reconciling of the dry run");
+        match reconcile.handle_key(event) {
+            ReconcileOutcome::Open => {}
+            ReconcileOutcome::Cancelled => {
+                app_state.reconcile = None;
+                app_state.status_line.info("the dry run was not saved");
+            }
+            ReconcileOutcome::KeepTheirs => {
+                log::info!("dropped the dry run for the changes on disk");
+                return Ok(ResponseToUserInput::Stop);
+            }
+            ReconcileOutcome::KeepMine => {
+                let reconcile = app_state.reconcile.take().expect("reconciling is open");
+                return Ok(save_dry_run_over(app_state, reconcile, false));
+            }
+            ReconcileOutcome::Merge => {
+                let reconcile = app_state.reconcile.take().expect("reconciling is open");
+                return Ok(save_dry_run_over(app_state, reconcile, true));
+            }
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if app_state.restore_preview.is_some() {
+        log::debug!("key goes to the restore preview");
+        let Some(Database { pets, .. }) = read_db_or_report(app_state) else {
+            return Ok(ResponseToUserInput::Continue);
+        };
+        let preview = app_state
+            .restore_preview
+            .as_mut()
+            .expect("restore preview is open");
+        match preview.handle_key(event, &pets) {
+            PreviewOutcome::Open => {}
+            PreviewOutcome::Cancelled => app_state.restore_preview = None,
+            PreviewOutcome::Restore(path) => {
+                let created_at = preview.selected_backup().created_at;
+                app_state.restore_preview = None;
+                request_change(DbChange::RestoreBackup { path, created_at }, app_state);
+            }
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(review) = &mut app_state.merge_review {
+        log::debug!("key goes to the merge review");
+        match review.handle_key(event) {
+            ReviewOutcome::Open => {}
+            ReviewOutcome::Cancelled => {
+                app_state.merge_review = None;
+                app_state.status_line.info("nothing was merged");
+            }
+            ReviewOutcome::Decided => {
+                let review = app_state.merge_review.take().expect("merge review is open");
+                let (theirs, plan) = review.into_parts();
+                let resolutions = plan
+                    .conflicts
+                    .iter()
+                    .filter_map(|conflict| Some((conflict.theirs.id, conflict.resolution?)))
+                    .collect();
+                request_change(
+                    DbChange::MergeDb {
+                        theirs,
+                        resolutions,
+                    },
+                    app_state,
+                );
+            }
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(picker) = &mut app_state.view_picker {
+        log::debug!("key goes to the view picker");
+        let index = picker.selected();
+        match picker.handle_key(event, app_state.saved_views.views.len()) {
+            PickerKey::Closed => app_state.view_picker = None,
+            PickerKey::Chosen(index) => {
+                app_state.view_picker = None;
+                apply_view(app_state, index);
+            }
+            PickerKey::Other(KeyCode::Char('r')) => {
+                app_state.view_picker = None;
+                let name = app_state.saved_views.views[index].name.clone();
+                app_state.prompt = Some(Prompt::new(
+                    PromptKind::RenameView { name: name.clone() },
+                    &name,
+                ));
+            }
+            PickerKey::Other(KeyCode::Char('d')) => {
+                let view = app_state.saved_views.views.remove(index);
+                if app_state.active_view.as_ref() == Some(&view.name) {
+                    app_state.active_view = None;
+                }
+                save_views(app_state);
+                app_state
+                    .status_line
+                    .info(format!("deleted view {}", view.name));
+                match &mut app_state.view_picker {
+                    Some(picker) if !app_state.saved_views.views.is_empty() => {
+                        picker.clamp(app_state.saved_views.views.len())
+                    }
+                    _ => app_state.view_picker = None,
+                }
+            }
+            PickerKey::Handled | PickerKey::Other(_) => {}
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(picker) = &mut app_state.yank_picker {
+        log::debug!("key goes to the yank ring");
+        match picker.handle_key(event, app_state.yank_ring.entries().len()) {
+            PickerKey::Closed => app_state.yank_picker = None,
+            PickerKey::Chosen(index) => {
+                app_state.yank_picker = None;
+                let pet = Pet {
+                    created_at: Utc::now(),
+                    archived_at: None,
+                    ..app_state.yank_ring.entries()[index].pet.clone()
+                };
+                request_change(DbChange::AddPet(Box::new(pet)), app_state);
+            }
+            PickerKey::Handled | PickerKey::Other(_) => {}
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(picker) = &mut app_state.workspace_picker {
+        log::debug!("key goes to the workspace picker");
+        match picker.handle_key(event) {
+            PickerOutcome::Open => {}
+            PickerOutcome::Cancelled => app_state.workspace_picker = None,
+            PickerOutcome::Chosen(workspace) => {
+                app_state.workspace_picker = None;
+                switch_workspace(app_state, workspace);
+            }
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(pager) = &mut app_state.message_pager {
+        log::debug!("key goes to the message pager");
+        if let PagerOutcome::Closed = pager.handle_key(event) {
+            app_state.message_pager = None;
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(chooser) = &mut app_state.column_chooser {
+        log::debug!("key goes to the column chooser");
+        match chooser.handle_key(event) {
+            ChooserOutcome::Open => {}
+            ChooserOutcome::Cancelled => app_state.column_chooser = None,
+            ChooserOutcome::Chosen(columns) => {
+                app_state.column_chooser = None;
+                app_state.report(
+                    StatusLevel::Info,
+                    format!("columns: {}", fields::format_list_columns(&columns)),
+                );
+                app_state.list_columns = columns;
+            }
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(manager) = &mut app_state.category_manager {
+        log::debug!("key goes to the category manager");
+        let current = manager.current().unwrap_or_default().to_owned();
+        match manager.handle_key(event) {
+            ManagerOutcome::Open => {}
+            ManagerOutcome::Closed => app_state.category_manager = None,
+            ManagerOutcome::Rename(from) => {
+                app_state.category_manager = None;
+                app_state.prompt = Some(Prompt::new(PromptKind::CategoryName { from }, &current));
+            }
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if app_state.quick_open.is_some() {
+        log::debug!("key goes to quick open");
+        let Some(Database { pets, .. }) = read_db_or_report(app_state) else {
+            return Ok(ResponseToUserInput::Continue);
+        };
+        let candidates = quick_open_candidates(&pets);
+        let names: Vec<&str> = candidates.iter().map(|pet| pet.name.as_str()).collect();
+        let quick_open = app_state.quick_open.as_mut().expect("quick open is open");
+        match quick_open.handle_key(event, &names) {
+            QuickOpenOutcome::Open => {}
+            QuickOpenOutcome::Cancelled => app_state.quick_open = None,
+            QuickOpenOutcome::Chosen(index) => {
+                app_state.quick_open = None;
+                let pet_id = candidates[index].id;
+                record_jump(app_state);
+                jump_to_pet(app_state, &pets, pet_id);
+            }
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if let Some(palette) = &mut app_state.palette {
+        log::debug!("key goes to the command palette");
+        let pets = read_db().unwrap_or_default();
+        match palette.handle_key(event, &app_state.command_history, &pets) {
+            PaletteOutcome::Open => {}
+            PaletteOutcome::Cancelled => app_state.palette = None,
+            PaletteOutcome::Submitted(line) => {
+                app_state.palette = None;
+                match palette::parse(&line) {
+                    Ok(command) => {
+                        input::remember(&mut app_state.command_history, &line);
+                        app_state.pending_count = command.count;
+                        return record_and_apply(command.action, app_state);
+                    }
+                    Err(err) => app_state.palette = Some(Palette::reopen(line, err)),
+                }
+            }
+        }
+        return Ok(ResponseToUserInput::Continue);
+    }
+    Ok(ResponseToUserInput::Continue)
+}
+
+/// Carries out `action`, adding it to the macro being recorded. Starting,
+/// stopping and replaying macros and quitting aren't recorded themselves.
+fn record_and_apply(
+    action: Action,
+    app_state: &mut AppState,
+) -> Result<ResponseToUserInput, AppError> {
+    if !matches!(
+        action,
+        Action::ToggleRecording | Action::ReplayMacro | Action::Quit
+    ) {
+        app_state.macros.record(Step::Action(action.clone()));
+    }
+    apply_action(action, app_state)
+}
+
+/// Runs the steps of the macro in `register` until one of them shows an
+/// error, saying at which step it stopped and why.
+fn replay_macro(register: char, app_state: &mut AppState) -> Result<ResponseToUserInput, AppError> {
+    let Some((register, steps)) = app_state.macros.steps(register) else {
+        app_state
+            .status_line
+            .error(format!("no macro in @{}", register));
+        return Ok(ResponseToUserInput::Continue);
+    };
+    let mut response = Ok(ResponseToUserInput::Continue);
+    let replayed = macros::replay(&steps, |step| {
+        let errors = app_state.status_line.error_count();
+        match step {
+            Step::Action(action) => {
+                response = apply_action(action.clone(), app_state);
+                if !matches!(response, Ok(ResponseToUserInput::Continue)) {
+                    return false;
+                }
+            }
+            Step::Answer(answer) => {
+                let Some(mut prompt) = app_state.prompt.take() else {
+                    app_state.report(StatusLevel::Error, "no prompt to answer".to_owned());
+                    return false;
+                };
+                prompt.input = TextInput::new(answer);
+                submit_prompt(prompt, app_state);
+            }
+        }
+        app_state.status_line.error_count() == errors
+    });
+    match replayed {
+        Replayed::Completed => {
+            app_state
+                .status_line
+                .info(format!("replayed @{} ({} steps)", register, steps.len()))
+        }
+        Replayed::StoppedAt(index) => {
+            let reason = app_state
+                .status_line
+                .message()
+                .map(|(text, _)| text.to_owned())
+                .unwrap_or_default();
+            app_state.report(
+                StatusLevel::Error,
+                format!(
+                    "@{} stopped at step {} of {}: {}",
+                    register,
+                    index + 1,
+                    steps.len(),
+                    reason
+                ),
+            );
+        }
+    }
+    response
+}
+
+/// Stops, unless a dry run made changes, which are then shown first with the
+/// choice to save them.
+fn quit(app_state: &mut AppState) -> ResponseToUserInput {
+    let Some(dry_run) = &app_state.dry_run else {
+        return ResponseToUserInput::Stop;
+    };
+    let revision = read_db_content().map_or(0, |content| db_format::revision(&content));
+    if dry_run.unsaved(revision) == 0 {
+        return ResponseToUserInput::Stop;
+    }
+    match dry_run.summary(Path::new(&db_path())) {
+        Ok(summary) => app_state.dry_run_summary = Some(summary),
+        Err(err) => app_state.report(
+            StatusLevel::Error,
+            format!("could not compare the dry run: {}", err),
+        ),
+    }
+    ResponseToUserInput::Continue
+}
+
+/// Saves the dry run to the DB and stops, unless the DB changed since the dry
+/// run started: then what both sides changed is shown, to keep either or
+/// merge them.
+fn save_dry_run(app_state: &mut AppState) -> ResponseToUserInput {
+    let dry_run = app_state.dry_run.as_ref().expect("only dry runs are saved");
+    let db = workspace_db();
+    let changed = dry_run.changed(Path::new(&db)).and_then(|changed| {
+        let Some((on_disk, theirs)) = changed else {
+            return Ok(None);
+        };
+        let base = dry_run.original();
+        let mine = read_database().map_err(|err| err.to_string());
+        let (base, mine) = base
+            .and_then(|base| Ok((base, mine?)))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let plan = three_way::plan(
+            (&base.pets, &base.owners),
+            (&mine.pets, &mine.owners),
+            (&theirs.pets, &theirs.owners),
+        );
+        Ok(Some(Reconcile::new(plan, on_disk, theirs)))
+    });
+    match changed {
+        Ok(Some(reconcile)) => {
+            log::info!(
+                "{} changed during the dry run: {}",
+                db,
+                reconcile.plan().summary()
+            );
+            app_state.reconcile = Some(reconcile);
+            return ResponseToUserInput::Continue;
+        }
+        Ok(None) => {}
+        Err(err) => {
+            app_state.report(
+                StatusLevel::Error,
+                format!("could not compare the dry run with {}: {}", db, err),
+            );
+            return ResponseToUserInput::Continue;
+        }
+    }
+    let saved = dry_run.save(
+        Path::new(&db_path()),
+        Path::new(&db),
+        app_state.write_options.keep_backups,
+        app_state.write_options.durability,
+    );
+    match saved {
+        Ok(()) => {
+            log::info!("saved the dry run to {}", db);
+            ResponseToUserInput::Stop
+        }
+        Err(err) => {
+            app_state.report(
+                StatusLevel::Error,
+                format!("could not save the dry run: {}", err),
+            );
+            ResponseToUserInput::Continue
+        }
+    }
+}
+
+/// Saves the dry run over the DB as it changed meanwhile and stops: merged
+/// with their changes, the conflicts decided, with `merge`, or else as it is.
+/// Either is written at a revision past theirs, so whoever made their changes
+/// notices it.
+fn save_dry_run_over(
+    app_state: &mut AppState,
+    reconcile: Reconcile,
+    merge: bool,
+) -> ResponseToUserInput {
+    let (plan, on_disk, theirs) = reconcile.into_parts();
+    let options = app_state.write_options;
+    let merged = read_database()
+        .map_err(|err| err.to_string())
+        .and_then(|mine| {
+            let mut next_id = mine.next_id.later(theirs.next_id);
+            let (pets, owners) = if merge {
+                let known: Vec<Pet> = mine.pets.iter().chain(&theirs.pets).cloned().collect();
+                let ids = next_id.take(&known, three_way::renumbered(&plan));
+                three_way::apply(&plan, ids)
+                    .map_err(|id| format!("the conflict over pet {} is undecided", id))?
+            } else {
+                (mine.pets, mine.owners)
+            };
+            let database = Database {
+                revision: mine.revision.max(theirs.revision) + 1,
+                schema_version: migrations::CURRENT_VERSION,
+                next_id,
+                pets,
+                owners,
+            };
+            let content =
+                db_format::serialize(&database, options.format).map_err(|err| err.to_string())?;
+            let on_disk_codec = (!on_disk.is_empty()).then(|| compression::detect(&on_disk));
+            let codec =
+                compression::for_write(Path::new(&workspace_db()), options.compress, on_disk_codec);
+            compression::encode(&content, codec, options.compress_level)
+                .map_err(|err| err.to_string())
+        });
+    let dry_run = app_state.dry_run.as_mut().expect("only dry runs are saved");
+    dry_run.rebase(on_disk);
+    let saved = merged.and_then(|content| {
+        dry_run.save_content(
+            &content,
+            Path::new(&workspace_db()),
+            options.keep_backups,
+            options.durability,
+        )
+    });
+    match saved {
+        Ok(()) => {
+            log::info!("saved the dry run over the changes to {}", workspace_db());
+            ResponseToUserInput::Stop
+        }
+        Err(err) => {
+            app_state.report(
+                StatusLevel::Error,
+                format!("could not save the dry run: {}", err),
+            );
+            ResponseToUserInput::Continue
+        }
+    }
+}
+
+/// Carries out `action` on the current tab. Quitting only stops the main loop;
+/// `main` puts the terminal back.
+fn apply_action(action: Action, app_state: &mut AppState) -> Result<ResponseToUserInput, AppError> {
+    if app_state.kiosk.is_some() && !kiosk::allows(&action) {
+        app_state
+            .status_line
+            .error("only browsing works on this terminal");
+        return Ok(ResponseToUserInput::Continue);
+    }
+    if action == Action::Quit {
+        return Ok(quit(app_state));
+    }
+    if app_state.write_options.read_only && action.changes_db() {
+        app_state
+            .status_line
+            .error("read-only: the DB can't be changed");
+        return Ok(ResponseToUserInput::Continue);
+    }
+    let Some(Database { pets, owners, .. }) = read_db_or_report(app_state) else {
+        return Ok(ResponseToUserInput::Continue);
+    };
+    let visible = app_state.visible_pets(&pets);
+    follow_pet_selection(app_state, &visible);
+    let selected = app_state.pet_list_state.selected();
+    let count = app_state.pending_count.take();
+    let observed = app_state.tutorial.is_some().then(|| action.clone());
+    log::debug!(
+        "{:?} on the {:?} tab, {} of {} pets visible, selected {:?}, count {:?}",
+        action,
+        app_state.active_menu_item,
+        visible.len(),
+        pets.len(),
+        selected,
+        count
+    );
+    match action {
+        Action::Quit => unreachable!("handled above"),
+        Action::OpenQuickOpen => app_state.quick_open = Some(QuickOpen::default()),
+        Action::OpenPalette => app_state.palette = Some(Palette::default()),
+        Action::JumpBack => {
+            let current = current_jump(app_state);
+            let valid = |jump: &Jump| jump_valid(jump, &pets);
+            match app_state.jumps.back(current, valid) {
+                Some(jump) => restore_jump(app_state, &pets, jump),
+                None => app_state.status_line.info("no older jump".to_owned()),
+            }
+        }
+        Action::JumpForward => {
+            let valid = |jump: &Jump| jump_valid(jump, &pets);
+            match app_state.jumps.forward(valid) {
+                Some(jump) => restore_jump(app_state, &pets, jump),
+                None => app_state.status_line.info("no newer jump".to_owned()),
+            }
+        }
+        Action::ShowIntegrity => {
+            check_integrity(app_state);
+            app_state.integrity_open = true;
+        }
+        Action::Open => match app_state.active_menu_item {
+            MenuItem::Home => {
+                let selected = app_state.reminder_list_state.selected().unwrap_or(0);
+                if let Some(reminder) = home_reminders(&pets).get(selected) {
+                    record_jump(app_state);
+                    jump_to_pet(app_state, &pets, reminder.pet_id);
+                }
+            }
+            MenuItem::Stats => {
+                let selected = app_state.stats_table_state.selected().unwrap_or(0);
+                let rows = stats::category_rows(&pets, app_state.stats_sort);
+                if let Some(row) = rows.into_iter().nth(selected) {
+                    record_jump(app_state);
+                    show_category(app_state, row.category);
+                }
+            }
+            MenuItem::Pets if app_state.selected_group.is_some() => toggle_group(app_state, &pets),
+            _ => {}
+        },
+        Action::ToggleGroup => toggle_group(app_state, &pets),
+        Action::ToggleGrouping => {
+            app_state.grouped = !app_state.grouped;
+            app_state.selected_group = None;
+            let visible = app_state.visible_pets(&pets);
+            follow_pet_selection(app_state, &visible);
+            let text = if app_state.grouped {
+                "grouped by category"
+            } else {
+                "not grouped"
+            };
+            app_state.status_line.info(text.to_owned());
+        }
+        Action::CycleStatsSort => {
+            app_state.stats_sort = app_state.stats_sort.next();
+            app_state.stats_table_state.select(Some(0));
+        }
+        Action::CycleAgeBuckets => {
+            app_state.age_buckets = app_state.age_buckets.next();
+            app_state
+                .status_line
+                .info(format!("age histogram: {} per bar", app_state.age_buckets));
+        }
+        Action::WidenGrowth => {
+            app_state.growth_months =
+                (app_state.growth_months + stats::GROWTH_STEP).min(stats::MAX_GROWTH_MONTHS);
+        }
+        Action::NarrowGrowth => {
+            app_state.growth_months = app_state
+                .growth_months
+                .saturating_sub(stats::GROWTH_STEP)
+                .max(stats::GROWTH_STEP);
+        }
+        Action::ToggleGrowthTotal => app_state.growth_total = !app_state.growth_total,
+        Action::ToggleRecording => match app_state.macros.stop() {
+            Some((register, length)) => app_state
+                .status_line
+                .info(format!("recorded {} steps into @{}", length, register)),
+            None => {
+                app_state.macros.wait_for_register(Pending::Record);
+                app_state
+                    .status_line
+                    .info("record into which register (a-z)?");
+            }
+        },
+        Action::ReplayMacro => {
+            app_state.macros.wait_for_register(Pending::Replay);
+        }
+        Action::ShowTab(menu_item) if !app_state.tabs.contains(&menu_item) => {
+            let name = strings::text(app_state.lang, menu::title(menu_item));
+            app_state
+                .status_line
+                .info(format!("the {} tab is turned off in the config", name));
+        }
+        Action::ShowTab(menu_item) => app_state.active_menu_item = menu_item,
+        Action::ShowNthTab(index) => match app_state.tabs.get(index) {
+            Some(tab) => app_state.active_menu_item = *tab,
+            None => app_state
+                .status_line
+                .info(format!("there are only {} tabs", app_state.tabs.len())),
+        },
+        Action::NextTab => {
+            app_state.active_menu_item = app_state.active_menu_item.next(&app_state.tabs)
+        }
+        Action::PreviousTab => {
+            app_state.active_menu_item = app_state.active_menu_item.prev(&app_state.tabs)
+        }
+        Action::CountDigit(digit) => {
+            let count = count.unwrap_or(0) * 10 + digit;
+            app_state.pending_count = Some(count.min(MAX_COUNT));
+        }
+        Action::MergeDuplicates => {
+            let rows = duplicate_rows(&pets);
+            if let Some((group, survivor)) = app_state
+                .tab_views
+                .list(MenuItem::Duplicates)
+                .selected()
+                .and_then(|index| rows.get(index))
+            {
+                let other_ids = rows
+                    .iter()
+                    .filter(|(other_group, pet)| other_group == group && pet.id != survivor.id)
+                    .map(|(_, pet)| pet.id)
+                    .collect();
+                app_state.pending_confirmation = Some(Confirmation::Merge {
+                    survivor_id: survivor.id,
+                    name: survivor.name.clone(),
+                    other_ids,
+                });
+            }
+        }
+        Action::RestoreFromTrash => {
+            let trash = archived_pets(&pets);
+            if let Some(pet) = app_state
+                .tab_views
+                .list(MenuItem::Trash)
+                .selected()
+                .and_then(|index| trash.get(index))
+            {
+                request_change(DbChange::Restore(pet.id), app_state);
+            }
+        }
+        Action::PurgeFromTrash => {
+            let trash = archived_pets(&pets);
+            if let Some(pet) = app_state
+                .tab_views
+                .list(MenuItem::Trash)
+                .selected()
+                .and_then(|index| trash.get(index))
+            {
+                app_state.pending_confirmation = Some(Confirmation::Purge {
+                    pet_id: pet.id,
+                    name: pet.name.clone(),
+                });
+            }
+        }
+        Action::AddOwner => {
+            app_state.prompt = Some(Prompt::new(PromptKind::NewOwner, ""));
+        }
+        Action::ResetDb => app_state.prompt = Some(Prompt::new(PromptKind::ResetDb, "")),
+        Action::QuickAdd => {
+            app_state.prompt = Some(Prompt::new(PromptKind::QuickAdd, ""));
+            let defaults = quick_add::describe(&app_state.add_defaults);
+            app_state.status_line.info(defaults);
+        }
+        Action::DeleteOwner => {
+            let selected_owner = app_state
+                .tab_views
+                .list(MenuItem::Owners)
+                .selected()
+                .and_then(|index| owners.get(index));
+            if let Some(owner) = selected_owner {
+                let pet_count = pet_count_of(&pets, owner.id);
+                if pet_count > 0 {
+                    app_state.pending_confirmation = Some(Confirmation::DeleteOwner {
+                        owner_id: owner.id,
+                        name: owner.name.clone(),
+                        pet_count,
+                    });
+                } else {
+                    request_change(DbChange::DeleteOwner(owner.id), app_state);
+                }
+            }
+        }
+        Action::AddPets => request_change(
+            DbChange::Add {
+                count: count.unwrap_or(1),
+                names: app_state.names.clone(),
+            },
+            app_state,
+        ),
+        Action::AssignOwner => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                let current = pet
+                    .owner_id
+                    .and_then(|id| owners.iter().find(|owner| owner.id == id))
+                    .map(|owner| owner.name.clone())
+                    .unwrap_or_default();
+                app_state.prompt =
+                    Some(Prompt::new(PromptKind::Owner { pet_id: pet.id }, &current));
+            }
+        }
+        Action::Delete => {
+            let marked = app_state.marks.effective(&visible, selected.unwrap_or(0));
+            if let (Some(count), Some(selected), true) = (count, selected, marked.is_empty()) {
+                // `3dd` takes the selected pet and the two below it.
+                let range = &visible[selected.min(visible.len())..];
+                let range = &range[..count.min(range.len())];
+                if let (Some(first), Some(last)) = (range.first(), range.last()) {
+                    let (first, last) = (first.name.clone(), last.name.clone());
+                    delete_pets(
+                        range,
+                        |ids| {
+                            (ids.len() > 1).then(|| Confirmation::ArchiveRange { ids, first, last })
+                        },
+                        app_state,
+                    );
+                }
+                return Ok(ResponseToUserInput::Continue);
+            }
+            if !marked.is_empty() {
+                let marked: Vec<&Pet> =
+                    pets.iter().filter(|pet| marked.contains(&pet.id)).collect();
+                delete_pets(
+                    &marked,
+                    |ids| Some(Confirmation::ArchiveMarked(ids)),
+                    app_state,
+                );
+                return Ok(ResponseToUserInput::Continue);
+            }
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                delete_pets(&[pet], |_| None, app_state);
+            }
+        }
+        Action::DeletePet(pet_id) => match pets.iter().find(|pet| pet.id == pet_id) {
+            Some(pet) => delete_pets(&[pet], |_| None, app_state),
+            None => app_state.status_line.error(format!("no pet #{}", pet_id)),
+        },
+        Action::Yank(format) => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                app_state.yank_ring.push(pet, Utc::now());
+                let text = clipboard::yank_text(pet, format, Local::now().date_naive());
+                match app_state.clipboard.copy(text) {
+                    Ok(()) => app_state
+                        .status_line
+                        .info(format!("copied {} to the clipboard", pet.name)),
+                    // The ring has it all the same.
+                    Err(err) => app_state.status_line.info(format!(
+                        "yanked {}, {} lists the yanked pets (not copied: {})",
+                        pet.name,
+                        keys::YANK_RING,
+                        err
+                    )),
+                }
+            }
+        }
+        Action::ShowYankRing => {
+            if app_state.yank_ring.entries().is_empty() {
+                app_state.report(
+                    StatusLevel::Error,
+                    format!(
+                        "nothing yanked yet, {} or {} yanks the selected pet",
+                        keys::YANK,
+                        keys::CUT
+                    ),
+                );
+            } else {
+                app_state.yank_picker = Some(Picker::default());
+            }
+        }
+        Action::CopyPet => match app_state.selected_pet(&visible) {
+            Some(pet) => {
+                app_state.prompt = Some(Prompt::new(
+                    PromptKind::Copy { pet_id: pet.id },
+                    &format!("{} (copy)", pet.name),
+                ))
+            }
+            None => app_state.report(StatusLevel::Error, "no pet selected to copy".to_owned()),
+        },
+        Action::EditNotes => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                app_state.notes_editor = Some(NotesEditor::new(pet.id, pet.notes.as_deref()));
+            }
+        }
+        Action::EditBirthdate => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                let current = pet
+                    .birthdate
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+                app_state.prompt = Some(Prompt::new(
+                    PromptKind::Birthdate { pet_id: pet.id },
+                    &current,
+                ));
+            }
+        }
+        Action::EditAge => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                let current = age::format_input(pet.age.months);
+                app_state.prompt = Some(Prompt::new(PromptKind::Age { pet_id: pet.id }, &current));
+            }
+        }
+        Action::ScrollNotesDown => {
+            app_state.notes_scroll = app_state.notes_scroll.saturating_add(1)
+        }
+        Action::ScrollNotesUp => app_state.notes_scroll = app_state.notes_scroll.saturating_sub(1),
+        Action::ScrollRecordsDown => {
+            let record_count = app_state
+                .selected_pet(&visible)
+                .map_or(0, |pet| pet.records.len());
+            app_state.records_scroll =
+                (app_state.records_scroll + 1).min(record_count.saturating_sub(1));
+        }
+        Action::ScrollDetailDown => {
+            let field_count = app_state.selected_pet(&visible).map_or(0, |pet| {
+                pet_fields(pet, &owners, Utc::now(), app_state.lang).len()
+            });
+            app_state.detail_scroll =
+                (app_state.detail_scroll + 1).min(field_count.saturating_sub(1));
+        }
+        Action::ToggleDrawRate => app_state.show_draw_rate = !app_state.show_draw_rate,
+        Action::ToggleDiagnostics => app_state.show_diagnostics = !app_state.show_diagnostics,
+        Action::WidenList => {
+            app_state.list_width = resize_split(app_state.list_width, LIST_WIDTH_STEP)
+        }
+        Action::NarrowList => {
+            app_state.list_width = resize_split(app_state.list_width, -LIST_WIDTH_STEP)
+        }
+        Action::ChooseColumns => {
+            app_state.column_chooser = Some(ColumnChooser::new(&app_state.list_columns))
+        }
+        Action::ManageCategories => {
+            let pets = read_db().unwrap_or_default();
+            if pets.is_empty() {
+                app_state.report(StatusLevel::Error, "no pets, so no categories".to_owned());
+            } else {
+                app_state.category_manager = Some(CategoryManager::new(&pets));
+            }
+        }
+        Action::ShowMessages => app_state.message_pager = Some(MessagePager::default()),
+        Action::PickWorkspace => {
+            if app_state.workspaces.is_empty() {
+                app_state.report(
+                    StatusLevel::Error,
+                    "no workspaces, see `workspaces` in the config file".to_owned(),
+                );
+            } else {
+                app_state.workspace_picker = Some(WorkspacePicker::new(workspace_entries(
+                    &app_state.workspaces,
+                )));
+            }
+        }
+        Action::PickView => {
+            if app_state.saved_views.views.is_empty() {
+                app_state.report(
+                    StatusLevel::Error,
+                    "no saved views, save the Pets tab's with :save-view NAME".to_owned(),
+                );
+            } else {
+                app_state.view_picker = Some(Picker::default());
+            }
+        }
+        Action::SaveView(name) => {
+            if let Err(err) = saved_views::check_name(&name) {
+                app_state.report(StatusLevel::Error, err);
+                return Ok(ResponseToUserInput::Continue);
+            }
+            let view = SavedView::capture(&name, &app_state.view);
+            let message = match app_state
+                .saved_views
+                .views
+                .iter_mut()
+                .find(|saved| saved.name == name)
+            {
+                Some(saved) => {
+                    *saved = view;
+                    format!("updated view {}", name)
+                }
+                None => {
+                    app_state.saved_views.views.push(view);
+                    format!("saved view {}, V lists the views", name)
+                }
+            };
+            app_state.active_view = Some(name);
+            save_views(app_state);
+            app_state.status_line.info(message);
+        }
+        Action::BatchEdit => match batch_targets(app_state, &visible) {
+            Ok(ids) => {
+                let mut ids: Vec<usize> = ids.into_iter().collect();
+                ids.sort_unstable();
+                app_state.prompt = Some(Prompt::new(PromptKind::BatchEdit { ids }, ""));
+            }
+            Err(err) => app_state.report(StatusLevel::Error, err),
+        },
+        Action::BatchSet(assignment) => match batch_targets(app_state, &visible) {
+            Ok(ids) => {
+                app_state.pending_confirmation = Some(Confirmation::BatchEdit { ids, assignment })
+            }
+            Err(err) => app_state.report(StatusLevel::Error, err),
+        },
+        Action::CutPet => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                app_state.cut_pet = Some(pet.id);
+                app_state.yank_ring.push(pet, Utc::now());
+                app_state.status_line.info(format!(
+                    "cut {}; {} pastes it after the selected pet, {} before",
+                    pet.name,
+                    keys::PASTE_AFTER,
+                    keys::PASTE_BEFORE
+                ));
+            }
+        }
+        Action::PastePet(side) => {
+            let Some(pet_id) = app_state.cut_pet else {
+                app_state
+                    .status_line
+                    .error(format!("nothing is cut, {} cuts a pet", keys::CUT));
+                return Ok(ResponseToUserInput::Continue);
+            };
+            if app_state.view.sort.is_some() {
+                app_state
+                    .status_line
+                    .error("the list is sorted, pets can only be moved in the DB's own order");
+                return Ok(ResponseToUserInput::Continue);
+            }
+            app_state.cut_pet = None;
+            let anchor_id = app_state.selected_pet(&visible).map(|pet| pet.id);
+            request_change(
+                DbChange::Move {
+                    pet_id,
+                    anchor_id,
+                    side,
+                },
+                app_state,
+            );
+        }
+        Action::ScrollDetailUp => {
+            app_state.detail_scroll = app_state.detail_scroll.saturating_sub(1)
+        }
+        Action::ScrollRecordsUp => {
+            app_state.records_scroll = app_state.records_scroll.saturating_sub(1)
+        }
+        Action::AddRecord => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+                app_state.prompt = Some(Prompt::new(
+                    PromptKind::Record { pet_id: pet.id },
+                    &format!("{}, ", today),
+                ));
+            }
+        }
+        Action::EditBreed => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                app_state.prompt = Some(Prompt::new(
+                    PromptKind::Breed { pet_id: pet.id },
+                    pet.breed.as_deref().unwrap_or_default(),
+                ));
+            }
+        }
+        Action::EditMicrochip => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                app_state.prompt = Some(Prompt::new(
+                    PromptKind::Microchip { pet_id: pet.id },
+                    pet.microchip.as_deref().unwrap_or_default(),
+                ));
+            }
+        }
+        Action::Rename => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                app_state.prompt = Some(Prompt::new(
+                    PromptKind::Rename { pet_id: pet.id },
+                    &pet.name,
+                ));
+            }
+        }
+        Action::EditAttribute => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                app_state.prompt = Some(Prompt::new(PromptKind::Attribute { pet_id: pet.id }, ""));
+            }
+        }
+        Action::EditPhoto => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                let path = pet.photo_path.as_deref().unwrap_or_else(|| Path::new(""));
+                app_state.prompt = Some(Prompt::new(
+                    PromptKind::Photo { pet_id: pet.id },
+                    &path.to_string_lossy(),
+                ));
+            }
+        }
+        Action::ExportPet => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                export_pet(app_state, pet);
+            }
+        }
+        Action::PreviewRestore => preview_restore(app_state, &pets),
+        Action::ImportPets => {
+            app_state.prompt = Some(Prompt::new(
+                PromptKind::Import,
+                &format!("{}/", share::EXPORT_DIR),
+            ));
+        }
+        Action::MergeDb => app_state.prompt = Some(Prompt::new(PromptKind::MergeDb, "")),
+        Action::AddWeight => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                app_state.prompt = Some(Prompt::new(PromptKind::Weight { pet_id: pet.id }, ""));
+            }
+        }
+        Action::CycleStatus => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                request_change(DbChange::AdvanceStatus(pet.id), app_state);
+            }
+        }
+        Action::CycleCategory => {
+            app_state.view.category.cycle(&pets);
+            app_state.marks.clear();
+        }
+        Action::CycleStatusFilter => {
+            app_state.view.status = pet_status::cycle_filter(app_state.view.status);
+            app_state.marks.clear();
+        }
+        Action::CycleSort => app_state.view.sort = Sort::cycle(app_state.view.sort),
+        Action::ReverseSort => {
+            let sort = app_state.view.sort.unwrap_or(Sort {
+                key: view::SortKey::Id,
+                descending: false,
+            });
+            app_state.view.sort = Some(Sort {
+                descending: !sort.descending,
+                ..sort
+            });
+        }
+        Action::Search => {
+            app_state.prompt = Some(Prompt::new(
+                PromptKind::Search,
+                app_state.view.query.as_ref().map_or("", Query::text),
+            ));
+        }
+        Action::Export => export_view(app_state, &pets),
+        Action::SetSort(sort) => app_state.view.sort = sort,
+        Action::SetCategory(category) => {
+            app_state.view.category = CategoryFilter::only(category);
+            app_state.marks.clear();
+        }
+        Action::SetQuery(query) => app_state.view.query = query,
+        Action::ExportTo(format, path) => {
+            let rendered = format.render(&visible, Utc::now(), app_state.export_extra_fields);
+            // Like the DB, an export to a `.gz` file is compressed.
+            let rendered = compression::encode(
+                rendered.as_bytes(),
+                compression::by_name(&path),
+                app_state.write_options.compress_level,
+            );
+            match rendered.and_then(|rendered| fs::write(&path, rendered)) {
+                Ok(()) => app_state.report(
+                    StatusLevel::Info,
+                    format!("exported {} pets to {}", visible.len(), path.display()),
+                ),
+                Err(err) => app_state.report(
+                    StatusLevel::Error,
+                    format!("could not export to {}: {}", path.display(), err),
+                ),
+            }
+        }
+        Action::SetTheme(theme) => app_state.theme = theme,
+        Action::ToggleMark => {
+            if let Some(pet) = app_state.selected_pet(&visible) {
+                app_state.marks.toggle(pet.id);
+            }
+        }
+        Action::ToggleVisual => {
+            if let Some(selected) = selected {
+                app_state.marks.toggle_visual(&visible, selected);
+            }
+        }
+        Action::ClearMarks => app_state.marks.clear(),
+        Action::First | Action::Last => {
+            let last = action == Action::Last;
+            match app_state.active_menu_item {
+                MenuItem::Log => select_end(
+                    app_state.tab_views.list(MenuItem::Log),
+                    app_state.activity_log.len(),
+                    last,
+                ),
+                MenuItem::Duplicates => select_end(
+                    app_state.tab_views.list(MenuItem::Duplicates),
+                    duplicate_rows(&pets).len(),
+                    last,
+                ),
+                MenuItem::Trash => select_end(
+                    app_state.tab_views.list(MenuItem::Trash),
+                    archived_pets(&pets).len(),
+                    last,
+                ),
+                MenuItem::Owners => select_end(
+                    app_state.tab_views.list(MenuItem::Owners),
+                    owners.len(),
+                    last,
+                ),
+                MenuItem::Home => select_end(
+                    &mut app_state.reminder_list_state,
+                    home_reminders(&pets).len(),
+                    last,
+                ),
+                MenuItem::Stats => select_end(
+                    &mut app_state.stats_table_state,
+                    stats::category_rows(&pets, app_state.stats_sort).len(),
+                    last,
+                ),
+                MenuItem::Pets => {
+                    app_state.notes_scroll = 0;
+                    app_state.records_scroll = 0;
+                    app_state.detail_scroll = 0;
+                    if app_state.grouped {
+                        let rows = app_state.pet_rows(&visible);
+                        select_row(app_state, &visible, &rows, grouping::end(&rows, last));
+                    } else {
+                        select_end(&mut app_state.pet_list_state, visible.len(), last);
+                        remember_pet_selection(app_state, &visible);
+                    }
+                }
+            }
+        }
+        Action::Next | Action::Previous => {
+            let forward = action == Action::Next;
+            let steps = count.unwrap_or(1);
+            match app_state.active_menu_item {
+                MenuItem::Log => step_selection(
+                    app_state.tab_views.list(MenuItem::Log),
+                    app_state.activity_log.len(),
+                    forward,
+                    steps,
+                ),
+                MenuItem::Duplicates => step_selection(
+                    app_state.tab_views.list(MenuItem::Duplicates),
+                    duplicate_rows(&pets).len(),
+                    forward,
+                    steps,
+                ),
+                MenuItem::Trash => step_selection(
+                    app_state.tab_views.list(MenuItem::Trash),
+                    archived_pets(&pets).len(),
+                    forward,
+                    steps,
+                ),
+                MenuItem::Owners => step_selection(
+                    app_state.tab_views.list(MenuItem::Owners),
+                    owners.len(),
+                    forward,
+                    steps,
+                ),
+                MenuItem::Home => step_selection(
+                    &mut app_state.reminder_list_state,
+                    home_reminders(&pets).len(),
+                    forward,
+                    steps,
+                ),
+                MenuItem::Stats => step_selection(
+                    &mut app_state.stats_table_state,
+                    stats::category_rows(&pets, app_state.stats_sort).len(),
+                    forward,
+                    steps,
+                ),
+                MenuItem::Pets if app_state.grouped => {
+                    app_state.notes_scroll = 0;
+                    app_state.records_scroll = 0;
+                    app_state.detail_scroll = 0;
+                    let rows = app_state.pet_rows(&visible);
+                    let from = app_state.selected_row(&visible, &rows);
+                    // `10j` stops at the last row rather than wrapping.
+                    let row = grouping::step(&rows, from, forward, steps, count.is_none());
+                    select_row(app_state, &visible, &rows, row);
+                }
+                MenuItem::Pets => {
+                    app_state.notes_scroll = 0;
+                    app_state.records_scroll = 0;
+                    app_state.detail_scroll = 0;
+                    match (selected, count) {
+                        // `10j` stops at the last pet rather than wrapping.
+                        (_, Some(_)) => step_selection(
+                            &mut app_state.pet_list_state,
+                            visible.len(),
+                            forward,
+                            steps,
+                        ),
+                        (Some(selected), None) => app_state
+                            .pet_list_state
+                            .select(Some(wrap_selection(selected, visible.len(), forward))),
+                        (None, None) => {}
+                    }
+                    remember_pet_selection(app_state, &visible);
+                }
+            }
+        }
+    }
+    // Only what took effect counts for the tutorial: a tab turned off in
+    // the config isn't shown, and an empty list has nothing to move through.
+    if let Some(action) = observed {
+        let tab = app_state.active_menu_item;
+        let took_effect = match action {
+            Action::ShowTab(shown) => tab == shown,
+            Action::Next | Action::Previous | Action::First | Action::Last => {
+                list_len(app_state, tab, &pets, &owners, &visible) > 0
+            }
+            _ => true,
+        };
+        if let Some(tutorial) = app_state.tutorial.as_mut().filter(|_| took_effect) {
+            tutorial.observe(&action, count);
+        }
+    }
+    Ok(ResponseToUserInput::Continue)
+}
+
+/// How many rows the list of `tab` has.
+fn list_len(
+    app_state: &AppState,
+    tab: MenuItem,
+    pets: &[Pet],
+    owners: &[Owner],
+    visible: &[&Pet],
+) -> usize {
+    match tab {
+        MenuItem::Home => home_reminders(pets).len(),
+        MenuItem::Pets => visible.len(),
+        MenuItem::Owners => owners.len(),
+        MenuItem::Duplicates => duplicate_rows(pets).len(),
+        MenuItem::Trash => archived_pets(pets).len(),
+        MenuItem::Log => app_state.activity_log.len(),
+        MenuItem::Stats => stats::category_rows(pets, app_state.stats_sort).len(),
+    }
+}
+
+/// The reminders the Home tab lists, as of today.
+fn home_reminders(pets: &[Pet]) -> Vec<reminders::Reminder> {
+    reminders::compute_reminders(pets, Local::now().date_naive())
+}
+
+/// The pets quick open can jump to: everything that isn't in the Trash.
+fn quick_open_candidates(pets: &[Pet]) -> Vec<&Pet> {
+    pets.iter().filter(|pet| !pet.is_archived()).collect()
+}
+
+/// Writes the pets of the Pets tab, as shown, to `EXPORT_PATH` as a Markdown
+/// report, and tells the `pet-cli report` command that gives the same.
+fn export_view(app_state: &mut AppState, pets: &[Pet]) {
+    let visible = app_state.visible_pets(pets);
+    let rendered = report::render(report::ReportFormat::Markdown, &visible, Utc::now());
+    match fs::write(EXPORT_PATH, rendered) {
+        Ok(()) => {
+            let mut command = format!("pet-cli report --out {}", EXPORT_PATH);
+            let args = app_state.view.to_args();
+            if !args.is_empty() {
+                command.push(' ');
+                command.push_str(&args);
+            }
+            app_state.report(
+                StatusLevel::Info,
+                format!(
+                    "exported {} pets to {} (same as `{}`)",
+                    visible.len(),
+                    EXPORT_PATH,
+                    command
+                ),
+            )
+        }
+        Err(err) => app_state.report(
+            StatusLevel::Error,
+            format!("could not export to {}: {}", EXPORT_PATH, err),
+        ),
+    }
+}
+
+/// Writes `pet` to its file under `exports/` to be imported elsewhere.
+fn export_pet(app_state: &mut AppState, pet: &Pet) {
+    let path = share::export_path(pet);
+    let written =
+        fs::create_dir_all(share::EXPORT_DIR).and_then(|()| fs::write(&path, share::snippet(pet)));
+    match written {
+        Ok(()) => app_state.report(
+            StatusLevel::Info,
+            format!("exported {} to {}", pet.name, path.display()),
+        ),
+        Err(err) => app_state.report(
+            StatusLevel::Error,
+            format!("could not export to {}: {}", path.display(), err),
+        ),
+    }
+}
+
+/// `imported Bella (#12)`, or the number of pets for more than one.
+fn describe_import(imported: &[Pet]) -> String {
+    match imported {
+        [pet] => format!("imported {} (#{})", pet.name, pet.id),
+        pets => format!("imported {} pets", pets.len()),
+    }
+}
+
+/// Asks on the terminal whether to resume the draft at `path` if it belongs
+/// to the DB at `db_path`. A draft that is turned down is deleted.
+fn offer_draft(path: &Path, db_path: &str) -> Option<draft::Draft> {
+    let draft = draft::resumable(draft::load(path), db_path)?;
+    print!("Resume the {}? (y/n) ", draft.describe());
+    let mut answer = String::new();
+    let answered =
+        io::Write::flush(&mut io::stdout()).and_then(|()| io::stdin().read_line(&mut answer));
+    if answered.is_ok() && answer.trim() == "y" {
+        return Some(draft);
+    }
+    if let Err(err) = draft::delete(path) {
+        eprintln!("could not delete the draft {}: {}", path.display(), err);
+    }
+    None
+}
+
+/// How the UI is drawn: `None` in plain mode, which reads and prints lines
+/// and so needs no terminal.
+fn check_terminal(cli: &cli::Cli, startup: &mut startup::Report) -> Option<tty::Mode> {
+    if cli.plain {
+        return None;
+    }
+    let mode = tty::mode(io::stdin().is_terminal(), io::stdout().is_terminal());
+    if mode == tty::Mode::NoTerminal {
+        startup.fail(
+            Check::Terminal,
+            "interactive mode requires a terminal, and stdin is not one",
+            "run pet-cli in a terminal, or use --plain or a command like `pet-cli list`",
+        );
+    }
+    Some(mode)
+}
+
+/// Checks that the DB file can be read, and starts an empty one, with its
+/// directory, where there is none yet.
+fn check_db_file(options: WriteOptions, startup: &mut startup::Report) {
+    let path = PathBuf::from(workspace_db());
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    match fs::read(&path) {
+        Ok(content) if db_format::is_encrypted(&content) => startup.fail(
+            Check::DbFile,
+            format!(
+                "{} is encrypted, which this pet-cli can't read",
+                path.display()
+            ),
+            "`pet-cli restore` a backup that isn't, or choose another DB with --workspace",
+        ),
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound && options.read_only => startup.warn(
+            Check::DbFile,
+            format!(
+                "there is no DB at {} and read-only mode doesn't start one",
+                path.display()
+            ),
+        ),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            let created = fs::create_dir_all(dir)
+                .map_err(Error::from)
+                .and_then(|()| write_db(&mut Database::default(), options));
+            match created {
+                Ok(()) => startup.warn(
+                    Check::DbFile,
+                    format!("there was no DB at {}, started an empty one", path.display()),
+                ),
+                Err(err) => startup.fail(
+                    Check::DbFile,
+                    format!("there is no DB at {} and starting one failed: {}", path.display(), err),
+                    format!(
+                        "make sure {} can be created and written to, or choose another DB with --workspace",
+                        dir.display()
+                    ),
+                ),
+            }
+        }
+        Err(err) => startup.fail(
+            Check::DbFile,
+            format!("could not read {}: {}", path.display(), err),
+            format!("make {} readable", path.display()),
+        ),
+    }
+}
+
+/// Checks that the DB's schema version can be brought up to date, naming
+/// the migration that fails. A DB that can't be parsed isn't a failure
+/// here: the UI offers the backups for it.
+fn check_db_schema(options: WriteOptions, startup: &mut startup::Report) {
+    let Ok(content) = read_db_content() else {
+        return;
+    };
+    let version = db_format::schema_version(&content);
+    if version == migrations::CURRENT_VERSION {
+        return;
+    }
+    let restore = "or `pet-cli restore` a backup";
+    match db_format::parse(&content) {
+        Err(Error::MigrationError(err @ migrations::MigrationError::Newer(_))) => startup.fail(
+            Check::Schema,
+            err.to_string(),
+            format!("use a newer pet-cli, {}", restore),
+        ),
+        Err(Error::MigrationError(migrations::MigrationError::Failed { from, to, message })) => {
+            let description = migrations::MIGRATIONS
+                .iter()
+                .find(|migration| migration.from == from)
+                .map_or("", |migration| migration.description);
+            startup.fail(
+                Check::Schema,
+                format!(
+                    "the migration from schema version {} to {} ({}) failed: {}",
+                    from, to, description, message
+                ),
+                format!("correct the DB file where it says, {}", restore),
+            )
+        }
+        _ if options.read_only => startup.warn(
+            Check::Schema,
+            format!(
+                "the DB has schema version {}, which read-only mode leaves it at",
+                version
+            ),
+        ),
+        _ => {}
+    }
+}
+
+/// Takes the DB lock for a moment, or for the whole session with
+/// `--exclusive`, which is then returned. A DB that will be opened read-only
+/// needs no lock.
+fn check_db_lock(
+    cli: &cli::Cli,
+    options: WriteOptions,
+    startup: &mut startup::Report,
+) -> Option<db_lock::DbLock> {
+    if options.read_only || !db_is_writable() {
+        return None;
+    }
+    let lock = if cli.exclusive {
+        db_lock::exclusive_for_session(Path::new(&workspace_db())).map(Some)
+    } else {
+        db_lock::exclusive(Path::new(&workspace_db())).map(|_| None)
+    };
+    lock.unwrap_or_else(|err| {
+        startup.fail(
+            Check::Lock,
+            err.to_string(),
+            "close the other pet-cli that has the DB open, or wait for it to finish",
+        );
+        None
+    })
+}
+
+/// Asks on the terminal whether to draw the UI there, as stdout is
+/// redirected, and returns it if so.
+fn offer_tty() -> Result<tty::Output, AppError> {
+    let mut tty = tty::Output::tty().map_err(|_| AppError::NoTerminal)?;
+    write!(
+        tty,
+        "stdout is not a terminal. Draw the UI on {} instead? (y/n) ",
+        tty::TTY_PATH
+    )?;
+    tty.flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if answer.trim() == "y" {
+        Ok(tty)
+    } else {
+        Err(AppError::NoTerminal)
+    }
+}
+
+/// Opens the form of a resumed draft with its text.
+/// Opens the list of backups to restore the DB from.
+fn preview_restore(app_state: &mut AppState, pets: &[Pet]) {
+    match backup::list(Path::new(&db_path())) {
+        Ok(backups) => match RestorePreview::new(backups, pets) {
+            Some(preview) => app_state.restore_preview = Some(preview),
+            None => app_state.status_line.error(format!(
+                "no backups in {}",
+                backup::backup_dir(Path::new(&db_path())).display()
+            )),
+        },
+        Err(err) => app_state
+            .status_line
+            .error(format!("could not list the backups: {}", err)),
+    }
+}
+
+/// Reads the DB on a thread of its own, sending how far it is and then
+/// `AppEvent::DbLoaded`, so that a big DB doesn't hold up the first frame.
+fn load_in_background(tx: UnboundedSender<AppEvent>) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let loaded = read_database_with(|read, size| {
+            let _ = tx.send(AppEvent::Progress(Progress {
+                label: "loading",
+                done: read as usize,
+                total: size as usize,
+            }));
+        })
+        .map(|database| database.pets);
+        let _ = tx.send(AppEvent::DbLoaded(loaded));
+    })
+}
+
+/// What startup leaves for when the first read of the DB arrives.
+struct Loading {
+    started: Instant,
+    /// `--select`.
+    select: Option<usize>,
+    /// The configured tab, which beats the one `--select` switches to.
+    tab: Option<MenuItem>,
+    /// The draft to resume.
+    draft: Option<draft::Form>,
+    /// How much of the DB file has been read, once there is enough of it to
+    /// say.
+    progress: Option<Progress>,
+}
+
+/// Takes the first read of the DB. A DB that can't be read opens the
+/// backups to restore it from, as `R` does.
+fn finish_loading(loaded: Result<Vec<Pet>, Error>, app_state: &mut AppState) {
+    let Some(loading) = app_state.loading.take() else {
+        return;
+    };
+    log::info!("loaded {} in {:?}", db_path(), loading.started.elapsed());
+    check_integrity(app_state);
+    let pets = match loaded {
+        Ok(pets) => pets,
+        Err(err) => {
+            app_state.report(StatusLevel::Error, err.to_string());
+            preview_restore(app_state, &[]);
+            return;
+        }
+    };
+    app_state.session_start_pets = pets.clone();
+    app_state.session_start_writes = metrics::snapshot().db_writes;
+    if let Some(pet_id) = loading.select {
+        if !jump_to_pet(app_state, &pets, pet_id) {
+            app_state.pet_list_state.select(Some(0));
+            app_state.selected_pet_id = None;
+            app_state
+                .status_line
+                .error(format!("no pet #{}, selected the first pet", pet_id));
+        }
+    }
+    if let Some(tab) = loading.tab {
+        app_state.active_menu_item = tab;
+    }
+    if let Some(form) = loading.draft {
+        resume_draft(app_state, form);
+    }
+}
+
+fn resume_draft(app_state: &mut AppState, form: draft::Form) {
+    match form.clone() {
+        draft::Form::Notes { pet_id, text } => {
+            let pets = read_db().unwrap_or_default();
+            jump_to_pet(app_state, &pets, pet_id);
+            app_state.notes_editor = Some(NotesEditor::new(pet_id, Some(&text)));
+        }
+        draft::Form::Prompt { kind, text } => {
+            app_state.prompt = Some(Prompt::new(kind, &text));
+        }
+    }
+    app_state.saved_draft = Some(form);
+}
+
+/// The form being filled in, as it would be saved in a draft.
+fn current_form(app_state: &AppState) -> Option<draft::Form> {
+    if let Some(editor) = &app_state.notes_editor {
+        return Some(draft::Form::Notes {
+            pet_id: editor.pet_id,
+            text: editor.text.clone(),
+        });
+    }
+    app_state.prompt.as_ref().map(|prompt| draft::Form::Prompt {
+        kind: prompt.kind.clone(),
+        text: prompt.input.text().to_owned(),
+    })
+}
+
+/// Saves the open form every few ticks if it changed, and deletes the draft
+/// as soon as the form is closed, whether saved or cancelled.
+fn update_draft(app_state: &mut AppState, tick: bool) {
+    let Some(path) = app_state.draft_path.clone() else {
+        return;
+    };
+    let Some(form) = current_form(app_state) else {
+        if app_state.saved_draft.take().is_some() {
+            if let Err(err) = draft::delete(&path) {
+                log::warn!("could not delete the draft {}: {}", path.display(), err);
+            }
+        }
+        return;
+    };
+    if !tick {
+        return;
+    }
+    app_state.draft_ticks = (app_state.draft_ticks + 1) % draft::SAVE_EVERY_TICKS;
+    if app_state.draft_ticks != 0 || app_state.saved_draft.as_ref() == Some(&form) {
+        return;
+    }
+    let draft = draft::Draft {
+        db_path: app_state.db_path.clone(),
+        saved_at: Utc::now(),
+        form: form.clone(),
+    };
+    match draft::save(&path, &draft) {
+        Ok(()) => app_state.saved_draft = Some(form),
+        Err(err) => log::warn!("could not save the draft {}: {}", path.display(), err),
+    }
+}
+
+/// Switches to the Pets tab with the pet with `pet_id` selected, dropping the
+/// category and status filters and search if they hide that pet. Returns
+/// whether there is such a pet outside the Trash.
+fn jump_to_pet(app_state: &mut AppState, pets: &[Pet], pet_id: usize) -> bool {
+    app_state.active_menu_item = MenuItem::Pets;
+    let shown = app_state
+        .visible_pets(pets)
+        .iter()
+        .any(|pet| pet.id == pet_id);
+    if !shown {
+        app_state.view.category = CategoryFilter::default();
+        app_state.view.status = None;
+        app_state.view.query = None;
+        app_state.marks.clear();
+    }
+    let found = select_pet(app_state, pets, pet_id);
+    if found {
+        app_state.notes_scroll = 0;
+        app_state.records_scroll = 0;
+        app_state.detail_scroll = 0;
+    }
+    found
+}
+
+/// Where the selection is now, to come back to with `Ctrl+o`.
+fn current_jump(app_state: &AppState) -> Jump {
+    Jump {
+        tab: app_state.active_menu_item,
+        pet_id: app_state.selected_pet_id,
+    }
+}
+
+/// Adds where the selection is to the jump list, before a jump moves it.
+fn record_jump(app_state: &mut AppState) {
+    let from = current_jump(app_state);
+    app_state.jumps.push(from);
+}
+
+/// Whether `jump` can still be gone to: its pet, if any, hasn't been
+/// deleted or moved to the Trash since.
+fn jump_valid(jump: &Jump, pets: &[Pet]) -> bool {
+    jump.pet_id.is_none_or(|pet_id| {
+        pets.iter()
+            .any(|pet| pet.id == pet_id && !pet.is_archived())
+    })
+}
+
+fn restore_jump(app_state: &mut AppState, pets: &[Pet], jump: Jump) {
+    if let Some(pet_id) = jump.pet_id {
+        jump_to_pet(app_state, pets, pet_id);
+    }
+    app_state.active_menu_item = jump.tab;
+}
+
+/// Switches to the Pets tab showing all the pets of `category`, dropping the
+/// status filter and search so none of them are hidden.
+fn show_category(app_state: &mut AppState, category: String) {
+    app_state.active_menu_item = MenuItem::Pets;
+    app_state.view.category = CategoryFilter::only(Some(category));
+    app_state.view.status = None;
+    app_state.view.query = None;
+    app_state.marks.clear();
+    app_state.pet_list_state.select(Some(0));
+    app_state.selected_pet_id = None;
+    app_state.notes_scroll = 0;
+    app_state.records_scroll = 0;
+    app_state.detail_scroll = 0;
+}
+
+/// Completes the breed prompt from the breeds known for the pet's species,
+/// returning whether `code` was Tab.
+fn handle_breed_key(code: KeyCode, pet_id: usize, app_state: &mut AppState) -> bool {
+    let prompt = app_state.prompt.as_mut().expect("prompt is open");
+    if code != KeyCode::Tab {
+        prompt.completer.reset();
+        return false;
+    }
+    let typed = prompt.input.text().to_owned();
+    let completed = prompt.completer.complete_ranked(&typed, || {
+        let pets = read_db().unwrap_or_default();
+        let category = pets
+            .iter()
+            .find(|pet| pet.id == pet_id)
+            .map_or("", |pet| pet.category.as_str());
+        breeds::suggestions(category, &typed)
+            .into_iter()
+            .map(str::to_owned)
+            .collect()
+    });
+    if let Some(completed) = completed {
+        prompt.input = TextInput::new(&completed);
+    }
+    true
+}
+
+/// Handles history and completion keys in the search prompt, returning
+/// whether `code` was one.
+fn handle_search_key(code: KeyCode, app_state: &mut AppState) -> bool {
+    let prompt = app_state.prompt.as_mut().expect("prompt is open");
+    if code != KeyCode::Tab {
+        prompt.completer.reset();
+    }
+    match code {
+        KeyCode::Tab => {
+            let pets = read_db().unwrap_or_default();
+            let source = Both(PetNames(&pets), Categories(&pets));
+            if let Some(completed) = prompt.completer.complete(prompt.input.text(), &source) {
+                prompt.input = TextInput::new(&completed);
+            }
+        }
+        KeyCode::Up => {
+            if let Some(query) = prompt.history.older(&app_state.search_history) {
+                prompt.input = TextInput::new(query);
+            }
+        }
+        KeyCode::Down => {
+            prompt.input = TextInput::new(prompt.history.newer(&app_state.search_history))
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn submit_prompt(prompt: Prompt, app_state: &mut AppState) {
+    match prompt.kind {
+        PromptKind::Search => {
+            let text = prompt.input.text().trim();
+            let query = match Query::parse(text) {
+                Ok(query) => query,
+                Err(err) => return prompt.reject(err.to_string(), app_state),
+            };
+            record_jump(app_state);
+            input::remember(&mut app_state.search_history, text);
+            app_state.view.query = Some(query).filter(|_| !text.is_empty());
+            app_state.marks.clear();
+        }
+        PromptKind::Birthdate { pet_id } => {
+            let text = prompt.input.text().trim();
+            let birthdate = if text.is_empty() {
+                None
+            } else {
+                let today = Local::now().date_naive();
+                match dates::parse_user_date(text, today, app_state.date_order) {
+                    Ok(date) => Some(date),
+                    Err(err) => return prompt.reject(err.to_string(), app_state),
+                }
+            };
+            request_change(DbChange::Birthdate { pet_id, birthdate }, app_state);
+        }
+        PromptKind::Age { pet_id } => match age::parse_typed(prompt.input.text()) {
+            Ok(age::Typed::Months(months)) => {
+                let age = Age { months };
+                request_change(DbChange::Age { pet_id, age }, app_state);
+            }
+            // Young animals are counted in days, which only a birthdate
+            // keeps.
+            Ok(age::Typed::Days(days)) => {
+                match dates::born_days_ago(days, Local::now().date_naive()) {
+                    Ok(birthdate) => {
+                        let birthdate = Some(birthdate);
+                        request_change(DbChange::Birthdate { pet_id, birthdate }, app_state);
+                    }
+                    Err(err) => {
+                        app_state.report(StatusLevel::Error, err);
+                        app_state.prompt = Some(prompt);
+                    }
+                }
+            }
+            Err(err) => {
+                app_state.report(StatusLevel::Error, err);
+                app_state.prompt = Some(prompt);
+            }
+        },
+        PromptKind::NewOwner => {
+            let text = prompt.input.text();
+            let parsed = text
+                .split_once(',')
+                .map(|(name, email)| (name.trim(), email.trim()))
+                .filter(|(name, email)| !name.is_empty() && !email.is_empty());
+            let Some((name, email)) = parsed else {
+                app_state
+                    .status_line
+                    .error("expected a name and an email separated by a comma");
+                app_state.prompt = Some(prompt);
+                return;
+            };
+            let change = DbChange::AddOwner {
+                name: name.to_owned(),
+                email: email.to_owned(),
+            };
+            request_change(change, app_state);
+        }
+        PromptKind::Owner { pet_id } => {
+            let text = prompt.input.text().trim();
+            let owner_id = if text.is_empty() {
+                None
+            } else {
+                let owners = read_database()
+                    .map(|database| database.owners)
+                    .unwrap_or_default();
+                match find_owner(&owners, text) {
+                    Some(owner) => Some(owner.id),
+                    None => {
+                        app_state
+                            .status_line
+                            .error(format!("no owner named '{}'", text));
+                        app_state.prompt = Some(prompt);
+                        return;
+                    }
+                }
+            };
+            request_change(DbChange::Owner { pet_id, owner_id }, app_state);
+        }
+        PromptKind::Record { pet_id } => {
+            let today = Local::now().date_naive();
+            let record = match parse_record(prompt.input.text(), today, app_state.date_order) {
+                Ok(record) => record,
+                Err(err) => return prompt.reject(err, app_state),
+            };
+            request_change(DbChange::AddRecord { pet_id, record }, app_state);
+        }
+        PromptKind::Copy { pet_id } => {
+            let name = prompt.input.text().trim().to_owned();
+            request_change(DbChange::Copy { pet_id, name }, app_state);
+        }
+        PromptKind::Rename { pet_id } => {
+            let name = prompt.input.text().trim().to_owned();
+            let pets = read_db().unwrap_or_default();
+            if let Some(pet) = pets.iter().find(|pet| pet.id == pet_id) {
+                let renamed = Pet {
+                    name: name.clone(),
+                    ..pet.clone()
+                };
+                let before = validation::validate(pet);
+                let issues: Vec<_> = validation::validate(&renamed)
+                    .into_iter()
+                    .filter(|issue| !before.contains(issue))
+                    .collect();
+                if !issues.is_empty() {
+                    app_state.report(StatusLevel::Error, validation::describe(&issues));
+                    app_state.prompt = Some(prompt);
+                    return;
+                }
+            }
+            request_change(DbChange::Rename { pet_id, name }, app_state);
+        }
+        PromptKind::Breed { pet_id } => {
+            let pets = read_db().unwrap_or_default();
+            let category = pets
+                .iter()
+                .find(|pet| pet.id == pet_id)
+                .map_or("", |pet| pet.category.as_str());
+            let breed = breeds::normalize(category, prompt.input.text());
+            request_change(DbChange::Breed { pet_id, breed }, app_state);
+        }
+        PromptKind::Microchip { pet_id } => {
+            let number = microchip::normalize(prompt.input.text());
+            let microchip = if number.is_empty() {
+                None
+            } else if let Err(issue) = microchip::check(&number) {
+                app_state.report(StatusLevel::Error, issue.to_string());
+                app_state.prompt = Some(prompt);
+                return;
+            } else {
+                Some(number)
+            };
+            request_change(DbChange::Microchip { pet_id, microchip }, app_state);
+        }
+        PromptKind::Attribute { pet_id } => {
+            match attributes::parse_assignment(prompt.input.text()) {
+                Ok((key, value)) => {
+                    request_change(DbChange::Attribute { pet_id, key, value }, app_state)
+                }
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, err);
+                    app_state.prompt = Some(prompt);
+                }
+            }
+        }
+        PromptKind::BatchEdit { ref ids } => match batch::Assignment::parse(prompt.input.text()) {
+            Ok(assignment) => {
+                app_state.pending_confirmation = Some(Confirmation::BatchEdit {
+                    ids: ids.iter().copied().collect(),
+                    assignment,
+                })
+            }
+            Err(err) => {
+                app_state.report(StatusLevel::Error, err);
+                app_state.prompt = Some(prompt);
+            }
+        },
+        PromptKind::ResetDb => {
+            if prompt.input.text().trim() != reset::CONFIRM_WORD {
+                let err = format!("type {} to go on, or Esc to cancel", reset::CONFIRM_WORD);
+                return prompt.reject(err, app_state);
+            }
+            request_change(DbChange::Reset, app_state);
+        }
+        PromptKind::QuickAdd => {
+            let today = Local::now().date_naive();
+            let pet = quick_add::merge(&app_state.add_defaults, prompt.input.text(), today)
+                .map(new_pet)
+                .and_then(|pet| {
+                    let issues = validation::validate(&pet);
+                    if issues.is_empty() {
+                        Ok(pet)
+                    } else {
+                        Err(validation::describe(&issues))
+                    }
+                });
+            match pet {
+                Ok(pet) => request_change(DbChange::AddPet(Box::new(pet)), app_state),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, err);
+                    app_state.prompt = Some(prompt);
+                }
+            }
+        }
+        PromptKind::Import => {
+            let path = prompt.input.text().trim().to_owned();
+            let parsed = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|content| share::parse(&content, Utc::now()));
+            match parsed {
+                Ok(pets) => request_change(DbChange::Import(pets), app_state),
+                Err(err) => {
+                    app_state
+                        .status_line
+                        .error(format!("could not import {}: {}", path, err));
+                    app_state.prompt = Some(prompt);
+                }
+            }
+        }
+        PromptKind::MergeDb => {
+            let path = prompt.input.text().trim().to_owned();
+            let theirs = match read_db_file(Path::new(&path)) {
+                Ok(theirs) => theirs,
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, err.to_string());
+                    app_state.prompt = Some(prompt);
+                    return;
+                }
+            };
+            let Some(mine) = read_db_or_report(app_state) else {
+                return;
+            };
+            let plan = merge::plan(&mine, &theirs);
+            app_state
+                .status_line
+                .info(format!("{}: {}", path, plan.summary()));
+            if plan.conflicts.is_empty() {
+                let resolutions = HashMap::new();
+                request_change(
+                    DbChange::MergeDb {
+                        theirs,
+                        resolutions,
+                    },
+                    app_state,
+                );
+            } else {
+                app_state.merge_review = Some(MergeReview::new(theirs, plan));
+            }
+        }
+        PromptKind::RenameView { ref name } => {
+            let new_name = prompt.input.text().trim().to_owned();
+            let taken = new_name != *name
+                && app_state
+                    .saved_views
+                    .views
+                    .iter()
+                    .any(|view| view.name == new_name);
+            let problem = saved_views::check_name(&new_name)
+                .err()
+                .or_else(|| taken.then(|| format!("there is a view {} already", new_name)));
+            if let Some(problem) = problem {
+                return prompt.reject(problem, app_state);
+            }
+            if let Some(view) = app_state
+                .saved_views
+                .views
+                .iter_mut()
+                .find(|view| view.name == *name)
+            {
+                view.name = new_name.clone();
+            }
+            if app_state.active_view.as_ref() == Some(name) {
+                app_state.active_view = Some(new_name.clone());
+            }
+            save_views(app_state);
+            app_state
+                .status_line
+                .info(format!("renamed view {} to {}", name, new_name));
+        }
+        PromptKind::CategoryName { ref from } => {
+            match categories::Rename::new(from.clone(), prompt.input.text(), false) {
+                Ok(rename) => request_change(DbChange::RenameCategories(rename), app_state),
+                Err(problem) => prompt.reject(problem, app_state),
+            }
+        }
+        PromptKind::Photo { pet_id } => {
+            let path = prompt.input.text().trim();
+            let photo_path = Some(PathBuf::from(path)).filter(|_| !path.is_empty());
+            request_change(DbChange::Photo { pet_id, photo_path }, app_state);
+        }
+        PromptKind::Weight { pet_id } => match weight::parse(prompt.input.text()) {
+            Ok(grams) => {
+                let entry = WeightEntry {
+                    date: Local::now().date_naive(),
+                    grams,
+                };
+                request_change(DbChange::AddWeight { pet_id, entry }, app_state);
+            }
+            Err(err) => {
+                app_state.report(StatusLevel::Error, err);
+                app_state.prompt = Some(prompt);
+            }
+        },
+    }
+}
+
+/// Parses `date, kind, note` where the note may be left out or contain
+/// further commas. The date is read by `dates::parse_user_date`.
+fn parse_record(
+    text: &str,
+    today: NaiveDate,
+    order: Option<DateOrder>,
+) -> Result<MedicalRecord, String> {
+    let mut fields = text.splitn(3, ',').map(str::trim);
+    let date = dates::parse_user_date(fields.next().unwrap_or_default(), today, order)
+        .map_err(|err| err.to_string())?;
+    let kind = fields
+        .next()
+        .filter(|kind| !kind.is_empty())
+        .ok_or("type the kind of record after the date, as in 2024-03-01, vaccination")?
+        .to_owned();
+    let note = fields.next().unwrap_or_default().to_owned();
+    Ok(MedicalRecord { date, kind, note })
+}
+
+/// The owner with id `query`, or else the one whose name is `query` ignoring
+/// case.
+fn find_owner<'o>(owners: &'o [Owner], query: &str) -> Option<&'o Owner> {
+    let id = query.parse::<usize>().ok();
+    owners
+        .iter()
+        .find(|owner| Some(owner.id) == id)
+        .or_else(|| {
+            owners
+                .iter()
+                .find(|owner| owner.name.eq_ignore_ascii_case(query))
+        })
+}
+
+fn pet_count_of(pets: &[Pet], owner_id: usize) -> usize {
+    pets.iter()
+        .filter(|pet| pet.owner_id == Some(owner_id))
+        .count()
+}
+
+/// The index after (or before) `selected` in a list of `len`, wrapping around
+/// at both ends.
+fn wrap_selection(selected: usize, len: usize, forward: bool) -> usize {
+    match (forward, selected) {
+        (true, selected) if selected + 1 >= len => 0,
+        (true, selected) => selected + 1,
+        (false, 0) => len.saturating_sub(1),
+        (false, selected) => selected - 1,
+    }
+}
+
+/// The selection of a list or a table, so that both move the same way.
+trait Selection {
+    fn selected(&self) -> Option<usize>;
+    fn select(&mut self, index: Option<usize>);
+}
+
+impl Selection for ListState {
+    fn selected(&self) -> Option<usize> {
+        ListState::selected(self)
+    }
+
+    fn select(&mut self, index: Option<usize>) {
+        ListState::select(self, index)
+    }
+}
+
+impl Selection for TableState {
+    fn selected(&self) -> Option<usize> {
+        TableState::selected(self)
+    }
+
+    fn select(&mut self, index: Option<usize>) {
+        TableState::select(self, index)
+    }
+}
+
+/// Moves a list selection `steps` rows, stopping at either end.
+fn step_selection(state: &mut impl Selection, len: usize, forward: bool, steps: usize) {
+    let selected = state.selected();
+    let next = match (selected, forward) {
+        (None, _) => 0,
+        (Some(index), true) => (index + steps).min(len.saturating_sub(1)),
+        (Some(index), false) => index.saturating_sub(steps),
+    };
+    state.select(Some(next));
+}
+
+fn select_end(state: &mut impl Selection, len: usize, last: bool) {
+    let index = if last { len.saturating_sub(1) } else { 0 };
+    state.select(Some(index));
+}
+
+fn confirm(confirmation: Confirmation, app_state: &mut AppState) {
+    match confirmation {
+        Confirmation::ArchiveMarked(ids) | Confirmation::ArchiveRange { ids, .. } => {
+            request_change(DbChange::Archive(ids), app_state)
+        }
+        Confirmation::Purge { pet_id, .. } => {
+            request_change(DbChange::Purge(HashSet::from([pet_id])), app_state)
+        }
+        Confirmation::PurgeMarked(ids) => request_change(DbChange::Purge(ids), app_state),
+        Confirmation::Merge {
+            survivor_id,
+            other_ids,
+            ..
+        } => request_change(
+            DbChange::Merge {
+                survivor_id,
+                other_ids,
+            },
+            app_state,
+        ),
+        Confirmation::DeleteOwner { owner_id, .. } => {
+            request_change(DbChange::DeleteOwner(owner_id), app_state)
+        }
+        Confirmation::BatchEdit { ids, assignment } => {
+            request_change(DbChange::BatchEdit { ids, assignment }, app_state)
+        }
+        Confirmation::Overwrite(change) => apply_change(change, app_state),
+        Confirmation::Chain(first, second) => {
+            confirm(*first, app_state);
+            // A write that turned out to need asking about asks first; the
+            // second question is dropped then.
+            if app_state.pending_confirmation.is_none() {
+                app_state.pending_confirmation = Some(*second);
+            }
+        }
+    }
+}
+
+/// `d` on `targets`. Those outside the Trash are moved there, after the
+/// question `ask` makes of their ids if it makes one. Those already in it are
+/// asked about deleting for good, separately and after that, as deleting
+/// them again can only mean that.
+fn delete_pets(
+    targets: &[&Pet],
+    ask: impl FnOnce(HashSet<usize>) -> Option<Confirmation>,
+    app_state: &mut AppState,
+) {
+    let (trashed, active): (Vec<&Pet>, Vec<&Pet>) =
+        targets.iter().partition(|pet| pet.is_archived());
+    let purge = match trashed[..] {
+        [] => None,
+        [pet] => Some(Confirmation::Purge {
+            pet_id: pet.id,
+            name: pet.name.clone(),
+        }),
+        _ => Some(Confirmation::PurgeMarked(
+            trashed.iter().map(|pet| pet.id).collect(),
+        )),
+    };
+    let active: HashSet<usize> = active.iter().map(|pet| pet.id).collect();
+    if active.is_empty() {
+        app_state.pending_confirmation = purge;
+        return;
+    }
+    match (ask(active.clone()), purge) {
+        (Some(archive), Some(purge)) => {
+            app_state.pending_confirmation =
+                Some(Confirmation::Chain(Box::new(archive), Box::new(purge)))
+        }
+        (Some(archive), None) => app_state.pending_confirmation = Some(archive),
+        (None, purge) => {
+            request_change(DbChange::Archive(active), app_state);
+            if app_state.pending_confirmation.is_none() {
+                app_state.pending_confirmation = purge;
+            }
+        }
+    }
+}
+
+/// Answers no to `confirmation`.
+fn decline(confirmation: Confirmation, app_state: &mut AppState) {
+    app_state.status_line.info("cancelled");
+    if let Confirmation::Chain(_, second) = confirmation {
+        app_state.pending_confirmation = Some(*second);
+    }
+}
+
+/// Applies `change` unless the DB was modified by another program since the
+/// view was last refreshed, in which case the user is asked first.
+fn request_change(change: DbChange, app_state: &mut AppState) {
+    // While our own writes are pending the file changing is expected.
+    if !app_state.db_writer.is_busy() && app_state.db_watch.changed() {
+        log::warn!("db changed on disk, asking before writing");
+        app_state.pending_confirmation = Some(Confirmation::Overwrite(change));
+    } else {
+        apply_change(change, app_state);
+    }
+}
+
+/// Queues `change` to be written in the background.
+fn apply_change(change: DbChange, app_state: &mut AppState) {
+    log::debug!("queueing {:?}", change);
+    app_state.db_writer.queue.push_back(change);
+    start_next_write(app_state);
+}
+
+/// Hands the oldest queued change to tokio's blocking pool unless a write is
+/// already running. Writes go one at a time so they can't race each other.
+fn start_next_write(app_state: &mut AppState) {
+    let writer = &mut app_state.db_writer;
+    if writer.in_flight {
+        return;
+    }
+    let Some(change) = writer.queue.pop_front() else {
+        return;
+    };
+    writer.in_flight = true;
+    let done_tx = writer.done_tx.clone();
+    let progress_tx = writer.progress_tx.clone();
+    writer.cancel = Cancel::default();
+    let progress = Reporter::new(
+        move |progress| {
+            let _ = progress_tx.send(progress);
+        },
+        writer.cancel.clone(),
+    );
+    let options = app_state.write_options;
+    let hooks = writer.hooks.clone();
+    let hook_tx = writer.hook_tx.clone();
+    tokio::task::spawn_blocking(move || {
+        log::debug!("applying {:?}", change);
+        let (event, pet_id) = hook_event(&change);
+        // Only read when there are hooks, to tell whether the write wrote.
+        let before = if hooks.commands(event).is_empty() {
+            None
+        } else {
+            read_database().ok()
+        };
+        let done = write_change(change, options, &progress);
+        if let Some(before) = before {
+            run_hooks(&hooks, event, pet_id, &before, move |outcome| {
+                let _ = hook_tx.send(outcome);
+            });
+        }
+        let _ = done_tx.send(done);
+    });
+}
+
+/// Which hooks a change runs, and the pet it is about if it knows it before
+/// writing. Adds learn theirs from the DB afterwards.
+fn hook_event(change: &DbChange) -> (hooks::HookEvent, Option<usize>) {
+    use hooks::HookEvent;
+    match change {
+        DbChange::Add { .. }
+        | DbChange::Copy { .. }
+        | DbChange::Import(_)
+        | DbChange::AddPet(_) => (HookEvent::Add, None),
+        DbChange::Archive(ids) => {
+            let pet_id = match ids.iter().collect::<Vec<_>>()[..] {
+                [id] => Some(*id),
+                _ => None,
+            };
+            (HookEvent::Delete, pet_id)
+        }
+        DbChange::Purge(ids) => {
+            let pet_id = match ids.iter().collect::<Vec<_>>()[..] {
+                [id] => Some(*id),
+                _ => None,
+            };
+            (HookEvent::Delete, pet_id)
+        }
+        DbChange::DeleteOwner(_) => (HookEvent::Delete, None),
+        DbChange::Restore(pet_id) | DbChange::AdvanceStatus(pet_id) => {
+            (HookEvent::Update, Some(*pet_id))
+        }
+        DbChange::Merge { survivor_id, .. } => (HookEvent::Update, Some(*survivor_id)),
+        DbChange::Notes { pet_id, .. }
+        | DbChange::Birthdate { pet_id, .. }
+        | DbChange::Age { pet_id, .. }
+        | DbChange::Owner { pet_id, .. }
+        | DbChange::AddRecord { pet_id, .. }
+        | DbChange::AddWeight { pet_id, .. }
+        | DbChange::Microchip { pet_id, .. }
+        | DbChange::Breed { pet_id, .. }
+        | DbChange::Photo { pet_id, .. }
+        | DbChange::Move { pet_id, .. }
+        | DbChange::Attribute { pet_id, .. }
+        | DbChange::Rename { pet_id, .. } => (HookEvent::Update, Some(*pet_id)),
+        DbChange::AddOwner { .. }
+        | DbChange::BatchEdit { .. }
+        | DbChange::RenameCategories(_)
+        | DbChange::MergeDb { .. }
+        | DbChange::RestoreBackup { .. }
+        | DbChange::Reset => (HookEvent::Update, None),
+    }
+}
+
+/// Starts the hooks for `event` if the DB moved on from `before`, that is,
+/// if the write didn't fail or get cancelled. An add of a single pet tells
+/// them its new id.
+fn run_hooks(
+    hooks: &hooks::Hooks,
+    event: hooks::HookEvent,
+    pet_id: Option<usize>,
+    before: &Database,
+    done: impl Fn(hooks::HookOutcome) + Send + 'static,
+) {
+    let Ok(after) = read_database() else {
+        return;
+    };
+    if after.revision == before.revision {
+        return;
+    }
+    let pet_id = pet_id.or_else(|| {
+        let known: HashSet<usize> = before.pets.iter().map(|pet| pet.id).collect();
+        let added: Vec<usize> = after
+            .pets
+            .iter()
+            .map(|pet| pet.id)
+            .filter(|id| !known.contains(id))
+            .collect();
+        match added[..] {
+            [id] => Some(id),
+            _ => None,
+        }
+    });
+    let context = hooks::HookContext {
+        event,
+        pet_id,
+        db_path: hooks::db_path(Path::new(&db_path())),
+    };
+    hooks::spawn(hooks, context, done);
+}
+
+/// Runs on the main loop once a write started by `start_next_write` is done.
+fn finish_write(done: WriteDone, app_state: &mut AppState) {
+    app_state.db_writer.progress = None;
+    done(app_state);
+    app_state.db_watch.mark_seen();
+    app_state.db_writer.in_flight = false;
+    start_next_write(app_state);
+}
+
+/// Writes `change` to the DB. This blocks, so it runs off the main loop; the
+/// returned closure reports the result and moves the selections on it.
+fn write_change(change: DbChange, options: WriteOptions, progress: &Reporter) -> WriteDone {
+    match change {
+        DbChange::Add { count, names } => {
+            let result = add_random_pets_to_db(options, count, None, &names, progress);
+            Box::new(move |app_state| match result {
+                Ok(pets) => {
+                    let added = &pets[pets.len() - count..];
+                    match added {
+                        [pet] => app_state.report(
+                            StatusLevel::Info,
+                            format!("added {} (#{})", pet.name, pet.id),
+                        ),
+                        _ => app_state.report(StatusLevel::Info, format!("added {} pets", count)),
+                    }
+                    // A pet the view hides can't be selected, so the
+                    // selection stays on the pet it was on.
+                    if let Some(pet) = added.iter().rev().find(|pet| app_state.view.matches(pet)) {
+                        select_pet(app_state, &pets, pet.id);
+                    }
+                }
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not add pet: {}", err))
+                }
+            })
+        }
+        DbChange::Archive(ids) => {
+            let result = set_archived(options, &ids, Some(Utc::now()));
+            Box::new(move |app_state| {
+                match result {
+                    Ok(archived) => match archived.as_slice() {
+                        [pet] => app_state.report(
+                            StatusLevel::Info,
+                            format!("moved {} (#{}) to the Trash", pet.name, pet.id),
+                        ),
+                        _ => app_state.report(
+                            StatusLevel::Info,
+                            format!("moved {} pets to the Trash", archived.len()),
+                        ),
+                    },
+                    Err(err) => {
+                        app_state.report(StatusLevel::Error, format!("could not archive: {}", err))
+                    }
+                }
+                app_state.marks.clear();
+                let pets = read_db().unwrap_or_default();
+                follow_pet_selection(app_state, &app_state.visible_pets(&pets));
+            })
+        }
+        DbChange::Restore(pet_id) => {
+            let result = set_archived(options, &HashSet::from([pet_id]), None);
+            Box::new(move |app_state| match result {
+                Ok(restored) => {
+                    if let Some(pet) = restored.first() {
+                        app_state.report(StatusLevel::Info, format!("restored {}", pet.name));
+                    }
+                    clamp_trash_selection(app_state);
+                }
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not restore: {}", err))
+                }
+            })
+        }
+        DbChange::Merge {
+            survivor_id,
+            other_ids,
+        } => {
+            let result = merge_pets(options, survivor_id, &other_ids);
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => {
+                    app_state.report(
+                        StatusLevel::Info,
+                        format!("merged {} pets into {}", other_ids.len(), pet.name),
+                    );
+                    let pets = read_db().unwrap_or_default();
+                    let row_count = duplicate_rows(&pets).len();
+                    let selected = app_state
+                        .tab_views
+                        .list(MenuItem::Duplicates)
+                        .selected()
+                        .unwrap_or(0);
+                    app_state
+                        .tab_views
+                        .list(MenuItem::Duplicates)
+                        .select(Some(selected.min(row_count.saturating_sub(1))));
+                }
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not merge: {}", err))
+                }
+            })
+        }
+        DbChange::Purge(ids) => {
+            let result = purge_pets(options, &ids);
+            Box::new(move |app_state| match result {
+                Ok(purged) => {
+                    match purged.as_slice() {
+                        [] => {}
+                        [pet] => app_state.report(
+                            StatusLevel::Info,
+                            format!("deleted {} (#{}) permanently", pet.name, pet.id),
+                        ),
+                        _ => app_state.report(
+                            StatusLevel::Info,
+                            format!("deleted {} pets permanently", purged.len()),
+                        ),
+                    }
+                    clamp_trash_selection(app_state);
+                    let pets = read_db().unwrap_or_default();
+                    follow_pet_selection(app_state, &app_state.visible_pets(&pets));
+                }
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not delete pet: {}", err))
+                }
+            })
+        }
+        DbChange::Notes { pet_id, notes } => {
+            let result = update_pet(options, pet_id, |pet| pet.notes = notes);
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => {
+                    app_state.report(StatusLevel::Info, format!("saved notes for {}", pet.name))
+                }
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not save notes: {}", err))
+                }
+            })
+        }
+        DbChange::Birthdate { pet_id, birthdate } => {
+            let result = update_pet(options, pet_id, |pet| pet.birthdate = birthdate);
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => app_state.report(
+                    StatusLevel::Info,
+                    format!("updated birthdate of {}", pet.name),
+                ),
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => app_state.report(
+                    StatusLevel::Error,
+                    format!("could not save birthdate: {}", err),
+                ),
+            })
+        }
+        DbChange::Age { pet_id, age } => {
+            let result = update_pet(options, pet_id, |pet| pet.age = age);
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => {
+                    app_state.report(StatusLevel::Info, format!("updated age of {}", pet.name))
+                }
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not save age: {}", err))
+                }
+            })
+        }
+        DbChange::Owner { pet_id, owner_id } => {
+            let result = update_pet(options, pet_id, |pet| pet.owner_id = owner_id);
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => {
+                    app_state.report(StatusLevel::Info, format!("updated owner of {}", pet.name))
+                }
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not save owner: {}", err))
+                }
+            })
+        }
+        DbChange::Move {
+            pet_id,
+            anchor_id,
+            side,
+        } => {
+            let result = move_pet(options, pet_id, anchor_id, side);
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => {
+                    app_state.report(StatusLevel::Info, format!("moved {}", pet.name));
+                    let pets = read_db().unwrap_or_default();
+                    jump_to_pet(app_state, &pets, pet.id);
+                }
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not move pet: {}", err))
+                }
+            })
+        }
+        DbChange::Copy { pet_id, name } => {
+            let result = copy_pet(options, pet_id, name);
+            Box::new(move |app_state| match result {
+                Ok(Some(copy)) => {
+                    app_state.report(
+                        StatusLevel::Info,
+                        format!("added {} (#{})", copy.name, copy.id),
+                    );
+                    let pets = read_db().unwrap_or_default();
+                    jump_to_pet(app_state, &pets, copy.id);
+                }
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not copy pet: {}", err))
+                }
+            })
+        }
+        DbChange::AddRecord { pet_id, record } => {
+            let result = update_pet(options, pet_id, |pet| pet.records.push(record));
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => app_state.report(
+                    StatusLevel::Info,
+                    format!("added a medical record for {}", pet.name),
+                ),
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not add record: {}", err))
+                }
+            })
+        }
+        DbChange::Breed { pet_id, breed } => {
+            let result = update_pet(options, pet_id, |pet| pet.breed = breed);
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => {
+                    app_state.report(StatusLevel::Info, format!("updated breed of {}", pet.name))
+                }
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not save breed: {}", err))
+                }
+            })
+        }
+        DbChange::Microchip { pet_id, microchip } => {
+            let result = update_pet(options, pet_id, |pet| pet.microchip = microchip);
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => app_state.report(
+                    StatusLevel::Info,
+                    format!("updated microchip of {}", pet.name),
+                ),
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => app_state.report(
+                    StatusLevel::Error,
+                    format!("could not save microchip: {}", err),
+                ),
+            })
+        }
+        DbChange::BatchEdit { ids, assignment } => {
+            let result = batch_edit(options, &ids, &assignment);
+            Box::new(move |app_state| match result {
+                Ok(count) => {
+                    app_state.marks.clear();
+                    app_state.report(
+                        StatusLevel::Info,
+                        format!("set {} on {} pets", assignment, count),
+                    )
+                }
+                Err(err) => app_state.report(
+                    StatusLevel::Error,
+                    format!("could not set {}: {}", assignment, err),
+                ),
+            })
+        }
+        DbChange::RenameCategories(rename) => {
+            let result = rename_categories(options, &rename);
+            Box::new(move |app_state| match result {
+                Ok(0) => app_state.report(
+                    StatusLevel::Info,
+                    format!("no pets to change for {}", rename),
+                ),
+                Ok(count) => app_state.report(
+                    StatusLevel::Info,
+                    format!("renamed {} on {} pets", rename, count),
+                ),
+                Err(err) => app_state.report(
+                    StatusLevel::Error,
+                    format!("could not rename {}: {}", rename, err),
+                ),
+            })
+        }
+        DbChange::Attribute { pet_id, key, value } => {
+            let removed = value.is_none();
+            let result = update_pet(options, pet_id, |pet| match value {
+                Some(value) => {
+                    pet.attributes.insert(key.clone(), value);
+                }
+                None => {
+                    pet.attributes.remove(&key);
+                }
+            });
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) if removed => app_state.report(
+                    StatusLevel::Info,
+                    format!("removed {} of {}", key, pet.name),
+                ),
+                Ok(Some(pet)) => {
+                    app_state.report(StatusLevel::Info, format!("set {} of {}", key, pet.name))
+                }
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => app_state.report(
+                    StatusLevel::Error,
+                    format!("could not save attribute: {}", err),
+                ),
+            })
+        }
+        DbChange::Rename { pet_id, name } => {
+            let result = update_pet(options, pet_id, |pet| pet.name = name);
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => {
+                    app_state.report(StatusLevel::Info, format!("renamed to {}", pet.name))
+                }
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not rename: {}", err))
+                }
+            })
+        }
+        DbChange::Photo { pet_id, photo_path } => {
+            let result = update_pet(options, pet_id, |pet| pet.photo_path = photo_path);
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => {
+                    app_state.report(StatusLevel::Info, format!("updated photo of {}", pet.name))
+                }
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not save photo: {}", err))
+                }
+            })
+        }
+        DbChange::Import(pets) => {
+            let result = import_pets(options, pets, progress);
+            Box::new(move |app_state| match result {
+                Ok(imported) => {
+                    app_state.report(StatusLevel::Info, describe_import(&imported));
+                    if let Some(first) = imported.first() {
+                        let pets = read_db().unwrap_or_default();
+                        jump_to_pet(app_state, &pets, first.id);
+                    }
+                }
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not import: {}", err))
+                }
+            })
+        }
+        DbChange::AddPet(pet) => {
+            let result = import_pets(options, vec![*pet], progress);
+            Box::new(move |app_state| match result {
+                Ok(added) => {
+                    if let Some(pet) = added.first() {
+                        app_state.report(
+                            StatusLevel::Info,
+                            format!(
+                                "added {} (#{}, {}, {})",
+                                pet.name,
+                                pet.id,
+                                pet.category,
+                                age::format(pet.age.months)
+                            ),
+                        );
+                        let pets = read_db().unwrap_or_default();
+                        jump_to_pet(app_state, &pets, pet.id);
+                    }
+                }
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not add pet: {}", err))
+                }
+            })
+        }
+        DbChange::MergeDb {
+            theirs,
+            resolutions,
+        } => {
+            let resolve = |conflict: &Conflict| resolutions.get(&conflict.theirs.id).copied();
+            let result = merge_db(options, &theirs, resolve, progress);
+            Box::new(move |app_state| match result {
+                Ok(merged) => {
+                    app_state.report(StatusLevel::Info, format!("merged: {}", merged.summary()))
+                }
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not merge: {}", err))
+                }
+            })
+        }
+        DbChange::RestoreBackup { path, created_at } => {
+            let result = lock_db(options)
+                .map_err(|err| err.to_string())
+                .and_then(|_lock| {
+                    backup::restore(Path::new(&db_path()), &path, options.keep_backups)
+                        .map_err(|err| err.to_string())
+                });
+            Box::new(move |app_state| match result {
+                Ok(()) => {
+                    let pets = read_db().unwrap_or_default();
+                    follow_pet_selection(app_state, &app_state.visible_pets(&pets));
+                    clamp_trash_selection(app_state);
+                    check_integrity(app_state);
+                    app_state.report(
+                        StatusLevel::Info,
+                        format!("restored the backup from {}", local_time(created_at)),
+                    );
+                }
+                Err(err) => app_state.report(
+                    StatusLevel::Error,
+                    format!("could not restore the backup: {}", err),
+                ),
+            })
+        }
+        DbChange::Reset => {
+            let result = reset_db(options);
+            Box::new(move |app_state| match result {
+                Ok(aside) => {
+                    app_state.marks.clear();
+                    let pets = read_db().unwrap_or_default();
+                    follow_pet_selection(app_state, &app_state.visible_pets(&pets));
+                    clamp_trash_selection(app_state);
+                    check_integrity(app_state);
+                    app_state.report(
+                        StatusLevel::Info,
+                        format!(
+                            "moved the DB to {} and started an empty one",
+                            aside.display()
+                        ),
+                    );
+                }
+                Err(err) => app_state.report(
+                    StatusLevel::Error,
+                    format!("could not reset the DB: {}", err),
+                ),
+            })
+        }
+        DbChange::AdvanceStatus(pet_id) => {
+            let mut previous = PetStatus::default();
+            let result = update_pet(options, pet_id, |pet| {
+                previous = pet.status;
+                pet.status = pet.status.next();
+            });
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => app_state.report(
+                    StatusLevel::Info,
+                    format!("{}: {} -> {}", pet.name, previous, pet.status),
+                ),
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => app_state.report(
+                    StatusLevel::Error,
+                    format!("could not change status: {}", err),
+                ),
+            })
+        }
+        DbChange::AddWeight { pet_id, entry } => {
+            let grams = entry.grams;
+            let result = update_pet(options, pet_id, |pet| pet.weights.push(entry));
+            Box::new(move |app_state| match result {
+                Ok(Some(pet)) => app_state.report(
+                    StatusLevel::Info,
+                    format!("recorded {} for {}", weight::format(grams), pet.name),
+                ),
+                Ok(None) => app_state.report(StatusLevel::Error, "pet no longer exists".to_owned()),
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not add weight: {}", err))
+                }
+            })
+        }
+        DbChange::AddOwner { name, email } => {
+            let result = add_owner(options, name, email);
+            Box::new(move |app_state| match result {
+                Ok(owner) => {
+                    app_state.report(
+                        StatusLevel::Info,
+                        format!("added owner {} (#{})", owner.name, owner.id),
+                    );
+                    let owner_count = read_database().map_or(0, |database| database.owners.len());
+                    app_state
+                        .tab_views
+                        .list(MenuItem::Owners)
+                        .select(Some(owner_count.saturating_sub(1)));
+                }
+                Err(err) => {
+                    app_state.report(StatusLevel::Error, format!("could not add owner: {}", err))
+                }
+            })
+        }
+        DbChange::DeleteOwner(owner_id) => {
+            let result = remove_owner(options, owner_id);
+            Box::new(move |app_state| match result {
+                Ok(Some(owner)) => {
+                    app_state.report(
+                        StatusLevel::Info,
+                        format!("deleted owner {} (#{})", owner.name, owner.id),
+                    );
+                    let selected = app_state
+                        .tab_views
+                        .list(MenuItem::Owners)
+                        .selected()
+                        .unwrap_or(0);
+                    app_state
+                        .tab_views
+                        .list(MenuItem::Owners)
+                        .select(Some(selected.saturating_sub(1)));
+                }
+                Ok(None) => {}
+                Err(err) => app_state.report(
+                    StatusLevel::Error,
+                    format!("could not delete owner: {}", err),
+                ),
+            })
+        }
+    }
+}
+
+/// The rows of the Duplicates tab: every pet that has a duplicate, with the
+/// index of its group. Pets in the Trash are left out.
+fn duplicate_rows(pets: &[Pet]) -> Vec<(usize, &Pet)> {
+    let active: Vec<&Pet> = pets.iter().filter(|pet| !pet.is_archived()).collect();
+    duplicates::group_duplicates(&active)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(group, pets)| pets.into_iter().map(move |pet| (group, pet)))
+        .collect()
+}
+
+fn clamp_trash_selection(app_state: &mut AppState) {
+    let pets = read_db().unwrap_or_default();
+    let trash_count = archived_pets(&pets).len();
+    let selected = app_state
+        .tab_views
+        .list(MenuItem::Trash)
+        .selected()
+        .unwrap_or(0);
+    app_state
+        .tab_views
+        .list(MenuItem::Trash)
+        .select(Some(selected.min(trash_count.saturating_sub(1))));
+}
+
+/// The pets in the Trash, most recently archived first.
+fn archived_pets(pets: &[Pet]) -> Vec<&Pet> {
+    let mut archived: Vec<_> = pets.iter().filter(|pet| pet.is_archived()).collect();
+    archived.sort_by_key(|pet| std::cmp::Reverse(pet.archived_at));
+    archived
+}
+
+/// Picks up edits made to the DB by another program. The pet list itself is
+/// re-read on every draw, so only the selection needs to follow.
+/// Whether `a` and `b` are the same file, which need not exist yet.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// The name of the workspace whose DB is open, if it is one of theirs.
+fn current_workspace(workspaces: &[Workspace]) -> Option<String> {
+    let db = workspace_db();
+    workspaces
+        .iter()
+        .find(|workspace| same_file(&workspace.path, Path::new(&db)))
+        .map(|workspace| workspace.name.clone())
+}
+
+/// The rows of the workspace picker: the configured workspaces and, unless
+/// one of them has it, the default DB, so there is a way back to it.
+fn workspace_entries(workspaces: &[Workspace]) -> Vec<workspace::Entry> {
+    let mut all = workspaces.to_vec();
+    if !all
+        .iter()
+        .any(|workspace| same_file(&workspace.path, Path::new(DB_PATH)))
+    {
+        all.push(Workspace {
+            name: "default".to_owned(),
+            path: PathBuf::from(DB_PATH),
+        });
+    }
+    let db = workspace_db();
+    all.into_iter()
+        .map(|workspace| workspace::Entry {
+            pet_count: count_pets(&workspace.path),
+            current: same_file(&workspace.path, Path::new(&db)),
+            workspace,
+        })
+        .collect()
+}
+
+/// The pets outside the Trash of the DB at `db`, which need not be open.
+fn count_pets(db: &Path) -> Result<usize, String> {
+    let content = match fs::read(db) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Err("no DB yet".to_owned()),
+        Err(err) => return Err(err.to_string()),
+    };
+    if db_format::is_encrypted(&content) {
+        return Err(Error::EncryptedDBError.to_string());
+    }
+    let content = decompress(content).map_err(|err| err.to_string())?;
+    let database =
+        db_format::parse(&String::from_utf8_lossy(&content)).map_err(|err| err.to_string())?;
+    Ok(database
+        .pets
+        .iter()
+        .filter(|pet| !pet.is_archived())
+        .count())
+}
+
+/// Opens the DB of `workspace` instead of the current one, creating it if
+/// there is none yet. The selection, marks and cut pet were of the other
+/// DB's pets and are dropped; the filters and order of the view are kept.
+/// Turns on kiosk mode for `--kiosk`: read-only, with the tabs of
+/// `kiosk::TABS` the config has on and only those in the menu, starting on
+/// the Pets tab.
+fn start_kiosk(app_state: &mut AppState, config: &config::Config) {
+    let mut tabs: Vec<MenuItem> = app_state
+        .tabs
+        .iter()
+        .copied()
+        .filter(|tab| kiosk::TABS.contains(tab))
+        .collect();
+    if tabs.is_empty() {
+        tabs.push(MenuItem::Pets);
+    }
+    if config.kiosk_unlock.value.is_empty() {
+        log::warn!("kiosk mode without kiosk_unlock, it can't be left");
+    }
+    app_state.kiosk = Some(Kiosk::new(
+        &config.kiosk_unlock.value,
+        config.kiosk_reset.value,
+        app_state.write_options.read_only,
+        std::mem::replace(&mut app_state.tabs, tabs),
+        Instant::now(),
+    ));
+    app_state.write_options.read_only = true;
+    app_state.menu = menu::entries(&app_state.tabs)
+        .into_iter()
+        .filter(|entry| entry.tab.is_some())
+        .collect();
+    app_state.active_menu_item = app_state.tabs[0];
+}
+
+/// After the `kiosk_unlock` characters: back to the tabs, menu and writing
+/// the session had without `--kiosk`.
+fn leave_kiosk(app_state: &mut AppState) {
+    let Some(kiosk) = app_state.kiosk.take() else {
+        return;
+    };
+    app_state.write_options.read_only = kiosk.read_only;
+    app_state.tabs = kiosk.tabs;
+    app_state.menu = menu::entries(&app_state.tabs);
+    if app_state.write_options.read_only {
+        app_state
+            .menu
+            .retain(|entry| !keys::EDITS.contains(&entry.hotkey));
+    }
+    log::info!("left kiosk mode");
+    app_state.report(StatusLevel::Info, "left kiosk mode".to_owned());
+}
+
+/// Puts a kiosk nobody used for `kiosk_reset` minutes back as it started:
+/// the Pets tab with every pet shown, the first one selected and nothing
+/// open over it. Returns whether it did.
+fn reset_idle_kiosk(app_state: &mut AppState) -> bool {
+    if !app_state
+        .kiosk
+        .as_mut()
+        .is_some_and(|kiosk| kiosk.tick(Instant::now()))
+    {
+        return false;
+    }
+    log::info!("resetting the idle kiosk");
+    app_state.prompt = None;
+    app_state.quick_open = None;
+    app_state.pending_count = None;
+    app_state.chord.cancel();
+    app_state.view = ViewParams::default();
+    app_state.active_view = None;
+    app_state.selected_group = None;
+    app_state.folded_groups.clear();
+    app_state.jumps = JumpList::default();
+    app_state.active_menu_item = app_state.tabs[0];
+    app_state.pet_list_state.select(Some(0));
+    app_state.selected_pet_id = None;
+    app_state.stats_table_state.select(Some(0));
+    app_state.notes_scroll = 0;
+    app_state.records_scroll = 0;
+    app_state.detail_scroll = 0;
+    app_state.status_line.take();
+    true
+}
+
+fn switch_workspace(app_state: &mut AppState, workspace: Workspace) {
+    let refusal = if app_state.dry_run.is_some() {
+        Some("a dry run can't switch workspaces")
+    } else if app_state.session_lock.is_some() {
+        Some("the DB is locked for this session with --exclusive, not switching workspaces")
+    } else if app_state.db_writer.is_busy() {
+        Some("still saving, switch workspaces once that's done")
+    } else {
+        None
+    };
+    if let Some(refusal) = refusal {
+        app_state.report(StatusLevel::Error, refusal.to_owned());
+        return;
+    }
+    let previous = workspace_db();
+    if same_file(&workspace.path, Path::new(&previous)) {
+        app_state
+            .status_line
+            .info(format!("already in workspace {}", workspace.name));
+        return;
+    }
+    set_workspace_db(&workspace.path);
+    if !workspace.path.exists() {
+        let created = match workspace.path.parent() {
+            _ if app_state.write_options.read_only => Err(Error::ReadOnly),
+            Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir)
+                .map_err(Error::from)
+                .and_then(|()| write_db(&mut Database::default(), app_state.write_options)),
+            _ => write_db(&mut Database::default(), app_state.write_options),
+        };
+        if let Err(err) = created {
+            set_workspace_db(Path::new(&previous));
+            app_state.report(
+                StatusLevel::Error,
+                format!("could not create {}: {}", workspace.path.display(), err),
+            );
+            return;
+        }
+        log::info!("created {}", workspace.path.display());
+    }
+    if let Err(err) = migrate_db(app_state.write_options) {
+        log::error!("migrating {} failed: {}", workspace.path.display(), err);
+    }
+    app_state.workspace = Some(workspace.name.clone());
+    app_state.session_start_pets = read_db().unwrap_or_default();
+    app_state.session_start_writes = metrics::snapshot().db_writes;
+    app_state.db_path = resolved_db_path();
+    app_state.db_watch = DbWatch::new(db_path());
+    app_state.marks = Marks::default();
+    app_state.cut_pet = None;
+    app_state.selected_pet_id = None;
+    app_state.saved_views = SavedViews::default();
+    app_state.active_view = None;
+    if let Some(state) = ui_state::load(&ui_state::state_path(Path::new(&workspace_db()))) {
+        load_saved_views(app_state, &state);
+    }
+    app_state.pet_list_state.select(Some(0));
+    for tab in [MenuItem::Owners, MenuItem::Duplicates, MenuItem::Trash] {
+        app_state.tab_views.reset(tab);
+    }
+    app_state.reminder_list_state.select(Some(0));
+    app_state.stats_table_state.select(Some(0));
+    check_integrity(app_state);
+    app_state.report(
+        StatusLevel::Info,
+        format!(
+            "switched to workspace {} ({})",
+            workspace.name, app_state.db_path
+        ),
+    );
+}
+
+fn reload_after_external_change(app_state: &mut AppState) {
+    app_state.db_watch.mark_seen();
+    let pets = read_db().unwrap_or_default();
+    follow_pet_selection(app_state, &app_state.visible_pets(&pets));
+    app_state.report(StatusLevel::Info, "reloaded: db changed on disk".to_owned());
+}
+
+/// Moves the Pets selection to the row of the pet it is on among the
+/// `visible` pets, which may have changed since it was made. Nothing is
+/// selected in an empty list.
+fn follow_pet_selection(app_state: &mut AppState, visible: &[&Pet]) {
+    let rows = app_state.pet_rows(visible);
+    if let Some(category) = &app_state.selected_group {
+        let still_folded = matches!(
+            grouping::row_of_header(&rows, category).map(|row| &rows[row]),
+            Some(ListRow::Header { folded: true, .. })
+        );
+        if still_folded {
+            return;
+        }
+        app_state.selected_group = None;
+    }
+    let ids: Vec<usize> = visible.iter().map(|pet| pet.id).collect();
+    let row = pet_selection::follow(
+        &ids,
+        app_state.selected_pet_id,
+        app_state.pet_list_state.selected(),
+    );
+    app_state.pet_list_state.select(row);
+    app_state.selected_pet_id = row.map(|row| ids[row]);
+    // A pet in a folded group is only shown by its header.
+    let folded = row.is_some_and(|row| app_state.folded_groups.contains(&visible[row].category));
+    if app_state.grouped && folded {
+        let row = app_state.selected_row(visible, &rows);
+        select_row(app_state, visible, &rows, row);
+    }
+}
+
+/// Makes the pet on the selected row the one the selection follows, after
+/// the user moved it.
+fn remember_pet_selection(app_state: &mut AppState, visible: &[&Pet]) {
+    app_state.selected_pet_id = app_state.selected_pet(visible).map(|pet| pet.id);
+}
+
+/// The UI draws on stdout, or on the terminal itself if stdout is redirected.
+type UiBackend = CrosstermBackend<tty::Output>;
+
+fn create_terminal(mut output: tty::Output) -> Result<Terminal<UiBackend>, AppError> {
+    execute!(output, EnterAlternateScreen)
+        .map_err(|err| AppError::TerminalInit(crossterm_io(err)))?;
+    let backend = CrosstermBackend::new(output);
+    let mut terminal = Terminal::new(backend).map_err(AppError::TerminalInit)?;
+    terminal.hide_cursor().map_err(AppError::TerminalInit)?;
+    terminal.clear().map_err(AppError::TerminalInit)?;
+    Ok(terminal)
+}
+
+fn restore_terminal(terminal: &mut Terminal<UiBackend>) -> Result<(), AppError> {
+    terminal::disable_raw_mode().map_err(AppError::terminal)?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen).map_err(AppError::terminal)?;
+    terminal.show_cursor().map_err(AppError::Terminal)?;
+    Ok(())
+}
+
+/// crossterm's errors are nearly always I/O errors, which say more than its
+/// own message for them.
+fn crossterm_io(err: crossterm::ErrorKind) -> io::Error {
+    match err {
+        crossterm::ErrorKind::IoError(err) => err,
+        err => io::Error::other(err.to_string()),
+    }
+}
+
+/// Puts the terminal `output` draws on back before a panic is printed.
+fn install_panic_hook(output: &tty::Output) {
+    let output = output.reopen().ok().map(Mutex::new);
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        log::error!("{}", info);
+        let _ = terminal::disable_raw_mode();
+        if let Some(Ok(mut output)) = output.as_ref().map(Mutex::lock) {
+            let _ = execute!(&mut *output, LeaveAlternateScreen, cursor::Show);
+        }
+        default_hook(info);
+    }));
+}
+
+/// Draws and handles events until the user quits, then waits for the writes
+/// still queued. DB writes run on the blocking pool, so the UI keeps drawing
+/// while they are in flight. The terminal's events are read from what
+/// `open_input` returns.
+async fn run_event_loop<B: Backend, S>(
+    terminal: &mut Terminal<B>,
+    app_state: &mut AppState,
+    mut background: UnboundedReceiver<AppEvent>,
+    tick_rate: Duration,
+    show_frame_time: bool,
+    mut open_input: impl FnMut() -> S,
+) -> Result<(), AppError>
+where
+    S: Stream<Item = Result<CEvent, crossterm::ErrorKind>> + Unpin,
+{
+    let mut input = open_input();
+    let mut input_closed = false;
+    let mut input_reopened = false;
+    let mut gave_up = false;
+    let mut tick = tokio::time::interval(tick_rate);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        // The terminal's events never end while it is open, so when they do
+        // something went wrong reading them: they are read afresh once
+        // before giving up.
+        if input_closed {
+            if input_reopened {
+                gave_up = true;
+                break;
+            }
+            input = open_input();
+            input_reopened = true;
+            input_closed = false;
+            app_state.report(
+                StatusLevel::Error,
+                "terminal input stopped and was reopened".to_owned(),
+            );
+        }
+        if app_state.dirty {
+            // Time spent in "draw" outside of "render" is the backend flush.
+            profile_span!("draw");
+            let started = Instant::now();
+            terminal
+                .draw(|rect| {
+                    draw(rect, app_state);
+                    let area = rect.size();
+                    rect.render_widget(capabilities::Fallback(app_state.capabilities), area);
+                })
+                .map_err(AppError::Terminal)?;
+            app_state.last_frame_time = started.elapsed();
+            if show_frame_time {
+                app_state.frame_time = Some(app_state.last_frame_time);
+            }
+            app_state.draw_rate.record(Instant::now());
+            app_state.dirty = false;
+        }
+
+        let event = tokio::select! {
+            event = input.next() => match event {
+                Some(event) => input_event(event),
+                None => {
+                    input_closed = true;
+                    continue;
+                }
+            },
+            _ = tick.tick() => AppEvent::Tick,
+            Some(event) = background.recv() => event,
+            Some(progress) = app_state.db_writer.progress_rx.recv() => AppEvent::Progress(progress),
+            Some(outcome) = app_state.db_writer.hook_rx.recv() => AppEvent::Hook(outcome),
+            Some(done) = app_state.db_writer.done_rx.recv() => {
+                finish_write(done, app_state);
+                app_state.dirty = true;
+                continue;
+            }
+        };
+        // Everything that is already waiting is handled before the next
+        // frame, so keys held down don't queue up behind one draw each.
+        let mut batch = VecDeque::from(vec![event]);
+        loop {
+            match input.next().now_or_never() {
+                Some(Some(event)) => batch.push_back(input_event(event)),
+                Some(None) => {
+                    input_closed = true;
+                    break;
+                }
+                None => match background.try_recv() {
+                    Ok(event) => batch.push_back(event),
+                    Err(_) => break,
+                },
+            }
+        }
+        app_state.events_waiting = batch.len();
+        if handle_batch(batch, terminal, app_state)? == ResponseToUserInput::Stop {
+            break;
+        }
+    }
+    finish_writes(app_state).await;
+    if gave_up {
+        return Err(AppError::EventChannelClosed);
+    }
+    Ok(())
+}
+
+/// Waits for the queued writes, so that quitting doesn't lose them.
+async fn finish_writes(app_state: &mut AppState) {
+    while app_state.db_writer.is_busy() {
+        let Some(done) = app_state.db_writer.done_rx.recv().await else {
+            break;
+        };
+        finish_write(done, app_state);
+    }
+}
+
+/// Handles events that were waiting together, in order. Runs of navigation
+/// keys are carried out as one move, and characters typed into a text input
+/// as one paste.
+fn handle_batch<B: Backend>(
+    mut batch: VecDeque<AppEvent>,
+    terminal: &mut Terminal<B>,
+    app_state: &mut AppState,
+) -> Result<ResponseToUserInput, AppError> {
+    while let Some(event) = batch.pop_front() {
+        let event = take_paste(event, &mut batch, app_state);
+        let tick = matches!(event, AppEvent::Tick);
+        let response = match navigation(&event, app_state) {
+            Some(action) => {
+                // Nothing a navigation action does changes what the keys
+                // after it mean, so they can be decoded up front.
+                let mut actions = vec![action];
+                while let Some(action) =
+                    batch.front().and_then(|event| navigation(event, app_state))
+                {
+                    batch.pop_front();
+                    actions.push(action);
+                }
+                apply_navigation(actions, app_state)?
+            }
+            None => handle_event(event, terminal, app_state)?,
+        };
+        if response == ResponseToUserInput::Stop {
+            return Ok(response);
+        }
+        update_draft(app_state, tick);
+    }
+    Ok(ResponseToUserInput::Continue)
+}
+
+/// Where a paste goes: the text input keys would go to, if nothing in front
+/// of it takes them first.
+#[derive(Copy, Clone, PartialEq)]
+enum PasteTarget {
+    Prompt,
+    Notes,
+}
+
+fn paste_target(app_state: &AppState) -> Option<PasteTarget> {
+    let taken_first = key_taken_first(app_state) || keys_watched(app_state);
+    if taken_first {
+        None
+    } else if app_state.prompt.is_some() {
+        Some(PasteTarget::Prompt)
+    } else if app_state.notes_editor.is_some() {
+        Some(PasteTarget::Notes)
+    } else {
+        None
+    }
+}
+
+/// `event` and the characters waiting right behind it as one
+/// [`AppEvent::Paste`], when it is a character going to a text input and
+/// there are more; Enter is part of a paste into the notes editor, where it
+/// only starts a line. Otherwise `event` itself.
+fn take_paste(event: AppEvent, batch: &mut VecDeque<AppEvent>, app_state: &AppState) -> AppEvent {
+    let Some(target) = paste_target(app_state) else {
+        return event;
+    };
+    let pasted = |event: &AppEvent| match event {
+        AppEvent::Key(key) if (key.modifiers - KeyModifiers::SHIFT).is_empty() => match key.code {
+            KeyCode::Char(c) => Some(c),
+            KeyCode::Enter if target == PasteTarget::Notes => Some('\n'),
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some(first) = pasted(&event) else {
+        return event;
+    };
+    if batch.front().is_none_or(|next| pasted(next).is_none()) {
+        return event;
+    }
+    let mut text = String::from(first);
+    while let Some(c) = batch.front().and_then(pasted) {
+        batch.pop_front();
+        text.push(c);
+    }
+    AppEvent::Paste(text)
+}
+
+/// Inserts pasted `text` into the open prompt or notes editor in one go,
+/// without control characters and cut to the length a name or notes may
+/// have. Ignored when neither is open.
+fn paste(text: &str, app_state: &mut AppState) {
+    let cut = match paste_target(app_state) {
+        Some(PasteTarget::Prompt) => {
+            let prompt = app_state.prompt.as_mut().expect("prompt is open");
+            let max = match prompt.kind {
+                PromptKind::Copy { .. } | PromptKind::QuickAdd | PromptKind::Rename { .. } => {
+                    validation::MAX_NAME_LENGTH
+                }
+                _ => usize::MAX,
+            };
+            let room = max.saturating_sub(prompt.input.text().chars().count());
+            let (text, cut) = text::clean_paste(text, false, room);
+            prompt.error = None;
+            prompt.completer.reset();
+            prompt.input.insert(&text);
+            cut.then_some(max)
+        }
+        Some(PasteTarget::Notes) => {
+            let editor = app_state.notes_editor.as_mut().expect("editor is open");
+            let max = validation::MAX_NOTES_LENGTH;
+            let room = max.saturating_sub(editor.text.chars().count());
+            let (text, cut) = text::clean_paste(text, true, room);
+            editor.text.push_str(&text);
+            cut.then_some(max)
+        }
+        None => {
+            log::debug!("ignoring a paste with no text input open");
+            None
+        }
+    };
+    if let Some(max) = cut {
+        app_state.report(
+            StatusLevel::Error,
+            format!("the paste was cut to {} characters", max),
+        );
+    }
+}
+
+/// The navigation action `event` stands for, if it is a key that would go
+/// straight to `map_key`: nothing is open to take it first and neither a
+/// count, a chord nor a macro register is being typed.
+fn navigation(event: &AppEvent, app_state: &AppState) -> Option<Action> {
+    let AppEvent::Key(key) = event else {
+        return None;
+    };
+    let taken_first = key_taken_first(app_state)
+        || keys_watched(app_state)
+        || app_state.prompt.is_some()
+        || app_state.notes_editor.is_some()
+        || app_state.chord.pending().is_some()
+        || app_state.macros.is_pending()
+        || app_state.pending_count.is_some();
+    if taken_first {
+        return None;
+    }
+    let mode = InputMode {
+        tab: app_state.active_menu_item,
+        counting: false,
+        folding: false,
+    };
+    action::map_key(*key, &mode).filter(Action::is_navigation)
+}
+
+/// Carries out navigation actions that were waiting together, each run of
+/// the same one as a single move, as if its length had been typed as a
+/// count. A macro being recorded still gets every step.
+fn apply_navigation(
+    actions: Vec<Action>,
+    app_state: &mut AppState,
+) -> Result<ResponseToUserInput, AppError> {
+    app_state.dirty = true;
+    // The keys never reached `handle_key`, which tells the idle lock of them.
+    app_state.idle_lock.input(Instant::now());
+    for (action, times) in action::coalesce(actions) {
+        if times > 1 {
+            log::debug!("{:?} {} times at once", action, times);
+        }
+        for _ in 0..times {
+            app_state.macros.record(Step::Action(action.clone()));
+        }
+        app_state.pending_count = Some(times);
+        let response = apply_action(action, app_state)?;
+        if response != ResponseToUserInput::Continue {
+            return Ok(response);
+        }
+    }
+    Ok(ResponseToUserInput::Continue)
+}
+
+fn input_event(event: Result<CEvent, crossterm::ErrorKind>) -> AppEvent {
+    match event {
+        Ok(CEvent::Key(key)) => AppEvent::Key(key),
+        Ok(CEvent::Resize(width, height)) => AppEvent::Resize(width, height),
+        Ok(CEvent::Mouse(mouse)) => AppEvent::Mouse(mouse),
+        Err(err) => AppEvent::Error(err.to_string()),
+    }
+}
+
+/// Drops the status message and pending chord once they are old enough.
+/// Returns whether that, or time passing, changed what is on screen.
+fn expire_timed_state(app_state: &mut AppState) -> bool {
+    let now = Instant::now();
+    let before = (
+        app_state.status_line.message().is_some(),
+        app_state.chord.pending(),
+    );
+    app_state.status_line.expire(now);
+    app_state.chord.expire(now);
+    let relative_times_changed = app_state
+        .next_time_change
+        .is_some_and(|next| Utc::now() >= next);
+    relative_times_changed
+        || before
+            != (
+                app_state.status_line.message().is_some(),
+                app_state.chord.pending(),
+            )
+}
+
+/// Sends [`AppEvent::DbChanged`] whenever the DB file's modification time
+/// changes, checking every `interval`. It follows the DB to another
+/// workspace.
+fn watch_db(interval: Duration, tx: UnboundedSender<AppEvent>, shutdown: &AtomicBool) {
+    let mut watch = DbWatch::new(db_path());
+    while !shutdown.load(Ordering::Relaxed) {
+        thread::sleep(interval);
+        let path = db_path();
+        if watch.path() != Path::new(&path) {
+            // Switched workspaces.
+            watch = DbWatch::new(path);
+        }
+        if watch.changed() {
+            watch.mark_seen();
+            if tx.send(AppEvent::DbChanged).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// The times the active tab shows relative to now, like "5 minutes ago".
+fn shown_timestamps(app_state: &AppState, pets: &[Pet], visible: &[&Pet]) -> Vec<DateTime<Utc>> {
+    match app_state.active_menu_item {
+        MenuItem::Home => {
+            let mut created: Vec<_> = pets
+                .iter()
+                .filter(|pet| !pet.is_archived())
+                .map(|pet| pet.created_at)
+                .collect();
+            created.sort_by_key(|created_at| std::cmp::Reverse(*created_at));
+            created.truncate(HOME_RECENT_COUNT);
+            created
+        }
+        MenuItem::Pets => app_state
+            .selected_pet(visible)
+            .map(|pet| pet.created_at)
+            .into_iter()
+            .collect(),
+        MenuItem::Trash => pets.iter().filter_map(|pet| pet.archived_at).collect(),
+        MenuItem::Owners | MenuItem::Duplicates | MenuItem::Log | MenuItem::Stats => Vec::new(),
+    }
+}
+
+fn draw<B: Backend>(total_drawing_rect: &mut tui::Frame<B>, app_state: &mut AppState) {
+    profile_span!("render");
+    app_state.animations.begin_draw();
+    if app_state.idle_lock.is_locked() {
+        total_drawing_rect.render_widget(
+            create_idle_paragraph(app_state, total_drawing_rect.size().height),
+            total_drawing_rect.size(),
+        );
+        return;
+    }
+    let app_rects = {
+        profile_span!("layout");
+        create_app_rects(total_drawing_rect.size(), app_state.footer.is_some())
+    };
+    let Some(app_rects) = app_rects else {
+        draw_too_small(total_drawing_rect, app_state);
+        return;
+    };
+    if let Some(loading) = &app_state.loading {
+        app_state
+            .animations
+            .run(Animation::Spinner, "", Instant::now());
+        total_drawing_rect.render_widget(
+            create_loading_paragraph(loading, app_state),
+            total_drawing_rect.size(),
+        );
+        return;
+    }
+    let database = read_database().unwrap_or_default();
+    let pets = &database.pets;
+    let visible = app_state.visible_pets(pets);
+    follow_pet_selection(app_state, &visible);
+    let theme = &app_state.theme;
+    let labels = menu::labels(
+        &app_state.menu,
+        app_state.lang,
+        &menu_counts(app_state, pets, visible.len()),
+    );
+    render_menu(
+        total_drawing_rect,
+        app_rects.menu,
+        labels,
+        active_entry(app_state),
+        &app_state.title,
+        theme,
+    );
+    let now = Utc::now();
+    app_state.next_time_change = shown_timestamps(app_state, pets, &visible)
+        .into_iter()
+        .map(|timestamp| dates::next_change(timestamp, now))
+        .min();
+    render_selected_widget(
+        total_drawing_rect,
+        &app_rects,
+        &database,
+        &visible,
+        app_state,
+    );
+    match &app_state.db_writer.progress {
+        Some(progress) => total_drawing_rect.render_widget(
+            create_progress_gauge(progress, &app_state.theme),
+            app_rects.status,
+        ),
+        None => {
+            let (status_bar, scrolling) = create_status_bar(
+                visible.len(),
+                database.revision,
+                app_rects.status.width,
+                app_state,
+            );
+            total_drawing_rect.render_widget(status_bar, app_rects.status);
+            if let Some(text) = scrolling {
+                app_state
+                    .animations
+                    .run(Animation::Marquee, &text, Instant::now());
+            }
+        }
+    }
+    if let (Some(footer), Some(area)) = (&app_state.footer, app_rects.footer) {
+        total_drawing_rect.render_widget(
+            create_copyright_paragraph(footer, app_state.lang, &app_state.theme),
+            area,
+        );
+    }
+    if let Some(confirmation) = &app_state.pending_confirmation {
+        let area = centered_rect(50, 5, total_drawing_rect.size());
+        total_drawing_rect.render_widget(Clear, area);
+        total_drawing_rect.render_widget(
+            create_confirmation_paragraph(confirmation, app_state.lang, &app_state.theme),
+            area,
+        );
+    }
+    let inline = |prompt: &&Prompt| matches!(prompt.kind, PromptKind::Rename { .. });
+    if let Some(prompt) = app_state.prompt.as_ref().filter(|prompt| !inline(prompt)) {
+        let size = total_drawing_rect.size();
+        let error_lines = prompt_error_lines(prompt, (size.width / 2).saturating_sub(2));
+        let area = centered_rect(50, 3 + error_lines.len() as u16, size);
+        total_drawing_rect.render_widget(Clear, area);
+        total_drawing_rect.render_widget(
+            create_prompt_paragraph(prompt, error_lines, &app_state.theme),
+            area,
+        );
+    }
+    if let Some(palette) = &app_state.palette {
+        let area = centered_rect(50, 3, total_drawing_rect.size());
+        total_drawing_rect.render_widget(Clear, area);
+        total_drawing_rect.render_widget(
+            create_palette_paragraph(palette, app_state.lang, &app_state.theme),
+            area,
+        );
+    }
+    if let (true, Some(report)) = (app_state.integrity_open, &app_state.integrity) {
+        let lines = integrity_lines(report);
+        let area = centered_rect(80, lines.len() as u16 + 2, total_drawing_rect.size());
+        total_drawing_rect.render_widget(Clear, area);
+        total_drawing_rect.render_widget(
+            Paragraph::new(lines.join("\n"))
+                .style(app_state.theme.text())
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .style(app_state.theme.border())
+                        .title(strings::text(app_state.lang, Msg::Integrity))
+                        .border_type(BorderType::Plain),
+                ),
+            area,
+        );
+    }
+    if let Some(preview) = &app_state.restore_preview {
+        let area = centered_rect(90, 24, total_drawing_rect.size());
+        total_drawing_rect.render_widget(Clear, area);
+        render_restore_preview(
+            total_drawing_rect,
+            area,
+            preview,
+            app_state.lang,
+            &app_state.theme,
+        );
+    }
+    if let Some(summary) = &app_state.dry_run_summary {
+        let area = centered_rect(80, 20, total_drawing_rect.size());
+        total_drawing_rect.render_widget(Clear, area);
+        total_drawing_rect.render_widget(
+            create_dry_run_summary(summary, app_state.lang, &app_state.theme),
+            area,
+        );
+    }
+    if let Some(reconcile) = &app_state.reconcile {
+        let area = centered_rect(80, 20, total_drawing_rect.size());
+        total_drawing_rect.render_widget(Clear, area);
+        total_drawing_rect.render_widget(
+            create_reconcile(reconcile, app_state.lang, &app_state.theme),
+            area,
+        );
+    }
+    if let Some(review) = &app_state.merge_review {
+        let area = centered_rect(80, 20, total_drawing_rect.size());
+        total_drawing_rect.render_widget(Clear, area);
+        total_drawing_rect.render_widget(
+            create_merge_review(review, app_state.lang, &app_state.theme),
+            area,
+        );
+    }
+    if let Some(pager) = &mut app_state.message_pager {
+        let area = centered_rect(90, 24, total_drawing_rect.size());
+        let lines = message_lines(
+            &app_state.activity_log,
+            area.width.saturating_sub(2),
+            &app_state.theme,
+        );
+        pager.set_lines(lines.len());
+        total_drawing_rect.render_widget(Clear, area);
+        total_drawing_rect.render_widget(
+            Paragraph::new(lines)
+                .style(app_state.theme.text())
+                .scroll((pager.scroll(), 0))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .style(app_state.theme.border())
+                        .title(strings::text(app_state.lang, Msg::Messages))
+                        .border_type(BorderType::Plain),
+                ),
+            area,
+        );
+    }
+    if let Some(picker) = &app_state.workspace_picker {
+        let area = centered_rect(
+            70,
+            picker.entries().len() as u16 + 2,
+            total_drawing_rect.size(),
+        );
+        total_drawing_rect.render_widget(Clear, area);
+        let (list, mut list_state) =
+            create_workspace_picker(picker, app_state.lang, &app_state.theme);
+        total_drawing_rect.render_stateful_widget(list, area, &mut list_state);
+    }
+    if let Some(picker) = &app_state.view_picker {
+        let area = centered_rect(
+            70,
+            app_state.saved_views.views.len() as u16 + 2,
+            total_drawing_rect.size(),
+        );
+        total_drawing_rect.render_widget(Clear, area);
+        let (list, mut list_state) = create_view_picker(
+            picker,
+            &app_state.saved_views.views,
+            app_state.lang,
+            &app_state.theme,
+        );
+        total_drawing_rect.render_stateful_widget(list, area, &mut list_state);
+    }
+    if let Some(picker) = &app_state.yank_picker {
+        let area = centered_rect(
+            70,
+            app_state.yank_ring.entries().len() as u16 + 2,
+            total_drawing_rect.size(),
+        );
+        total_drawing_rect.render_widget(Clear, area);
+        let (list, mut list_state) = create_yank_picker(
+            picker,
+            &app_state.yank_ring,
+            app_state.lang,
+            &app_state.theme,
+        );
+        total_drawing_rect.render_stateful_widget(list, area, &mut list_state);
+    }
+    if let Some(chooser) = &app_state.column_chooser {
+        let area = centered_rect(
+            30,
+            chooser.columns().len() as u16 + 2,
+            total_drawing_rect.size(),
+        );
+        total_drawing_rect.render_widget(Clear, area);
+        let (list, mut list_state) =
+            create_column_chooser(chooser, app_state.lang, &app_state.theme);
+        total_drawing_rect.render_stateful_widget(list, area, &mut list_state);
+    }
+    if let Some(manager) = &app_state.category_manager {
+        let area = centered_rect(
+            40,
+            manager.categories().len().min(20) as u16 + 2,
+            total_drawing_rect.size(),
+        );
+        total_drawing_rect.render_widget(Clear, area);
+        let (list, mut list_state) =
+            create_category_manager(manager, app_state.lang, &app_state.theme);
+        total_drawing_rect.render_stateful_widget(list, area, &mut list_state);
+    }
+    if let Some(quick_open) = &app_state.quick_open {
+        let area = centered_rect(60, 12, total_drawing_rect.size());
+        total_drawing_rect.render_widget(Clear, area);
+        render_quick_open(
+            total_drawing_rect,
+            area,
+            quick_open,
+            &quick_open_candidates(pets),
+            strings::text(app_state.lang, Msg::OpenPet),
+            &app_state.theme,
+        );
+    }
+    if let Some(tutorial) = &app_state.tutorial {
+        render_tutorial(total_drawing_rect, &app_rects, tutorial, &app_state.theme);
+    }
+    if app_state.show_diagnostics {
+        let lines = diagnostics_lines(app_state);
+        let size = total_drawing_rect.size();
+        let width = 44.min(size.width);
+        let area = Rect::new(
+            size.width - width,
+            0,
+            width,
+            (lines.len() as u16 + 2).min(size.height),
+        );
+        total_drawing_rect.render_widget(Clear, area);
+        total_drawing_rect.render_widget(
+            Paragraph::new(lines.join("\n"))
+                .style(app_state.theme.text())
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .style(app_state.theme.border())
+                        .title("Diagnostics (F12 hides)")
+                        .border_type(BorderType::Plain),
+                ),
+            area,
+        );
+    }
+}
+
+/// The current step of `--tutorial` in a box at the bottom of the screen,
+/// with the part of the screen it is about outlined, or the summary once the
+/// tutorial is over.
+fn render_tutorial<B: Backend>(
+    frame: &mut tui::Frame<B>,
+    app_rects: &AppRects,
+    tutorial: &Tutorial,
+    theme: &Theme,
+) {
+    let block = |title: String| {
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme.border())
+            .title(title)
+            .border_type(BorderType::Plain)
+    };
+    let Some(step) = tutorial.current() else {
+        let lines = tutorial.summary();
+        let area = centered_rect(70, lines.len() as u16 + 2, frame.size());
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines.join("\n"))
+                .style(theme.text())
+                .block(block("Tutorial".to_owned())),
+            area,
+        );
+        return;
+    };
+    let target = match step.target {
+        tutorial::Target::Menu => app_rects.menu,
+        tutorial::Target::Main => app_rects.main_widget,
+        tutorial::Target::StatusBar => app_rects.status,
+    };
+    // Only the edges are restyled, so the titles and text stay readable.
+    let Rect {
+        x,
+        y,
+        width,
+        height,
+    } = target;
+    for edge in [
+        Rect::new(x, y, width, 1),
+        Rect::new(x, (y + height).saturating_sub(1), width, 1),
+        Rect::new(x, y, 1, height),
+        Rect::new((x + width).saturating_sub(1), y, 1, height),
+    ] {
+        frame.render_widget(Block::default().style(theme.emphasis()), edge);
+    }
+    let main = app_rects.main_widget;
+    let width = (main.width * 7 / 10).max(20).min(main.width);
+    let area = Rect::new(
+        main.x + (main.width - width) / 2,
+        (main.y + main.height).saturating_sub(4).max(main.y),
+        width,
+        4.min(main.height),
+    );
+    let (number, steps) = tutorial.progress();
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(format!("{}\nEsc ends the tutorial.", step.prompt))
+            .style(theme.emphasis())
+            .wrap(Wrap { trim: true })
+            .block(block(format!("Tutorial, step {} of {}", number, steps))),
+        area,
+    );
+}
+
+/// What F12 shows. The TUI reads the DB afresh for each action rather than
+/// keeping the pets, so the memory the pets take is that of the last read.
+fn diagnostics_lines(app_state: &AppState) -> Vec<String> {
+    let metrics = metrics::snapshot();
+    let took = |took: Option<Duration>| {
+        took.map_or_else(|| "-".to_owned(), |took| format!("{:.1?}", took))
+    };
+    let writes_queued =
+        app_state.db_writer.queue.len() + usize::from(app_state.db_writer.in_flight);
+    vec![
+        format!("frame        {:.1?}", app_state.last_frame_time),
+        format!(
+            "draws        {:.1}/s over the last minute",
+            app_state.draw_rate.per_minute(Instant::now()) as f64 / 60.0
+        ),
+        format!(
+            "DB reads     {}, last {}",
+            metrics.db_reads,
+            took(metrics.last_read)
+        ),
+        format!(
+            "DB writes    {}, last {} ({})",
+            metrics.db_writes,
+            took(metrics.last_write),
+            app_state.write_options.durability.name()
+        ),
+        format!(
+            "queued       {} events, {} writes",
+            app_state.events_waiting, writes_queued
+        ),
+        format!("pets read    {} ({} bytes)", metrics.pets, metrics.db_bytes),
+        format!("terminal     {}", app_state.capabilities.describe()),
+    ]
+}
+
+/// The pets a dry run added, removed and changed, as `pet-cli diff` lists
+/// them, and whether to save them.
+fn create_dry_run_summary<'a>(
+    summary: &dry_run::Summary,
+    lang: Lang,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let mut lines: Vec<Spans> = diff::lines(summary.diff())
+        .into_iter()
+        .map(|line| {
+            let style = match line.kind {
+                diff::LineKind::Added => theme.accent(),
+                diff::LineKind::Removed => theme.error(),
+                diff::LineKind::Changed => theme.label(),
+                diff::LineKind::Field => theme.text(),
+            };
+            Spans::from(Span::styled(line.text, style))
+        })
+        .collect();
+    if summary.owners_changed() {
+        lines.push(Spans::from(Span::styled("~ owners", theme.label())));
+    }
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .style(theme.text())
+        .scroll((summary.scroll(), 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(format!(
+                    "{}: {} (y saves, n discards, Esc goes back)",
+                    strings::text(lang, Msg::DryRunSummary),
+                    summary.diff().summary()
+                ))
+                .border_type(BorderType::Plain),
+        )
+}
+
+fn render_restore_preview<B: Backend>(
+    rect: &mut tui::Frame<B>,
+    area: Rect,
+    preview: &RestorePreview,
+    lang: Lang,
+    theme: &Theme,
+) {
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(34), Constraint::Min(0)].as_ref())
+        .split(area);
+    let items: Vec<_> = preview
+        .backups()
+        .map(|(backup, pet_count)| {
+            let pets = pet_count
+                .map(|count| format!("{} pets", count))
+                .unwrap_or_else(|| "unreadable".to_owned());
+            ListItem::new(format!("{}  {}", backup_time(backup), pets))
+        })
+        .collect();
+    let mut list_state = ListState::default();
+    list_state.select(Some(preview.selected()));
+    rect.render_stateful_widget(
+        List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .style(theme.border())
+                    .title(strings::text(lang, Msg::Backups))
+                    .border_type(BorderType::Plain),
+            )
+            .style(theme.text())
+            .highlight_style(theme.selection()),
+        panes[0],
+        &mut list_state,
+    );
+    let (title, lines) = match preview.diff() {
+        Ok(diff) => {
+            let lines: Vec<Spans> = diff::lines(diff)
+                .into_iter()
+                .map(|line| {
+                    let style = match line.kind {
+                        diff::LineKind::Added => theme.accent(),
+                        diff::LineKind::Removed => theme.error(),
+                        diff::LineKind::Changed => theme.label(),
+                        diff::LineKind::Field => theme.text(),
+                    };
+                    Spans::from(Span::styled(line.text, style))
+                })
+                .collect();
+            (
+                format!(
+                    "{}: {}",
+                    strings::text(lang, Msg::RestoreDiff),
+                    diff.summary()
+                ),
+                lines,
+            )
+        }
+        Err(err) => (
+            strings::text(lang, Msg::RestoreDiff).to_owned(),
+            vec![Spans::from(Span::styled(
+                format!("the backup can't be read: {}", err),
+                theme.error(),
+            ))],
+        ),
+    };
+    rect.render_widget(
+        Paragraph::new(lines)
+            .style(theme.text())
+            .scroll((preview.scroll(), 0))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .style(theme.border())
+                    .title(title)
+                    .border_type(BorderType::Plain),
+            ),
+        panes[1],
+    );
+}
+
+/// What the dry run and the DB on disk each changed, and the keys to keep one
+/// or merge them; once merging, the conflict being decided as in
+/// `create_merge_review`.
+fn create_reconcile<'a>(reconcile: &Reconcile, lang: Lang, theme: &Theme) -> Paragraph<'a> {
+    let plan = reconcile.plan();
+    let Some(conflict) = reconcile.conflict() else {
+        let mut lines = vec![
+            Spans::from(Span::styled(
+                format!("mine:   {}", plan.mine.summary()),
+                theme.label(),
+            )),
+            Spans::from(Span::styled(
+                format!("theirs: {}", plan.theirs.summary()),
+                theme.label(),
+            )),
+        ];
+        lines.extend(plan.conflicts.iter().map(|conflict| {
+            let pet = conflict.pet();
+            Spans::from(Span::styled(
+                format!(
+                    "! #{} {} ({}): {}",
+                    pet.id,
+                    pet.name,
+                    pet.category,
+                    conflict_kind(conflict.kind)
+                ),
+                theme.error(),
+            ))
+        }));
+        if plan.owner_conflicts > 0 {
+            lines.push(Spans::from(Span::styled(
+                format!(
+                    "! {} owners changed on both sides; merging keeps theirs",
+                    plan.owner_conflicts
+                ),
+                theme.error(),
+            )));
+        }
+        lines.push(Spans::default());
+        lines.push(Spans::from(Span::styled(
+            "m keep mine, t keep theirs, a merge (conflicts are asked about), Esc back",
+            theme.accent(),
+        )));
+        return Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(format!(
+                    "{}: {} conflicting",
+                    strings::text(lang, Msg::ChangedOnDisk),
+                    plan.conflicts.len()
+                ))
+                .border_type(BorderType::Plain),
+        );
+    };
+    let pet = conflict.pet();
+    let mut lines = vec![
+        Spans::from(Span::styled(
+            format!("#{} {} ({})", pet.id, pet.name, pet.category),
+            theme.label(),
+        )),
+        Spans::from(Span::styled(
+            format!("    {}", conflict_kind(conflict.kind)),
+            theme.text(),
+        )),
+    ];
+    lines.extend(conflict.differences().into_iter().map(|change| {
+        Spans::from(Span::styled(
+            format!("    {}: {} -> {}", change.field, change.old, change.new),
+            theme.text(),
+        ))
+    }));
+    lines.push(Spans::default());
+    lines.push(Spans::from(Span::styled(
+        "m keep mine, t keep theirs, b keep both (M, T, B for the rest too), k back, Esc cancel",
+        theme.accent(),
+    )));
+    Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme.border())
+            .title(format!(
+                "{} {}/{}",
+                strings::text(lang, Msg::MergeConflict),
+                reconcile.current().unwrap_or(0) + 1,
+                plan.conflicts.len()
+            ))
+            .border_type(BorderType::Plain),
+    )
+}
+
+fn conflict_kind(kind: three_way::ConflictKind) -> &'static str {
+    match kind {
+        three_way::ConflictKind::Edited => "edited on both sides",
+        three_way::ConflictKind::DeletedByMe => "deleted here, edited on disk",
+        three_way::ConflictKind::DeletedByThem => "edited here, deleted on disk",
+        three_way::ConflictKind::AddedByBoth => "added on both sides under the same id",
+    }
+}
+
+/// The conflict being decided: our pet, the fields theirs differs in and the
+/// keys to answer with.
+fn create_merge_review<'a>(review: &MergeReview, lang: Lang, theme: &Theme) -> Paragraph<'a> {
+    let conflict = review.conflict();
+    let mine = &conflict.mine;
+    let mut lines = vec![Spans::from(Span::styled(
+        format!("#{} {} ({})", mine.id, mine.name, mine.category),
+        theme.label(),
+    ))];
+    lines.extend(conflict.differences().into_iter().map(|change| {
+        Spans::from(Span::styled(
+            format!("    {}: {} -> {}", change.field, change.old, change.new),
+            theme.text(),
+        ))
+    }));
+    lines.push(Spans::default());
+    lines.push(Spans::from(Span::styled(
+        "m keep mine, t keep theirs, b keep both (M, T, B for the rest too), k back, Esc cancel",
+        theme.accent(),
+    )));
+    Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme.border())
+            .title(format!(
+                "{} {}/{}",
+                strings::text(lang, Msg::MergeConflict),
+                review.current() + 1,
+                review.plan().conflicts.len()
+            ))
+            .border_type(BorderType::Plain),
+    )
+}
+
+/// A checkbox per column, in the order the list would show them.
+fn create_column_chooser<'a>(
+    chooser: &ColumnChooser,
+    lang: Lang,
+    theme: &Theme,
+) -> (List<'a>, ListState) {
+    let items: Vec<_> = chooser
+        .columns()
+        .iter()
+        .map(|(column, shown)| {
+            let check = if *shown { "[x]" } else { "[ ]" };
+            ListItem::new(format!("{} {}", check, strings::text(lang, column.label())))
+        })
+        .collect();
+    let mut list_state = ListState::default();
+    list_state.select(Some(chooser.selected()));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::Columns))
+                .border_type(BorderType::Plain),
+        )
+        .style(theme.text())
+        .highlight_style(theme.selection());
+    (list, list_state)
+}
+
+/// A category per line with its pet count, the marked ones checked.
+fn create_category_manager<'a>(
+    manager: &CategoryManager,
+    lang: Lang,
+    theme: &Theme,
+) -> (List<'a>, ListState) {
+    let name_width = manager
+        .categories()
+        .iter()
+        .map(|(category, _)| text::width(category))
+        .max()
+        .unwrap_or(0);
+    let items: Vec<_> = manager
+        .categories()
+        .iter()
+        .enumerate()
+        .map(|(index, (category, count))| {
+            let check = if manager.is_marked(index) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            ListItem::new(Spans::from(vec![
+                Span::raw(format!("{} ", check)),
+                Span::styled(text::pad(category, name_width), theme.category(category)),
+                Span::raw(format!(" {}", count)),
+            ]))
+        })
+        .collect();
+    let mut list_state = ListState::default();
+    list_state.select(Some(manager.selected()));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::Categories))
+                .border_type(BorderType::Plain),
+        )
+        .style(theme.text())
+        .highlight_style(theme.selection());
+    (list, list_state)
+}
+
+/// A workspace per line with its pet count, the current one marked.
+fn create_workspace_picker<'a>(
+    picker: &WorkspacePicker,
+    lang: Lang,
+    theme: &Theme,
+) -> (List<'a>, ListState) {
+    let name_width = picker
+        .entries()
+        .iter()
+        .map(|entry| text::width(&entry.workspace.name))
+        .max()
+        .unwrap_or(0);
+    let items: Vec<_> = picker
+        .entries()
+        .iter()
+        .map(|entry| {
+            let marker = if entry.current { "* " } else { "  " };
+            let count = match &entry.pet_count {
+                Ok(count) => format!("{} pet{}", count, if *count == 1 { "" } else { "s" }),
+                Err(err) => err.clone(),
+            };
+            ListItem::new(format!(
+                "{}{}  {}  ({})",
+                marker,
+                text::pad(&entry.workspace.name, name_width),
+                entry.workspace.path.display(),
+                count
+            ))
+        })
+        .collect();
+    let mut list_state = ListState::default();
+    list_state.select(Some(picker.selected()));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::Workspaces))
+                .border_type(BorderType::Plain),
+        )
+        .style(theme.text())
+        .highlight_style(theme.selection());
+    (list, list_state)
+}
+
+fn create_view_picker<'a>(
+    picker: &Picker,
+    views: &[SavedView],
+    lang: Lang,
+    theme: &Theme,
+) -> (List<'a>, ListState) {
+    let name_width = views
+        .iter()
+        .map(|view| text::width(&view.name))
+        .max()
+        .unwrap_or(0);
+    let items: Vec<_> = views
+        .iter()
+        .map(|view| {
+            ListItem::new(format!(
+                "{}  {}",
+                text::pad(&view.name, name_width),
+                view.describe()
+            ))
+        })
+        .collect();
+    let mut list_state = ListState::default();
+    list_state.select(Some(picker.selected()));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::SavedViews))
+                .border_type(BorderType::Plain),
+        )
+        .style(theme.text())
+        .highlight_style(theme.selection());
+    (list, list_state)
+}
+
+fn create_yank_picker<'a>(
+    picker: &Picker,
+    ring: &YankRing,
+    lang: Lang,
+    theme: &Theme,
+) -> (List<'a>, ListState) {
+    let now = Utc::now();
+    let width = |field: fn(&Pet) -> &str| {
+        ring.entries()
+            .iter()
+            .map(|yanked| text::width(field(&yanked.pet)))
+            .max()
+            .unwrap_or(0)
+    };
+    let (name_width, category_width) = (width(|pet| &pet.name), width(|pet| &pet.category));
+    let items: Vec<_> = ring
+        .entries()
+        .iter()
+        .map(|yanked| {
+            ListItem::new(format!(
+                "{}  {}  {}",
+                text::pad(&yanked.pet.name, name_width),
+                text::pad(&yanked.pet.category, category_width),
+                dates::format_relative(yanked.at, now)
+            ))
+        })
+        .collect();
+    let mut list_state = ListState::default();
+    list_state.select(Some(picker.selected()));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::YankRing))
+                .border_type(BorderType::Plain),
+        )
+        .style(theme.text())
+        .highlight_style(theme.selection());
+    (list, list_state)
+}
+
+fn render_quick_open<B: Backend>(
+    rect: &mut tui::Frame<B>,
+    area: Rect,
+    quick_open: &QuickOpen,
+    pets: &[&Pet],
+    title: &str,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(theme.border())
+        .title(title.to_owned())
+        .border_type(BorderType::Plain);
+    let inner = block.inner(area);
+    rect.render_widget(block, area);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+        .split(inner);
+
+    let names: Vec<&str> = pets.iter().map(|pet| pet.name.as_str()).collect();
+    let items: Vec<_> = quick_open
+        .matches(&names)
+        .into_iter()
+        .map(|(index, _)| ListItem::new(format!("{} ({})", names[index], pets[index].category)))
+        .collect();
+    let mut list_state = ListState::default();
+    if !items.is_empty() {
+        list_state.select(Some(quick_open.selected()));
+    }
+    rect.render_widget(
+        Paragraph::new(format!("> {}_", quick_open.query())).style(theme.text()),
+        rows[0],
+    );
+    rect.render_stateful_widget(
+        List::new(items)
+            .style(theme.text())
+            .highlight_style(theme.selection()),
+        rows[1],
+        &mut list_state,
+    );
+}
+
+/// What the entries of a list tab are, in the order shown: owner or pet ids,
+/// or the positions of the Log's entries, which only ever grows at the end.
+fn tab_ids(tab: MenuItem, database: &Database, log: &ActivityLog) -> Vec<usize> {
+    match tab {
+        MenuItem::Owners => database.owners.iter().map(|owner| owner.id).collect(),
+        MenuItem::Duplicates => duplicate_rows(&database.pets)
+            .into_iter()
+            .map(|(_, pet)| pet.id)
+            .collect(),
+        MenuItem::Trash => archived_pets(&database.pets)
+            .into_iter()
+            .map(|pet| pet.id)
+            .collect(),
+        _ => (0..log.len()).collect(),
+    }
+}
+
+fn render_selected_widget<B: Backend>(
+    rect: &mut tui::Frame<B>,
+    app_rects: &AppRects,
+    database: &Database,
+    visible: &[&Pet],
+    app_state: &mut AppState,
+) {
+    let tab = app_state.active_menu_item;
+    if tab_views::TABS.contains(&tab) {
+        let ids = tab_ids(tab, database, &app_state.activity_log);
+        app_state.tab_views.view(tab).reconcile(&ids);
+    }
+    match app_state.active_menu_item {
+        MenuItem::Home => render_home(
+            rect,
+            app_rects.main_widget,
+            &database.pets,
+            &mut app_state.reminder_list_state,
+            &app_state.quick_actions,
+            app_state.lang,
+            &app_state.theme,
+        ),
+        MenuItem::Pets => {
+            let pet_rects = {
+                profile_span!("layout");
+                create_pet_rects(
+                    &app_rects.main_widget,
+                    app_state.list_width,
+                    app_state.kiosk.is_none(),
+                )
+            };
+            let selected = app_state.pet_list_state.selected();
+            let marked = app_state.marks.effective(visible, selected.unwrap_or(0));
+            let rows = app_state.pet_rows(visible);
+            let selected = app_state.selected_row(visible, &rows);
+            let window = app_state.pet_viewport.window(
+                selected,
+                rows.len(),
+                pet_rects.names.height.saturating_sub(2).into(),
+            );
+            let (left, right) = create_pet_widgets(
+                visible,
+                &rows[window.clone()],
+                pet_rects.names.width.saturating_sub(2).into(),
+                &database.owners,
+                &marked,
+                app_state,
+            );
+            // The list only holds the rows in `window`, so its selection is
+            // relative to the window.
+            let mut window_state = ListState::default();
+            window_state.select(selected.map(|selected| selected - window.start));
+            rect.render_stateful_widget(left, pet_rects.names, &mut window_state);
+            #[cfg(feature = "image-preview")]
+            let details = render_photo(
+                rect,
+                pet_rects.details,
+                app_state
+                    .selected_pet(visible)
+                    .and_then(|pet| pet.photo_path.as_deref()),
+                app_state,
+            );
+            #[cfg(not(feature = "image-preview"))]
+            let details = pet_rects.details;
+            rect.render_widget(right, details);
+            let records = app_state
+                .selected_pet(visible)
+                .map(|pet| pet.records.as_slice())
+                .unwrap_or_default();
+            if app_state.kiosk.is_none() {
+                rect.render_widget(
+                    create_records_table(
+                        records,
+                        app_state.records_scroll,
+                        app_state.lang,
+                        &app_state.theme,
+                    ),
+                    pet_rects.records,
+                );
+            }
+            let notes = match &app_state.notes_editor {
+                Some(editor) => create_notes_paragraph(
+                    Some(&editor.text),
+                    true,
+                    0,
+                    app_state.lang,
+                    &app_state.theme,
+                ),
+                None => {
+                    let selected_pet = app_state.selected_pet(visible);
+                    create_notes_paragraph(
+                        selected_pet.and_then(|pet| pet.notes.as_deref()),
+                        false,
+                        app_state.notes_scroll,
+                        app_state.lang,
+                        &app_state.theme,
+                    )
+                }
+            };
+            rect.render_widget(notes, pet_rects.notes);
+            let weights = app_state
+                .selected_pet(visible)
+                .map(|pet| pet.weights.as_slice())
+                .unwrap_or_default();
+            if app_state.kiosk.is_none() {
+                render_weight_chart(
+                    rect,
+                    pet_rects.weights,
+                    weights,
+                    app_state.lang,
+                    &app_state.theme,
+                    app_state.capabilities.braille,
+                );
+            }
+        }
+        MenuItem::Owners => rect.render_stateful_widget(
+            create_owner_list(database, app_state.lang, &app_state.theme),
+            app_rects.main_widget,
+            app_state.tab_views.list(MenuItem::Owners),
+        ),
+        MenuItem::Duplicates => rect.render_stateful_widget(
+            create_duplicate_list(&database.pets, app_state.lang, &app_state.theme),
+            app_rects.main_widget,
+            app_state.tab_views.list(MenuItem::Duplicates),
+        ),
+        MenuItem::Trash => rect.render_stateful_widget(
+            create_trash_list(&database.pets, app_state.lang, &app_state.theme),
+            app_rects.main_widget,
+            app_state.tab_views.list(MenuItem::Trash),
+        ),
+        MenuItem::Log => rect.render_stateful_widget(
+            create_log_list(&app_state.activity_log, app_state.lang, &app_state.theme),
+            app_rects.main_widget,
+            app_state.tab_views.list(MenuItem::Log),
+        ),
+        MenuItem::Stats => {
+            let rows = stats::category_rows(&database.pets, app_state.stats_sort);
+            let selected = app_state.stats_table_state.selected().unwrap_or(0);
+            app_state
+                .stats_table_state
+                .select(Some(selected.min(rows.len().saturating_sub(1))));
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Min(5),
+                        Constraint::Length(10),
+                        Constraint::Length(10),
+                    ]
+                    .as_ref(),
+                )
+                .split(app_rects.main_widget);
+            let bottom = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+                .split(chunks[1]);
+            rect.render_stateful_widget(
+                create_stats_table(
+                    &rows,
+                    app_state.stats_sort,
+                    app_state.lang,
+                    &app_state.theme,
+                ),
+                chunks[0],
+                &mut app_state.stats_table_state,
+            );
+            let category = app_state
+                .stats_table_state
+                .selected()
+                .and_then(|selected| rows.get(selected))
+                .map_or("", |row| row.category.as_str());
+            rect.render_widget(
+                create_breed_list(&database.pets, category, app_state.lang, &app_state.theme),
+                bottom[0],
+            );
+            let today = Local::now().date_naive();
+            let histogram = stats::age_histogram(
+                &stats::pet_ages(&database.pets, today),
+                app_state.age_buckets,
+            );
+            render_age_histogram(
+                rect,
+                bottom[1],
+                &histogram,
+                app_state.lang,
+                &app_state.theme,
+            );
+            let months =
+                stats::added_per_month(&database.pets, Utc::now(), app_state.growth_months);
+            render_growth_chart(
+                rect,
+                chunks[2],
+                &months,
+                app_state.growth_total,
+                app_state.lang,
+                &app_state.theme,
+            );
+        }
+    }
+}
+
+/// A row per category with its pets by adoption status.
+fn create_stats_table<'a>(
+    rows: &[stats::CategoryRow],
+    sort: StatsSort,
+    lang: Lang,
+    theme: &Theme,
+) -> Table<'a> {
+    let rows: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let mut cells = vec![
+                Cell::from(Span::styled(
+                    row.category.clone(),
+                    theme.category(&row.category),
+                )),
+                Cell::from(Span::styled(row.total().to_string(), theme.emphasis())),
+            ];
+            cells.extend(
+                PetStatus::ALL
+                    .iter()
+                    .zip(row.by_status)
+                    .map(|(status, count)| {
+                        Cell::from(Span::styled(count.to_string(), theme.badge(*status)))
+                    }),
+            );
+            Row::new(cells)
+        })
+        .collect();
+    let bold = theme.label();
+    let mut header = vec![
+        Cell::from(Span::styled(strings::text(lang, Msg::ColumnCategory), bold)),
+        Cell::from(Span::styled(strings::text(lang, Msg::ColumnTotal), bold)),
+    ];
+    header.extend(
+        PetStatus::ALL
+            .iter()
+            .map(|status| Cell::from(Span::styled(status.name(), bold))),
+    );
+    let title = match sort {
+        StatsSort::Count => Msg::StatsByCount,
+        StatsSort::Name => Msg::StatsByName,
+    };
+    Table::new(rows)
+        .header(Row::new(header))
+        .style(theme.text())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, title))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selection())
+        .widths(&[
+            Constraint::Percentage(40),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+        ])
+}
+
+/// How many pets of the category selected in the stats table there are of
+/// each breed.
+fn create_breed_list<'a>(pets: &[Pet], category: &str, lang: Lang, theme: &Theme) -> List<'a> {
+    let items: Vec<_> = stats::breed_counts(pets, category)
+        .into_iter()
+        .map(|(breed, count)| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:>5}  ", count), theme.emphasis()),
+                Span::styled(breed, theme.text()),
+            ]))
+        })
+        .collect();
+    List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme.border())
+            .title(format!(
+                "{}: {}",
+                strings::text(lang, Msg::FieldBreed),
+                category
+            ))
+            .border_type(BorderType::Plain),
+    )
+}
+
+/// A bar per age bucket, labeled with its years, and beside it the counts
+/// as text, since terminals can't show them on hover.
+fn render_age_histogram<B: Backend>(
+    rect: &mut tui::Frame<B>,
+    area: Rect,
+    histogram: &stats::AgeHistogram,
+    lang: Lang,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(theme.border())
+        .title(format!(
+            "{} ({}y per bar, {} for more)",
+            strings::text(lang, Msg::AgeHistogram),
+            histogram.width_years,
+            keys::AGE_BUCKETS
+        ))
+        .border_type(BorderType::Plain);
+    let inner = block.inner(area);
+    rect.render_widget(block, area);
+    let mut legend: Vec<ListItem> = histogram
+        .buckets
+        .iter()
+        .map(|bucket| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:>6} ", bucket.label()), theme.label()),
+                Span::styled(bucket.count.to_string(), theme.text()),
+            ]))
+        })
+        .collect();
+    if histogram.unknown > 0 {
+        legend.push(ListItem::new(Spans::from(vec![
+            Span::styled(format!("{:>6} ", "?"), theme.label()),
+            Span::styled(histogram.unknown.to_string(), theme.text()),
+        ])));
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(10), Constraint::Length(12)].as_ref())
+        .split(inner);
+    let labels: Vec<String> = histogram
+        .buckets
+        .iter()
+        .map(|bucket| bucket.label())
+        .collect();
+    let data: Vec<(&str, u64)> = labels
+        .iter()
+        .zip(&histogram.buckets)
+        .map(|(label, bucket)| (label.as_str(), bucket.count as u64))
+        .collect();
+    let bar_width = labels
+        .iter()
+        .map(|label| text::width(label) as u16)
+        .max()
+        .unwrap_or(1);
+    rect.render_widget(
+        BarChart::default()
+            .data(&data)
+            .bar_width(bar_width)
+            .bar_gap(1)
+            .bar_style(theme.accent())
+            .value_style(theme.selection())
+            .label_style(theme.label()),
+        chunks[0],
+    );
+    rect.render_widget(List::new(legend), chunks[1]);
+}
+
+/// A bar per month of pets added, or of the running total. When the months
+/// don't fit the latest ones are shown.
+fn render_growth_chart<B: Backend>(
+    rect: &mut tui::Frame<B>,
+    area: Rect,
+    months: &[stats::MonthCount],
+    total: bool,
+    lang: Lang,
+    theme: &Theme,
+) {
+    let shown = if total {
+        Msg::GrowthTotal
+    } else {
+        Msg::GrowthAdded
+    };
+    let width = area.width.saturating_sub(2) as usize;
+    // Three columns fit a label; narrower bars cut it.
+    let per_month = (width / months.len().max(1)).clamp(1, 4);
+    let (bar_width, bar_gap) = match per_month {
+        1 => (1, 0),
+        per_month => (per_month as u16 - 1, 1),
+    };
+    let months = &months[months.len() - (width / per_month).min(months.len())..];
+    let labels: Vec<String> = months.iter().map(stats::MonthCount::label).collect();
+    let data: Vec<(&str, u64)> = labels
+        .iter()
+        .zip(months)
+        .map(|(label, month)| {
+            let value = if total { month.total } else { month.added };
+            (label.as_str(), value as u64)
+        })
+        .collect();
+    let title = format!(
+        "{}, {} months ({}/{} for more or fewer, {} switches)",
+        strings::text(lang, shown),
+        months.len(),
+        keys::WIDEN_GROWTH,
+        keys::NARROW_GROWTH,
+        keys::GROWTH_TOTAL,
+    );
+    rect.render_widget(
+        BarChart::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .style(theme.border())
+                    .title(title)
+                    .border_type(BorderType::Plain),
+            )
+            .data(&data)
+            .bar_width(bar_width)
+            .bar_gap(bar_gap)
+            .bar_style(theme.accent())
+            .value_style(theme.selection())
+            .label_style(theme.label()),
+        area,
+    );
+}
+
+fn create_owner_list<'a>(database: &Database, lang: Lang, theme: &Theme) -> List<'a> {
+    let items: Vec<_> = database
+        .owners
+        .iter()
+        .map(|owner| {
+            let pet_count = pet_count_of(&database.pets, owner.id);
+            ListItem::new(Spans::from(vec![
+                Span::styled(owner.name.clone(), theme.text()),
+                Span::styled(format!("  <{}>", owner.email), theme.accent()),
+                Span::styled(
+                    format!(
+                        "  {} {}",
+                        pet_count,
+                        if pet_count == 1 { "pet" } else { "pets" }
+                    ),
+                    theme.text(),
+                ),
+            ]))
+        })
+        .collect();
+    List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::Owners))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selection())
+}
+
+fn create_duplicate_list<'a>(pets: &[Pet], lang: Lang, theme: &Theme) -> List<'a> {
+    let items: Vec<_> = duplicate_rows(pets)
+        .into_iter()
+        .map(|(group, pet)| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:>3}  ", group + 1), theme.accent()),
+                Span::styled(format!("{:?}", pet.name), theme.text()),
+                Span::styled(
+                    format!(
+                        "  #{}, {}, {}",
+                        pet.id,
+                        pet.category,
+                        age::format(pet.age.months)
+                    ),
+                    theme.text(),
+                ),
+            ]))
+        })
+        .collect();
+    List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::DuplicatesHint))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selection())
+}
+
+fn create_trash_list<'a>(pets: &[Pet], lang: Lang, theme: &Theme) -> List<'a> {
+    let now = Utc::now();
+    let items: Vec<_> = archived_pets(pets)
+        .into_iter()
+        .map(|pet| {
+            let archived_at = pet.archived_at.expect("only archived pets are listed");
+            ListItem::new(Spans::from(vec![
+                Span::styled(pet.name.clone(), theme.text()),
+                Span::styled(
+                    format!("  archived {}", dates::format_relative(archived_at, now)),
+                    theme.accent(),
+                ),
+            ]))
+        })
+        .collect();
+    List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::TrashHint))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selection())
+}
+
+fn create_log_list<'a>(log: &ActivityLog, lang: Lang, theme: &Theme) -> List<'a> {
+    let items: Vec<_> = log
+        .entries()
+        .map(|entry| {
+            let style = match entry.level {
+                StatusLevel::Info => theme.text(),
+                StatusLevel::Error => theme.error(),
+            };
+            ListItem::new(Spans::from(vec![
+                Span::styled(entry.at.format("%H:%M:%S  ").to_string(), theme.accent()),
+                Span::styled(entry.text.clone(), style),
+            ]))
+        })
+        .collect();
+    List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::Activity))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selection())
+}
+
+/// The messages of the session, newest first, each under its time and
+/// wrapped to `width` columns.
+fn message_lines<'a>(log: &ActivityLog, width: u16, theme: &Theme) -> Vec<Spans<'a>> {
+    let mut lines = Vec::new();
+    for entry in log.entries() {
+        let (level, style) = match entry.level {
+            StatusLevel::Info => ("info", theme.text()),
+            StatusLevel::Error => ("error", theme.error()),
+        };
+        lines.push(Spans::from(Span::styled(
+            format!("{} {}", entry.at.format("%H:%M:%S"), level),
+            theme.accent(),
+        )));
+        lines.extend(
+            text::wrap(&entry.text, width as usize)
+                .into_iter()
+                .map(|line| Spans::from(Span::styled(line, style))),
+        );
+    }
+    lines
+}
+
+struct PetRects {
+    names: Rect,
+    details: Rect,
+    records: Rect,
+    weights: Rect,
+    notes: Rect,
+}
+
+/// Room for every field `pet_fields` can produce, plus the borders.
+const DETAIL_HEIGHT: u16 = 14;
+const WEIGHT_HEIGHT: u16 = 8;
+/// Below this the weight chart has no room for its axes and only the title
+/// is shown.
+const MIN_CHART_SIZE: (u16, u16) = (24, 6);
+
+/// The share of the Pets tab's width the list takes at first, in percent.
+const DEFAULT_LIST_WIDTH: u16 = 20;
+/// How far `<` and `>` move the split between the list and the detail panes.
+const LIST_WIDTH_STEP: i16 = 5;
+/// Below this many columns the list goes above the detail panes instead of
+/// beside them.
+const MIN_SIDE_BY_SIDE_WIDTH: u16 = 30;
+
+/// `list_width` moved by `delta` percent, kept between 10% and 90% so neither
+/// side disappears.
+fn resize_split(list_width: u16, delta: i16) -> u16 {
+    (list_width as i16 + delta).clamp(10, 90) as u16
+}
+
+fn stack_panes(width: u16) -> bool {
+    width < MIN_SIDE_BY_SIDE_WIDTH
+}
+
+/// Without `history` the records and weights get no room, for a kiosk.
+fn create_pet_rects(parent_rect: &Rect, list_width: u16, history: bool) -> PetRects {
+    let direction = if stack_panes(parent_rect.width) {
+        Direction::Vertical
+    } else {
+        Direction::Horizontal
+    };
+    let pet_rects = Layout::default()
+        .direction(direction)
+        .constraints(
+            [
+                Constraint::Percentage(list_width),
+                Constraint::Percentage(100 - list_width),
+            ]
+            .as_ref(),
+        )
+        .split(*parent_rect);
+    let detail_rects = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(DETAIL_HEIGHT),
+                Constraint::Percentage(if history { 20 } else { 0 }),
+                Constraint::Length(if history { WEIGHT_HEIGHT } else { 0 }),
+                Constraint::Min(3),
+            ]
+            .as_ref(),
+        )
+        .split(pet_rects[1]);
+    PetRects {
+        names: pet_rects[0],
+        details: detail_rects[0],
+        records: detail_rects[1],
+        weights: detail_rects[2],
+        notes: detail_rects[3],
+    }
+}
+
+/// The detail table keeps at least this many columns beside a photo.
+#[cfg(feature = "image-preview")]
+const MIN_DETAIL_WIDTH_BESIDE_PHOTO: u16 = 40;
+/// As many columns as the pane has pixel rows, so the pixels come out square.
+#[cfg(feature = "image-preview")]
+const PHOTO_WIDTH: u16 = 2 * (DETAIL_HEIGHT - 2) + 2;
+
+/// Shows the photo at `path` at the right of `area` if there is room and
+/// returns what is left for the detail table. A photo that can't be read is
+/// replaced by the reason.
+#[cfg(feature = "image-preview")]
+fn render_photo<B: Backend>(
+    rect: &mut tui::Frame<B>,
+    area: Rect,
+    path: Option<&Path>,
+    app_state: &mut AppState,
+) -> Rect {
+    let Some(path) = path else {
+        return area;
+    };
+    if area.width < MIN_DETAIL_WIDTH_BESIDE_PHOTO + PHOTO_WIDTH {
+        return area;
+    }
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(PHOTO_WIDTH)].as_ref())
+        .split(area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(app_state.theme.border())
+        .title(strings::text(app_state.lang, Msg::FieldPhoto))
+        .border_type(BorderType::Plain);
+    let inner = block.inner(columns[1]);
+    let paragraph = match app_state.photos.preview(path, inner.width, inner.height) {
+        Ok(cells) => Paragraph::new(photo::to_spans(
+            cells,
+            app_state.theme.is_colored() && app_state.capabilities.images,
+        )),
+        Err(err) => Paragraph::new(format!("{}: {}", path.display(), err))
+            .style(app_state.theme.error())
+            .wrap(Wrap { trim: true }),
+    };
+    rect.render_widget(paragraph.block(block), columns[1]);
+    columns[0]
+}
+
+/// The weight trend of a pet, or a hint to add weights while there are none.
+/// The axes are fitted to the weights, so any number of them can be shown.
+fn render_weight_chart<B: Backend>(
+    rect: &mut tui::Frame<B>,
+    area: Rect,
+    weights: &[WeightEntry],
+    lang: Lang,
+    theme: &Theme,
+    braille: bool,
+) {
+    let latest = weights.iter().max_by_key(|entry| entry.date);
+    let title = match latest {
+        Some(entry) => format!(
+            "{} ({})",
+            strings::text(lang, Msg::Weight),
+            weight::format(entry.grams)
+        ),
+        None => strings::text(lang, Msg::NoWeights).to_owned(),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(theme.border())
+        .title(title)
+        .border_type(BorderType::Plain);
+    let points = weight::points(weights);
+    let (Some(first), Some(last)) = (
+        weights.iter().map(|entry| entry.date).min(),
+        weights.iter().map(|entry| entry.date).max(),
+    ) else {
+        rect.render_widget(block, area);
+        return;
+    };
+    if area.width < MIN_CHART_SIZE.0 || area.height < MIN_CHART_SIZE.1 {
+        rect.render_widget(block, area);
+        return;
+    };
+    let x_bounds = weight::bounds(points.iter().map(|(x, _)| *x), 1.0);
+    let y_bounds = weight::bounds(points.iter().map(|(_, y)| *y), 0.1);
+    let dataset = Dataset::default()
+        // Without braille a dot per cell, which reads as a coarser line.
+        .marker(if braille {
+            symbols::Marker::Braille
+        } else {
+            symbols::Marker::Dot
+        })
+        .graph_type(GraphType::Line)
+        .style(theme.accent())
+        .data(&points);
+    let date_label = |date: NaiveDate| Span::raw(date.format("%Y-%m-%d").to_string());
+    let weight_label = |kg: f64| Span::raw(format!("{:.2}", kg.max(0.0)));
+    let chart = Chart::new(vec![dataset])
+        .block(block)
+        .style(theme.text())
+        .hidden_legend_constraints((Constraint::Length(0), Constraint::Length(0)))
+        .x_axis(
+            Axis::default()
+                .bounds(x_bounds)
+                .labels(vec![date_label(first), date_label(last)])
+                .style(theme.border()),
+        )
+        .y_axis(
+            Axis::default()
+                .title("kg")
+                .bounds(y_bounds)
+                .labels(vec![weight_label(y_bounds[0]), weight_label(y_bounds[1])])
+                .style(theme.border()),
+        );
+    rect.render_widget(chart, area);
+}
+
+/// The medical history of a pet, newest first, starting `scroll` rows down.
+fn create_records_table<'a>(
+    records: &[MedicalRecord],
+    scroll: usize,
+    lang: Lang,
+    theme: &Theme,
+) -> Table<'a> {
+    let mut records: Vec<_> = records.iter().collect();
+    records.sort_by_key(|record| std::cmp::Reverse(record.date));
+    let rows: Vec<_> = records
+        .into_iter()
+        .skip(scroll)
+        .map(|record| {
+            Row::new(vec![
+                Cell::from(Span::raw(record.date.format("%Y-%m-%d").to_string())),
+                Cell::from(Span::raw(record.kind.clone())),
+                Cell::from(Span::raw(record.note.clone())),
+            ])
+        })
+        .collect();
+    let title = if rows.is_empty() && scroll == 0 {
+        strings::text(lang, Msg::RecordsEmpty)
+    } else {
+        strings::text(lang, Msg::Records)
+    };
+    let bold = theme.label();
+    Table::new(rows)
+        .header(Row::new(vec![
+            Cell::from(Span::styled(strings::text(lang, Msg::ColumnDate), bold)),
+            Cell::from(Span::styled(strings::text(lang, Msg::ColumnKind), bold)),
+            Cell::from(Span::styled(strings::text(lang, Msg::ColumnNote), bold)),
+        ]))
+        .style(theme.text())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(title)
+                .border_type(BorderType::Plain),
+        )
+        .widths(&[
+            Constraint::Length(12),
+            Constraint::Percentage(25),
+            Constraint::Percentage(60),
+        ])
+}
+
+fn create_notes_paragraph<'a>(
+    notes: Option<&str>,
+    editing: bool,
+    scroll: u16,
+    lang: Lang,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let (text, title) = if editing {
+        (
+            format!("{}_", notes.unwrap_or_default()),
+            strings::text(lang, Msg::NotesEditing),
+        )
+    } else {
+        (
+            notes
+                .unwrap_or(strings::text(lang, Msg::NoNotes))
+                .to_owned(),
+            strings::text(lang, Msg::Notes),
+        )
+    };
+    Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .style(theme.text())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(title)
+                .border_type(BorderType::Plain),
+        )
+}
+
+/// The menu bar, with as much of each label as fits; see `menu::fit`.
+/// The index of the active tab's entry in the menu. A tab turned off in the
+/// config has none, though a pet can still be shown on Pets, say.
+fn active_entry(app_state: &AppState) -> Option<usize> {
+    app_state
+        .menu
+        .iter()
+        .position(|entry| entry.tab == Some(app_state.active_menu_item))
+}
+
+fn render_menu<B: Backend>(
+    rect: &mut tui::Frame<B>,
+    area: Rect,
+    labels: Vec<MenuLabel>,
+    active: Option<usize>,
+    title: &str,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .title(title.to_owned())
+        .borders(Borders::ALL)
+        .style(theme.border());
+    let line = match menu::fit(&labels, block.inner(area).width) {
+        MenuFit::Full => {
+            let tabs = create_tabs(create_menu(labels, theme), active, title, theme);
+            rect.render_widget(tabs, area);
+            return;
+        }
+        MenuFit::Hotkeys => {
+            let mut spans = Vec::new();
+            for (index, label) in labels.iter().enumerate() {
+                if index > 0 {
+                    spans.push(Span::raw("|"));
+                }
+                let style = if Some(index) == active {
+                    theme.emphasis()
+                } else {
+                    theme.hotkey()
+                };
+                spans.push(Span::styled(label.hotkey.to_string(), style));
+            }
+            Spans::from(spans)
+        }
+        MenuFit::Collapsed => {
+            let active = active
+                .and_then(|active| labels.get(active))
+                .map(MenuLabel::text)
+                .unwrap_or_default();
+            Spans::from(vec![
+                Span::raw("▾ "),
+                Span::styled(active, theme.emphasis()),
+            ])
+        }
+    };
+    rect.render_widget(Paragraph::new(line).style(theme.text()).block(block), area);
+}
+
+fn create_tabs<'a>(
+    menu: Vec<Spans<'a>>,
+    active: Option<usize>,
+    title: &str,
+    theme: &Theme,
+) -> Tabs<'a> {
+    // Past the end for no highlight.
+    let active = active.unwrap_or(menu.len());
+    Tabs::new(menu)
+        .select(active)
+        .block(
+            Block::default()
+                .title(title.to_owned())
+                .borders(Borders::ALL)
+                .style(theme.border()),
+        )
+        .style(theme.text())
+        .highlight_style(theme.emphasis())
+        .divider(Span::raw("|"))
+}
+
+struct AppRects {
+    menu: Rect,
+    main_widget: Rect,
+    status: Rect,
+    /// `None` when the footer is hidden.
+    footer: Option<Rect>,
+}
+
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 10;
+/// The menu bar's height, with its borders.
+const MENU_HEIGHT: u16 = 3;
+
+fn fits_minimum_size(area: Rect) -> bool {
+    area.width >= MIN_WIDTH && area.height >= MIN_HEIGHT
+}
+
+fn create_app_rects(total_drawing_rect: Rect, show_footer: bool) -> Option<AppRects> {
+    if !fits_minimum_size(total_drawing_rect) {
+        return None;
+    }
+    let mut constraints = vec![
+        Constraint::Length(MENU_HEIGHT),
+        Constraint::Min(2),
+        Constraint::Length(1),
+    ];
+    if show_footer {
+        constraints.push(Constraint::Length(3));
+    }
+    let areas = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints(constraints)
+        .split(total_drawing_rect);
+    Some(AppRects {
+        menu: areas[0],
+        main_widget: areas[1],
+        status: areas[2],
+        footer: areas.get(3).copied(),
+    })
+}
+
+/// Below the minimum size the menu is still drawn, without the margin and
+/// as short as it has to be, with the size needed under it. A terminal too
+/// low for the menu only gets the size needed.
+fn draw_too_small<B: Backend>(total_drawing_rect: &mut tui::Frame<B>, app_state: &AppState) {
+    let area = total_drawing_rect.size();
+    let paragraph = create_too_small_paragraph(&app_state.theme);
+    if area.height < MENU_HEIGHT + 1 {
+        total_drawing_rect.render_widget(paragraph, area);
+        return;
+    }
+    let areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(MENU_HEIGHT), Constraint::Min(1)])
+        .split(area);
+    let database = read_database().unwrap_or_default();
+    let visible = app_state.visible_pets(&database.pets);
+    let labels = menu::labels(
+        &app_state.menu,
+        app_state.lang,
+        &menu_counts(app_state, &database.pets, visible.len()),
+    );
+    render_menu(
+        total_drawing_rect,
+        areas[0],
+        labels,
+        active_entry(app_state),
+        &app_state.title,
+        &app_state.theme,
+    );
+    total_drawing_rect.render_widget(paragraph, areas[1]);
+}
+
+fn create_too_small_paragraph<'a>(theme: &Theme) -> Paragraph<'a> {
+    Paragraph::new(format!(
+        "terminal too small, need at least {}x{}",
+        MIN_WIDTH, MIN_HEIGHT
+    ))
+    .style(theme.error())
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true })
+}
+
+/// The menu titles with their hotkeys underlined. A translated title that
+/// lacks its hotkey gets it appended, as in "Haustiere (p)".
+fn menu_counts(app_state: &AppState, pets: &[Pet], shown_pets: usize) -> MenuCounts {
+    let editing = app_state.prompt.is_some() || app_state.notes_editor.is_some();
+    MenuCounts {
+        shown_pets,
+        trashed_pets: pets.iter().filter(|pet| pet.is_archived()).count(),
+        // Prompts and the notes editor take every key, so the tab they were
+        // opened on is still the active one.
+        unsaved: active_entry(app_state).filter(|_| editing),
+    }
+}
+
+fn create_menu<'a>(labels: Vec<MenuLabel>, theme: &Theme) -> Vec<Spans<'a>> {
+    labels
+        .into_iter()
+        .map(|label| {
+            let title = label.title;
+            let mut spans = match menu::hotkey_range(title, label.hotkey) {
+                Some((start, end)) => vec![
+                    Span::styled(&title[..start], theme.text()),
+                    Span::styled(&title[start..end], theme.hotkey()),
+                    Span::styled(&title[end..], theme.text()),
+                ],
+                None => vec![
+                    Span::styled(format!("{} (", title), theme.text()),
+                    Span::styled(label.hotkey.to_string(), theme.hotkey()),
+                    Span::styled(")", theme.text()),
+                ],
+            };
+            spans.push(Span::styled(label.suffix, theme.text()));
+            Spans::from(spans)
+        })
+        .collect()
+}
+
+/// Stands in for the status bar while a long write is running.
+fn create_progress_gauge<'a>(progress: &Progress, theme: &Theme) -> Gauge<'a> {
+    let label = format!(
+        "{} {}/{} (Esc cancels)",
+        progress.label, progress.done, progress.total
+    );
+    Gauge::default()
+        .gauge_style(theme.accent())
+        .ratio(progress.ratio())
+        .label(Span::styled(label, theme.emphasis()))
+}
+
+/// `revision` is the DB's, to count the unsaved changes of a dry run.
+/// What the app shows at the bottom. A message too long for the `width` left
+/// is cut off with a hint at the key that shows it in full.
+/// Also returns the status message if it scrolls, being too long.
+fn create_status_bar<'a>(
+    pet_count: usize,
+    revision: u64,
+    width: u16,
+    app_state: &AppState,
+) -> (Paragraph<'a>, Option<String>) {
+    let theme = &app_state.theme;
+    let mut spans = vec![
+        Span::styled(
+            format!(
+                " {}{}",
+                pet_count,
+                strings::text(app_state.lang, Msg::PetCount)
+            ),
+            theme.accent(),
+        ),
+        Span::raw(" | "),
+    ];
+    if let Some(workspace) = &app_state.workspace {
+        spans.push(Span::styled(format!("{} ", workspace), theme.label()));
+    }
+    spans.push(Span::raw(app_state.db_path.clone()));
+    if app_state.demo {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled("[demo]", theme.emphasis()));
+    }
+    if let Some(dry_run) = &app_state.dry_run {
+        let unsaved = dry_run.unsaved(revision);
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!(
+                "[dry-run, {} unsaved change{}]",
+                unsaved,
+                if unsaved == 1 { "" } else { "s" }
+            ),
+            theme.emphasis(),
+        ));
+    }
+    if app_state.view.category.is_active() {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::raw(format!(
+            "category: {}",
+            app_state.view.category.label()
+        )));
+    }
+    if let Some(status) = app_state.view.status {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::raw(format!("status: {}", status)));
+    }
+    if let Some(report) = app_state
+        .integrity
+        .as_ref()
+        .filter(|report| !report.findings.is_empty())
+    {
+        let style = if report.count(integrity::Severity::Error) > 0 {
+            theme.error()
+        } else {
+            theme.emphasis()
+        };
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled(
+            format!(
+                "{} DB problem{} ({})",
+                report.findings.len(),
+                if report.findings.len() == 1 { "" } else { "s" },
+                keys::INTEGRITY
+            ),
+            style,
+        ));
+    }
+    if let Some(register) = app_state.macros.recording() {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled(
+            format!("recording @{}", register),
+            theme.emphasis(),
+        ));
+    }
+    let active_view = app_state.active_view.as_ref().filter(|name| {
+        app_state
+            .saved_views
+            .views
+            .iter()
+            .any(|view| view.name == **name && view.is_shown_by(&app_state.view))
+    });
+    if let Some(name) = active_view {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled(format!("view: {}", name), theme.label()));
+    }
+    if let Some(query) = &app_state.view.query {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::raw(format!("search: {}", query)));
+    }
+    if let Some(sort) = app_state.view.sort {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::raw(format!("sort: {}", sort)));
+    }
+    if app_state.marks.in_visual_mode() {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled("VISUAL", theme.label()));
+    }
+    if app_state.write_options.read_only {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled("[read-only]", theme.emphasis()));
+    }
+    if app_state.db_writer.is_busy() {
+        spans.push(Span::raw(" | "));
+        match app_state.db_writer.queue.len() {
+            0 => spans.push(Span::styled("saving…", theme.accent())),
+            queued => spans.push(Span::styled(
+                format!("saving… ({} queued)", queued),
+                theme.accent(),
+            )),
+        }
+    }
+    let pending_keys = format!(
+        "{}{}",
+        app_state
+            .pending_count
+            .map_or_else(String::new, |count| count.to_string()),
+        app_state
+            .chord
+            .pending()
+            .map_or_else(String::new, |key| key.to_string())
+    );
+    if !pending_keys.is_empty() {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::raw(pending_keys));
+    }
+    if app_state.show_draw_rate {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::raw(format!(
+            "{} draws/min",
+            app_state.draw_rate.per_minute(Instant::now())
+        )));
+    }
+    if let Some(frame_time) = app_state.frame_time {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::raw(format!("frame {:.1?}", frame_time)));
+    }
+    let mut scrolling = None;
+    if let Some((text, level)) = app_state.status_line.message() {
+        let style = match level {
+            StatusLevel::Info => theme.text(),
+            StatusLevel::Error => theme.error(),
+        };
+        spans.push(Span::raw(" | "));
+        let room = (width as usize).saturating_sub(Spans::from(spans.clone()).width());
+        if text::width(text) <= room {
+            spans.push(Span::styled(text.to_owned(), style));
+        } else {
+            let hint = format!(" ({}: expand)", keys::MESSAGES);
+            let room = room.saturating_sub(text::width(&hint));
+            let shown = if app_state.animations.enabled() {
+                scrolling = Some(text.to_owned());
+                // Held still at first, to be read from the start.
+                let frame = app_state
+                    .animations
+                    .frame(Animation::Marquee, Instant::now());
+                let step = frame.saturating_sub(MARQUEE_PAUSE) as usize;
+                text::pad(&text::marquee(text, room, step), room)
+            } else {
+                text::truncate(text, room)
+            };
+            spans.push(Span::styled(shown, style));
+            spans.push(Span::styled(hint, theme.label()));
+        }
+    }
+    (
+        Paragraph::new(Spans::from(spans)).style(theme.text()),
+        scrolling,
+    )
+}
+
+/// Frames of the marquee before a long status message starts scrolling.
+const MARQUEE_PAUSE: u64 = 8;
+
+/// The text of `input` with the grapheme under the cursor shown reversed, or
+/// `_` when the cursor is at the end.
+fn input_spans<'a>(input: &TextInput, theme: &Theme) -> Vec<Span<'a>> {
+    let (before, after) = input.split_at_cursor();
+    let (under, rest) = after.split_at(text::next_boundary(after, 0).unwrap_or(0));
+    let under = match under {
+        "" => Span::raw("_"),
+        under => Span::styled(under.to_owned(), theme.cursor()),
+    };
+    vec![
+        Span::raw(before.to_owned()),
+        under,
+        Span::raw(rest.to_owned()),
+    ]
+}
+
+/// The prompt's error cut into lines of `width`, to go under the input.
+fn prompt_error_lines(prompt: &Prompt, width: u16) -> Vec<String> {
+    let Some(error) = &prompt.error else {
+        return Vec::new();
+    };
+    let chars: Vec<char> = error.chars().collect();
+    chars
+        .chunks(usize::from(width.max(1)))
+        .map(|line| line.iter().collect())
+        .collect()
+}
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// All that is shown while the idle lock is on, in the middle of the screen.
+fn create_idle_paragraph<'a>(app_state: &AppState, height: u16) -> Paragraph<'a> {
+    let theme = &app_state.theme;
+    let mut lines = vec![Spans::from(""); usize::from(height / 2).saturating_sub(1)];
+    lines.push(Spans::from(Span::styled("pet-CLI", theme.accent())));
+    lines.push(Spans::from(strings::text(app_state.lang, Msg::PressAnyKey)));
+    Paragraph::new(lines)
+        .style(theme.text())
+        .alignment(Alignment::Center)
+}
+
+/// Drawn until the first read of the DB arrives, with the status message
+/// below, e.g. why a key did nothing.
+fn create_loading_paragraph<'a>(loading: &Loading, app_state: &AppState) -> Paragraph<'a> {
+    let theme = &app_state.theme;
+    let frame = app_state
+        .animations
+        .frame(Animation::Spinner, Instant::now());
+    let frame = SPINNER[frame as usize % SPINNER.len()];
+    let mut lines = vec![
+        Spans::from(Span::styled(
+            match &loading.progress {
+                Some(progress) => format!(
+                    "{} loading {}… {:.0}%",
+                    frame,
+                    app_state.db_path,
+                    progress.ratio() * 100.0
+                ),
+                None => format!("{} loading {}…", frame, app_state.db_path),
+            },
+            theme.accent(),
+        )),
+        Spans::from(Span::raw(format!("{} quits", keys::QUIT))),
+    ];
+    if let Some((text, level)) = app_state.status_line.message() {
+        let style = match level {
+            StatusLevel::Info => theme.text(),
+            StatusLevel::Error => theme.error(),
+        };
+        lines.push(Spans::from(Span::styled(text.to_owned(), style)));
+    }
+    Paragraph::new(lines).style(theme.text()).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme.border())
+            .title(app_state.title.clone())
+            .border_type(BorderType::Plain),
+    )
+}
+
+fn create_prompt_paragraph<'a>(
+    prompt: &Prompt,
+    error_lines: Vec<String>,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let title = match prompt.completer.position() {
+        Some((shown, count)) => format!("[{}/{}] {}", shown, count, prompt.kind.label()),
+        None => prompt.kind.label().to_owned(),
+    };
+    let mut lines = vec![Spans::from(input_spans(&prompt.input, theme))];
+    lines.extend(
+        error_lines
+            .into_iter()
+            .map(|line| Spans::from(Span::styled(line, theme.error()))),
+    );
+    Paragraph::new(lines).style(theme.text()).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme.emphasis())
+            .title(title)
+            .border_type(BorderType::Plain),
+    )
+}
+
+/// The command being typed, titled with why the last one failed if it did.
+fn create_palette_paragraph<'a>(palette: &Palette, lang: Lang, theme: &Theme) -> Paragraph<'a> {
+    let title = match palette.error() {
+        Some(err) => Span::styled(err.to_owned(), theme.error()),
+        None => match palette.completion() {
+            Some((shown, count)) => Span::raw(format!(
+                "[{}/{}] {}",
+                shown,
+                count,
+                strings::text(lang, Msg::Command)
+            )),
+            None => Span::raw(strings::text(lang, Msg::Command)),
+        },
+    };
+    let mut spans = vec![Span::raw(":")];
+    spans.extend(input_spans(palette.input(), theme));
+    Paragraph::new(Spans::from(spans))
+        .style(theme.text())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.emphasis())
+                .title(title)
+                .border_type(BorderType::Plain),
+        )
+}
+
+fn create_confirmation_paragraph<'a>(
+    confirmation: &Confirmation,
+    lang: Lang,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    Paragraph::new(confirmation.prompt())
+        .style(theme.text())
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.emphasis())
+                .title(strings::text(lang, Msg::Confirm))
+                .border_type(BorderType::Plain),
+        )
+}
+
+/// A rect of `width_percent` of `area`'s width and `height` rows, centered in
+/// `area`.
+/// The setup wizard's step, shown in the theme being chosen.
+fn draw_wizard<B: Backend>(rect: &mut tui::Frame<B>, wizard: &wizard::Wizard) {
+    let theme = Theme::named(wizard.theme()).expect("the wizard offers known themes");
+    let (question, hint) = match wizard.step() {
+        wizard::Step::Location => (
+            format!(
+                "Welcome to pet-cli! Your pets will be kept in {}, relative to this directory.",
+                DB_PATH
+            ),
+            "Enter: create it    Esc: skip the setup",
+        ),
+        wizard::Step::Samples => (
+            "Add a few sample pets to look around with?".to_owned(),
+            "↑/↓: choose    Enter: next    ←: back    Esc: skip the setup",
+        ),
+        wizard::Step::Theme => (
+            "Choose a color theme. It is saved to your config file.".to_owned(),
+            "↑/↓: choose    Enter: finish    ←: back    Esc: skip the setup",
+        ),
+    };
+    let (choices, selected) = wizard.choices();
+    let mut lines = vec![
+        Spans::from(Span::styled(question, theme.text())),
+        Spans::default(),
+    ];
+    for (index, choice) in choices.iter().enumerate() {
+        let line = if index == selected {
+            Span::styled(format!("> {}", choice), theme.selection())
+        } else {
+            Span::styled(format!("  {}", choice), theme.text())
+        };
+        lines.push(Spans::from(line));
+    }
+    lines.push(Spans::default());
+    lines.push(Spans::from(Span::styled(hint, theme.label())));
+    let area = centered_rect(70, lines.len() as u16 + 2, rect.size());
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme.border())
+            .title("Setup")
+            .border_type(BorderType::Plain),
+    );
+    rect.render_widget(Clear, area);
+    rect.render_widget(paragraph, area);
+}
+
+fn centered_rect(width_percent: u16, height: u16, area: Rect) -> Rect {
+    let width = area.width * width_percent / 100;
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn create_copyright_paragraph<'a>(text: &str, lang: Lang, theme: &Theme) -> Paragraph<'a> {
+    Paragraph::new(text.to_owned())
+        .style(theme.accent())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::Copyright))
+                .border_type(BorderType::Plain),
+        )
+}
+
+fn default_footer() -> String {
+    format!("pet-CLI {}", Utc::now().year())
+}
+
+const HOME_WELCOME_HEIGHT: u16 = 3;
+const HOME_STATS_HEIGHT: u16 = 7;
+const HOME_RECENT_COUNT: usize = 5;
+const HOME_RECENT_HEIGHT: u16 = HOME_RECENT_COUNT as u16 + 2;
+/// The reminders shown at once; the list scrolls through the rest.
+const HOME_REMINDER_ROWS: usize = 5;
+
+/// The Home dashboard. When there isn't room for everything, the recent pets
+/// go first, then the stats and then the reminders; the welcome line and the
+/// keys always stay, with the quick actions of the config below them if it
+/// binds any. The reminders are only shown when there are any.
+fn render_home<B: Backend>(
+    rect: &mut tui::Frame<B>,
+    area: Rect,
+    pets: &[Pet],
+    reminder_list_state: &mut ListState,
+    quick_actions: &[config::QuickAction],
+    lang: Lang,
+    theme: &Theme,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(theme.border())
+        .title(strings::text(lang, Msg::Home))
+        .border_type(BorderType::Plain);
+    let inner = block.inner(area);
+    rect.render_widget(block, area);
+
+    let reminders = home_reminders(pets);
+    let pets: Vec<&Pet> = pets.iter().filter(|pet| !pet.is_archived()).collect();
+    let keys_height = keys::ESSENTIALS.len() as u16 + 2;
+    let reminders_height = match reminders.len() {
+        0 => 0,
+        count => count.min(HOME_REMINDER_ROWS) as u16 + 2,
+    };
+    let quick_actions_height = match quick_actions.len() {
+        0 => 0,
+        count => count as u16 + 2,
+    };
+    let mut needed = HOME_WELCOME_HEIGHT + keys_height + quick_actions_height;
+    let show_reminders = reminders_height > 0 && inner.height >= needed + reminders_height;
+    if show_reminders {
+        needed += reminders_height;
+    }
+    let show_stats = inner.height >= needed + HOME_STATS_HEIGHT;
+    let show_recent = inner.height >= needed + HOME_STATS_HEIGHT + HOME_RECENT_HEIGHT;
+
+    let mut constraints = vec![Constraint::Length(HOME_WELCOME_HEIGHT)];
+    if show_reminders {
+        constraints.push(Constraint::Length(reminders_height));
+    }
+    if show_stats {
+        constraints.push(Constraint::Length(HOME_STATS_HEIGHT));
+    }
+    if show_recent {
+        constraints.push(Constraint::Length(HOME_RECENT_HEIGHT));
+    }
+    if quick_actions_height > 0 {
+        constraints.push(Constraint::Length(keys_height));
+    }
+    constraints.push(Constraint::Min(0));
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(inner);
+    let mut rows = rows.into_iter();
+    let mut next_row = || rows.next().expect("one row per panel");
+
+    rect.render_widget(create_home_welcome(lang, theme), next_row());
+    if show_reminders {
+        let selected = reminder_list_state.selected().unwrap_or(0);
+        reminder_list_state.select(Some(selected.min(reminders.len() - 1)));
+        rect.render_stateful_widget(
+            create_home_reminders(&reminders, lang, theme),
+            next_row(),
+            reminder_list_state,
+        );
+    }
+    if show_stats {
+        rect.render_widget(create_home_stats(&pets, lang, theme), next_row());
+    }
+    if show_recent {
+        rect.render_widget(
+            create_home_recent(&pets, Utc::now(), lang, theme),
+            next_row(),
+        );
+    }
+    rect.render_widget(create_home_keys(lang, theme), next_row());
+    if quick_actions_height > 0 {
+        rect.render_widget(
+            create_home_quick_actions(quick_actions, lang, theme),
+            next_row(),
+        );
+    }
+}
+
+fn create_home_welcome<'a>(lang: Lang, theme: &Theme) -> Paragraph<'a> {
+    Paragraph::new(vec![
         Spans::from(vec![Span::raw("")]),
-        Spans::from(vec![Span::raw("Press 'p' to access pets, 'a' to add random new pets and 'd' to delete the currently selected pet.")]),
+        Spans::from(vec![
+            Span::raw(strings::text(lang, Msg::WelcomeTo)),
+            Span::styled("pet-CLI", theme.accent()),
+        ]),
     ])
     .alignment(Alignment::Center)
-    .block(
+    .style(theme.text())
+}
+
+/// The number of pets, of each adoption status and of the three most common
+/// categories.
+fn create_home_stats<'a>(pets: &[&Pet], lang: Lang, theme: &Theme) -> Paragraph<'a> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for pet in pets {
+        match counts
+            .iter_mut()
+            .find(|(category, _)| *category == pet.category)
+        {
+            Some((_, count)) => *count += 1,
+            None => counts.push((&pet.category, 1)),
+        }
+    }
+    counts.sort_by(|(a_name, a_count), (b_name, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_name.cmp(b_name))
+    });
+    let mut statuses = vec![Span::raw("      ")];
+    for status in PetStatus::ALL {
+        let count = pets.iter().filter(|pet| pet.status == status).count();
+        statuses.push(Span::styled(
+            format!("{} {}", count, status),
+            theme.badge(status),
+        ));
+        statuses.push(Span::raw("  "));
+    }
+    let mut lines = vec![
+        Spans::from(vec![
+            Span::styled(pets.len().to_string(), theme.emphasis()),
+            Span::raw(strings::text(lang, Msg::PetCount)),
+        ]),
+        Spans::from(statuses),
+    ];
+    lines.extend(counts.into_iter().take(3).map(|(category, count)| {
+        Spans::from(vec![
+            Span::styled(format!("{:>5}", count), theme.accent()),
+            Span::raw(format!("  {}", category)),
+        ])
+    }));
+    Paragraph::new(lines).style(theme.text()).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme.border())
+            .title(strings::text(lang, Msg::Stats))
+            .border_type(BorderType::Plain),
+    )
+}
+
+/// The most recently added pets with how long ago that was.
+fn create_home_recent<'a>(
+    pets: &[&Pet],
+    now: DateTime<Utc>,
+    lang: Lang,
+    theme: &Theme,
+) -> List<'a> {
+    let mut recent = pets.to_vec();
+    recent.sort_by_key(|pet| std::cmp::Reverse(pet.created_at));
+    let items: Vec<_> = recent
+        .into_iter()
+        .take(HOME_RECENT_COUNT)
+        .map(|pet| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(pet.name.clone(), theme.text()),
+                Span::styled(
+                    format!("  {}", dates::format_relative(pet.created_at, now)),
+                    theme.accent(),
+                ),
+            ]))
+        })
+        .collect();
+    List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme.border())
+            .title(strings::text(lang, Msg::RecentlyAdded))
+            .border_type(BorderType::Plain),
+    )
+}
+
+/// Warnings in the error style and birthdays in the accent color.
+fn create_home_reminders<'a>(
+    reminders: &[reminders::Reminder],
+    lang: Lang,
+    theme: &Theme,
+) -> List<'a> {
+    let today = Local::now().date_naive();
+    let items: Vec<_> = reminders
+        .iter()
+        .map(|reminder| {
+            let style = match reminder.severity() {
+                reminders::Severity::Warning => theme.error(),
+                reminders::Severity::Info => theme.accent(),
+            };
+            ListItem::new(Span::styled(reminder.describe(today), style))
+        })
+        .collect();
+    List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(strings::text(lang, Msg::Reminders))
+                .border_type(BorderType::Plain),
+        )
+        .highlight_style(theme.selection())
+}
+
+fn create_home_keys<'a>(lang: Lang, theme: &Theme) -> Paragraph<'a> {
+    let lines: Vec<_> = keys::ESSENTIALS
+        .iter()
+        .map(|(modifier, key, description)| {
+            Spans::from(vec![
+                Span::styled(
+                    format!("{:>8}", keys::key_name(modifier, *key)),
+                    theme.emphasis(),
+                ),
+                Span::raw(format!("  {}", description)),
+            ])
+        })
+        .collect();
+    Paragraph::new(lines).style(theme.text()).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme.border())
+            .title(strings::text(lang, Msg::Keys))
+            .border_type(BorderType::Plain),
+    )
+}
+
+fn create_home_quick_actions<'a>(
+    quick_actions: &[config::QuickAction],
+    lang: Lang,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let lines: Vec<_> = quick_actions
+        .iter()
+        .map(|quick_action| {
+            Spans::from(vec![
+                Span::styled(format!("{:>8}", quick_action.key_name()), theme.emphasis()),
+                Span::raw(format!("  {}", quick_action.line)),
+            ])
+        })
+        .collect();
+    Paragraph::new(lines).style(theme.text()).block(
         Block::default()
             .borders(Borders::ALL)
-            .style(Style::default().fg(Color::White))
-            .title("Home")
+            .style(theme.border())
+            .title(strings::text(lang, Msg::QuickActions))
             .border_type(BorderType::Plain),
+    )
+}
+
+/// The rows of the detail pane: the fields every pet has, plus the optional
+/// ones it has, ending with its extra fields. Notes have a pane of their own.
+fn pet_fields(
+    pet: &Pet,
+    owners: &[Owner],
+    now: DateTime<Utc>,
+    lang: Lang,
+) -> Vec<(String, String)> {
+    PetField::ALL
+        .iter()
+        .filter_map(|field| {
+            let mut value = field.value(pet, owners, now, lang)?;
+            if *field == PetField::Created {
+                value = format!(
+                    "{} ({})",
+                    value,
+                    dates::format_relative(pet.created_at, now)
+                );
+            }
+            Some((strings::text(lang, field.label()).to_owned(), value))
+        })
+        .chain(
+            pet.attributes
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        )
+        .chain(
+            pet.extra
+                .iter()
+                .map(|(key, value)| (key.clone(), extra_fields::display(value))),
+        )
+        .collect()
+}
+
+/// The Pets list, holding only the pets in `window`, and the detail table of
+/// the selected pet scrolled down by `app_state.detail_scroll` fields.
+/// The text of `column` in the pet list: the status as a badge, the rest as
+/// the detail pane shows it and empty where the pet has no value.
+fn list_cell(column: PetField, pet: &Pet, owners: &[Owner], lang: Lang) -> String {
+    match column {
+        PetField::Status => format!("[{}]", pet.status),
+        column => column
+            .value(pet, owners, Utc::now(), lang)
+            .unwrap_or_default(),
+    }
+}
+
+/// The list shows the pets in `window` in `app_state.list_columns`, as many
+/// of them as fit in `list_width` columns, laid out by `list_layout`.
+/// `rows` are the rows of the list on screen; see `AppState::pet_rows`.
+fn create_pet_widgets<'a>(
+    pet_list: &[&Pet],
+    rows: &[ListRow],
+    list_width: usize,
+    owners: &[Owner],
+    marked: &HashSet<usize>,
+    app_state: &AppState,
+) -> (List<'a>, Table<'a>) {
+    let (lang, theme) = (app_state.lang, &app_state.theme);
+    let pets = Block::default()
+        .borders(Borders::ALL)
+        .style(theme.border())
+        .title(strings::text(lang, Msg::Pets))
+        .border_type(BorderType::Plain);
+
+    let items: Vec<_> = {
+        profile_span!("list_build");
+        // Columns are as wide as their widest cell among the rows on screen.
+        // When some rows start with `!`, the others are indented to match.
+        let columns = &app_state.list_columns;
+        let shown: Vec<&Pet> = rows
+            .iter()
+            .filter_map(|row| match row {
+                ListRow::Pet(index) => Some(pet_list[*index]),
+                ListRow::Header { .. } => None,
+            })
+            .collect();
+        let cells: Vec<Vec<String>> = shown
+            .iter()
+            .map(|pet| {
+                columns
+                    .iter()
+                    .map(|column| list_cell(*column, pet, owners, lang))
+                    .collect()
+            })
+            .collect();
+        let mut widths = vec![0; columns.len()];
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(text::width(cell));
+            }
+        }
+        let any_invalid = shown
+            .iter()
+            .any(|pet| !validation::validate(pet).is_empty());
+        let prefix = if any_invalid { 4 } else { 2 };
+        let specs: Vec<_> = columns
+            .iter()
+            .zip(&widths)
+            .map(|(column, content)| {
+                let fixed = app_state
+                    .list_column_widths
+                    .iter()
+                    .find(|(field, _)| field == column)
+                    .map(|(_, width)| *width);
+                ColumnSpec::of(*column, *content, fixed)
+            })
+            .collect();
+        let fitted = list_layout::allocate(list_width.saturating_sub(prefix), &specs);
+        let kept: Vec<(usize, usize)> = fitted
+            .iter()
+            .enumerate()
+            .filter_map(|(index, width)| Some((index, (*width)?)))
+            .collect();
+        let mut cells = cells.iter();
+        rows.iter()
+            .map(|row| {
+                let index = match row {
+                    ListRow::Pet(index) => *index,
+                    ListRow::Header {
+                        category,
+                        count,
+                        folded,
+                    } => {
+                        let text = if *folded {
+                            format!("— {} ({}, folded) —", category, count)
+                        } else {
+                            format!("— {} ({}) —", category, count)
+                        };
+                        return ListItem::new(Span::styled(text, theme.category(category)));
+                    }
+                };
+                let pet = pet_list[index];
+                let cells = cells.next().expect("a row of cells per pet");
+                let (marker, style) = if marked.contains(&pet.id) {
+                    ("* ", theme.emphasis())
+                } else {
+                    ("  ", theme.text())
+                };
+                let warning = if validation::validate(pet).is_empty() {
+                    Span::raw(if any_invalid { "  " } else { "" })
+                } else {
+                    Span::styled("! ", theme.error())
+                };
+                let mut spans = vec![Span::styled(marker, style), warning];
+                match &app_state.prompt {
+                    Some(Prompt {
+                        kind: PromptKind::Rename { pet_id },
+                        input,
+                        ..
+                    }) if *pet_id == pet.id => spans.extend(input_spans(input, theme)),
+                    _ => {
+                        for (position, (index, width)) in kept.iter().enumerate() {
+                            let last = position + 1 == kept.len();
+                            let style = match columns[*index] {
+                                PetField::Name => style,
+                                PetField::Status => theme.badge(pet.status),
+                                PetField::Category => theme.category(&pet.category),
+                                _ => theme.text(),
+                            };
+                            let text = text::truncate(&cells[*index], *width);
+                            let text = if columns[*index].right_aligned() {
+                                text::pad_left(&text, *width)
+                            } else if last {
+                                text
+                            } else {
+                                text::pad(&text, *width)
+                            };
+                            spans.push(Span::styled(text, style));
+                            if !last {
+                                spans.push(Span::raw(" "));
+                            }
+                        }
+                    }
+                }
+                ListItem::new(Spans::from(spans))
+            })
+            .collect()
+    };
+
+    let selected_pet = app_state
+        .pet_list_state
+        .selected()
+        .and_then(|selected| pet_list.get(selected));
+
+    let list = List::new(items)
+        .block(pets)
+        .highlight_style(theme.selection());
+
+    profile_span!("detail_build");
+    let issues = selected_pet
+        .map(|pet| validation::validate(pet))
+        .unwrap_or_default();
+    let detail_title = if issues.is_empty() {
+        Span::raw(strings::text(lang, Msg::Detail))
+    } else {
+        Span::styled(
+            format!(
+                "{}: {}",
+                strings::text(lang, Msg::Detail),
+                validation::describe(&issues)
+            ),
+            theme.error(),
+        )
+    };
+    let fields = selected_pet
+        .map(|pet| pet_fields(pet, owners, Utc::now(), lang))
+        .unwrap_or_default();
+    // Extra fields can't be edited, so they are shown dimmed.
+    let editable = fields.len() - selected_pet.map_or(0, |pet| pet.extra.len());
+    let detail_rows: Vec<_> = fields
+        .into_iter()
+        .enumerate()
+        .skip(app_state.detail_scroll)
+        .map(|(index, (field, value))| {
+            let style = match selected_pet {
+                _ if index >= editable => theme.border(),
+                Some(pet) if field == strings::text(lang, Msg::FieldStatus) => {
+                    theme.badge(pet.status)
+                }
+                Some(pet) if field == strings::text(lang, Msg::FieldCategory) => {
+                    theme.category(&pet.category)
+                }
+                _ => theme.text(),
+            };
+            Row::new(vec![
+                Cell::from(Span::styled(field, theme.label())),
+                Cell::from(Span::styled(value, style)),
+            ])
+        })
+        .collect();
+    let pet_detail = Table::new(detail_rows)
+        .style(theme.text())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(theme.border())
+                .title(detail_title)
+                .border_type(BorderType::Plain),
+        )
+        .widths(&[Constraint::Length(10), Constraint::Percentage(100)]);
+
+    (list, pet_detail)
+}
+
+fn resolved_db_path() -> String {
+    fs::canonicalize(db_path())
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| db_path())
+}
+
+fn read_db() -> Result<Vec<Pet>, Error> {
+    Ok(read_database()?.pets)
+}
+
+/// The DB file as text, decompressed. Encrypted DBs are refused up front, as
+/// their bytes would only make a confusing parse error.
+fn read_db_content() -> Result<String, Error> {
+    let bytes = fs::read(db_path())?;
+    if db_format::is_encrypted(&bytes) {
+        return Err(Error::EncryptedDBError);
+    }
+    String::from_utf8(decompress(bytes)?)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err).into())
+}
+
+/// The bytes of a DB file decompressed. A broken compressed stream is a DB
+/// that can't be parsed, which the loader offers the backups for.
+fn decompress(bytes: Vec<u8>) -> Result<Vec<u8>, Error> {
+    compression::decode(bytes).map_err(|err| Error::ParseDBError(serde_json::Error::io(err)))
+}
+
+fn read_database() -> Result<Database, Error> {
+    read_database_with(|_, _| {})
+}
+
+/// Reads the DB, calling `progress` with the bytes read so far and the size
+/// of the file now and then. DBs as pet-cli writes them are parsed as they
+/// are read; the text of others is read whole first.
+fn read_database_with(mut progress: impl FnMut(u64, u64)) -> Result<Database, Error> {
+    profile_span!("db_read");
+    let started = Instant::now();
+    let read_error = |err: Error| {
+        log::error!("reading {} failed: {}", db_path(), err);
+        err
+    };
+    let file = fs::File::open(db_path()).map_err(|err| read_error(err.into()))?;
+    let size = file.metadata().map_or(0, |metadata| metadata.len());
+    let (streamed, bytes) = db_format::stream(file, |read| progress(read, size));
+    let (parsed, bytes) = match streamed {
+        Some(database) => (Ok(database), bytes),
+        // Other formats, older schema versions, encrypted and broken files;
+        // reading the text refuses encrypted ones up front, and parsing it
+        // gives broken ones their line and column.
+        None => {
+            let db_content = read_db_content().map_err(read_error)?;
+            (db_format::parse(&db_content), db_content.len() as u64)
+        }
+    };
+    let mut parsed = parsed
+        .and_then(|parsed| check_schema(&parsed).map(|()| parsed))
+        .map_err(|err| {
+            log::error!("parsing {} failed: {}", db_path(), err);
+            err
+        })?;
+    // A `next_id` behind the pets, as in a file edited by hand, would give
+    // the id of a pet purged after this read to the next one added.
+    parsed.next_id.take(&parsed.pets, 0);
+    metrics::record_read(parsed.pets.len(), bytes as usize, started.elapsed());
+    log::debug!(
+        "read {} pets and {} owners ({} bytes) in {:?}",
+        parsed.pets.len(),
+        parsed.owners.len(),
+        bytes,
+        started.elapsed()
+    );
+    Ok(parsed)
+}
+
+/// Reads the DB for a command, showing why in the status bar if it can't.
+fn read_db_or_report(app_state: &mut AppState) -> Option<Database> {
+    match read_database() {
+        Ok(database) => Some(database),
+        Err(err) => {
+            app_state.report(StatusLevel::Error, err.to_string());
+            None
+        }
+    }
+}
+
+/// Appends `count` random pets with a single write and returns the whole DB.
+/// A `seed` makes the pets reproducible.
+/// Fills the new DB of a demo with its pets.
+fn write_demo_db(options: WriteOptions, names: &generator::Names) -> Result<(), Error> {
+    let _lock = lock_db(options)?;
+    let pets = demo::pets(names, Utc::now());
+    let mut database = Database {
+        schema_version: migrations::CURRENT_VERSION,
+        ..Database::default()
+    };
+    database.pets = pets;
+    write_db(&mut database, options)
+}
+
+fn add_random_pets_to_db(
+    options: WriteOptions,
+    count: usize,
+    seed: Option<u64>,
+    names: &generator::Names,
+    progress: &Reporter,
+) -> Result<Vec<Pet>, Error> {
+    retry_on_conflict(|| {
+        profile_span!("db_add");
+        let _lock = lock_db(options)?;
+        let mut database = read_database()?;
+        let mut rng = generator::seeded_rng(seed);
+        let created_at = Utc::now();
+        // Each chunk is named unlike the ones before, which are in the DB by
+        // then, so the pets are the same as if made all at once.
+        progress::chunked("generating pets", count, progress, |chunk| {
+            let first_id = database.next_id.take(&database.pets, chunk.len()).start;
+            let generated = generator::generate(
+                &mut rng,
+                chunk.len(),
+                first_id,
+                created_at,
+                names,
+                &database.pets,
+            );
+            if let Some(issues) = generated
+                .iter()
+                .map(validation::validate)
+                .find(|issues| !issues.is_empty())
+            {
+                return Err(Error::InvalidPetError(validation::describe(&issues)));
+            }
+            database.pets.extend(generated);
+            Ok(())
+        })?;
+        write_db(&mut database, options)?;
+        Ok(database.pets)
+    })
+}
+
+/// Adds `pet` under a new id and returns it as stored.
+#[cfg(feature = "server")]
+fn add_pet(options: WriteOptions, pet: Pet) -> Result<Pet, Error> {
+    retry_on_conflict(|| {
+        profile_span!("db_add");
+        let _lock = lock_db(options)?;
+        let mut database = read_database()?;
+        let pet = Pet {
+            id: database.next_id.next(&database.pets),
+            ..pet.clone()
+        };
+        let issues = validation::validate(&pet);
+        if !issues.is_empty() {
+            return Err(Error::InvalidPetError(validation::describe(&issues)));
+        }
+        database.pets.push(pet.clone());
+        write_db(&mut database, options)?;
+        Ok(pet)
+    })
+}
+
+/// Adds `pets` under new ids with a single write, or none of them
+/// if any is invalid. Returns them as stored.
+fn import_pets(
+    options: WriteOptions,
+    pets: Vec<Pet>,
+    progress: &Reporter,
+) -> Result<Vec<Pet>, Error> {
+    retry_on_conflict(|| {
+        profile_span!("db_add");
+        let _lock = lock_db(options)?;
+        let mut database = read_database()?;
+        let ids = database.next_id.take(&database.pets, pets.len());
+        let pets: Vec<Pet> = ids
+            .zip(&pets)
+            .map(|(id, pet)| Pet { id, ..pet.clone() })
+            .collect();
+        let mut failures: Vec<String> = Vec::new();
+        progress::chunked::<Error>("checking pets", pets.len(), progress, |chunk| {
+            failures.extend(pets[chunk].iter().filter_map(|pet| {
+                let issues = validation::validate(pet);
+                (!issues.is_empty())
+                    .then(|| format!("{}: {}", pet.name, validation::describe(&issues)))
+            }));
+            Ok(())
+        })?;
+        if !failures.is_empty() {
+            return Err(Error::InvalidPetError(failures.join("; ")));
+        }
+        database.pets.extend(pets.iter().cloned());
+        write_db(&mut database, options)?;
+        Ok(pets)
+    })
+}
+
+/// The pet `fields` describe, as it is before it is given an id.
+fn new_pet(fields: quick_add::NewPet) -> Pet {
+    Pet {
+        id: 0,
+        name: fields.name,
+        category: fields.category,
+        age: Age {
+            months: fields.age_months,
+        },
+        created_at: Utc::now(),
+        notes: None,
+        birthdate: fields.birthdate,
+        owner_id: None,
+        records: Vec::new(),
+        weights: Vec::new(),
+        microchip: None,
+        breed: None,
+        status: PetStatus::default(),
+        photo_path: None,
+        attributes: BTreeMap::new(),
+        archived_at: None,
+        extra: Default::default(),
+    }
+}
+
+/// Merges the DB file `theirs` into the DB, planned again against the DB as
+/// it is once locked. `resolve` decides the conflicts; one it has no answer
+/// for must have appeared since the plan was shown, which fails the merge as
+/// a conflicting write.
+fn merge_db(
+    options: WriteOptions,
+    theirs: &Database,
+    resolve: impl Fn(&Conflict) -> Option<Strategy>,
+    progress: &Reporter,
+) -> Result<Merged, Error> {
+    retry_on_conflict(|| {
+        profile_span!("db_merge");
+        let _lock = lock_db(options)?;
+        let mut database = read_database()?;
+        let mut plan = merge::plan(&database, theirs);
+        for conflict in &mut plan.conflicts {
+            conflict.resolution = resolve(conflict);
+        }
+        let merged = merge::apply(&plan, &mut database).map_err(|_| Error::ConflictError)?;
+        let changed: Vec<&Pet> = merged.added.iter().chain(&merged.replaced).collect();
+        let mut failures: Vec<String> = Vec::new();
+        progress::chunked::<Error>("checking pets", changed.len(), progress, |chunk| {
+            failures.extend(changed[chunk].iter().filter_map(|pet| {
+                let issues = validation::validate(pet);
+                (!issues.is_empty())
+                    .then(|| format!("{}: {}", pet.name, validation::describe(&issues)))
+            }));
+            Ok(())
+        })?;
+        if !failures.is_empty() {
+            return Err(Error::InvalidPetError(failures.join("; ")));
+        }
+        write_db(&mut database, options)?;
+        Ok(merged)
+    })
+}
+
+/// Makes `assignment` on the pets with `ids` in a single write, or fails
+/// naming every pet it would make invalid. Returns how many pets were
+/// changed, which leaves out those deleted meanwhile.
+fn batch_edit(
+    options: WriteOptions,
+    ids: &HashSet<usize>,
+    assignment: &batch::Assignment,
+) -> Result<usize, Error> {
+    retry_on_conflict(|| {
+        profile_span!("db_batch_edit");
+        let _lock = lock_db(options)?;
+        let mut database = read_database()?;
+        let count = database
+            .pets
+            .iter()
+            .filter(|pet| ids.contains(&pet.id))
+            .count();
+        database.pets = batch::apply(std::mem::take(&mut database.pets), ids, assignment).map_err(
+            |failures| {
+                let failures: Vec<String> = failures.iter().map(ToString::to_string).collect();
+                Error::InvalidPetError(format!(
+                    "{} pets would be invalid: {}",
+                    failures.len(),
+                    failures.join("; ")
+                ))
+            },
+        )?;
+        back_up_db(options)?;
+        write_db(&mut database, options)?;
+        Ok(count)
+    })
+}
+
+/// Makes `rename` in a single write, skipped if it changes no pet. Returns
+/// how many pets it changed.
+fn rename_categories(options: WriteOptions, rename: &categories::Rename) -> Result<usize, Error> {
+    retry_on_conflict(|| {
+        profile_span!("db_rename_categories");
+        let _lock = lock_db(options)?;
+        let mut database = read_database()?;
+        let count = rename.apply(&mut database.pets);
+        if count > 0 {
+            back_up_db(options)?;
+            write_db(&mut database, options)?;
+        }
+        Ok(count)
+    })
+}
+
+/// The pets a batch edit changes: the marked ones, or else those shown if
+/// the list is filtered. Setting a field on the whole DB this way is refused.
+fn batch_targets(app_state: &AppState, visible: &[&Pet]) -> Result<HashSet<usize>, String> {
+    let selected = app_state.pet_list_state.selected().unwrap_or(0);
+    let marked = app_state.marks.effective(visible, selected);
+    if !marked.is_empty() {
+        return Ok(marked);
+    }
+    if !app_state.view.is_filtered() {
+        return Err("mark pets or filter the list to choose the pets to change".to_owned());
+    }
+    if visible.is_empty() {
+        return Err("no pets are shown".to_owned());
+    }
+    Ok(visible.iter().map(|pet| pet.id).collect())
+}
+
+/// Moves the pet with `id` next to the pet with `anchor_id`, or to the end
+/// of the DB. Returns `None` if either pet is gone.
+fn move_pet(
+    options: WriteOptions,
+    id: usize,
+    anchor_id: Option<usize>,
+    side: reorder::Side,
+) -> Result<Option<Pet>, Error> {
+    retry_on_conflict(|| {
+        profile_span!("db_move");
+        let _lock = lock_db(options)?;
+        let mut database = read_database()?;
+        let Some(index) = reorder::move_pet(&mut database.pets, id, anchor_id, side) else {
+            return Ok(None);
+        };
+        write_db(&mut database, options)?;
+        Ok(Some(database.pets[index].clone()))
+    })
+}
+
+/// Adds a copy of the pet with `id` named `name`, with a new id and creation
+/// time and out of the Trash. Returns `None` if no pet has that id.
+fn copy_pet(options: WriteOptions, id: usize, name: String) -> Result<Option<Pet>, Error> {
+    retry_on_conflict(|| {
+        profile_span!("db_copy");
+        let _lock = lock_db(options)?;
+        let mut database = read_database()?;
+        let Some(original) = database.pets.iter().find(|pet| pet.id == id) else {
+            return Ok(None);
+        };
+        let copy = Pet {
+            id: database.next_id.next(&database.pets),
+            name: name.clone(),
+            created_at: Utc::now(),
+            archived_at: None,
+            ..original.clone()
+        };
+        let issues = validation::validate(&copy);
+        if !issues.is_empty() {
+            return Err(Error::InvalidPetError(validation::describe(&issues)));
+        }
+        database.pets.push(copy.clone());
+        write_db(&mut database, options)?;
+        Ok(Some(copy))
+    })
+}
+
+/// Applies `change` to the pet with `id` and writes the DB, returning the
+/// updated pet or `None` if no pet has that id.
+fn update_pet(
+    options: WriteOptions,
+    id: usize,
+    change: impl FnOnce(&mut Pet),
+) -> Result<Option<Pet>, Error> {
+    profile_span!("db_update");
+    let _lock = lock_db(options)?;
+    let mut database = read_database()?;
+    let Some(pet) = database.pets.iter_mut().find(|pet| pet.id == id) else {
+        return Ok(None);
+    };
+    let issues_before = validation::validate(pet);
+    change(pet);
+    // Pets made invalid by hand-editing the file can still be edited here, as
+    // long as the edit doesn't add a problem of its own.
+    let new_issues: Vec<_> = validation::validate(pet)
+        .into_iter()
+        .filter(|issue| !issues_before.contains(issue))
+        .collect();
+    if !new_issues.is_empty() {
+        return Err(Error::InvalidPetError(validation::describe(&new_issues)));
+    }
+    let updated = pet.clone();
+    back_up_db(options)?;
+    write_db(&mut database, options)?;
+    Ok(Some(updated))
+}
+
+/// Deletes every pet in `ids` that is in the Trash with a single write. A
+/// pet restored since it was asked about is kept.
+fn purge_pets(options: WriteOptions, ids: &HashSet<usize>) -> Result<Vec<Pet>, Error> {
+    profile_span!("db_remove");
+    let _lock = lock_db(options)?;
+    let mut database = read_database()?;
+    let (removed, kept): (Vec<Pet>, Vec<Pet>) = database
+        .pets
+        .into_iter()
+        .partition(|pet| ids.contains(&pet.id) && pet.is_archived());
+    database.pets = kept;
+    if removed.is_empty() {
+        return Ok(removed);
+    }
+    back_up_db(options)?;
+    write_db(&mut database, options)?;
+    Ok(removed)
+}
+
+/// Sets `archived_at` on every pet in `ids` with a single write and returns
+/// the pets that changed.
+fn set_archived(
+    options: WriteOptions,
+    ids: &HashSet<usize>,
+    archived_at: Option<DateTime<Utc>>,
+) -> Result<Vec<Pet>, Error> {
+    profile_span!("db_archive");
+    let _lock = lock_db(options)?;
+    let mut database = read_database()?;
+    let mut changed = Vec::new();
+    for pet in &mut database.pets {
+        if ids.contains(&pet.id) && pet.archived_at.is_some() != archived_at.is_some() {
+            pet.archived_at = archived_at;
+            changed.push(pet.clone());
+        }
+    }
+    if changed.is_empty() {
+        return Ok(changed);
+    }
+    back_up_db(options)?;
+    write_db(&mut database, options)?;
+    Ok(changed)
+}
+
+/// Merges the pets in `other_ids` into the pet with `survivor_id` and
+/// deletes them, in one write. Returns the merged pet, or `None` if the
+/// survivor doesn't exist.
+fn merge_pets(
+    options: WriteOptions,
+    survivor_id: usize,
+    other_ids: &[usize],
+) -> Result<Option<Pet>, Error> {
+    profile_span!("db_merge");
+    let _lock = lock_db(options)?;
+    let mut database = read_database()?;
+    let Some(survivor) = database.pets.iter().find(|pet| pet.id == survivor_id) else {
+        return Ok(None);
+    };
+    let others: Vec<&Pet> = database
+        .pets
+        .iter()
+        .filter(|pet| pet.id != survivor_id && other_ids.contains(&pet.id))
+        .collect();
+    let merged = duplicates::merge(survivor, &others);
+    database
+        .pets
+        .retain(|pet| pet.id == survivor_id || !other_ids.contains(&pet.id));
+    for pet in &mut database.pets {
+        if pet.id == survivor_id {
+            *pet = merged.clone();
+        }
+    }
+    back_up_db(options)?;
+    write_db(&mut database, options)?;
+    Ok(Some(merged))
+}
+
+/// Permanently deletes pets that have been archived for longer than
+/// `older_than`, returning them.
+fn purge_archived(options: WriteOptions, older_than: chrono::Duration) -> Result<Vec<Pet>, Error> {
+    profile_span!("db_purge");
+    let _lock = lock_db(options)?;
+    let mut database = read_database()?;
+    let cutoff = Utc::now() - older_than;
+    let (purged, kept): (Vec<Pet>, Vec<Pet>) = database.pets.into_iter().partition(|pet| {
+        pet.archived_at
+            .is_some_and(|archived_at| archived_at < cutoff)
+    });
+    database.pets = kept;
+    if purged.is_empty() {
+        return Ok(purged);
+    }
+    back_up_db(options)?;
+    write_db(&mut database, options)?;
+    Ok(purged)
+}
+
+/// Adds an owner with the next free id.
+fn add_owner(options: WriteOptions, name: String, email: String) -> Result<Owner, Error> {
+    retry_on_conflict(|| {
+        profile_span!("db_add_owner");
+        let _lock = lock_db(options)?;
+        let mut database = read_database()?;
+        let id = database
+            .owners
+            .iter()
+            .map(|owner| owner.id + 1)
+            .max()
+            .unwrap_or(1);
+        let owner = Owner {
+            id,
+            name: name.clone(),
+            email: email.clone(),
+        };
+        database.owners.push(owner.clone());
+        write_db(&mut database, options)?;
+        Ok(owner)
+    })
+}
+
+/// Removes the owner with `id`, clearing the owner of every pet they had.
+fn remove_owner(options: WriteOptions, id: usize) -> Result<Option<Owner>, Error> {
+    profile_span!("db_remove_owner");
+    let _lock = lock_db(options)?;
+    let mut database = read_database()?;
+    let Some(index) = database.owners.iter().position(|owner| owner.id == id) else {
+        return Ok(None);
+    };
+    let owner = database.owners.remove(index);
+    for pet in &mut database.pets {
+        if pet.owner_id == Some(id) {
+            pet.owner_id = None;
+        }
+    }
+    back_up_db(options)?;
+    write_db(&mut database, options)?;
+    Ok(Some(owner))
+}
+
+/// Rewrites a DB of an older schema version in the current shape, after
+/// backing it up, so other tools see the same file layout the app writes.
+/// Returns the migrations that ran, or that would have in read-only mode.
+/// Fails for a DB newer than this pet-cli understands.
+fn migrate_db(options: WriteOptions) -> Result<Vec<&'static Migration>, Error> {
+    let _lock = match options.read_only {
+        true => None,
+        false => Some(lock_db(options)?),
+    };
+    let Ok(content) = read_db_content() else {
+        return Ok(Vec::new());
+    };
+    let steps = migrations::steps(db_format::schema_version(&content))?;
+    if steps.is_empty() || options.read_only {
+        return Ok(steps);
+    }
+    for step in &steps {
+        log::info!(
+            "migrating {} from schema version {} to {}: {}",
+            db_path(),
+            step.from,
+            step.to,
+            step.description
+        );
+    }
+    let mut database = db_format::parse(&content)?;
+    back_up_db(options)?;
+    write_db(&mut database, options)?;
+    Ok(steps)
+}
+
+/// How often a change that can be redone on a newer DB, like adding a pet, is
+/// tried when the DB keeps changing under it.
+const WRITE_ATTEMPTS: usize = 3;
+
+/// Runs `attempt` again, reading the DB afresh, while it fails with
+/// [`Error::ConflictError`]. Only for changes that don't depend on the pets
+/// they read staying the same: updates and deletions report the conflict.
+fn retry_on_conflict<T>(mut attempt: impl FnMut() -> Result<T, Error>) -> Result<T, Error> {
+    let mut attempts = 1;
+    loop {
+        match attempt() {
+            Err(Error::ConflictError) if attempts < WRITE_ATTEMPTS => {
+                log::info!("{} changed while writing, trying again", db_path());
+                attempts += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Takes the exclusive lock every write holds, unless the DB is read-only.
+fn lock_db(options: WriteOptions) -> Result<db_lock::DbLock, Error> {
+    if options.read_only {
+        return Err(Error::ReadOnly);
+    }
+    Ok(db_lock::exclusive(Path::new(&db_path()))?)
+}
+
+/// Whether the DB file can be opened for writing. A missing file counts as
+/// writable since the first write creates it.
+fn db_is_writable() -> bool {
+    match fs::OpenOptions::new().write(true).open(db_path()) {
+        Ok(_) => true,
+        Err(err) => err.kind() == io::ErrorKind::NotFound,
+    }
+}
+
+/// Encodes `database` with `codec` and writes it over the DB file, returning
+/// the bytes written.
+fn write_db_file(
+    database: &Database,
+    options: WriteOptions,
+    codec: compression::Codec,
+) -> Result<Vec<u8>, Error> {
+    let temp_path = format!("{}.tmp", db_path());
+    let content = compression::encode(
+        &db_format::serialize(database, options.format)?,
+        codec,
+        options.compress_level,
+    )?;
+    durability::write_atomic(
+        Path::new(&db_path()),
+        Path::new(&temp_path),
+        &content,
+        options.durability,
+    )
+    .map_err(|err| {
+        log::error!("writing {} failed: {}", db_path(), err);
+        err
+    })?;
+    Ok(content)
+}
+
+/// Replaces the DB file in one step, so readers never see a half-written
+/// file, and moves `database` to the next revision once it is written.
+/// Callers hold the exclusive DB lock, but hand edits and older versions of
+/// pet-cli don't take it: if the file isn't at the revision `database` was
+/// read at any more, nothing is written and the write fails with
+/// [`Error::ConflictError`].
+fn write_db(database: &mut Database, options: WriteOptions) -> Result<(), Error> {
+    let started = Instant::now();
+    let (on_disk_content, on_disk_codec) = match fs::read(db_path()) {
+        Ok(bytes) => {
+            let codec = compression::detect(&bytes);
+            let content = String::from_utf8(decompress(bytes)?)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            (content, Some(codec))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => (String::new(), None),
+        Err(err) => return Err(err.into()),
+    };
+    let on_disk = db_format::revision(&on_disk_content);
+    if on_disk != database.revision {
+        log::warn!(
+            "{} is at revision {}, not {} as read",
+            db_path(),
+            on_disk,
+            database.revision
+        );
+        return Err(Error::ConflictError);
+    }
+    let read_at = (database.revision, database.schema_version);
+    database.revision += 1;
+    database.schema_version = migrations::CURRENT_VERSION;
+    let codec = compression::for_write(Path::new(&db_path()), options.compress, on_disk_codec);
+    let written = write_db_file(database, options, codec);
+    if written.is_err() {
+        // Back at the revision of the file, so that trying again doesn't look
+        // like a conflict.
+        (database.revision, database.schema_version) = read_at;
+    }
+    let content = written?;
+    metrics::record_write(
+        database.pets.len(),
+        content.len(),
+        options.durability,
+        started.elapsed(),
     );
-    home
+    if let Some(max_bytes) = options.audit_max_bytes {
+        // The write is done; a failure to audit it mustn't undo that.
+        let before = db_format::parse(&on_disk_content)
+            .map(|database| database.pets)
+            .unwrap_or_default();
+        let records = audit::records(&before, &database.pets, Utc::now());
+        let path = audit::path_for(Path::new(&db_path()));
+        if let Err(err) = audit::append(&path, &records, max_bytes) {
+            log::error!("writing {} failed: {}", path.display(), err);
+        }
+    }
+    log::debug!(
+        "wrote revision {} with {} pets and {} owners ({} bytes, {:?}, {:?}) in {:?}",
+        database.revision,
+        database.pets.len(),
+        database.owners.len(),
+        content.len(),
+        options.format,
+        codec,
+        started.elapsed()
+    );
+    Ok(())
+}
+
+/// Moves the DB file aside and writes an empty DB in its place, returning
+/// where the old file went. The ids carry on from the old DB, as ids are
+/// never given out twice, unless it can't be read.
+fn reset_db(options: WriteOptions) -> Result<PathBuf, Error> {
+    let _lock = lock_db(options)?;
+    let next_id = read_database().map_or(IdAllocator::default(), |database| database.next_id);
+    let aside = reset::set_aside(Path::new(&db_path()), Utc::now())?;
+    let mut database = Database {
+        next_id,
+        ..Database::default()
+    };
+    write_db(&mut database, options)?;
+    Ok(aside)
+}
+
+/// `pet-cli nuke`: resets the DB once `reset` is typed, or straight away
+/// with `--yes`.
+fn run_nuke(options: WriteOptions, yes: bool) -> Result<(), AppError> {
+    let mut out = io::stdout().lock();
+    if !yes {
+        write!(
+            out,
+            "This moves {} aside and starts an empty DB. Type {} to go on: ",
+            workspace_db(),
+            reset::CONFIRM_WORD
+        )?;
+        io::Write::flush(&mut io::stdout())?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if answer.trim() != reset::CONFIRM_WORD {
+            writeln!(out, "cancelled")?;
+            return Ok(());
+        }
+    }
+    let aside = reset_db(options).map_err(AppError::db_write)?;
+    writeln!(
+        out,
+        "moved the DB to {} and started an empty one",
+        aside.display()
+    )?;
+    Ok(())
 }
 
-fn create_pet_widgets<'a>(pet_list_state: &ListState) -> (List<'a>, Table<'a>) {
-    let pets = Block::default()
-        .borders(Borders::ALL)
-        .style(Style::default().fg(Color::White))
-        .title("Pets")
-        .border_type(BorderType::Plain);
+/// Called before any write that removes or overwrites pets. A failed backup
+/// fails the write.
+fn back_up_db(options: WriteOptions) -> Result<(), Error> {
+    backup::create(Path::new(&db_path()), options.keep_backups).map_err(Error::BackupDBError)?;
+    Ok(())
+}
 
-    let pet_list = read_db().expect("can fetch pet list");
-    let items: Vec<_> = pet_list
-        .iter()
-        .map(|pet| {
-            ListItem::new(Spans::from(vec![Span::styled(
-                pet.name.clone(),
-                Style::default(),
-            )]))
-        })
-        .collect();
+/// `pet-cli report`: renders the pets of `view` to `out` or stdout.
+fn run_report(
+    format: report::ReportFormat,
+    out: Option<&Path>,
+    view: &ViewParams,
+) -> Result<(), AppError> {
+    let pets = read_db().map_err(AppError::db_read)?;
+    let pets = view.apply(&pets);
+    let rendered = report::render(format, &pets, Utc::now());
+    let mut stdout = io::stdout().lock();
+    match out {
+        Some(path) => {
+            fs::write(path, rendered).map_err(|source| AppError::File {
+                path: path.to_owned(),
+                source,
+            })?;
+            writeln!(stdout, "wrote {} pets to {}", pets.len(), path.display())?;
+        }
+        None => write!(stdout, "{}", rendered)?,
+    }
+    Ok(())
+}
 
-    let selected_pet = pet_list
-        .get(
-            pet_list_state
-                .selected()
-                .expect("there is always a selected pet"),
+/// `pet-cli stats`: prints what the Stats tab shows, as text or JSON.
+fn run_stats(json: bool, age_buckets: AgeBucketWidth) -> Result<(), AppError> {
+    let mut out = io::stdout().lock();
+    let pets = read_db().map_err(AppError::db_read)?;
+    let summary = stats::summary(&pets, Utc::now(), age_buckets);
+    if json {
+        let json = serde_json::to_string_pretty(&summary).expect("stats serialize");
+        writeln!(out, "{}", json)?;
+        return Ok(());
+    }
+    let average = |months: Option<f64>| {
+        months.map_or_else(
+            || "-".to_owned(),
+            |months| age::format(months.round() as usize),
         )
-        .expect("exists")
-        .clone();
+    };
+    writeln!(
+        out,
+        "{} pets, on average {}",
+        summary.total,
+        average(summary.average_age_months)
+    )?;
+    let width = summary
+        .categories
+        .iter()
+        .map(|category| text::width(&category.category))
+        .max()
+        .unwrap_or(0);
+    for category in &summary.categories {
+        let by_status: Vec<String> = category
+            .by_status
+            .iter()
+            .map(|(status, count)| format!("{} {}", status, count))
+            .collect();
+        writeln!(
+            out,
+            "{}  {:>4}  {}, on average {}",
+            text::pad(&category.category, width),
+            category.total,
+            by_status.join(", "),
+            average(category.average_age_months)
+        )?;
+    }
+    writeln!(out, "ages, {}y per bar:", summary.ages.width_years)?;
+    let most = summary.ages.buckets.iter().map(|bucket| bucket.count).max();
+    for bucket in &summary.ages.buckets {
+        // Bars of at most 40 columns.
+        let bar = most.map_or(0, |most| bucket.count * 40 / most.max(1));
+        let line = format!(
+            "{:>7} {:>4} {}",
+            bucket.label(),
+            bucket.count,
+            "#".repeat(bar)
+        );
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    if summary.ages.unknown > 0 {
+        writeln!(out, "{:>7} {:>4}", "unknown", summary.ages.unknown)?;
+    }
+    writeln!(out, "added per month, with the running total:")?;
+    for month in &summary.added_per_month {
+        writeln!(
+            out,
+            "{}-{:02} {:>4} {:>6}",
+            month.year, month.month, month.added, month.total
+        )?;
+    }
+    Ok(())
+}
 
-    let list = List::new(items).block(pets).highlight_style(
-        Style::default()
-            .bg(Color::Yellow)
-            .fg(Color::Black)
-            .add_modifier(Modifier::BOLD),
-    );
+/// `pet-cli list`: prints the pets of `view`, with only `fields` if any are
+/// given.
+fn print_list(
+    format: list::ListFormat,
+    fields: &[PetField],
+    view: &ViewParams,
+    extra_fields: bool,
+    max_width: Option<usize>,
+) -> Result<(), AppError> {
+    let mut out = io::stdout().lock();
+    let database = read_database().map_err(AppError::db_read)?;
+    let pets = view.apply(&database.pets);
+    let now = Utc::now();
+    if fields.is_empty() {
+        write!(
+            out,
+            "{}",
+            list::render(format, &pets, now, extra_fields, max_width)
+        )?;
+    } else {
+        let rendered = list::render_fields(format, &pets, fields, &database.owners, now, max_width);
+        write!(out, "{}", rendered)?;
+    }
+    Ok(())
+}
 
-    let pet_detail = Table::new(vec![Row::new(vec![
-        Cell::from(Span::raw(selected_pet.id.to_string())),
-        Cell::from(Span::raw(selected_pet.name)),
-        Cell::from(Span::raw(selected_pet.category)),
-        Cell::from(Span::raw(selected_pet.age.to_string())),
-        Cell::from(Span::raw(selected_pet.created_at.to_string())),
-    ])])
-    .header(Row::new(vec![
-        Cell::from(Span::styled(
-            "ID",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
-        Cell::from(Span::styled(
-            "Name",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
-        Cell::from(Span::styled(
-            "Category",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
-        Cell::from(Span::styled(
-            "Age",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
-        Cell::from(Span::styled(
-            "Created At",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
-    ]))
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .style(Style::default().fg(Color::White))
-            .title("Detail")
-            .border_type(BorderType::Plain),
-    )
-    .widths(&[
-        Constraint::Percentage(5),
-        Constraint::Percentage(20),
-        Constraint::Percentage(20),
-        Constraint::Percentage(5),
-        Constraint::Percentage(20),
-    ]);
+/// `pet-cli list --watch`: prints the list, then again every time the DB's
+/// modification time changes, until interrupted. A DB that can't be read,
+/// e.g. because it was moved away, is reported and waited for.
+fn run_list_watch(
+    format: list::ListFormat,
+    fields: &[PetField],
+    view: &ViewParams,
+    extra_fields: bool,
+    max_width: Option<usize>,
+    interval: Duration,
+    clear: bool,
+) -> Result<(), AppError> {
+    let mut watch = DbWatch::new(workspace_db());
+    loop {
+        watch.mark_seen();
+        if clear {
+            execute!(
+                io::stdout(),
+                terminal::Clear(terminal::ClearType::All),
+                cursor::MoveTo(0, 0)
+            )
+            .map_err(crossterm_io)?;
+        }
+        match print_list(format, fields, view, extra_fields, max_width) {
+            Err(err) if err.is_broken_pipe() => return Err(err),
+            Err(err) => eprintln!("{}, waiting for it to change", err),
+            Ok(()) => {}
+        }
+        io::stdout().flush()?;
+        while !watch.changed() {
+            thread::sleep(interval);
+        }
+    }
+}
 
-    (list, pet_detail)
+/// How often `notify-daemon` looks for a signal to stop while it waits.
+const NOTIFY_STOP_POLL: Duration = Duration::from_millis(200);
+
+/// `pet-cli notify-daemon`: sends the reminders that are due every
+/// `interval`, or just once with `once`. SIGTERM and Ctrl+C stop it between
+/// checks, so what was sent is always saved.
+fn run_notify_daemon(
+    channels: &notify::Channels,
+    snooze: Duration,
+    interval: Duration,
+    once: bool,
+) -> Result<(), AppError> {
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in [signal_hook::SIGTERM, signal_hook::SIGINT] {
+        signal_hook::flag::register(signal, Arc::clone(&stop)).map_err(AppError::Runtime)?;
+    }
+    let sent_path = notify::path_for(Path::new(&workspace_db()));
+    loop {
+        match send_reminders(channels, snooze, &sent_path) {
+            Err(err) if once => return Err(err),
+            Err(err) => eprintln!("{}, trying again at the next check", err),
+            Ok(()) => {}
+        }
+        if once {
+            return Ok(());
+        }
+        let next_check = Instant::now() + interval;
+        loop {
+            if stop.load(Ordering::Relaxed) {
+                log::info!("notify-daemon stopped");
+                return Ok(());
+            }
+            let now = Instant::now();
+            if now >= next_check {
+                break;
+            }
+            thread::sleep(NOTIFY_STOP_POLL.min(next_check - now));
+        }
+    }
 }
 
-fn read_db() -> Result<Vec<Pet>, Error> {
-    let db_content = fs::read_to_string(DB_PATH)?;
-    let parsed: Vec<Pet> = serde_json::from_str(&db_content)?;
-    Ok(parsed)
+/// Sends the reminders due now to `channels` and saves when they were sent
+/// to `sent_path`. A reminder that reached at least one channel counts as
+/// sent; one that reached none is tried again at the next check.
+fn send_reminders(
+    channels: &notify::Channels,
+    snooze: Duration,
+    sent_path: &Path,
+) -> Result<(), AppError> {
+    let store_error = |source| AppError::File {
+        path: sent_path.to_owned(),
+        source,
+    };
+    let pets = read_db().map_err(AppError::db_read)?;
+    let mut sent = notify::Sent::load(sent_path).map_err(store_error)?;
+    let now = Utc::now();
+    let today = now.with_timezone(&Local).date_naive();
+    let reminders = reminders::compute_reminders(&pets, today);
+    let db_path = hooks::db_path(Path::new(&workspace_db()));
+    for reminder in sent.due(&reminders, now, snooze) {
+        let failures = notify::send(channels, reminder, today, now, &db_path);
+        for (channel, err) in &failures {
+            log::error!("sending a reminder by {} failed: {}", channel, err);
+            eprintln!(
+                "could not send \"{}\" by {}: {}",
+                reminder.describe(today),
+                channel,
+                err
+            );
+        }
+        if failures.len() < channels.len() {
+            log::info!("sent {}", reminder.describe(today));
+            sent.mark(reminder, now);
+        }
+    }
+    sent.save(sent_path).map_err(store_error)
 }
 
-fn add_random_pet_to_db() -> Result<Vec<Pet>, Error> {
-    let mut rng = rand::thread_rng();
-    let db_content = fs::read_to_string(DB_PATH)?;
-    let mut parsed: Vec<Pet> = serde_json::from_str(&db_content)?;
-    let catsdogs = match rng.gen_range(0, 1) {
-        0 => "cats",
-        _ => "dogs",
+/// `pet-cli category`: lists the categories, or renames or merges them as
+/// `=` does in the UI.
+fn run_category(options: WriteOptions, command: &cli::CategoryCommand) -> Result<(), AppError> {
+    let mut out = io::stdout().lock();
+    let rename = match command {
+        cli::CategoryCommand::List => {
+            let pets = read_db().map_err(AppError::db_read)?;
+            for (category, count) in categories::counts(&pets) {
+                writeln!(out, "{:>5}  {}", count, category)?;
+            }
+            return Ok(());
+        }
+        cli::CategoryCommand::Rename {
+            from,
+            to,
+            ignore_case,
+        } => categories::Rename::new(vec![from.clone()], to, *ignore_case),
+        cli::CategoryCommand::Merge {
+            categories,
+            into,
+            ignore_case,
+        } => categories::Rename::new(categories.clone(), into, *ignore_case),
     };
+    let rename = rename.unwrap_or_else(|problem| {
+        cli::Cli::command()
+            .error(clap::error::ErrorKind::InvalidValue, problem)
+            .exit()
+    });
+    match rename_categories(options, &rename).map_err(AppError::db_write)? {
+        0 => writeln!(out, "no pets to change for {}", rename)?,
+        count => writeln!(out, "renamed {} on {} pets", rename, count)?,
+    }
+    Ok(())
+}
 
-    let random_pet = Pet {
-        id: rng.gen_range(0, 9999999),
-        name: rng.sample_iter(Alphanumeric).take(10).collect(),
-        category: catsdogs.to_owned(),
-        age: rng.gen_range(1, 15),
-        created_at: Utc::now(),
+/// `pet-cli dedupe`: merges every group of duplicates into its first pet, or
+/// only prints what would be merged with `dry_run`.
+fn run_dedupe(options: WriteOptions, dry_run: bool) -> Result<(), AppError> {
+    let mut out = io::stdout().lock();
+    let pets = read_db().map_err(AppError::db_read)?;
+    let rows = duplicate_rows(&pets);
+    let mut groups: Vec<Vec<&Pet>> = Vec::new();
+    for (group, pet) in rows {
+        match groups.get_mut(group) {
+            Some(pets) => pets.push(pet),
+            None => groups.push(vec![pet]),
+        }
+    }
+    if groups.is_empty() {
+        writeln!(out, "no duplicates")?;
+        return Ok(());
+    }
+    for group in &groups {
+        let (survivor, others) = group.split_first().expect("groups have several pets");
+        let others_text: Vec<_> = others
+            .iter()
+            .map(|pet| format!("{:?} (#{})", pet.name, pet.id))
+            .collect();
+        writeln!(
+            out,
+            "{} {} into {:?} (#{})",
+            if dry_run { "would merge" } else { "merging" },
+            others_text.join(", "),
+            survivor.name,
+            survivor.id
+        )?;
+        if !dry_run {
+            let other_ids: Vec<usize> = others.iter().map(|pet| pet.id).collect();
+            merge_pets(options, survivor.id, &other_ids).map_err(AppError::db_write)?;
+        }
+    }
+    Ok(())
+}
+
+/// `pet-cli pick`: lets the user choose one of the pets of `view` in a
+/// quick open list and prints it. Everything else is drawn on stderr, so
+/// that stdout gets the pet alone, and keys are read from the terminal even
+/// if stdin is redirected. Esc exits with status 1 without printing.
+fn run_pick(
+    output: cli::PickOutput,
+    view: &ViewParams,
+    theme: &Theme,
+    lang: Lang,
+) -> Result<(), AppError> {
+    if !io::stderr().is_terminal() {
+        return Err(AppError::TerminalInit(io::Error::other(
+            "pick draws its list on stderr, which is not a terminal",
+        )));
+    }
+    let database = read_database().map_err(AppError::db_read)?;
+    let pets = view.apply(&database.pets);
+    let names: Vec<&str> = pets.iter().map(|pet| pet.name.as_str()).collect();
+
+    terminal::enable_raw_mode().map_err(|err| AppError::TerminalInit(crossterm_io(err)))?;
+    let mut terminal = match create_pick_terminal() {
+        Ok(terminal) => terminal,
+        Err(err) => {
+            let _ = terminal::disable_raw_mode();
+            let _ = execute!(io::stderr(), LeaveAlternateScreen);
+            return Err(err);
+        }
+    };
+    let mut quick_open = QuickOpen::default();
+    let title = strings::text(lang, Msg::PickPet);
+    let chosen = loop {
+        let drawn = terminal.draw(|rect| {
+            let area = rect.size();
+            render_quick_open(rect, area, &quick_open, &pets, title, theme)
+        });
+        if let Err(err) = drawn {
+            let _ = restore_pick_terminal(&mut terminal);
+            return Err(AppError::Terminal(err));
+        }
+        match crossterm::event::read() {
+            Ok(CEvent::Key(key)) => match quick_open.handle_key(key, &names) {
+                QuickOpenOutcome::Open => {}
+                QuickOpenOutcome::Cancelled => break None,
+                QuickOpenOutcome::Chosen(index) => break Some(pets[index]),
+            },
+            Ok(_) => {}
+            Err(err) => {
+                let _ = restore_pick_terminal(&mut terminal);
+                return Err(AppError::terminal(err));
+            }
+        }
     };
+    restore_pick_terminal(&mut terminal)?;
 
-    parsed.push(random_pet);
-    fs::write(DB_PATH, serde_json::to_vec(&parsed)?)?;
-    Ok(parsed)
+    let Some(pet) = chosen else {
+        process::exit(1);
+    };
+    let printed = picked_text(output, pet, &database.owners, lang)?;
+    writeln!(io::stdout().lock(), "{}", printed)?;
+    Ok(())
+}
+
+/// What `pick` prints of `pet`: one line, without the newline.
+fn picked_text(
+    output: cli::PickOutput,
+    pet: &Pet,
+    owners: &[Owner],
+    lang: Lang,
+) -> io::Result<String> {
+    Ok(match output {
+        cli::PickOutput::Json => serde_json::to_string(pet).map_err(io::Error::other)?,
+        cli::PickOutput::Field(field) => field
+            .value(pet, owners, Utc::now(), lang)
+            .unwrap_or_default(),
+    })
+}
+
+fn create_pick_terminal() -> Result<Terminal<CrosstermBackend<io::Stderr>>, AppError> {
+    let mut stderr = io::stderr();
+    execute!(stderr, EnterAlternateScreen)
+        .map_err(|err| AppError::TerminalInit(crossterm_io(err)))?;
+    let mut terminal =
+        Terminal::new(CrosstermBackend::new(stderr)).map_err(AppError::TerminalInit)?;
+    terminal.hide_cursor().map_err(AppError::TerminalInit)?;
+    terminal.clear().map_err(AppError::TerminalInit)?;
+    Ok(terminal)
+}
+
+fn restore_pick_terminal(
+    terminal: &mut Terminal<CrosstermBackend<io::Stderr>>,
+) -> Result<(), AppError> {
+    terminal::disable_raw_mode().map_err(AppError::terminal)?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen).map_err(AppError::terminal)?;
+    terminal.show_cursor().map_err(AppError::Terminal)?;
+    Ok(())
+}
+
+/// `pet-cli check`: prints what is wrong with the DB and exits with status 1
+/// if any of it is an error.
+fn run_check() -> Result<(), AppError> {
+    let report = integrity::check(&read_db_content().map_err(AppError::db_read)?);
+    for line in integrity_lines(&report) {
+        writeln!(io::stdout().lock(), "{}", line)?;
+    }
+    if report.count(integrity::Severity::Error) > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// The findings of `report` as a table, then the size of the DB and the
+/// totals.
+fn integrity_lines(report: &integrity::Report) -> Vec<String> {
+    let mut lines = if report.findings.is_empty() {
+        vec!["no problems found".to_owned()]
+    } else {
+        integrity::render_table(&report.findings)
+    };
+    lines.push(format!(
+        "{}: {} bytes, {} pets ({} in the Trash), {} owners; {}",
+        workspace_db(),
+        report.bytes,
+        report.pets,
+        report.archived,
+        report.owners,
+        report.describe()
+    ));
+    lines
+}
+
+/// Checks the DB file as it is on disk for the status bar and `!`.
+fn check_integrity(app_state: &mut AppState) {
+    app_state.integrity = match read_db_content() {
+        Ok(content) => Some(integrity::check(&content)),
+        Err(err) => {
+            log::error!("reading {} to check it failed: {}", workspace_db(), err);
+            None
+        }
+    };
+}
+
+/// `pet-cli import-pet`: adds the pets of a shared file to the DB.
+fn run_import(options: WriteOptions, file: &Path) -> Result<(), AppError> {
+    let failed = |message: String| AppError::Import {
+        path: file.to_owned(),
+        message,
+    };
+    let content = fs::read_to_string(file).map_err(|err| failed(err.to_string()))?;
+    let pets = share::parse(&content, Utc::now()).map_err(failed)?;
+    let imported =
+        import_pets(options, pets, &Reporter::none()).map_err(|err| failed(err.to_string()))?;
+    writeln!(io::stdout().lock(), "{}", describe_import(&imported))?;
+    Ok(())
+}
+
+/// `pet-cli import`: adds the rows of `table` in `file` as pets, mapped by
+/// `map`. They have no ids of ours, so the only conflicts are pets that are
+/// already here, which are left out as by `pet-cli merge`.
+fn run_table_import(
+    options: WriteOptions,
+    from: ImportFrom,
+    file: &Path,
+    table: &str,
+    map: &str,
+    date_order: Option<DateOrder>,
+    dry_run: bool,
+) -> Result<(), AppError> {
+    let mut out = io::stdout().lock();
+    let failed = |message: String| AppError::Import {
+        path: file.to_owned(),
+        message,
+    };
+    let mapping = table_import::parse_mapping(map).map_err(failed)?;
+    let table = match from {
+        ImportFrom::Sqlite => sqlite::read_table(file, table).map_err(failed)?,
+    };
+    let columns = table_import::resolve_columns(&mapping, &table).map_err(failed)?;
+    let theirs = Database {
+        pets: table_import::pets(&table, &columns, Utc::now(), date_order).map_err(failed)?,
+        ..Database::default()
+    };
+    let plan = merge::plan(&read_database().map_err(AppError::db_read)?, &theirs);
+    for pet in plan
+        .added
+        .iter()
+        .chain(plan.conflicts.iter().map(|c| &c.theirs))
+    {
+        writeln!(out, "+ {} ({})", pet.name, pet.category)?;
+    }
+    for pet in &plan.duplicates {
+        writeln!(out, "= {} ({})", pet.name, pet.category)?;
+    }
+    if dry_run {
+        writeln!(
+            out,
+            "{} to import, {} already here",
+            plan.added.len() + plan.conflicts.len(),
+            plan.duplicates.len()
+        )?;
+        return Ok(());
+    }
+    let merged = merge_db(
+        options,
+        &theirs,
+        |_| Some(Strategy::Both),
+        &Reporter::none(),
+    )
+    .map_err(AppError::db_write)?;
+    writeln!(out, "{}", describe_import(&merged.added))?;
+    Ok(())
 }
 
-fn remove_pet_at_index(pet_list_state: &mut ListState) -> Result<(), Error> {
-    let Some(selected) = pet_list_state.selected() else {
+/// `pet-cli restore`: lists the backups, or restores the one numbered
+/// `choice` in that list after asking.
+fn run_restore(choice: Option<usize>, yes: bool, keep_backups: usize) -> Result<(), AppError> {
+    let mut out = io::stdout().lock();
+    let backup_dir = backup::backup_dir(Path::new(&workspace_db()));
+    let backups = backup::list(Path::new(&workspace_db())).map_err(|source| AppError::File {
+        path: backup_dir.clone(),
+        source,
+    })?;
+    let Some(choice) = choice else {
+        if backups.is_empty() {
+            writeln!(out, "no backups in {}", backup_dir.display())?;
+        }
+        for (number, backup) in backups.iter().enumerate() {
+            let pets = backup
+                .pet_count()
+                .map(|count| format!("{} pets", count))
+                .unwrap_or_else(|| "unreadable".to_owned());
+            writeln!(out, "{:>3}  {}  {}", number + 1, backup_time(backup), pets)?;
+        }
         return Ok(());
     };
-    let db_content = fs::read_to_string(DB_PATH)?;
-    let mut parsed: Vec<Pet> = serde_json::from_str(&db_content)?;
-    parsed.remove(selected);
-    fs::write(DB_PATH, serde_json::to_vec(&parsed)?)?;
-    if selected > 0 {
-        pet_list_state.select(Some(selected - 1));
-    } else {
-        pet_list_state.select(Some(0));
+    let Some(backup) = choice.checked_sub(1).and_then(|index| backups.get(index)) else {
+        return Err(AppError::NoSuchBackup(choice));
+    };
+    if !yes {
+        write!(
+            out,
+            "Replace {} with the backup from {}? (y/n) ",
+            workspace_db(),
+            backup_time(backup)
+        )?;
+        io::Write::flush(&mut io::stdout())?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if answer.trim() != "y" {
+            writeln!(out, "cancelled")?;
+            return Ok(());
+        }
+    }
+    backup::restore(Path::new(&workspace_db()), &backup.path, keep_backups).map_err(|source| {
+        AppError::File {
+            path: backup.path.clone(),
+            source,
+        }
+    })?;
+    writeln!(out, "restored backup from {}", backup_time(backup))?;
+    Ok(())
+}
+
+fn backup_time(backup: &backup::Backup) -> String {
+    local_time(backup.created_at)
+}
+
+fn local_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// `pet-cli audit`: prints the records of the workspace DB's audit file that
+/// `filter` lets through, warning about lines that aren't records.
+fn run_audit(filter: &audit::AuditFilter, format: audit::AuditFormat) -> Result<(), AppError> {
+    let path = audit::path_for(Path::new(&workspace_db()));
+    let (records, warnings) = audit::read(&path, filter).map_err(|source| AppError::File {
+        path: path.clone(),
+        source,
+    })?;
+    for warning in &warnings {
+        eprintln!("warning: {}", warning);
+    }
+    write!(
+        io::stdout().lock(),
+        "{}",
+        audit::render(&records, format).expect("audit records serialize")
+    )?;
+    Ok(())
+}
+
+/// `pet-cli migrate`: prints each migration as it runs, or with `dry_run`
+/// only which would.
+fn run_migrate(options: WriteOptions, dry_run: bool) -> Result<(), AppError> {
+    let mut out = io::stdout().lock();
+    fs::metadata(workspace_db()).map_err(|err| AppError::db_read(err.into()))?;
+    let options = WriteOptions {
+        read_only: options.read_only || dry_run,
+        ..options
+    };
+    let steps = migrate_db(options).map_err(AppError::db_read)?;
+    if steps.is_empty() {
+        writeln!(
+            out,
+            "{} is at schema version {}, nothing to migrate",
+            workspace_db(),
+            migrations::CURRENT_VERSION
+        )?;
+    }
+    for step in steps {
+        writeln!(out, "{} -> {}: {}", step.from, step.to, step.description)?;
+    }
+    if options.read_only && !dry_run {
+        writeln!(out, "not migrated, the DB is read-only")?;
+    }
+    Ok(())
+}
+
+/// A DB file other than the DB, like one to compare or merge with.
+fn read_db_file(path: &Path) -> Result<Database, AppError> {
+    let failed = |source| AppError::DbRead {
+        path: path.display().to_string(),
+        source,
+    };
+    let content = compression::read_to_string(path).map_err(|err| failed(err.into()))?;
+    db_format::parse(&content)
+        .and_then(|database| check_schema(&database).map(|()| database))
+        .map_err(failed)
+}
+
+/// `pet-cli merge`: prints the plan, asks about each conflict unless
+/// `strategy` decides them all, and merges.
+fn run_merge(
+    options: WriteOptions,
+    file: &Path,
+    strategy: Option<Strategy>,
+    dry_run: bool,
+) -> Result<(), AppError> {
+    let mut out = io::stdout().lock();
+    let theirs = read_db_file(file)?;
+    let plan = merge::plan(&read_database().map_err(AppError::db_read)?, &theirs);
+    for pet in &plan.added {
+        writeln!(out, "+ #{} {} ({})", pet.id, pet.name, pet.category)?;
     }
+    for pet in &plan.duplicates {
+        writeln!(out, "= #{} {} ({})", pet.id, pet.name, pet.category)?;
+    }
+    writeln!(out, "{}", plan.summary())?;
+    if dry_run {
+        return Ok(());
+    }
+    let mut chosen = HashMap::new();
+    if strategy.is_none() {
+        for conflict in &plan.conflicts {
+            let Some(choice) = ask_resolution(conflict, file)? else {
+                writeln!(out, "nothing was merged")?;
+                return Ok(());
+            };
+            chosen.insert(conflict.theirs.id, choice);
+        }
+    }
+    let resolve = |conflict: &Conflict| chosen.get(&conflict.theirs.id).copied().or(strategy);
+    let merged =
+        merge_db(options, &theirs, resolve, &Reporter::none()).map_err(AppError::db_write)?;
+    writeln!(out, "{}", merged.summary())?;
+    Ok(())
+}
+
+/// Shows how the pet in `file` differs from ours and asks which to keep,
+/// until the answer is one of the choices. `None` at the end of the input.
+fn ask_resolution(conflict: &Conflict, file: &Path) -> Result<Option<Strategy>, AppError> {
+    let mut out = io::stdout().lock();
+    let (mine, theirs) = (&conflict.mine, &conflict.theirs);
+    writeln!(
+        out,
+        "~ #{} {} ({}) is another pet in {}",
+        mine.id,
+        mine.name,
+        mine.category,
+        file.display()
+    )?;
+    for change in conflict.differences() {
+        writeln!(
+            out,
+            "    {}: {} -> {}",
+            change.field, change.old, change.new
+        )?;
+    }
+    loop {
+        write!(out, "Keep (m)ine, (t)heirs or (b)oth for #{}? ", theirs.id)?;
+        io::Write::flush(&mut io::stdout())?;
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        match answer.trim() {
+            "m" => return Ok(Some(Strategy::Mine)),
+            "t" => return Ok(Some(Strategy::Theirs)),
+            "b" => return Ok(Some(Strategy::Both)),
+            _ => {}
+        }
+    }
+}
+
+/// `pet-cli diff`: prints what changed from `old` to `new`, then the totals.
+fn run_diff(old: &Path, new: &Path) -> Result<(), AppError> {
+    let mut out = io::stdout().lock();
+    let diff = diff::diff_pets(&read_db_file(old)?.pets, &read_db_file(new)?.pets);
+    for line in diff::lines(&diff) {
+        writeln!(out, "{}", line.text)?;
+    }
+    writeln!(out, "{}", diff.summary())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages_and_codes(errors: Vec<AppError>) -> Vec<(String, i32)> {
+        errors
+            .into_iter()
+            .map(|err| (err.to_string(), err.exit_code()))
+            .collect()
+    }
+
+    #[test]
+    fn errors_say_what_failed_and_exit_by_cause() {
+        let not_found = || io::Error::new(io::ErrorKind::NotFound, "No such file or directory");
+        let errors = vec![
+            AppError::NoSuchBackup(4),
+            AppError::DbRead {
+                path: "data/db.json".to_owned(),
+                source: Error::ReadDBError(not_found()),
+            },
+            AppError::DbWrite {
+                path: "data/db.json".to_owned(),
+                source: Error::ReadOnly,
+            },
+            AppError::Validation("name is empty".to_owned()),
+            AppError::TerminalInit(not_found()),
+            AppError::EventChannelClosed,
+            AppError::NoTerminal,
+            AppError::File {
+                path: PathBuf::from("pets.csv"),
+                source: not_found(),
+            },
+        ];
+        assert_eq!(
+            messages_and_codes(errors),
+            vec![
+                (
+                    "no backup number 4, run `pet-cli restore` to list them".to_owned(),
+                    2
+                ),
+                (
+                    "data/db.json: error reading the DB file: No such file or directory".to_owned(),
+                    3
+                ),
+                ("data/db.json: the DB is read-only".to_owned(), 1),
+                (
+                    "invalid pet, nothing was changed: name is empty".to_owned(),
+                    1
+                ),
+                (
+                    "could not set up the terminal: No such file or directory".to_owned(),
+                    4
+                ),
+                (
+                    "the terminal stopped sending input, even after reopening it".to_owned(),
+                    4
+                ),
+                (
+                    "interactive mode requires a terminal; try `pet-cli list`".to_owned(),
+                    5
+                ),
+                ("pets.csv: No such file or directory".to_owned(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn config_errors_from_the_file_mention_safe_mode() {
+        let invalid = |from| config::ConfigError::Invalid {
+            key: "theme",
+            value: "plaid".to_owned(),
+            from,
+            message: "valid themes are: dark".to_owned(),
+        };
+        let from_file = AppError::from(invalid(config::Source::File(PathBuf::from("c.toml"))));
+        assert_eq!(
+            from_file.to_string(),
+            "invalid theme 'plaid' from c.toml: valid themes are: dark\n\
+             (--safe-mode starts without the config file)"
+        );
+        assert_eq!(from_file.exit_code(), 2);
+        let from_flag = AppError::from(invalid(config::Source::Flag));
+        assert_eq!(
+            from_flag.to_string(),
+            "invalid theme 'plaid' from command line: valid themes are: dark"
+        );
+    }
+
+    #[test]
+    fn a_failed_startup_exits_with_its_first_failed_check() {
+        let mut report = startup::Report::default();
+        report.warn(startup::Check::Config, "unknown key `colour`");
+        report.fail(
+            startup::Check::Lock,
+            "the DB is locked",
+            "close the other pet-cli",
+        );
+        report.fail(
+            startup::Check::DbFile,
+            "data/db.json is encrypted",
+            "restore a backup",
+        );
+        let err = AppError::Startup(report);
+        assert_eq!(err.exit_code(), 3);
+        assert!(err.to_string().starts_with("pet-cli could not start:"));
+    }
+
+    #[test]
+    fn refused_writes_are_validation_errors() {
+        let _db_in_use = ui_tests::DB_IN_USE
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        set_workspace_db(Path::new("data/db.json"));
+        let err = AppError::db_write(Error::InvalidPetError("name is empty".to_owned()));
+        assert!(matches!(err, AppError::Validation(_)));
+        let err = AppError::db_write(Error::ConflictError);
+        assert_eq!(
+            err.to_string(),
+            "data/db.json: the DB was changed by another program meanwhile, nothing was changed"
+        );
+        assert_eq!(AppError::db_read(Error::EncryptedDBError).exit_code(), 3);
+    }
+
+    #[test]
+    fn every_write_moves_the_revision_on() {
+        let _harness = ui_tests::Harness::new(Vec::new());
+        let options = AppState::default().write_options;
+        let start = read_database().unwrap().revision;
+        for number in 1..=3 {
+            let mut database = read_database().unwrap();
+            write_db(&mut database, options).unwrap();
+            assert_eq!(database.revision, start + number);
+            assert_eq!(read_database().unwrap().revision, start + number);
+        }
+    }
+
+    #[test]
+    fn a_write_over_a_newer_file_is_a_conflict() {
+        let _harness = ui_tests::Harness::new(vec![ui_tests::pet(1, "Coco", "cats")]);
+        let options = AppState::default().write_options;
+        let mut stale = read_database().unwrap();
+        let mut newer = read_database().unwrap();
+        newer.pets[0].name = "Cocoa".to_owned();
+        write_db(&mut newer, options).unwrap();
+        stale.pets.clear();
+        assert!(matches!(
+            write_db(&mut stale, options),
+            Err(Error::ConflictError)
+        ));
+        let on_disk = read_database().unwrap();
+        assert_eq!(on_disk.revision, newer.revision);
+        assert_eq!(on_disk.pets[0].name, "Cocoa");
+    }
+
+    #[test]
+    fn a_failed_write_keeps_the_revision_it_was_read_at() {
+        let _harness = ui_tests::Harness::new(vec![ui_tests::pet(1, "Coco", "cats")]);
+        let options = AppState::default().write_options;
+        let mut database = read_database().unwrap();
+        let read_at = database.revision;
+        // A directory where the temporary file goes, so the write fails.
+        let temp_path = format!("{}.tmp", db_path());
+        fs::create_dir(&temp_path).unwrap();
+        database.pets[0].name = "Cocoa".to_owned();
+        assert!(matches!(
+            write_db(&mut database, options),
+            Err(Error::ReadDBError(_))
+        ));
+        assert_eq!(database.revision, read_at);
+        assert_eq!(read_database().unwrap().revision, read_at);
+
+        fs::remove_dir(&temp_path).unwrap();
+        write_db(&mut database, options).unwrap();
+        assert_eq!(database.revision, read_at + 1);
+        assert_eq!(read_database().unwrap().pets[0].name, "Cocoa");
+    }
+
+    #[test]
+    fn conflicts_are_retried_a_few_times() {
+        let mut attempts = 0;
+        let result: Result<(), Error> = retry_on_conflict(|| {
+            attempts += 1;
+            Err(Error::ConflictError)
+        });
+        assert!(matches!(result, Err(Error::ConflictError)));
+        assert_eq!(attempts, WRITE_ATTEMPTS);
+
+        let mut attempts = 0;
+        let result = retry_on_conflict(|| {
+            attempts += 1;
+            if attempts < WRITE_ATTEMPTS {
+                Err(Error::ConflictError)
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), WRITE_ATTEMPTS);
+
+        let mut attempts = 0;
+        let result: Result<(), Error> = retry_on_conflict(|| {
+            attempts += 1;
+            Err(Error::ReadOnly)
+        });
+        assert!(matches!(result, Err(Error::ReadOnly)));
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn an_add_retried_after_a_conflict_keeps_both_changes() {
+        let _harness = ui_tests::Harness::new(vec![ui_tests::pet(1, "Coco", "cats")]);
+        let options = AppState::default().write_options;
+        let mut sneaked_in = false;
+        let added = retry_on_conflict(|| {
+            let mut database = read_database()?;
+            if !sneaked_in {
+                sneaked_in = true;
+                let mut other = read_database()?;
+                other.pets[0].name = "Cocoa".to_owned();
+                write_db(&mut other, options)?;
+            }
+            let pet = ui_tests::pet(database.next_id.next(&database.pets), "Rex", "dogs");
+            database.pets.push(pet.clone());
+            write_db(&mut database, options)?;
+            Ok(pet)
+        })
+        .unwrap();
+        let names: Vec<String> = read_database()
+            .unwrap()
+            .pets
+            .into_iter()
+            .map(|pet| pet.name)
+            .collect();
+        assert_eq!(names, vec!["Cocoa", "Rex"]);
+        assert_eq!(added.id, 2);
+    }
+
+    #[test]
+    fn adds_from_many_threads_are_all_kept() {
+        let _harness = ui_tests::Harness::new(vec![ui_tests::pet(1, "Coco", "cats")]);
+        let options = AppState::default().write_options;
+        let start = read_database().unwrap().revision;
+        let threads: Vec<_> = (0..8)
+            .map(|thread| {
+                std::thread::spawn(move || {
+                    for number in 0..5 {
+                        let name = format!("owner {}-{}", thread, number);
+                        add_owner(options, name, String::new()).unwrap();
+                        copy_pet(options, 1, format!("Coco {}-{}", thread, number)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let database = read_database().unwrap();
+        assert_eq!(database.revision, start + 80);
+        let owner_ids: HashSet<usize> = database.owners.iter().map(|owner| owner.id).collect();
+        assert_eq!(owner_ids.len(), 40);
+        let pet_ids: HashSet<usize> = database.pets.iter().map(|pet| pet.id).collect();
+        assert_eq!(pet_ids.len(), 41);
+    }
+
+    /// A reporter that cancels once `done` pets are done.
+    fn cancelling_at(done: usize) -> Reporter {
+        let cancel = progress::Cancel::default();
+        let flag = cancel.clone();
+        Reporter::new(
+            move |progress| {
+                if progress.done == done {
+                    flag.cancel();
+                }
+            },
+            cancel,
+        )
+    }
+
+    #[test]
+    fn a_cancelled_import_writes_nothing() {
+        let _harness = ui_tests::Harness::new(vec![ui_tests::pet(1, "Coco", "cats")]);
+        let options = AppState::default().write_options;
+        let before = read_database().unwrap();
+        let pets: Vec<Pet> = (0..1200)
+            .map(|number| ui_tests::pet(0, &format!("Pet {}", number), "dogs"))
+            .collect();
+        let err = import_pets(options, pets.clone(), &cancelling_at(500)).unwrap_err();
+        assert!(matches!(err, Error::Cancelled));
+        let after = read_database().unwrap();
+        assert_eq!(after.revision, before.revision);
+        assert_eq!(after.pets.len(), 1);
+        assert_eq!(
+            import_pets(options, pets, &Reporter::none()).unwrap().len(),
+            1200
+        );
+        assert_eq!(read_database().unwrap().pets.len(), 1201);
+    }
+
+    #[test]
+    fn a_cancelled_generate_writes_nothing() {
+        let _harness = ui_tests::Harness::new(Vec::new());
+        let options = AppState::default().write_options;
+        let names = generator::Names::default();
+        let err = add_random_pets_to_db(options, 1500, Some(1), &names, &cancelling_at(1000))
+            .unwrap_err();
+        assert!(matches!(err, Error::Cancelled));
+        assert!(read_database().unwrap().pets.is_empty());
+    }
+
+    const EXTRA_FIELDS: &str = include_str!("../tests/fixtures/extra_fields.json");
+
+    #[test]
+    fn unknown_fields_survive_a_write() {
+        let (_harness, path) = db_file(EXTRA_FIELDS);
+        let options = AppState::default().write_options;
+        update_pet(options, 1, |pet| pet.name = "Cocoa".to_owned()).unwrap();
+        update_pet(options, 2, |pet| pet.age.months = 6).unwrap();
+        let before: serde_json::Value = serde_json::from_str(EXTRA_FIELDS).unwrap();
+        let after: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(after["pets"][0]["name"], "Cocoa");
+        for key in ["vet", "vaccinations", "insurance"] {
+            assert_eq!(after["pets"][0][key], before["pets"][0][key], "{}", key);
+        }
+        assert_eq!(
+            after["pets"][0]["insurance"]["notes"],
+            serde_json::Value::Null
+        );
+        assert!(after["pets"][1].get("vet").is_none());
+    }
+
+    #[test]
+    fn unknown_fields_survive_a_dedupe() {
+        let (_harness, path) = db_file(EXTRA_FIELDS);
+        let options = AppState::default().write_options;
+        let merged = merge_pets(options, 2, &[1]).unwrap().unwrap();
+        assert_eq!(merged.extra["vet"], "Dr. Smith");
+        let before: serde_json::Value = serde_json::from_str(EXTRA_FIELDS).unwrap();
+        let after: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(after["pets"].as_array().unwrap().len(), 1);
+        assert_eq!(after["pets"][0]["id"], 2);
+        for key in ["vet", "vaccinations", "insurance"] {
+            assert_eq!(after["pets"][0][key], before["pets"][0][key], "{}", key);
+        }
+    }
+
+    #[test]
+    fn strict_schema_refuses_unknown_fields_per_pet() {
+        let (_harness, _) = db_file(EXTRA_FIELDS);
+        STRICT_SCHEMA.store(true, Ordering::Relaxed);
+        let result = read_database();
+        STRICT_SCHEMA.store(false, Ordering::Relaxed);
+        let Err(err) = result else {
+            panic!("read with unknown fields");
+        };
+        assert!(matches!(&err, Error::UnknownFields(records)
+            if records == "pet #1: insurance, vaccinations, vet"));
+        assert_eq!(read_database().unwrap().pets.len(), 2);
+    }
+
+    /// A DB with `content` as its file, and its path.
+    fn db_file(content: &str) -> (ui_tests::Harness, PathBuf) {
+        let harness = ui_tests::Harness::new(Vec::new());
+        let path = PathBuf::from(db_path());
+        fs::write(&path, content).unwrap();
+        (harness, path)
+    }
+
+    #[test]
+    fn an_old_db_is_backed_up_then_rewritten_in_the_current_shape() {
+        let old = include_str!("../tests/fixtures/migrations/v0.json");
+        let (_harness, path) = db_file(old);
+        let options = AppState::default().write_options;
+        let steps = migrate_db(options).unwrap();
+        let ran: Vec<(u64, u64)> = steps.iter().map(|step| (step.from, step.to)).collect();
+        assert_eq!(ran, [(0, 1), (1, 2), (2, 3)]);
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            db_format::schema_version(&content),
+            migrations::CURRENT_VERSION
+        );
+        let backups = backup::list(&path).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(&backups[0].path).unwrap(), old);
+        assert_eq!(read_database().unwrap().pets[0].age.months, 24);
+        assert!(migrate_db(options).unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_failed_backup_stops_the_write() {
+        let mut rex = ui_tests::pet(1, "Rex", "dogs");
+        rex.archived_at = Some(Utc::now());
+        let _harness = ui_tests::Harness::new(vec![rex]);
+        let path = PathBuf::from(db_path());
+        let before = fs::read(&path).unwrap();
+        // A file where the backups directory goes, so no backup can be made.
+        fs::write(path.with_file_name("backups"), "").unwrap();
+        let options = AppState::default().write_options;
+        let err = purge_pets(options, &HashSet::from([1])).unwrap_err();
+        assert!(matches!(err, Error::BackupDBError(_)), "{:?}", err);
+        assert_eq!(fs::read(&path).unwrap(), before);
+    }
+
+    #[test]
+    fn a_dry_run_migration_changes_nothing() {
+        let old = include_str!("../tests/fixtures/migrations/v2.json");
+        let (_harness, path) = db_file(old);
+        let options = WriteOptions {
+            read_only: true,
+            ..AppState::default().write_options
+        };
+        let steps = migrate_db(options).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), old);
+        assert!(backup::list(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_newer_db_is_not_migrated() {
+        let newer = format!(
+            "{{\"schema_version\": {}, \"pets\": []}}",
+            migrations::CURRENT_VERSION + 1
+        );
+        let (_harness, path) = db_file(&newer);
+        let Err(err) = migrate_db(AppState::default().write_options) else {
+            panic!("a newer DB was migrated");
+        };
+        assert!(matches!(
+            err,
+            Error::MigrationError(migrations::MigrationError::Newer(_))
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+    }
+
+    #[test]
+    fn only_a_closed_pipe_ends_output_quietly() {
+        let pipe = AppError::Output(io::Error::new(io::ErrorKind::BrokenPipe, "closed"));
+        assert!(pipe.is_broken_pipe());
+        let full = AppError::Output(io::Error::other("disk full"));
+        assert!(!full.is_broken_pipe());
+        assert_eq!(full.to_string(), "could not write the output: disk full");
+    }
+
+    #[test]
+    fn pick_prints_one_line_of_the_chosen_pet() {
+        let rex = ui_tests::pet(7, "Rex", "dogs");
+        let text = |output| picked_text(output, &rex, &[], Lang::En).unwrap();
+        assert_eq!(text(cli::PickOutput::Field(fields::PetField::Id)), "7");
+        assert_eq!(text(cli::PickOutput::Field(fields::PetField::Name)), "Rex");
+        let json = text(cli::PickOutput::Json);
+        assert!(!json.contains('\n'), "{}", json);
+        let parsed: Pet = serde_json::from_str(&json).unwrap();
+        assert_eq!((parsed.id, parsed.name), (7, "Rex".to_owned()));
+    }
+
+    #[test]
+    fn a_demo_leaves_the_config_file_alone() {
+        let demo = cli::Cli::try_parse_from(["pet-cli", "--demo", "--keep"]).unwrap();
+        assert!(demo.made_up_db() && demo.keep);
+        assert!(matches!(config_files(&demo), config::ConfigFiles::Skip));
+        let ui = cli::Cli::try_parse_from(["pet-cli"]).unwrap();
+        assert!(matches!(config_files(&ui), config::ConfigFiles::Read));
+        assert!(cli::Cli::try_parse_from(["pet-cli", "--keep"]).is_err());
+        assert!(cli::Cli::try_parse_from(["pet-cli", "--demo", "--config", "x.toml"]).is_err());
+    }
+
+    #[test]
+    fn the_wizard_stays_away_when_told_what_to_open() {
+        for args in [
+            &["pet-cli", "check"][..],
+            &["pet-cli", "--demo"],
+            &["pet-cli", "--tutorial"],
+            &["pet-cli", "--safe-mode"],
+            &["pet-cli", "--dry-run"],
+            &["pet-cli", "--workspace", "clinic"],
+            &["pet-cli", "--config", "other.toml"],
+        ] {
+            let cli = cli::Cli::try_parse_from(args).unwrap();
+            assert!(!is_first_run(&cli), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn the_split_moves_in_steps_and_stops_short_of_the_edges() {
+        assert_eq!(resize_split(DEFAULT_LIST_WIDTH, LIST_WIDTH_STEP), 25);
+        assert_eq!(resize_split(DEFAULT_LIST_WIDTH, -LIST_WIDTH_STEP), 15);
+        assert_eq!(resize_split(15, -LIST_WIDTH_STEP), 10);
+        assert_eq!(resize_split(10, -LIST_WIDTH_STEP), 10);
+        assert_eq!(resize_split(90, LIST_WIDTH_STEP), 90);
+        assert_eq!(resize_split(88, LIST_WIDTH_STEP), 90);
+        assert_eq!(resize_split(0, 0), 10);
+        assert_eq!(resize_split(100, 0), 90);
+    }
+
+    #[test]
+    fn narrow_screens_stack_the_panes() {
+        assert!(stack_panes(0));
+        assert!(stack_panes(MIN_SIDE_BY_SIDE_WIDTH - 1));
+        assert!(!stack_panes(MIN_SIDE_BY_SIDE_WIDTH));
+        assert!(!stack_panes(200));
+
+        let wide = create_pet_rects(&Rect::new(0, 0, 100, 40), 30, true);
+        assert_eq!((wide.names.width, wide.names.height), (30, 40));
+        assert_eq!(wide.details.x, 30);
+        let narrow = create_pet_rects(&Rect::new(0, 0, 20, 40), 30, true);
+        assert_eq!((narrow.names.width, narrow.names.height), (20, 12));
+        assert_eq!(narrow.details.y, 12);
+    }
+}
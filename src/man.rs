@@ -0,0 +1,141 @@
+//! `pet-cli man`: a roff man page built from the clap definition of the CLI
+//! and `keys::BINDINGS`, so it documents exactly the flags and keys there are.
+
+use crate::keys;
+use clap::{Arg, Command};
+
+pub fn render(cli: &mut Command, db_path: &str) -> String {
+    cli.build();
+    let name = cli.get_name().to_owned();
+    let mut out = format!(".TH {} 1\n", escape(&name.to_uppercase()));
+    out.push_str(&format!(
+        ".SH NAME\n{} \\- {}\n",
+        escape(&name),
+        escape(&about(cli))
+    ));
+    out.push_str(&format!(
+        ".SH SYNOPSIS\n\\fB{}\\fR [\\fIOPTIONS\\fR] [\\fICOMMAND\\fR]\n",
+        escape(&name)
+    ));
+    out.push_str(&format!(
+        ".SH DESCRIPTION\nWithout a command, {} opens the terminal UI on the DB at \\fI{}\\fR.\n",
+        escape(&name),
+        escape(db_path)
+    ));
+    out.push_str(".SH OPTIONS\n");
+    for arg in cli.get_arguments().filter(|arg| !arg.is_hide_set()) {
+        push_arg(&mut out, arg);
+    }
+    out.push_str(".SH COMMANDS\n");
+    for command in cli
+        .get_subcommands()
+        .filter(|command| !command.is_hide_set() && command.get_name() != "help")
+    {
+        out.push_str(&format!(
+            ".SS \"{} {}\"\n{}\n",
+            escape(&name),
+            escape(command.get_name()),
+            escape(&about(command))
+        ));
+        // Global options are listed once above.
+        for arg in command
+            .get_arguments()
+            .filter(|arg| !arg.is_hide_set() && !arg.is_global_set())
+            .filter(|arg| arg.get_id() != "help")
+        {
+            push_arg(&mut out, arg);
+        }
+    }
+    out.push_str(".SH KEYS\nIn the terminal UI:\n");
+    let mut contexts: Vec<keys::Context> = Vec::new();
+    for (context, ..) in keys::BINDINGS {
+        if !contexts.contains(&context) {
+            contexts.push(context);
+        }
+    }
+    for context in contexts {
+        out.push_str(&format!(".SS \"{}\"\n", escape(&context.heading())));
+        for (_, modifier, key, description) in
+            keys::BINDINGS.iter().filter(|binding| binding.0 == context)
+        {
+            out.push_str(&format!(
+                ".TP\n\\fB{}\\fR\n{}\n",
+                escape(&keys::key_name(modifier, *key)),
+                escape(description)
+            ));
+        }
+    }
+    out.push_str(&format!(
+        ".SH FILES\n.TP\n\\fI{}\\fR\nThe DB, relative to the working directory.\n",
+        escape(db_path)
+    ));
+    out
+}
+
+fn push_arg(out: &mut String, arg: &Arg) {
+    let mut term = match (arg.get_long(), arg.get_short()) {
+        (Some(long), Some(short)) => {
+            format!("\\fB\\-{}\\fR, \\fB\\-\\-{}\\fR", short, escape(long))
+        }
+        (Some(long), None) => format!("\\fB\\-\\-{}\\fR", escape(long)),
+        (None, Some(short)) => format!("\\fB\\-{}\\fR", short),
+        (None, None) => String::new(),
+    };
+    if arg.get_action().takes_values() {
+        let value_name = arg
+            .get_value_names()
+            .and_then(|names| names.first())
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| arg.get_id().to_string().to_uppercase());
+        if !term.is_empty() {
+            term.push(' ');
+        }
+        term.push_str(&format!("\\fI{}\\fR", escape(&value_name)));
+    }
+    let mut description = arg
+        .get_long_help()
+        .or_else(|| arg.get_help())
+        .map(|help| help.to_string())
+        .unwrap_or_default();
+    let choices: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_owned())
+        .collect();
+    if arg.get_action().takes_values() && !choices.is_empty() {
+        description.push_str(&format!(" (one of: {})", choices.join(", ")));
+    }
+    let defaults: Vec<String> = arg
+        .get_default_values()
+        .iter()
+        .map(|value| value.to_string_lossy().into_owned())
+        .collect();
+    if arg.get_action().takes_values() && !defaults.is_empty() {
+        description.push_str(&format!(" [default: {}]", defaults.join(", ")));
+    }
+    out.push_str(&format!(".TP\n{}\n{}\n", term, escape(&description)));
+}
+
+fn about(command: &Command) -> String {
+    command
+        .get_about()
+        .map(|about| about.to_string())
+        .unwrap_or_default()
+}
+
+/// Escapes backslashes and hyphens, and keeps lines starting with `.` or `'`
+/// from being read as requests.
+fn escape(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line = line.replace('\\', "\\e").replace('-', "\\-");
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,54 @@
+use crate::Pet;
+use std::collections::HashSet;
+
+/// Pets marked for a bulk operation. Marks are kept by pet id so they stay on
+/// the same pets when the list changes order.
+#[derive(Default)]
+pub struct Marks {
+    marked: HashSet<usize>,
+    visual_anchor: Option<usize>,
+}
+
+impl Marks {
+    pub fn toggle(&mut self, pet_id: usize) {
+        if !self.marked.remove(&pet_id) {
+            self.marked.insert(pet_id);
+        }
+    }
+
+    /// Starts a visual range at `cursor`, or ends the active one and keeps
+    /// everything it covered marked.
+    pub fn toggle_visual(&mut self, pets: &[&Pet], cursor: usize) {
+        if self.visual_anchor.is_some() {
+            self.marked = self.effective(pets, cursor);
+            self.visual_anchor = None;
+        } else {
+            self.visual_anchor = Some(cursor);
+        }
+    }
+
+    pub fn in_visual_mode(&self) -> bool {
+        self.visual_anchor.is_some()
+    }
+
+    /// The ids of every marked pet, including the active visual range between
+    /// its anchor and `cursor`.
+    pub fn effective(&self, pets: &[&Pet], cursor: usize) -> HashSet<usize> {
+        let mut marked = self.marked.clone();
+        if let Some(anchor) = self.visual_anchor {
+            let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+            marked.extend(
+                pets.iter()
+                    .skip(start)
+                    .take(end + 1 - start)
+                    .map(|pet| pet.id),
+            );
+        }
+        marked
+    }
+
+    pub fn clear(&mut self) {
+        self.marked.clear();
+        self.visual_anchor = None;
+    }
+}
@@ -0,0 +1,419 @@
+use crate::keys;
+use crate::strings::{self, Lang, Msg};
+use crate::text;
+use crate::MenuItem;
+use clap::ValueEnum;
+
+/// An entry of the menu bar and the key that triggers it.
+#[derive(Copy, Clone, Debug)]
+pub struct MenuEntry {
+    pub title: Msg,
+    pub hotkey: char,
+    /// The tab it shows, for all but the actions and Quit.
+    pub tab: Option<MenuItem>,
+}
+
+/// Every entry, with the tabs in their default order.
+pub const MENU_ENTRIES: [MenuEntry; 11] = [
+    MenuEntry {
+        title: Msg::Home,
+        hotkey: keys::HOME,
+        tab: Some(MenuItem::Home),
+    },
+    MenuEntry {
+        title: Msg::Pets,
+        hotkey: keys::PETS,
+        tab: Some(MenuItem::Pets),
+    },
+    MenuEntry {
+        title: Msg::Owners,
+        hotkey: keys::OWNERS,
+        tab: Some(MenuItem::Owners),
+    },
+    MenuEntry {
+        title: Msg::Duplicates,
+        hotkey: keys::DUPLICATES,
+        tab: Some(MenuItem::Duplicates),
+    },
+    MenuEntry {
+        title: Msg::Trash,
+        hotkey: keys::TRASH,
+        tab: Some(MenuItem::Trash),
+    },
+    MenuEntry {
+        title: Msg::Log,
+        hotkey: keys::LOG,
+        tab: Some(MenuItem::Log),
+    },
+    MenuEntry {
+        title: Msg::Stats,
+        hotkey: keys::STATS,
+        tab: Some(MenuItem::Stats),
+    },
+    MenuEntry {
+        title: Msg::Add,
+        hotkey: keys::ADD,
+        tab: None,
+    },
+    MenuEntry {
+        title: Msg::Delete,
+        hotkey: keys::DELETE,
+        tab: None,
+    },
+    MenuEntry {
+        title: Msg::MergeDb,
+        hotkey: keys::MERGE_DB,
+        tab: None,
+    },
+    MenuEntry {
+        title: Msg::Quit,
+        hotkey: keys::QUIT,
+        tab: None,
+    },
+];
+
+/// The entries of the menu bar for the tabs of the `tabs` config key: those
+/// tabs in that order, then the actions and Quit, which are always there.
+pub fn entries(tabs: &[MenuItem]) -> Vec<MenuEntry> {
+    let tab_entries = tabs
+        .iter()
+        .filter_map(|tab| MENU_ENTRIES.iter().find(|entry| entry.tab == Some(*tab)));
+    let others = MENU_ENTRIES.iter().filter(|entry| entry.tab.is_none());
+    tab_entries.chain(others).copied().collect()
+}
+
+/// The title of `tab` in the menu.
+pub fn title(tab: MenuItem) -> Msg {
+    MENU_ENTRIES
+        .iter()
+        .find(|entry| entry.tab == Some(tab))
+        .map(|entry| entry.title)
+        .expect("every tab has an entry")
+}
+
+/// Reads the `tabs` config key, like `pets,stats,home`. At least one tab has
+/// to be left.
+pub fn parse_tabs(text: &str) -> Result<Vec<MenuItem>, String> {
+    let mut tabs = Vec::new();
+    for name in text
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let tab = MenuItem::from_str(name, true).map_err(|_| {
+            let names: Vec<String> = MenuItem::ALL.iter().map(|tab| tab_name(*tab)).collect();
+            format!("valid tabs are: {}", names.join(", "))
+        })?;
+        if tabs.contains(&tab) {
+            return Err(format!("{} is listed twice", name));
+        }
+        tabs.push(tab);
+    }
+    if tabs.is_empty() {
+        return Err("at least one tab must be enabled".to_owned());
+    }
+    Ok(tabs)
+}
+
+/// The inverse of `parse_tabs`.
+pub fn format_tabs(tabs: &[MenuItem]) -> String {
+    let names: Vec<String> = tabs.iter().map(|tab| tab_name(*tab)).collect();
+    names.join(",")
+}
+
+fn tab_name(tab: MenuItem) -> String {
+    tab.to_possible_value()
+        .expect("no skipped tabs")
+        .get_name()
+        .to_owned()
+}
+
+/// What the menu says about the app besides the titles, gathered each frame.
+#[derive(Default)]
+pub struct MenuCounts {
+    /// The pets the Pets tab shows, after its filters.
+    pub shown_pets: usize,
+    pub trashed_pets: usize,
+    /// The index of the entry whose tab has input that isn't saved yet, like
+    /// an open prompt.
+    pub unsaved: Option<usize>,
+}
+
+/// A menu entry as drawn in one frame. The hotkey is looked for in the title
+/// only, never in the suffix.
+#[derive(Debug, PartialEq)]
+pub struct MenuLabel {
+    pub title: &'static str,
+    pub hotkey: char,
+    /// Like ` (42)` or `*`, or empty.
+    pub suffix: String,
+}
+
+/// The labels of `entries`: the Pets tab with the number of pets it shows,
+/// the Trash with the number in it unless it is empty, and `*` after the tab
+/// with unsaved input.
+pub fn labels(entries: &[MenuEntry], lang: Lang, counts: &MenuCounts) -> Vec<MenuLabel> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let mut suffix = match entry.title {
+                Msg::Pets => format!(" ({})", counts.shown_pets),
+                Msg::Trash if counts.trashed_pets > 0 => format!(" ({})", counts.trashed_pets),
+                _ => String::new(),
+            };
+            if counts.unsaved == Some(index) {
+                suffix.push('*');
+            }
+            MenuLabel {
+                title: strings::text(lang, entry.title),
+                hotkey: entry.hotkey,
+                suffix,
+            }
+        })
+        .collect()
+}
+
+/// How much of the labels the menu bar has room for.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MenuFit {
+    /// The labels as they are, like `Home | Pets (12) | Quit`.
+    Full,
+    /// Only the hotkeys, like `h|p|q`.
+    Hotkeys,
+    /// Only the active tab, like `▾ Pets (12)`.
+    Collapsed,
+}
+
+/// How to draw `labels` in a menu bar `width` columns wide inside its
+/// borders. The full labels take a space either side and a divider between
+/// each, as the Tabs widget draws them; the hotkeys only the divider.
+pub fn fit(labels: &[MenuLabel], width: u16) -> MenuFit {
+    let width = usize::from(width);
+    let count = labels.len();
+    let full: usize = labels.iter().map(|label| text::width(&label.text())).sum();
+    if full + 3 * count <= width + 1 {
+        MenuFit::Full
+    } else if 2 * count <= width + 1 {
+        MenuFit::Hotkeys
+    } else {
+        MenuFit::Collapsed
+    }
+}
+
+impl MenuLabel {
+    /// The label as drawn in full, with the hotkey after the title if the
+    /// title doesn't have it.
+    pub fn text(&self) -> String {
+        match hotkey_range(self.title, self.hotkey) {
+            Some(_) => format!("{}{}", self.title, self.suffix),
+            None => format!("{} ({}){}", self.title, self.hotkey, self.suffix),
+        }
+    }
+}
+
+/// The byte range of the first character of `title` matching `hotkey`,
+/// ignoring case. `None` if the hotkey doesn't appear in the title.
+pub fn hotkey_range(title: &str, hotkey: char) -> Option<(usize, usize)> {
+    title
+        .char_indices()
+        .find(|(_, c)| c.to_lowercase().eq(hotkey.to_lowercase()))
+        .map(|(start, c)| (start, start + c.len_utf8()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suffixes(counts: &MenuCounts) -> Vec<(&'static str, String)> {
+        labels(&MENU_ENTRIES, Lang::En, counts)
+            .into_iter()
+            .filter(|label| !label.suffix.is_empty())
+            .map(|label| (label.title, label.suffix))
+            .collect()
+    }
+
+    #[test]
+    fn the_pets_tab_counts_the_pets_it_shows() {
+        let counts = MenuCounts {
+            shown_pets: 42,
+            ..MenuCounts::default()
+        };
+        assert_eq!(suffixes(&counts), [("Pets", " (42)".to_owned())]);
+        let filtered = MenuCounts {
+            shown_pets: 3,
+            trashed_pets: 2,
+            ..MenuCounts::default()
+        };
+        assert_eq!(
+            suffixes(&filtered),
+            [("Pets", " (3)".to_owned()), ("Trash", " (2)".to_owned())]
+        );
+    }
+
+    #[test]
+    fn an_empty_db_shows_zero_pets_and_no_trash_count() {
+        assert_eq!(
+            suffixes(&MenuCounts::default()),
+            [("Pets", " (0)".to_owned())]
+        );
+    }
+
+    #[test]
+    fn the_tab_with_unsaved_input_gets_an_asterisk() {
+        let counts = MenuCounts {
+            shown_pets: 4,
+            trashed_pets: 0,
+            unsaved: Some(1),
+        };
+        assert_eq!(suffixes(&counts), [("Pets", " (4)*".to_owned())]);
+        let counts = MenuCounts {
+            unsaved: Some(0),
+            ..MenuCounts::default()
+        };
+        assert_eq!(
+            suffixes(&counts),
+            [("Home", "*".to_owned()), ("Pets", " (0)".to_owned())]
+        );
+    }
+
+    #[test]
+    fn labels_follow_the_language() {
+        let counts = MenuCounts {
+            trashed_pets: 1,
+            ..MenuCounts::default()
+        };
+        let trash = labels(&MENU_ENTRIES, Lang::De, &counts)
+            .into_iter()
+            .find(|label| label.hotkey == keys::TRASH)
+            .unwrap();
+        assert_eq!(trash.text(), "Papierkorb (t) (1)");
+    }
+
+    #[test]
+    fn the_hotkey_is_only_looked_for_in_the_title() {
+        let label = MenuLabel {
+            title: "Home",
+            hotkey: '4',
+            suffix: " (42)".to_owned(),
+        };
+        assert_eq!(hotkey_range(label.title, label.hotkey), None);
+        assert_eq!(label.text(), "Home (4) (42)");
+        assert_eq!(hotkey_range("Pets", 'p'), Some((0, 1)));
+        assert_eq!(hotkey_range("Größe", 'ö'), Some((2, 4)));
+        assert_eq!(hotkey_range("Größe", 'Ö'), Some((2, 4)));
+    }
+
+    #[test]
+    fn the_menu_shortens_as_it_gets_narrower() {
+        let counts = MenuCounts {
+            shown_pets: 12,
+            ..MenuCounts::default()
+        };
+        let labels = labels(&MENU_ENTRIES, Lang::En, &counts);
+        // 64 columns of labels, 2 of padding each and 10 dividers.
+        for (width, expected) in [
+            (200, MenuFit::Full),
+            (96, MenuFit::Full),
+            (95, MenuFit::Hotkeys),
+            (34, MenuFit::Hotkeys),
+            (21, MenuFit::Hotkeys),
+            (20, MenuFit::Collapsed),
+            // A 10-column terminal, inside the menu's borders.
+            (8, MenuFit::Collapsed),
+            (0, MenuFit::Collapsed),
+        ] {
+            assert_eq!(fit(&labels, width), expected, "{} columns", width);
+        }
+    }
+
+    #[test]
+    fn fewer_or_longer_labels_change_where_the_menu_shortens() {
+        let counts = MenuCounts::default();
+        let tabs = entries(&[MenuItem::Pets]);
+        let few = labels(&tabs, Lang::En, &counts);
+        assert_eq!(fit(&few, 48), MenuFit::Full);
+        assert_eq!(fit(&few, 9), MenuFit::Hotkeys);
+        let german = labels(&MENU_ENTRIES, Lang::De, &counts);
+        let english = labels(&MENU_ENTRIES, Lang::En, &counts);
+        assert_eq!(fit(&english, 96), MenuFit::Full);
+        assert_eq!(fit(&german, 96), MenuFit::Hotkeys);
+    }
+
+    fn hotkeys(entries: &[MenuEntry]) -> String {
+        entries.iter().map(|entry| entry.hotkey).collect()
+    }
+
+    #[test]
+    fn the_menu_has_the_tabs_in_their_order_then_the_actions() {
+        let entries = entries(&[MenuItem::Stats, MenuItem::Pets, MenuItem::Home]);
+        let tabs: Vec<Option<MenuItem>> = entries.iter().map(|entry| entry.tab).collect();
+        assert_eq!(
+            tabs,
+            [
+                Some(MenuItem::Stats),
+                Some(MenuItem::Pets),
+                Some(MenuItem::Home),
+                None,
+                None,
+                None,
+                None
+            ]
+        );
+        assert_eq!(
+            hotkeys(&entries),
+            [
+                keys::STATS,
+                keys::PETS,
+                keys::HOME,
+                keys::ADD,
+                keys::DELETE,
+                keys::MERGE_DB,
+                keys::QUIT
+            ]
+            .iter()
+            .collect::<String>()
+        );
+        assert_eq!(
+            hotkeys(&super::entries(&MenuItem::ALL)),
+            hotkeys(&MENU_ENTRIES)
+        );
+    }
+
+    #[test]
+    fn a_tab_turned_off_leaves_its_hotkey_out_of_the_menu() {
+        let entries = entries(&[MenuItem::Home, MenuItem::Pets]);
+        assert!(!hotkeys(&entries).contains(keys::TRASH));
+        assert!(!hotkeys(&entries).contains(keys::STATS));
+    }
+
+    #[test]
+    fn quit_is_always_in_the_menu() {
+        for tabs in [&[][..], &[MenuItem::Log], &MenuItem::ALL] {
+            let entries = entries(tabs);
+            let last = entries.last().unwrap();
+            assert_eq!((last.title, last.hotkey), (Msg::Quit, keys::QUIT));
+        }
+        assert_eq!(entries(&[]).len(), 4);
+    }
+
+    #[test]
+    fn tabs_are_read_in_their_order_once_each() {
+        assert_eq!(
+            parse_tabs("pets, stats,home,").unwrap(),
+            [MenuItem::Pets, MenuItem::Stats, MenuItem::Home]
+        );
+        assert_eq!(
+            format_tabs(&[MenuItem::Pets, MenuItem::Stats, MenuItem::Home]),
+            "pets,stats,home"
+        );
+        assert_eq!(parse_tabs("pets,Pets").unwrap_err(), "Pets is listed twice");
+        assert_eq!(
+            parse_tabs(" , ").unwrap_err(),
+            "at least one tab must be enabled"
+        );
+        assert!(parse_tabs("pets,kennel")
+            .unwrap_err()
+            .starts_with("valid tabs are: home, pets,"));
+    }
+}
@@ -0,0 +1,342 @@
+//! Merging the pets and owners of another DB file into this one, for `pet-cli
+//! merge` and the Merge entry of the menu. The plan is made without touching
+//! either DB, so it can be shown and its conflicts decided on before anything
+//! is written.
+
+use crate::diff::{self, FieldChange};
+use crate::duplicates::normalize_name;
+use crate::{Database, Owner, Pet};
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+/// What to do about a pet of the other file that has the id of one of ours
+/// but isn't the same pet.
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum Strategy {
+    /// Keep our pet and leave theirs out.
+    Mine,
+    /// Replace our pet with theirs, under the same id.
+    Theirs,
+    /// Keep ours and add theirs under a new id.
+    Both,
+}
+
+#[derive(Debug)]
+pub struct Conflict {
+    pub mine: Pet,
+    pub theirs: Pet,
+    /// `None` until decided.
+    pub resolution: Option<Strategy>,
+}
+
+impl Conflict {
+    /// The fields in which their pet differs from ours, as `old` and `new`.
+    pub fn differences(&self) -> Vec<FieldChange> {
+        diff::diff_fields(&self.mine, &self.theirs)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MergePlan {
+    /// Their pets that are neither the same as one of ours nor have the id of
+    /// one, which are added under new ids.
+    pub added: Vec<Pet>,
+    /// Their pets that are the same as one of ours, which are left out.
+    pub duplicates: Vec<Pet>,
+    pub conflicts: Vec<Conflict>,
+    /// Their owners whose name none of ours has, which are added under new
+    /// ids.
+    pub new_owners: Vec<Owner>,
+    /// Their owners who are also ours, as their id and ours.
+    pub same_owners: Vec<(usize, usize)>,
+}
+
+impl MergePlan {
+    /// Like `3 to add, 1 already here, 2 conflicting`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} to add, {} already here, {} conflicting",
+            self.added.len(),
+            self.duplicates.len(),
+            self.conflicts.len()
+        )
+    }
+}
+
+/// What `apply` changed.
+#[derive(Debug, Default)]
+pub struct Merged {
+    /// As added, with their new ids.
+    pub added: Vec<Pet>,
+    /// Their pets that replaced ours.
+    pub replaced: Vec<Pet>,
+    pub owners_added: usize,
+}
+
+impl Merged {
+    /// Like `added 3 pets, replaced 1 and added 2 owners`.
+    pub fn summary(&self) -> String {
+        format!(
+            "added {} pets, replaced {} and added {} owners",
+            self.added.len(),
+            self.replaced.len(),
+            self.owners_added
+        )
+    }
+}
+
+/// How `theirs` would be merged into `mine`. Pets count as the same when
+/// their names and categories match, ignoring case and surrounding spaces,
+/// and so do their birthdates; owners when their names match.
+pub fn plan(mine: &Database, theirs: &Database) -> MergePlan {
+    let mut plan = MergePlan::default();
+    for pet in &theirs.pets {
+        if mine.pets.iter().any(|ours| same_pet(ours, pet)) {
+            plan.duplicates.push(pet.clone());
+        } else if let Some(ours) = mine.pets.iter().find(|ours| ours.id == pet.id) {
+            plan.conflicts.push(Conflict {
+                mine: ours.clone(),
+                theirs: pet.clone(),
+                resolution: None,
+            });
+        } else {
+            plan.added.push(pet.clone());
+        }
+    }
+    for owner in &theirs.owners {
+        let name = normalize_name(&owner.name);
+        match mine
+            .owners
+            .iter()
+            .find(|ours| normalize_name(&ours.name) == name)
+        {
+            Some(ours) => plan.same_owners.push((owner.id, ours.id)),
+            None => plan.new_owners.push(owner.clone()),
+        }
+    }
+    plan
+}
+
+fn same_pet(a: &Pet, b: &Pet) -> bool {
+    normalize_name(&a.name) == normalize_name(&b.name)
+        && normalize_name(&a.category) == normalize_name(&b.category)
+        && a.birthdate == b.birthdate
+}
+
+/// Carries out `plan` on `database`, which must be the DB it was made
+/// against. Their pets keep their owners, under our ids for them; an owner
+/// their file doesn't have is dropped. Fails with the id of a conflicting pet
+/// without a resolution, changing nothing.
+pub fn apply(plan: &MergePlan, database: &mut Database) -> Result<Merged, usize> {
+    if let Some(conflict) = plan.conflicts.iter().find(|c| c.resolution.is_none()) {
+        return Err(conflict.theirs.id);
+    }
+    let mut owner_ids: HashMap<usize, usize> = plan.same_owners.iter().copied().collect();
+    let first_owner_id = database
+        .owners
+        .iter()
+        .map(|owner| owner.id + 1)
+        .max()
+        .unwrap_or(1);
+    for (id, owner) in (first_owner_id..).zip(&plan.new_owners) {
+        owner_ids.insert(owner.id, id);
+        database.owners.push(Owner {
+            id,
+            ..owner.clone()
+        });
+    }
+    let with_our_owner = |pet: &Pet| Pet {
+        owner_id: pet.owner_id.and_then(|id| owner_ids.get(&id).copied()),
+        ..pet.clone()
+    };
+    let resolved = |strategy| {
+        plan.conflicts
+            .iter()
+            .filter(move |conflict| conflict.resolution == Some(strategy))
+    };
+    let mut merged = Merged {
+        owners_added: plan.new_owners.len(),
+        ..Merged::default()
+    };
+    let kept_both = resolved(Strategy::Both).map(|conflict| &conflict.theirs);
+    let adding: Vec<&Pet> = plan.added.iter().chain(kept_both).collect();
+    let ids = database.next_id.take(&database.pets, adding.len());
+    for (id, pet) in ids.zip(adding) {
+        let pet = Pet {
+            id,
+            ..with_our_owner(pet)
+        };
+        database.pets.push(pet.clone());
+        merged.added.push(pet);
+    }
+    for conflict in resolved(Strategy::Theirs) {
+        if let Some(ours) = database
+            .pets
+            .iter_mut()
+            .find(|pet| pet.id == conflict.mine.id)
+        {
+            *ours = with_our_owner(&conflict.theirs);
+            merged.replaced.push(ours.clone());
+        }
+    }
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use chrono::NaiveDate;
+
+    fn owner(id: usize, name: &str) -> Owner {
+        Owner {
+            id,
+            name: name.to_owned(),
+            email: String::new(),
+        }
+    }
+
+    fn database(pets: Vec<Pet>, owners: Vec<Owner>) -> Database {
+        Database {
+            pets,
+            owners,
+            ..Database::default()
+        }
+    }
+
+    fn ids(pets: &[Pet]) -> Vec<usize> {
+        pets.iter().map(|pet| pet.id).collect()
+    }
+
+    /// Ours: Coco, Rex and Bun. Theirs: Coco again under another id, Max
+    /// under Rex's id, and Tom under an id of its own.
+    fn both() -> (Database, Database) {
+        let mine = database(
+            vec![
+                pet(1, "Coco", "cats"),
+                pet(2, "Rex", "dogs"),
+                pet(3, "Bun", "rabbits"),
+            ],
+            vec![owner(1, "Ann")],
+        );
+        let theirs = database(
+            vec![
+                pet(7, " coco ", "Cats"),
+                pet(2, "Max", "dogs"),
+                pet(9, "Tom", "cats"),
+            ],
+            vec![owner(4, "ann"), owner(5, "Bo")],
+        );
+        (mine, theirs)
+    }
+
+    #[test]
+    fn plans_additions_duplicates_and_conflicts() {
+        let (mine, theirs) = both();
+        let plan = plan(&mine, &theirs);
+        assert_eq!(ids(&plan.added), [9]);
+        assert_eq!(ids(&plan.duplicates), [7]);
+        assert_eq!(plan.conflicts.len(), 1);
+        let conflict = &plan.conflicts[0];
+        assert_eq!((conflict.mine.id, conflict.theirs.id), (2, 2));
+        assert_eq!(conflict.resolution, None);
+        let changes = conflict.differences();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            (changes[0].old.as_str(), changes[0].new.as_str()),
+            ("\"Rex\"", "\"Max\"")
+        );
+        assert_eq!(plan.summary(), "1 to add, 1 already here, 1 conflicting");
+        assert_eq!(plan.same_owners, [(4, 1)]);
+        assert_eq!(plan.new_owners.len(), 1);
+        assert_eq!(plan.new_owners[0].name, "Bo");
+    }
+
+    #[test]
+    fn a_pet_with_another_birthdate_is_not_the_same() {
+        let mut coco = pet(1, "Coco", "cats");
+        coco.birthdate = NaiveDate::from_ymd_opt(2020, 5, 1);
+        let mine = database(vec![coco], Vec::new());
+        let theirs = database(vec![pet(1, "Coco", "cats")], Vec::new());
+        let plan = plan(&mine, &theirs);
+        assert!(plan.duplicates.is_empty());
+        assert_eq!(plan.conflicts.len(), 1);
+    }
+
+    #[test]
+    fn the_same_file_is_all_duplicates() {
+        let (mine, _) = both();
+        let plan = plan(&mine, &mine);
+        assert_eq!(ids(&plan.duplicates), [1, 2, 3]);
+        assert!(plan.added.is_empty() && plan.conflicts.is_empty());
+    }
+
+    #[test]
+    fn nothing_is_applied_with_an_undecided_conflict() {
+        let (mut mine, theirs) = both();
+        let plan = plan(&mine, &theirs);
+        assert_eq!(apply(&plan, &mut mine).unwrap_err(), 2);
+        assert_eq!(ids(&mine.pets), [1, 2, 3]);
+        assert_eq!(mine.owners.len(), 1);
+    }
+
+    fn apply_with(strategy: Strategy) -> (Database, Merged) {
+        let (mut mine, mut theirs) = both();
+        theirs.pets[1].owner_id = Some(4);
+        theirs.pets[2].owner_id = Some(5);
+        let mut plan = plan(&mine, &theirs);
+        plan.conflicts[0].resolution = Some(strategy);
+        let merged = apply(&plan, &mut mine).unwrap();
+        (mine, merged)
+    }
+
+    #[test]
+    fn keeping_mine_adds_only_the_new_pets() {
+        let (mine, merged) = apply_with(Strategy::Mine);
+        assert_eq!(ids(&mine.pets), [1, 2, 3, 4]);
+        assert_eq!(mine.pets[1].name, "Rex");
+        assert_eq!(mine.pets[3].name, "Tom");
+        assert!(merged.replaced.is_empty());
+        assert_eq!(
+            merged.summary(),
+            "added 1 pets, replaced 0 and added 1 owners"
+        );
+    }
+
+    #[test]
+    fn keeping_theirs_replaces_ours_under_the_same_id() {
+        let (mine, merged) = apply_with(Strategy::Theirs);
+        assert_eq!(ids(&mine.pets), [1, 2, 3, 4]);
+        assert_eq!(mine.pets[1].name, "Max");
+        assert_eq!(ids(&merged.replaced), [2]);
+        // Their Ann is our Ann.
+        assert_eq!(mine.pets[1].owner_id, Some(1));
+    }
+
+    #[test]
+    fn keeping_both_adds_theirs_under_a_new_id() {
+        let (mine, merged) = apply_with(Strategy::Both);
+        assert_eq!(ids(&mine.pets), [1, 2, 3, 4, 5]);
+        assert_eq!(mine.pets[1].name, "Rex");
+        assert_eq!(mine.pets[4].name, "Max");
+        assert_eq!(ids(&merged.added), [4, 5]);
+    }
+
+    #[test]
+    fn their_owners_are_added_under_new_ids() {
+        let (mine, _) = apply_with(Strategy::Mine);
+        assert_eq!(mine.owners.len(), 2);
+        assert_eq!((mine.owners[1].id, mine.owners[1].name.as_str()), (2, "Bo"));
+        assert_eq!(mine.pets[3].owner_id, Some(2));
+    }
+
+    #[test]
+    fn an_owner_their_file_lacks_is_dropped() {
+        let mut mine = database(Vec::new(), Vec::new());
+        let mut tom = pet(1, "Tom", "cats");
+        tom.owner_id = Some(8);
+        let plan = plan(&mine, &database(vec![tom], Vec::new()));
+        apply(&plan, &mut mine).unwrap();
+        assert_eq!(mine.pets[0].owner_id, None);
+    }
+}
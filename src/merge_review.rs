@@ -0,0 +1,191 @@
+use crate::merge::{Conflict, MergePlan, Strategy};
+use crate::Database;
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// The overlay of the Merge entry that goes through the conflicting pets of
+/// the file being merged, one at a time, asking which to keep.
+pub struct MergeReview {
+    theirs: Database,
+    plan: MergePlan,
+    current: usize,
+}
+
+pub enum ReviewOutcome {
+    Open,
+    Cancelled,
+    /// Every conflict is decided; `into_parts` has the plan to merge with.
+    Decided,
+}
+
+impl MergeReview {
+    /// `plan` must have conflicts.
+    pub fn new(theirs: Database, plan: MergePlan) -> MergeReview {
+        MergeReview {
+            theirs,
+            plan,
+            current: 0,
+        }
+    }
+
+    /// The file being merged and the plan, with the resolutions chosen.
+    pub fn into_parts(self) -> (Database, MergePlan) {
+        (self.theirs, self.plan)
+    }
+
+    pub fn plan(&self) -> &MergePlan {
+        &self.plan
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn conflict(&self) -> &Conflict {
+        &self.plan.conflicts[self.current]
+    }
+
+    /// `m`, `t` and `b` keep mine, theirs or both and go to the next
+    /// conflict; `M`, `T` and `B` decide the rest the same way. `k` goes back
+    /// to the previous one.
+    pub fn handle_key(&mut self, key: KeyEvent) -> ReviewOutcome {
+        let (strategy, rest) = match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return ReviewOutcome::Cancelled,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.current = self.current.saturating_sub(1);
+                return ReviewOutcome::Open;
+            }
+            KeyCode::Char('m') => (Strategy::Mine, false),
+            KeyCode::Char('t') => (Strategy::Theirs, false),
+            KeyCode::Char('b') => (Strategy::Both, false),
+            KeyCode::Char('M') => (Strategy::Mine, true),
+            KeyCode::Char('T') => (Strategy::Theirs, true),
+            KeyCode::Char('B') => (Strategy::Both, true),
+            _ => return ReviewOutcome::Open,
+        };
+        let end = if rest {
+            self.plan.conflicts.len()
+        } else {
+            self.current + 1
+        };
+        for conflict in &mut self.plan.conflicts[self.current..end] {
+            conflict.resolution = Some(strategy);
+        }
+        if end < self.plan.conflicts.len() {
+            self.current = end;
+            return ReviewOutcome::Open;
+        }
+        ReviewOutcome::Decided
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge;
+    use crate::ui_tests::pet;
+    use crossterm::event::KeyModifiers;
+
+    /// Three conflicts, for pets 1 to 3.
+    fn review() -> MergeReview {
+        let mine = Database {
+            pets: vec![
+                pet(1, "Coco", "cats"),
+                pet(2, "Rex", "dogs"),
+                pet(3, "Bun", "rabbits"),
+            ],
+            ..Database::default()
+        };
+        let theirs = Database {
+            pets: vec![
+                pet(1, "Tom", "cats"),
+                pet(2, "Max", "dogs"),
+                pet(3, "Kit", "rabbits"),
+            ],
+            ..Database::default()
+        };
+        let plan = merge::plan(&mine, &theirs);
+        MergeReview::new(theirs, plan)
+    }
+
+    fn press(review: &mut MergeReview, keys: &str) -> ReviewOutcome {
+        let mut outcome = ReviewOutcome::Open;
+        for key in keys.chars() {
+            let code = if key == '\x1b' {
+                KeyCode::Esc
+            } else {
+                KeyCode::Char(key)
+            };
+            outcome = review.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        outcome
+    }
+
+    fn resolutions(review: &MergeReview) -> Vec<Option<Strategy>> {
+        review
+            .plan()
+            .conflicts
+            .iter()
+            .map(|conflict| conflict.resolution)
+            .collect()
+    }
+
+    #[test]
+    fn each_key_decides_one_conflict_and_moves_on() {
+        let mut review = review();
+        assert!(matches!(press(&mut review, "m"), ReviewOutcome::Open));
+        assert!(matches!(press(&mut review, "t"), ReviewOutcome::Open));
+        assert_eq!(review.current(), 2);
+        assert_eq!(review.conflict().theirs.name, "Kit");
+        assert!(matches!(press(&mut review, "b"), ReviewOutcome::Decided));
+        assert_eq!(
+            resolutions(&review),
+            [
+                Some(Strategy::Mine),
+                Some(Strategy::Theirs),
+                Some(Strategy::Both)
+            ]
+        );
+    }
+
+    #[test]
+    fn capitals_decide_the_rest() {
+        let mut review = review();
+        press(&mut review, "b");
+        assert!(matches!(press(&mut review, "T"), ReviewOutcome::Decided));
+        assert_eq!(
+            resolutions(&review),
+            [
+                Some(Strategy::Both),
+                Some(Strategy::Theirs),
+                Some(Strategy::Theirs)
+            ]
+        );
+    }
+
+    #[test]
+    fn k_goes_back_to_change_a_decision() {
+        let mut review = review();
+        press(&mut review, "mmk");
+        assert_eq!(review.current(), 1);
+        press(&mut review, "kkt");
+        assert_eq!(review.current(), 1);
+        assert_eq!(
+            resolutions(&review),
+            [Some(Strategy::Theirs), Some(Strategy::Mine), None]
+        );
+    }
+
+    #[test]
+    fn esc_cancels_and_other_keys_are_ignored() {
+        let mut review = review();
+        assert!(matches!(press(&mut review, "xj"), ReviewOutcome::Open));
+        assert_eq!(resolutions(&review), [None, None, None]);
+        assert!(matches!(
+            press(&mut review, "\x1b"),
+            ReviewOutcome::Cancelled
+        ));
+        let (theirs, plan) = review.into_parts();
+        assert_eq!(theirs.pets.len(), 3);
+        assert_eq!(plan.conflicts.len(), 3);
+    }
+}
@@ -0,0 +1,108 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// The overlay with every message of the session, for the ones too long for
+/// the status bar.
+#[derive(Default)]
+pub struct MessagePager {
+    scroll: u16,
+    /// How many lines the messages took when last drawn, wrapped to the
+    /// overlay's width.
+    lines: usize,
+}
+
+pub enum PagerOutcome {
+    Open,
+    Closed,
+}
+
+impl MessagePager {
+    pub fn scroll(&self) -> u16 {
+        self.scroll
+    }
+
+    pub fn set_lines(&mut self, lines: usize) {
+        self.lines = lines;
+        self.scroll = self.scroll.min(self.last());
+    }
+
+    /// Scrolling down stops once the last line is at the top.
+    fn last(&self) -> u16 {
+        self.lines.saturating_sub(1).min(u16::MAX as usize) as u16
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PagerOutcome {
+        let last = self.last();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return PagerOutcome::Closed,
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll = 0,
+            KeyCode::End | KeyCode::Char('G') => self.scroll = last,
+            _ => {}
+        }
+        self.scroll = self.scroll.min(last);
+        PagerOutcome::Open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(pager: &mut MessagePager, code: KeyCode) -> PagerOutcome {
+        pager.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn scrolling_stops_at_the_last_line() {
+        let mut pager = MessagePager::default();
+        pager.set_lines(5);
+        press(&mut pager, KeyCode::Char('j'));
+        press(&mut pager, KeyCode::Down);
+        assert_eq!(pager.scroll(), 2);
+        press(&mut pager, KeyCode::PageDown);
+        assert_eq!(pager.scroll(), 4);
+        press(&mut pager, KeyCode::Char('k'));
+        assert_eq!(pager.scroll(), 3);
+        press(&mut pager, KeyCode::PageUp);
+        assert_eq!(pager.scroll(), 0);
+        press(&mut pager, KeyCode::Char('G'));
+        assert_eq!(pager.scroll(), 4);
+        press(&mut pager, KeyCode::Home);
+        assert_eq!(pager.scroll(), 0);
+    }
+
+    #[test]
+    fn fewer_lines_pull_the_scroll_back() {
+        let mut pager = MessagePager::default();
+        pager.set_lines(20);
+        press(&mut pager, KeyCode::End);
+        assert_eq!(pager.scroll(), 19);
+        pager.set_lines(3);
+        assert_eq!(pager.scroll(), 2);
+        pager.set_lines(0);
+        assert_eq!(pager.scroll(), 0);
+        press(&mut pager, KeyCode::Down);
+        assert_eq!(pager.scroll(), 0);
+    }
+
+    #[test]
+    fn q_and_esc_close_it() {
+        let mut pager = MessagePager::default();
+        assert!(matches!(
+            press(&mut pager, KeyCode::Char('q')),
+            PagerOutcome::Closed
+        ));
+        assert!(matches!(
+            press(&mut pager, KeyCode::Esc),
+            PagerOutcome::Closed
+        ));
+        assert!(matches!(
+            press(&mut pager, KeyCode::Char('x')),
+            PagerOutcome::Open
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+//! Counters of what pet-cli did since it started, served as `GET /metrics`
+//! by `pet-cli serve` and shown by the TUI's diagnostics overlay (F12). The
+//! DB is read and written by free functions, on the server's threads too, so
+//! there is one set per process that they update as they go, the way
+//! `WORKSPACE_DB` is one path per process.
+
+use crate::durability::Durability;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metrics {
+    /// Requests to `pet-cli serve` by route, like `/pets/{id}`, and status.
+    pub requests: BTreeMap<(&'static str, u16), u64>,
+    pub db_reads: u64,
+    pub db_writes: u64,
+    /// The pets in the DB as of the last read or write.
+    pub pets: usize,
+    /// The size of the DB file as of the last read or write.
+    pub db_bytes: usize,
+    pub last_read: Option<Duration>,
+    pub last_write: Option<Duration>,
+    /// The `write_durability` the last write was made with.
+    pub durability: Option<Durability>,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Metrics {
+            requests: BTreeMap::new(),
+            db_reads: 0,
+            db_writes: 0,
+            pets: 0,
+            db_bytes: 0,
+            last_read: None,
+            last_write: None,
+            durability: None,
+        }
+    }
+
+    /// In Prometheus' text format, with durations in seconds.
+    #[cfg(feature = "server")]
+    pub fn exposition(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP pet_cli_http_requests_total Requests served, by route and status.\n");
+        out.push_str("# TYPE pet_cli_http_requests_total counter\n");
+        for ((route, status), count) in &self.requests {
+            out.push_str(&format!(
+                "pet_cli_http_requests_total{{route=\"{}\",status=\"{}\"}} {}\n",
+                route, status, count
+            ));
+        }
+        let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+            out.push_str(&format!("# HELP pet_cli_{} {}\n", name, help));
+            out.push_str(&format!("# TYPE pet_cli_{} {}\n", name, kind));
+            out.push_str(&format!("pet_cli_{} {}\n", name, value));
+        };
+        metric(
+            "db_reads_total",
+            "counter",
+            "Reads of the DB file.",
+            self.db_reads.to_string(),
+        );
+        metric(
+            "db_writes_total",
+            "counter",
+            "Writes of the DB file.",
+            self.db_writes.to_string(),
+        );
+        metric(
+            "pets",
+            "gauge",
+            "Pets in the DB as of the last read or write.",
+            self.pets.to_string(),
+        );
+        metric(
+            "db_bytes",
+            "gauge",
+            "Size of the DB file as of the last read or write.",
+            self.db_bytes.to_string(),
+        );
+        let seconds = |took: Option<Duration>| took.unwrap_or_default().as_secs_f64().to_string();
+        metric(
+            "db_last_read_seconds",
+            "gauge",
+            "How long the last read of the DB file took.",
+            seconds(self.last_read),
+        );
+        metric(
+            "db_last_write_seconds",
+            "gauge",
+            "How long the last write of the DB file took.",
+            seconds(self.last_write),
+        );
+        if let Some(durability) = self.durability {
+            out.push_str(
+                "# HELP pet_cli_db_write_durability The write_durability of the last write.\n",
+            );
+            out.push_str("# TYPE pet_cli_db_write_durability gauge\n");
+            out.push_str(&format!(
+                "pet_cli_db_write_durability{{level=\"{}\"}} 1\n",
+                durability.name()
+            ));
+        }
+        out
+    }
+}
+
+static METRICS: Mutex<Metrics> = Mutex::new(Metrics::new());
+
+/// The counters as they are now.
+pub fn snapshot() -> Metrics {
+    METRICS
+        .lock()
+        .map(|metrics| metrics.clone())
+        .unwrap_or_else(|_| Metrics::new())
+}
+
+pub fn record_read(pets: usize, bytes: usize, took: Duration) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        metrics.db_reads += 1;
+        metrics.pets = pets;
+        metrics.db_bytes = bytes;
+        metrics.last_read = Some(took);
+    }
+}
+
+pub fn record_write(pets: usize, bytes: usize, durability: Durability, took: Duration) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        metrics.durability = Some(durability);
+        metrics.db_writes += 1;
+        metrics.pets = pets;
+        metrics.db_bytes = bytes;
+        metrics.last_write = Some(took);
+    }
+}
+
+#[cfg(feature = "server")]
+pub fn record_request(route: &'static str, status: u16) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        *metrics.requests.entry((route, status)).or_insert(0) += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_and_writes_are_counted() {
+        // Other tests read and write the DB at the same time, so only what
+        // can't go backwards is checked.
+        let before = snapshot();
+        record_read(3, 120, Duration::from_millis(2));
+        record_write(4, 150, Durability::Fsync, Duration::from_millis(5));
+        let after = snapshot();
+        assert!(after.db_reads > before.db_reads);
+        assert!(after.db_writes > before.db_writes);
+        assert!(after.last_read.is_some() && after.last_write.is_some());
+        assert!(after.durability.is_some());
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn exposition_is_in_prometheus_text_format() {
+        let mut metrics = Metrics::new();
+        metrics.requests.insert(("/pets", 200), 3);
+        metrics.requests.insert(("/pets/{id}", 404), 1);
+        metrics.db_reads = 5;
+        metrics.db_writes = 2;
+        metrics.pets = 7;
+        metrics.db_bytes = 1024;
+        metrics.last_read = Some(Duration::from_millis(1500));
+        metrics.durability = Some(Durability::Flush);
+        let out = metrics.exposition();
+        let samples: Vec<&str> = out.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(
+            samples,
+            [
+                "pet_cli_http_requests_total{route=\"/pets\",status=\"200\"} 3",
+                "pet_cli_http_requests_total{route=\"/pets/{id}\",status=\"404\"} 1",
+                "pet_cli_db_reads_total 5",
+                "pet_cli_db_writes_total 2",
+                "pet_cli_pets 7",
+                "pet_cli_db_bytes 1024",
+                "pet_cli_db_last_read_seconds 1.5",
+                "pet_cli_db_last_write_seconds 0",
+                "pet_cli_db_write_durability{level=\"flush\"} 1",
+            ]
+        );
+        // Every metric has its help and type before its samples.
+        for name in ["http_requests_total", "db_reads_total", "pets"] {
+            let help = out
+                .find(&format!("# HELP pet_cli_{} ", name))
+                .expect("help");
+            let kind = out
+                .find(&format!("# TYPE pet_cli_{} ", name))
+                .expect("type");
+            let sample = out.find(&format!("\npet_cli_{}", name)).expect("sample");
+            assert!(help < kind && kind < sample, "{}", out);
+        }
+        assert!(out.contains("# TYPE pet_cli_db_reads_total counter\n"));
+        assert!(out.contains("# TYPE pet_cli_pets gauge\n"));
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn nothing_served_yet_has_no_request_samples() {
+        let out = Metrics::new().exposition();
+        assert!(!out.contains("pet_cli_http_requests_total{"), "{}", out);
+        assert!(!out.contains("durability"), "{}", out);
+        assert!(
+            out.ends_with("pet_cli_db_last_write_seconds 0\n"),
+            "{}",
+            out
+        );
+    }
+}
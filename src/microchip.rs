@@ -0,0 +1,161 @@
+//! ISO 11784 microchip numbers: 15 digits, the first three a country code or
+//! the code of the chip's manufacturer.
+
+use std::fmt;
+
+/// ISO 3166 numeric codes of countries whose chips vets commonly see.
+const COUNTRY_CODES: [&str; 18] = [
+    "036", // Australia
+    "040", // Austria
+    "056", // Belgium
+    "124", // Canada
+    "203", // Czechia
+    "208", // Denmark
+    "250", // France
+    "276", // Germany
+    "372", // Ireland
+    "380", // Italy
+    "528", // Netherlands
+    "554", // New Zealand
+    "578", // Norway
+    "616", // Poland
+    "724", // Spain
+    "752", // Sweden
+    "756", // Switzerland
+    "826", // United Kingdom
+];
+
+/// How many digits a number has.
+pub const DIGITS: usize = 15;
+
+/// ICAR codes of manufacturers that sell chips under their own code.
+const MANUFACTURER_CODES: [&str; 10] = [
+    "941", // Felixcan
+    "952", // Microchips Australia
+    "956", // Trovan
+    "968", // AEG
+    "972", // Planet ID
+    "977", // Avid
+    "978", // Ordicam
+    "981", // Datamars
+    "982", // Allflex
+    "985", // Destron Fearing
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MicrochipIssue {
+    /// The number of digits found instead of 15.
+    Length(usize),
+    NotNumeric,
+    UnknownPrefix(String),
+}
+
+impl fmt::Display for MicrochipIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MicrochipIssue::Length(length) => {
+                write!(f, "microchip has {} digits instead of {}", length, DIGITS)
+            }
+            MicrochipIssue::NotNumeric => write!(f, "microchip may only contain digits"),
+            MicrochipIssue::UnknownPrefix(prefix) => write!(
+                f,
+                "microchip starts with {}, which is no known country or manufacturer code",
+                prefix
+            ),
+        }
+    }
+}
+
+/// Checks a microchip number as stored, without spaces.
+pub fn check(number: &str) -> Result<(), MicrochipIssue> {
+    if !number.chars().all(|c| c.is_ascii_digit()) {
+        return Err(MicrochipIssue::NotNumeric);
+    }
+    if number.len() != DIGITS {
+        return Err(MicrochipIssue::Length(number.len()));
+    }
+    let prefix = &number[..3];
+    if COUNTRY_CODES.contains(&prefix) || MANUFACTURER_CODES.contains(&prefix) {
+        Ok(())
+    } else {
+        Err(MicrochipIssue::UnknownPrefix(prefix.to_owned()))
+    }
+}
+
+/// A regular expression for the numbers `check` accepts, as for a JSON
+/// Schema.
+pub fn pattern() -> String {
+    let prefixes: Vec<&str> = COUNTRY_CODES
+        .iter()
+        .chain(&MANUFACTURER_CODES)
+        .copied()
+        .collect();
+    format!("^({})[0-9]{{{}}}$", prefixes.join("|"), DIGITS - 3)
+}
+
+/// A number as written on paperwork, often in groups, the way it is stored.
+pub fn normalize(input: &str) -> String {
+    input.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_country_and_manufacturer_codes() {
+        assert_eq!(check("250268500012345"), Ok(()));
+        assert_eq!(check("985112003456789"), Ok(()));
+        for code in COUNTRY_CODES.iter().chain(&MANUFACTURER_CODES) {
+            assert_eq!(check(&format!("{}000000000000", code)), Ok(()), "{}", code);
+        }
+    }
+
+    #[test]
+    fn refuses_short_and_long_numbers() {
+        assert_eq!(check(""), Err(MicrochipIssue::Length(0)));
+        assert_eq!(check("25026850001234"), Err(MicrochipIssue::Length(14)));
+        assert_eq!(check("2502685000123456"), Err(MicrochipIssue::Length(16)));
+    }
+
+    #[test]
+    fn refuses_anything_but_digits() {
+        for number in [
+            "25026850001234a",
+            "250 268500012345",
+            "-50268500012345",
+            "２50268500012345",
+        ] {
+            assert_eq!(check(number), Err(MicrochipIssue::NotNumeric), "{}", number);
+        }
+    }
+
+    #[test]
+    fn refuses_unknown_prefixes() {
+        assert_eq!(
+            check("123456789012345"),
+            Err(MicrochipIssue::UnknownPrefix("123".to_owned()))
+        );
+        assert_eq!(
+            check("999000000000000").unwrap_err().to_string(),
+            "microchip starts with 999, which is no known country or manufacturer code"
+        );
+    }
+
+    #[test]
+    fn normalizes_grouped_numbers() {
+        assert_eq!(normalize(" 250 268 500\t012345 "), "250268500012345");
+        assert_eq!(check(&normalize("981 098 100 234 567")), Ok(()));
+    }
+
+    #[test]
+    fn the_pattern_has_every_prefix() {
+        let pattern = pattern();
+        assert!(pattern.starts_with("^(036|040|"));
+        assert!(pattern.ends_with("|985)[0-9]{12}$"));
+        assert_eq!(
+            pattern.matches('|').count(),
+            COUNTRY_CODES.len() + MANUFACTURER_CODES.len() - 1
+        );
+    }
+}
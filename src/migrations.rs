@@ -0,0 +1,251 @@
+//! The shapes the DB file has had, numbered by its `schema_version`, and the
+//! steps that bring a file from one to the next. Reading migrates in memory,
+//! so older DBs, backups and files to compare can always be read; the DB
+//! itself is rewritten in the current shape when pet-cli starts.
+
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// The schema version this pet-cli writes. Files without a `schema_version`
+/// are 1, or 0 if they are a bare array of pets.
+pub const CURRENT_VERSION: u64 = 3;
+
+pub struct Migration {
+    pub from: u64,
+    pub to: u64,
+    pub description: &'static str,
+    pub run: fn(Value) -> Result<Value, String>,
+}
+
+/// In order; each starts where the one before ends.
+pub const MIGRATIONS: [Migration; 3] = [
+    Migration {
+        from: 0,
+        to: 1,
+        description: "wrap the bare array of pets in an object, with no owners",
+        run: wrap_pets,
+    },
+    Migration {
+        from: 1,
+        to: 2,
+        description: "store ages in months instead of whole years",
+        run: ages_in_months,
+    },
+    Migration {
+        from: 2,
+        to: 3,
+        description: "record the next pet id, so ids of purged pets aren't given out again",
+        run: record_next_id,
+    },
+];
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("the DB has schema version {0}, newer than {CURRENT_VERSION}, the newest this pet-cli can read")]
+    Newer(u64),
+    #[error("could not migrate the DB from schema version {from} to {to}: {message}")]
+    Failed { from: u64, to: u64, message: String },
+}
+
+/// The schema version of a DB file read as JSON.
+pub fn version(db: &Value) -> u64 {
+    match db {
+        Value::Array(_) => 0,
+        db => db
+            .get("schema_version")
+            .and_then(Value::as_u64)
+            .unwrap_or(1),
+    }
+}
+
+/// The migrations that bring a DB at `version` up to `CURRENT_VERSION`, in
+/// the order they run.
+pub fn steps(version: u64) -> Result<Vec<&'static Migration>, MigrationError> {
+    if version > CURRENT_VERSION {
+        return Err(MigrationError::Newer(version));
+    }
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|migration| migration.from >= version)
+        .collect())
+}
+
+/// `db` in the current shape, with its `schema_version` set.
+pub fn migrate(db: Value) -> Result<Value, MigrationError> {
+    let mut db = db;
+    for migration in steps(version(&db))? {
+        let failed = |message| MigrationError::Failed {
+            from: migration.from,
+            to: migration.to,
+            message,
+        };
+        db = (migration.run)(db).map_err(failed)?;
+        match db.as_object_mut() {
+            Some(fields) => fields.insert("schema_version".to_owned(), json!(migration.to)),
+            None => return Err(failed("the result is not a JSON object".to_owned())),
+        };
+    }
+    Ok(db)
+}
+
+fn wrap_pets(db: Value) -> Result<Value, String> {
+    match db {
+        Value::Array(pets) => Ok(json!({ "pets": pets, "owners": [] })),
+        _ => Err("expected an array of pets".to_owned()),
+    }
+}
+
+/// Pets from before `age_months` have their age in whole years in `age`.
+fn ages_in_months(mut db: Value) -> Result<Value, String> {
+    let pets = db
+        .get_mut("pets")
+        .and_then(Value::as_array_mut)
+        .ok_or("expected an object with an array of pets")?;
+    for pet in pets.iter_mut().filter_map(Value::as_object_mut) {
+        let Some(years) = pet.remove("age") else {
+            continue;
+        };
+        if pet.contains_key("age_months") {
+            continue;
+        }
+        let months = years
+            .as_u64()
+            .and_then(|years| years.checked_mul(12))
+            .ok_or_else(|| format!("invalid age {}", years))?;
+        pet.insert("age_months".to_owned(), json!(months));
+    }
+    Ok(db)
+}
+
+/// The highest id a pet had can't be known once it is purged, so the next
+/// id starts after the highest one still there.
+fn record_next_id(mut db: Value) -> Result<Value, String> {
+    let pets = db
+        .get("pets")
+        .and_then(Value::as_array)
+        .ok_or("expected an object with an array of pets")?;
+    let next_id = pets
+        .iter()
+        .filter_map(|pet| pet.get("id").and_then(Value::as_u64))
+        .map(|id| id + 1)
+        .max()
+        .unwrap_or(1);
+    db.as_object_mut()
+        .ok_or("expected an object")?
+        .insert("next_id".to_owned(), json!(next_id));
+    Ok(db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V0: &str = include_str!("../tests/fixtures/migrations/v0.json");
+    const V1: &str = include_str!("../tests/fixtures/migrations/v1.json");
+    const V2: &str = include_str!("../tests/fixtures/migrations/v2.json");
+
+    fn fixture(text: &str) -> Value {
+        serde_json::from_str(text).expect("a JSON fixture")
+    }
+
+    fn run(from: u64, db: Value) -> Result<Value, String> {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|migration| migration.from == from)
+            .expect("a migration");
+        (migration.run)(db)
+    }
+
+    #[test]
+    fn fixtures_are_at_their_versions() {
+        assert_eq!(version(&fixture(V0)), 0);
+        assert_eq!(version(&fixture(V1)), 1);
+        assert_eq!(version(&fixture(V2)), 2);
+    }
+
+    #[test]
+    fn migrations_follow_on_from_each_other() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.from, index as u64);
+            assert_eq!(migration.to, migration.from + 1);
+        }
+        assert_eq!(MIGRATIONS.last().unwrap().to, CURRENT_VERSION);
+    }
+
+    #[test]
+    fn version_0_is_wrapped_with_no_owners() {
+        let db = run(0, fixture(V0)).unwrap();
+        assert_eq!(db["pets"], fixture(V0));
+        assert_eq!(db["owners"], json!([]));
+        assert!(run(0, fixture(V1)).is_err());
+    }
+
+    #[test]
+    fn version_1_ages_in_years_become_months() {
+        let db = run(1, fixture(V1)).unwrap();
+        let pets = db["pets"].as_array().unwrap();
+        assert_eq!(pets[0]["age_months"], 24);
+        assert_eq!(pets[0].get("age"), None);
+        // Already in months.
+        assert_eq!(pets[1]["age_months"], 5);
+        assert_eq!(db["owners"], fixture(V1)["owners"]);
+        let bad = json!({"pets": [{"id": 1, "age": "two"}]});
+        assert_eq!(run(1, bad).unwrap_err(), "invalid age \"two\"");
+        assert!(run(1, fixture(V0)).is_err());
+    }
+
+    #[test]
+    fn version_2_records_the_next_id_after_the_highest() {
+        let db = run(2, fixture(V2)).unwrap();
+        assert_eq!(db["next_id"], 10);
+        assert_eq!(db["revision"], 7);
+        let empty = run(2, json!({"pets": []})).unwrap();
+        assert_eq!(empty["next_id"], 1);
+    }
+
+    #[test]
+    fn each_version_migrates_to_the_current_one() {
+        for (text, from) in [(V0, 0), (V1, 1), (V2, 2)] {
+            assert_eq!(
+                steps(from).unwrap().len(),
+                (CURRENT_VERSION - from) as usize
+            );
+            let db = migrate(fixture(text)).unwrap();
+            assert_eq!(version(&db), CURRENT_VERSION);
+            assert_eq!(db["pets"][0]["age_months"], 24, "from {}", from);
+            assert_eq!(db["pets"][1]["name"], "Rex");
+            assert!(db["next_id"].as_u64().unwrap() > 4);
+        }
+    }
+
+    #[test]
+    fn the_current_version_is_left_as_it_is() {
+        assert!(steps(CURRENT_VERSION).unwrap().is_empty());
+        let db = json!({"schema_version": CURRENT_VERSION, "pets": [], "next_id": 3});
+        assert_eq!(migrate(db.clone()).unwrap(), db);
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let db = json!({"schema_version": CURRENT_VERSION + 1, "pets": []});
+        let err = migrate(db).unwrap_err();
+        assert!(matches!(err, MigrationError::Newer(version) if version == CURRENT_VERSION + 1));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "the DB has schema version {}, newer than {}, the newest this pet-cli can read",
+                CURRENT_VERSION + 1,
+                CURRENT_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn a_failed_step_names_its_versions() {
+        let err = migrate(json!({"pets": [{"id": 1, "age": -1}]})).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "could not migrate the DB from schema version 1 to 2: invalid age -1"
+        );
+    }
+}
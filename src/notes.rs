@@ -0,0 +1,47 @@
+use crate::text;
+use crossterm::event::KeyCode;
+
+/// Multi-line editor for a pet's notes. Enter inserts a newline, Esc ends
+/// editing so the caller can save the text.
+pub struct NotesEditor {
+    pub pet_id: usize,
+    pub text: String,
+}
+
+pub enum EditorOutcome {
+    Editing,
+    Finished,
+}
+
+impl NotesEditor {
+    pub fn new(pet_id: usize, notes: Option<&str>) -> Self {
+        Self {
+            pet_id,
+            text: notes.unwrap_or_default().to_owned(),
+        }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> EditorOutcome {
+        match code {
+            KeyCode::Esc => return EditorOutcome::Finished,
+            KeyCode::Enter => self.text.push('\n'),
+            KeyCode::Backspace => {
+                let last = text::previous_boundary(&self.text, self.text.len());
+                self.text.truncate(last.unwrap_or(0));
+            }
+            KeyCode::Char(c) => self.text.push(c),
+            _ => {}
+        }
+        EditorOutcome::Editing
+    }
+
+    /// The edited notes, with blank notes stored as no notes at all.
+    pub fn into_notes(self) -> Option<String> {
+        let text = self.text.trim_end().to_owned();
+        if text.is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! `pet-cli notify-daemon`: checks the reminders of the Home tab every so
+//! often without a terminal and sends each one to the channels turned on in
+//! the config: a desktop notification, a shell command or a line appended to
+//! a file. When each reminder was sent is kept in `notified.json` next to the
+//! DB, so it isn't sent again until `notify_snooze` hours later, across
+//! restarts and `--once` runs from cron too.
+
+use crate::hooks::{self, HookError};
+use crate::reminders::{Kind, Reminder};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+const FILE_NAME: &str = "notified.json";
+
+/// Where reminders are sent; each one set gets every reminder.
+#[derive(Clone, Debug, Default)]
+pub struct Channels {
+    pub desktop: bool,
+    /// Run with the shell, like the hooks.
+    pub command: Option<String>,
+    /// Appended to, one line per reminder.
+    pub file: Option<PathBuf>,
+    /// After which the command or desktop notifier is killed.
+    pub timeout: Duration,
+}
+
+impl Channels {
+    /// How many channels are turned on.
+    pub fn len(&self) -> usize {
+        usize::from(self.desktop)
+            + usize::from(self.command.is_some())
+            + usize::from(self.file.is_some())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The store of sent reminders of the DB at `db_path`.
+pub fn path_for(db_path: &Path) -> PathBuf {
+    db_path.with_file_name(FILE_NAME)
+}
+
+/// When each reminder was last sent, by [`key`].
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Sent {
+    sent: BTreeMap<String, DateTime<Utc>>,
+}
+
+impl Sent {
+    /// An empty store for a missing file.
+    pub fn load(path: &Path) -> io::Result<Sent> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Sent::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    /// The `reminders` to send at `now`: those not sent in the `snooze`
+    /// before it. Reminders no longer among them are forgotten, so one that
+    /// comes back is sent straight away.
+    pub fn due<'r>(
+        &mut self,
+        reminders: &'r [Reminder],
+        now: DateTime<Utc>,
+        snooze: Duration,
+    ) -> Vec<&'r Reminder> {
+        let keys: Vec<String> = reminders.iter().map(key).collect();
+        self.sent.retain(|sent, _| keys.contains(sent));
+        reminders
+            .iter()
+            .zip(&keys)
+            .filter(|(_, key)| {
+                self.sent
+                    .get(*key)
+                    .is_none_or(|at| (now - *at).to_std().is_ok_and(|since| since >= snooze))
+            })
+            .map(|(reminder, _)| reminder)
+            .collect()
+    }
+
+    pub fn mark(&mut self, reminder: &Reminder, now: DateTime<Utc>) {
+        self.sent.insert(key(reminder), now);
+    }
+}
+
+/// What makes a reminder the same from one check to the next. A birthday is
+/// a new one every year and a checkup a new one after another record, but a
+/// long intake stays the one reminder however many days it counts.
+pub fn key(reminder: &Reminder) -> String {
+    match reminder.kind {
+        Kind::CheckupDue { last } => format!("{}:checkup:{}", reminder.pet_id, last),
+        Kind::Birthday { on, .. } => format!("{}:birthday:{}", reminder.pet_id, on),
+        Kind::LongIntake { .. } => format!("{}:intake", reminder.pet_id),
+    }
+}
+
+/// The value of `PET_CLI_REMINDER_KIND` for the command.
+fn kind_name(kind: &Kind) -> &'static str {
+    match kind {
+        Kind::CheckupDue { .. } => "checkup",
+        Kind::Birthday { .. } => "birthday",
+        Kind::LongIntake { .. } => "intake",
+    }
+}
+
+/// Sends `reminder` to every channel, returning the failures by channel.
+pub fn send(
+    channels: &Channels,
+    reminder: &Reminder,
+    today: NaiveDate,
+    now: DateTime<Utc>,
+    db_path: &str,
+) -> Vec<(&'static str, String)> {
+    let text = reminder.describe(today);
+    let mut failures = Vec::new();
+    if channels.desktop {
+        if let Err(err) = desktop(&text, channels.timeout) {
+            failures.push(("desktop", err));
+        }
+    }
+    if let Some(command) = &channels.command {
+        let mut shell = hooks::shell(command);
+        shell
+            .env("PET_CLI_REMINDER", &text)
+            .env("PET_CLI_REMINDER_KIND", kind_name(&reminder.kind))
+            .env("PET_CLI_PET_ID", reminder.pet_id.to_string())
+            .env("PET_CLI_DB", db_path);
+        if let Err(err) = hooks::run_command(shell, channels.timeout) {
+            failures.push(("command", err.to_string()));
+        }
+    }
+    if let Some(path) = &channels.file {
+        if let Err(err) = append(path, &text, now) {
+            failures.push(("file", format!("{}: {}", path.display(), err)));
+        }
+    }
+    failures
+}
+
+/// Appends `text` to the file at `path` after the time, tab-separated.
+fn append(path: &Path, text: &str, now: DateTime<Utc>) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}\t{}", now.to_rfc3339(), text)
+}
+
+/// Shows `text` with the desktop's notifier. There is no notifier to run on
+/// Windows.
+fn desktop(text: &str, timeout: Duration) -> Result<(), String> {
+    let notifier = notifier(text)?;
+    match hooks::run_command(notifier, timeout) {
+        Ok(_) => Ok(()),
+        Err(HookError::Spawn(err)) if err.kind() == io::ErrorKind::NotFound => {
+            Err(format!("{} isn't installed", NOTIFIER))
+        }
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[cfg(target_os = "macos")]
+const NOTIFIER: &str = "osascript";
+#[cfg(not(target_os = "macos"))]
+const NOTIFIER: &str = "notify-send";
+
+#[cfg(target_os = "macos")]
+fn notifier(text: &str) -> Result<Command, String> {
+    // An AppleScript string, in which only `\` and `"` need escaping.
+    let quoted = text.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut command = Command::new(NOTIFIER);
+    command.arg("-e").arg(format!(
+        "display notification \"{}\" with title \"pet-cli\"",
+        quoted
+    ));
+    Ok(command)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn notifier(text: &str) -> Result<Command, String> {
+    let mut command = Command::new(NOTIFIER);
+    command.args(["--app-name", "pet-cli", "pet-cli", text]);
+    Ok(command)
+}
+
+#[cfg(windows)]
+fn notifier(_text: &str) -> Result<Command, String> {
+    Err("desktop notifications aren't supported on Windows".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn hours(hours: u64) -> Duration {
+        Duration::from_secs(hours * 60 * 60)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, hour, 0, 0).unwrap()
+    }
+
+    fn reminder(pet_id: usize, kind: Kind) -> Reminder {
+        Reminder {
+            pet_id,
+            pet_name: "Rex".to_owned(),
+            kind,
+        }
+    }
+
+    fn checkup(pet_id: usize) -> Reminder {
+        reminder(
+            pet_id,
+            Kind::CheckupDue {
+                last: date(2023, 1, 5),
+            },
+        )
+    }
+
+    fn ids(due: Vec<&Reminder>) -> Vec<usize> {
+        due.iter().map(|reminder| reminder.pet_id).collect()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pet-cli-notify-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn a_sent_reminder_waits_out_the_snooze() {
+        let snooze = hours(24);
+        let reminders = [checkup(1), checkup(2)];
+        let mut sent = Sent::default();
+        assert_eq!(ids(sent.due(&reminders, at(1, 9), snooze)), [1, 2]);
+        sent.mark(&reminders[0], at(1, 9));
+        // Only what was actually sent is held back.
+        assert_eq!(ids(sent.due(&reminders, at(1, 10), snooze)), [2]);
+        sent.mark(&reminders[1], at(1, 10));
+        assert!(sent.due(&reminders, at(2, 8), snooze).is_empty());
+        assert_eq!(ids(sent.due(&reminders, at(2, 9), snooze)), [1]);
+        assert_eq!(ids(sent.due(&reminders, at(3, 0), snooze)), [1, 2]);
+    }
+
+    #[test]
+    fn a_reminder_that_goes_away_is_forgotten() {
+        let snooze = hours(24);
+        let mut sent = Sent::default();
+        let reminders = [checkup(1)];
+        sent.mark(&reminders[0], at(1, 9));
+        assert!(sent.due(&[], at(1, 10), snooze).is_empty());
+        assert_eq!(sent, Sent::default());
+        assert_eq!(ids(sent.due(&reminders, at(1, 11), snooze)), [1]);
+    }
+
+    #[test]
+    fn reminders_are_the_same_until_their_date_changes() {
+        let birthday = |on| reminder(1, Kind::Birthday { on, turning: 3 });
+        let intake = |days| reminder(1, Kind::LongIntake { days });
+        assert_eq!(key(&intake(31)), key(&intake(40)));
+        assert_ne!(
+            key(&intake(31)),
+            key(&reminder(2, Kind::LongIntake { days: 31 }))
+        );
+        assert_ne!(
+            key(&birthday(date(2024, 6, 5))),
+            key(&birthday(date(2025, 6, 5)))
+        );
+        assert_ne!(key(&checkup(1)), key(&intake(31)));
+
+        let snooze = hours(7 * 24);
+        let mut sent = Sent::default();
+        let this_year = [birthday(date(2024, 6, 5))];
+        sent.mark(&this_year[0], at(1, 9));
+        let next_year = [birthday(date(2025, 6, 5))];
+        assert_eq!(ids(sent.due(&next_year, at(2, 9), snooze)), [1]);
+    }
+
+    #[test]
+    fn the_store_survives_a_restart() {
+        let path = temp_path("sent.json");
+        assert_eq!(Sent::load(&path).unwrap(), Sent::default());
+        let mut sent = Sent::default();
+        sent.mark(&checkup(1), at(1, 9));
+        sent.save(&path).unwrap();
+        let mut loaded = Sent::load(&path).unwrap();
+        assert_eq!(loaded, sent);
+        assert!(loaded.due(&[checkup(1)], at(1, 12), hours(24)).is_empty());
+        fs::write(&path, "{").unwrap();
+        assert!(Sent::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn the_file_gets_a_line_per_reminder() {
+        let path = temp_path("file.log");
+        let channels = Channels {
+            file: Some(path.clone()),
+            ..Channels::default()
+        };
+        let today = date(2024, 6, 1);
+        assert!(send(&channels, &checkup(1), today, at(1, 9), "db.json").is_empty());
+        assert!(send(&channels, &checkup(2), today, at(1, 10), "db.json").is_empty());
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            written,
+            "2024-06-01T09:00:00+00:00\tRex: last medical record on 2023-01-05\n\
+             2024-06-01T10:00:00+00:00\tRex: last medical record on 2023-01-05\n"
+        );
+    }
+
+    #[test]
+    fn failing_channels_are_reported_by_name() {
+        let channels = Channels {
+            file: Some(temp_path("missing").join("file.log")),
+            ..Channels::default()
+        };
+        let failures = send(&channels, &checkup(1), date(2024, 6, 1), at(1, 9), "db");
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "file");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_command_gets_the_reminder_in_its_environment() {
+        let path = temp_path("command.txt");
+        let channels = Channels {
+            command: Some(format!(
+                "echo \"$PET_CLI_REMINDER_KIND $PET_CLI_PET_ID $PET_CLI_DB $PET_CLI_REMINDER\" > '{}'",
+                path.display()
+            )),
+            timeout: Duration::from_secs(5),
+            ..Channels::default()
+        };
+        let birthday = reminder(
+            4,
+            Kind::Birthday {
+                on: date(2024, 6, 2),
+                turning: 3,
+            },
+        );
+        assert!(send(
+            &channels,
+            &birthday,
+            date(2024, 6, 1),
+            at(1, 9),
+            "/pets/db.json"
+        )
+        .is_empty());
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, "birthday 4 /pets/db.json Rex: turns 3 tomorrow\n");
+
+        let failing = Channels {
+            command: Some("exit 3".to_owned()),
+            ..channels
+        };
+        let failures = send(&failing, &birthday, date(2024, 6, 1), at(1, 9), "db");
+        assert_eq!(failures[0].0, "command");
+    }
+}
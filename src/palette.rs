@@ -0,0 +1,510 @@
+//! The `:` command line, for what has no key of its own or needs an argument,
+//! like `:sort age desc` or `:export csv pets.csv`. Commands are parsed into
+//! the same actions as the keys, so both are carried out by `apply_action`.
+
+use crate::action::Action;
+use crate::batch::Assignment;
+use crate::completion::{Both, Candidates, Categories, Completer, PetNames, Words};
+use crate::input::{HistoryCursor, InputOutcome, TextInput};
+use crate::search::Query;
+use crate::theme::Theme;
+use crate::view::{Sort, SortKey};
+use crate::{list, report, Pet};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::path::PathBuf;
+
+/// The commands with their arguments, for completion and help.
+pub const COMMANDS: [(&str, &str); 11] = [
+    ("add", "[COUNT]"),
+    ("delete", "[ID]"),
+    ("sort", "[KEY [asc|desc]]"),
+    ("filter", "[CATEGORY]"),
+    ("search", "[QUERY]"),
+    ("save-view", "NAME"),
+    ("export", "md|html|csv|json PATH"),
+    ("set", "FIELD VALUE"),
+    ("theme", "dark|light|mono"),
+    ("reset", ""),
+    ("quit", ""),
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    #[value(name = "md")]
+    Markdown,
+    Html,
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn render(self, pets: &[&Pet], now: DateTime<Utc>, extra_fields: bool) -> String {
+        match self {
+            ExportFormat::Markdown => report::render(report::ReportFormat::Markdown, pets, now),
+            ExportFormat::Html => report::render(report::ReportFormat::Html, pets, now),
+            ExportFormat::Csv => list::render(list::ListFormat::Csv, pets, now, extra_fields, None),
+            ExportFormat::Json => {
+                list::render(list::ListFormat::Json, pets, now, extra_fields, None)
+            }
+        }
+    }
+}
+
+/// A parsed command line: the action and the count to run it with, as if
+/// typed before its key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Command {
+    pub count: Option<usize>,
+    pub action: Action,
+}
+
+impl From<Action> for Command {
+    fn from(action: Action) -> Self {
+        Command {
+            count: None,
+            action,
+        }
+    }
+}
+
+/// Reads a command line, with or without the leading `:`.
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let line = line.strip_prefix(':').unwrap_or(line).trim_start();
+    let (name, rest) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(name, rest)| (name, rest.trim()));
+    let args: Vec<&str> = rest.split_whitespace().collect();
+    let usage = |name: &str| {
+        let (_, args) = COMMANDS
+            .iter()
+            .find(|(command, _)| *command == name)
+            .expect("usage of a known command");
+        format!("usage: :{} {}", name, args).trim_end().to_owned()
+    };
+    let command = match (name, args.as_slice()) {
+        ("add", []) => Action::AddPets.into(),
+        ("add", [count]) => Command {
+            count: Some(parse_number(count, "count")?),
+            action: Action::AddPets,
+        },
+        ("delete", []) => Action::Delete.into(),
+        ("delete", [id]) => Action::DeletePet(parse_number(id, "id")?).into(),
+        ("sort", []) => Action::SetSort(None).into(),
+        ("sort", [key]) => Action::SetSort(Some(parse_sort(key, "asc")?)).into(),
+        ("sort", [key, direction]) => Action::SetSort(Some(parse_sort(key, direction)?)).into(),
+        ("filter", []) => Action::SetCategory(None).into(),
+        ("filter", [category]) => Action::SetCategory(Some((*category).to_owned())).into(),
+        ("search", []) => Action::SetQuery(None).into(),
+        ("save-view", [name]) => Action::SaveView((*name).to_owned()).into(),
+        ("search", _) => {
+            Action::SetQuery(Some(Query::parse(rest).map_err(|err| err.to_string())?)).into()
+        }
+        ("export", [format, path]) => {
+            let format = ExportFormat::from_str(format, true).map_err(|_| {
+                format!(
+                    "unknown format '{}', expected md, html, csv or json",
+                    format
+                )
+            })?;
+            Action::ExportTo(format, PathBuf::from(path)).into()
+        }
+        ("set", [_, _, ..]) => Action::BatchSet(Assignment::parse(rest)?).into(),
+        ("theme", [name]) => {
+            Action::SetTheme(Theme::named(name).map_err(|err| err.to_string())?).into()
+        }
+        ("reset", []) => Action::ResetDb.into(),
+        ("quit" | "q", []) => Action::Quit.into(),
+        ("", _) => return Err("type a command, e.g. :sort age desc".to_owned()),
+        (name, _) if COMMANDS.iter().any(|(command, _)| *command == name) => {
+            return Err(usage(name))
+        }
+        (name, _) => {
+            let names: Vec<&str> = COMMANDS.iter().map(|(command, _)| *command).collect();
+            return Err(format!(
+                "unknown command '{}', expected one of: {}",
+                name,
+                names.join(", ")
+            ));
+        }
+    };
+    Ok(command)
+}
+
+fn parse_number(input: &str, what: &str) -> Result<usize, String> {
+    input
+        .parse()
+        .map_err(|_| format!("invalid {} '{}', expected a number", what, input))
+}
+
+fn parse_sort(key: &str, direction: &str) -> Result<Sort, String> {
+    let key = SortKey::from_str(key, true).map_err(|_| {
+        format!(
+            "unknown sort key '{}', expected id, name, category, age or created",
+            key
+        )
+    })?;
+    let descending = match direction {
+        "asc" => false,
+        "desc" => true,
+        _ => {
+            return Err(format!(
+                "invalid direction '{}', expected asc or desc",
+                direction
+            ))
+        }
+    };
+    Ok(Sort { key, descending })
+}
+
+/// The command line with the command name completed, if what has been typed
+/// of it starts only one command, or as far as the commands it starts agree.
+pub fn complete(text: &str) -> Option<String> {
+    if text.contains(char::is_whitespace) {
+        return None;
+    }
+    let matching: Vec<&str> = COMMANDS
+        .iter()
+        .map(|(command, _)| *command)
+        .filter(|command| command.starts_with(text))
+        .collect();
+    match matching.as_slice() {
+        [] => None,
+        [command] => Some(format!("{} ", command)),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.len(), |common, command| {
+                first
+                    .char_indices()
+                    .zip(command.chars())
+                    .take_while(|((start, a), b)| *start < common && a == b)
+                    .map(|((start, a), _)| start + a.len_utf8())
+                    .last()
+                    .unwrap_or(0)
+            });
+            Some(first[..common].to_owned()).filter(|common| common.len() > text.len())
+        }
+    }
+}
+
+/// What the argument at `position` of command `name` is completed from.
+fn argument_candidates<'a>(
+    name: &str,
+    position: usize,
+    pets: &'a [Pet],
+) -> Option<Box<dyn Candidates + 'a>> {
+    let source: Box<dyn Candidates + 'a> = match (name, position) {
+        ("sort", 0) => Box::new(Words(&["id", "name", "category", "age", "created"])),
+        ("sort", 1) => Box::new(Words(&["asc", "desc"])),
+        ("filter", 0) => Box::new(Categories(pets)),
+        ("search", _) => Box::new(Both(PetNames(pets), Categories(pets))),
+        ("export", 0) => Box::new(Words(&["md", "html", "csv", "json"])),
+        ("theme", 0) => Box::new(Words(&["dark", "light", "mono"])),
+        _ => return None,
+    };
+    Some(source)
+}
+
+/// The open command line.
+#[derive(Default)]
+pub struct Palette {
+    input: TextInput,
+    /// Why the last command submitted couldn't be run, until the next key.
+    error: Option<String>,
+    history: HistoryCursor,
+    completer: Completer,
+}
+
+pub enum PaletteOutcome {
+    Open,
+    Cancelled,
+    Submitted(String),
+}
+
+impl Palette {
+    pub fn input(&self) -> &TextInput {
+        &self.input
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Which argument completion is shown and of how many.
+    pub fn completion(&self) -> Option<(usize, usize)> {
+        self.completer.position()
+    }
+
+    /// Puts a submitted command back to be corrected.
+    pub fn reopen(line: String, error: String) -> Palette {
+        Palette {
+            input: TextInput::new(&line),
+            error: Some(error),
+            history: HistoryCursor::default(),
+            completer: Completer::default(),
+        }
+    }
+
+    /// `history` has the oldest command first. `pets` are what arguments
+    /// like the category are completed from.
+    pub fn handle_key(
+        &mut self,
+        key: KeyEvent,
+        history: &[String],
+        pets: &[Pet],
+    ) -> PaletteOutcome {
+        self.error = None;
+        if key.code != KeyCode::Tab {
+            self.completer.reset();
+        }
+        match key.code {
+            KeyCode::Tab => {
+                let text = self.input.text().to_owned();
+                let completed = match text.split_once(char::is_whitespace) {
+                    None => complete(&text),
+                    Some((name, _)) => {
+                        let (start, word) = text
+                            .rsplit_once(char::is_whitespace)
+                            .expect("there is a space");
+                        let position = text[name.len()..].split_whitespace().count()
+                            - usize::from(!word.is_empty());
+                        argument_candidates(name, position, pets)
+                            .and_then(|source| self.completer.complete(word, source.as_ref()))
+                            .map(|word| format!("{} {}", start, word))
+                    }
+                };
+                if let Some(completed) = completed {
+                    self.input = TextInput::new(&completed);
+                }
+            }
+            KeyCode::Up => {
+                if let Some(line) = self.history.older(history) {
+                    self.input = TextInput::new(line);
+                }
+            }
+            KeyCode::Down => self.input = TextInput::new(self.history.newer(history)),
+            code => match self.input.handle_key(code) {
+                InputOutcome::Editing => {}
+                InputOutcome::Cancelled => return PaletteOutcome::Cancelled,
+                InputOutcome::Submitted => {
+                    return PaletteOutcome::Submitted(self.input.text().to_owned())
+                }
+            },
+        }
+        PaletteOutcome::Open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pet_status::PetStatus;
+    use crate::ui_tests::pet;
+    use crossterm::event::KeyModifiers;
+
+    fn action(line: &str) -> Action {
+        let command = parse(line).unwrap_or_else(|err| panic!("{:?}: {}", line, err));
+        assert_eq!(command.count, None, "{:?}", line);
+        command.action
+    }
+
+    fn sort(key: SortKey, descending: bool) -> Action {
+        Action::SetSort(Some(Sort { key, descending }))
+    }
+
+    #[test]
+    fn parses_every_command() {
+        assert_eq!(action(":add"), Action::AddPets);
+        assert_eq!(
+            parse("add 3"),
+            Ok(Command {
+                count: Some(3),
+                action: Action::AddPets
+            })
+        );
+        assert_eq!(action("delete"), Action::Delete);
+        assert_eq!(action("delete 42"), Action::DeletePet(42));
+        assert_eq!(action("sort"), Action::SetSort(None));
+        assert_eq!(action("sort Age"), sort(SortKey::Age, false));
+        assert_eq!(action("sort name desc"), sort(SortKey::Name, true));
+        assert_eq!(action("filter"), Action::SetCategory(None));
+        assert_eq!(
+            action("filter cats"),
+            Action::SetCategory(Some("cats".to_owned()))
+        );
+        assert_eq!(action("search"), Action::SetQuery(None));
+        assert_eq!(
+            action("search category:cats  rex"),
+            Action::SetQuery(Some(Query::parse("category:cats  rex").unwrap()))
+        );
+        assert_eq!(
+            action("save-view dogs"),
+            Action::SaveView("dogs".to_owned())
+        );
+        assert_eq!(
+            action("export CSV out/pets.csv"),
+            Action::ExportTo(ExportFormat::Csv, PathBuf::from("out/pets.csv"))
+        );
+        assert_eq!(
+            action("set status adopted"),
+            Action::BatchSet(Assignment::Status(PetStatus::Adopted))
+        );
+        assert_eq!(action("theme mono"), Action::SetTheme(Theme::mono()));
+        assert_eq!(action("reset"), Action::ResetDb);
+        assert_eq!(action("quit"), Action::Quit);
+        assert_eq!(action("  :  q  "), Action::Quit);
+    }
+
+    #[test]
+    fn every_listed_command_parses_with_its_usage_arguments() {
+        let examples = [
+            ("add", "2"),
+            ("delete", "1"),
+            ("sort", "age desc"),
+            ("filter", "cats"),
+            ("search", "rex"),
+            ("save-view", "mine"),
+            ("export", "md pets.md"),
+            ("set", "category cats"),
+            ("theme", "dark"),
+            ("reset", ""),
+            ("quit", ""),
+        ];
+        assert_eq!(examples.len(), COMMANDS.len());
+        for ((name, _), (example, args)) in COMMANDS.iter().zip(examples) {
+            assert_eq!(name, &example);
+            assert!(parse(&format!("{} {}", name, args)).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn wrong_arguments_show_the_usage() {
+        assert_eq!(
+            parse("export csv").unwrap_err(),
+            "usage: :export md|html|csv|json PATH"
+        );
+        assert_eq!(parse("reset now").unwrap_err(), "usage: :reset");
+        assert_eq!(parse("save-view").unwrap_err(), "usage: :save-view NAME");
+        assert_eq!(parse("set status").unwrap_err(), "usage: :set FIELD VALUE");
+    }
+
+    #[test]
+    fn bad_arguments_say_what_was_expected() {
+        assert_eq!(
+            parse("add many").unwrap_err(),
+            "invalid count 'many', expected a number"
+        );
+        assert_eq!(
+            parse("delete -1").unwrap_err(),
+            "invalid id '-1', expected a number"
+        );
+        assert!(parse("sort weight")
+            .unwrap_err()
+            .contains("unknown sort key 'weight'"));
+        assert!(parse("sort age up")
+            .unwrap_err()
+            .contains("invalid direction 'up'"));
+        assert!(parse("export pdf pets.pdf")
+            .unwrap_err()
+            .contains("unknown format 'pdf'"));
+        assert!(parse("theme neon")
+            .unwrap_err()
+            .contains("unknown theme 'neon'"));
+        assert!(parse("search age:>>2").is_err());
+    }
+
+    #[test]
+    fn unknown_and_empty_commands() {
+        assert_eq!(
+            parse(":").unwrap_err(),
+            "type a command, e.g. :sort age desc"
+        );
+        assert_eq!(
+            parse("   ").unwrap_err(),
+            "type a command, e.g. :sort age desc"
+        );
+        let err = parse("frobnicate").unwrap_err();
+        assert!(err.starts_with("unknown command 'frobnicate', expected one of: add, delete"));
+    }
+
+    #[test]
+    fn completes_command_names() {
+        assert_eq!(complete("ex").as_deref(), Some("export "));
+        assert_eq!(complete("se").as_deref(), None);
+        assert_eq!(complete("sa").as_deref(), Some("save-view "));
+        assert_eq!(complete("r").as_deref(), Some("reset "));
+        assert_eq!(complete("zz"), None);
+        assert_eq!(complete("sort a"), None);
+    }
+
+    fn press(
+        palette: &mut Palette,
+        code: KeyCode,
+        history: &[String],
+        pets: &[Pet],
+    ) -> PaletteOutcome {
+        palette.handle_key(KeyEvent::new(code, KeyModifiers::NONE), history, pets)
+    }
+
+    fn type_text(palette: &mut Palette, text: &str) {
+        for c in text.chars() {
+            press(palette, KeyCode::Char(c), &[], &[]);
+        }
+    }
+
+    #[test]
+    fn up_and_down_go_through_the_history() {
+        let history = ["sort age".to_owned(), "filter cats".to_owned()];
+        let mut palette = Palette::default();
+        press(&mut palette, KeyCode::Up, &history, &[]);
+        assert_eq!(palette.input().text(), "filter cats");
+        press(&mut palette, KeyCode::Up, &history, &[]);
+        assert_eq!(palette.input().text(), "sort age");
+        press(&mut palette, KeyCode::Up, &history, &[]);
+        assert_eq!(palette.input().text(), "sort age");
+        press(&mut palette, KeyCode::Down, &history, &[]);
+        assert_eq!(palette.input().text(), "filter cats");
+        press(&mut palette, KeyCode::Down, &history, &[]);
+        assert_eq!(palette.input().text(), "");
+    }
+
+    #[test]
+    fn tab_completes_names_and_arguments() {
+        let pets = [pet(1, "Rex", "dogs"), pet(2, "Coco", "cats")];
+        let mut palette = Palette::default();
+        type_text(&mut palette, "fi");
+        press(&mut palette, KeyCode::Tab, &[], &pets);
+        assert_eq!(palette.input().text(), "filter ");
+        type_text(&mut palette, "c");
+        press(&mut palette, KeyCode::Tab, &[], &pets);
+        assert_eq!(palette.input().text(), "filter cats");
+        let mut palette = Palette::default();
+        type_text(&mut palette, "sort age d");
+        press(&mut palette, KeyCode::Tab, &[], &pets);
+        assert_eq!(palette.input().text(), "sort age desc");
+    }
+
+    #[test]
+    fn enter_submits_and_esc_cancels() {
+        let mut palette = Palette::default();
+        type_text(&mut palette, "quit");
+        assert!(matches!(
+            press(&mut palette, KeyCode::Enter, &[], &[]),
+            PaletteOutcome::Submitted(line) if line == "quit"
+        ));
+        assert!(matches!(
+            press(&mut palette, KeyCode::Esc, &[], &[]),
+            PaletteOutcome::Cancelled
+        ));
+    }
+
+    #[test]
+    fn a_reopened_command_keeps_its_error_until_the_next_key() {
+        let mut palette = Palette::reopen("sort weight".to_owned(), "unknown sort key".to_owned());
+        assert_eq!(palette.input().text(), "sort weight");
+        assert_eq!(palette.error(), Some("unknown sort key"));
+        press(&mut palette, KeyCode::Backspace, &[], &[]);
+        assert_eq!(palette.error(), None);
+    }
+}
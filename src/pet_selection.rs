@@ -0,0 +1,41 @@
+//! The Pets selection follows the pet rather than the row: after pets were
+//! added, deleted, sorted, filtered or changed by another program, it stays
+//! on the pet it was on, wherever that moved to. Only if that pet is no
+//! longer shown does it stay on the row, so that after a delete it lands on
+//! the next pet, or on the one before if the last was deleted.
+
+/// The row to select among the pets with `ids`, in display order, given
+/// the pet the selection was on and the row it was at.
+pub fn follow(ids: &[usize], pet_id: Option<usize>, row: Option<usize>) -> Option<usize> {
+    if ids.is_empty() {
+        return None;
+    }
+    let followed = pet_id.and_then(|pet_id| ids.iter().position(|id| *id == pet_id));
+    Some(followed.unwrap_or_else(|| row.unwrap_or(0).min(ids.len() - 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_selection_stays_on_its_pet_wherever_it_moved() {
+        assert_eq!(follow(&[3, 1, 2], Some(2), Some(1)), Some(2));
+        assert_eq!(follow(&[9, 3, 1, 2], Some(3), Some(2)), Some(1));
+        assert_eq!(follow(&[1], Some(1), Some(5)), Some(0));
+    }
+
+    #[test]
+    fn a_deleted_pet_leaves_the_selection_on_the_next() {
+        assert_eq!(follow(&[1, 3, 4], Some(2), Some(1)), Some(1));
+        assert_eq!(follow(&[1, 2, 3], Some(4), Some(3)), Some(2));
+        assert_eq!(follow(&[], Some(1), Some(0)), None);
+    }
+
+    #[test]
+    fn without_a_pet_the_row_or_the_first_is_kept() {
+        assert_eq!(follow(&[1, 2, 3], None, Some(2)), Some(2));
+        assert_eq!(follow(&[1, 2, 3], None, None), Some(0));
+        assert_eq!(follow(&[1, 2], Some(7), None), Some(0));
+    }
+}
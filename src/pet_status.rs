@@ -0,0 +1,58 @@
+//! Where a pet is in the rescue's workflow, from intake to adoption.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Pets from files written before there were statuses are available. An
+/// unknown status in the file is a parse error rather than being guessed at.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PetStatus {
+    /// Just taken in, not up for adoption yet.
+    Intake,
+    #[default]
+    Available,
+    Adopted,
+}
+
+impl PetStatus {
+    pub const ALL: [PetStatus; 3] = [PetStatus::Intake, PetStatus::Available, PetStatus::Adopted];
+
+    /// The next step of the workflow, back to intake after adopted for pets
+    /// that are returned.
+    pub fn next(self) -> PetStatus {
+        match self {
+            PetStatus::Intake => PetStatus::Available,
+            PetStatus::Available => PetStatus::Adopted,
+            PetStatus::Adopted => PetStatus::Intake,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PetStatus::Intake => "intake",
+            PetStatus::Available => "available",
+            PetStatus::Adopted => "adopted",
+        }
+    }
+}
+
+impl fmt::Display for PetStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Restricts the Pets view to one status, cycling through all of them and
+/// then back to every pet, like the category filter.
+pub fn cycle_filter(filter: Option<PetStatus>) -> Option<PetStatus> {
+    match filter {
+        None => Some(PetStatus::ALL[0]),
+        Some(status) => PetStatus::ALL
+            .iter()
+            .skip_while(|candidate| **candidate != status)
+            .nth(1)
+            .copied(),
+    }
+}
@@ -0,0 +1,385 @@
+//! The photo preview of the Pets tab. Each terminal cell shows two pixels, one
+//! above the other, as an upper half block colored with the top pixel on a
+//! background of the bottom one. Photos are read as Netpbm files (`.ppm` and
+//! `.pgm`, binary or plain), which any image tool can write.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+
+pub type Rgb = [u8; 3];
+
+/// Decoded pixels, row by row.
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    /// `None` unless there is exactly one pixel per position.
+    pub fn new(width: usize, height: usize, pixels: Vec<Rgb>) -> Option<Image> {
+        (width > 0 && height > 0 && pixels.len() == width * height).then_some(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// One terminal cell of a preview. The bottom pixel is missing in the last
+/// row of images with an odd number of rows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cell {
+    pub top: Rgb,
+    pub bottom: Option<Rgb>,
+}
+
+/// Reads a PPM (`P3`, `P6`) or PGM (`P2`, `P5`) file with up to 8 bits per
+/// channel.
+pub fn decode(bytes: &[u8]) -> Result<Image, String> {
+    let mut header = Header { bytes, position: 0 };
+    let magic = header.token()?;
+    let (channels, plain) = match magic.as_str() {
+        "P2" => (1, true),
+        "P3" => (3, true),
+        "P5" => (1, false),
+        "P6" => (3, false),
+        _ => return Err("not a PPM or PGM image".to_owned()),
+    };
+    let width = header.number()?;
+    let height = header.number()?;
+    let max = header.number()?;
+    if max == 0 || max > 255 {
+        return Err(format!("unsupported maximum value {}", max));
+    }
+    let count = width
+        .checked_mul(height)
+        .and_then(|count| count.checked_mul(channels))
+        .ok_or("image is too large")?;
+    let samples: Vec<usize> = if plain {
+        (0..count)
+            .map(|_| header.number())
+            .collect::<Result<_, _>>()?
+    } else {
+        // A single whitespace byte separates the header from the samples.
+        let start = header.position + 1;
+        let data = bytes
+            .get(start..start + count)
+            .ok_or("image data is truncated")?;
+        data.iter().map(|&sample| usize::from(sample)).collect()
+    };
+    let scale = |sample: usize| (sample.min(max) * 255 / max) as u8;
+    let pixels = samples
+        .chunks(channels)
+        .map(|pixel| match pixel {
+            [gray] => [scale(*gray); 3],
+            [r, g, b] => [scale(*r), scale(*g), scale(*b)],
+            _ => unreachable!("chunks have one or three samples"),
+        })
+        .collect();
+    Image::new(width, height, pixels).ok_or_else(|| "image is empty".to_owned())
+}
+
+struct Header<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Header<'_> {
+    /// The next whitespace-separated token, skipping `#` comments.
+    fn token(&mut self) -> Result<String, String> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                Some(b'#') => {
+                    while self
+                        .bytes
+                        .get(self.position)
+                        .is_some_and(|byte| *byte != b'\n')
+                    {
+                        self.position += 1;
+                    }
+                }
+                Some(_) => break,
+                None => return Err("image header is truncated".to_owned()),
+            }
+        }
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.position]).into_owned())
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| format!("invalid number '{}' in image", token))
+    }
+}
+
+/// The image scaled to fit `columns` by `rows` cells, keeping its aspect
+/// ratio. Each output pixel is the average of the pixels it covers.
+pub fn to_cells(image: &Image, columns: u16, rows: u16) -> Vec<Vec<Cell>> {
+    let (max_width, max_height) = (f64::from(columns), f64::from(rows) * 2.0);
+    let scale = (max_width / image.width as f64).min(max_height / image.height as f64);
+    let width =
+        ((image.width as f64 * scale).round() as usize).clamp(1, usize::from(columns.max(1)));
+    let height = ((image.height as f64 * scale).round() as usize).max(1);
+    let sample = |x: usize, y: usize| -> Rgb {
+        let (x0, x1) = span(x, width, image.width);
+        let (y0, y1) = span(y, height, image.height);
+        let mut sums = [0usize; 3];
+        for source_y in y0..y1 {
+            for source_x in x0..x1 {
+                for (sum, channel) in sums.iter_mut().zip(image.pixel(source_x, source_y)) {
+                    *sum += usize::from(channel);
+                }
+            }
+        }
+        let count = (x1 - x0) * (y1 - y0);
+        sums.map(|sum| (sum / count) as u8)
+    };
+    (0..height)
+        .step_by(2)
+        .map(|y| {
+            (0..width)
+                .map(|x| Cell {
+                    top: sample(x, y),
+                    bottom: (y + 1 < height).then(|| sample(x, y + 1)),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The cells as upper half blocks in the pixels' colors, or without colors as
+/// characters getting denser with the brightness of the cell.
+pub fn to_spans(cells: &[Vec<Cell>], colored: bool) -> Vec<Spans<'static>> {
+    cells
+        .iter()
+        .map(|row| {
+            let spans: Vec<Span> = row
+                .iter()
+                .map(|cell| {
+                    if colored {
+                        let [r, g, b] = cell.top;
+                        let style = Style::default().fg(Color::Rgb(r, g, b));
+                        match cell.bottom {
+                            Some([r, g, b]) => Span::styled("▀", style.bg(Color::Rgb(r, g, b))),
+                            None => Span::styled("▀", style),
+                        }
+                    } else {
+                        Span::raw(shade(cell).to_string())
+                    }
+                })
+                .collect();
+            Spans::from(spans)
+        })
+        .collect()
+}
+
+const SHADES: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+fn shade(cell: &Cell) -> char {
+    let luma = |[r, g, b]: Rgb| 0.299 * f64::from(r) + 0.587 * f64::from(g) + 0.114 * f64::from(b);
+    let brightness = match cell.bottom {
+        Some(bottom) => (luma(cell.top) + luma(bottom)) / 2.0,
+        None => luma(cell.top),
+    };
+    SHADES[((brightness / 256.0) * SHADES.len() as f64) as usize]
+}
+
+/// The source pixels that output pixel `index` of `output` covers, at least
+/// one.
+fn span(index: usize, output: usize, source: usize) -> (usize, usize) {
+    let start = index * source / output;
+    let end = ((index + 1) * source / output).max(start + 1);
+    (start, end.min(source))
+}
+
+type Preview = Result<Vec<Vec<Cell>>, String>;
+
+/// Previews by path, size and modification time, so the photo of a pet is
+/// only decoded again when the pane is resized or the file changes.
+#[derive(Default)]
+pub struct PhotoCache {
+    previews: HashMap<(PathBuf, Option<SystemTime>, u16, u16), Preview>,
+}
+
+/// Enough for the photos of a long session of browsing without holding on to
+/// every size a resize went through.
+const CACHE_CAPACITY: usize = 64;
+
+impl PhotoCache {
+    pub fn preview(&mut self, path: &Path, columns: u16, rows: u16) -> &Preview {
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let key = (path.to_owned(), modified, columns, rows);
+        if !self.previews.contains_key(&key) && self.previews.len() >= CACHE_CAPACITY {
+            self.previews.clear();
+        }
+        self.previews.entry(key).or_insert_with(|| {
+            let bytes = fs::read(path).map_err(|err| err.to_string())?;
+            Ok(to_cells(&decode(&bytes)?, columns, rows))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgb = [255, 0, 0];
+    const GREEN: Rgb = [0, 255, 0];
+    const BLUE: Rgb = [0, 0, 255];
+    const WHITE: Rgb = [255, 255, 255];
+    const BLACK: Rgb = [0, 0, 0];
+
+    /// A `width` by `height` image with each pixel from `color`.
+    fn generated(width: usize, height: usize, color: impl Fn(usize, usize) -> Rgb) -> Image {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| color(x, y))
+            .collect();
+        Image::new(width, height, pixels).unwrap()
+    }
+
+    /// Red, green, blue and white quarters, clockwise from the top left.
+    fn quarters(size: usize) -> Image {
+        generated(size, size, |x, y| match (x < size / 2, y < size / 2) {
+            (true, true) => RED,
+            (false, true) => GREEN,
+            (false, false) => BLUE,
+            (true, false) => WHITE,
+        })
+    }
+
+    #[test]
+    fn each_cell_is_two_pixels_stacked() {
+        let cells = to_cells(&quarters(8), 2, 1);
+        assert_eq!(
+            cells,
+            [[
+                Cell {
+                    top: RED,
+                    bottom: Some(WHITE)
+                },
+                Cell {
+                    top: GREEN,
+                    bottom: Some(BLUE)
+                }
+            ]]
+        );
+    }
+
+    #[test]
+    fn pixels_are_averaged_when_scaled_down() {
+        let checkers = generated(2, 2, |x, y| if (x + y) % 2 == 0 { WHITE } else { BLACK });
+        assert_eq!(
+            to_cells(&checkers, 1, 1),
+            [[Cell {
+                top: [127, 127, 127],
+                bottom: None
+            }]]
+        );
+    }
+
+    #[test]
+    fn keeps_the_aspect_ratio() {
+        let wide = generated(100, 50, |_, _| RED);
+        let cells = to_cells(&wide, 20, 20);
+        assert_eq!((cells[0].len(), cells.len()), (20, 5));
+        let tall = generated(10, 100, |_, _| RED);
+        let cells = to_cells(&tall, 40, 10);
+        assert_eq!((cells[0].len(), cells.len()), (2, 10));
+    }
+
+    #[test]
+    fn small_images_are_scaled_up_to_fit() {
+        let cells = to_cells(&generated(1, 1, |_, _| BLUE), 4, 2);
+        assert_eq!((cells[0].len(), cells.len()), (4, 2));
+        assert!(cells.iter().flatten().all(|cell| cell.top == BLUE));
+    }
+
+    #[test]
+    fn an_odd_number_of_pixel_rows_leaves_the_last_bottom_out() {
+        let cells = to_cells(&generated(3, 3, |_, _| GREEN), 3, 2);
+        assert_eq!(cells.len(), 2);
+        assert!(cells[0].iter().all(|cell| cell.bottom == Some(GREEN)));
+        assert!(cells[1].iter().all(|cell| cell.bottom.is_none()));
+    }
+
+    #[test]
+    fn spans_are_colored_half_blocks_or_shades() {
+        let cells = to_cells(&quarters(2), 2, 1);
+        let spans = to_spans(&cells, true);
+        let first = &spans[0].0[0];
+        assert_eq!(first.content, "▀");
+        assert_eq!(first.style.fg, Some(Color::Rgb(255, 0, 0)));
+        assert_eq!(first.style.bg, Some(Color::Rgb(255, 255, 255)));
+        let shades = |cells: &[Vec<Cell>]| -> String {
+            to_spans(cells, false)[0]
+                .0
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect()
+        };
+        let black_and_white = to_cells(&generated(2, 1, |x, _| [BLACK, WHITE][x]), 2, 1);
+        assert_eq!(shades(&black_and_white), " @");
+    }
+
+    #[test]
+    fn decodes_plain_and_binary_netpbm() {
+        let plain = decode(b"P3\n# a comment\n2 1\n255\n255 0 0  0 0 255\n").unwrap();
+        assert_eq!(plain.pixels, [RED, BLUE]);
+        let binary = decode(&[b"P6 1 1 255\n".as_slice(), &[0, 255, 0]].concat()).unwrap();
+        assert_eq!(binary.pixels, [GREEN]);
+        let gray = decode(b"P2 2 1 15 15 0").unwrap();
+        assert_eq!(gray.pixels, [WHITE, BLACK]);
+        let gray = decode(&[b"P5 1 1 255 ".as_slice(), &[128]].concat()).unwrap();
+        assert_eq!(gray.pixels, [[128; 3]]);
+    }
+
+    #[test]
+    fn refuses_broken_images() {
+        for bytes in [
+            b"GIF89a".as_slice(),
+            b"P3 2 1",
+            b"P3 2 1 255 1 2 3",
+            b"P3 0 0 255",
+            b"P3 1 1 0 0 0 0",
+            b"P3 1 1 65535 0 0 0",
+            b"P3 x 1 255",
+            b"P6 2 2 255\n\x01\x02",
+            b"P6 99999999999 99999999999 255\n",
+        ] {
+            assert!(
+                decode(bytes).is_err(),
+                "{:?}",
+                String::from_utf8_lossy(bytes)
+            );
+        }
+    }
+
+    #[test]
+    fn a_missing_photo_is_an_error_preview() {
+        let mut cache = PhotoCache::default();
+        let path = Path::new("/nonexistent/pet-cli/photo.ppm");
+        assert!(cache.preview(path, 10, 5).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+//! The selection of a small overlay list, like the `V` and `"` pickers:
+//! `j`/`k` or the arrows move, Enter chooses, Esc or `q` closes. Other keys
+//! are left to the overlay, for what it does to the selected row.
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+#[derive(Default)]
+pub struct Picker {
+    selected: usize,
+}
+
+pub enum PickerKey {
+    /// The key moved the selection, or did nothing.
+    Handled,
+    Closed,
+    Chosen(usize),
+    /// A key of the overlay's own, with a row selected.
+    Other(KeyCode),
+}
+
+impl Picker {
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Keeps the selection on a row once the list got shorter.
+    pub fn clamp(&mut self, len: usize) {
+        self.selected = self.selected.min(len.saturating_sub(1));
+    }
+
+    /// Handles `key` for a list of `len` rows.
+    pub fn handle_key(&mut self, key: KeyEvent, len: usize) -> PickerKey {
+        let last = len.saturating_sub(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return PickerKey::Closed,
+            _ if len == 0 => {}
+            KeyCode::Enter => return PickerKey::Chosen(self.selected),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(last),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            code => return PickerKey::Other(code),
+        }
+        PickerKey::Handled
+    }
+}
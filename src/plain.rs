@@ -0,0 +1,215 @@
+//! `pet-cli --plain`: the UI as lines of text instead of a full screen, for
+//! screen readers and shell buffers like Emacs'. It prints the pets the view
+//! shows, then reads one command per line: the `:` commands of the command
+//! line, with or without the `:`, and a few of its own to look at pets.
+//! There is no raw mode and no cursor movement, so stdin and stdout can be
+//! pipes.
+
+use crate::palette;
+use crate::saved_views::SavedView;
+use crate::status::StatusLevel;
+use crate::{
+    apply_action, confirm, decline, finish_loading, finish_write, jump_to_pet, list_cell,
+    pet_fields, read_database, AppError, AppState, MenuItem, ResponseToUserInput,
+};
+use chrono::Utc;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// The commands only plain mode has, with their arguments.
+const COMMANDS: [(&str, &str, &str); 4] = [
+    ("list", "", "print the pets the view shows"),
+    (
+        "show",
+        "[ID]",
+        "print the fields of a pet, the selected one by default",
+    ),
+    (
+        "select",
+        "ID",
+        "select a pet, for the commands that act on it",
+    ),
+    ("help", "", "print this help"),
+];
+
+/// Runs until `quit` or the end of stdin. Writes are waited for after each
+/// command, so what is printed next includes them.
+pub async fn run(app_state: &mut AppState) -> Result<(), AppError> {
+    let pets = read_database().map_err(AppError::db_read)?.pets;
+    finish_loading(Ok(pets), app_state);
+    app_state.active_menu_item = MenuItem::Pets;
+    print_messages(app_state);
+    let mut listed = listing(app_state);
+    println!("{}", listed);
+    let mut lines = io::stdin().lock().lines();
+    let interactive = io::stdin().is_terminal();
+    loop {
+        if interactive {
+            print!("> ");
+            let _ = io::stdout().flush();
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line.map_err(AppError::Terminal)?;
+        if run_command(line.trim(), app_state)? == ResponseToUserInput::Stop {
+            break;
+        }
+        // Answering one question can lead to another.
+        while let Some(confirmation) = app_state.pending_confirmation.take() {
+            println!("{}", confirmation.prompt());
+            let answer = lines.next().transpose().map_err(AppError::Terminal)?;
+            if answer.is_some_and(|answer| answer.trim().eq_ignore_ascii_case("y")) {
+                confirm(confirmation, app_state);
+            } else {
+                decline(confirmation, app_state);
+            }
+        }
+        while app_state.db_writer.is_busy() {
+            let Some(done) = app_state.db_writer.done_rx.recv().await else {
+                break;
+            };
+            finish_write(done, app_state);
+        }
+        print_messages(app_state);
+        // The list again only when the command changed it.
+        let now_listed = listing(app_state);
+        if now_listed != listed {
+            println!("{}", now_listed);
+            listed = now_listed;
+        }
+    }
+    Ok(())
+}
+
+fn run_command(line: &str, app_state: &mut AppState) -> Result<ResponseToUserInput, AppError> {
+    let (name, argument) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(name, rest)| (name, rest.trim()));
+    match name {
+        "" => {}
+        "list" => println!("{}", listing(app_state)),
+        "help" => print_help(),
+        "show" => match pet_id(argument, app_state) {
+            Ok(pet_id) => print_pet(pet_id, app_state),
+            Err(err) => app_state.status_line.error(err),
+        },
+        "select" => match argument.parse() {
+            Ok(pet_id) => {
+                let pets = read_database().map_err(AppError::db_read)?.pets;
+                if jump_to_pet(app_state, &pets, pet_id) {
+                    app_state.status_line.info(format!("selected #{}", pet_id));
+                } else {
+                    app_state.status_line.error(format!("no pet #{}", pet_id));
+                }
+            }
+            Err(_) => app_state.status_line.error("usage: select ID"),
+        },
+        name if !is_command(name) => {
+            let names: Vec<&str> = COMMANDS
+                .iter()
+                .map(|(command, ..)| *command)
+                .chain(palette::COMMANDS.iter().map(|(command, _)| *command))
+                .collect();
+            app_state.status_line.error(format!(
+                "unknown command '{}', expected one of: {}",
+                name,
+                names.join(", ")
+            ));
+        }
+        _ => match palette::parse(line) {
+            Ok(command) => {
+                app_state.pending_count = command.count;
+                return apply_action(command.action, app_state);
+            }
+            Err(err) => app_state.status_line.error(err),
+        },
+    }
+    Ok(ResponseToUserInput::Continue)
+}
+
+/// Whether `name`, with or without a `:`, is a command of the command line.
+fn is_command(name: &str) -> bool {
+    let name = name.strip_prefix(':').unwrap_or(name);
+    name.is_empty()
+        || name == "q"
+        || palette::COMMANDS
+            .iter()
+            .any(|(command, _)| *command == name)
+}
+
+/// The id typed after `show`, or the selected pet's.
+fn pet_id(argument: &str, app_state: &AppState) -> Result<usize, String> {
+    if !argument.is_empty() {
+        return argument.parse().map_err(|_| "usage: show [ID]".to_owned());
+    }
+    let pets = read_database().map_err(|err| err.to_string())?.pets;
+    let visible = app_state.visible_pets(&pets);
+    app_state
+        .selected_pet(&visible)
+        .map(|pet| pet.id)
+        .ok_or_else(|| "no pet is selected; type show ID".to_owned())
+}
+
+/// The message of the last command, if it left one, once.
+fn print_messages(app_state: &mut AppState) {
+    if let Some((text, level)) = app_state.status_line.take() {
+        match level {
+            StatusLevel::Info => println!("{}", text),
+            StatusLevel::Error => println!("error: {}", text),
+        }
+    }
+}
+
+/// The pets of the view, one per line with the columns of the list, after a
+/// line saying how many there are and what the view is.
+fn listing(app_state: &AppState) -> String {
+    let database = match read_database() {
+        Ok(database) => database,
+        Err(err) => return format!("error: {}", err),
+    };
+    let visible = app_state.visible_pets(&database.pets);
+    let selected = app_state.selected_pet(&visible).map(|pet| pet.id);
+    let view = SavedView::capture("", &app_state.view).describe();
+    let count = match visible.len() {
+        1 => "1 pet".to_owned(),
+        count => format!("{} pets", count),
+    };
+    let mut lines = vec![format!("{}, {}", count, view)];
+    for pet in &visible {
+        let cells: Vec<String> = app_state
+            .list_columns
+            .iter()
+            .map(|column| list_cell(*column, pet, &database.owners, app_state.lang))
+            .filter(|cell| !cell.is_empty())
+            .collect();
+        let marker = if selected == Some(pet.id) {
+            ", selected"
+        } else {
+            ""
+        };
+        lines.push(format!("#{} {}{}", pet.id, cells.join(", "), marker));
+    }
+    lines.join("\n")
+}
+
+fn print_pet(pet_id: usize, app_state: &mut AppState) {
+    let database = match read_database() {
+        Ok(database) => database,
+        Err(err) => return app_state.status_line.error(err.to_string()),
+    };
+    let Some(pet) = database.pets.iter().find(|pet| pet.id == pet_id) else {
+        return app_state.status_line.error(format!("no pet #{}", pet_id));
+    };
+    for (label, value) in pet_fields(pet, &database.owners, Utc::now(), app_state.lang) {
+        println!("{}: {}", label, value);
+    }
+}
+
+fn print_help() {
+    for (name, args, help) in COMMANDS {
+        println!("{}: {}", format!("{} {}", name, args).trim_end(), help);
+    }
+    for (name, args) in palette::COMMANDS {
+        println!("{}", format!("{} {}", name, args).trim_end());
+    }
+}
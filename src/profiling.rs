@@ -0,0 +1,35 @@
+//! Opt-in tracing instrumentation. Without the `profiling` feature every
+//! `profile_span!` expands to nothing, so release builds pay no cost.
+
+/// Opens a span that lasts until the end of the enclosing block.
+#[cfg(feature = "profiling")]
+macro_rules! profile_span {
+    ($name:literal) => {
+        let _profile_span = tracing::info_span!($name).entered();
+    };
+    ($name:literal, $($field:tt)+) => {
+        let _profile_span = tracing::info_span!($name, $($field)+).entered();
+    };
+}
+
+#[cfg(not(feature = "profiling"))]
+macro_rules! profile_span {
+    ($($ignored:tt)*) => {};
+}
+
+#[cfg(feature = "profiling")]
+pub use tracing_chrome::FlushGuard;
+
+/// Installs a subscriber writing Chrome trace events to `path`. The trace is
+/// only complete once the returned guard is dropped.
+#[cfg(feature = "profiling")]
+pub fn init(path: &std::path::Path) -> FlushGuard {
+    use tracing_subscriber::prelude::*;
+
+    let (chrome_layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+        .file(path)
+        .include_args(true)
+        .build();
+    tracing_subscriber::registry().with(chrome_layer).init();
+    guard
+}
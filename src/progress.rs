@@ -0,0 +1,229 @@
+//! Progress of the long DB writes, like importing or generating thousands of
+//! pets. They work through their pets in chunks on the blocking pool, telling
+//! the UI how far they are after each one and stopping, with nothing
+//! written, if it was cancelled meanwhile.
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// How many pets are worked through between reports.
+pub const CHUNK_SIZE: usize = 500;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    pub label: &'static str,
+    pub done: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    /// From 0 to 1; done if there is nothing to do.
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done.min(self.total) as f64 / self.total as f64
+        }
+    }
+}
+
+/// Shared between the UI, which sets it on Esc, and the write, which checks
+/// it between chunks.
+#[derive(Clone, Debug, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The operation was cancelled before it wrote anything.
+#[derive(Debug, PartialEq)]
+pub struct Cancelled;
+
+/// Where a write sends its progress and learns whether to stop.
+pub struct Reporter {
+    send: Box<dyn Fn(Progress) + Send + Sync>,
+    cancel: Cancel,
+}
+
+impl Reporter {
+    pub fn new(send: impl Fn(Progress) + Send + Sync + 'static, cancel: Cancel) -> Reporter {
+        Reporter {
+            send: Box::new(send),
+            cancel,
+        }
+    }
+
+    /// For the command line, where nobody watches or cancels.
+    pub fn none() -> Reporter {
+        Reporter::new(|_| {}, Cancel::default())
+    }
+
+    pub fn report(&self, label: &'static str, done: usize, total: usize) {
+        (self.send)(Progress { label, done, total });
+    }
+
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.cancel.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Calls `work` with `total` split into chunks of at most [`CHUNK_SIZE`],
+/// as the range of each, reporting after every chunk. Stops at the first
+/// error, or before a chunk if the operation was cancelled, and checks once
+/// more after the last one so that a cancel in time is never followed by a
+/// write.
+pub fn chunked<E: From<Cancelled>>(
+    label: &'static str,
+    total: usize,
+    reporter: &Reporter,
+    mut work: impl FnMut(Range<usize>) -> Result<(), E>,
+) -> Result<(), E> {
+    reporter.report(label, 0, total);
+    let mut done = 0;
+    while done < total {
+        reporter.check()?;
+        let end = (done + CHUNK_SIZE).min(total);
+        work(done..end)?;
+        done = end;
+        reporter.report(label, done, total);
+    }
+    Ok(reporter.check()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// What a reporter was sent, as done and total.
+    type Sent = Arc<Mutex<Vec<(usize, usize)>>>;
+
+    /// A reporter that keeps what it is sent, and cancels once `cancel_at`
+    /// are done if given.
+    fn recording(cancel_at: Option<usize>) -> (Reporter, Sent) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let cancel = Cancel::default();
+        let reporter = {
+            let sent = Arc::clone(&sent);
+            let flag = cancel.clone();
+            Reporter::new(
+                move |progress: Progress| {
+                    assert_eq!(progress.label, "checking");
+                    sent.lock().unwrap().push((progress.done, progress.total));
+                    if Some(progress.done) == cancel_at {
+                        flag.cancel();
+                    }
+                },
+                cancel,
+            )
+        };
+        (reporter, sent)
+    }
+
+    fn ranges(total: usize, reporter: &Reporter) -> (Result<(), Cancelled>, Vec<Range<usize>>) {
+        let mut ranges = Vec::new();
+        let result = chunked("checking", total, reporter, |range| {
+            ranges.push(range);
+            Ok(())
+        });
+        (result, ranges)
+    }
+
+    #[test]
+    fn work_comes_in_chunks_with_a_report_after_each() {
+        let (reporter, sent) = recording(None);
+        let (result, ranges) = ranges(1200, &reporter);
+        assert_eq!(result, Ok(()));
+        assert_eq!(ranges, [0..500, 500..1000, 1000..1200]);
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [(0, 1200), (500, 1200), (1000, 1200), (1200, 1200)]
+        );
+    }
+
+    #[test]
+    fn nothing_to_do_is_reported_once() {
+        let (reporter, sent) = recording(None);
+        let (result, ranges) = ranges(0, &reporter);
+        assert_eq!(result, Ok(()));
+        assert!(ranges.is_empty());
+        assert_eq!(*sent.lock().unwrap(), [(0, 0)]);
+    }
+
+    #[test]
+    fn a_cancel_stops_before_the_next_chunk() {
+        let (reporter, sent) = recording(Some(500));
+        let (result, ranges) = ranges(1200, &reporter);
+        assert_eq!(result, Err(Cancelled));
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0], 0..500);
+        assert_eq!(sent.lock().unwrap().last(), Some(&(500, 1200)));
+    }
+
+    #[test]
+    fn a_cancel_during_the_last_chunk_still_fails() {
+        let (reporter, _) = recording(Some(1200));
+        let (result, ranges) = ranges(1200, &reporter);
+        assert_eq!(result, Err(Cancelled));
+        assert_eq!(ranges.len(), 3);
+    }
+
+    #[test]
+    fn a_cancel_before_starting_does_no_work() {
+        let (reporter, _) = recording(Some(0));
+        let (result, ranges) = ranges(10, &reporter);
+        assert_eq!(result, Err(Cancelled));
+        assert!(ranges.is_empty());
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Failed {
+        Cancelled,
+        At(usize),
+    }
+
+    impl From<Cancelled> for Failed {
+        fn from(_: Cancelled) -> Self {
+            Failed::Cancelled
+        }
+    }
+
+    #[test]
+    fn an_error_stops_the_work() {
+        let mut chunks = 0;
+        let result = chunked("checking", 2000, &Reporter::none(), |range| {
+            chunks += 1;
+            if range.start == 500 {
+                return Err(Failed::At(range.start));
+            }
+            Ok(())
+        });
+        assert_eq!(result, Err(Failed::At(500)));
+        assert_eq!(chunks, 2);
+    }
+
+    #[test]
+    fn the_ratio_stays_between_0_and_1() {
+        let progress = |done, total| Progress {
+            label: "checking",
+            done,
+            total,
+        };
+        assert_eq!(progress(0, 0).ratio(), 1.0);
+        assert_eq!(progress(0, 4).ratio(), 0.0);
+        assert_eq!(progress(1, 4).ratio(), 0.25);
+        assert_eq!(progress(9, 4).ratio(), 1.0);
+    }
+}
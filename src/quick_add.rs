@@ -0,0 +1,181 @@
+//! `Ctrl+a`: adds a pet from little more than its name. The category, age
+//! and a prefix for the name come from the `add_category`, `add_age` and
+//! `add_name_prefix` config keys, and whatever is typed after the name, as
+//! in `Tom, dogs, 2y`, wins over them.
+
+use crate::age::{self, Typed};
+use crate::dates;
+use chrono::NaiveDate;
+
+/// The `add_` config keys.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AddDefaults {
+    pub category: Option<String>,
+    pub age_months: Option<usize>,
+    /// Put before every typed name, e.g. `Foster-`.
+    pub name_prefix: String,
+}
+
+/// The fields of the pet to add. Whether they make a valid pet is up to
+/// validation, as for any other pet.
+#[derive(Debug, PartialEq)]
+pub struct NewPet {
+    pub name: String,
+    pub category: String,
+    pub age_months: usize,
+    /// For an age typed in days or weeks, like `12d`.
+    pub birthdate: Option<NaiveDate>,
+}
+
+/// Reads `name[, category[, age]]` as typed into the prompt and takes what
+/// was left out, or left empty, from `defaults`. An age in days or weeks
+/// is kept as the birthdate it gives on `today`.
+pub fn merge(defaults: &AddDefaults, typed: &str, today: NaiveDate) -> Result<NewPet, String> {
+    let mut parts = typed.splitn(3, ',').map(str::trim);
+    let name = parts.next().unwrap_or_default();
+    if name.is_empty() {
+        return Err("type a name first".to_owned());
+    }
+    let category = match parts.next().filter(|category| !category.is_empty()) {
+        Some(category) => category.to_owned(),
+        None => defaults.category.clone().ok_or_else(|| {
+            "no category: type one after the name, or set add_category".to_owned()
+        })?,
+    };
+    let (age_months, birthdate) = match parts.next().filter(|age| !age.is_empty()) {
+        Some(age) => match age::parse_typed(age)? {
+            Typed::Months(months) => (months, None),
+            Typed::Days(days) => {
+                let birthdate = dates::born_days_ago(days, today)?;
+                let months = dates::months_between(birthdate, today).max(0) as usize;
+                (months, Some(birthdate))
+            }
+        },
+        None => (
+            defaults
+                .age_months
+                .ok_or_else(|| "no age: type one after the category, or set add_age".to_owned())?,
+            None,
+        ),
+    };
+    Ok(NewPet {
+        name: format!("{}{}", defaults.name_prefix, name),
+        category,
+        age_months,
+        birthdate,
+    })
+}
+
+/// What a bare name would add, shown when the prompt opens.
+pub fn describe(defaults: &AddDefaults) -> String {
+    let category = defaults.category.as_deref().unwrap_or("no category");
+    let age = defaults
+        .age_months
+        .map_or_else(|| "no age".to_owned(), age::format);
+    if defaults.name_prefix.is_empty() {
+        format!("a name alone adds {}, {}", category, age)
+    } else {
+        format!(
+            "a name alone adds {}…, {}, {}",
+            defaults.name_prefix, category, age
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, 10).unwrap()
+    }
+
+    fn foster_cats() -> AddDefaults {
+        AddDefaults {
+            category: Some("cats".to_owned()),
+            age_months: Some(12),
+            name_prefix: "Foster-".to_owned(),
+        }
+    }
+
+    fn new_pet(name: &str, category: &str, age_months: usize) -> NewPet {
+        NewPet {
+            name: name.to_owned(),
+            category: category.to_owned(),
+            age_months,
+            birthdate: None,
+        }
+    }
+
+    #[test]
+    fn a_name_alone_takes_the_defaults() {
+        assert_eq!(
+            merge(&foster_cats(), " Tom ", today()),
+            Ok(new_pet("Foster-Tom", "cats", 12))
+        );
+        assert_eq!(
+            merge(&foster_cats(), "Tom, , ", today()),
+            Ok(new_pet("Foster-Tom", "cats", 12))
+        );
+    }
+
+    #[test]
+    fn what_is_typed_wins_over_the_defaults() {
+        assert_eq!(
+            merge(&foster_cats(), "Tom, dogs", today()),
+            Ok(new_pet("Foster-Tom", "dogs", 12))
+        );
+        assert_eq!(
+            merge(&foster_cats(), "Tom, dogs, 2y6m", today()),
+            Ok(new_pet("Foster-Tom", "dogs", 30))
+        );
+        assert_eq!(
+            merge(&foster_cats(), "Tom, , 8m", today()),
+            Ok(new_pet("Foster-Tom", "cats", 8))
+        );
+    }
+
+    #[test]
+    fn days_and_weeks_give_a_birthdate() {
+        let pet = merge(&foster_cats(), "Tom, cats, 3w", today()).unwrap();
+        assert_eq!(pet.birthdate, NaiveDate::from_ymd_opt(2025, 2, 17));
+        assert_eq!(pet.age_months, 0);
+        let pet = merge(&foster_cats(), "Tom, cats, 40d", today()).unwrap();
+        assert_eq!(pet.age_months, 1);
+    }
+
+    #[test]
+    fn what_is_missing_and_has_no_default_is_asked_for() {
+        let none = AddDefaults::default();
+        assert_eq!(
+            merge(&none, "  ", today()),
+            Err("type a name first".to_owned())
+        );
+        assert_eq!(
+            merge(&none, "Tom", today()),
+            Err("no category: type one after the name, or set add_category".to_owned())
+        );
+        assert_eq!(
+            merge(&none, "Tom, cats", today()),
+            Err("no age: type one after the category, or set add_age".to_owned())
+        );
+        assert_eq!(
+            merge(&none, "Tom, cats, 1y", today()),
+            Ok(new_pet("Tom", "cats", 12))
+        );
+        let err = merge(&foster_cats(), "Tom, cats, old", today()).unwrap_err();
+        assert!(err.starts_with("invalid age 'old'"), "{}", err);
+    }
+
+    #[test]
+    fn the_prompt_says_what_a_name_alone_adds() {
+        assert_eq!(
+            describe(&foster_cats()),
+            "a name alone adds Foster-…, cats, 1 y"
+        );
+        assert_eq!(
+            describe(&AddDefaults::default()),
+            "a name alone adds no category, no age"
+        );
+    }
+}
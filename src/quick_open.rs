@@ -0,0 +1,64 @@
+use crate::fuzzy::fuzzy_rank;
+use crate::input::{InputOutcome, TextInput};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// The `Ctrl+p` palette for jumping to a pet by fuzzy-matching its name.
+#[derive(Default)]
+pub struct QuickOpen {
+    input: TextInput,
+    selected: usize,
+}
+
+pub enum QuickOpenOutcome {
+    Open,
+    Cancelled,
+    /// The user picked the candidate at this index of the names passed in.
+    Chosen(usize),
+}
+
+impl QuickOpen {
+    pub fn query(&self) -> &str {
+        self.input.text()
+    }
+
+    /// Position of the highlighted candidate within `matches`.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn matches(&self, names: &[&str]) -> Vec<(usize, i64)> {
+        fuzzy_rank(self.input.text(), names)
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent, names: &[&str]) -> QuickOpenOutcome {
+        let match_count = self.matches(names).len();
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Up => self.select_previous(),
+            KeyCode::Char('p') if ctrl => self.select_previous(),
+            KeyCode::Down => self.select_next(match_count),
+            KeyCode::Char('n') if ctrl => self.select_next(match_count),
+            code => match self.input.handle_key(code) {
+                InputOutcome::Editing => self.selected = 0,
+                InputOutcome::Cancelled => return QuickOpenOutcome::Cancelled,
+                InputOutcome::Submitted => {
+                    return match self.matches(names).get(self.selected) {
+                        Some((index, _)) => QuickOpenOutcome::Chosen(*index),
+                        None => QuickOpenOutcome::Cancelled,
+                    };
+                }
+            },
+        }
+        QuickOpenOutcome::Open
+    }
+
+    fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    fn select_next(&mut self, match_count: usize) {
+        if self.selected + 1 < match_count {
+            self.selected += 1;
+        }
+    }
+}
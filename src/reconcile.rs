@@ -0,0 +1,106 @@
+use crate::merge::Strategy;
+use crate::three_way::{Conflict, Plan};
+use crate::Database;
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// The overlay shown when a dry run is saved but the DB changed on disk
+/// since it started: what each side changed and which pets both did, then
+/// the conflicts one at a time if the changes are merged.
+pub struct Reconcile {
+    plan: Plan,
+    /// The DB on disk that `plan` merges with, byte for byte, so the save
+    /// can tell whether it changed yet again, and as read.
+    on_disk: Vec<u8>,
+    theirs: Database,
+    /// The conflict being decided, once merging was chosen.
+    current: Option<usize>,
+}
+
+pub enum ReconcileOutcome {
+    Open,
+    /// Back to the UI, without quitting.
+    Cancelled,
+    /// Save the dry run over their changes.
+    KeepMine,
+    /// Drop the dry run's changes.
+    KeepTheirs,
+    /// Every conflict is decided; `into_parts` has the plan to save.
+    Merge,
+}
+
+impl Reconcile {
+    pub fn new(plan: Plan, on_disk: Vec<u8>, theirs: Database) -> Reconcile {
+        Reconcile {
+            plan,
+            on_disk,
+            theirs,
+            current: None,
+        }
+    }
+
+    /// The plan, with the resolutions chosen, and the DB on disk as bytes
+    /// and as read.
+    pub fn into_parts(self) -> (Plan, Vec<u8>, Database) {
+        (self.plan, self.on_disk, self.theirs)
+    }
+
+    pub fn plan(&self) -> &Plan {
+        &self.plan
+    }
+
+    /// The index of the conflict being decided, if merging was chosen.
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn conflict(&self) -> Option<&Conflict> {
+        self.current.map(|current| &self.plan.conflicts[current])
+    }
+
+    /// First `m` and `t` keep one side and `a` merges, going through the
+    /// conflicts if there are any: `m`, `t` and `b` keep mine, theirs or both
+    /// and go to the next; `M`, `T` and `B` decide the rest the same way and
+    /// `k` goes back. Esc goes back to the UI.
+    pub fn handle_key(&mut self, key: KeyEvent) -> ReconcileOutcome {
+        let Some(current) = self.current else {
+            return match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => ReconcileOutcome::Cancelled,
+                KeyCode::Char('m') => ReconcileOutcome::KeepMine,
+                KeyCode::Char('t') => ReconcileOutcome::KeepTheirs,
+                KeyCode::Char('a') if self.plan.conflicts.is_empty() => ReconcileOutcome::Merge,
+                KeyCode::Char('a') => {
+                    self.current = Some(0);
+                    ReconcileOutcome::Open
+                }
+                _ => ReconcileOutcome::Open,
+            };
+        };
+        let (strategy, rest) = match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return ReconcileOutcome::Cancelled,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.current = Some(current.saturating_sub(1));
+                return ReconcileOutcome::Open;
+            }
+            KeyCode::Char('m') => (Strategy::Mine, false),
+            KeyCode::Char('t') => (Strategy::Theirs, false),
+            KeyCode::Char('b') => (Strategy::Both, false),
+            KeyCode::Char('M') => (Strategy::Mine, true),
+            KeyCode::Char('T') => (Strategy::Theirs, true),
+            KeyCode::Char('B') => (Strategy::Both, true),
+            _ => return ReconcileOutcome::Open,
+        };
+        let end = if rest {
+            self.plan.conflicts.len()
+        } else {
+            current + 1
+        };
+        for conflict in &mut self.plan.conflicts[current..end] {
+            conflict.resolution = Some(strategy);
+        }
+        if end < self.plan.conflicts.len() {
+            self.current = Some(end);
+            return ReconcileOutcome::Open;
+        }
+        ReconcileOutcome::Merge
+    }
+}
@@ -0,0 +1,311 @@
+//! What needs doing about the pets, listed on the Home tab: checkups that are
+//! overdue, birthdays coming up and pets that have waited a long time in
+//! intake. Pets in the Trash or adopted get no reminders, and neither do pets
+//! without the field a reminder is about.
+
+use crate::dates;
+use crate::pet_status::PetStatus;
+use crate::Pet;
+use chrono::{Datelike, NaiveDate};
+
+/// A pet whose last medical record is at least this old is due a checkup.
+pub const CHECKUP_MONTHS: i32 = 12;
+/// Birthdays are mentioned this many days ahead.
+pub const BIRTHDAY_DAYS: i64 = 30;
+/// How long a pet may stay in intake. There is no record of when the status
+/// was set, so this counts from when the pet was added.
+pub const INTAKE_DAYS: i64 = 30;
+
+/// Ordered so that sorting puts the most pressing last.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    CheckupDue { last: NaiveDate },
+    Birthday { on: NaiveDate, turning: i32 },
+    LongIntake { days: i64 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reminder {
+    pub pet_id: usize,
+    pub pet_name: String,
+    pub kind: Kind,
+}
+
+impl Reminder {
+    pub fn severity(&self) -> Severity {
+        match self.kind {
+            Kind::CheckupDue { .. } | Kind::LongIntake { .. } => Severity::Warning,
+            Kind::Birthday { .. } => Severity::Info,
+        }
+    }
+
+    /// Like `Rex: last checkup on 2023-01-05`, relative to `today`.
+    pub fn describe(&self, today: NaiveDate) -> String {
+        let what = match &self.kind {
+            Kind::CheckupDue { last } => format!("last medical record on {}", last),
+            Kind::Birthday { on, turning } => match (*on - today).num_days() {
+                0 => format!("turns {} today", turning),
+                1 => format!("turns {} tomorrow", turning),
+                days => format!("turns {} on {} (in {} days)", turning, on, days),
+            },
+            Kind::LongIntake { days } => format!("in intake for {} days", days),
+        };
+        format!("{}: {}", self.pet_name, what)
+    }
+}
+
+/// The reminders for `pets` on `today`, warnings first, then the soonest
+/// birthdays, then by pet id.
+pub fn compute_reminders(pets: &[Pet], today: NaiveDate) -> Vec<Reminder> {
+    let mut reminders = Vec::new();
+    for pet in pets {
+        if pet.is_archived() || pet.status == PetStatus::Adopted {
+            continue;
+        }
+        let remind = |kind| Reminder {
+            pet_id: pet.id,
+            pet_name: pet.name.clone(),
+            kind,
+        };
+        if let Some(last) = pet.records.iter().map(|record| record.date).max() {
+            if dates::months_between(last, today) >= CHECKUP_MONTHS {
+                reminders.push(remind(Kind::CheckupDue { last }));
+            }
+        }
+        if let Some((on, turning)) = pet.birthdate.and_then(|born| next_birthday(born, today)) {
+            if (on - today).num_days() <= BIRTHDAY_DAYS {
+                reminders.push(remind(Kind::Birthday { on, turning }));
+            }
+        }
+        if pet.status == PetStatus::Intake {
+            let days = (today - pet.created_at.date_naive()).num_days();
+            if days > INTAKE_DAYS {
+                reminders.push(remind(Kind::LongIntake { days }));
+            }
+        }
+    }
+    reminders.sort_by(|a, b| {
+        b.severity()
+            .cmp(&a.severity())
+            .then_with(|| birthday(a).cmp(&birthday(b)))
+            .then_with(|| a.pet_id.cmp(&b.pet_id))
+    });
+    reminders
+}
+
+fn birthday(reminder: &Reminder) -> Option<NaiveDate> {
+    match reminder.kind {
+        Kind::Birthday { on, .. } => Some(on),
+        _ => None,
+    }
+}
+
+/// The first birthday on or after `today` and the age it is, which may be
+/// next year's. A pet born on 29 February has it on the 28th in other years.
+/// `None` before the pet is a year old on that day.
+fn next_birthday(born: NaiveDate, today: NaiveDate) -> Option<(NaiveDate, i32)> {
+    let in_year = |year: i32| {
+        born.with_year(year)
+            .or_else(|| NaiveDate::from_ymd_opt(year, 2, 28))
+    };
+    let mut year = today.year();
+    let mut on = in_year(year)?;
+    if on < today {
+        year += 1;
+        on = in_year(year)?;
+    }
+    let turning = year - born.year();
+    (turning > 0).then_some((on, turning))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use crate::MedicalRecord;
+    use chrono::{TimeZone, Utc};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn born(id: usize, on: NaiveDate) -> Pet {
+        let mut pet = pet(id, "Coco", "cats");
+        pet.birthdate = Some(on);
+        pet
+    }
+
+    fn checked(id: usize, on: NaiveDate) -> Pet {
+        let mut pet = pet(id, "Rex", "dogs");
+        pet.records.push(MedicalRecord {
+            date: on,
+            kind: "checkup".to_owned(),
+            note: String::new(),
+        });
+        pet
+    }
+
+    fn kinds(pets: &[Pet], today: NaiveDate) -> Vec<Kind> {
+        compute_reminders(pets, today)
+            .into_iter()
+            .map(|reminder| reminder.kind)
+            .collect()
+    }
+
+    #[test]
+    fn pets_without_the_fields_get_no_reminders() {
+        let today = date(2024, 6, 1);
+        assert!(compute_reminders(&[pet(1, "Coco", "cats")], today).is_empty());
+        assert!(compute_reminders(&[], today).is_empty());
+    }
+
+    #[test]
+    fn a_birthday_in_january_is_found_in_december() {
+        let pets = vec![born(1, date(2020, 1, 5))];
+        assert_eq!(
+            kinds(&pets, date(2023, 12, 20)),
+            vec![Kind::Birthday {
+                on: date(2024, 1, 5),
+                turning: 4
+            }]
+        );
+        assert_eq!(
+            kinds(&pets, date(2024, 1, 5)),
+            vec![Kind::Birthday {
+                on: date(2024, 1, 5),
+                turning: 4
+            }]
+        );
+        assert!(kinds(&pets, date(2024, 1, 6)).is_empty());
+    }
+
+    #[test]
+    fn birthdays_are_mentioned_up_to_thirty_days_ahead() {
+        let pets = vec![born(1, date(2020, 1, 31))];
+        assert_eq!(kinds(&pets, date(2024, 1, 1)).len(), 1);
+        assert!(kinds(&pets, date(2023, 12, 31)).is_empty());
+        let reminder = &compute_reminders(&pets, date(2024, 1, 30))[0];
+        assert_eq!(
+            reminder.describe(date(2024, 1, 30)),
+            "Coco: turns 4 tomorrow"
+        );
+        assert_eq!(reminder.describe(date(2024, 1, 31)), "Coco: turns 4 today");
+        let reminder = &compute_reminders(&pets, date(2024, 1, 21))[0];
+        assert_eq!(
+            reminder.describe(date(2024, 1, 21)),
+            "Coco: turns 4 on 2024-01-31 (in 10 days)"
+        );
+    }
+
+    #[test]
+    fn a_leap_day_birthday_is_on_the_28th_in_other_years() {
+        let pets = vec![born(1, date(2020, 2, 29))];
+        assert_eq!(
+            kinds(&pets, date(2023, 2, 1)),
+            vec![Kind::Birthday {
+                on: date(2023, 2, 28),
+                turning: 3
+            }]
+        );
+        assert_eq!(
+            kinds(&pets, date(2024, 2, 1)),
+            vec![Kind::Birthday {
+                on: date(2024, 2, 29),
+                turning: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn no_birthday_before_the_first() {
+        let pets = vec![born(1, date(2023, 12, 25))];
+        assert!(kinds(&pets, date(2023, 12, 26)).is_empty());
+        assert_eq!(
+            kinds(&pets, date(2024, 12, 1)),
+            vec![Kind::Birthday {
+                on: date(2024, 12, 25),
+                turning: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn a_checkup_is_due_twelve_months_on_across_the_new_year() {
+        let last = date(2023, 12, 15);
+        let pets = vec![checked(1, last)];
+        assert!(kinds(&pets, date(2024, 12, 14)).is_empty());
+        assert_eq!(
+            kinds(&pets, date(2024, 12, 15)),
+            vec![Kind::CheckupDue { last }]
+        );
+        assert_eq!(
+            kinds(&pets, date(2025, 1, 2)),
+            vec![Kind::CheckupDue { last }]
+        );
+    }
+
+    #[test]
+    fn only_the_latest_record_counts() {
+        let mut pet = checked(1, date(2024, 3, 1));
+        pet.records.insert(
+            0,
+            MedicalRecord {
+                date: date(2020, 1, 1),
+                kind: "vaccination".to_owned(),
+                note: String::new(),
+            },
+        );
+        assert!(kinds(&[pet], date(2024, 6, 1)).is_empty());
+    }
+
+    #[test]
+    fn long_intakes_count_from_when_the_pet_was_added() {
+        let mut pet = pet(1, "Bun", "rabbits");
+        pet.status = PetStatus::Intake;
+        pet.created_at = Utc.with_ymd_and_hms(2023, 12, 10, 9, 0, 0).unwrap();
+        let pets = vec![pet];
+        assert!(kinds(&pets, date(2024, 1, 9)).is_empty());
+        assert_eq!(
+            kinds(&pets, date(2024, 1, 10)),
+            vec![Kind::LongIntake { days: 31 }]
+        );
+    }
+
+    #[test]
+    fn archived_and_adopted_pets_get_no_reminders() {
+        let mut archived = checked(1, date(2020, 1, 1));
+        archived.archived_at = Some(archived.created_at);
+        let mut adopted = born(2, date(2020, 6, 2));
+        adopted.status = PetStatus::Adopted;
+        assert!(compute_reminders(&[archived, adopted], date(2024, 6, 1)).is_empty());
+    }
+
+    #[test]
+    fn warnings_come_first_then_the_soonest_birthdays() {
+        let pets = vec![
+            born(1, date(2020, 6, 20)),
+            born(2, date(2020, 6, 5)),
+            checked(3, date(2022, 1, 1)),
+            checked(4, date(2021, 1, 1)),
+        ];
+        let order: Vec<(usize, Severity)> = compute_reminders(&pets, date(2024, 6, 1))
+            .iter()
+            .map(|reminder| (reminder.pet_id, reminder.severity()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (3, Severity::Warning),
+                (4, Severity::Warning),
+                (2, Severity::Info),
+                (1, Severity::Info),
+            ]
+        );
+    }
+}
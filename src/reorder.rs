@@ -0,0 +1,140 @@
+//! Moving a pet to another place in the DB, whose order the Pets tab shows
+//! when it isn't sorted.
+
+use crate::Pet;
+
+/// Where a cut pet goes, relative to another pet.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Side {
+    Before,
+    After,
+}
+
+/// Moves the pet with `id` next to the pet with `anchor`, or to the end if
+/// there is no anchor. Returns the pet's new index, or `None` if either pet
+/// is missing, in which case nothing moved. Moving a pet next to itself
+/// leaves it where it is.
+pub fn move_pet(
+    pets: &mut Vec<Pet>,
+    id: usize,
+    anchor: Option<usize>,
+    side: Side,
+) -> Option<usize> {
+    let from = pets.iter().position(|pet| pet.id == id)?;
+    if anchor == Some(id) {
+        return Some(from);
+    }
+    if let Some(anchor) = anchor {
+        pets.iter().position(|pet| pet.id == anchor)?;
+    }
+    let pet = pets.remove(from);
+    let to = match anchor {
+        None => pets.len(),
+        Some(anchor) => {
+            let at = pets
+                .iter()
+                .position(|pet| pet.id == anchor)
+                .expect("the anchor was found before");
+            match side {
+                Side::Before => at,
+                Side::After => at + 1,
+            }
+        }
+    };
+    pets.insert(to, pet);
+    Some(to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+
+    /// Pets 1 to `count`, in that order.
+    fn pets(count: usize) -> Vec<Pet> {
+        (1..=count).map(|id| pet(id, "Pet", "dogs")).collect()
+    }
+
+    fn ids(pets: &[Pet]) -> Vec<usize> {
+        pets.iter().map(|pet| pet.id).collect()
+    }
+
+    fn moved(
+        count: usize,
+        id: usize,
+        anchor: Option<usize>,
+        side: Side,
+    ) -> (Option<usize>, Vec<usize>) {
+        let mut pets = pets(count);
+        let to = move_pet(&mut pets, id, anchor, side);
+        (to, ids(&pets))
+    }
+
+    #[test]
+    fn moves_before_and_after_the_anchor() {
+        assert_eq!(
+            moved(4, 1, Some(3), Side::After),
+            (Some(2), vec![2, 3, 1, 4])
+        );
+        assert_eq!(
+            moved(4, 1, Some(3), Side::Before),
+            (Some(1), vec![2, 1, 3, 4])
+        );
+        assert_eq!(
+            moved(4, 4, Some(2), Side::After),
+            (Some(2), vec![1, 2, 4, 3])
+        );
+        assert_eq!(
+            moved(4, 4, Some(2), Side::Before),
+            (Some(1), vec![1, 4, 2, 3])
+        );
+    }
+
+    #[test]
+    fn moves_to_either_end() {
+        assert_eq!(
+            moved(4, 2, Some(4), Side::After),
+            (Some(3), vec![1, 3, 4, 2])
+        );
+        assert_eq!(
+            moved(4, 3, Some(1), Side::Before),
+            (Some(0), vec![3, 1, 2, 4])
+        );
+    }
+
+    #[test]
+    fn the_last_pet_can_be_moved_and_stays_last_after_its_neighbour() {
+        assert_eq!(
+            moved(4, 4, Some(1), Side::Before),
+            (Some(0), vec![4, 1, 2, 3])
+        );
+        assert_eq!(
+            moved(4, 4, Some(3), Side::After),
+            (Some(3), vec![1, 2, 3, 4])
+        );
+        assert_eq!(
+            moved(4, 3, Some(4), Side::Before),
+            (Some(2), vec![1, 2, 3, 4])
+        );
+    }
+
+    #[test]
+    fn without_an_anchor_the_pet_goes_last() {
+        assert_eq!(moved(3, 1, None, Side::Before), (Some(2), vec![2, 3, 1]));
+        // The only pet, as when pasting into a list that shows no pets.
+        assert_eq!(moved(1, 1, None, Side::After), (Some(0), vec![1]));
+    }
+
+    #[test]
+    fn moving_next_to_itself_leaves_it() {
+        assert_eq!(moved(3, 2, Some(2), Side::After), (Some(1), vec![1, 2, 3]));
+        assert_eq!(moved(3, 2, Some(2), Side::Before), (Some(1), vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn missing_pets_move_nothing() {
+        assert_eq!(moved(3, 9, Some(1), Side::After), (None, vec![1, 2, 3]));
+        assert_eq!(moved(3, 1, Some(9), Side::After), (None, vec![1, 2, 3]));
+        assert_eq!(moved(0, 1, None, Side::After), (None, vec![]));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::fields::PetField;
+use crate::strings::{self, Lang};
+use crate::Pet;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum ReportFormat {
+    /// A Markdown table.
+    #[default]
+    #[value(name = "md")]
+    Markdown,
+    /// A standalone HTML page.
+    Html,
+}
+
+const COLUMNS: [PetField; 5] = [
+    PetField::Id,
+    PetField::Name,
+    PetField::Category,
+    PetField::Age,
+    PetField::Created,
+];
+
+pub fn render(format: ReportFormat, pets: &[&Pet], generated_at: DateTime<Utc>) -> String {
+    match format {
+        ReportFormat::Markdown => render_markdown(pets, generated_at),
+        ReportFormat::Html => render_html(pets, generated_at),
+    }
+}
+
+pub fn render_markdown(pets: &[&Pet], generated_at: DateTime<Utc>) -> String {
+    let mut out = String::from("# Pets\n\n");
+    out.push_str(&format!(
+        "Generated at {}. {} pets in total.\n\n",
+        generated_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        pets.len()
+    ));
+    for (category, count) in category_counts(pets) {
+        out.push_str(&format!("- {}: {}\n", escape_markdown(category), count));
+    }
+    let labels = COLUMNS.map(|column| strings::text(Lang::En, column.label()));
+    out.push_str(&format!("\n| {} |\n", labels.join(" | ")));
+    let rules: String = COLUMNS
+        .iter()
+        .map(|column| {
+            if column.right_aligned() {
+                "---:|"
+            } else {
+                "---|"
+            }
+        })
+        .collect();
+    out.push_str(&format!("|{}\n", rules));
+    for pet in pets {
+        let cells = row(pet, generated_at);
+        let cells: Vec<_> = cells.iter().map(|cell| escape_markdown(cell)).collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    out
+}
+
+pub fn render_html(pets: &[&Pet], generated_at: DateTime<Utc>) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Pets</title>\n</head>\n<body>\n<h1>Pets</h1>\n",
+    );
+    out.push_str(&format!(
+        "<p>Generated at {}. {} pets in total.</p>\n<ul>\n",
+        generated_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        pets.len()
+    ));
+    for (category, count) in category_counts(pets) {
+        out.push_str(&format!("<li>{}: {}</li>\n", escape_html(category), count));
+    }
+    out.push_str("</ul>\n<table>\n<tr>");
+    for column in COLUMNS {
+        out.push_str(&format!(
+            "<th{}>{}</th>",
+            html_align(column),
+            strings::text(Lang::En, column.label())
+        ));
+    }
+    out.push_str("</tr>\n");
+    for pet in pets {
+        out.push_str("<tr>");
+        for (cell, column) in row(pet, generated_at).iter().zip(COLUMNS) {
+            out.push_str(&format!(
+                "<td{}>{}</td>",
+                html_align(column),
+                escape_html(cell)
+            ));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
+
+fn row(pet: &Pet, generated_at: DateTime<Utc>) -> [String; 5] {
+    [
+        pet.id.to_string(),
+        pet.name.clone(),
+        pet.category.clone(),
+        pet.display_age(generated_at.date_naive()),
+        pet.created_at.format("%Y-%m-%d").to_string(),
+    ]
+}
+
+fn html_align(column: PetField) -> &'static str {
+    if column.right_aligned() {
+        " style=\"text-align: right\""
+    } else {
+        ""
+    }
+}
+
+fn category_counts<'p>(pets: &[&'p Pet]) -> BTreeMap<&'p str, usize> {
+    let mut counts = BTreeMap::new();
+    for pet in pets {
+        *counts.entry(pet.category.as_str()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Escapes the characters that would end a table cell or start inline
+/// formatting. Line breaks become spaces since a cell is a single line.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\r' | '\n' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use chrono::TimeZone;
+
+    const GOLDEN_MD: &str = include_str!("../tests/fixtures/report/pets.md");
+    const GOLDEN_HTML: &str = include_str!("../tests/fixtures/report/pets.html");
+
+    fn generated_at() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap()
+    }
+
+    /// Names with characters each format has to escape.
+    fn golden_pets() -> Vec<Pet> {
+        vec![
+            pet(1, "Rex | Jr", "dogs"),
+            pet(2, "<b>Coco</b> & \"Mo\"", "cats"),
+            pet(3, "*Dot*_[x]\nline", "dogs"),
+            pet(10, "O'Malley #1", "cats"),
+        ]
+    }
+
+    #[test]
+    fn markdown_matches_the_golden_file() {
+        let pets = golden_pets();
+        let pets: Vec<&Pet> = pets.iter().collect();
+        assert_eq!(render_markdown(&pets, generated_at()), GOLDEN_MD);
+    }
+
+    #[test]
+    fn html_matches_the_golden_file() {
+        let pets = golden_pets();
+        let pets: Vec<&Pet> = pets.iter().collect();
+        assert_eq!(render_html(&pets, generated_at()), GOLDEN_HTML);
+    }
+
+    #[test]
+    fn markdown_aligns_ids_and_ages_to_the_right() {
+        let rex = pet(12, "Rex | Jr", "dogs");
+        let out = render(ReportFormat::Markdown, &[&rex], generated_at());
+        assert!(
+            out.contains("\n| ID | Name | Category | Age | Created |\n|---:|---|---|---:|---|\n"),
+            "{}",
+            out
+        );
+        assert!(out.contains("| 12 | Rex \\| Jr | dogs |"), "{}", out);
+        assert!(out.contains("- dogs: 1\n"), "{}", out);
+    }
+
+    #[test]
+    fn html_aligns_ids_and_ages_to_the_right() {
+        let coco = pet(3, "<Coco>", "cats");
+        let out = render(ReportFormat::Html, &[&coco], generated_at());
+        let right = " style=\"text-align: right\"";
+        assert!(
+            out.contains(&format!("<th{}>ID</th><th>Name</th>", right)),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains(&format!("<td{}>3</td><td>&lt;Coco&gt;</td>", right)),
+            "{}",
+            out
+        );
+        assert_eq!(out.matches(right).count(), 4, "{}", out);
+    }
+}
@@ -0,0 +1,69 @@
+//! Starting over with an empty DB: `pet-cli nuke` and `:reset` in the TUI.
+//! The DB file isn't deleted but renamed next to itself with the time, like
+//! `db.json.reset-20240301T101500.000Z`, so a reset typed by mistake can be
+//! undone by renaming it back. Both ask for [`CONFIRM_WORD`] to be typed
+//! first rather than a y, which is too easily pressed.
+
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const CONFIRM_WORD: &str = "reset";
+
+/// Basic ISO 8601 like the backups', which sorts chronologically and is safe
+/// in file names.
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// Moves the DB file at `db_path` aside, returning where to.
+pub fn set_aside(db_path: &Path, now: DateTime<Utc>) -> io::Result<PathBuf> {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".reset-{}", now.format(TIMESTAMP_FORMAT)));
+    let aside = db_path.with_file_name(name);
+    fs::rename(db_path, &aside)?;
+    Ok(aside)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "pet-cli-reset-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn the_db_is_renamed_next_to_itself_with_the_time() {
+        let dir = temp_dir("aside");
+        let db = dir.join("db.json");
+        fs::write(&db, "[]").unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 10, 15, 0).unwrap();
+        let aside = set_aside(&db, now).unwrap();
+        assert_eq!(aside, dir.join("db.json.reset-20240301T101500.000Z"));
+        assert!(!db.exists());
+        assert_eq!(fs::read_to_string(&aside).unwrap(), "[]");
+        // A second reset a moment later keeps the first file too.
+        fs::write(&db, "[{}]").unwrap();
+        let later = set_aside(&db, now + chrono::Duration::milliseconds(1)).unwrap();
+        assert_ne!(later, aside);
+        assert!(aside.exists() && later.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_missing_db_is_an_error_and_nothing_is_made() {
+        let dir = temp_dir("missing");
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 10, 15, 0).unwrap();
+        let err = set_aside(&dir.join("db.json"), now).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
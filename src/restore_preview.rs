@@ -0,0 +1,97 @@
+use crate::backup::Backup;
+use crate::diff::{self, PetDiff};
+use crate::{compression, db_format, Pet};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::path::PathBuf;
+
+/// The `R` view: the backups on one side and on the other what restoring the
+/// selected one would do to the DB.
+pub struct RestorePreview {
+    backups: Vec<Backup>,
+    /// Of each of `backups`, read once when the view opens.
+    pet_counts: Vec<Option<usize>>,
+    selected: usize,
+    /// Of the selected backup against the DB as it was when it was selected.
+    diff: Result<PetDiff, String>,
+    scroll: u16,
+}
+
+pub enum PreviewOutcome {
+    Open,
+    Cancelled,
+    Restore(PathBuf),
+}
+
+impl RestorePreview {
+    /// Selects the newest backup. `None` if there are none. `backups` are
+    /// oldest first, as `backup::list` returns them.
+    pub fn new(backups: Vec<Backup>, current: &[Pet]) -> Option<RestorePreview> {
+        let selected = backups.len().checked_sub(1)?;
+        let pet_counts = backups.iter().map(Backup::pet_count).collect();
+        let mut preview = RestorePreview {
+            backups,
+            pet_counts,
+            selected,
+            diff: Ok(PetDiff::default()),
+            scroll: 0,
+        };
+        preview.select(selected, current);
+        Some(preview)
+    }
+
+    /// Newest first with their pet counts, for listing.
+    pub fn backups(&self) -> impl Iterator<Item = (&Backup, Option<usize>)> {
+        self.backups
+            .iter()
+            .zip(self.pet_counts.iter().copied())
+            .rev()
+    }
+
+    /// Position of the selected backup within `backups`.
+    pub fn selected(&self) -> usize {
+        self.backups.len() - 1 - self.selected
+    }
+
+    pub fn selected_backup(&self) -> &Backup {
+        &self.backups[self.selected]
+    }
+
+    pub fn diff(&self) -> Result<&PetDiff, &str> {
+        self.diff.as_ref().map_err(String::as_str)
+    }
+
+    pub fn scroll(&self) -> u16 {
+        self.scroll
+    }
+
+    /// `current` are the pets in the DB now, to compare the backup with.
+    pub fn handle_key(&mut self, key: KeyEvent, current: &[Pet]) -> PreviewOutcome {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return PreviewOutcome::Cancelled,
+            KeyCode::Enter | KeyCode::Char('y') => {
+                return PreviewOutcome::Restore(self.selected_backup().path.clone())
+            }
+            // The list shows the newest backup first, so down goes back in
+            // time.
+            KeyCode::Down | KeyCode::Char('j') if self.selected > 0 => {
+                self.select(self.selected - 1, current)
+            }
+            KeyCode::Up | KeyCode::Char('k') if self.selected + 1 < self.backups.len() => {
+                self.select(self.selected + 1, current)
+            }
+            KeyCode::PageDown | KeyCode::Char('J') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::PageUp | KeyCode::Char('K') => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        PreviewOutcome::Open
+    }
+
+    fn select(&mut self, index: usize, current: &[Pet]) {
+        self.selected = index;
+        self.scroll = 0;
+        self.diff = compression::read_to_string(&self.backups[index].path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| db_format::parse(&content).map_err(|err| err.to_string()))
+            .map(|backup| diff::diff_pets(current, &backup.pets));
+    }
+}
@@ -0,0 +1,303 @@
+//! Saved views: the search, category and status filters and sort of the Pets
+//! tab kept under a name with `:save-view NAME`. `V` lists them; Enter shows
+//! one, `r` renames it and `d` deletes it. They are kept in the state file of
+//! the DB, see `ui_state`.
+
+use crate::filter::CategoryFilter;
+use crate::pet_status::PetStatus;
+use crate::search::Query;
+use crate::view::{Sort, ViewParams};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// A view as the state file has it. Its parts are kept as text so that one
+/// that no longer reads, like a search with a field since removed, only
+/// loses that view rather than the whole file.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredView {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedView {
+    pub name: String,
+    pub query: Option<Query>,
+    pub category: Option<String>,
+    pub status: Option<PetStatus>,
+    pub sort: Option<Sort>,
+}
+
+impl SavedView {
+    /// The parts of `view` a saved view keeps, under `name`.
+    pub fn capture(name: &str, view: &ViewParams) -> Self {
+        SavedView {
+            name: name.to_owned(),
+            query: view.query.clone(),
+            category: view.category.category().map(str::to_owned),
+            status: view.status,
+            sort: view.sort,
+        }
+    }
+
+    /// The Pets tab's view showing this one.
+    pub fn params(&self) -> ViewParams {
+        ViewParams {
+            category: CategoryFilter::only(self.category.clone()),
+            status: self.status,
+            sort: self.sort,
+            query: self.query.clone(),
+            ..ViewParams::default()
+        }
+    }
+
+    /// Whether `view` shows what this one does.
+    pub fn is_shown_by(&self, view: &ViewParams) -> bool {
+        *self == SavedView::capture(&self.name, view)
+    }
+
+    pub fn store(&self) -> StoredView {
+        StoredView {
+            name: self.name.clone(),
+            query: self.query.as_ref().map(|query| query.text().to_owned()),
+            category: self.category.clone(),
+            status: self.status.map(|status| status.to_string()),
+            sort: self.sort.map(|sort| sort.to_string()),
+        }
+    }
+
+    /// A summary for the picker, like `search: age:>5, cats, sort: age:desc`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(query) = &self.query {
+            parts.push(format!("search: {}", query));
+        }
+        parts.extend(self.category.clone());
+        parts.extend(self.status.map(|status| status.to_string()));
+        if let Some(sort) = self.sort {
+            parts.push(format!("sort: {}", sort));
+        }
+        if parts.is_empty() {
+            return "every pet".to_owned();
+        }
+        parts.join(", ")
+    }
+}
+
+impl TryFrom<&StoredView> for SavedView {
+    type Error = String;
+
+    fn try_from(stored: &StoredView) -> Result<Self, Self::Error> {
+        let query = match &stored.query {
+            Some(text) => Some(Query::parse(text).map_err(|err| err.to_string())?),
+            None => None,
+        };
+        let status = match &stored.status {
+            Some(status) => Some(
+                PetStatus::from_str(status, true)
+                    .map_err(|_| format!("unknown status '{}'", status))?,
+            ),
+            None => None,
+        };
+        let sort = match &stored.sort {
+            Some(sort) => Some(Sort::from_str(sort)?),
+            None => None,
+        };
+        Ok(SavedView {
+            name: stored.name.clone(),
+            query,
+            category: stored.category.clone(),
+            status,
+            sort,
+        })
+    }
+}
+
+/// The saved views of a state file: those that still read, and those that
+/// don't, which are written back as they were in case a later pet-cli reads
+/// them again.
+#[derive(Default)]
+pub struct SavedViews {
+    pub views: Vec<SavedView>,
+    unreadable: Vec<StoredView>,
+}
+
+impl SavedViews {
+    /// Also returns a warning for each view left out.
+    pub fn load(stored: &[StoredView]) -> (Self, Vec<String>) {
+        let mut saved = SavedViews::default();
+        let mut warnings = Vec::new();
+        for stored in stored {
+            match SavedView::try_from(stored) {
+                Ok(view) => saved.views.push(view),
+                Err(err) => {
+                    warnings.push(format!("skipped saved view {}: {}", stored.name, err));
+                    saved.unreadable.push(stored.clone());
+                }
+            }
+        }
+        (saved, warnings)
+    }
+
+    /// An unreadable view gives way to a view saved under its name since.
+    pub fn store(&self) -> Vec<StoredView> {
+        let unreadable = self
+            .unreadable
+            .iter()
+            .filter(|stored| !self.views.iter().any(|view| view.name == stored.name));
+        self.views
+            .iter()
+            .map(SavedView::store)
+            .chain(unreadable.cloned())
+            .collect()
+    }
+}
+
+/// Names are typed after `:save-view`, so they are one word.
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err("a view name is one word, like cats-by-age".to_owned());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(name: &str, query: Option<&str>, sort: Option<&str>) -> StoredView {
+        StoredView {
+            name: name.to_owned(),
+            query: query.map(str::to_owned),
+            category: None,
+            status: None,
+            sort: sort.map(str::to_owned),
+        }
+    }
+
+    fn cats_by_age() -> SavedView {
+        SavedView {
+            name: "cats-by-age".to_owned(),
+            query: Some("age:>5 rex".parse().unwrap()),
+            category: Some("cats".to_owned()),
+            status: Some(PetStatus::Available),
+            sort: Some("age:desc".parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn a_view_round_trips_through_the_state_file() {
+        let view = cats_by_age();
+        let json = serde_json::to_string(&view.store()).unwrap();
+        let read: StoredView = serde_json::from_str(&json).unwrap();
+        assert_eq!(SavedView::try_from(&read), Ok(view));
+    }
+
+    #[test]
+    fn an_empty_view_stores_only_its_name() {
+        let view = SavedView::capture("all", &ViewParams::default());
+        let json = serde_json::to_string(&view.store()).unwrap();
+        assert_eq!(json, r#"{"name":"all"}"#);
+        assert_eq!(view.describe(), "every pet");
+    }
+
+    #[test]
+    fn capturing_and_showing_a_view_give_the_same_view() {
+        let view = cats_by_age();
+        let params = view.params();
+        assert!(view.is_shown_by(&params));
+        assert_eq!(SavedView::capture("cats-by-age", &params), view);
+        let other = ViewParams {
+            sort: None,
+            ..params
+        };
+        assert!(!view.is_shown_by(&other));
+    }
+
+    #[test]
+    fn the_description_lists_what_the_view_narrows_to() {
+        assert_eq!(
+            cats_by_age().describe(),
+            "search: age:>5 rex, cats, available, sort: age:desc"
+        );
+    }
+
+    #[test]
+    fn views_that_no_longer_read_are_skipped_with_a_warning() {
+        let views = [
+            stored("old", Some("colour:red"), None),
+            stored("by-age", None, Some("age:desc")),
+            stored("weird", None, Some("weight")),
+        ];
+        let (saved, warnings) = SavedViews::load(&views);
+        let names: Vec<&str> = saved.views.iter().map(|view| view.name.as_str()).collect();
+        assert_eq!(names, ["by-age"]);
+        assert_eq!(warnings.len(), 2);
+        assert!(
+            warnings[0].starts_with("skipped saved view old: unknown field 'colour'"),
+            "{}",
+            warnings[0]
+        );
+        assert!(
+            warnings[1].starts_with("skipped saved view weird: "),
+            "{}",
+            warnings[1]
+        );
+        let status = StoredView {
+            status: Some("lost".to_owned()),
+            ..stored("lost", None, None)
+        };
+        let (_, warnings) = SavedViews::load(&[status]);
+        assert_eq!(warnings, ["skipped saved view lost: unknown status 'lost'"]);
+    }
+
+    #[test]
+    fn skipped_views_are_written_back_until_replaced() {
+        let views = [
+            stored("old", Some("colour:red"), None),
+            stored("by-age", None, Some("age:desc")),
+        ];
+        let (mut saved, _) = SavedViews::load(&views);
+        let names = |saved: &SavedViews| -> Vec<(String, Option<String>)> {
+            saved
+                .store()
+                .into_iter()
+                .map(|view| (view.name, view.query))
+                .collect()
+        };
+        assert_eq!(
+            names(&saved),
+            [
+                ("by-age".to_owned(), None),
+                ("old".to_owned(), Some("colour:red".to_owned())),
+            ]
+        );
+        saved
+            .views
+            .push(SavedView::capture("old", &ViewParams::default()));
+        assert_eq!(
+            names(&saved),
+            [("by-age".to_owned(), None), ("old".to_owned(), None)]
+        );
+    }
+
+    #[test]
+    fn names_are_one_word() {
+        assert_eq!(check_name("cats-by-age"), Ok(()));
+        for name in ["", "cats by age", "cats\tage"] {
+            assert_eq!(
+                check_name(name),
+                Err("a view name is one word, like cats-by-age".to_owned())
+            );
+        }
+    }
+}
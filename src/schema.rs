@@ -0,0 +1,380 @@
+//! `pet-cli schema`: a JSON Schema (draft 2020-12) of the DB file as the
+//! `compact` and `pretty` formats write it, for other programs that read or
+//! write it. The limits are taken from `validation` and `microchip`, which
+//! every edit is checked with, so the two can't drift apart. Pets may have
+//! fields pet-cli doesn't know; they are kept as they are.
+
+use crate::microchip;
+use crate::migrations::CURRENT_VERSION;
+use crate::pet_status::PetStatus;
+use crate::validation::{AGE_RANGE, MAX_NAME_LENGTH, MAX_NOTES_LENGTH};
+use serde_json::{json, Value};
+
+/// Anything but blank, as `trim().is_empty()` has it.
+const NOT_BLANK: &str = "\\S";
+/// What `char::is_control` matches, which attribute keys mustn't have.
+const CONTROL_CHARACTERS: &str = "[\\u0000-\\u001f\\u007f-\\u009f]";
+
+pub fn schema() -> Value {
+    let statuses: Vec<Value> = PetStatus::ALL
+        .iter()
+        .map(|status| serde_json::to_value(status).expect("statuses serialize"))
+        .collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "pet-cli DB",
+        "description": format!(
+            "A pet-cli DB file at schema version {}. Files at older versions are \
+             migrated when read.",
+            CURRENT_VERSION
+        ),
+        "type": "object",
+        "required": ["schema_version", "pets"],
+        "properties": {
+            "revision": {
+                "description": "Counts the writes, so a write can tell whether the file changed since it was read.",
+                "type": "integer",
+                "minimum": 0
+            },
+            "schema_version": { "const": CURRENT_VERSION },
+            "next_id": {
+                "description": "The id the next pet added gets, unless a pet has a higher one.",
+                "type": "integer",
+                "minimum": 0
+            },
+            "pets": { "type": "array", "items": { "$ref": "#/$defs/pet" } },
+            "owners": { "type": "array", "items": { "$ref": "#/$defs/owner" } }
+        },
+        "$defs": {
+            "pet": {
+                "type": "object",
+                "required": ["id", "name", "category", "age_months", "created_at"],
+                "properties": {
+                    "id": { "type": "integer", "minimum": 0 },
+                    "name": {
+                        "type": "string",
+                        "maxLength": MAX_NAME_LENGTH,
+                        "pattern": NOT_BLANK
+                    },
+                    "category": { "type": "string", "pattern": NOT_BLANK },
+                    "age_months": {
+                        "description": "Only used when there is no birthdate.",
+                        "type": "integer",
+                        "minimum": AGE_RANGE.start(),
+                        "maximum": AGE_RANGE.end()
+                    },
+                    "created_at": { "type": "string", "format": "date-time" },
+                    "notes": { "type": "string", "maxLength": MAX_NOTES_LENGTH },
+                    "birthdate": { "type": "string", "format": "date" },
+                    "owner_id": {
+                        "description": "The id of one of the owners.",
+                        "type": "integer",
+                        "minimum": 0
+                    },
+                    "records": { "type": "array", "items": { "$ref": "#/$defs/record" } },
+                    "weights": {
+                        "description": "In the order they were entered.",
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/weight" }
+                    },
+                    "microchip": {
+                        "description": "An ISO 11784 number without spaces, starting with a known country or manufacturer code.",
+                        "type": "string",
+                        "pattern": microchip::pattern()
+                    },
+                    "breed": { "type": "string" },
+                    "status": { "enum": statuses, "default": "available" },
+                    "photo_path": {
+                        "description": "Relative to the working directory unless absolute.",
+                        "type": "string"
+                    },
+                    "attributes": {
+                        "type": "object",
+                        "propertyNames": {
+                            "pattern": NOT_BLANK,
+                            "not": { "pattern": CONTROL_CHARACTERS }
+                        },
+                        "additionalProperties": { "type": "string" }
+                    },
+                    "archived_at": {
+                        "description": "When the pet was moved to the Trash.",
+                        "type": "string",
+                        "format": "date-time"
+                    }
+                }
+            },
+            "owner": {
+                "type": "object",
+                "required": ["id", "name", "email"],
+                "properties": {
+                    "id": { "type": "integer", "minimum": 0 },
+                    "name": { "type": "string" },
+                    "email": { "type": "string" }
+                }
+            },
+            "record": {
+                "type": "object",
+                "required": ["date", "kind"],
+                "properties": {
+                    "date": { "type": "string", "format": "date" },
+                    "kind": { "type": "string" },
+                    "note": { "type": "string" }
+                }
+            },
+            "weight": {
+                "type": "object",
+                "required": ["date", "grams"],
+                "properties": {
+                    "date": { "type": "string", "format": "date" },
+                    "grams": { "type": "integer", "minimum": 0, "maximum": u32::MAX }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_format::{self, DbFormat};
+    use crate::Database;
+
+    /// Checks `value` against the parts of JSON Schema that `schema` uses,
+    /// returning what doesn't hold, each with its path. Only the patterns
+    /// `schema` has are understood.
+    fn check(value: &Value, schema: &Value, root: &Value, path: &str) -> Vec<String> {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/$defs/");
+            return check(value, &root["$defs"][name], root, path);
+        }
+        let mut problems = Vec::new();
+        let mut fail = |problem: String| problems.push(format!("{}: {}", path, problem));
+        if let Some(expected) = schema.get("const") {
+            if value != expected {
+                fail(format!("is not {}", expected));
+            }
+        }
+        if let Some(allowed) = schema["enum"].as_array() {
+            if !allowed.contains(value) {
+                fail(format!("{} is not one of {:?}", value, allowed));
+            }
+        }
+        let type_matches = match schema["type"].as_str() {
+            Some("object") => value.is_object(),
+            Some("array") => value.is_array(),
+            Some("string") => value.is_string(),
+            Some("integer") => value.is_u64() || value.is_i64(),
+            Some(other) => panic!("a type the test doesn't know: {}", other),
+            None => true,
+        };
+        if !type_matches {
+            fail(format!("{} is not of type {}", value, schema["type"]));
+            return problems;
+        }
+        if let Some(number) = value.as_f64() {
+            if schema["minimum"]
+                .as_f64()
+                .is_some_and(|minimum| number < minimum)
+            {
+                fail(format!("{} is below the minimum", number));
+            }
+            if schema["maximum"]
+                .as_f64()
+                .is_some_and(|maximum| number > maximum)
+            {
+                fail(format!("{} is above the maximum", number));
+            }
+        }
+        if let Some(text) = value.as_str() {
+            if schema["maxLength"]
+                .as_u64()
+                .is_some_and(|max| text.chars().count() as u64 > max)
+            {
+                fail("is too long".to_owned());
+            }
+            if let Some(pattern) = schema["pattern"].as_str() {
+                if !matches(pattern, text) {
+                    fail(format!("{:?} doesn't match {}", text, pattern));
+                }
+            }
+        }
+        if let Some(object) = value.as_object() {
+            for required in schema["required"].as_array().into_iter().flatten() {
+                if !object.contains_key(required.as_str().unwrap()) {
+                    fail(format!("{} is missing", required));
+                }
+            }
+            for (key, property) in object {
+                if let Some(property_schema) = schema["properties"].get(key) {
+                    problems.extend(check(
+                        property,
+                        property_schema,
+                        root,
+                        &format!("{}.{}", path, key),
+                    ));
+                } else if let Some(values) = schema.get("additionalProperties") {
+                    problems.extend(check(property, values, root, &format!("{}.{}", path, key)));
+                }
+            }
+        }
+        for (index, item) in value.as_array().into_iter().flatten().enumerate() {
+            problems.extend(check(
+                item,
+                &schema["items"],
+                root,
+                &format!("{}[{}]", path, index),
+            ));
+        }
+        problems
+    }
+
+    /// `\S`, or the microchip's `^(code|code|...)[0-9]{n}$`.
+    fn matches(pattern: &str, text: &str) -> bool {
+        if pattern == NOT_BLANK {
+            return !text.trim().is_empty();
+        }
+        let (codes, digits) = pattern
+            .strip_prefix("^(")
+            .and_then(|rest| rest.strip_suffix("}$"))
+            .and_then(|rest| rest.split_once(")[0-9]{"))
+            .expect("the microchip pattern");
+        let digits: usize = digits.parse().unwrap();
+        codes.split('|').any(|code| {
+            text.strip_prefix(code).is_some_and(|rest| {
+                rest.len() == digits && rest.chars().all(|c| c.is_ascii_digit())
+            })
+        })
+    }
+
+    /// A DB with every field a pet can have, as written to a file.
+    fn sample() -> Value {
+        let database: Database = serde_json::from_value(json!({
+            "revision": 4,
+            "schema_version": CURRENT_VERSION,
+            "next_id": 3,
+            "pets": [
+                {
+                    "id": 1,
+                    "name": "Coco",
+                    "category": "cats",
+                    "age_months": 24,
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "notes": "shy",
+                    "birthdate": "2022-01-01",
+                    "owner_id": 1,
+                    "records": [{"date": "2024-02-01", "kind": "vaccination", "note": "rabies"}],
+                    "weights": [{"date": "2024-02-01", "grams": 4200}],
+                    "microchip": "250268500012345",
+                    "breed": "Siamese",
+                    "status": "adopted",
+                    "photo_path": "photos/coco.png",
+                    "attributes": {"diet": "wet food"},
+                    "archived_at": "2024-03-01T00:00:00Z",
+                    "vet": "Dr. Smith"
+                },
+                {
+                    "id": 2,
+                    "name": "Rex",
+                    "category": "dogs",
+                    "age_months": 0,
+                    "created_at": "2024-01-02T00:00:00Z"
+                }
+            ],
+            "owners": [{"id": 1, "name": "Ann", "email": "ann@example.com"}]
+        }))
+        .unwrap();
+        let content = db_format::serialize(&database, DbFormat::Compact).unwrap();
+        serde_json::from_slice(&content).unwrap()
+    }
+
+    fn problems(db: &Value) -> Vec<String> {
+        let schema = schema();
+        check(db, &schema, &schema, "db")
+    }
+
+    #[test]
+    fn a_written_db_matches_the_schema() {
+        assert_eq!(problems(&sample()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn every_field_pet_cli_writes_is_described() {
+        let schema = schema();
+        let described = schema["$defs"]["pet"]["properties"].as_object().unwrap();
+        let sample = sample();
+        let written: Vec<&String> = sample["pets"][0]
+            .as_object()
+            .unwrap()
+            .keys()
+            .filter(|key| *key != "vet")
+            .collect();
+        for key in &written {
+            assert!(described.contains_key(*key), "{} isn't in the schema", key);
+        }
+        assert_eq!(written.len(), described.len());
+    }
+
+    #[test]
+    fn missing_fields_are_refused() {
+        for field in ["id", "name", "category", "age_months", "created_at"] {
+            let mut db = sample();
+            db["pets"][1].as_object_mut().unwrap().remove(field);
+            assert_eq!(
+                problems(&db),
+                [format!("db.pets[1]: \"{}\" is missing", field)]
+            );
+        }
+        let mut db = sample();
+        db.as_object_mut().unwrap().remove("schema_version");
+        assert_eq!(problems(&db), ["db: \"schema_version\" is missing"]);
+    }
+
+    #[test]
+    fn microchips_are_checked_like_microchip_does() {
+        for (number, valid) in [
+            ("250268500012345", true),
+            ("985112003456789", true),
+            ("25026850001234", false),
+            ("2502685000123456", false),
+            ("123456789012345", false),
+            ("25026850001234x", false),
+        ] {
+            let mut db = sample();
+            db["pets"][0]["microchip"] = json!(number);
+            assert_eq!(problems(&db).is_empty(), valid, "{}", number);
+            assert_eq!(microchip::check(number).is_ok(), valid, "{}", number);
+        }
+    }
+
+    #[test]
+    fn ages_are_checked_against_the_range_edits_allow() {
+        for (months, valid) in [
+            (0, true),
+            (*AGE_RANGE.end(), true),
+            (AGE_RANGE.end() + 1, false),
+        ] {
+            let mut db = sample();
+            db["pets"][1]["age_months"] = json!(months);
+            assert_eq!(problems(&db).is_empty(), valid, "{}", months);
+        }
+        let mut db = sample();
+        db["pets"][1]["age_months"] = json!(-1);
+        assert_eq!(
+            problems(&db),
+            ["db.pets[1].age_months: -1 is below the minimum"]
+        );
+    }
+
+    #[test]
+    fn names_and_statuses_are_checked() {
+        let mut db = sample();
+        db["pets"][1]["name"] = json!("  ");
+        db["pets"][1]["status"] = json!("lost");
+        db["schema_version"] = json!(CURRENT_VERSION + 1);
+        assert_eq!(problems(&db).len(), 3, "{:?}", problems(&db));
+        db["pets"][1]["name"] = json!("x".repeat(MAX_NAME_LENGTH + 1));
+        assert!(problems(&db)
+            .iter()
+            .any(|problem| problem == "db.pets[1].name: is too long"));
+    }
+}
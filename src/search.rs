@@ -0,0 +1,467 @@
+//! The search of the Pets list and of `pet-cli list --query`, like
+//! `category:cats age:>5 rex`. Every term has to match: a bare word matches
+//! the name, and `field:value` one field. Values with spaces are quoted,
+//! `name:"mr whiskers"` or `"mr whiskers"`.
+//!
+//! `age` and `id` compare with `>`, `<`, `>=`, `<=` or a range, `age:2..5`,
+//! which includes both ends. A bare age is in whole years; `age:<6m` and
+//! `age:1y6m..2y` give months as `age::parse` reads them.
+
+use crate::age;
+use crate::microchip;
+use crate::pet_status::PetStatus;
+use crate::Pet;
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+const FIELDS: &str = "name, category, breed, status, microchip, notes, age, id";
+
+#[derive(Error, Debug, PartialEq)]
+pub enum QueryError {
+    #[error("unknown field '{field}', expected one of: {FIELDS}")]
+    UnknownField { field: String },
+    #[error("'{field}:' needs a value")]
+    NoValue { field: String },
+    #[error("missing closing \" in the search")]
+    UnclosedQuote,
+    #[error("invalid comparison '{value}' for {field}, expected e.g. 5, >5, <=5 or 2..5")]
+    InvalidComparison { field: String, value: String },
+    #[error("{field} compares numbers, not '{value}'")]
+    NotANumber { field: String, value: String },
+    #[error("invalid age '{0}', expected whole years like 5 or an age like 8m or 1y6m")]
+    InvalidAge(String),
+    #[error("unknown status '{0}', expected intake, available or adopted")]
+    UnknownStatus(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Term {
+    /// A bare word or quoted phrase.
+    Text(String),
+    Name(String),
+    Category(String),
+    Breed(String),
+    Status(PetStatus),
+    /// The digits, without spaces.
+    Microchip(String),
+    Notes(String),
+    Age(Comparison),
+    Id(Comparison),
+}
+
+/// A number to compare with, and the months in one of its units for ages:
+/// `5` is whole years, so 5 y 11 m is still 5.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Amount {
+    value: usize,
+    unit: usize,
+}
+
+impl Amount {
+    fn of(self, value: usize) -> usize {
+        value / self.unit
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Comparison {
+    Equal(Amount),
+    Greater(Amount),
+    GreaterOrEqual(Amount),
+    Less(Amount),
+    LessOrEqual(Amount),
+    Between(Amount, Amount),
+}
+
+impl Comparison {
+    fn holds(self, value: usize) -> bool {
+        match self {
+            Comparison::Equal(amount) => amount.of(value) == amount.value,
+            Comparison::Greater(amount) => amount.of(value) > amount.value,
+            Comparison::GreaterOrEqual(amount) => amount.of(value) >= amount.value,
+            Comparison::Less(amount) => amount.of(value) < amount.value,
+            Comparison::LessOrEqual(amount) => amount.of(value) <= amount.value,
+            Comparison::Between(low, high) => {
+                low.of(value) >= low.value && high.of(value) <= high.value
+            }
+        }
+    }
+}
+
+/// A parsed search, which keeps the text it was read from to show it and
+/// give it back to the search prompt.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    text: String,
+    terms: Vec<Term>,
+}
+
+impl Query {
+    /// Reads `input`; an empty one matches every pet.
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        let terms = words(input)?
+            .into_iter()
+            .map(|(field, value)| term(field.as_deref(), value))
+            .collect::<Result<_, _>>()?;
+        Ok(Query {
+            text: input.trim().to_owned(),
+            terms,
+        })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether `pet` matches every term, with ages taken on `today`.
+    pub fn matches(&self, pet: &Pet, today: NaiveDate) -> bool {
+        self.terms.iter().all(|term| term_matches(term, pet, today))
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Query::parse(input)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// Splits `input` at spaces outside quotes, into the field before a `:`, if
+/// any, and the value with the quotes taken out. A `:` inside quotes, or
+/// after one, is part of the value.
+fn words(input: &str) -> Result<Vec<(Option<String>, String)>, QueryError> {
+    let mut words = Vec::new();
+    let mut chars = input.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(words);
+        }
+        let mut field = None;
+        let mut value = String::new();
+        let mut quoted = false;
+        while let Some(c) = chars.next_if(|c| quoted || !c.is_whitespace()) {
+            match c {
+                '"' => quoted = !quoted,
+                ':' if !quoted && field.is_none() && !value.is_empty() => {
+                    field = Some(std::mem::take(&mut value));
+                }
+                c => value.push(c),
+            }
+        }
+        if quoted {
+            return Err(QueryError::UnclosedQuote);
+        }
+        words.push((field, value));
+    }
+}
+
+fn term(field: Option<&str>, value: String) -> Result<Term, QueryError> {
+    let Some(field) = field else {
+        return Ok(Term::Text(value.to_lowercase()));
+    };
+    let field = field.to_lowercase();
+    if value.is_empty() {
+        return Err(match field_known(&field) {
+            true => QueryError::NoValue { field },
+            false => QueryError::UnknownField { field },
+        });
+    }
+    let term = match field.as_str() {
+        "name" => Term::Name(value.to_lowercase()),
+        "category" => Term::Category(value.to_lowercase()),
+        "breed" => Term::Breed(value.to_lowercase()),
+        "status" => Term::Status(
+            PetStatus::from_str(&value, true).map_err(|_| QueryError::UnknownStatus(value))?,
+        ),
+        "microchip" => Term::Microchip(microchip::normalize(&value)),
+        "notes" => Term::Notes(value.to_lowercase()),
+        "age" => Term::Age(comparison(&field, &value, age_amount)?),
+        "id" => Term::Id(comparison(&field, &value, |input| number(&field, input))?),
+        _ => return Err(QueryError::UnknownField { field }),
+    };
+    Ok(term)
+}
+
+fn field_known(field: &str) -> bool {
+    FIELDS.split(", ").any(|known| known == field)
+}
+
+/// Reads `5`, `>5`, `>=5`, `<5`, `<=5` or `2..5`, each number read with
+/// `amount`.
+fn comparison(
+    field: &str,
+    value: &str,
+    amount: impl Fn(&str) -> Result<Amount, QueryError>,
+) -> Result<Comparison, QueryError> {
+    let invalid = || QueryError::InvalidComparison {
+        field: field.to_owned(),
+        value: value.to_owned(),
+    };
+    if let Some((low, high)) = value.split_once("..") {
+        if low.is_empty() || high.is_empty() {
+            return Err(invalid());
+        }
+        return Ok(Comparison::Between(amount(low)?, amount(high)?));
+    }
+    let (make, rest): (fn(Amount) -> Comparison, &str) =
+        if let Some(rest) = value.strip_prefix(">=") {
+            (Comparison::GreaterOrEqual, rest)
+        } else if let Some(rest) = value.strip_prefix("<=") {
+            (Comparison::LessOrEqual, rest)
+        } else if let Some(rest) = value.strip_prefix('>') {
+            (Comparison::Greater, rest)
+        } else if let Some(rest) = value.strip_prefix('<') {
+            (Comparison::Less, rest)
+        } else {
+            (Comparison::Equal, value)
+        };
+    if rest.is_empty() || rest.starts_with(|c: char| "<>=!".contains(c)) {
+        return Err(invalid());
+    }
+    Ok(make(amount(rest)?))
+}
+
+fn number(field: &str, input: &str) -> Result<Amount, QueryError> {
+    let value = input.parse().map_err(|_| QueryError::NotANumber {
+        field: field.to_owned(),
+        value: input.to_owned(),
+    })?;
+    Ok(Amount { value, unit: 1 })
+}
+
+/// A bare number of years, or months given the way `age::parse` reads them.
+fn age_amount(input: &str) -> Result<Amount, QueryError> {
+    if let Ok(years) = input.parse() {
+        return Ok(Amount {
+            value: years,
+            unit: 12,
+        });
+    }
+    let months = age::parse(input).map_err(|_| QueryError::InvalidAge(input.to_owned()))?;
+    Ok(Amount {
+        value: months,
+        unit: 1,
+    })
+}
+
+fn term_matches(term: &Term, pet: &Pet, today: NaiveDate) -> bool {
+    let contains = |text: &str, part: &str| text.to_lowercase().contains(part);
+    match term {
+        Term::Text(text) => matches_text(pet, text),
+        Term::Name(name) => contains(&pet.name, name),
+        Term::Category(category) => pet.category.to_lowercase() == *category,
+        Term::Breed(breed) => pet
+            .breed
+            .as_deref()
+            .is_some_and(|pet_breed| contains(pet_breed, breed)),
+        Term::Status(status) => pet.status == *status,
+        Term::Microchip(digits) => pet
+            .microchip
+            .as_ref()
+            .is_some_and(|number| number.contains(digits.as_str())),
+        Term::Notes(notes) => pet
+            .notes
+            .as_deref()
+            .is_some_and(|pet_notes| contains(pet_notes, notes)),
+        Term::Age(comparison) => pet
+            .age_months(today)
+            .is_some_and(|months| comparison.holds(months)),
+        Term::Id(comparison) => comparison.holds(pet.id),
+    }
+}
+
+/// A bare word matches the name or an attribute value, ignoring case, or
+/// the microchip number, with spaces left out as numbers are often written
+/// in groups.
+fn matches_text(pet: &Pet, lowercase: &str) -> bool {
+    let matches = |text: &String| text.to_lowercase().contains(lowercase);
+    if matches(&pet.name) || pet.attributes.values().any(matches) {
+        return true;
+    }
+    let digits = microchip::normalize(lowercase);
+    !digits.is_empty()
+        && pet
+            .microchip
+            .as_ref()
+            .is_some_and(|number| number.contains(&digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()
+    }
+
+    fn aged(id: usize, name: &str, category: &str, months: usize) -> Pet {
+        let mut pet = pet(id, name, category);
+        pet.age.months = months;
+        pet
+    }
+
+    fn shelter() -> Vec<Pet> {
+        let mut rex = aged(1, "Rex", "dogs", 6 * 12);
+        rex.breed = Some("Border Collie".to_owned());
+        rex.notes = Some("Shy with cats".to_owned());
+        let mut whiskers = aged(2, "Mr Whiskers", "cats", 5 * 12 + 11);
+        whiskers.status = PetStatus::Adopted;
+        whiskers.microchip = Some("985112345678901".to_owned());
+        whiskers
+            .attributes
+            .insert("color".to_owned(), "ginger".to_owned());
+        let pup = aged(3, "Rexina", "Dogs", 5);
+        let old = aged(12, "Coco", "cats", 2 * 12);
+        vec![rex, whiskers, pup, old]
+    }
+
+    fn ids(query: &str) -> Vec<usize> {
+        let query = Query::parse(query).unwrap();
+        shelter()
+            .iter()
+            .filter(|pet| query.matches(pet, today()))
+            .map(|pet| pet.id)
+            .collect()
+    }
+
+    fn error(query: &str) -> String {
+        Query::parse(query).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn an_empty_query_matches_every_pet() {
+        assert_eq!(ids(""), [1, 2, 3, 12]);
+        assert_eq!(ids("   "), [1, 2, 3, 12]);
+        assert_eq!(Query::parse("  rex ").unwrap().text(), "rex");
+    }
+
+    #[test]
+    fn bare_words_match_the_name_attributes_or_microchip() {
+        assert_eq!(ids("rex"), [1, 3]);
+        assert_eq!(ids("REX"), [1, 3]);
+        assert_eq!(ids("ginger"), [2]);
+        assert_eq!(ids("98511 2345"), [2]);
+        assert_eq!(ids("\"mr whiskers\""), [2]);
+        assert_eq!(ids("collie"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn terms_must_all_match() {
+        assert_eq!(ids("rex category:dogs"), [1, 3]);
+        assert_eq!(ids("rex age:>1"), [1]);
+        assert_eq!(ids("category:cats age:>=5 mr"), [2]);
+        assert_eq!(ids("category:cats status:available"), [12]);
+    }
+
+    #[test]
+    fn fields_match_their_own_values() {
+        assert_eq!(ids("name:rex"), [1, 3]);
+        assert_eq!(ids("Name:\"mr whiskers\""), [2]);
+        assert_eq!(ids("name:\"mr\"\" whiskers\""), [2]);
+        assert_eq!(ids("category:dogs"), [1, 3]);
+        assert_eq!(ids("category:dog"), Vec::<usize>::new());
+        assert_eq!(ids("breed:collie"), [1]);
+        assert_eq!(ids("status:Adopted"), [2]);
+        assert_eq!(ids("microchip:\"985 112\""), [2]);
+        assert_eq!(ids("notes:\"shy with\""), [1]);
+        assert_eq!(ids("id:12"), [12]);
+        assert_eq!(ids("id:>=3"), [3, 12]);
+    }
+
+    #[test]
+    fn a_colon_after_the_field_is_part_of_the_value() {
+        assert_eq!(ids("notes:\"with: cats\""), Vec::<usize>::new());
+        assert_eq!(ids("\"a:b\""), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn a_bare_age_is_in_whole_years() {
+        assert_eq!(ids("age:5"), [2]);
+        assert_eq!(ids("age:>5"), [1]);
+        assert_eq!(ids("age:>=5"), [1, 2]);
+        assert_eq!(ids("age:<2"), [3]);
+        assert_eq!(ids("age:<=2"), [3, 12]);
+        assert_eq!(ids("age:2..5"), [2, 12]);
+        assert_eq!(ids("age:0..0"), [3]);
+    }
+
+    #[test]
+    fn ages_with_units_are_in_months() {
+        assert_eq!(ids("age:<6m"), [3]);
+        assert_eq!(ids("age:5y11m"), [2]);
+        assert_eq!(ids("age:>5y11m"), [1]);
+        assert_eq!(ids("age:1y6m..6y"), [1, 2, 12]);
+    }
+
+    #[test]
+    fn a_birthdate_gives_the_age_on_the_day() {
+        let mut pet = aged(1, "Pip", "cats", 0);
+        pet.birthdate = NaiveDate::from_ymd_opt(2020, 6, 1);
+        let query = Query::parse("age:5").unwrap();
+        assert!(query.matches(&pet, today()));
+        assert!(!query.matches(&pet, NaiveDate::from_ymd_opt(2025, 5, 31).unwrap()));
+    }
+
+    #[test]
+    fn unknown_fields_and_missing_values_are_refused() {
+        assert_eq!(
+            error("colour:red"),
+            "unknown field 'colour', expected one of: \
+             name, category, breed, status, microchip, notes, age, id"
+        );
+        assert_eq!(
+            Query::parse("colour:"),
+            Err(QueryError::UnknownField {
+                field: "colour".to_owned()
+            })
+        );
+        assert_eq!(error("name:"), "'name:' needs a value");
+        assert_eq!(error("name:\"\""), "'name:' needs a value");
+    }
+
+    #[test]
+    fn invalid_comparisons_are_refused() {
+        for value in [">", "<=", "=5", ">>5", "<>5", ">!5", "..5", "2..", ".."] {
+            assert_eq!(
+                Query::parse(&format!("age:{}", value)),
+                Err(QueryError::InvalidComparison {
+                    field: "age".to_owned(),
+                    value: value.to_owned(),
+                }),
+                "{}",
+                value
+            );
+        }
+        assert_eq!(error("id:>five"), "id compares numbers, not 'five'");
+        assert_eq!(error("id:1..2y"), "id compares numbers, not '2y'");
+        assert_eq!(
+            error("age:old"),
+            "invalid age 'old', expected whole years like 5 or an age like 8m or 1y6m"
+        );
+        assert_eq!(
+            error("status:lost"),
+            "unknown status 'lost', expected intake, available or adopted"
+        );
+    }
+
+    #[test]
+    fn an_unclosed_quote_is_refused() {
+        assert_eq!(
+            Query::parse("\"mr whiskers"),
+            Err(QueryError::UnclosedQuote)
+        );
+        assert_eq!(error("name:\"mr"), "missing closing \" in the search");
+    }
+}
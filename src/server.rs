@@ -0,0 +1,863 @@
+//! `pet-cli serve`: a small JSON API over the DB, for looking at the pets from
+//! another device. It speaks just enough HTTP/1.1 for that: one request per
+//! connection, bodies sized by `Content-Length`. Writes go through the same
+//! storage functions as the TUI, so they take the DB lock and replace the file
+//! in one step.
+//!
+//! `GET /pets` is paged with `?limit=&offset=`, ordered by id so the pages
+//! line up from one request to the next, and narrowed down with `?category=`,
+//! `?status=` and `?q=`, the last a search as in the TUI's `/`.
+//!
+//! `GET /metrics` has the counters of `metrics` in Prometheus' text format.
+
+use crate::age::Age;
+use crate::filter::CategoryFilter;
+use crate::metrics;
+use crate::microchip;
+use crate::pet_status::PetStatus;
+use crate::search::Query;
+use crate::view::{Sort, SortKey, ViewParams};
+use crate::{add_pet, read_db, set_archived, validation, Error, Pet, WriteOptions};
+use chrono::{NaiveDate, Utc};
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Request bodies larger than this are refused; a pet is a few hundred bytes.
+const MAX_BODY_LEN: usize = 64 * 1024;
+
+/// The most the request line and headers together can take. Requests with
+/// more are refused rather than read into memory line after line.
+const MAX_HEADER_LEN: u64 = 8 * 1024;
+
+/// How long a client can take to send its request or read the response
+/// before the connection is dropped, so a stalled one doesn't hold a thread.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections served at once. Any more are answered 503 straight away.
+const MAX_CONNECTIONS: usize = 32;
+
+/// How long what is left of a refused request is read for before closing.
+const LINGER: Duration = Duration::from_millis(100);
+
+/// Pets per page of `GET /pets` without a `limit`, and the most a `limit` can
+/// ask for.
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+/// The fields of a pet a client chooses. The id and creation time are
+/// assigned by the server, so a full pet as returned by `GET` is accepted too.
+#[derive(Deserialize)]
+struct NewPet {
+    name: String,
+    category: String,
+    /// `age_months`, or `age` in whole years.
+    #[serde(flatten)]
+    age: Age,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    birthdate: Option<NaiveDate>,
+    #[serde(default)]
+    owner_id: Option<usize>,
+    #[serde(default)]
+    microchip: Option<String>,
+    #[serde(default)]
+    breed: Option<String>,
+    #[serde(default)]
+    status: PetStatus,
+    #[serde(default)]
+    attributes: BTreeMap<String, String>,
+}
+
+struct Request {
+    method: String,
+    /// With the query string, if any.
+    path: String,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Option<Body>,
+}
+
+enum Body {
+    Json(serde_json::Value),
+    /// Served as Prometheus' text format, the only text there is.
+    Metrics(String),
+}
+
+impl Response {
+    fn json(status: u16, body: serde_json::Value) -> Self {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: Some(Body::Json(body)),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Response::json(status, json!({ "error": message.into() }))
+    }
+}
+
+/// Serves until the process is killed. Each connection gets its own thread,
+/// up to `MAX_CONNECTIONS` of them; the DB lock keeps concurrent writes apart.
+pub fn serve(addr: SocketAddr, options: WriteOptions) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("serving the pet DB on http://{}", listener.local_addr()?);
+    accept(listener, options);
+    Ok(())
+}
+
+fn accept(listener: TcpListener, options: WriteOptions) {
+    let open = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::warn!("accepting a connection failed: {}", err);
+                continue;
+            }
+        };
+        if let Err(err) = stream
+            .set_read_timeout(Some(TIMEOUT))
+            .and_then(|()| stream.set_write_timeout(Some(TIMEOUT)))
+        {
+            log::warn!("setting the connection's timeouts failed: {}", err);
+            continue;
+        }
+        let Some(slot) = Slot::take(&open) else {
+            log::warn!("refusing a connection, {} are open", MAX_CONNECTIONS);
+            let response = Response::error(503, "too many connections");
+            if let Err(err) = refuse(&mut stream, &response) {
+                log::warn!("connection failed: {}", err);
+            }
+            continue;
+        };
+        thread::spawn(move || {
+            let _slot = slot;
+            if let Err(err) = handle_connection(stream, options) {
+                log::warn!("connection failed: {}", err);
+            }
+        });
+    }
+}
+
+/// One of the `MAX_CONNECTIONS`, given back when the connection is done.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn take(open: &Arc<AtomicUsize>) -> Option<Slot> {
+        open.fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+            (count < MAX_CONNECTIONS).then_some(count + 1)
+        })
+        .ok()
+        .map(|_| Slot(Arc::clone(open)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, options: WriteOptions) -> io::Result<()> {
+    let request = match read_request(&mut stream)? {
+        Ok(request) => request,
+        Err(response) => return refuse(&mut stream, &response),
+    };
+    let response = route(&request, options);
+    metrics::record_request(route_name(&request.path), response.status);
+    log::info!("{} {} -> {}", request.method, request.path, response.status);
+    write_response(&mut stream, &response)
+}
+
+/// Answers a request that wasn't read in full. What is left of it is read
+/// for a moment before closing: closing with input unread resets the
+/// connection, and the client can lose the response with it.
+fn refuse(stream: &mut TcpStream, response: &Response) -> io::Result<()> {
+    write_response(stream, response)?;
+    stream.shutdown(Shutdown::Write)?;
+    stream.set_read_timeout(Some(LINGER))?;
+    let _ = io::copy(&mut stream.take(MAX_BODY_LEN as u64), &mut io::sink());
+    Ok(())
+}
+
+/// The response to refuse with for requests that aren't HTTP, 400, or have
+/// headers or a body that are too large, 431 and 413.
+fn read_request(stream: &mut TcpStream) -> io::Result<Result<Request, Response>> {
+    let malformed = || Response::error(400, "malformed request");
+    let mut reader = BufReader::new(stream).take(MAX_HEADER_LEN);
+    // A line cut short by the cap rather than by the client closing.
+    let cut_short = |reader: &io::Take<_>| {
+        if reader.limit() == 0 {
+            Response::error(
+                431,
+                format!("the headers are over {} bytes", MAX_HEADER_LEN),
+            )
+        } else {
+            malformed()
+        }
+    };
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    if !request_line.ends_with('\n') {
+        return Ok(Err(cut_short(&reader)));
+    }
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(Err(malformed()));
+    };
+    let (method, path) = (method.to_owned(), path.to_owned());
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || !header.ends_with('\n') {
+            return Ok(Err(cut_short(&reader)));
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                let Ok(length) = value.trim().parse() else {
+                    return Ok(Err(malformed()));
+                };
+                content_length = length;
+            }
+        }
+    }
+    if content_length > MAX_BODY_LEN {
+        return Ok(Err(Response::error(
+            413,
+            format!("the body is over {} bytes", MAX_BODY_LEN),
+        )));
+    }
+    reader.set_limit(content_length as u64);
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Ok(Request { method, path, body }))
+}
+
+fn route(request: &Request, options: WriteOptions) -> Response {
+    let (path, query) = request.path.split_once('?').unwrap_or((&request.path, ""));
+    let segments: Vec<&str> = path
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let result = match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["pets"]) => match parse_listing(query) {
+            Ok(listing) => list_pets(&listing),
+            Err(message) => Ok(Response::error(400, message)),
+        },
+        ("POST", ["pets"]) => create_pet(&request.body, options),
+        (method, ["pets", id]) => match id.parse() {
+            Ok(id) if method == "GET" => get_pet(id),
+            Ok(id) if method == "DELETE" => delete_pet(id, options),
+            Ok(_) => Ok(Response::error(405, "method not allowed")),
+            Err(_) => Ok(Response::error(404, format!("no pet {:?}", id))),
+        },
+        (_, ["pets"]) => Ok(Response::error(405, "method not allowed")),
+        ("GET", ["metrics"]) => Ok(Response {
+            status: 200,
+            headers: Vec::new(),
+            body: Some(Body::Metrics(metrics::snapshot().exposition())),
+        }),
+        (_, ["metrics"]) => Ok(Response::error(405, "method not allowed")),
+        _ => Ok(Response::error(404, "not found")),
+    };
+    result.unwrap_or_else(|err| match err {
+        Error::ReadOnly => Response::error(403, err.to_string()),
+        err => {
+            log::error!("{} {} failed: {}", request.method, request.path, err);
+            Response::error(500, err.to_string())
+        }
+    })
+}
+
+/// The route `path` is for, as the metrics count requests: with ids left
+/// out, so that there is one count per route rather than per pet.
+fn route_name(path: &str) -> &'static str {
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+    let segments: Vec<&str> = path
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    match segments.as_slice() {
+        ["pets"] => "/pets",
+        ["pets", _] => "/pets/{id}",
+        ["metrics"] => "/metrics",
+        _ => "other",
+    }
+}
+
+/// A page of `GET /pets` and the pets it is taken from.
+struct Listing {
+    view: ViewParams,
+    limit: usize,
+    offset: usize,
+}
+
+/// Reads the query string of `GET /pets`, refusing parameters it doesn't
+/// know rather than ignoring a misspelt filter.
+fn parse_listing(query: &str) -> Result<Listing, String> {
+    let mut listing = Listing {
+        view: ViewParams {
+            sort: Some(Sort {
+                key: SortKey::Id,
+                descending: false,
+            }),
+            ..ViewParams::default()
+        },
+        limit: DEFAULT_LIMIT,
+        offset: 0,
+    };
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = decode(value).ok_or_else(|| format!("invalid encoding in {:?}", pair))?;
+        let number = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("invalid {} {:?}, expected a number", name, value))
+        };
+        match name {
+            "limit" => {
+                listing.limit = number()?;
+                if listing.limit > MAX_LIMIT {
+                    return Err(format!(
+                        "limit {} is over the maximum of {}",
+                        listing.limit, MAX_LIMIT
+                    ));
+                }
+            }
+            "offset" => listing.offset = number()?,
+            "category" => listing.view.category = CategoryFilter::only(Some(value)),
+            "status" => {
+                let status = PetStatus::from_str(&value, true).map_err(|_| {
+                    format!(
+                        "unknown status {:?}, expected intake, available or adopted",
+                        value
+                    )
+                })?;
+                listing.view.status = Some(status);
+            }
+            "q" => {
+                let query = Query::parse(&value).map_err(|err| err.to_string())?;
+                listing.view.query = Some(query).filter(|_| !value.trim().is_empty());
+            }
+            name => {
+                return Err(format!(
+                    "unknown parameter {:?}, expected limit, offset, category, status or q",
+                    name
+                ))
+            }
+        }
+    }
+    Ok(listing)
+}
+
+/// Decodes `%XX` escapes and `+` for a space.
+fn decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.bytes();
+    while let Some(byte) = rest.next() {
+        bytes.push(match byte {
+            b'+' => b' ',
+            b'%' => {
+                let hex = [rest.next()?, rest.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            }
+            byte => byte,
+        });
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Pets in the Trash are left out, as in the Pets view. `X-Total-Count` is
+/// how many pets the filters leave, on every page; an offset past them is an
+/// empty page.
+fn list_pets(listing: &Listing) -> Result<Response, Error> {
+    let pets = read_db()?;
+    let matching = listing.view.apply(&pets);
+    let page: Vec<&Pet> = matching
+        .iter()
+        .skip(listing.offset)
+        .take(listing.limit)
+        .copied()
+        .collect();
+    let mut response = Response::json(200, json!(page));
+    response
+        .headers
+        .push(("X-Total-Count", matching.len().to_string()));
+    Ok(response)
+}
+
+fn get_pet(id: usize) -> Result<Response, Error> {
+    let pets = read_db()?;
+    Ok(
+        match pets.iter().find(|pet| pet.id == id && !pet.is_archived()) {
+            Some(pet) => Response::json(200, json!(pet)),
+            None => Response::error(404, format!("no pet #{}", id)),
+        },
+    )
+}
+
+fn create_pet(body: &[u8], options: WriteOptions) -> Result<Response, Error> {
+    let new_pet: NewPet = match serde_json::from_slice(body) {
+        Ok(new_pet) => new_pet,
+        Err(err) => return Ok(Response::error(400, format!("invalid JSON: {}", err))),
+    };
+    let pet = Pet {
+        id: 0,
+        name: new_pet.name,
+        category: new_pet.category,
+        age: new_pet.age,
+        created_at: Utc::now(),
+        notes: new_pet.notes,
+        birthdate: new_pet.birthdate,
+        owner_id: new_pet.owner_id,
+        records: Vec::new(),
+        weights: Vec::new(),
+        microchip: new_pet.microchip.as_deref().map(microchip::normalize),
+        breed: new_pet.breed,
+        status: new_pet.status,
+        photo_path: None,
+        attributes: new_pet.attributes,
+        archived_at: None,
+        extra: Default::default(),
+    };
+    let issues = validation::validate(&pet);
+    if !issues.is_empty() {
+        let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        return Ok(Response::json(422, json!({ "issues": issues })));
+    }
+    let pet = add_pet(options, pet)?;
+    Ok(Response::json(201, json!(pet)))
+}
+
+/// Moves the pet to the Trash, like `d` in the TUI.
+fn delete_pet(id: usize, options: WriteOptions) -> Result<Response, Error> {
+    let archived = set_archived(options, &HashSet::from([id]), Some(Utc::now()))?;
+    Ok(if archived.is_empty() {
+        Response::error(404, format!("no pet #{}", id))
+    } else {
+        Response {
+            status: 204,
+            headers: Vec::new(),
+            body: None,
+        }
+    })
+}
+
+fn write_response(stream: &mut TcpStream, response: &Response) -> io::Result<()> {
+    let body = match &response.body {
+        Some(Body::Json(body)) => body.to_string(),
+        Some(Body::Metrics(text)) => text.clone(),
+        None => String::new(),
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        reason(response.status),
+        body.len()
+    );
+    match response.body {
+        Some(Body::Json(_)) => head.push_str("Content-Type: application/json\r\n"),
+        Some(Body::Metrics(_)) => head.push_str("Content-Type: text/plain; version=0.0.4\r\n"),
+        None => {}
+    }
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Content Too Large",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::{pet, DB_IN_USE};
+    use crate::{read_database, set_workspace_db, write_db, AppState, Database};
+    use serde_json::Value;
+    use std::path::PathBuf;
+    use std::sync::MutexGuard;
+
+    /// The server on an ephemeral port, serving a DB of its own in a
+    /// temporary directory.
+    struct Server {
+        addr: SocketAddr,
+        dir: PathBuf,
+        _db_in_use: MutexGuard<'static, ()>,
+    }
+
+    impl Server {
+        fn start(pets: Vec<Pet>) -> Server {
+            let db_in_use = DB_IN_USE.lock().unwrap_or_else(|err| err.into_inner());
+            static DIRS: AtomicUsize = AtomicUsize::new(0);
+            let dir = std::env::temp_dir().join(format!(
+                "pet-cli-server-test-{}-{}",
+                std::process::id(),
+                DIRS.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::fs::create_dir_all(&dir).expect("a temporary directory");
+            set_workspace_db(&dir.join("db.json"));
+            let options = AppState::default().write_options;
+            let mut database = Database {
+                pets,
+                ..Database::default()
+            };
+            write_db(&mut database, options).expect("the DB is written");
+            let listener = TcpListener::bind("127.0.0.1:0").expect("an ephemeral port");
+            let addr = listener.local_addr().expect("the port");
+            thread::spawn(move || accept(listener, options));
+            Server {
+                addr,
+                dir,
+                _db_in_use: db_in_use,
+            }
+        }
+
+        /// Sends `raw` as it is and returns the status, headers and body.
+        fn send(&self, raw: &[u8]) -> (u16, Vec<(String, String)>, String) {
+            let mut stream = TcpStream::connect(self.addr).expect("the server is up");
+            stream.write_all(raw).expect("the request is sent");
+            let mut response = String::new();
+            stream
+                .read_to_string(&mut response)
+                .expect("the response is read");
+            let (head, body) = response.split_once("\r\n\r\n").expect("a response");
+            let mut lines = head.lines();
+            let status = lines
+                .next()
+                .and_then(|line| line.split_whitespace().nth(1))
+                .and_then(|status| status.parse().ok())
+                .expect("a status line");
+            let headers = lines
+                .filter_map(|line| line.split_once(": "))
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                .collect();
+            (status, headers, body.to_owned())
+        }
+
+        fn request(&self, method: &str, path: &str, body: &str) -> (u16, Value) {
+            let raw = format!(
+                "{} {} HTTP/1.1\r\nHost: test\r\nContent-Length: {}\r\n\r\n{}",
+                method,
+                path,
+                body.len(),
+                body
+            );
+            let (status, _, body) = self.send(raw.as_bytes());
+            (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+        }
+
+        /// The names of a page of `GET path` and its `X-Total-Count`.
+        fn page(&self, path: &str) -> (Vec<String>, usize) {
+            let raw = format!("GET {} HTTP/1.1\r\n\r\n", path);
+            let (status, headers, body) = self.send(raw.as_bytes());
+            assert_eq!(status, 200, "{}", body);
+            let pets: Vec<Pet> = serde_json::from_str(&body).expect("a list of pets");
+            let total = headers
+                .iter()
+                .find(|(name, _)| name == "X-Total-Count")
+                .and_then(|(_, value)| value.parse().ok())
+                .expect("a total count");
+            (pets.into_iter().map(|pet| pet.name).collect(), total)
+        }
+    }
+
+    impl Drop for Server {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn with_status(mut pet: Pet, status: PetStatus) -> Pet {
+        pet.status = status;
+        pet
+    }
+
+    fn shelter() -> Vec<Pet> {
+        vec![
+            pet(3, "Bun", "rabbits"),
+            pet(1, "Coco", "cats"),
+            with_status(pet(2, "Rex", "dogs"), PetStatus::Adopted),
+            pet(4, "Max", "dogs"),
+            pet(5, "Mia", "cats"),
+        ]
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn lists_pets_by_id() {
+        let server = Server::start(shelter());
+        assert_eq!(
+            server.page("/pets"),
+            (names(&["Coco", "Rex", "Bun", "Max", "Mia"]), 5)
+        );
+    }
+
+    #[test]
+    fn pages_line_up() {
+        let server = Server::start(shelter());
+        assert_eq!(server.page("/pets?limit=2"), (names(&["Coco", "Rex"]), 5));
+        assert_eq!(
+            server.page("/pets?limit=2&offset=2"),
+            (names(&["Bun", "Max"]), 5)
+        );
+        assert_eq!(server.page("/pets?limit=2&offset=4"), (names(&["Mia"]), 5));
+    }
+
+    #[test]
+    fn offset_past_the_end_is_an_empty_page() {
+        let server = Server::start(shelter());
+        assert_eq!(server.page("/pets?offset=5"), (names(&[]), 5));
+        assert_eq!(server.page("/pets?offset=1000000"), (names(&[]), 5));
+    }
+
+    #[test]
+    fn limit_zero_is_an_empty_page_with_the_total() {
+        let server = Server::start(shelter());
+        assert_eq!(server.page("/pets?limit=0"), (names(&[]), 5));
+    }
+
+    #[test]
+    fn limit_up_to_the_maximum_is_allowed() {
+        let server = Server::start(shelter());
+        let path = format!("/pets?limit={}", MAX_LIMIT);
+        assert_eq!(server.page(&path).1, 5);
+        let (status, body) = server.request("GET", &format!("/pets?limit={}", MAX_LIMIT + 1), "");
+        assert_eq!(status, 400);
+        assert!(body["error"]
+            .as_str()
+            .is_some_and(|error| error.contains("maximum")));
+    }
+
+    #[test]
+    fn filters_combine() {
+        let server = Server::start(shelter());
+        assert_eq!(
+            server.page("/pets?category=dogs"),
+            (names(&["Rex", "Max"]), 2)
+        );
+        assert_eq!(
+            server.page("/pets?category=dogs&status=available"),
+            (names(&["Max"]), 1)
+        );
+        assert_eq!(
+            server.page("/pets?category=cats&q=mi"),
+            (names(&["Mia"]), 1)
+        );
+        assert_eq!(
+            server.page("/pets?q=category%3Acats&limit=1&offset=1"),
+            (names(&["Mia"]), 2)
+        );
+    }
+
+    #[test]
+    fn unknown_parameters_and_values_are_refused() {
+        let server = Server::start(shelter());
+        for path in [
+            "/pets?categroy=cats",
+            "/pets?status=lost",
+            "/pets?limit=-1",
+            "/pets?offset=x",
+            "/pets?q=%zz",
+        ] {
+            let (status, body) = server.request("GET", path, "");
+            assert_eq!(status, 400, "{}", path);
+            assert!(body["error"].is_string(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn gets_a_pet_by_id() {
+        let server = Server::start(shelter());
+        let (status, body) = server.request("GET", "/pets/4", "");
+        assert_eq!(status, 200);
+        assert_eq!(body["name"], "Max");
+        assert_eq!(server.request("GET", "/pets/99", "").0, 404);
+        assert_eq!(server.request("GET", "/pets/max", "").0, 404);
+    }
+
+    #[test]
+    fn creates_a_pet() {
+        let server = Server::start(shelter());
+        let (status, body) = server.request(
+            "POST",
+            "/pets",
+            r#"{"name": "Pip", "category": "cats", "age_months": 3}"#,
+        );
+        assert_eq!(status, 201);
+        assert_eq!(body["name"], "Pip");
+        let id = body["id"].as_u64().expect("an id") as usize;
+        let database = read_database().expect("the DB can be read");
+        assert!(database
+            .pets
+            .iter()
+            .any(|pet| pet.id == id && pet.name == "Pip"));
+    }
+
+    #[test]
+    fn invalid_pets_are_422_with_the_issues() {
+        let server = Server::start(shelter());
+        let (status, body) = server.request(
+            "POST",
+            "/pets",
+            r#"{"name": " ", "category": "cats", "age_months": 3}"#,
+        );
+        assert_eq!(status, 422);
+        assert_eq!(body["issues"].as_array().map(Vec::len), Some(1));
+        let (status, _) = server.request("POST", "/pets", "{not json");
+        assert_eq!(status, 400);
+        assert_eq!(read_database().expect("the DB").pets.len(), 5);
+    }
+
+    #[test]
+    fn deleting_moves_a_pet_to_the_trash() {
+        let server = Server::start(shelter());
+        assert_eq!(server.request("DELETE", "/pets/1", "").0, 204);
+        assert_eq!(server.request("GET", "/pets/1", "").0, 404);
+        assert_eq!(server.request("DELETE", "/pets/1", "").0, 404);
+        assert_eq!(server.page("/pets").1, 4);
+        let database = read_database().expect("the DB can be read");
+        assert!(database
+            .pets
+            .iter()
+            .any(|pet| pet.id == 1 && pet.is_archived()));
+    }
+
+    #[test]
+    fn other_methods_and_paths() {
+        let server = Server::start(shelter());
+        assert_eq!(server.request("PUT", "/pets", "").0, 405);
+        assert_eq!(server.request("PATCH", "/pets/1", "").0, 405);
+        assert_eq!(server.request("GET", "/owners", "").0, 404);
+    }
+
+    #[test]
+    fn headers_past_the_cap_are_refused_with_431() {
+        let server = Server::start(shelter());
+        let raw = format!(
+            "GET /pets HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(MAX_HEADER_LEN as usize)
+        );
+        let (status, _, body) = server.send(raw.as_bytes());
+        assert_eq!(status, 431);
+        assert_eq!(body, r#"{"error":"the headers are over 8192 bytes"}"#);
+        let raw = format!(
+            "GET /{} HTTP/1.1\r\n\r\n",
+            "a".repeat(MAX_HEADER_LEN as usize)
+        );
+        assert_eq!(server.send(raw.as_bytes()).0, 431);
+    }
+
+    #[test]
+    fn bodies_past_the_cap_are_refused_with_413() {
+        let server = Server::start(shelter());
+        let raw = format!(
+            "POST /pets HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_LEN + 1
+        );
+        let (status, _, body) = server.send(raw.as_bytes());
+        assert_eq!(status, 413);
+        assert_eq!(body, r#"{"error":"the body is over 65536 bytes"}"#);
+        let raw = "POST /pets HTTP/1.1\r\nContent-Length: lots\r\n\r\n";
+        assert_eq!(server.send(raw.as_bytes()).0, 400);
+    }
+
+    #[test]
+    fn connections_past_the_limit_get_503() {
+        let server = Server::start(shelter());
+        let idle: Vec<TcpStream> = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(server.addr).expect("the server is up"))
+            .collect();
+        let (status, body) = server.request("GET", "/pets", "");
+        assert_eq!(status, 503);
+        assert_eq!(body["error"], "too many connections");
+        drop(idle);
+        // The slots come back as the idle connections' threads see them close.
+        let status = (0..100)
+            .map(|_| {
+                thread::sleep(Duration::from_millis(10));
+                server.request("GET", "/pets/1", "").0
+            })
+            .find(|status| *status != 503);
+        assert_eq!(status, Some(200));
+    }
+
+    #[test]
+    fn metrics_count_requests_by_route_and_status() {
+        let server = Server::start(shelter());
+        server.request("GET", "/pets/1", "");
+        server.request("GET", "/pets/999?x=1", "");
+        let (status, headers, body) = server.send(b"GET /metrics HTTP/1.1\r\n\r\n");
+        assert_eq!(status, 200);
+        assert!(
+            headers
+                .iter()
+                .any(|(name, value)| name == "Content-Type" && value.starts_with("text/plain")),
+            "{:?}",
+            headers
+        );
+        assert!(
+            body.contains("pet_cli_http_requests_total{route=\"/pets/{id}\",status=\"200\"}"),
+            "{}",
+            body
+        );
+        assert!(
+            body.contains("pet_cli_http_requests_total{route=\"/pets/{id}\",status=\"404\"}"),
+            "{}",
+            body
+        );
+        assert!(body.contains("\npet_cli_db_reads_total "), "{}", body);
+        let (status, _) = server.request("POST", "/metrics", "");
+        assert_eq!(status, 405);
+    }
+
+    #[test]
+    fn routes_are_counted_without_ids_or_queries() {
+        assert_eq!(route_name("/pets"), "/pets");
+        assert_eq!(route_name("/pets/?limit=2"), "/pets");
+        assert_eq!(route_name("/pets/12"), "/pets/{id}");
+        assert_eq!(route_name("//metrics/"), "/metrics");
+        assert_eq!(route_name("/pets/1/records"), "other");
+        assert_eq!(route_name("/"), "other");
+    }
+}
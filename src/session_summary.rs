@@ -0,0 +1,156 @@
+//! The line printed once the TUI has quit and the terminal is back to normal,
+//! like `session: 3 pets added, 1 deleted, 2 edited, db written 4 times,
+//! path: ./data/db.json`. `--quiet` leaves it out.
+
+use crate::diff;
+use crate::Pet;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SessionStats {
+    pub added: usize,
+    /// Moved to the Trash or deleted for good.
+    pub deleted: usize,
+    pub edited: usize,
+    /// Writes of the DB file, including those that left the pets as they
+    /// were, like adding an owner.
+    pub writes: u64,
+}
+
+impl SessionStats {
+    /// What changed from `start` to `end`. A pet added and deleted again in
+    /// between doesn't count; a pet restored from the Trash counts as edited.
+    pub fn between(start: &[Pet], end: &[Pet], writes: u64) -> Self {
+        let diff = diff::diff_pets(start, end);
+        let trashed = diff
+            .changed
+            .iter()
+            .filter(|change| {
+                let was_archived = start
+                    .iter()
+                    .any(|pet| pet.id == change.id && pet.is_archived());
+                let is_archived = end
+                    .iter()
+                    .any(|pet| pet.id == change.id && pet.is_archived());
+                !was_archived && is_archived
+            })
+            .count();
+        // Added and moved to the Trash again is as good as never added.
+        let added = diff.added.iter().filter(|pet| !pet.is_archived()).count();
+        SessionStats {
+            added,
+            deleted: diff.removed.len() + trashed,
+            edited: diff.changed.len() - trashed,
+            writes,
+        }
+    }
+}
+
+/// The summary of a session in the DB at `path`.
+pub fn line(stats: &SessionStats, path: &str) -> String {
+    let mut parts = Vec::new();
+    let counts = [
+        (stats.added, "added"),
+        (stats.deleted, "deleted"),
+        (stats.edited, "edited"),
+    ];
+    for (count, what) in counts {
+        if count == 0 {
+            continue;
+        }
+        // Only the first count says what is counted.
+        let noun = match (parts.is_empty(), count) {
+            (false, _) => "",
+            (true, 1) => " pet",
+            (true, _) => " pets",
+        };
+        parts.push(format!("{}{} {}", count, noun, what));
+    }
+    if parts.is_empty() {
+        parts.push("no changes".to_owned());
+    }
+    match stats.writes {
+        0 => {}
+        1 => parts.push("db written once".to_owned()),
+        writes => parts.push(format!("db written {} times", writes)),
+    }
+    parts.push(format!("path: {}", path));
+    format!("session: {}", parts.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use chrono::{TimeZone, Utc};
+
+    fn stats(added: usize, deleted: usize, edited: usize, writes: u64) -> SessionStats {
+        SessionStats {
+            added,
+            deleted,
+            edited,
+            writes,
+        }
+    }
+
+    #[test]
+    fn a_session_without_changes_says_so() {
+        assert_eq!(
+            line(&SessionStats::default(), "./data/db.json"),
+            "session: no changes, path: ./data/db.json"
+        );
+        // Writes that changed no pet, like adding an owner.
+        assert_eq!(
+            line(&stats(0, 0, 0, 2), "db.json"),
+            "session: no changes, db written 2 times, path: db.json"
+        );
+    }
+
+    #[test]
+    fn counts_are_singular_or_plural() {
+        assert_eq!(
+            line(&stats(3, 1, 2, 4), "./data/db.json"),
+            "session: 3 pets added, 1 deleted, 2 edited, db written 4 times, path: ./data/db.json"
+        );
+        assert_eq!(
+            line(&stats(1, 0, 0, 1), "db.json"),
+            "session: 1 pet added, db written once, path: db.json"
+        );
+        assert_eq!(
+            line(&stats(0, 2, 1, 3), "db.json"),
+            "session: 2 pets deleted, 1 edited, db written 3 times, path: db.json"
+        );
+        assert_eq!(
+            line(&stats(0, 0, 1, 1), "db.json"),
+            "session: 1 pet edited, db written once, path: db.json"
+        );
+    }
+
+    #[test]
+    fn changes_are_counted_between_the_start_and_the_end() {
+        let trashed_at = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let mut in_trash = pet(4, "Max", "dogs");
+        in_trash.archived_at = Some(trashed_at);
+        let start = vec![
+            pet(1, "Coco", "cats"),
+            pet(2, "Rex", "dogs"),
+            pet(3, "Bun", "rabbits"),
+            in_trash,
+        ];
+        let mut end = start.clone();
+        end[0].name = "Coco Chanel".to_owned();
+        end[1].archived_at = Some(trashed_at);
+        end.remove(2);
+        end[2].archived_at = None;
+        end.push(pet(5, "Pip", "birds"));
+        assert_eq!(SessionStats::between(&start, &end, 6), stats(1, 2, 2, 6));
+        // A pet added and deleted again doesn't count.
+        let mut added_and_trashed = start.clone();
+        added_and_trashed.push(pet(6, "Ghost", "cats"));
+        added_and_trashed[4].archived_at = Some(trashed_at);
+        assert_eq!(
+            SessionStats::between(&start, &added_and_trashed, 2),
+            stats(0, 0, 0, 2)
+        );
+        assert_eq!(SessionStats::between(&start, &start, 0), stats(0, 0, 0, 0));
+    }
+}
@@ -0,0 +1,78 @@
+//! Single pets as JSON files to pass on to someone with their own DB. A
+//! snippet is the pet as stored, without what the importing DB assigns anew,
+//! its id and creation time, or what only means something in this DB, its
+//! owner and whether it is in the Trash.
+
+use crate::Pet;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Where `E` writes pets to, relative to the working directory.
+pub const EXPORT_DIR: &str = "exports";
+
+const LOCAL_FIELDS: [&str; 4] = ["id", "created_at", "owner_id", "archived_at"];
+
+pub fn snippet(pet: &Pet) -> String {
+    let mut value = serde_json::to_value(pet).expect("pets serialize");
+    if let Value::Object(fields) = &mut value {
+        for field in LOCAL_FIELDS {
+            fields.remove(field);
+        }
+    }
+    serde_json::to_string_pretty(&value).expect("values serialize")
+}
+
+/// `exports/<name>-<id>.json`, with anything but letters and digits in the
+/// name replaced so it makes a safe file name.
+pub fn export_path(pet: &Pet) -> PathBuf {
+    let name: String = pet
+        .name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    PathBuf::from(EXPORT_DIR).join(format!("{}-{}.json", name, pet.id))
+}
+
+/// The pets in a snippet, or in an array of them, created at `now`. Their
+/// ids are placeholders for the DB to replace. Fails with every entry that
+/// could not be read, so one look at the error is enough to fix the file.
+pub fn parse(content: &str, now: DateTime<Utc>) -> Result<Vec<Pet>, String> {
+    let value: Value = serde_json::from_str(content).map_err(|err| err.to_string())?;
+    let (entries, single) = match value {
+        Value::Array(entries) => (entries, false),
+        entry => (vec![entry], true),
+    };
+    if entries.is_empty() {
+        return Err("the file has no pets".to_owned());
+    }
+    let mut pets = Vec::new();
+    let mut failures = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        match pet_from(entry, now) {
+            Ok(pet) => pets.push(pet),
+            Err(err) if single => failures.push(err),
+            Err(err) => failures.push(format!("entry {}: {}", index + 1, err)),
+        }
+    }
+    if failures.is_empty() {
+        Ok(pets)
+    } else {
+        Err(failures.join("; "))
+    }
+}
+
+fn pet_from(entry: Value, now: DateTime<Utc>) -> Result<Pet, String> {
+    let Value::Object(mut fields) = entry else {
+        return Err("expected a pet object".to_owned());
+    };
+    for field in LOCAL_FIELDS {
+        fields.remove(field);
+    }
+    fields.insert("id".to_owned(), Value::from(0));
+    fields.insert(
+        "created_at".to_owned(),
+        serde_json::to_value(now).expect("times serialize"),
+    );
+    serde_json::from_value(Value::Object(fields)).map_err(|err| err.to_string())
+}
@@ -0,0 +1,595 @@
+//! Just enough of the SQLite file format to read the rows of one table, for
+//! `pet-cli import --from sqlite`: the header, the table b-trees with their
+//! overflow pages, records, and the column names from the `CREATE TABLE` in
+//! the schema. Nothing is ever written, and indexes, `WITHOUT ROWID` tables
+//! and text in UTF-16 aren't read. Databases in WAL mode are refused, as the
+//! main file alone can miss rows.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+const MAGIC: &[u8; 16] = b"SQLite format 3\0";
+const HEADER_SIZE: usize = 100;
+/// The file format version in bytes 18 and 19 of the header when the
+/// database is in WAL mode.
+const WAL_VERSION: u8 = 2;
+const WAL_REFUSED: &str = "the database is in WAL mode, where rows can still be in its -wal file; \
+     run `PRAGMA wal_checkpoint(TRUNCATE); PRAGMA journal_mode=DELETE;` on it first";
+
+const INTERIOR_TABLE_PAGE: u8 = 0x05;
+const LEAF_TABLE_PAGE: u8 = 0x0d;
+
+/// A value as SQLite stores it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl Value {
+    /// The value as text, `None` for NULL. SQLite keeps whatever was put in
+    /// a column, so numbers are turned into text too.
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            Value::Null => None,
+            Value::Integer(number) => Some(number.to_string()),
+            Value::Real(number) => Some(number.to_string()),
+            Value::Text(text) => Some(text.clone()),
+            Value::Blob(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+        }
+    }
+}
+
+/// The rows of a table, in the order of their rowids.
+#[derive(Debug)]
+pub struct Table {
+    pub columns: Vec<String>,
+    /// One value per column.
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Reads the table named `name`, ignoring case as SQLite does, from the
+/// database file at `path`.
+pub fn read_table(path: &Path, name: &str) -> Result<Table, String> {
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    if fs::metadata(wal).is_ok_and(|wal| wal.len() > 0) {
+        return Err(WAL_REFUSED.to_owned());
+    }
+    let file = File::new(fs::read(path).map_err(|err| err.to_string())?)?;
+    let mut tables = Vec::new();
+    let mut found = None;
+    for (_, record) in file.rows(1)? {
+        let text = |index: usize| match record.get(index) {
+            Some(Value::Text(text)) => text.as_str(),
+            _ => "",
+        };
+        if text(0) != "table" || text(1).starts_with("sqlite_") {
+            continue;
+        }
+        tables.push(text(1).to_owned());
+        if text(1).eq_ignore_ascii_case(name) {
+            let root = match record.get(3) {
+                Some(Value::Integer(root)) if *root > 0 => *root as u32,
+                _ => return Err(format!("table {} has no pages", name)),
+            };
+            found = Some((root, text(4).to_owned()));
+        }
+    }
+    let Some((root, sql)) = found else {
+        return Err(match tables.as_slice() {
+            [] => format!("no table {}, the file has none", name),
+            tables => format!("no table {}, expected one of: {}", name, tables.join(", ")),
+        });
+    };
+    let (columns, rowid_column) = columns(&sql)?;
+    let rows = file
+        .rows(root)?
+        .into_iter()
+        .map(|(rowid, mut values)| {
+            values.resize(columns.len(), Value::Null);
+            if let Some(index) = rowid_column {
+                values[index] = Value::Integer(rowid);
+            }
+            values
+        })
+        .collect();
+    Ok(Table { columns, rows })
+}
+
+/// The database file, read whole.
+struct File {
+    bytes: Vec<u8>,
+    page_size: usize,
+    /// The page size less the bytes reserved at the end of each page.
+    usable_size: usize,
+}
+
+impl File {
+    fn new(bytes: Vec<u8>) -> Result<Self, String> {
+        if bytes.len() < HEADER_SIZE || &bytes[..16] != MAGIC {
+            return Err("not an SQLite database".to_owned());
+        }
+        let page_size = match u16::from_be_bytes([bytes[16], bytes[17]]) {
+            1 => 65536,
+            size => usize::from(size),
+        };
+        if page_size < 512 || !page_size.is_power_of_two() {
+            return Err(format!("invalid page size {}", page_size));
+        }
+        if bytes[18] == WAL_VERSION || bytes[19] == WAL_VERSION {
+            return Err(WAL_REFUSED.to_owned());
+        }
+        let encoding = u32::from_be_bytes([bytes[56], bytes[57], bytes[58], bytes[59]]);
+        if encoding > 1 {
+            return Err("text in UTF-16 isn't supported".to_owned());
+        }
+        Ok(File {
+            usable_size: page_size - usize::from(bytes[20]),
+            page_size,
+            bytes,
+        })
+    }
+
+    fn page(&self, number: u32) -> Result<&[u8], String> {
+        let start = (number as usize)
+            .checked_sub(1)
+            .map(|index| index * self.page_size)
+            .filter(|start| start + self.page_size <= self.bytes.len())
+            .ok_or_else(|| format!("page {} is past the end of the file", number))?;
+        Ok(&self.bytes[start..start + self.page_size])
+    }
+
+    /// The rowid and values of every row of the table b-tree at page `root`.
+    fn rows(&self, root: u32) -> Result<Vec<(i64, Vec<Value>)>, String> {
+        let mut rows = Vec::new();
+        let mut pending = vec![root];
+        let mut seen = HashSet::new();
+        while let Some(number) = pending.pop() {
+            if !seen.insert(number) {
+                return Err(format!("page {} is in the table twice", number));
+            }
+            let page = self.page(number)?;
+            // The first page starts with the file header.
+            let header = if number == 1 { HEADER_SIZE } else { 0 };
+            let kind = page[header];
+            let cell_count = usize::from(read_u16(page, header + 3)?);
+            let cells_at = header + if kind == INTERIOR_TABLE_PAGE { 12 } else { 8 };
+            match kind {
+                INTERIOR_TABLE_PAGE => {
+                    // Pushed so that the leftmost child is taken next, keeping
+                    // the rows in order.
+                    pending.push(read_u32(page, header + 8)?);
+                    for index in (0..cell_count).rev() {
+                        let cell = usize::from(read_u16(page, cells_at + 2 * index)?);
+                        pending.push(read_u32(page, cell)?);
+                    }
+                }
+                LEAF_TABLE_PAGE => {
+                    for index in 0..cell_count {
+                        let cell = usize::from(read_u16(page, cells_at + 2 * index)?);
+                        let (payload_size, used) = varint(page, cell)?;
+                        let (rowid, used_rowid) = varint(page, cell + used)?;
+                        let payload =
+                            self.payload(page, cell + used + used_rowid, payload_size as usize)?;
+                        rows.push((rowid as i64, record(&payload)?));
+                    }
+                }
+                kind => return Err(format!("page {} is not a table page ({:#x})", number, kind)),
+            }
+        }
+        Ok(rows)
+    }
+
+    /// The `size` bytes of a payload starting at `at` in `page`, with the
+    /// rest from its overflow pages if it doesn't fit.
+    fn payload(&self, page: &[u8], at: usize, size: usize) -> Result<Vec<u8>, String> {
+        let usable = self.usable_size;
+        let max_local = usable - 35;
+        let local = if size <= max_local {
+            size
+        } else {
+            let min_local = (usable - 12) * 32 / 255 - 23;
+            let local = min_local + (size - min_local) % (usable - 4);
+            if local <= max_local {
+                local
+            } else {
+                min_local
+            }
+        };
+        let mut payload = slice(page, at, local)?.to_vec();
+        let mut next = if local < size {
+            read_u32(page, at + local)?
+        } else {
+            0
+        };
+        let mut seen = HashSet::new();
+        while payload.len() < size {
+            if next == 0 || !seen.insert(next) {
+                return Err("a row's overflow pages end early".to_owned());
+            }
+            let overflow = self.page(next)?;
+            let take = (size - payload.len()).min(usable - 4);
+            payload.extend_from_slice(slice(overflow, 4, take)?);
+            next = read_u32(overflow, 0)?;
+        }
+        Ok(payload)
+    }
+}
+
+/// The values of a record.
+fn record(payload: &[u8]) -> Result<Vec<Value>, String> {
+    let (header_size, mut at) = varint(payload, 0)?;
+    let mut types = Vec::new();
+    while at < header_size as usize {
+        let (serial_type, used) = varint(payload, at)?;
+        types.push(serial_type);
+        at += used;
+    }
+    let mut body = header_size as usize;
+    let mut values = Vec::new();
+    for serial_type in types {
+        let integer = |size: usize| -> Result<i64, String> {
+            let bytes = slice(payload, body, size)?;
+            // Sign-extended from the first byte.
+            let first = i64::from(bytes[0] as i8);
+            Ok(bytes[1..]
+                .iter()
+                .fold(first, |value, byte| (value << 8) | i64::from(*byte)))
+        };
+        let (value, size) = match serial_type {
+            0 => (Value::Null, 0),
+            1..=4 => {
+                let size = serial_type as usize;
+                (Value::Integer(integer(size)?), size)
+            }
+            5 => (Value::Integer(integer(6)?), 6),
+            6 => (Value::Integer(integer(8)?), 8),
+            7 => {
+                let bits = integer(8)? as u64;
+                (Value::Real(f64::from_bits(bits)), 8)
+            }
+            8 => (Value::Integer(0), 0),
+            9 => (Value::Integer(1), 0),
+            10 | 11 => return Err(format!("unknown serial type {}", serial_type)),
+            _ => {
+                let size = ((serial_type - 12) / 2) as usize;
+                let bytes = slice(payload, body, size)?.to_vec();
+                if serial_type % 2 == 0 {
+                    (Value::Blob(bytes), size)
+                } else {
+                    let text = String::from_utf8(bytes)
+                        .map_err(|_| "a row has text that isn't UTF-8".to_owned())?;
+                    (Value::Text(text), size)
+                }
+            }
+        };
+        values.push(value);
+        body += size;
+    }
+    Ok(values)
+}
+
+/// The column names of a `CREATE TABLE` statement, and the index of the
+/// `INTEGER PRIMARY KEY` column if there is one, which SQLite stores as the
+/// rowid rather than in the record.
+fn columns(sql: &str) -> Result<(Vec<String>, Option<usize>), String> {
+    let unsupported = || format!("can't read the columns of `{}`", sql);
+    let open = sql.find('(').ok_or_else(unsupported)?;
+    let close = sql
+        .rfind(')')
+        .filter(|close| *close > open)
+        .ok_or_else(unsupported)?;
+    if sql[close + 1..].to_uppercase().contains("WITHOUT ROWID") {
+        return Err("tables WITHOUT ROWID aren't supported".to_owned());
+    }
+    let mut columns = Vec::new();
+    let mut rowid_column = None;
+    for definition in split_top_level(&sql[open + 1..close]) {
+        let (name, rest) = split_name(definition);
+        let first = name.to_uppercase();
+        let is_constraint = ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"]
+            .iter()
+            .any(|keyword| first == *keyword || first.starts_with(&format!("{}(", keyword)));
+        if name.is_empty() || is_constraint {
+            continue;
+        }
+        let rest: Vec<String> = rest
+            .split_whitespace()
+            .map(|word| word.to_uppercase())
+            .collect();
+        let rest = rest.join(" ");
+        if rest.starts_with("INTEGER") && rest.contains("PRIMARY KEY") {
+            rowid_column = Some(columns.len());
+        }
+        columns.push(unquote(name));
+    }
+    Ok((columns, rowid_column))
+}
+
+/// A column definition's name, quotes and all, and what follows it. A
+/// quoted name can have spaces in it.
+fn split_name(definition: &str) -> (&str, &str) {
+    let close = match definition.chars().next() {
+        Some('"') => Some('"'),
+        Some('`') => Some('`'),
+        Some('[') => Some(']'),
+        Some('\'') => Some('\''),
+        _ => None,
+    };
+    let end = match close {
+        Some(close) => definition[1..].find(close).map(|at| at + 2),
+        None => definition.find(char::is_whitespace),
+    };
+    definition.split_at(end.unwrap_or(definition.len()))
+}
+
+/// `text` split at the commas outside of parentheses and quotes.
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (at, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(text[start..at].trim());
+                start = at + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+    parts
+}
+
+fn unquote(name: &str) -> String {
+    let quoted = [('"', '"'), ('`', '`'), ('[', ']'), ('\'', '\'')]
+        .iter()
+        .find_map(|(open, close)| name.strip_prefix(*open)?.strip_suffix(*close));
+    quoted.unwrap_or(name).to_owned()
+}
+
+fn slice(bytes: &[u8], at: usize, size: usize) -> Result<&[u8], String> {
+    bytes
+        .get(at..at + size)
+        .ok_or_else(|| "the file is cut short or corrupt".to_owned())
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Result<u16, String> {
+    let bytes = slice(bytes, at, 2)?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, String> {
+    let bytes = slice(bytes, at, 4)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// The variable-length integer at `at` and the bytes it takes: seven bits
+/// from each byte with the high bit set, all eight of the ninth.
+fn varint(bytes: &[u8], at: usize) -> Result<(u64, usize), String> {
+    let mut value = 0u64;
+    for index in 0..9 {
+        let byte = *slice(bytes, at + index, 1)?.first().unwrap_or(&0);
+        if index == 8 {
+            return Ok(((value << 8) | u64::from(byte), 9));
+        }
+        value = (value << 7) | u64::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    unreachable!("a varint takes at most nine bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Made by `tests/fixtures/pets.sql`.
+    fn fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/pets.sqlite")
+    }
+
+    /// A copy of the fixture with `edit` made to its bytes.
+    fn broken_fixture(name: &str, edit: impl FnOnce(&mut Vec<u8>)) -> PathBuf {
+        let mut bytes = fs::read(fixture()).expect("the fixture");
+        edit(&mut bytes);
+        let path = std::env::temp_dir().join(format!(
+            "pet-cli-sqlite-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, bytes).expect("the copy is written");
+        path
+    }
+
+    fn text(text: &str) -> Value {
+        Value::Text(text.to_owned())
+    }
+
+    #[test]
+    fn reads_a_table_over_interior_pages_in_rowid_order() {
+        let root = File::new(fs::read(fixture()).unwrap()).unwrap();
+        assert_eq!(root.page(2).unwrap()[0], INTERIOR_TABLE_PAGE);
+        let table = read_table(&fixture(), "pets").unwrap();
+        assert_eq!(
+            table.columns,
+            ["id", "name", "pet category", "age_months", "notes"]
+        );
+        assert_eq!(table.rows.len(), 300);
+        for (index, row) in table.rows.iter().enumerate() {
+            assert_eq!(row[0], Value::Integer(index as i64 + 1));
+        }
+        assert_eq!(
+            table.rows[6],
+            [
+                Value::Integer(7),
+                text("pet 7"),
+                text("dogs"),
+                Value::Integer(7),
+                Value::Null
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_rows_over_overflow_pages() {
+        let table = read_table(&fixture(), "pets").unwrap();
+        let notes: String = (0..400).map(|i| format!("{:04}", i)).collect();
+        assert_eq!(table.rows[149][4], Value::Text(notes));
+        assert_eq!(table.rows[148][4], Value::Null);
+        assert_eq!(table.rows[150][1], text("pet 151"));
+    }
+
+    #[test]
+    fn reads_every_serial_type() {
+        let table = read_table(&fixture(), "KINDS").unwrap();
+        assert_eq!(table.columns, ["kind", "value"]);
+        let values: Vec<(String, Value)> = table
+            .rows
+            .into_iter()
+            .map(|row| (row[0].as_text().unwrap(), row[1].clone()))
+            .collect();
+        let expected = [
+            ("null", Value::Null),
+            ("zero", Value::Integer(0)),
+            ("one", Value::Integer(1)),
+            ("int8", Value::Integer(-100)),
+            ("int16", Value::Integer(1000)),
+            ("int24", Value::Integer(-1_000_000)),
+            ("int32", Value::Integer(2_000_000_000)),
+            ("int48", Value::Integer(100_000_000_000)),
+            ("int64", Value::Integer(-9_000_000_000_000_000_000)),
+            ("real", Value::Real(2.5)),
+            ("blob", Value::Blob(vec![0x00, 0xff, 0x10])),
+            ("text", text("héllo")),
+            ("empty text", text("")),
+            ("empty blob", Value::Blob(Vec::new())),
+        ];
+        let expected: Vec<(String, Value)> = expected
+            .iter()
+            .map(|(kind, value)| (kind.to_string(), value.clone()))
+            .collect();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn unknown_tables_list_the_ones_there_are() {
+        let err = read_table(&fixture(), "owners").unwrap_err();
+        assert_eq!(err, "no table owners, expected one of: pets, kinds");
+    }
+
+    #[test]
+    fn refuses_files_that_are_not_sqlite() {
+        let path = broken_fixture("not-sqlite", |bytes| bytes[0] = b's');
+        assert_eq!(
+            read_table(&path, "pets").unwrap_err(),
+            "not an SQLite database"
+        );
+        let _ = fs::remove_file(path);
+        let path = broken_fixture("utf16", |bytes| bytes[59] = 2);
+        assert!(read_table(&path, "pets").unwrap_err().contains("UTF-16"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn refuses_databases_in_wal_mode() {
+        let path = broken_fixture("wal-header", |bytes| {
+            bytes[18] = WAL_VERSION;
+            bytes[19] = WAL_VERSION;
+        });
+        assert_eq!(read_table(&path, "pets").unwrap_err(), WAL_REFUSED);
+        let _ = fs::remove_file(path);
+
+        let path = broken_fixture("wal-file", |_| {});
+        let mut wal = path.as_os_str().to_owned();
+        wal.push("-wal");
+        fs::write(&wal, "").unwrap();
+        assert!(read_table(&path, "pets").is_ok(), "an empty -wal is fine");
+        fs::write(&wal, [0x37, 0x7f, 0x06, 0x82]).unwrap();
+        let err = read_table(&path, "pets").unwrap_err();
+        assert!(err.contains("PRAGMA wal_checkpoint"), "{}", err);
+        let _ = fs::remove_file(wal);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn truncated_files_fail_without_panicking() {
+        let length = fs::read(fixture()).unwrap().len();
+        let path = broken_fixture("truncated", |_| {});
+        for cut in (0..length).step_by(97) {
+            fs::write(&path, &fs::read(fixture()).unwrap()[..cut]).unwrap();
+            let _ = read_table(&path, "kinds");
+            // The pets table takes up most of the first half of the file.
+            if cut < length / 2 {
+                assert!(read_table(&path, "pets").is_err(), "cut to {}", cut);
+            }
+        }
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn columns_of_create_table() {
+        let (names, rowid) = columns(
+            "CREATE TABLE t (\"a b\" TEXT, [c] INT, `d`, e integer primary key, \
+             f DECIMAL(10, 2), PRIMARY KEY (e), CONSTRAINT u UNIQUE (a, c), \
+             CHECK (c > 0), FOREIGN KEY (d) REFERENCES o (id))",
+        )
+        .unwrap();
+        assert_eq!(names, ["a b", "c", "d", "e", "f"]);
+        assert_eq!(rowid, Some(3));
+        // Only INTEGER makes the column the rowid; INT is a column of its own.
+        let (names, rowid) = columns("CREATE TABLE t (id INT PRIMARY KEY, name)").unwrap();
+        assert_eq!(names, ["id", "name"]);
+        assert_eq!(rowid, None);
+        assert!(columns("CREATE TABLE t (id) WITHOUT ROWID").is_err());
+        assert!(columns("CREATE TABLE t AS SELECT 1").is_err());
+    }
+
+    #[test]
+    fn varints() {
+        assert_eq!(varint(&[0x00], 0), Ok((0, 1)));
+        assert_eq!(varint(&[0x7f], 0), Ok((127, 1)));
+        assert_eq!(varint(&[0x81, 0x00], 0), Ok((128, 2)));
+        assert_eq!(varint(&[0xff, 0x7f], 0), Ok((16383, 2)));
+        assert_eq!(varint(&[0x00, 0x81, 0x80, 0x00], 1), Ok((16384, 3)));
+        // The ninth byte gives all eight of its bits.
+        assert_eq!(varint(&[0xff; 9], 0), Ok((u64::MAX, 9)));
+        assert_eq!(varint(&[0x80; 9], 0), Ok((0x80, 9)));
+        assert!(varint(&[0x81], 0).is_err());
+        assert!(varint(&[], 0).is_err());
+    }
+
+    #[test]
+    fn records_with_bad_serial_types_or_lengths_fail() {
+        // A header of two bytes with serial type 10, which is reserved.
+        assert!(record(&[2, 10]).is_err());
+        // Text of 5 bytes with only 2 there.
+        assert!(record(&[2, 23, b'h', b'i']).is_err());
+        assert_eq!(
+            record(&[3, 1, 8, 0xfe]),
+            Ok(vec![Value::Integer(-2), Value::Integer(0)])
+        );
+    }
+
+    #[test]
+    fn values_as_text() {
+        assert_eq!(Value::Null.as_text(), None);
+        assert_eq!(Value::Integer(-3).as_text().as_deref(), Some("-3"));
+        assert_eq!(Value::Real(1.5).as_text().as_deref(), Some("1.5"));
+        assert_eq!(Value::Blob(b"ok".to_vec()).as_text().as_deref(), Some("ok"));
+    }
+}
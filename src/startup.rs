@@ -0,0 +1,175 @@
+//! What the terminal UI checks before it takes over the screen, in the order
+//! the checks depend on each other. Startup notes every failure instead of
+//! stopping at the first, leaves out the checks that need something that
+//! already failed and, if any failure is fatal, prints them all numbered
+//! with a way to fix each and exits with the code of the first. The rest
+//! are shown on the status bar once the UI is up.
+
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Check {
+    Config,
+    DbPath,
+    DbFile,
+    Schema,
+    Lock,
+    Terminal,
+}
+
+impl Check {
+    pub const ALL: [Check; 6] = [
+        Check::Config,
+        Check::DbPath,
+        Check::DbFile,
+        Check::Schema,
+        Check::Lock,
+        Check::Terminal,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Check::Config => "config",
+            Check::DbPath => "DB path",
+            Check::DbFile => "DB file",
+            Check::Schema => "DB schema",
+            Check::Lock => "DB lock",
+            Check::Terminal => "terminal",
+        }
+    }
+
+    /// The checks that have to pass for this one to be made.
+    fn needs(self) -> &'static [Check] {
+        match self {
+            Check::DbPath => &[Check::Config],
+            Check::DbFile => &[Check::DbPath],
+            Check::Schema | Check::Lock => &[Check::DbFile],
+            Check::Config | Check::Terminal => &[],
+        }
+    }
+
+    /// What pet-cli exits with when this is the first check that failed.
+    /// The config, an unreadable DB and a missing terminal keep the codes
+    /// they have elsewhere.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Check::Config => 2,
+            Check::DbFile => 3,
+            Check::Terminal => 5,
+            Check::DbPath => 7,
+            Check::Schema => 8,
+            Check::Lock => 9,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Finding {
+    check: Check,
+    problem: String,
+    /// How to fix it, for the fatal ones.
+    fix: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    findings: Vec<Finding>,
+}
+
+impl Report {
+    /// Notes a failure that keeps the UI from starting.
+    pub fn fail(&mut self, check: Check, problem: impl Into<String>, fix: impl Into<String>) {
+        self.findings.push(Finding {
+            check,
+            problem: problem.into(),
+            fix: Some(fix.into()),
+        });
+    }
+
+    /// Notes a problem the UI starts with all the same.
+    pub fn warn(&mut self, check: Check, problem: impl Into<String>) {
+        self.findings.push(Finding {
+            check,
+            problem: problem.into(),
+            fix: None,
+        });
+    }
+
+    fn failed(&self, check: Check) -> bool {
+        self.findings
+            .iter()
+            .any(|finding| finding.check == check && finding.fix.is_some())
+    }
+
+    /// Whether `check` can be made: nothing it needs, directly or through
+    /// another check, failed.
+    pub fn can_check(&self, check: Check) -> bool {
+        check
+            .needs()
+            .iter()
+            .all(|need| !self.failed(*need) && self.can_check(*need))
+    }
+
+    pub fn is_fatal(&self) -> bool {
+        self.findings.iter().any(|finding| finding.fix.is_some())
+    }
+
+    /// The exit code of the first check in order that failed, if any did.
+    pub fn exit_code(&self) -> Option<i32> {
+        Check::ALL
+            .iter()
+            .find(|check| self.failed(**check))
+            .map(|check| check.exit_code())
+    }
+
+    /// The problems that aren't fatal, for the status bar.
+    pub fn warnings(&self) -> Vec<String> {
+        self.findings
+            .iter()
+            .filter(|finding| finding.fix.is_none())
+            .map(|finding| format!("{}: {}", finding.check.name(), finding.problem))
+            .collect()
+    }
+}
+
+impl fmt::Display for Report {
+    /// The failures in the order of the checks, numbered, each with its
+    /// fix, then the warnings and the checks that couldn't be made.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut lines = vec!["pet-cli could not start:".to_owned()];
+        let mut number = 0;
+        for check in Check::ALL {
+            for finding in self
+                .findings
+                .iter()
+                .filter(|finding| finding.check == check)
+            {
+                if let Some(fix) = &finding.fix {
+                    number += 1;
+                    lines.push(format!(
+                        "{:>3}. {}: {}",
+                        number,
+                        check.name(),
+                        finding.problem
+                    ));
+                    lines.push(format!("     fix: {}", fix));
+                }
+            }
+        }
+        for warning in self.warnings() {
+            lines.push(format!("warning: {}", warning));
+        }
+        let unchecked: Vec<_> = Check::ALL
+            .iter()
+            .filter(|check| !self.can_check(**check))
+            .map(|check| check.name())
+            .collect();
+        if !unchecked.is_empty() {
+            lines.push(format!(
+                "not checked until those are fixed: {}",
+                unchecked.join(", ")
+            ));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
@@ -0,0 +1,563 @@
+//! The Stats tab: how many pets there are of each category, by adoption
+//! status, and of each breed in the selected category, how old they are and
+//! how many were added each month. Pets in the Trash aren't counted.
+//! `pet-cli stats` prints the same.
+
+use crate::pet_status::PetStatus;
+use crate::Pet;
+use chrono::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum StatsSort {
+    /// The most common category first, ties by name.
+    #[default]
+    Count,
+    Name,
+}
+
+impl StatsSort {
+    pub fn next(self) -> StatsSort {
+        match self {
+            StatsSort::Count => StatsSort::Name,
+            StatsSort::Name => StatsSort::Count,
+        }
+    }
+}
+
+pub struct CategoryRow {
+    pub category: String,
+    /// In the order of `PetStatus::ALL`.
+    pub by_status: [usize; 3],
+}
+
+impl CategoryRow {
+    pub fn total(&self) -> usize {
+        self.by_status.iter().sum()
+    }
+}
+
+pub fn category_rows(pets: &[Pet], sort: StatsSort) -> Vec<CategoryRow> {
+    let mut rows: Vec<CategoryRow> = Vec::new();
+    for pet in pets.iter().filter(|pet| !pet.is_archived()) {
+        let index = match rows.iter().position(|row| row.category == pet.category) {
+            Some(index) => index,
+            None => {
+                rows.push(CategoryRow {
+                    category: pet.category.clone(),
+                    by_status: [0; 3],
+                });
+                rows.len() - 1
+            }
+        };
+        let status = PetStatus::ALL
+            .iter()
+            .position(|status| *status == pet.status)
+            .expect("every status is listed in PetStatus::ALL");
+        rows[index].by_status[status] += 1;
+    }
+    match sort {
+        StatsSort::Count => rows.sort_by(|a, b| {
+            b.total()
+                .cmp(&a.total())
+                .then_with(|| a.category.cmp(&b.category))
+        }),
+        StatsSort::Name => rows.sort_by(|a, b| a.category.cmp(&b.category)),
+    }
+    rows
+}
+
+/// How many pets of `category` there are of each breed, the most common
+/// first, ties by name. Pets without a breed count as `unknown`.
+pub fn breed_counts(pets: &[Pet], category: &str) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    let of_category = pets
+        .iter()
+        .filter(|pet| !pet.is_archived() && pet.category == category);
+    for pet in of_category {
+        let breed = pet.breed.as_deref().unwrap_or("unknown");
+        match counts.iter_mut().find(|(name, _)| name == breed) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((breed.to_owned(), 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// How many years of age each bar of the age histogram covers.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum AgeBucketWidth {
+    #[default]
+    One,
+    Two,
+    Five,
+}
+
+impl AgeBucketWidth {
+    pub const ALL: [AgeBucketWidth; 3] = [
+        AgeBucketWidth::One,
+        AgeBucketWidth::Two,
+        AgeBucketWidth::Five,
+    ];
+
+    pub fn years(self) -> usize {
+        match self {
+            AgeBucketWidth::One => 1,
+            AgeBucketWidth::Two => 2,
+            AgeBucketWidth::Five => 5,
+        }
+    }
+
+    pub fn next(self) -> AgeBucketWidth {
+        match self {
+            AgeBucketWidth::One => AgeBucketWidth::Two,
+            AgeBucketWidth::Two => AgeBucketWidth::Five,
+            AgeBucketWidth::Five => AgeBucketWidth::One,
+        }
+    }
+
+    /// Parses `1y`, `2y` or `5y`.
+    pub fn parse(value: &str) -> Result<AgeBucketWidth, String> {
+        AgeBucketWidth::ALL
+            .iter()
+            .copied()
+            .find(|width| width.to_string() == value)
+            .ok_or_else(|| "expected 1y, 2y or 5y".to_owned())
+    }
+}
+
+impl fmt::Display for AgeBucketWidth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}y", self.years())
+    }
+}
+
+/// The pets from `from_years` up to, but not including, `to_years` old.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AgeBucket {
+    pub from_years: usize,
+    pub to_years: usize,
+    pub count: usize,
+}
+
+impl AgeBucket {
+    /// `3y` for a year, `0-4y` for more.
+    pub fn label(&self) -> String {
+        if self.to_years - self.from_years == 1 {
+            format!("{}y", self.from_years)
+        } else {
+            format!("{}-{}y", self.from_years, self.to_years - 1)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AgeHistogram {
+    pub width_years: usize,
+    pub buckets: Vec<AgeBucket>,
+    /// The pets whose age isn't known, left out of the buckets.
+    pub unknown: usize,
+}
+
+/// Counts `ages`, in months and `None` where unknown, into buckets of
+/// `width`. The buckets start at 0 and go on up to the oldest pet's, empty
+/// ones included, so that the bars are evenly spaced; without any known age
+/// there are none.
+pub fn age_histogram(ages: &[Option<usize>], width: AgeBucketWidth) -> AgeHistogram {
+    let years = width.years();
+    let known: Vec<usize> = ages.iter().flatten().map(|months| months / 12).collect();
+    let bucket_count = known.iter().max().map_or(0, |oldest| oldest / years + 1);
+    let mut buckets: Vec<AgeBucket> = (0..bucket_count)
+        .map(|index| AgeBucket {
+            from_years: index * years,
+            to_years: (index + 1) * years,
+            count: 0,
+        })
+        .collect();
+    for age in &known {
+        buckets[age / years].count += 1;
+    }
+    AgeHistogram {
+        width_years: years,
+        buckets,
+        unknown: ages.len() - known.len(),
+    }
+}
+
+/// The ages of the pets outside the Trash, in months, on `today`.
+pub fn pet_ages(pets: &[Pet], today: NaiveDate) -> Vec<Option<usize>> {
+    pets.iter()
+        .filter(|pet| !pet.is_archived())
+        .map(|pet| pet.age_months(today))
+        .collect()
+}
+
+/// The mean of the known `ages`, in months to one decimal.
+fn average(ages: &[Option<usize>]) -> Option<f64> {
+    let known: Vec<usize> = ages.iter().flatten().copied().collect();
+    if known.is_empty() {
+        return None;
+    }
+    let mean = known.iter().sum::<usize>() as f64 / known.len() as f64;
+    Some((mean * 10.0).round() / 10.0)
+}
+
+/// The months the Stats tab shows at first and `pet-cli stats` prints.
+pub const GROWTH_MONTHS: usize = 24;
+/// How far the Stats tab's window of months widens and narrows in a step,
+/// and its limits.
+pub const GROWTH_STEP: usize = 12;
+pub const MAX_GROWTH_MONTHS: usize = 120;
+
+/// The pets added in a month, by `created_at` in UTC.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MonthCount {
+    pub year: i32,
+    /// From 1.
+    pub month: u32,
+    pub added: usize,
+    /// The pets added up to the end of the month, from before the first month
+    /// shown too.
+    pub total: usize,
+}
+
+impl MonthCount {
+    /// `Mar` for most months, `'25` for January, so the years can be told
+    /// apart on a chart.
+    pub fn label(&self) -> String {
+        if self.month == 1 {
+            format!("'{:02}", self.year % 100)
+        } else {
+            NaiveDate::from_ymd_opt(self.year, self.month, 1)
+                .expect("months are valid")
+                .format("%b")
+                .to_string()
+        }
+    }
+}
+
+/// The pets added in each of the `months` months up to and including the
+/// one of `now`, oldest first. Months without any are kept, so that there is
+/// one per month; pets added after `now` aren't counted.
+pub fn added_per_month(pets: &[Pet], now: DateTime<Utc>, months: usize) -> Vec<MonthCount> {
+    let index = |at: DateTime<Utc>| at.year() * 12 + at.month0() as i32;
+    let last = index(now);
+    let first = last - months as i32 + 1;
+    let mut added = vec![0; months];
+    let mut before = 0;
+    for pet in pets.iter().filter(|pet| !pet.is_archived()) {
+        let month = index(pet.created_at);
+        if month < first {
+            before += 1;
+        } else if month <= last {
+            added[(month - first) as usize] += 1;
+        }
+    }
+    let mut total = before;
+    added
+        .into_iter()
+        .enumerate()
+        .map(|(offset, added)| {
+            let month = first + offset as i32;
+            total += added;
+            MonthCount {
+                year: month.div_euclid(12),
+                month: month.rem_euclid(12) as u32 + 1,
+                added,
+                total,
+            }
+        })
+        .collect()
+}
+
+/// What `pet-cli stats --json` prints.
+#[derive(Serialize)]
+pub struct Summary {
+    pub total: usize,
+    pub average_age_months: Option<f64>,
+    pub categories: Vec<CategorySummary>,
+    pub ages: AgeHistogram,
+    /// The last `GROWTH_MONTHS` months.
+    pub added_per_month: Vec<MonthCount>,
+}
+
+#[derive(Serialize)]
+pub struct CategorySummary {
+    pub category: String,
+    pub total: usize,
+    /// By status name.
+    pub by_status: BTreeMap<String, usize>,
+    pub average_age_months: Option<f64>,
+}
+
+pub fn summary(pets: &[Pet], now: DateTime<Utc>, width: AgeBucketWidth) -> Summary {
+    let today = now.with_timezone(&Local).date_naive();
+    let ages = pet_ages(pets, today);
+    let categories = category_rows(pets, StatsSort::Count)
+        .into_iter()
+        .map(|row| {
+            let of_category: Vec<Option<usize>> = pets
+                .iter()
+                .filter(|pet| !pet.is_archived() && pet.category == row.category)
+                .map(|pet| pet.age_months(today))
+                .collect();
+            CategorySummary {
+                total: row.total(),
+                by_status: PetStatus::ALL
+                    .iter()
+                    .zip(row.by_status)
+                    .map(|(status, count)| (status.to_string(), count))
+                    .collect(),
+                average_age_months: average(&of_category),
+                category: row.category,
+            }
+        })
+        .collect();
+    Summary {
+        total: ages.len(),
+        average_age_months: average(&ages),
+        categories,
+        ages: age_histogram(&ages, width),
+        added_per_month: added_per_month(pets, now, GROWTH_MONTHS),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+
+    fn with_breed(id: usize, category: &str, breed: Option<&str>) -> Pet {
+        let mut pet = pet(id, &format!("Pet{}", id), category);
+        pet.breed = breed.map(str::to_owned);
+        pet
+    }
+
+    #[test]
+    fn breeds_of_the_category_are_counted_most_common_first() {
+        let mut pets = vec![
+            with_breed(1, "dogs", Some("Whippet")),
+            with_breed(2, "dogs", Some("Beagle")),
+            with_breed(3, "dogs", None),
+            with_breed(4, "dogs", Some("Whippet")),
+            with_breed(5, "cats", Some("Persian")),
+            with_breed(6, "dogs", Some("Boxer")),
+            with_breed(7, "dogs", Some("Whippet")),
+        ];
+        pets[6].archived_at = Some(Utc::now());
+        let count = |breed: &str, count| (breed.to_owned(), count);
+        assert_eq!(
+            breed_counts(&pets, "dogs"),
+            [
+                count("Whippet", 2),
+                count("Beagle", 1),
+                count("Boxer", 1),
+                count("unknown", 1)
+            ]
+        );
+        assert_eq!(breed_counts(&pets, "cats"), [count("Persian", 1)]);
+        assert!(breed_counts(&pets, "rabbits").is_empty());
+    }
+
+    fn aged(id: usize, category: &str, months: usize) -> Pet {
+        let mut pet = pet(id, &format!("Pet{}", id), category);
+        pet.age.months = months;
+        pet
+    }
+
+    fn counts(histogram: &AgeHistogram) -> Vec<(String, usize)> {
+        histogram
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.label(), bucket.count))
+            .collect()
+    }
+
+    #[test]
+    fn no_ages_make_no_buckets() {
+        for width in AgeBucketWidth::ALL {
+            let histogram = age_histogram(&[], width);
+            assert!(histogram.buckets.is_empty());
+            assert_eq!(histogram.unknown, 0);
+            assert_eq!(histogram.width_years, width.years());
+        }
+        let unknown = age_histogram(&[None, None], AgeBucketWidth::One);
+        assert!(unknown.buckets.is_empty());
+        assert_eq!(unknown.unknown, 2);
+    }
+
+    #[test]
+    fn a_single_age_fills_the_buckets_up_to_its_own() {
+        let histogram = age_histogram(&[Some(30)], AgeBucketWidth::One);
+        assert_eq!(
+            counts(&histogram),
+            [
+                ("0y".to_owned(), 0),
+                ("1y".to_owned(), 0),
+                ("2y".to_owned(), 1)
+            ]
+        );
+        let histogram = age_histogram(&[Some(0)], AgeBucketWidth::Five);
+        assert_eq!(counts(&histogram), [("0-4y".to_owned(), 1)]);
+    }
+
+    #[test]
+    fn ages_are_counted_into_buckets_of_the_width() {
+        let ages = [Some(11), Some(12), Some(47), None, Some(60), Some(119)];
+        let histogram = age_histogram(&ages, AgeBucketWidth::Two);
+        assert_eq!(
+            counts(&histogram),
+            [
+                ("0-1y".to_owned(), 2),
+                ("2-3y".to_owned(), 1),
+                ("4-5y".to_owned(), 1),
+                ("6-7y".to_owned(), 0),
+                ("8-9y".to_owned(), 1)
+            ]
+        );
+        assert_eq!(histogram.unknown, 1);
+        let histogram = age_histogram(&ages, AgeBucketWidth::Five);
+        assert_eq!(
+            counts(&histogram),
+            [("0-4y".to_owned(), 3), ("5-9y".to_owned(), 2)]
+        );
+    }
+
+    #[test]
+    fn bucket_widths_cycle_and_parse() {
+        assert_eq!(AgeBucketWidth::default().next(), AgeBucketWidth::Two);
+        assert_eq!(AgeBucketWidth::Five.next(), AgeBucketWidth::One);
+        for width in AgeBucketWidth::ALL {
+            assert_eq!(AgeBucketWidth::parse(&width.to_string()), Ok(width));
+        }
+        assert_eq!(
+            AgeBucketWidth::parse("3y"),
+            Err("expected 1y, 2y or 5y".to_owned())
+        );
+    }
+
+    #[test]
+    fn a_birthdate_in_the_future_is_an_unknown_age() {
+        let today = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let mut pets = vec![aged(1, "dogs", 40), aged(2, "dogs", 5), aged(3, "cats", 7)];
+        pets[1].birthdate = NaiveDate::from_ymd_opt(2024, 6, 1);
+        pets[2].birthdate = NaiveDate::from_ymd_opt(2026, 1, 1);
+        assert_eq!(pet_ages(&pets, today), [Some(40), Some(12), None]);
+        pets[0].archived_at = Some(Utc::now());
+        assert_eq!(pet_ages(&pets, today), [Some(12), None]);
+    }
+
+    #[test]
+    fn animals_days_or_weeks_old_are_in_the_first_bucket() {
+        let today = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let mut pets = vec![aged(1, "cats", 0), aged(2, "cats", 0), aged(3, "dogs", 30)];
+        pets[0].birthdate = NaiveDate::from_ymd_opt(2025, 5, 20);
+        pets[1].birthdate = NaiveDate::from_ymd_opt(2025, 3, 25);
+        let ages = pet_ages(&pets, today);
+        assert_eq!(ages, [Some(0), Some(2), Some(30)]);
+        let histogram = age_histogram(&ages, AgeBucketWidth::One);
+        assert_eq!(
+            counts(&histogram),
+            [
+                ("0y".to_owned(), 2),
+                ("1y".to_owned(), 0),
+                ("2y".to_owned(), 1)
+            ]
+        );
+    }
+
+    fn created(id: usize, at: &str) -> Pet {
+        let mut pet = pet(id, &format!("Pet{}", id), "dogs");
+        pet.created_at = at.parse().unwrap();
+        pet
+    }
+
+    fn months_of(counts: &[MonthCount]) -> Vec<(i32, u32, usize, usize)> {
+        counts
+            .iter()
+            .map(|month| (month.year, month.month, month.added, month.total))
+            .collect()
+    }
+
+    #[test]
+    fn an_empty_db_has_a_bar_for_every_month() {
+        let now = Utc.with_ymd_and_hms(2025, 2, 10, 0, 0, 0).unwrap();
+        let months = added_per_month(&[], now, 4);
+        assert_eq!(
+            months_of(&months),
+            [
+                (2024, 11, 0, 0),
+                (2024, 12, 0, 0),
+                (2025, 1, 0, 0),
+                (2025, 2, 0, 0)
+            ]
+        );
+        assert!(added_per_month(&[], now, 0).is_empty());
+    }
+
+    #[test]
+    fn months_are_counted_across_the_new_year() {
+        let pets = vec![
+            created(1, "2024-06-30T12:00:00Z"),
+            created(2, "2024-12-31T23:59:59Z"),
+            created(3, "2025-01-01T00:00:00Z"),
+            created(4, "2025-01-20T08:00:00Z"),
+            created(5, "2025-02-28T00:00:00Z"),
+            created(6, "2025-03-01T00:00:00Z"),
+            created(7, "2025-01-05T00:00:00Z"),
+        ];
+        let mut pets = pets;
+        pets[6].archived_at = Some(Utc::now());
+        let now = Utc.with_ymd_and_hms(2025, 2, 10, 0, 0, 0).unwrap();
+        assert_eq!(
+            months_of(&added_per_month(&pets, now, 3)),
+            [(2024, 12, 1, 2), (2025, 1, 2, 4), (2025, 2, 1, 5)]
+        );
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            months_of(&added_per_month(&pets, now, 2)),
+            [(2024, 12, 1, 2), (2025, 1, 2, 4)]
+        );
+    }
+
+    #[test]
+    fn months_are_labeled_with_the_year_in_january() {
+        let month = |year, month| MonthCount {
+            year,
+            month,
+            added: 0,
+            total: 0,
+        };
+        assert_eq!(month(2025, 1).label(), "'25");
+        assert_eq!(month(2009, 1).label(), "'09");
+        assert_eq!(month(2024, 12).label(), "Dec");
+        assert_eq!(month(2024, 3).label(), "Mar");
+    }
+
+    #[test]
+    fn the_summary_has_the_aggregates_as_json() {
+        let pets = vec![
+            aged(1, "dogs", 12),
+            aged(2, "dogs", 25),
+            aged(3, "cats", 70),
+        ];
+        let now = Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap();
+        let summary = summary(&pets, now, AgeBucketWidth::Five);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["total"], 3);
+        assert_eq!(json["average_age_months"], 35.7);
+        assert_eq!(json["categories"][0]["category"], "dogs");
+        assert_eq!(json["categories"][0]["total"], 2);
+        assert_eq!(json["categories"][0]["by_status"]["available"], 2);
+        assert_eq!(json["categories"][0]["average_age_months"], 18.5);
+        assert_eq!(json["ages"]["width_years"], 5);
+        assert_eq!(json["ages"]["buckets"][1]["count"], 1);
+        assert_eq!(json["ages"]["unknown"], 0);
+        let months = json["added_per_month"].as_array().unwrap();
+        assert_eq!(months.len(), GROWTH_MONTHS);
+        assert_eq!(months[GROWTH_MONTHS - 1]["total"], 3);
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+const MESSAGE_LIFETIME: Duration = Duration::from_secs(3);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StatusLevel {
+    Info,
+    Error,
+}
+
+struct StatusMessage {
+    text: String,
+    level: StatusLevel,
+    posted_at: Instant,
+}
+
+/// The last action message shown in the status bar. Messages disappear once
+/// they are older than `MESSAGE_LIFETIME`, which is checked on every tick.
+#[derive(Default)]
+pub struct StatusLine {
+    message: Option<StatusMessage>,
+    /// How many errors were shown so far, to tell whether a step of a macro
+    /// failed.
+    errors: usize,
+}
+
+impl StatusLine {
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(text.into(), StatusLevel::Info);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.errors += 1;
+        self.push(text.into(), StatusLevel::Error);
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.errors
+    }
+
+    fn push(&mut self, text: String, level: StatusLevel) {
+        self.message = Some(StatusMessage {
+            text,
+            level,
+            posted_at: Instant::now(),
+        });
+    }
+
+    pub fn expire(&mut self, now: Instant) {
+        if let Some(message) = &self.message {
+            if now.duration_since(message.posted_at) >= MESSAGE_LIFETIME {
+                self.message = None;
+            }
+        }
+    }
+
+    /// The message, which is then gone; for `--plain`, which prints each
+    /// message once.
+    pub fn take(&mut self) -> Option<(String, StatusLevel)> {
+        self.message
+            .take()
+            .map(|message| (message.text, message.level))
+    }
+
+    pub fn message(&self) -> Option<(&str, StatusLevel)> {
+        self.message
+            .as_ref()
+            .map(|message| (message.text.as_str(), message.level))
+    }
+}
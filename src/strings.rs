@@ -0,0 +1,259 @@
+//! The UI's fixed text in each language it speaks. Text is looked up by
+//! [`Msg`]; a language that lacks a message shows the English one, so a
+//! partial translation never leaves a blank.
+
+use clap::ValueEnum;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    De,
+}
+
+impl Lang {
+    /// The language of a locale such as `de_DE.UTF-8`, if the UI speaks it.
+    pub fn from_locale(locale: &str) -> Option<Lang> {
+        let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+        match language {
+            "en" | "C" | "POSIX" => Some(Lang::En),
+            "de" => Some(Lang::De),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Msg {
+    MenuTitle,
+    Home,
+    Pets,
+    Owners,
+    Duplicates,
+    Trash,
+    Log,
+    StatsByCount,
+    StatsByName,
+    ColumnCategory,
+    ColumnTotal,
+    Add,
+    Delete,
+    MergeDb,
+    Quit,
+    DuplicatesHint,
+    TrashHint,
+    Activity,
+    Detail,
+    OpenPet,
+    PickPet,
+    Columns,
+    Categories,
+    MergeConflict,
+    Confirm,
+    Command,
+    Integrity,
+    Backups,
+    RestoreDiff,
+    DryRunSummary,
+    ChangedOnDisk,
+    Messages,
+    Workspaces,
+    SavedViews,
+    YankRing,
+    AgeHistogram,
+    GrowthAdded,
+    GrowthTotal,
+    Copyright,
+    WelcomeTo,
+    PetCount,
+    Stats,
+    RecentlyAdded,
+    Reminders,
+    Keys,
+    QuickActions,
+    PressAnyKey,
+    FieldId,
+    FieldName,
+    FieldCategory,
+    FieldBreed,
+    FieldAge,
+    FieldBirthdate,
+    FieldOwner,
+    FieldCreated,
+    FieldRecords,
+    UnknownOwner,
+    Records,
+    RecordsEmpty,
+    ColumnDate,
+    ColumnKind,
+    ColumnNote,
+    Notes,
+    NotesEditing,
+    NoNotes,
+    FieldWeight,
+    FieldMicrochip,
+    FieldStatus,
+    FieldPhoto,
+    Weight,
+    NoWeights,
+}
+
+pub fn text(lang: Lang, msg: Msg) -> &'static str {
+    let translated = match lang {
+        Lang::En => None,
+        Lang::De => german(msg),
+    };
+    translated.unwrap_or_else(|| english(msg))
+}
+
+fn english(msg: Msg) -> &'static str {
+    match msg {
+        Msg::MenuTitle => "Menu",
+        Msg::Home => "Home",
+        Msg::Pets => "Pets",
+        Msg::Owners => "Owners",
+        Msg::Duplicates => "Duplicates",
+        Msg::Trash => "Trash",
+        Msg::Log => "Log",
+        Msg::StatsByCount => "Categories by count (s sorts, Enter shows the pets)",
+        Msg::StatsByName => "Categories by name (s sorts, Enter shows the pets)",
+        Msg::ColumnCategory => "Category",
+        Msg::ColumnTotal => "Total",
+        Msg::Add => "Add",
+        Msg::Delete => "Delete",
+        Msg::MergeDb => "Merge",
+        Msg::Quit => "Quit",
+        Msg::DuplicatesHint => "Duplicates (M merges the group into the selected pet)",
+        Msg::TrashHint => "Trash (r restores, D deletes permanently)",
+        Msg::Activity => "Activity",
+        Msg::Detail => "Detail",
+        Msg::OpenPet => "Open pet",
+        Msg::PickPet => "Pick a pet (Enter prints it, Esc cancels)",
+        Msg::Columns => "Columns",
+        Msg::Categories => "Categories (Space marks, r renames, m merges)",
+        Msg::MergeConflict => "Conflict",
+        Msg::Confirm => "Confirm",
+        Msg::Command => "Command (Tab completes, Up and Down for history)",
+        Msg::Integrity => "DB check (any key closes)",
+        Msg::Backups => "Backups (Enter restores)",
+        Msg::RestoreDiff => "Restoring would make",
+        Msg::DryRunSummary => "The dry run made",
+        Msg::ChangedOnDisk => "The DB changed on disk meanwhile",
+        Msg::Messages => "Messages, newest first (q closes)",
+        Msg::Workspaces => "Workspaces (Enter switches)",
+        Msg::SavedViews => "Views (Enter shows, r renames, d deletes)",
+        Msg::YankRing => "Yanked pets (Enter adds a copy)",
+        Msg::AgeHistogram => "Ages",
+        Msg::GrowthAdded => "Pets added per month",
+        Msg::GrowthTotal => "Pets in total",
+        Msg::Copyright => "Copyright",
+        Msg::WelcomeTo => "Welcome to ",
+        Msg::PetCount => " pets",
+        Msg::Stats => "Stats",
+        Msg::RecentlyAdded => "Recently added",
+        Msg::Reminders => "Reminders (Enter shows the pet)",
+        Msg::Keys => "Keys",
+        Msg::QuickActions => "Quick actions",
+        Msg::PressAnyKey => "press any key",
+        Msg::FieldId => "ID",
+        Msg::FieldName => "Name",
+        Msg::FieldCategory => "Category",
+        Msg::FieldBreed => "Breed",
+        Msg::FieldAge => "Age",
+        Msg::FieldBirthdate => "Birthdate",
+        Msg::FieldOwner => "Owner",
+        Msg::FieldCreated => "Created",
+        Msg::FieldRecords => "Records",
+        Msg::UnknownOwner => "unknown",
+        Msg::Records => "Medical records",
+        Msg::RecordsEmpty => "Medical records (press 'm' to add one)",
+        Msg::ColumnDate => "Date",
+        Msg::ColumnKind => "Kind",
+        Msg::ColumnNote => "Note",
+        Msg::Notes => "Notes",
+        Msg::NotesEditing => "Notes (editing, Esc to save)",
+        Msg::NoNotes => "no notes, press 'n' to add some",
+        Msg::FieldWeight => "Weight",
+        Msg::FieldMicrochip => "Microchip",
+        Msg::FieldStatus => "Status",
+        Msg::FieldPhoto => "Photo",
+        Msg::Weight => "Weight",
+        Msg::NoWeights => "Weight (press 'w' to add one)",
+    }
+}
+
+fn german(msg: Msg) -> Option<&'static str> {
+    Some(match msg {
+        Msg::MenuTitle => "Menü",
+        Msg::Home => "Start",
+        Msg::Pets => "Haustiere",
+        Msg::Owners => "Besitzer",
+        Msg::Duplicates => "Duplikate",
+        Msg::Trash => "Papierkorb",
+        Msg::Log => "Protokoll",
+        Msg::StatsByCount => "Kategorien nach Anzahl (s sortiert, Enter zeigt die Tiere)",
+        Msg::StatsByName => "Kategorien nach Name (s sortiert, Enter zeigt die Tiere)",
+        Msg::ColumnCategory => "Kategorie",
+        Msg::ColumnTotal => "Gesamt",
+        Msg::Add => "Neu",
+        Msg::Delete => "Löschen",
+        Msg::MergeDb => "Zusammenführen",
+        Msg::Quit => "Beenden",
+        Msg::DuplicatesHint => "Duplikate (M führt die Gruppe im gewählten Haustier zusammen)",
+        Msg::TrashHint => "Papierkorb (r stellt wieder her, D löscht endgültig)",
+        Msg::Activity => "Aktivität",
+        Msg::Detail => "Details",
+        Msg::OpenPet => "Haustier öffnen",
+        Msg::PickPet => "Haustier wählen (Enter gibt es aus, Esc bricht ab)",
+        Msg::Columns => "Spalten",
+        Msg::Categories => "Kategorien (Leertaste markiert, r benennt um, m führt zusammen)",
+        Msg::MergeConflict => "Konflikt",
+        Msg::Confirm => "Bestätigen",
+        Msg::Command => "Befehl (Tab ergänzt, Pfeiltasten für frühere)",
+        Msg::Integrity => "DB-Prüfung (beliebige Taste schließt)",
+        Msg::Backups => "Sicherungen (Enter: zurück)",
+        Msg::RestoreDiff => "Wiederherstellen ergäbe",
+        Msg::DryRunSummary => "Der Probelauf ergab",
+        Msg::ChangedOnDisk => "Die DB wurde inzwischen geändert",
+        Msg::Messages => "Meldungen, neueste zuerst (q schließt)",
+        Msg::Workspaces => "Arbeitsbereiche (Enter wechselt)",
+        Msg::SavedViews => "Ansichten (Enter zeigt, r benennt um, d löscht)",
+        Msg::YankRing => "Kopierte Haustiere (Enter fügt eine Kopie hinzu)",
+        Msg::AgeHistogram => "Alter",
+        Msg::GrowthAdded => "Neue Haustiere pro Monat",
+        Msg::GrowthTotal => "Haustiere insgesamt",
+        Msg::Copyright => "Copyright",
+        Msg::WelcomeTo => "Willkommen bei ",
+        Msg::PetCount => " Haustiere",
+        Msg::Stats => "Statistik",
+        Msg::RecentlyAdded => "Zuletzt hinzugefügt",
+        Msg::Reminders => "Erinnerungen (Enter zeigt das Tier)",
+        Msg::Keys => "Tasten",
+        Msg::QuickActions => "Schnellaktionen",
+        Msg::PressAnyKey => "beliebige Taste drücken",
+        Msg::FieldId => "ID",
+        Msg::FieldName => "Name",
+        Msg::FieldCategory => "Kategorie",
+        Msg::FieldBreed => "Rasse",
+        Msg::FieldAge => "Alter",
+        Msg::FieldBirthdate => "Geburtstag",
+        Msg::FieldOwner => "Besitzer",
+        Msg::FieldCreated => "Angelegt",
+        Msg::FieldRecords => "Befunde",
+        Msg::UnknownOwner => "unbekannt",
+        Msg::Records => "Befunde",
+        Msg::RecordsEmpty => "Befunde ('m' fügt einen hinzu)",
+        Msg::ColumnDate => "Datum",
+        Msg::ColumnKind => "Art",
+        Msg::ColumnNote => "Notiz",
+        Msg::Notes => "Notizen",
+        Msg::NotesEditing => "Notizen (Bearbeiten, Esc speichert)",
+        Msg::NoNotes => "keine Notizen, 'n' fügt welche hinzu",
+        Msg::FieldWeight => "Gewicht",
+        Msg::FieldMicrochip => "Chipnummer",
+        Msg::FieldStatus => "Status",
+        Msg::FieldPhoto => "Foto",
+        Msg::Weight => "Gewicht",
+        Msg::NoWeights => "Gewicht ('w' fügt eines hinzu)",
+    })
+}
@@ -0,0 +1,129 @@
+//! The selection of each tab that is a plain list: Owners, Duplicates, Trash
+//! and Log. Every tab keeps its own while the others are shown, and the
+//! state file keeps them between runs. The Pets tab's selection follows its
+//! pet by itself; see `pet_selection`.
+
+use crate::MenuItem;
+use serde::{Deserialize, Serialize};
+use tui::widgets::ListState;
+
+/// The tabs with a [`TabView`], in menu order.
+pub const TABS: [MenuItem; 4] = [
+    MenuItem::Owners,
+    MenuItem::Duplicates,
+    MenuItem::Trash,
+    MenuItem::Log,
+];
+
+#[derive(Default)]
+pub struct TabView {
+    pub list: ListState,
+    /// The row selected and the id of the entry on it when the tab was last
+    /// drawn.
+    shown: Option<(usize, usize)>,
+}
+
+impl TabView {
+    fn at(row: Option<usize>) -> TabView {
+        let mut list = ListState::default();
+        list.select(row);
+        TabView { list, shown: None }
+    }
+
+    /// Brings the selection up to date with the tab's entries, given by id
+    /// in the order shown. A selection that wasn't moved since the tab was
+    /// last drawn stays on its entry wherever that went; one past the end
+    /// goes to the last entry. This runs whenever the tab is drawn, so
+    /// changes made while another tab was shown are caught up with then.
+    pub fn reconcile(&mut self, ids: &[usize]) {
+        let Some(selected) = self.list.selected() else {
+            return;
+        };
+        let selected = match self.shown {
+            Some((row, id)) if row == selected => ids
+                .iter()
+                .position(|known| *known == id)
+                .unwrap_or(selected),
+            _ => selected,
+        };
+        let selected = selected.min(ids.len().saturating_sub(1));
+        self.list.select(Some(selected));
+        self.shown = ids.get(selected).map(|id| (selected, *id));
+    }
+}
+
+/// A tab's selection in the state file.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StoredSelection {
+    pub tab: MenuItem,
+    pub row: usize,
+    /// The entry on the row, followed if it moved meanwhile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<usize>,
+}
+
+pub struct TabViews {
+    views: Vec<(MenuItem, TabView)>,
+}
+
+impl Default for TabViews {
+    /// The top of every list, except the Log, which starts with nothing
+    /// selected.
+    fn default() -> TabViews {
+        let views = TABS
+            .iter()
+            .map(|tab| {
+                let row = (*tab != MenuItem::Log).then_some(0);
+                (*tab, TabView::at(row))
+            })
+            .collect();
+        TabViews { views }
+    }
+}
+
+impl TabViews {
+    /// The view of `tab`, which must be one of [`TABS`].
+    pub fn view(&mut self, tab: MenuItem) -> &mut TabView {
+        self.views
+            .iter_mut()
+            .find(|(known, _)| *known == tab)
+            .map(|(_, view)| view)
+            .expect("only tabs with a view are asked for")
+    }
+
+    pub fn list(&mut self, tab: MenuItem) -> &mut ListState {
+        &mut self.view(tab).list
+    }
+
+    /// Back to the top of the list of `tab`, as for another DB.
+    pub fn reset(&mut self, tab: MenuItem) {
+        *self.view(tab) = TabView::at(Some(0));
+    }
+
+    /// The selections worth keeping: the Log starts empty every session.
+    pub fn store(&self) -> Vec<StoredSelection> {
+        self.views
+            .iter()
+            .filter(|(tab, _)| *tab != MenuItem::Log)
+            .filter_map(|(tab, view)| {
+                let row = view.list.selected()?;
+                let id = view
+                    .shown
+                    .filter(|(shown, _)| *shown == row)
+                    .map(|(_, id)| id);
+                Some(StoredSelection { tab: *tab, row, id })
+            })
+            .collect()
+    }
+
+    /// Takes the selections of a state file, to be reconciled with the DB
+    /// when each tab is first drawn. Tabs without a view are left out, as a
+    /// state file may have been edited.
+    pub fn restore(&mut self, stored: &[StoredSelection]) {
+        for selection in stored.iter().filter(|stored| TABS.contains(&stored.tab)) {
+            let view = self.view(selection.tab);
+            view.list.select(Some(selection.row));
+            view.shown = selection.id.map(|id| (selection.row, id));
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! `pet-cli import`: pets from the rows of another program's table, like the
+//! `animals` table of an SQLite file, with a mapping of its columns to pet
+//! fields such as `name=name,species=category,dob=birthdate`. Columns left
+//! out of the mapping are ignored.
+
+use crate::dates::{self, DateOrder};
+use crate::quick_add::NewPet;
+use crate::sqlite::Table;
+use crate::Pet;
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::ValueEnum;
+
+/// Where `pet-cli import` reads from.
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum ImportFrom {
+    /// A table of an SQLite database file.
+    Sqlite,
+}
+
+/// The pet fields a column can be mapped to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Target {
+    Name,
+    Category,
+    Birthdate,
+    Notes,
+    Breed,
+    Microchip,
+}
+
+const TARGETS: [(&str, Target); 6] = [
+    ("name", Target::Name),
+    ("category", Target::Category),
+    ("birthdate", Target::Birthdate),
+    ("notes", Target::Notes),
+    ("breed", Target::Breed),
+    ("microchip", Target::Microchip),
+];
+
+/// Reads a mapping like `name=name,species=category` into the column and the
+/// field of each entry. Every field is mapped at most once, and the name and
+/// category always are.
+pub fn parse_mapping(text: &str) -> Result<Vec<(String, Target)>, String> {
+    let mut mapping: Vec<(String, Target)> = Vec::new();
+    for entry in text
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (column, field) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("expected column=field, got '{}'", entry))?;
+        let (column, field) = (column.trim(), field.trim());
+        let target = TARGETS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(field))
+            .map(|(_, target)| *target)
+            .ok_or_else(|| {
+                let names: Vec<&str> = TARGETS.iter().map(|(name, _)| *name).collect();
+                format!(
+                    "unknown field '{}', expected one of: {}",
+                    field,
+                    names.join(", ")
+                )
+            })?;
+        if mapping.iter().any(|(_, mapped)| *mapped == target) {
+            return Err(format!("{} is mapped twice", field));
+        }
+        mapping.push((column.to_owned(), target));
+    }
+    for (name, target) in &TARGETS[..2] {
+        if !mapping.iter().any(|(_, mapped)| mapped == target) {
+            return Err(format!("no column is mapped to {}", name));
+        }
+    }
+    Ok(mapping)
+}
+
+/// The index in `table` of each mapped column, ignoring case as SQLite does.
+/// Fails naming the columns the table has if one of them isn't there.
+pub fn resolve_columns(
+    mapping: &[(String, Target)],
+    table: &Table,
+) -> Result<Vec<(usize, Target)>, String> {
+    mapping
+        .iter()
+        .map(|(column, target)| {
+            table
+                .columns
+                .iter()
+                .position(|known| known.eq_ignore_ascii_case(column))
+                .map(|index| (index, *target))
+                .ok_or_else(|| {
+                    format!(
+                        "no column {}, the table has: {}",
+                        column,
+                        table.columns.join(", ")
+                    )
+                })
+        })
+        .collect()
+}
+
+/// The pets of the rows of `table`, created at `now`, with ids for the DB
+/// to replace. Fails with every row that could not be read, numbered from 1.
+pub fn pets(
+    table: &Table,
+    columns: &[(usize, Target)],
+    now: DateTime<Utc>,
+    date_order: Option<DateOrder>,
+) -> Result<Vec<Pet>, String> {
+    let today = now.date_naive();
+    let mut pets = Vec::new();
+    let mut failures = Vec::new();
+    for (number, row) in (1..).zip(&table.rows) {
+        let mut pet = crate::new_pet(NewPet {
+            name: String::new(),
+            category: String::new(),
+            age_months: 0,
+            birthdate: None,
+        });
+        pet.created_at = now;
+        let mut failed = None;
+        for (index, target) in columns {
+            let Some(value) = row[*index]
+                .as_text()
+                .filter(|value| !value.trim().is_empty())
+            else {
+                continue;
+            };
+            let value = value.trim().to_owned();
+            match target {
+                Target::Name => pet.name = value,
+                Target::Category => pet.category = value,
+                Target::Notes => pet.notes = Some(value),
+                Target::Breed => pet.breed = Some(value),
+                Target::Microchip => pet.microchip = Some(value.replace(' ', "")),
+                Target::Birthdate => match birthdate(&value, today, date_order) {
+                    Ok(birthdate) => {
+                        pet.age.months = dates::months_between(birthdate, today).max(0) as usize;
+                        pet.birthdate = Some(birthdate);
+                    }
+                    Err(err) => failed = Some(err),
+                },
+            }
+        }
+        match failed {
+            Some(err) => failures.push(format!("row {}: {}", number, err)),
+            None if pet.name.is_empty() => failures.push(format!("row {}: no name", number)),
+            None => pets.push(pet),
+        }
+    }
+    if failures.is_empty() {
+        Ok(pets)
+    } else {
+        Err(failures.join("; "))
+    }
+}
+
+/// A date as typed into the prompts, or a date and time as SQLite writes
+/// them, like `2020-05-01 12:00:00`.
+fn birthdate(
+    value: &str,
+    today: NaiveDate,
+    date_order: Option<DateOrder>,
+) -> Result<NaiveDate, String> {
+    dates::parse_user_date(value, today, date_order).or_else(|err| {
+        match value.get(..10).zip(value.get(10..11)) {
+            Some((date, " " | "T")) => dates::parse_user_date(date, today, date_order),
+            _ => Err(err),
+        }
+        .map_err(|err| err.to_string())
+    })
+}
@@ -0,0 +1,256 @@
+//! Text as the terminal shows it. CJK characters and most emoji take two
+//! columns and combining marks none, and what reads as one character can be
+//! several `char`s, so text is measured in columns and only ever cut between
+//! grapheme clusters.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const ELLIPSIS: &str = "…";
+
+/// The columns `text` takes.
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// `text` followed by spaces up to `width` columns.
+pub fn pad(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(self::width(text));
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+/// `text` without control characters, except for newlines if `newlines`,
+/// and then no more than its first `max` characters. Also returns whether
+/// any were cut off.
+pub fn clean_paste(text: &str, newlines: bool, max: usize) -> (String, bool) {
+    let mut kept = text
+        .chars()
+        .filter(|c| !c.is_control() || (newlines && *c == '\n'));
+    let cleaned: String = kept.by_ref().take(max).collect();
+    (cleaned, kept.next().is_some())
+}
+
+/// `text` right-aligned in `width` columns.
+pub fn pad_left(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(self::width(text));
+    format!("{}{}", " ".repeat(padding), text)
+}
+
+/// `text` if it fits in `max` columns, otherwise as much of it as fits
+/// followed by `…`.
+pub fn truncate(text: &str, max: usize) -> String {
+    if width(text) <= max {
+        return text.to_owned();
+    }
+    let room = max.saturating_sub(width(ELLIPSIS));
+    let mut used = 0;
+    let mut truncated = String::new();
+    for grapheme in text.graphemes(true) {
+        used += width(grapheme);
+        if used > room {
+            break;
+        }
+        truncated.push_str(grapheme);
+    }
+    if max > 0 {
+        truncated.push_str(ELLIPSIS);
+    }
+    truncated
+}
+
+/// As much of `text` as fits in `max` columns, scrolled left by `step`
+/// grapheme clusters and starting over after a gap, for text that is too long
+/// to show at once.
+pub fn marquee(text: &str, max: usize, step: usize) -> String {
+    let graphemes: Vec<&str> = text.graphemes(true).chain([" "; 3]).collect();
+    let mut used = 0;
+    let mut shown = String::new();
+    for grapheme in graphemes.iter().cycle().skip(step % graphemes.len()) {
+        used += width(grapheme);
+        if used > max {
+            break;
+        }
+        shown.push_str(grapheme);
+    }
+    shown
+}
+
+/// The byte offset of the start of the grapheme cluster before `index`.
+pub fn previous_boundary(text: &str, index: usize) -> Option<usize> {
+    text[..index]
+        .grapheme_indices(true)
+        .next_back()
+        .map(|(start, _)| start)
+}
+
+/// The byte offset of the end of the grapheme cluster from `index`.
+pub fn next_boundary(text: &str, index: usize) -> Option<usize> {
+    text[index..]
+        .graphemes(true)
+        .next()
+        .map(|grapheme| index + grapheme.len())
+}
+
+/// `text` broken into lines of at most `max` columns, between words where it
+/// can and between grapheme clusters where a word is longer than a line.
+/// Line breaks in `text` are kept.
+pub fn wrap(text: &str, max: usize) -> Vec<String> {
+    let max = max.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let separator = if line.is_empty() { 0 } else { 1 };
+            if width(&line) + separator + width(word) <= max {
+                if separator == 1 {
+                    line.push(' ');
+                }
+                line.push_str(word);
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for grapheme in word.graphemes(true) {
+                if !line.is_empty() && width(&line) + width(grapheme) > max {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push_str(grapheme);
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: [&str; 6] = [
+        "🐱🐱🐱",
+        "ねこ",
+        "Zoe\u{308}",
+        "a\u{301}e\u{301}i\u{301}o\u{301}",
+        "👨\u{200d}👩\u{200d}👧 family",
+        "Rex",
+    ];
+
+    #[test]
+    fn measures_in_columns() {
+        let widths: Vec<usize> = NAMES.iter().map(|name| width(name)).collect();
+        assert_eq!(widths, vec![6, 4, 3, 4, 9, 3]);
+    }
+
+    #[test]
+    fn truncation_never_goes_past_the_width_or_splits_a_grapheme() {
+        for name in NAMES {
+            let boundaries: Vec<usize> = name
+                .grapheme_indices(true)
+                .map(|(start, _)| start)
+                .chain([name.len()])
+                .collect();
+            for max in 0..=width(name) + 2 {
+                let truncated = truncate(name, max);
+                assert!(
+                    width(&truncated) <= max,
+                    "{:?} in {}: {:?}",
+                    name,
+                    max,
+                    truncated
+                );
+                let kept = truncated.trim_end_matches(ELLIPSIS);
+                assert!(
+                    name.starts_with(kept),
+                    "{:?} in {}: {:?}",
+                    name,
+                    max,
+                    truncated
+                );
+                assert!(
+                    boundaries.contains(&kept.len()),
+                    "{:?} in {}: {:?}",
+                    name,
+                    max,
+                    truncated
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn truncation_keeps_what_fits() {
+        assert_eq!(truncate("🐱🐱🐱", 6), "🐱🐱🐱");
+        assert_eq!(truncate("🐱🐱🐱", 5), "🐱🐱…");
+        assert_eq!(truncate("🐱🐱🐱", 4), "🐱…");
+        assert_eq!(truncate("ねこ", 2), "…");
+        assert_eq!(
+            truncate("a\u{301}e\u{301}i\u{301}o\u{301}", 3),
+            "a\u{301}e\u{301}…"
+        );
+        assert_eq!(truncate("Rex", 0), "");
+    }
+
+    #[test]
+    fn pads_to_columns_not_chars() {
+        assert_eq!(pad("ねこ", 6), "ねこ  ");
+        assert_eq!(pad_left("ねこ", 6), "  ねこ");
+        assert_eq!(pad("Zoe\u{308}", 4), "Zoe\u{308} ");
+        assert_eq!(pad("🐱🐱🐱", 2), "🐱🐱🐱");
+        for name in NAMES {
+            assert_eq!(width(&pad(name, 12)), 12);
+            assert_eq!(width(&pad_left(name, 12)), 12);
+        }
+    }
+
+    #[test]
+    fn boundaries_are_between_graphemes() {
+        let name = "a\u{301}🐱ね";
+        assert_eq!(next_boundary(name, 0), Some(3));
+        assert_eq!(next_boundary(name, 3), Some(7));
+        assert_eq!(next_boundary(name, name.len()), None);
+        assert_eq!(previous_boundary(name, name.len()), Some(7));
+        assert_eq!(previous_boundary(name, 3), Some(0));
+        assert_eq!(previous_boundary(name, 0), None);
+    }
+
+    #[test]
+    fn wrapping_fits_the_width() {
+        assert_eq!(wrap("ねこ ねこ ねこ", 9), vec!["ねこ ねこ", "ねこ"]);
+        assert_eq!(wrap("🐱🐱🐱", 4), vec!["🐱🐱", "🐱"]);
+        assert_eq!(wrap("one\n\ntwo", 10), vec!["one", "", "two"]);
+        for name in NAMES {
+            for max in 2..12 {
+                for line in wrap(&format!("{} {}", name, name), max) {
+                    assert!(width(&line) <= max, "{:?} in {}", line, max);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn the_marquee_scrolls_by_grapheme() {
+        assert_eq!(marquee("ねこ", 4, 0), "ねこ");
+        assert_eq!(marquee("ねこ", 4, 1), "こ  ");
+        assert_eq!(marquee("ねこ", 3, 2), "   ");
+        assert_eq!(marquee("a\u{301}bc", 2, 1), "bc");
+        for name in NAMES {
+            for step in 0..20 {
+                assert!(width(&marquee(name, 5, step)) <= 5);
+            }
+        }
+    }
+
+    #[test]
+    fn pasting_drops_control_characters_and_counts_chars() {
+        assert_eq!(
+            clean_paste("ね\tこ\n🐱", false, 10),
+            ("ねこ🐱".to_owned(), false)
+        );
+        assert_eq!(
+            clean_paste("ね\tこ\n🐱", true, 10),
+            ("ねこ\n🐱".to_owned(), false)
+        );
+        assert_eq!(clean_paste("ねこ🐱", false, 2), ("ねこ".to_owned(), true));
+    }
+}
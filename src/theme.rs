@@ -0,0 +1,436 @@
+use crate::pet_status::PetStatus;
+use thiserror::Error;
+use tui::style::{Color, Modifier, Style};
+
+pub const THEME_NAMES: [&str; 3] = ["dark", "light", "mono"];
+const ROLE_NAMES: [&str; 5] = ["highlight", "text", "border", "accent", "error"];
+
+/// The colors categories get on a dark background, leaving out the ones
+/// that already mean something, like yellow for the highlight and red for
+/// errors.
+const DARK_CATEGORY_COLORS: [Color; 6] = [
+    Color::LightCyan,
+    Color::LightGreen,
+    Color::LightMagenta,
+    Color::LightBlue,
+    Color::Cyan,
+    Color::Green,
+];
+/// On a light background blue is the highlight.
+const LIGHT_CATEGORY_COLORS: [Color; 6] = [
+    Color::LightMagenta,
+    Color::Green,
+    Color::Magenta,
+    Color::Cyan,
+    Color::DarkGray,
+    Color::LightBlue,
+];
+/// What tells categories apart without colors.
+const CATEGORY_MODIFIERS: [Modifier; 3] = [Modifier::ITALIC, Modifier::UNDERLINED, Modifier::BOLD];
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ThemeError {
+    #[error("unknown theme '{0}', valid themes are: {}", THEME_NAMES.join(", "))]
+    UnknownTheme(String),
+    #[error("unknown color '{0}', use a color name like 'yellow' or a hex value like '#ffaa00'")]
+    UnknownColor(String),
+    #[error("unknown theme color '{0}', valid colors are: {}", ROLE_NAMES.join(", "))]
+    UnknownRole(String),
+    #[error("invalid color override '{0}', expected NAME=COLOR")]
+    InvalidOverride(String),
+}
+
+/// The colors the UI is drawn with. A `None` color means "no color": the
+/// terminal's default is used and emphasis comes from modifiers instead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    highlight: Option<Color>,
+    text: Option<Color>,
+    border: Option<Color>,
+    accent: Option<Color>,
+    error: Option<Color>,
+    /// The colors categories are hashed into; empty for none.
+    category_palette: &'static [Color],
+    /// Categories with a color of their own, from the config.
+    category_colors: Vec<(String, Color)>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            highlight: Some(Color::Yellow),
+            text: Some(Color::White),
+            border: Some(Color::White),
+            accent: Some(Color::LightCyan),
+            error: Some(Color::Red),
+            category_palette: &DARK_CATEGORY_COLORS,
+            category_colors: Vec::new(),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            highlight: Some(Color::Blue),
+            text: Some(Color::Black),
+            border: Some(Color::DarkGray),
+            accent: Some(Color::Magenta),
+            error: Some(Color::Red),
+            category_palette: &LIGHT_CATEGORY_COLORS,
+            category_colors: Vec::new(),
+        }
+    }
+
+    pub fn mono() -> Self {
+        Self {
+            highlight: None,
+            text: None,
+            border: None,
+            accent: None,
+            error: None,
+            category_palette: &[],
+            category_colors: Vec::new(),
+        }
+    }
+
+    pub fn named(name: &str) -> Result<Self, ThemeError> {
+        match name {
+            "dark" => Ok(Self::dark()),
+            "light" => Ok(Self::light()),
+            "mono" => Ok(Self::mono()),
+            _ => Err(ThemeError::UnknownTheme(name.to_owned())),
+        }
+    }
+
+    /// Applies an override of the form `highlight=#ffaa00`.
+    pub fn apply_override(&mut self, assignment: &str) -> Result<(), ThemeError> {
+        let (role, color) = assignment
+            .split_once('=')
+            .ok_or_else(|| ThemeError::InvalidOverride(assignment.to_owned()))?;
+        let color = Some(parse_color(color.trim())?);
+        match role.trim() {
+            "highlight" => self.highlight = color,
+            "text" => self.text = color,
+            "border" => self.border = color,
+            "accent" => self.accent = color,
+            "error" => self.error = color,
+            other => return Err(ThemeError::UnknownRole(other.to_owned())),
+        }
+        Ok(())
+    }
+
+    /// Whether any role has a color, which the mono theme and `--no-color`
+    /// don't.
+    #[cfg(feature = "image-preview")]
+    pub fn is_colored(&self) -> bool {
+        [
+            self.highlight,
+            self.text,
+            self.border,
+            self.accent,
+            self.error,
+        ]
+        .iter()
+        .any(Option::is_some)
+    }
+
+    /// Gives the categories in `colors` their own color instead of the one
+    /// their name hashes to. Themes without colors keep using modifiers.
+    pub fn set_category_colors(&mut self, colors: Vec<(String, Color)>) {
+        self.category_colors = colors;
+    }
+
+    /// A pet's category, in a color of its own that is the same in every
+    /// run, or without colors in italics, underlined or bold.
+    pub fn category(&self, category: &str) -> Style {
+        if self.category_palette.is_empty() {
+            let modifier = CATEGORY_MODIFIERS[hash(category) % CATEGORY_MODIFIERS.len()];
+            return Style::default().add_modifier(modifier);
+        }
+        let color = self
+            .category_colors
+            .iter()
+            .find(|(name, _)| name == category)
+            .map(|(_, color)| *color)
+            .unwrap_or_else(|| self.category_palette[hash(category) % self.category_palette.len()]);
+        Style::default().fg(color)
+    }
+
+    pub fn text(&self) -> Style {
+        fg(self.text)
+    }
+
+    pub fn border(&self) -> Style {
+        fg(self.border)
+    }
+
+    pub fn accent(&self) -> Style {
+        fg(self.accent)
+    }
+
+    /// Text that needs to stand out, such as the active tab or a marked pet.
+    pub fn emphasis(&self) -> Style {
+        match self.highlight {
+            Some(color) => Style::default().fg(color),
+            None => Style::default().add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// The key of a menu entry.
+    pub fn hotkey(&self) -> Style {
+        self.emphasis().add_modifier(Modifier::UNDERLINED)
+    }
+
+    /// Field names and column headings. Bold in every theme, so they never
+    /// depend on a color.
+    pub fn label(&self) -> Style {
+        Style::default().add_modifier(Modifier::BOLD)
+    }
+
+    /// The adoption status of a pet. Without colors the badge's text tells
+    /// the statuses apart.
+    pub fn badge(&self, status: PetStatus) -> Style {
+        match status {
+            PetStatus::Intake => self.accent(),
+            PetStatus::Available => self.emphasis(),
+            PetStatus::Adopted => self.border(),
+        }
+    }
+
+    pub fn error(&self) -> Style {
+        match self.error {
+            Some(color) => Style::default().fg(color),
+            None => Style::default().add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// The character under the cursor of a text input. Reversed, so it shows
+    /// on a selected row too.
+    pub fn cursor(&self) -> Style {
+        Style::default().add_modifier(Modifier::REVERSED)
+    }
+
+    /// The selected row of a list. Without a highlight color the row is
+    /// reversed so it stays visible on any terminal.
+    pub fn selection(&self) -> Style {
+        let style = Style::default().add_modifier(Modifier::BOLD);
+        match self.highlight {
+            Some(color) => style.bg(color).fg(Color::Black),
+            None => style.add_modifier(Modifier::REVERSED),
+        }
+    }
+}
+
+/// FNV-1a, which unlike the standard library's hasher is guaranteed to give
+/// the same value in every run and release.
+fn hash(text: &str) -> usize {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in text.bytes() {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash as usize
+}
+
+/// Parses per-category colors like `cats=magenta,dogs=#ffaa00`.
+pub fn parse_category_colors(input: &str) -> Result<Vec<(String, Color)>, ThemeError> {
+    let mut colors = Vec::new();
+    for assignment in input
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (category, color) = assignment
+            .split_once('=')
+            .ok_or_else(|| ThemeError::InvalidOverride(assignment.to_owned()))?;
+        colors.push((category.trim().to_owned(), parse_color(color.trim())?));
+    }
+    Ok(colors)
+}
+
+fn fg(color: Option<Color>) -> Style {
+    match color {
+        Some(color) => Style::default().fg(color),
+        None => Style::default(),
+    }
+}
+
+/// Parses a color name ("yellow", "lightblue") or a `#rrggbb` hex value.
+pub fn parse_color(input: &str) -> Result<Color, ThemeError> {
+    if let Some(hex) = input.strip_prefix('#') {
+        if hex.len() == 6 {
+            if let Ok(rgb) = u32::from_str_radix(hex, 16) {
+                return Ok(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+            }
+        }
+        return Err(ThemeError::UnknownColor(input.to_owned()));
+    }
+    let color = match input.to_lowercase().as_str() {
+        "reset" | "default" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return Err(ThemeError::UnknownColor(input.to_owned())),
+    };
+    Ok(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every style a theme gives out, for every status and a few categories.
+    fn styles(theme: &Theme) -> Vec<Style> {
+        let mut styles = vec![
+            theme.text(),
+            theme.border(),
+            theme.accent(),
+            theme.emphasis(),
+            theme.hotkey(),
+            theme.label(),
+            theme.error(),
+            theme.cursor(),
+            theme.selection(),
+        ];
+        for status in [PetStatus::Intake, PetStatus::Available, PetStatus::Adopted] {
+            styles.push(theme.badge(status));
+        }
+        for category in ["cats", "dogs", "rabbits", "birds", "", "ねこ"] {
+            styles.push(theme.category(category));
+        }
+        styles
+    }
+
+    fn is_uncolored(color: Option<Color>) -> bool {
+        matches!(color, None | Some(Color::Reset))
+    }
+
+    #[test]
+    fn mono_uses_no_colors() {
+        let mut theme = Theme::mono();
+        theme.set_category_colors(vec![("cats".to_owned(), Color::Magenta)]);
+        for style in styles(&theme) {
+            assert!(
+                is_uncolored(style.fg) && is_uncolored(style.bg),
+                "{:?}",
+                style
+            );
+        }
+    }
+
+    #[test]
+    fn mono_tells_things_apart_with_modifiers() {
+        let theme = Theme::mono();
+        assert!(theme.selection().add_modifier.contains(Modifier::REVERSED));
+        assert!(theme.emphasis().add_modifier.contains(Modifier::BOLD));
+        assert!(theme.error().add_modifier.contains(Modifier::BOLD));
+        assert!(!theme.category("cats").add_modifier.is_empty());
+    }
+
+    #[test]
+    fn colored_themes_color_the_highlight() {
+        for theme in [Theme::dark(), Theme::light()] {
+            assert!(theme.emphasis().fg.is_some());
+            assert!(theme.selection().bg.is_some());
+        }
+    }
+
+    #[test]
+    fn category_colors_are_the_same_every_time() {
+        let theme = Theme::dark();
+        assert_eq!(theme.category("cats"), Theme::dark().category("cats"));
+        // FNV-1a's value for it, so a change of hash shows up here.
+        assert_eq!(hash("cats"), 0x9d2d_8a9c);
+        let mut theme = theme;
+        theme.set_category_colors(vec![("cats".to_owned(), Color::Red)]);
+        assert_eq!(theme.category("cats").fg, Some(Color::Red));
+    }
+
+    #[test]
+    fn category_palettes_leave_out_the_highlight_and_error_colors() {
+        for theme in [Theme::dark(), Theme::light()] {
+            for color in theme.category_palette {
+                assert_ne!(Some(*color), theme.highlight);
+                assert_ne!(Some(*color), theme.error);
+            }
+            for category in ["cats", "dogs", "rabbits", "birds", "", "ねこ"] {
+                let color = theme.category(category).fg.unwrap();
+                assert!(theme.category_palette.contains(&color), "{}", category);
+            }
+        }
+    }
+
+    #[test]
+    fn categories_spread_over_the_palette() {
+        let theme = Theme::dark();
+        let mut colors: Vec<_> = ["cats", "dogs", "rabbits", "birds", "fish", "ferrets"]
+            .iter()
+            .map(|category| theme.category(category).fg)
+            .collect();
+        colors.dedup();
+        assert!(colors.len() > 2, "{:?}", colors);
+    }
+
+    #[test]
+    fn only_the_named_categories_are_overridden() {
+        let mut theme = Theme::light();
+        let dogs = theme.category("dogs");
+        theme.set_category_colors(vec![("cats".to_owned(), Color::Rgb(1, 2, 3))]);
+        assert_eq!(theme.category("cats").fg, Some(Color::Rgb(1, 2, 3)));
+        assert_eq!(theme.category("Cats"), Theme::light().category("Cats"));
+        assert_eq!(theme.category("dogs"), dogs);
+    }
+
+    #[test]
+    fn bad_category_colors_are_refused() {
+        assert_eq!(parse_category_colors(" , "), Ok(Vec::new()));
+        assert_eq!(
+            parse_category_colors("cats"),
+            Err(ThemeError::InvalidOverride("cats".to_owned()))
+        );
+        assert_eq!(
+            parse_category_colors("cats=red,dogs=mauve"),
+            Err(ThemeError::UnknownColor("mauve".to_owned()))
+        );
+    }
+
+    #[test]
+    fn parses_colors_and_overrides() {
+        assert_eq!(parse_color("LightBlue"), Ok(Color::LightBlue));
+        assert_eq!(parse_color("#ffaa00"), Ok(Color::Rgb(255, 170, 0)));
+        assert!(parse_color("#ffaa0").is_err());
+        assert!(parse_color("mauve").is_err());
+        let mut theme = Theme::mono();
+        theme.apply_override("highlight = red").unwrap();
+        assert_eq!(theme.emphasis().fg, Some(Color::Red));
+        assert_eq!(
+            theme.apply_override("shadow=red"),
+            Err(ThemeError::UnknownRole("shadow".to_owned()))
+        );
+        assert_eq!(
+            parse_category_colors("cats=magenta, dogs=#000001"),
+            Ok(vec![
+                ("cats".to_owned(), Color::Magenta),
+                ("dogs".to_owned(), Color::Rgb(0, 0, 1))
+            ])
+        );
+    }
+}
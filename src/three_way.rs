@@ -0,0 +1,393 @@
+//! Merging the changes of a dry run with the changes somebody else made to
+//! the DB meanwhile, for when a dry run is saved over a DB that changed on
+//! disk. Pets and owners are matched by id against the DB as it was when the
+//! dry run started: a change only one side made is taken as it is, and only
+//! pets both sides changed differently are asked about. The plan is made
+//! without touching any DB, so it can be shown first.
+
+use crate::diff::{self, FieldChange, PetDiff};
+use crate::merge::Strategy;
+use crate::{Owner, Pet};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// What the two sides did to a pet they both changed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ConflictKind {
+    /// Both edited it, differently.
+    Edited,
+    /// We deleted it and they edited it.
+    DeletedByMe,
+    /// They deleted it and we edited it.
+    DeletedByThem,
+    /// Both added a pet under the id, as both gave out the same next id.
+    AddedByBoth,
+}
+
+#[derive(Debug)]
+pub struct Conflict {
+    pub id: usize,
+    pub kind: ConflictKind,
+    /// `None` where the side deleted the pet.
+    pub mine: Option<Pet>,
+    pub theirs: Option<Pet>,
+    /// `None` until decided.
+    pub resolution: Option<Strategy>,
+}
+
+impl Conflict {
+    /// The fields in which their pet differs from ours; none where either
+    /// side deleted it.
+    pub fn differences(&self) -> Vec<FieldChange> {
+        match (&self.mine, &self.theirs) {
+            (Some(mine), Some(theirs)) => diff::diff_fields(mine, theirs),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The pet as we or they have it, to name it by.
+    pub fn pet(&self) -> &Pet {
+        self.mine
+            .as_ref()
+            .or(self.theirs.as_ref())
+            .expect("a conflict has a pet on at least one side")
+    }
+}
+
+#[derive(Debug)]
+pub struct Plan {
+    /// What the dry run changed.
+    pub mine: PetDiff,
+    /// What changed on disk meanwhile.
+    pub theirs: PetDiff,
+    /// The pets of the merge, in their order with ours added at the end;
+    /// the conflicts keep their place until decided.
+    pets: Vec<Slot>,
+    pub conflicts: Vec<Conflict>,
+    pub owners: Vec<Owner>,
+    /// Owners both sides changed differently, which are theirs in `owners`.
+    pub owner_conflicts: usize,
+}
+
+#[derive(Debug)]
+enum Slot {
+    Pet(Box<Pet>),
+    Conflict(usize),
+}
+
+impl Plan {
+    /// Like `mine: 1 added, ...; theirs: ...; 2 conflicting`.
+    pub fn summary(&self) -> String {
+        format!(
+            "mine: {}; theirs: {}; {} conflicting",
+            self.mine.summary(),
+            self.theirs.summary(),
+            self.conflicts.len()
+        )
+    }
+}
+
+/// How the changes from `base` to `mine` and from `base` to `theirs` merge.
+pub fn plan(
+    base: (&[Pet], &[Owner]),
+    mine: (&[Pet], &[Owner]),
+    theirs: (&[Pet], &[Owner]),
+) -> Plan {
+    let mut pets = Vec::new();
+    let mut conflicts = Vec::new();
+    for id in ids(theirs.0, mine.0, |pet| pet.id) {
+        let find = |pets: &[Pet]| pets.iter().find(|pet| pet.id == id).cloned();
+        let (b, m, t) = (find(base.0), find(mine.0), find(theirs.0));
+        match merge_one(&b, &m, &t) {
+            Some(merged) => pets.extend(merged.map(|pet| Slot::Pet(Box::new(pet)))),
+            None => {
+                pets.push(Slot::Conflict(conflicts.len()));
+                let kind = match (&b, &m, &t) {
+                    (None, _, _) => ConflictKind::AddedByBoth,
+                    (_, None, _) => ConflictKind::DeletedByMe,
+                    (_, _, None) => ConflictKind::DeletedByThem,
+                    _ => ConflictKind::Edited,
+                };
+                conflicts.push(Conflict {
+                    id,
+                    kind,
+                    mine: m,
+                    theirs: t,
+                    resolution: None,
+                });
+            }
+        }
+    }
+    let mut owners = Vec::new();
+    let mut owner_conflicts = 0;
+    for id in ids(theirs.1, mine.1, |owner| owner.id) {
+        let find = |owners: &[Owner]| owners.iter().find(|owner| owner.id == id).cloned();
+        let (b, m, t) = (find(base.1), find(mine.1), find(theirs.1));
+        match merge_one(&b, &m, &t) {
+            Some(merged) => owners.extend(merged),
+            None => {
+                owner_conflicts += 1;
+                owners.extend(t);
+            }
+        }
+    }
+    Plan {
+        mine: diff::diff_pets(base.0, mine.0),
+        theirs: diff::diff_pets(base.0, theirs.0),
+        pets,
+        conflicts,
+        owners,
+        owner_conflicts,
+    }
+}
+
+/// The ids of `theirs` in their order, then those only `mine` has.
+fn ids<T>(theirs: &[T], mine: &[T], id: impl Fn(&T) -> usize) -> Vec<usize> {
+    let mut seen = BTreeSet::new();
+    theirs
+        .iter()
+        .chain(mine)
+        .map(id)
+        .filter(|id| seen.insert(*id))
+        .collect()
+}
+
+/// The merge of one entry as it was, as we have it and as they have it,
+/// `None` inside for one that is gone; `None` if the two sides conflict.
+fn merge_one<T: Clone + Serialize>(
+    base: &Option<T>,
+    mine: &Option<T>,
+    theirs: &Option<T>,
+) -> Option<Option<T>> {
+    if same(mine, theirs) || same(base, mine) {
+        Some(theirs.clone())
+    } else if same(base, theirs) {
+        Some(mine.clone())
+    } else {
+        None
+    }
+}
+
+/// Compared as stored, as that is what a change is.
+fn same<T: Serialize>(a: &Option<T>, b: &Option<T>) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// The merged pets and owners, with the conflicts decided as in their
+/// resolutions. `Both` keeps their pet under the id and ours under one from
+/// `new_ids`; where one side deleted the pet it keeps the other. Fails with
+/// the id of a conflict without a resolution.
+pub fn apply(
+    plan: &Plan,
+    mut new_ids: impl Iterator<Item = usize>,
+) -> Result<(Vec<Pet>, Vec<Owner>), usize> {
+    if let Some(conflict) = plan.conflicts.iter().find(|c| c.resolution.is_none()) {
+        return Err(conflict.id);
+    }
+    let mut pets = Vec::new();
+    let mut renumbered = Vec::new();
+    for slot in &plan.pets {
+        let conflict = match slot {
+            Slot::Pet(pet) => {
+                pets.push(Pet::clone(pet));
+                continue;
+            }
+            Slot::Conflict(index) => &plan.conflicts[*index],
+        };
+        match (conflict.resolution, &conflict.mine, &conflict.theirs) {
+            (Some(Strategy::Mine), mine, _) => pets.extend(mine.clone()),
+            (Some(Strategy::Theirs), _, theirs) => pets.extend(theirs.clone()),
+            (_, Some(mine), Some(theirs)) => {
+                pets.push(theirs.clone());
+                renumbered.push(mine.clone());
+            }
+            (_, mine, theirs) => pets.extend(mine.clone().or_else(|| theirs.clone())),
+        }
+    }
+    for pet in renumbered {
+        let id = new_ids.next().expect("there are ids for every pet");
+        pets.push(Pet { id, ..pet });
+    }
+    Ok((pets, plan.owners.clone()))
+}
+
+/// How many pets `apply` gives new ids.
+pub fn renumbered(plan: &Plan) -> usize {
+    plan.conflicts
+        .iter()
+        .filter(|c| c.resolution == Some(Strategy::Both) && c.mine.is_some() && c.theirs.is_some())
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+
+    fn base() -> Vec<Pet> {
+        vec![
+            pet(1, "Coco", "cats"),
+            pet(2, "Rex", "dogs"),
+            pet(3, "Bun", "rabbits"),
+        ]
+    }
+
+    fn renamed(pets: &[Pet], id: usize, name: &str) -> Vec<Pet> {
+        let mut pets = pets.to_vec();
+        pets.iter_mut().find(|pet| pet.id == id).unwrap().name = name.to_owned();
+        pets
+    }
+
+    fn without(pets: &[Pet], id: usize) -> Vec<Pet> {
+        pets.iter().filter(|pet| pet.id != id).cloned().collect()
+    }
+
+    fn pets_of(mine: &[Pet], theirs: &[Pet]) -> Plan {
+        plan((&base(), &[]), (mine, &[]), (theirs, &[]))
+    }
+
+    fn names(pets: &[Pet]) -> Vec<(usize, &str)> {
+        pets.iter().map(|pet| (pet.id, pet.name.as_str())).collect()
+    }
+
+    fn kinds(plan: &Plan) -> Vec<(usize, ConflictKind)> {
+        plan.conflicts.iter().map(|c| (c.id, c.kind)).collect()
+    }
+
+    fn resolve(plan: &mut Plan, strategy: Strategy) {
+        for conflict in &mut plan.conflicts {
+            conflict.resolution = Some(strategy);
+        }
+    }
+
+    fn owner(id: usize, name: &str) -> Owner {
+        Owner {
+            id,
+            name: name.to_owned(),
+            email: format!("{}@example.com", name.to_lowercase()),
+        }
+    }
+
+    #[test]
+    fn disjoint_changes_merge_without_conflicts() {
+        let mut mine = renamed(&without(&base(), 3), 1, "Cocoa");
+        mine.push(pet(4, "Mia", "cats"));
+        let mut theirs = base();
+        theirs[1].category = "wolves".to_owned();
+        let plan = pets_of(&mine, &theirs);
+        assert!(plan.conflicts.is_empty());
+        assert_eq!(
+            plan.summary(),
+            "mine: 1 added, 1 removed, 1 changed; theirs: 0 added, 0 removed, 1 changed; 0 conflicting"
+        );
+        let (pets, owners) = apply(&plan, 10..).unwrap();
+        assert_eq!(names(&pets), [(1, "Cocoa"), (2, "Rex"), (4, "Mia")]);
+        assert_eq!(pets[1].category, "wolves");
+        assert!(owners.is_empty());
+    }
+
+    #[test]
+    fn the_same_change_on_both_sides_is_not_a_conflict() {
+        let mine = without(&renamed(&base(), 1, "Cocoa"), 2);
+        let theirs = without(&renamed(&base(), 1, "Cocoa"), 2);
+        let plan = pets_of(&mine, &theirs);
+        assert!(plan.conflicts.is_empty());
+        let (pets, _) = apply(&plan, 10..).unwrap();
+        assert_eq!(names(&pets), [(1, "Cocoa"), (3, "Bun")]);
+    }
+
+    #[test]
+    fn overlapping_edits_conflict_until_decided() {
+        let mine = renamed(&base(), 1, "Cocoa");
+        let theirs = renamed(&base(), 1, "Choco");
+        let mut plan = pets_of(&mine, &theirs);
+        assert_eq!(kinds(&plan), [(1, ConflictKind::Edited)]);
+        assert_eq!(plan.conflicts[0].differences().len(), 1);
+        assert_eq!(plan.conflicts[0].pet().name, "Cocoa");
+        assert_eq!(apply(&plan, 10..).unwrap_err(), 1);
+
+        resolve(&mut plan, Strategy::Mine);
+        let (pets, _) = apply(&plan, 10..).unwrap();
+        assert_eq!(names(&pets), [(1, "Cocoa"), (2, "Rex"), (3, "Bun")]);
+        resolve(&mut plan, Strategy::Theirs);
+        let (pets, _) = apply(&plan, 10..).unwrap();
+        assert_eq!(names(&pets), [(1, "Choco"), (2, "Rex"), (3, "Bun")]);
+    }
+
+    #[test]
+    fn deleting_a_pet_the_other_side_edited_is_a_conflict() {
+        let mine = without(&renamed(&base(), 3, "Bunny"), 2);
+        let theirs = without(&renamed(&base(), 2, "Rexy"), 3);
+        let mut plan = pets_of(&mine, &theirs);
+        assert_eq!(
+            kinds(&plan),
+            [
+                (2, ConflictKind::DeletedByMe),
+                (3, ConflictKind::DeletedByThem)
+            ]
+        );
+        assert!(plan.conflicts[0].differences().is_empty());
+        assert_eq!(plan.conflicts[0].pet().name, "Rexy");
+
+        resolve(&mut plan, Strategy::Mine);
+        let (pets, _) = apply(&plan, 10..).unwrap();
+        assert_eq!(names(&pets), [(1, "Coco"), (3, "Bunny")]);
+        resolve(&mut plan, Strategy::Theirs);
+        let (pets, _) = apply(&plan, 10..).unwrap();
+        assert_eq!(names(&pets), [(1, "Coco"), (2, "Rexy")]);
+        // Keeping both keeps whichever side still has the pet, unrenumbered.
+        resolve(&mut plan, Strategy::Both);
+        assert_eq!(renumbered(&plan), 0);
+        let (pets, _) = apply(&plan, 10..).unwrap();
+        assert_eq!(names(&pets), [(1, "Coco"), (2, "Rexy"), (3, "Bunny")]);
+    }
+
+    #[test]
+    fn keeping_both_renumbers_ours_after_the_rest() {
+        let mut mine = base();
+        mine.push(pet(4, "Mia", "cats"));
+        let mut theirs = renamed(&base(), 1, "Choco");
+        theirs.push(pet(4, "Max", "dogs"));
+        let mine = renamed(&mine, 1, "Cocoa");
+        let mut plan = pets_of(&mine, &theirs);
+        assert_eq!(
+            kinds(&plan),
+            [(1, ConflictKind::Edited), (4, ConflictKind::AddedByBoth)]
+        );
+        resolve(&mut plan, Strategy::Both);
+        assert_eq!(renumbered(&plan), 2);
+        let (pets, _) = apply(&plan, 10..).unwrap();
+        assert_eq!(
+            names(&pets),
+            [
+                (1, "Choco"),
+                (2, "Rex"),
+                (3, "Bun"),
+                (4, "Max"),
+                (10, "Cocoa"),
+                (11, "Mia")
+            ]
+        );
+    }
+
+    #[test]
+    fn owners_merge_and_conflicting_ones_are_theirs() {
+        let base_owners = [owner(1, "Ann"), owner(2, "Bob")];
+        let mine_owners = [owner(1, "Anna"), owner(2, "Rob"), owner(3, "Cy")];
+        let theirs_owners = [owner(1, "Ann"), owner(2, "Bobby")];
+        let pets = base();
+        let plan = plan(
+            (&pets, &base_owners),
+            (&pets, &mine_owners),
+            (&pets, &theirs_owners),
+        );
+        assert!(plan.conflicts.is_empty());
+        assert_eq!(plan.owner_conflicts, 1);
+        let (_, owners) = apply(&plan, 10..).unwrap();
+        let names: Vec<(usize, &str)> = owners
+            .iter()
+            .map(|owner| (owner.id, owner.name.as_str()))
+            .collect();
+        assert_eq!(names, [(1, "Anna"), (2, "Bobby"), (3, "Cy")]);
+    }
+}
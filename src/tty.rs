@@ -0,0 +1,91 @@
+//! Whether the UI has a terminal to run on, and the writer it draws with:
+//! stdout, or the terminal itself when stdout is redirected, as in
+//! `pet-cli > log.txt`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Stdout, Write};
+
+/// The terminal of the process, whatever stdin and stdout are.
+#[cfg(unix)]
+pub const TTY_PATH: &str = "/dev/tty";
+#[cfg(windows)]
+pub const TTY_PATH: &str = "CONOUT$";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mode {
+    /// Keys come from a terminal and the UI draws on stdout.
+    Stdout,
+    /// Keys come from a terminal but stdout goes elsewhere, so the UI can
+    /// only draw on `TTY_PATH`, which is offered first.
+    OfferTty,
+    /// The keys don't come from a terminal, e.g. in a pipeline or CI job.
+    NoTerminal,
+}
+
+/// Where the UI can run, given whether stdin and stdout are terminals.
+pub fn mode(stdin_is_tty: bool, stdout_is_tty: bool) -> Mode {
+    match (stdin_is_tty, stdout_is_tty) {
+        (true, true) => Mode::Stdout,
+        (true, false) => Mode::OfferTty,
+        (false, _) => Mode::NoTerminal,
+    }
+}
+
+/// The writer the UI draws with.
+pub enum Output {
+    Stdout(Stdout),
+    Tty(File),
+}
+
+impl Output {
+    pub fn stdout() -> Self {
+        Output::Stdout(io::stdout())
+    }
+
+    pub fn tty() -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(TTY_PATH)?;
+        Ok(Output::Tty(file))
+    }
+
+    /// Another writer to the same place, for the panic hook to put the
+    /// terminal back with.
+    pub fn reopen(&self) -> io::Result<Self> {
+        match self {
+            Output::Stdout(_) => Ok(Output::stdout()),
+            Output::Tty(_) => Output::tty(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::Tty(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::Tty(file) => file.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_ui_draws_on_stdout_only_when_both_are_terminals() {
+        assert_eq!(mode(true, true), Mode::Stdout);
+        assert_eq!(mode(true, false), Mode::OfferTty);
+    }
+
+    #[test]
+    fn without_a_terminal_for_keys_there_is_no_ui() {
+        assert_eq!(mode(false, true), Mode::NoTerminal);
+        assert_eq!(mode(false, false), Mode::NoTerminal);
+    }
+}
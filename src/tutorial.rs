@@ -0,0 +1,293 @@
+//! `--tutorial`: walks a new user through the keys on the made-up pets of
+//! `--demo`. Each step asks for an action, highlights the part of the screen
+//! it works on and moves on once `apply_action` has been handed that action
+//! as many times as the step wants. The steps are the list `steps` returns,
+//! so adding one doesn't touch the engine. Esc ends the tutorial at any
+//! point, and a summary of the steps done is shown before quitting.
+
+use crate::action::Action;
+use crate::keys;
+use crate::MenuItem;
+
+/// The part of the screen a step is about, outlined while it is shown.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    Menu,
+    /// The list or view of the active tab.
+    Main,
+    StatusBar,
+}
+
+pub struct Step {
+    pub prompt: String,
+    pub expected: Action,
+    /// How many times in a row the action has to be performed.
+    pub times: usize,
+    pub target: Target,
+}
+
+impl Step {
+    fn new(prompt: String, expected: Action, times: usize, target: Target) -> Step {
+        Step {
+            prompt,
+            expected,
+            times,
+            target,
+        }
+    }
+}
+
+/// The tutorial's steps, in order, leaving out showing a tab that isn't
+/// one of `tabs`. The prompts take the keys from `keys`, so they can't drift
+/// from the bindings.
+pub fn steps(tabs: &[MenuItem]) -> Vec<Step> {
+    let steps = vec![
+        Step::new(
+            format!("Press {} to open the Pets tab.", keys::PETS),
+            Action::ShowTab(MenuItem::Pets),
+            1,
+            Target::Menu,
+        ),
+        Step::new(
+            format!("Now press {} twice to move down the list.", keys::NEXT),
+            Action::Next,
+            2,
+            Target::Main,
+        ),
+        Step::new(
+            format!("Press {} to move back up.", keys::PREVIOUS),
+            Action::Previous,
+            1,
+            Target::Main,
+        ),
+        Step::new(
+            format!("Press {} to jump to the last pet.", keys::LAST),
+            Action::Last,
+            1,
+            Target::Main,
+        ),
+        Step::new(
+            format!("Press {0}{0} to jump back to the first.", keys::FIRST),
+            Action::First,
+            1,
+            Target::Main,
+        ),
+        Step::new(
+            format!(
+                "Press {} to sort the pets; the status bar says by what.",
+                keys::CYCLE_SORT
+            ),
+            Action::CycleSort,
+            1,
+            Target::StatusBar,
+        ),
+        Step::new(
+            format!(
+                "Press {} to show one category at a time.",
+                keys::CYCLE_CATEGORY
+            ),
+            Action::CycleCategory,
+            1,
+            Target::Main,
+        ),
+        Step::new(
+            format!("Press {} to see the counts on the Stats tab.", keys::STATS),
+            Action::ShowTab(MenuItem::Stats),
+            1,
+            Target::Menu,
+        ),
+        Step::new(
+            format!("Press {} to go back to the Home tab.", keys::HOME),
+            Action::ShowTab(MenuItem::Home),
+            1,
+            Target::Menu,
+        ),
+    ];
+    steps
+        .into_iter()
+        .filter(|step| match step.expected {
+            Action::ShowTab(tab) => tabs.contains(&tab),
+            _ => true,
+        })
+        .collect()
+}
+
+pub struct Tutorial {
+    steps: Vec<Step>,
+    /// The index of the current step, `steps.len()` once all are done.
+    step: usize,
+    /// How many times the current step's action was performed in a row.
+    performed: usize,
+    /// Set once the tutorial is over, done or quit, and the summary shows.
+    over: bool,
+}
+
+impl Tutorial {
+    pub fn new(steps: Vec<Step>) -> Tutorial {
+        Tutorial {
+            steps,
+            step: 0,
+            performed: 0,
+            over: false,
+        }
+    }
+
+    pub fn current(&self) -> Option<&Step> {
+        self.steps.get(self.step).filter(|_| !self.over)
+    }
+
+    /// The current step's number, counting from 1, and how many there are.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.step + 1, self.steps.len())
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    /// Feeds an action through, `count` times for a count like the `2` of
+    /// `2j`. Another action than the step's starts its count again.
+    pub fn observe(&mut self, action: &Action, count: Option<usize>) {
+        let Some(times) = self
+            .current()
+            .filter(|step| step.expected == *action)
+            .map(|step| step.times)
+        else {
+            self.performed = 0;
+            return;
+        };
+        self.performed += count.unwrap_or(1);
+        if self.performed >= times {
+            self.step += 1;
+            self.performed = 0;
+            self.over = self.step == self.steps.len();
+        }
+    }
+
+    /// Esc: ends the tutorial where it is.
+    pub fn quit(&mut self) {
+        self.over = true;
+    }
+
+    /// What the summary screen says: the steps, each ticked if it was done.
+    pub fn summary(&self) -> Vec<String> {
+        let done = self.step.min(self.steps.len());
+        let mut lines = vec![if done == self.steps.len() {
+            "You finished the tutorial!".to_owned()
+        } else {
+            format!("You did {} of the {} steps.", done, self.steps.len())
+        }];
+        lines.push(String::new());
+        for (index, step) in self.steps.iter().enumerate() {
+            let mark = if index < done { "✓" } else { " " };
+            lines.push(format!("{} {}", mark, step.prompt));
+        }
+        lines.push(String::new());
+        lines.push("Start pet-cli without --tutorial to use your own DB.".to_owned());
+        lines.push("Press any key to quit.".to_owned());
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tutorial(steps: &[(Action, usize)]) -> Tutorial {
+        Tutorial::new(
+            steps
+                .iter()
+                .map(|(action, times)| {
+                    Step::new(
+                        format!("{:?}", action),
+                        action.clone(),
+                        *times,
+                        Target::Main,
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn a_step_is_done_once_its_action_came_often_enough() {
+        let mut tutorial = tutorial(&[(Action::Next, 2), (Action::Last, 1)]);
+        tutorial.observe(&Action::Next, None);
+        assert_eq!(tutorial.progress(), (1, 2));
+        tutorial.observe(&Action::Next, None);
+        assert_eq!(tutorial.progress(), (2, 2));
+        assert_eq!(tutorial.current().unwrap().expected, Action::Last);
+        tutorial.observe(&Action::Last, None);
+        assert!(tutorial.is_over());
+        assert!(tutorial.current().is_none());
+    }
+
+    #[test]
+    fn a_count_counts_as_that_many_times() {
+        let mut tutorial = tutorial(&[(Action::Next, 2), (Action::Last, 1)]);
+        tutorial.observe(&Action::Next, Some(2));
+        assert_eq!(tutorial.progress(), (2, 2));
+    }
+
+    #[test]
+    fn another_action_starts_the_count_again() {
+        let mut tutorial = tutorial(&[(Action::Next, 2)]);
+        tutorial.observe(&Action::Next, None);
+        tutorial.observe(&Action::Previous, None);
+        tutorial.observe(&Action::Next, None);
+        assert_eq!(tutorial.progress(), (1, 1));
+        tutorial.observe(&Action::Next, None);
+        assert!(tutorial.is_over());
+    }
+
+    #[test]
+    fn quitting_ticks_only_the_steps_done() {
+        let mut tutorial = tutorial(&[(Action::Next, 1), (Action::Last, 1)]);
+        tutorial.observe(&Action::Next, None);
+        tutorial.quit();
+        assert!(tutorial.is_over() && tutorial.current().is_none());
+        let summary = tutorial.summary();
+        assert_eq!(summary[0], "You did 1 of the 2 steps.");
+        assert_eq!(summary[2], "✓ Next");
+        assert_eq!(summary[3], "  Last");
+        tutorial.observe(&Action::Last, None);
+        assert_eq!(tutorial.summary()[0], "You did 1 of the 2 steps.");
+    }
+
+    #[test]
+    fn a_finished_tutorial_says_so() {
+        let mut tutorial = tutorial(&[(Action::Next, 1)]);
+        tutorial.observe(&Action::Next, None);
+        assert_eq!(tutorial.summary()[0], "You finished the tutorial!");
+    }
+
+    #[test]
+    fn tabs_that_are_turned_off_have_no_step() {
+        let shown = |steps: Vec<Step>| -> Vec<Action> {
+            steps
+                .into_iter()
+                .map(|step| step.expected)
+                .filter(|action| matches!(action, Action::ShowTab(_)))
+                .collect()
+        };
+        assert_eq!(
+            shown(steps(&MenuItem::ALL)),
+            [
+                Action::ShowTab(MenuItem::Pets),
+                Action::ShowTab(MenuItem::Stats),
+                Action::ShowTab(MenuItem::Home)
+            ]
+        );
+        assert_eq!(
+            shown(steps(&[MenuItem::Home, MenuItem::Pets])),
+            [
+                Action::ShowTab(MenuItem::Pets),
+                Action::ShowTab(MenuItem::Home)
+            ]
+        );
+        assert_eq!(
+            steps(&MenuItem::ALL).len() - steps(&[MenuItem::Pets]).len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use crate::fields::PetField;
+use crate::saved_views::StoredView;
+use crate::tab_views::StoredSelection;
+use crate::MenuItem;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const STATE_FILE_NAME: &str = "state.json";
+
+/// The parts of the UI worth remembering between runs. The selection is stored
+/// by pet id rather than index so it survives edits to the DB made elsewhere.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PersistedUiState {
+    pub active_menu_item: MenuItem,
+    pub selected_pet_id: Option<usize>,
+    /// The other tabs' selections; see `tab_views`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tab_selections: Vec<StoredSelection>,
+    /// Missing from state files written before the split was adjustable.
+    #[serde(default)]
+    pub list_width: Option<u16>,
+    #[serde(default)]
+    pub list_columns: Option<Vec<PetField>>,
+    /// Kept whether or not the rest is restored; see `saved_views`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub saved_views: Vec<StoredView>,
+}
+
+/// The state file lives next to the DB it belongs to.
+pub fn state_path(db_path: &Path) -> PathBuf {
+    db_path.with_file_name(STATE_FILE_NAME)
+}
+
+/// Returns `None` for missing or unreadable state files; a bad state file
+/// should never keep the app from starting.
+pub fn load(path: &Path) -> Option<PersistedUiState> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn save(path: &Path, state: &PersistedUiState) -> io::Result<()> {
+    fs::write(path, serde_json::to_vec(state)?)
+}
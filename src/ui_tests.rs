@@ -0,0 +1,2933 @@
+//! Tests of the UI as a user drives it: a [`Harness`] puts a DB in a
+//! temporary directory, feeds key presses through the same `handle_batch` as
+//! the event loop and draws into tui's `TestBackend`, so a test reads like
+//! "press `p` then `j`, and the second pet is highlighted".
+
+use super::*;
+use std::sync::atomic::AtomicUsize;
+use std::sync::MutexGuard;
+use std::time::SystemTime;
+use tui::backend::TestBackend;
+use tui::buffer::{Buffer, Cell};
+use tui::style::{Color, Modifier};
+
+/// The DB path is global, so tests that use one, here and elsewhere, take
+/// turns.
+pub static DB_IN_USE: Mutex<()> = Mutex::new(());
+
+pub struct Harness {
+    pub app_state: AppState,
+    terminal: Terminal<TestBackend>,
+    runtime: tokio::runtime::Runtime,
+    dir: PathBuf,
+    _db_in_use: MutexGuard<'static, ()>,
+}
+
+/// A pet with only the fields a test cares about.
+pub fn pet(id: usize, name: &str, category: &str) -> Pet {
+    serde_json::from_value(serde_json::json!({
+        "id": id,
+        "name": name,
+        "category": category,
+        "age_months": 12,
+        "created_at": "2024-01-01T00:00:00Z",
+    }))
+    .expect("a valid pet")
+}
+
+impl Harness {
+    /// The UI on a DB of `pets`, drawn on a 100x30 screen, before any key.
+    pub fn new(pets: Vec<Pet>) -> Harness {
+        let db_in_use = DB_IN_USE.lock().unwrap_or_else(|err| err.into_inner());
+        static DIRS: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "pet-cli-ui-test-{}-{}",
+            process::id(),
+            DIRS.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).expect("a temporary directory");
+        set_workspace_db(&dir.join("db.json"));
+        set_dry_run_db(None);
+        let mut app_state = AppState::default();
+        let mut database = Database {
+            pets,
+            ..Database::default()
+        };
+        write_db(&mut database, app_state.write_options).expect("the DB is written");
+        app_state.db_watch.mark_seen();
+        let terminal = Terminal::new(TestBackend::new(100, 30)).expect("a test terminal");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("a runtime");
+        Harness {
+            app_state,
+            terminal,
+            runtime,
+            dir,
+            _db_in_use: db_in_use,
+        }
+    }
+
+    /// Presses `keys` one at a time, each handled before the next arrives,
+    /// and waits for the writes they start. `\x1b` is Esc, `\n` Enter, `\t`
+    /// Tab and `\x08` Backspace.
+    pub fn press(&mut self, keys: &str) -> ResponseToUserInput {
+        for key in keys.chars() {
+            if self.send(VecDeque::from(vec![key_event(key)])) == ResponseToUserInput::Stop {
+                return ResponseToUserInput::Stop;
+            }
+        }
+        ResponseToUserInput::Continue
+    }
+
+    /// Presses Ctrl and `key`.
+    pub fn press_ctrl(&mut self, key: char) -> ResponseToUserInput {
+        let code = KeyCode::Char(key);
+        let event = AppEvent::Key(KeyEvent {
+            code,
+            modifiers: KeyModifiers::CONTROL,
+        });
+        self.send(VecDeque::from(vec![event]))
+    }
+
+    /// Presses a key that has no character, like Up.
+    pub fn press_code(&mut self, code: KeyCode) -> ResponseToUserInput {
+        let event = AppEvent::Key(KeyEvent {
+            code,
+            modifiers: KeyModifiers::NONE,
+        });
+        self.send(VecDeque::from(vec![event]))
+    }
+
+    /// Presses `keys` all at once, as when a key is held down or typed
+    /// faster than a frame is drawn.
+    pub fn press_together(&mut self, keys: &str) -> ResponseToUserInput {
+        self.send(keys.chars().map(key_event).collect())
+    }
+
+    /// Hands `batch` to the UI like the event loop does and waits for the
+    /// writes it starts.
+    pub fn send(&mut self, batch: VecDeque<AppEvent>) -> ResponseToUserInput {
+        let _runtime = self.runtime.enter();
+        let response =
+            handle_batch(batch, &mut self.terminal, &mut self.app_state).expect("no UI error");
+        self.runtime.block_on(finish_writes(&mut self.app_state));
+        response
+    }
+
+    /// Draws the UI and returns the screen, one line per row without the
+    /// spaces at the end.
+    pub fn screen(&mut self) -> String {
+        buffer_text(self.draw())
+    }
+
+    /// The text of the highlighted row of the list on screen.
+    pub fn highlighted(&mut self) -> Option<String> {
+        let selection = self.app_state.theme.selection();
+        let buffer = self.draw();
+        let area = buffer.area;
+        (area.top()..area.bottom()).find_map(|y| {
+            let text: String = row_cells(buffer, y)
+                .filter(|cell| {
+                    cell.modifier.contains(Modifier::BOLD)
+                        && match selection.bg {
+                            Some(bg) => cell.bg == bg,
+                            None => cell.modifier.contains(Modifier::REVERSED),
+                        }
+                })
+                .map(|cell| cell.symbol.as_str())
+                .collect();
+            Some(text.trim().to_owned()).filter(|text| !text.is_empty())
+        })
+    }
+
+    pub fn database(&self) -> Database {
+        read_database().expect("the DB can be read")
+    }
+
+    /// Replaces the pets in the DB as another program would and tells the UI
+    /// the file changed, as the DB watcher does.
+    pub fn rewrite_db(&mut self, pets: Vec<Pet>) -> ResponseToUserInput {
+        let mut database = self.database();
+        database.pets = pets;
+        write_db(&mut database, self.app_state.write_options).expect("the DB is written");
+        // A write within the same tick as the last one could leave the
+        // modification time as it was.
+        let later = SystemTime::now() + Duration::from_secs(1);
+        fs::File::options()
+            .write(true)
+            .open(self.dir.join("db.json"))
+            .and_then(|file| file.set_modified(later))
+            .expect("the DB's time is set");
+        self.send(VecDeque::from(vec![AppEvent::DbChanged]))
+    }
+
+    fn draw(&mut self) -> &Buffer {
+        let app_state = &mut self.app_state;
+        self.terminal
+            .draw(|rect| {
+                draw(rect, app_state);
+                let area = rect.size();
+                rect.render_widget(capabilities::Fallback(app_state.capabilities), area);
+            })
+            .expect("the UI draws");
+        self.terminal.backend().buffer()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn key_event(key: char) -> AppEvent {
+    let code = match key {
+        '\x1b' => KeyCode::Esc,
+        '\n' => KeyCode::Enter,
+        '\x08' => KeyCode::Backspace,
+        '\t' => KeyCode::Tab,
+        key => KeyCode::Char(key),
+    };
+    let modifiers = if key.is_uppercase() {
+        KeyModifiers::SHIFT
+    } else {
+        KeyModifiers::NONE
+    };
+    AppEvent::Key(KeyEvent { code, modifiers })
+}
+
+/// `buffer` as text, for snapshots and `contains` checks.
+/// The cells of row `y` that show, leaving out those a wide character
+/// covers. Drawing skips those, so `TestBackend` keeps what was there before.
+fn row_cells(buffer: &Buffer, y: u16) -> impl Iterator<Item = &Cell> {
+    let area = buffer.area;
+    let mut covered = 0;
+    (area.left()..area.right()).filter_map(move |x| {
+        let cell = buffer.get(x, y);
+        if covered > 0 {
+            covered -= 1;
+            return None;
+        }
+        covered = text::width(&cell.symbol).saturating_sub(1);
+        Some(cell)
+    })
+}
+
+pub fn buffer_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    (area.top()..area.bottom())
+        .map(|y| {
+            let line: String = row_cells(buffer, y)
+                .map(|cell| cell.symbol.as_str())
+                .collect();
+            line.trim_end().to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn shelter() -> Vec<Pet> {
+    vec![
+        pet(1, "Coco", "cats"),
+        pet(2, "Rex", "dogs"),
+        pet(3, "Bun", "rabbits"),
+        pet(4, "Max", "dogs"),
+    ]
+}
+
+#[test]
+fn starts_on_the_home_tab() {
+    let mut harness = Harness::new(shelter());
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Home);
+    assert!(harness.screen().contains("Home"));
+}
+
+#[test]
+fn p_opens_the_pets_tab_on_the_first_pet() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Pets);
+    assert!(harness.highlighted().unwrap().starts_with("Coco"));
+}
+
+#[test]
+fn j_highlights_the_second_pet() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pj");
+    assert!(harness.highlighted().unwrap().starts_with("Rex"));
+}
+
+#[test]
+fn k_moves_back_up() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjjk");
+    assert!(harness.highlighted().unwrap().starts_with("Rex"));
+}
+
+#[test]
+fn keys_held_down_move_as_far_as_one_at_a_time() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.press_together("jjj");
+    assert!(harness.highlighted().unwrap().starts_with("Max"));
+}
+
+#[test]
+fn capital_g_and_gg_jump_to_the_ends() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pG");
+    assert!(harness.highlighted().unwrap().starts_with("Max"));
+    harness.press("gg");
+    assert!(harness.highlighted().unwrap().starts_with("Coco"));
+}
+
+#[test]
+fn a_count_moves_that_many_rows() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p2j");
+    assert!(harness.highlighted().unwrap().starts_with("Bun"));
+}
+
+#[test]
+fn the_stats_tab_counts_the_categories() {
+    let mut harness = Harness::new(shelter());
+    harness.press("%");
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Stats);
+    let screen = harness.screen();
+    assert!(screen.contains("4 pets"), "{}", screen);
+    assert!(screen.contains("dogs"), "{}", screen);
+}
+
+#[test]
+fn h_cycles_the_age_histograms_buckets() {
+    let mut pets = shelter();
+    pets[0].age.months = 30;
+    pets[1].age.months = 75;
+    let mut harness = Harness::new(pets);
+    harness.press("%");
+    let screen = harness.screen();
+    assert!(screen.contains("1y per bar"), "{}", screen);
+    assert!(screen.contains("6y"), "{}", screen);
+    harness.press("H");
+    assert_eq!(harness.app_state.age_buckets, AgeBucketWidth::Two);
+    let screen = harness.screen();
+    assert!(screen.contains("2y per bar"), "{}", screen);
+    assert!(screen.contains("6-7y"), "{}", screen);
+    harness.press("HH");
+    assert_eq!(harness.app_state.age_buckets, AgeBucketWidth::One);
+}
+
+#[test]
+fn the_growth_chart_widens_and_switches_to_the_total() {
+    let mut harness = Harness::new(shelter());
+    harness.press("%");
+    assert_eq!(harness.app_state.growth_months, stats::GROWTH_MONTHS);
+    assert!(harness.screen().contains("Pets added per month"));
+    harness.press("+");
+    assert_eq!(harness.app_state.growth_months, 36);
+    harness.press(&"+".repeat(20));
+    assert_eq!(harness.app_state.growth_months, stats::MAX_GROWTH_MONTHS);
+    harness.press(&"-".repeat(20));
+    assert_eq!(harness.app_state.growth_months, stats::GROWTH_STEP);
+    harness.press("#");
+    assert!(harness.app_state.growth_total);
+    let screen = harness.screen();
+    assert!(screen.contains("Pets in total, 12 months"), "{}", screen);
+}
+
+#[test]
+fn unknown_fields_are_shown_in_the_detail_pane() {
+    let mut pets = shelter();
+    pets[0].extra.insert("vet".to_owned(), "Dr. Smith".into());
+    pets[0]
+        .extra
+        .insert("doses".to_owned(), serde_json::json!([1, 2]));
+    let mut harness = Harness::new(pets);
+    harness.press("p");
+    let screen = harness.screen();
+    assert!(
+        screen
+            .lines()
+            .any(|line| line.contains("vet") && line.contains("Dr. Smith")),
+        "{}",
+        screen
+    );
+    assert!(screen.contains("[1,2]"), "{}", screen);
+    harness.press("j");
+    assert!(!harness.screen().contains("Dr. Smith"));
+}
+
+#[test]
+fn tab_switching_keeps_the_selection() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjj%p");
+    assert!(harness.highlighted().unwrap().starts_with("Bun"));
+}
+
+#[test]
+fn dd_moves_the_pet_to_the_trash() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjdd");
+    let database = harness.database();
+    let rex = database.pets.iter().find(|pet| pet.id == 2).unwrap();
+    assert!(rex.archived_at.is_some());
+    assert!(harness.highlighted().unwrap().starts_with("Bun"));
+    assert!(harness.screen().contains("3 pets"));
+}
+
+#[test]
+fn deleting_the_last_visible_pet_selects_the_one_before() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p/category:dogs\nGdd");
+    assert!(harness.database().pets[3].archived_at.is_some());
+    assert!(harness.highlighted().unwrap().starts_with("Rex"));
+    harness.press("dd");
+    assert_eq!(harness.highlighted(), None);
+}
+
+#[test]
+fn adding_a_pet_selects_it() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pj");
+    harness.press_ctrl(keys::QUICK_ADD);
+    harness.press("Pip, cats, 3m\n");
+    assert!(harness.highlighted().unwrap().starts_with("Pip"));
+    assert_eq!(harness.app_state.selected_pet_id, Some(5));
+}
+
+#[test]
+fn adding_a_pet_the_filter_hides_keeps_the_selection() {
+    let mut pets = shelter();
+    pets[1].microchip = Some("985112345678901".to_owned());
+    pets[3].microchip = Some("985112345678902".to_owned());
+    let mut harness = Harness::new(pets);
+    harness.press("p/98511234567890\nja");
+    assert_eq!(harness.database().pets.len(), 5);
+    assert!(harness.highlighted().unwrap().starts_with("Max"));
+}
+
+#[test]
+fn sorting_keeps_the_selection_on_its_pet() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pj");
+    for _ in 0..4 {
+        harness.press("s");
+        assert!(harness.highlighted().unwrap().starts_with("Rex"));
+        let pets = harness.database().pets;
+        let row = harness
+            .app_state
+            .visible_pets(&pets)
+            .iter()
+            .position(|pet| pet.name == "Rex");
+        assert_eq!(harness.app_state.pet_list_state.selected(), row);
+    }
+    harness.press("S");
+    assert!(harness.highlighted().unwrap().starts_with("Rex"));
+}
+
+#[test]
+fn the_trash_tab_shows_deleted_pets() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjddt");
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Trash);
+    assert!(harness.screen().contains("Rex"));
+}
+
+#[test]
+fn quick_add_adds_a_named_pet() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.press_ctrl(keys::QUICK_ADD);
+    harness.press("Pip, cats, 3m\n");
+    let database = harness.database();
+    assert_eq!(database.pets.len(), 5, "{}", harness.screen());
+    assert!(database.pets.iter().any(|pet| pet.name == "Pip"));
+    assert!(harness.screen().contains("Pip"));
+}
+
+#[cfg(unix)]
+#[test]
+fn a_write_runs_its_hook_with_the_new_pet() {
+    let mut harness = Harness::new(shelter());
+    let marker = harness.dir.join("hook-ran");
+    harness.app_state.db_writer.hooks = hooks::Hooks {
+        on_add: Some(format!(
+            "echo \"$PET_CLI_EVENT $PET_CLI_PET_ID\" > '{}'",
+            marker.display()
+        )),
+        timeout: Duration::from_secs(5),
+        ..hooks::Hooks::default()
+    };
+    harness.press("p");
+    harness.press_ctrl(keys::QUICK_ADD);
+    harness.press("Pip, cats, 3m\n");
+    let outcome = harness
+        .runtime
+        .block_on(harness.app_state.db_writer.hook_rx.recv())
+        .unwrap();
+    assert_eq!(outcome.key, "on_add");
+    assert_eq!(fs::read_to_string(&marker).unwrap(), "add 5\n");
+}
+
+#[test]
+fn a_failed_hook_is_reported_and_the_write_kept() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.press_ctrl(keys::QUICK_ADD);
+    harness.press("Pip, cats, 3m\n");
+    harness.send(VecDeque::from([AppEvent::Hook(hooks::HookOutcome {
+        key: "on_add",
+        result: Err(hooks::HookError::TimedOut(Duration::from_secs(5))),
+    })]));
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        ("on_add hook failed: killed after 5s", StatusLevel::Error)
+    );
+    assert_eq!(harness.database().pets.len(), 5);
+}
+
+/// Starts a dry run on the harness's DB, as `--dry-run` does, and returns
+/// the DB's bytes from before.
+fn start_dry_run(harness: &mut Harness) -> Vec<u8> {
+    let db = harness.dir.join("db.json");
+    let original = fs::read(&db).unwrap();
+    let (mut dry_run, copy) = dry_run::DryRun::start(&db).unwrap();
+    set_dry_run_db(Some(&copy));
+    dry_run.set_base(db_format::revision(&read_db_content().unwrap()));
+    harness.app_state.dry_run = Some(dry_run);
+    original
+}
+
+#[test]
+fn quitting_a_dry_run_without_saving_leaves_the_db_as_it_was() {
+    let mut harness = Harness::new(shelter());
+    let original = start_dry_run(&mut harness);
+    harness.press("pjdd");
+    harness.press_ctrl(keys::QUICK_ADD);
+    harness.press("Pip, cats, 3m\n");
+    assert!(harness.highlighted().unwrap().starts_with("Pip"));
+    let screen = harness.screen();
+    assert!(
+        screen.contains("[dry-run, 2 unsaved changes]"),
+        "{}",
+        screen
+    );
+
+    assert_eq!(harness.press("q"), ResponseToUserInput::Continue);
+    let summary = harness.app_state.dry_run_summary.as_ref().unwrap();
+    assert_eq!(summary.diff().added.len(), 1);
+    assert_eq!(summary.diff().changed.len(), 1);
+    assert_eq!(harness.press("n"), ResponseToUserInput::Stop);
+    assert_eq!(fs::read(harness.dir.join("db.json")).unwrap(), original);
+    harness.app_state.dry_run.take().unwrap().finish();
+}
+
+#[test]
+fn a_dry_run_can_be_saved_on_quit() {
+    let mut harness = Harness::new(shelter());
+    start_dry_run(&mut harness);
+    harness.press("p");
+    harness.press_ctrl(keys::QUICK_ADD);
+    harness.press("Pip, cats, 3m\n");
+    assert!(harness.screen().contains("[dry-run, 1 unsaved change]"));
+    harness.press("q\x1b");
+    assert!(harness.app_state.dry_run_summary.is_none());
+    assert_eq!(harness.press("qy"), ResponseToUserInput::Stop);
+    let saved = fs::read_to_string(harness.dir.join("db.json")).unwrap();
+    let saved = db_format::parse(&saved).unwrap();
+    assert_eq!(saved.pets.len(), 5);
+    assert_eq!(saved.pets[4].name, "Pip");
+    harness.app_state.dry_run.take().unwrap().finish();
+}
+
+#[test]
+fn a_dry_run_without_changes_quits_at_once() {
+    let mut harness = Harness::new(shelter());
+    start_dry_run(&mut harness);
+    assert_eq!(harness.press("q"), ResponseToUserInput::Stop);
+    harness.app_state.dry_run.take().unwrap().finish();
+}
+
+#[test]
+fn an_empty_db_shows_no_pets() {
+    let mut harness = Harness::new(Vec::new());
+    harness.press("p");
+    let screen = harness.screen();
+    assert!(screen.contains("0 pets"), "{}", screen);
+    assert_eq!(harness.highlighted(), None);
+}
+
+#[test]
+fn moving_in_an_empty_db_does_nothing() {
+    let mut harness = Harness::new(Vec::new());
+    assert_eq!(harness.press("pjjkGgg"), ResponseToUserInput::Continue);
+    assert!(harness.database().pets.is_empty());
+}
+
+#[test]
+fn q_quits() {
+    let mut harness = Harness::new(shelter());
+    assert_eq!(harness.press("q"), ResponseToUserInput::Stop);
+}
+
+#[test]
+fn search_narrows_the_list() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p/Rex\n");
+    let screen = harness.screen();
+    assert!(screen.contains("Rex"), "{}", screen);
+    assert!(!screen.contains("Coco"), "{}", screen);
+}
+
+/// An idle lock that locks a second from now, unless a key comes first.
+fn idle_lock_almost_due() -> IdleLock {
+    let after = Duration::from_secs(60);
+    let started = Instant::now()
+        .checked_sub(after - Duration::from_secs(1))
+        .expect("the clock is far enough along");
+    IdleLock::new(Some(after), started)
+}
+
+#[test]
+fn keys_held_down_keep_the_idle_lock_away() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.app_state.idle_lock = idle_lock_almost_due();
+    harness.press_together("jj");
+    harness
+        .app_state
+        .idle_lock
+        .tick(Instant::now() + Duration::from_secs(2));
+    assert!(!harness.app_state.idle_lock.is_locked());
+    assert!(harness.highlighted().unwrap().starts_with("Bun"));
+}
+
+/// A kiosk unlocked by `unlock`, reset a second from now unless a key comes
+/// first.
+fn kiosk_almost_due(unlock: &str) -> Kiosk {
+    let after = Duration::from_secs(60);
+    let started = Instant::now()
+        .checked_sub(after - Duration::from_secs(1))
+        .expect("the clock is far enough along");
+    Kiosk::new(unlock, Some(after), false, kiosk::TABS.to_vec(), started)
+}
+
+#[test]
+fn keys_held_down_in_a_kiosk_keep_it_from_resetting() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.app_state.kiosk = Some(kiosk_almost_due(""));
+    harness.press_together("jj");
+    let kiosk = harness.app_state.kiosk.as_mut().unwrap();
+    assert!(!kiosk.tick(Instant::now() + Duration::from_secs(2)));
+    assert!(harness.highlighted().unwrap().starts_with("Bun"));
+}
+
+#[test]
+fn a_kiosk_unlock_typed_fast_still_unlocks() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.app_state.kiosk = Some(kiosk_almost_due("jjkk"));
+    harness.press_together("jjkk");
+    assert!(harness.app_state.kiosk.is_none());
+}
+
+#[test]
+fn a_kiosk_ignores_keys_that_change_the_db() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.app_state.kiosk = Some(kiosk_almost_due(""));
+    harness.press("dd");
+    assert!(harness
+        .database()
+        .pets
+        .iter()
+        .all(|pet| pet.archived_at.is_none()));
+}
+
+#[test]
+fn the_mono_theme_draws_no_colors() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.theme = Theme::mono();
+    for keys in ["h", "p", "j", "%", "t", "l"] {
+        harness.press(keys);
+        let buffer = harness.draw();
+        let area = buffer.area;
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buffer.get(x, y);
+                assert!(
+                    cell.fg == Color::Reset && cell.bg == Color::Reset,
+                    "after {:?}, ({}, {}) {:?} is {:?} on {:?}",
+                    keys,
+                    x,
+                    y,
+                    cell.symbol,
+                    cell.fg,
+                    cell.bg
+                );
+            }
+        }
+    }
+}
+
+/// The cells showing `text` first on the line that has `on` too.
+fn cells_of<'a>(buffer: &'a Buffer, on: &str, text: &str) -> Vec<&'a Cell> {
+    let area = buffer.area;
+    (area.top()..area.bottom())
+        .map(|y| row_cells(buffer, y).collect::<Vec<_>>())
+        .find_map(|cells| {
+            let line: String = cells.iter().map(|cell| cell.symbol.as_str()).collect();
+            let start = line.find(on).and_then(|_| line.find(text))?;
+            let start = line[..start].chars().count();
+            Some(cells[start..start + text.chars().count()].to_vec())
+        })
+        .unwrap_or_else(|| panic!("no {:?} with {:?}", text, on))
+}
+
+#[test]
+fn categories_are_drawn_in_their_colors() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.theme = Theme::dark();
+    harness.press("pj");
+    let dogs = Theme::dark().category("dogs").fg.unwrap();
+    let buffer = harness.draw();
+    for cell in cells_of(buffer, "Category", "dogs") {
+        assert_eq!(cell.fg, dogs);
+    }
+    harness.press("%");
+    let buffer = harness.draw();
+    let cats = Theme::dark().category("cats").fg.unwrap();
+    assert_ne!(cats, dogs);
+    for cell in cells_of(buffer, "cats", "cats") {
+        assert_eq!(cell.fg, cats);
+    }
+}
+
+#[test]
+fn the_selected_row_keeps_the_highlight_over_the_category_color() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.theme = Theme::dark();
+    harness.press("%");
+    let buffer = harness.draw();
+    let selection = Theme::dark().selection();
+    for cell in cells_of(buffer, "dogs", "dogs") {
+        assert_eq!(
+            (cell.fg, cell.bg),
+            (selection.fg.unwrap(), selection.bg.unwrap())
+        );
+    }
+}
+
+#[test]
+fn a_long_message_can_be_read_in_full_in_the_pager() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness
+        .app_state
+        .report(StatusLevel::Info, "first message".to_owned());
+    let long = format!(
+        "could not save {}: permission denied; check that the directory is writable",
+        "/home/someone/pets/".repeat(3)
+    );
+    harness.app_state.report(StatusLevel::Error, long.clone());
+    let screen = harness.screen();
+    assert!(screen.contains("(L: expand)"), "{}", screen);
+    assert!(!screen.contains("directory is writable"), "{}", screen);
+
+    harness.press("L");
+    assert!(harness.app_state.message_pager.is_some());
+    let screen = harness.screen();
+    assert!(screen.contains("directory is writable"), "{}", screen);
+    let newest = screen.find("could not save").unwrap();
+    let oldest = screen.find("first message").unwrap();
+    assert!(newest < oldest, "{}", screen);
+    harness.press("q");
+    assert!(harness.app_state.message_pager.is_none());
+}
+
+#[test]
+fn a_message_that_fits_gets_no_hint() {
+    let mut harness = Harness::new(shelter());
+    harness
+        .app_state
+        .report(StatusLevel::Info, "saved".to_owned());
+    assert!(!harness.screen().contains("expand)"));
+}
+
+#[test]
+fn w_switches_to_another_workspaces_db() {
+    let mut harness = Harness::new(shelter());
+    let foster = harness.dir.join("foster").join("db.json");
+    harness.app_state.workspaces = vec![
+        Workspace {
+            name: "home".to_owned(),
+            path: harness.dir.join("db.json"),
+        },
+        Workspace {
+            name: "foster".to_owned(),
+            path: foster.clone(),
+        },
+    ];
+    harness.press("pj W");
+    harness.press("\x1b");
+    assert!(harness.app_state.workspace_picker.is_none());
+    harness.press("W");
+    let picker = harness.app_state.workspace_picker.as_ref().unwrap();
+    assert_eq!(picker.selected(), 0);
+    assert_eq!(picker.entries()[0].pet_count, Ok(4));
+    assert!(harness.screen().contains("foster"));
+
+    harness.press("j\n");
+    assert!(foster.exists());
+    assert_eq!(harness.app_state.workspace.as_deref(), Some("foster"));
+    assert!(harness.database().pets.is_empty());
+    let (message, _) = harness.app_state.status_line.message().unwrap();
+    assert!(
+        message.starts_with("switched to workspace foster"),
+        "{}",
+        message
+    );
+    let screen = harness.screen();
+    assert!(screen.contains("0 pets | foster "), "{}", screen);
+    let pets = harness.database().pets;
+    assert!(harness
+        .app_state
+        .marks
+        .effective(&harness.app_state.visible_pets(&pets), 0)
+        .is_empty());
+
+    harness.press("Wk\n");
+    assert_eq!(harness.database().pets.len(), 4);
+    assert!(harness.highlighted().unwrap().starts_with("Coco"));
+}
+
+#[test]
+fn w_without_workspaces_says_how_to_set_them_up() {
+    let mut harness = Harness::new(shelter());
+    harness.press("W");
+    assert!(harness.app_state.workspace_picker.is_none());
+    assert!(harness.screen().contains("no workspaces"));
+}
+
+#[test]
+fn a_recorded_macro_replays_its_moves() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.press_ctrl('r');
+    harness.press("aj");
+    harness.press_ctrl('r');
+    assert!(harness.highlighted().unwrap().starts_with("Rex"));
+    harness.press("@a");
+    assert!(harness.highlighted().unwrap().starts_with("Bun"));
+    harness.press("@@");
+    assert!(harness.highlighted().unwrap().starts_with("Max"));
+}
+
+#[test]
+fn a_recorded_macro_replays_its_prompt_answers() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.press_ctrl('r');
+    harness.press("qA\x08\x08\x08\x083y\nj");
+    harness.press_ctrl('r');
+    harness.press("@q@q");
+    let ages: Vec<usize> = harness
+        .database()
+        .pets
+        .iter()
+        .map(|pet| pet.age.months)
+        .collect();
+    assert_eq!(ages, vec![36, 36, 36, 12]);
+    assert!(harness.highlighted().unwrap().starts_with("Max"));
+}
+
+#[test]
+fn replaying_an_empty_register_reports_it() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p@z");
+    assert!(harness.screen().contains("no macro in @z"));
+    assert!(harness.highlighted().unwrap().starts_with("Coco"));
+}
+
+#[test]
+fn an_open_form_is_saved_as_a_draft_until_it_is_closed() {
+    let mut harness = Harness::new(shelter());
+    let path = harness.dir.join("draft.json");
+    harness.app_state.draft_path = Some(path.clone());
+    harness.press("pjA\x08\x082y");
+    let ticks = || {
+        (0..draft::SAVE_EVERY_TICKS)
+            .map(|_| AppEvent::Tick)
+            .collect()
+    };
+    harness.send(ticks());
+    let saved = draft::load(&path).expect("a draft");
+    assert_eq!(saved.db_path, harness.app_state.db_path);
+    assert_eq!(
+        saved.form,
+        draft::Form::Prompt {
+            kind: PromptKind::Age { pet_id: 2 },
+            text: "2y".to_owned()
+        }
+    );
+    harness.press("\x1b");
+    assert!(!path.exists());
+
+    harness.press("A");
+    harness.send(ticks());
+    assert!(path.exists());
+    harness.press("\n");
+    assert!(!path.exists());
+}
+
+#[test]
+fn a_resumed_draft_reopens_its_form() {
+    let mut harness = Harness::new(shelter());
+    resume_draft(
+        &mut harness.app_state,
+        draft::Form::Notes {
+            pet_id: 3,
+            text: "half a note".to_owned(),
+        },
+    );
+    assert!(harness.highlighted().unwrap().starts_with("Bun"));
+    let editor = harness.app_state.notes_editor.as_ref().expect("the editor");
+    assert_eq!((editor.pet_id, editor.text.as_str()), (3, "half a note"));
+}
+
+#[test]
+fn bang_shows_the_problems_in_the_db() {
+    let mut harness = Harness::new(shelter());
+    harness.press("!");
+    assert!(harness.screen().contains("no problems found"));
+    harness.press("\x1b");
+    let mut pets = serde_json::to_value(shelter()).unwrap();
+    pets[1]["id"] = serde_json::json!(1);
+    fs::write(harness.dir.join("db.json"), pets.to_string()).unwrap();
+    harness.press("!");
+    let screen = harness.screen();
+    assert!(screen.contains("id 1 is used by 2 pets"), "{}", screen);
+    assert!(screen.contains("1 DB problem (!)"), "{}", screen);
+    harness.press("\x1b");
+    assert!(!harness.screen().contains("id 1 is used by 2 pets"));
+}
+
+#[test]
+fn an_encrypted_db_is_refused_with_a_clear_error() {
+    let harness = Harness::new(shelter());
+    let path = harness.dir.join("db.json");
+    let mut content = db_format::ENCRYPTED_MAGIC.to_vec();
+    content.extend([0x8f, 0x00, 0xff]);
+    fs::write(&path, content).unwrap();
+    let err = read_database().expect_err("an encrypted DB can't be read");
+    assert!(matches!(err, Error::EncryptedDBError));
+    assert!(err.to_string().contains("this DB is encrypted"));
+    assert_eq!(count_pets(&path), Err(err.to_string()));
+    let mut startup = startup::Report::default();
+    check_db_file(AppState::default().write_options, &mut startup);
+    assert!(startup.is_fatal());
+}
+
+#[test]
+fn tab_completes_a_search_from_names_and_categories() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p/c");
+    harness.press("\t");
+    let prompt = harness
+        .app_state
+        .prompt
+        .as_ref()
+        .expect("the search prompt");
+    assert_eq!(prompt.input.text(), "cats");
+    assert!(harness.screen().contains("[1/2] "));
+    harness.press("\t");
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "Coco"
+    );
+    assert!(harness.screen().contains("[2/2] "));
+    harness.press("\t");
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "cats"
+    );
+    harness.press("\x08\x08\x08\x08mA\t");
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "Max"
+    );
+    assert!(harness.screen().contains("[1/1] "));
+    harness.press("\x08");
+    assert!(!harness.screen().contains("[1/1] "));
+}
+
+#[test]
+fn up_and_down_go_through_earlier_searches() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p/rex\n/\x08\x08\x08max\n/");
+    harness.press_code(KeyCode::Up);
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "max"
+    );
+    harness.press_code(KeyCode::Up);
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "rex"
+    );
+    harness.press_code(KeyCode::Up);
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "rex"
+    );
+    harness.press_code(KeyCode::Down);
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "max"
+    );
+    harness.press_code(KeyCode::Down);
+    assert_eq!(harness.app_state.prompt.as_ref().unwrap().input.text(), "");
+}
+
+#[test]
+fn enter_on_a_reminder_jumps_to_its_pet() {
+    let mut pets = shelter();
+    pets[3].records.push(MedicalRecord {
+        date: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+        kind: "checkup".to_owned(),
+        note: String::new(),
+    });
+    let mut harness = Harness::new(pets);
+    assert!(harness
+        .screen()
+        .contains("Max: last medical record on 2020-01-01"));
+    harness.press("\n");
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Pets);
+    assert!(harness.highlighted().unwrap().starts_with("Max"));
+}
+
+#[test]
+fn moves_on_the_stats_tab_move_the_category_table() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pj%jj");
+    assert_eq!(harness.app_state.stats_table_state.selected(), Some(2));
+    harness.press("k");
+    assert_eq!(harness.app_state.stats_table_state.selected(), Some(1));
+    harness.press("G");
+    assert_eq!(harness.app_state.stats_table_state.selected(), Some(2));
+    harness.press("p");
+    assert!(harness.highlighted().unwrap().starts_with("Rex"));
+}
+
+#[test]
+fn the_category_table_sorts_by_count_or_name() {
+    let mut harness = Harness::new(shelter());
+    harness.press("%");
+    let first = |harness: &mut Harness| harness.highlighted().unwrap();
+    assert!(first(&mut harness).contains("dogs"));
+    harness.press("j");
+    harness.press(&keys::CYCLE_SORT.to_string());
+    assert_eq!(harness.app_state.stats_sort, StatsSort::Name);
+    assert_eq!(harness.app_state.stats_table_state.selected(), Some(0));
+    assert!(first(&mut harness).contains("cats"));
+}
+
+#[test]
+fn enter_on_a_category_shows_its_pets() {
+    let mut harness = Harness::new(shelter());
+    harness.press("%\n");
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Pets);
+    let screen = harness.screen();
+    assert!(
+        screen.contains("Rex") && screen.contains("Max"),
+        "{}",
+        screen
+    );
+    assert!(
+        !screen.contains("Coco") && !screen.contains("Bun"),
+        "{}",
+        screen
+    );
+}
+
+fn finish_loading_with(harness: &mut Harness, select: Option<usize>, tab: Option<MenuItem>) {
+    harness.app_state.loading = Some(Loading {
+        started: Instant::now(),
+        select,
+        tab,
+        draft: None,
+        progress: None,
+    });
+    finish_loading(Ok(shelter()), &mut harness.app_state);
+}
+
+#[test]
+fn select_starts_on_that_pet() {
+    let mut harness = Harness::new(shelter());
+    finish_loading_with(&mut harness, Some(3), None);
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Pets);
+    assert_eq!(harness.app_state.selected_pet_id, Some(3));
+    assert!(harness.highlighted().unwrap().starts_with("Bun"));
+}
+
+#[test]
+fn select_of_a_missing_pet_starts_on_the_first() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.pet_list_state.select(Some(2));
+    finish_loading_with(&mut harness, Some(42), None);
+    assert!(harness.highlighted().unwrap().starts_with("Coco"));
+    assert!(harness
+        .screen()
+        .contains("no pet #42, selected the first pet"));
+}
+
+#[test]
+fn a_chosen_tab_beats_the_one_select_switches_to() {
+    let mut harness = Harness::new(shelter());
+    finish_loading_with(&mut harness, Some(2), Some(MenuItem::Stats));
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Stats);
+    assert_eq!(harness.app_state.selected_pet_id, Some(2));
+}
+
+#[test]
+fn selecting_a_hidden_pet_drops_the_filters_that_hide_it() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p/coco\n");
+    let pets = shelter();
+    assert!(!select_pet(&mut harness.app_state, &pets, 4));
+    assert!(jump_to_pet(&mut harness.app_state, &pets, 4));
+    assert_eq!(harness.app_state.view.query, None);
+    assert!(harness.highlighted().unwrap().starts_with("Max"));
+    assert!(!jump_to_pet(&mut harness.app_state, &pets, 42));
+}
+
+fn name_of(harness: &Harness, pet_id: usize) -> String {
+    let database = harness.database();
+    let pet = database.pets.iter().find(|pet| pet.id == pet_id).unwrap();
+    pet.name.clone()
+}
+
+#[test]
+fn r_renames_the_pet_in_its_row() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjr");
+    assert!(harness.screen().contains("Rex"));
+    harness.press("\x08\x08\x08ねこ🐱\n");
+    assert_eq!(name_of(&harness, 2), "ねこ🐱");
+    assert!(harness.app_state.prompt.is_none());
+    let highlighted = harness.highlighted().unwrap();
+    assert!(highlighted.starts_with("ねこ🐱"), "{:?}", highlighted);
+}
+
+#[test]
+fn the_rename_edits_where_the_cursor_is() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjr");
+    for code in [KeyCode::Home, KeyCode::Right, KeyCode::Delete] {
+        harness.press_code(code);
+    }
+    harness.press("o");
+    harness.press_code(KeyCode::End);
+    harness.press("y\n");
+    assert_eq!(name_of(&harness, 2), "Roxy");
+}
+
+#[test]
+fn esc_cancels_the_rename() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjr\x08\x08Z\x1b");
+    assert_eq!(name_of(&harness, 2), "Rex");
+    assert!(harness.app_state.prompt.is_none());
+}
+
+#[test]
+fn an_empty_name_keeps_the_rename_open() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjr\x08\x08\x08  \n");
+    assert_eq!(name_of(&harness, 2), "Rex");
+    let prompt = harness
+        .app_state
+        .prompt
+        .as_ref()
+        .expect("the rename is open");
+    assert_eq!(prompt.input.text(), "  ");
+    let screen = harness.screen();
+    assert!(screen.contains("name is empty"), "{}", screen);
+}
+
+/// The shelter with notes and a record for Rex, to copy.
+fn shelter_with_history() -> Vec<Pet> {
+    let mut pets = shelter();
+    pets[1].notes = Some("afraid of the hoover".to_owned());
+    pets[1].records.push(MedicalRecord {
+        date: NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+        kind: "vaccination".to_owned(),
+        note: "rabies".to_owned(),
+    });
+    pets
+}
+
+/// The pet added by a copy, the one with the highest id.
+fn newest(harness: &Harness) -> Pet {
+    let database = harness.database();
+    database.pets.into_iter().max_by_key(|pet| pet.id).unwrap()
+}
+
+#[test]
+fn shift_c_copies_the_pet_with_a_fresh_id_and_creation_time() {
+    let mut harness = Harness::new(shelter_with_history());
+    harness.press("pjC");
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "Rex (copy)"
+    );
+    harness.press("\n");
+    let copy = newest(&harness);
+    assert_eq!(harness.database().pets.len(), 5);
+    assert_eq!(copy.name, "Rex (copy)");
+    assert!(shelter().iter().all(|pet| pet.id != copy.id));
+    assert!(copy.created_at > shelter()[1].created_at);
+    assert_eq!(copy.category, "dogs");
+    assert_eq!(copy.notes.as_deref(), Some("afraid of the hoover"));
+    assert_eq!(copy.records.len(), 1);
+    assert_eq!(copy.records[0].note, "rabies");
+    let highlighted = harness.highlighted().unwrap();
+    assert!(highlighted.starts_with("Rex (copy)"), "{:?}", highlighted);
+}
+
+#[test]
+fn the_copy_takes_the_name_typed() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjC");
+    harness.press(&"\x08".repeat(" (copy)".len()));
+    harness.press("sy\n");
+    assert_eq!(newest(&harness).name, "Rexsy");
+    assert_eq!(name_of(&harness, 2), "Rex");
+}
+
+#[test]
+fn editing_the_copy_leaves_the_original_alone() {
+    let mut harness = Harness::new(shelter_with_history());
+    harness.press("pjC\n");
+    let copy = newest(&harness);
+    let options = harness.app_state.write_options;
+    update_pet(options, copy.id, |pet| {
+        pet.notes.as_mut().unwrap().push_str(" and the cat");
+        pet.records[0].note.push_str(", booster");
+        pet.records.push(MedicalRecord {
+            date: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            kind: "checkup".to_owned(),
+            note: String::new(),
+        });
+    })
+    .unwrap()
+    .unwrap();
+    let database = harness.database();
+    let original = database.pets.iter().find(|pet| pet.id == 2).unwrap();
+    assert_eq!(original.notes.as_deref(), Some("afraid of the hoover"));
+    assert_eq!(original.records.len(), 1);
+    assert_eq!(original.records[0].note, "rabies");
+    let copy = newest(&harness);
+    assert_eq!(
+        copy.notes.as_deref(),
+        Some("afraid of the hoover and the cat")
+    );
+    assert_eq!(copy.records.len(), 2);
+}
+
+#[test]
+fn wide_and_combined_names_draw_at_any_width() {
+    let names = ["🐱🐱🐱", "ねこ", "Zoe\u{308}", "👨\u{200d}👩\u{200d}👧"];
+    let pets: Vec<Pet> = names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| pet(index + 1, name, "ねこ科"))
+        .collect();
+    let mut harness = Harness::new(pets);
+    harness.press("p");
+    let screen = harness.screen();
+    for name in names {
+        assert!(screen.contains(name), "{} in\n{}", name, screen);
+    }
+    for width in (20..100).step_by(7) {
+        harness.terminal = Terminal::new(TestBackend::new(width, 24)).expect("a test terminal");
+        for keys in ["%", "h", "p", "j", "r"] {
+            harness.press(keys);
+            for line in harness.screen().lines() {
+                assert!(
+                    text::width(line) <= width as usize,
+                    "{:?} at {}",
+                    line,
+                    width
+                );
+            }
+        }
+        harness.press("\x1b");
+    }
+}
+
+/// The row of the pet list on screen for the pet named `name`, without the
+/// border and the marker.
+fn list_row(harness: &mut Harness, name: &str) -> String {
+    let screen = harness.screen();
+    screen
+        .lines()
+        .find_map(|line| {
+            let row = line
+                .trim_start()
+                .strip_prefix('|')?
+                .split('|')
+                .next()?
+                .trim();
+            row.starts_with(name).then(|| row.to_owned())
+        })
+        .unwrap_or_else(|| panic!("no row for {} in\n{}", name, screen))
+}
+
+#[test]
+fn the_column_chooser_shows_and_orders_the_list_columns() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p|");
+    assert!(harness.app_state.column_chooser.is_some());
+    harness.press("jj K\n");
+    assert!(harness.app_state.column_chooser.is_none());
+    assert_eq!(
+        harness.app_state.list_columns,
+        [PetField::Name, PetField::Category, PetField::Status]
+    );
+    harness.app_state.list_width = 50;
+    assert_eq!(list_row(&mut harness, "Coco"), "Coco cats    [available]");
+}
+
+#[test]
+fn esc_leaves_the_columns_as_they_were() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p|j \x1b");
+    assert!(harness.app_state.column_chooser.is_none());
+    assert_eq!(
+        harness.app_state.list_columns,
+        [PetField::Name, PetField::Status]
+    );
+}
+
+#[test]
+fn a_narrow_list_drops_columns_from_the_right_but_keeps_the_name() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.app_state.list_columns = vec![PetField::Name, PetField::Category, PetField::Created];
+    harness.app_state.list_width = 50;
+    let created = dates::format_local(shelter()[1].created_at);
+    assert_eq!(
+        list_row(&mut harness, "Rex"),
+        format!("Rex  dogs    {}", created)
+    );
+    harness.app_state.list_width = 20;
+    assert_eq!(list_row(&mut harness, "Rex"), "Rex  dogs");
+    harness.app_state.list_width = 10;
+    assert_eq!(list_row(&mut harness, "Rex"), "Rex");
+}
+
+#[test]
+fn restored_columns_need_the_name_and_must_be_list_columns() {
+    let mut harness = Harness::new(shelter());
+    let state = |columns: Vec<PetField>| PersistedUiState {
+        active_menu_item: MenuItem::Pets,
+        selected_pet_id: None,
+        tab_selections: Vec::new(),
+        list_width: None,
+        list_columns: Some(columns),
+        saved_views: Vec::new(),
+    };
+    restore_ui_state(&mut harness.app_state, &state(vec![PetField::Status]));
+    restore_ui_state(
+        &mut harness.app_state,
+        &state(vec![PetField::Name, PetField::Breed]),
+    );
+    assert_eq!(
+        harness.app_state.list_columns,
+        [PetField::Name, PetField::Status]
+    );
+    restore_ui_state(
+        &mut harness.app_state,
+        &state(vec![PetField::Owner, PetField::Name]),
+    );
+    assert_eq!(
+        harness.app_state.list_columns,
+        [PetField::Owner, PetField::Name]
+    );
+}
+
+fn numbered(count: usize) -> Vec<Pet> {
+    (1..=count)
+        .map(|id| pet(id, &format!("Pet{}", id), "dogs"))
+        .collect()
+}
+
+#[test]
+fn the_selection_follows_its_pet_when_the_db_shrinks_elsewhere() {
+    let mut harness = Harness::new(numbered(10));
+    harness.press("pjjjjjjj");
+    assert_eq!(harness.highlighted().unwrap(), "Pet8");
+    let kept: Vec<Pet> = numbered(10)
+        .into_iter()
+        .filter(|pet| [2, 8, 9].contains(&pet.id))
+        .collect();
+    harness.rewrite_db(kept);
+    assert_eq!(harness.app_state.pet_list_state.selected(), Some(1));
+    assert_eq!(harness.highlighted().unwrap(), "Pet8");
+    assert!(harness.screen().contains("reloaded: db changed on disk"));
+}
+
+#[test]
+fn the_selection_is_clamped_when_its_pet_is_gone() {
+    let mut harness = Harness::new(numbered(10));
+    harness.press("pjjjjjjj");
+    harness.rewrite_db(numbered(3));
+    assert_eq!(harness.app_state.pet_list_state.selected(), Some(2));
+    assert_eq!(harness.highlighted().unwrap(), "Pet3");
+    assert_eq!(harness.app_state.selected_pet_id, Some(3));
+    harness.press("jk");
+    assert_eq!(harness.highlighted().unwrap(), "Pet2");
+}
+
+#[test]
+fn nothing_is_selected_when_the_db_empties_elsewhere() {
+    let mut harness = Harness::new(numbered(10));
+    harness.press("pjjjjjjj");
+    harness.rewrite_db(Vec::new());
+    assert_eq!(harness.app_state.pet_list_state.selected(), None);
+    assert_eq!(harness.app_state.selected_pet_id, None);
+    assert_eq!(harness.highlighted(), None);
+    // Keys that act on the selected pet have nothing to act on.
+    harness.press("jkd");
+    assert!(harness.database().pets.is_empty());
+    harness.screen();
+}
+
+#[test]
+fn the_tabs_show_counts_and_unsaved_input() {
+    let mut pets = shelter();
+    pets[3].archived_at = Some(Utc::now());
+    let mut harness = Harness::new(pets);
+    harness.terminal = Terminal::new(TestBackend::new(160, 30)).expect("a test terminal");
+    harness.press("p");
+    let menu = |harness: &mut Harness| {
+        let screen = harness.screen();
+        screen
+            .lines()
+            .find(|line| line.contains("Home"))
+            .unwrap()
+            .to_owned()
+    };
+    let line = menu(&mut harness);
+    assert!(line.contains("Pets (3)"), "{}", line);
+    assert!(line.contains("Trash (1)"), "{}", line);
+    harness.press("/rex\n");
+    let line = menu(&mut harness);
+    assert!(line.contains("Pets (1)"), "{}", line);
+    harness.press("/");
+    let line = menu(&mut harness);
+    assert!(line.contains("Pets (1)*"), "{}", line);
+    harness.press("\x1b");
+    let line = menu(&mut harness);
+    assert!(!line.contains('*'), "{}", line);
+}
+
+#[test]
+fn merging_a_db_file_asks_about_conflicts() {
+    let mut harness = Harness::new(shelter());
+    let other = harness.dir.join("other.json");
+    let theirs = vec![
+        pet(2, "Fido", "dogs"),
+        pet(3, "bun", "Rabbits"),
+        pet(8, "Tom", "cats"),
+    ];
+    fs::write(&other, serde_json::to_string(&theirs).unwrap()).unwrap();
+    harness.press("pe");
+    harness.press(&format!("{}\n", other.display()));
+    assert!(harness.app_state.merge_review.is_some());
+    let (message, _) = harness.app_state.status_line.message().unwrap();
+    assert!(
+        message.ends_with("other.json: 1 to add, 1 already here, 1 conflicting"),
+        "{}",
+        message
+    );
+    assert!(harness.screen().contains("Fido"));
+    harness.press("t");
+    assert!(harness.app_state.merge_review.is_none());
+    let names: Vec<(usize, String)> = harness
+        .database()
+        .pets
+        .into_iter()
+        .map(|pet| (pet.id, pet.name))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            (1, "Coco".to_owned()),
+            (2, "Fido".to_owned()),
+            (3, "Bun".to_owned()),
+            (4, "Max".to_owned()),
+            (5, "Tom".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn cancelling_the_merge_review_merges_nothing() {
+    let mut harness = Harness::new(shelter());
+    let other = harness.dir.join("other.json");
+    fs::write(
+        &other,
+        serde_json::to_string(&vec![pet(2, "Fido", "dogs")]).unwrap(),
+    )
+    .unwrap();
+    harness.press("pe");
+    harness.press(&format!("{}\n\x1b", other.display()));
+    assert!(harness.app_state.merge_review.is_none());
+    assert!(harness.screen().contains("nothing was merged"));
+    assert_eq!(harness.database().pets[1].name, "Rex");
+}
+
+#[test]
+fn an_unreadable_merge_file_keeps_the_prompt_open() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pe");
+    harness.press(&format!("{}\n", harness.dir.join("missing.json").display()));
+    assert!(harness.app_state.prompt.is_some());
+    assert_eq!(harness.database().pets.len(), 4);
+}
+
+#[test]
+fn attributes_are_set_shown_searched_and_removed() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjFdiet=grain-free\n");
+    let rex = harness.database().pets.remove(1);
+    assert_eq!(rex.attributes["diet"], "grain-free");
+    let screen = harness.screen();
+    assert!(screen.contains("set diet of Rex"), "{}", screen);
+    assert!(
+        screen
+            .lines()
+            .any(|line| line.contains("diet") && line.contains("grain-free")),
+        "{}",
+        screen
+    );
+    harness.press("/grain\n");
+    assert_eq!(
+        harness
+            .app_state
+            .visible_pets(&harness.database().pets)
+            .len(),
+        1
+    );
+    assert!(harness.highlighted().unwrap().starts_with("Rex"));
+    harness.press("Fdiet=\n");
+    assert!(harness.database().pets[1].attributes.is_empty());
+}
+
+#[test]
+fn tab_suggests_breeds_of_the_pets_species() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjNlabr");
+    harness.press_code(KeyCode::Tab);
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "Labrador Retriever"
+    );
+    harness.press_code(KeyCode::Enter);
+    let rex = harness.database().pets.remove(1);
+    assert_eq!(rex.breed.as_deref(), Some("Labrador Retriever"));
+    assert!(harness.screen().contains("updated breed of Rex"));
+    harness.press("N");
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "Labrador Retriever"
+    );
+    for _ in 0.."Labrador Retriever".len() {
+        harness.press_code(KeyCode::Backspace);
+    }
+    harness.press_code(KeyCode::Enter);
+    assert_eq!(harness.database().pets[1].breed, None);
+}
+
+#[test]
+fn other_species_take_any_breed_as_typed() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjjNlop");
+    harness.press_code(KeyCode::Tab);
+    assert_eq!(
+        harness.app_state.prompt.as_ref().unwrap().input.text(),
+        "lop"
+    );
+    harness.press(" Holland");
+    harness.press_code(KeyCode::Enter);
+    let bun = harness.database().pets.remove(2);
+    assert_eq!(bun.breed.as_deref(), Some("lop Holland"));
+}
+
+#[test]
+fn the_stats_tab_counts_the_breeds_of_the_selected_category() {
+    let mut pets = shelter();
+    pets[1].breed = Some("Whippet".to_owned());
+    pets[3].breed = Some("Whippet".to_owned());
+    let mut harness = Harness::new(pets);
+    harness.press("%");
+    let screen = harness.screen();
+    assert!(
+        screen
+            .lines()
+            .any(|line| line.contains("Whippet") && line.contains('2')),
+        "{}",
+        screen
+    );
+}
+
+#[test]
+fn an_attribute_without_a_key_keeps_the_prompt_open() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pF=grain-free\n");
+    assert!(harness.app_state.prompt.is_some());
+    assert!(harness.screen().contains("an attribute key can't be empty"));
+    assert!(harness.database().pets[0].attributes.is_empty());
+}
+
+#[test]
+fn relative_times_redraw_only_once_their_text_changes() {
+    let mut rex = pet(1, "Rex", "dogs");
+    rex.created_at = Utc::now() - chrono::Duration::seconds(90);
+    let mut harness = Harness::new(vec![rex.clone()]);
+    harness.screen();
+    assert_eq!(
+        harness.app_state.next_time_change,
+        Some(rex.created_at + chrono::Duration::minutes(2))
+    );
+    assert!(!expire_timed_state(&mut harness.app_state));
+    harness.app_state.next_time_change = Some(Utc::now() - chrono::Duration::seconds(1));
+    assert!(expire_timed_state(&mut harness.app_state));
+}
+
+#[test]
+fn tabs_without_relative_times_have_nothing_to_redraw() {
+    let mut harness = Harness::new(shelter());
+    harness.press("o");
+    harness.screen();
+    assert_eq!(harness.app_state.next_time_change, None);
+}
+
+fn names(harness: &Harness) -> Vec<String> {
+    harness
+        .database()
+        .pets
+        .into_iter()
+        .map(|pet| pet.name)
+        .collect()
+}
+
+#[test]
+fn a_cut_pet_is_pasted_after_or_before_the_selected_one() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pX");
+    // Nothing moves until the paste.
+    assert_eq!(names(&harness), ["Coco", "Rex", "Bun", "Max"]);
+    harness.press("jj]");
+    assert_eq!(names(&harness), ["Rex", "Bun", "Coco", "Max"]);
+    harness.press("jX");
+    harness.press("kkk[");
+    assert_eq!(names(&harness), ["Max", "Rex", "Bun", "Coco"]);
+    harness.press("]");
+    assert!(harness.screen().contains("nothing is cut"));
+}
+
+#[test]
+fn a_cut_pet_pasted_into_an_empty_list_goes_last() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pX/nobody\n]");
+    assert_eq!(names(&harness), ["Rex", "Bun", "Max", "Coco"]);
+}
+
+#[test]
+fn pasting_is_refused_while_sorted() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjjjX");
+    harness.press("s");
+    assert!(harness.app_state.view.sort.is_some());
+    harness.press("[");
+    assert!(harness.screen().contains("the list is sorted"));
+    assert_eq!(names(&harness), ["Coco", "Rex", "Bun", "Max"]);
+}
+
+#[test]
+fn a_batch_edit_sets_a_field_on_the_marked_pets_after_confirming() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p j jB");
+    harness.press("category cats\n");
+    assert!(harness
+        .screen()
+        .contains("Set category = cats on 2 pets? (y/n)"));
+    harness.press("y");
+    let categories: Vec<String> = harness
+        .database()
+        .pets
+        .into_iter()
+        .map(|pet| pet.category)
+        .collect();
+    assert_eq!(categories, ["cats", "cats", "rabbits", "dogs"]);
+    assert!(harness.screen().contains("set category = cats on 2 pets"));
+}
+
+#[test]
+fn set_changes_the_filtered_pets() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p/category:dogs\n");
+    harness.press(":set status adopted\ny");
+    let adopted: Vec<String> = harness
+        .database()
+        .pets
+        .into_iter()
+        .filter(|pet| pet.status == PetStatus::Adopted)
+        .map(|pet| pet.name)
+        .collect();
+    assert_eq!(adopted, ["Rex", "Max"]);
+}
+
+#[test]
+fn a_batch_edit_needs_marks_or_a_filter() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pB");
+    assert!(harness.app_state.prompt.is_none());
+    assert!(harness.screen().contains("mark pets or filter the list"));
+}
+
+#[test]
+fn a_batch_that_would_make_a_pet_invalid_changes_none() {
+    let mut harness = Harness::new(shelter());
+    let revision = harness.database().revision;
+    harness.press("p j B");
+    harness.press("age 61y\ny");
+    let database = harness.database();
+    assert_eq!(database.revision, revision);
+    assert!(database.pets.iter().all(|pet| pet.age.months == 12));
+    let (message, level) = harness.app_state.status_line.message().unwrap();
+    assert_eq!(level, StatusLevel::Error);
+    assert!(message.contains("Coco (#1)"), "{}", message);
+    assert!(message.contains("Rex (#2)"), "{}", message);
+}
+
+#[test]
+fn a_long_write_shows_a_gauge_and_esc_cancels_it() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.app_state.db_writer.progress = Some(Progress {
+        label: "checking pets",
+        done: 500,
+        total: 1200,
+    });
+    assert!(harness
+        .screen()
+        .contains("checking pets 500/1200 (Esc cancels)"));
+    harness.press("\x1b");
+    assert!(harness.app_state.db_writer.cancel.is_cancelled());
+    let (message, _) = harness.app_state.status_line.message().unwrap();
+    assert_eq!(message, "cancelling…");
+}
+
+#[test]
+fn quick_add_fills_in_the_configured_defaults() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.add_defaults = quick_add::AddDefaults {
+        category: Some("cats".to_owned()),
+        age_months: Some(12),
+        name_prefix: "Foster-".to_owned(),
+    };
+    harness.press("p");
+    harness.press_ctrl(keys::QUICK_ADD);
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        ("a name alone adds Foster-…, cats, 1 y", StatusLevel::Info)
+    );
+    harness.press("Tom\n");
+    assert!(harness.app_state.prompt.is_none());
+    let database = harness.database();
+    let pet = database.pets.iter().find(|pet| pet.id == 5).unwrap();
+    assert_eq!(
+        (pet.name.as_str(), pet.category.as_str(), pet.age.months),
+        ("Foster-Tom", "cats", 12)
+    );
+    assert_eq!(harness.app_state.selected_pet_id, Some(5));
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        ("added Foster-Tom (#5, cats, 1 y)", StatusLevel::Info)
+    );
+}
+
+#[test]
+fn quick_add_defaults_are_validated_like_typed_values() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.add_defaults = quick_add::AddDefaults {
+        category: Some("cats".to_owned()),
+        age_months: Some(12),
+        name_prefix: "x".repeat(validation::MAX_NAME_LENGTH),
+    };
+    harness.press("p");
+    harness.press_ctrl(keys::QUICK_ADD);
+    harness.press("Tom\n");
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        ("name is 103 characters, more than 100", StatusLevel::Error)
+    );
+    assert!(harness.app_state.prompt.is_some());
+    assert_eq!(harness.database().pets.len(), 4);
+}
+
+#[test]
+fn quitting_waits_for_the_queued_writes() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    let batch = "aaaq".chars().map(key_event).collect();
+    let _runtime = harness.runtime.enter();
+    let response = handle_batch(batch, &mut harness.terminal, &mut harness.app_state).unwrap();
+    assert_eq!(response, ResponseToUserInput::Stop);
+    assert!(harness.app_state.db_writer.is_busy());
+    let screen = buffer_text(harness.draw());
+    assert!(screen.contains("(2 queued)"), "{}", screen);
+
+    harness
+        .runtime
+        .block_on(finish_writes(&mut harness.app_state));
+    assert!(!harness.app_state.db_writer.is_busy());
+    assert!(!harness.screen().contains("saving"));
+    assert_eq!(harness.database().pets.len(), 7);
+}
+
+#[test]
+fn ctrl_o_goes_back_before_a_jump_and_ctrl_n_forward_again() {
+    let mut pets = shelter();
+    pets[3].records.push(MedicalRecord {
+        date: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+        kind: "checkup".to_owned(),
+        note: String::new(),
+    });
+    let mut harness = Harness::new(pets);
+    harness.press("pjh\n");
+    assert!(harness.highlighted().unwrap().starts_with("Max"));
+    harness.press_ctrl(keys::JUMP_BACK);
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Home);
+    assert_eq!(harness.app_state.selected_pet_id, Some(2));
+    harness.press_ctrl(keys::JUMP_BACK);
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        ("no older jump", StatusLevel::Info)
+    );
+    harness.press_ctrl(keys::JUMP_FORWARD);
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Pets);
+    assert!(harness.highlighted().unwrap().starts_with("Max"));
+    harness.press_ctrl(keys::JUMP_FORWARD);
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        ("no newer jump", StatusLevel::Info)
+    );
+}
+
+#[test]
+fn a_refused_birthdate_is_explained_under_the_prompt() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pb2023-02-29\n");
+    let prompt = harness.app_state.prompt.as_ref().unwrap();
+    assert_eq!(
+        prompt.error.as_deref(),
+        Some("February 2023 has no day 29, only 28")
+    );
+    assert!(harness
+        .screen()
+        .contains("February 2023 has no day 29, only 28"));
+
+    harness.app_state.date_order = Some(dates::DateOrder::Dmy);
+    for _ in 0.."2023-02-29".len() {
+        harness.press("\x08");
+    }
+    harness.press("28/02/2023\n");
+    assert!(harness.app_state.prompt.is_none());
+    assert_eq!(
+        harness.database().pets[0].birthdate,
+        NaiveDate::from_ymd_opt(2023, 2, 28)
+    );
+}
+
+#[test]
+fn the_grouped_list_moves_over_headers_and_folds_groups() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pzg");
+    let screen = harness.screen();
+    assert!(screen.contains("— dogs (2) —"), "{}", screen);
+    assert!(harness.highlighted().unwrap().starts_with("Coco"));
+    harness.press("j");
+    assert!(harness.highlighted().unwrap().starts_with("Rex"));
+    harness.press("za");
+    assert!(!harness.screen().contains("Max"));
+    assert!(harness
+        .highlighted()
+        .unwrap()
+        .starts_with("— dogs (2, folded"));
+    harness.press("j");
+    assert!(harness.highlighted().unwrap().starts_with("Bun"));
+    harness.press("k\n");
+    assert!(harness.screen().contains("— dogs (2) —"));
+    assert!(harness.highlighted().unwrap().starts_with("Rex"));
+
+    harness.press("jdd");
+    let database = harness.database();
+    let trashed: Vec<&str> = database
+        .pets
+        .iter()
+        .filter(|pet| pet.archived_at.is_some())
+        .map(|pet| pet.name.as_str())
+        .collect();
+    assert_eq!(trashed, ["Max"]);
+}
+
+/// Puts the harness back in the state startup leaves it in, and starts the
+/// first read of its DB.
+fn start_loading(harness: &mut Harness) -> UnboundedReceiver<AppEvent> {
+    harness.app_state.loading = Some(Loading {
+        started: Instant::now(),
+        select: None,
+        tab: None,
+        draft: None,
+        progress: None,
+    });
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    load_in_background(tx).join().unwrap();
+    rx
+}
+
+/// Hands the harness what the loader sent, up to and including the loaded
+/// DB; returns how many progress reports there were.
+fn deliver_loaded(harness: &mut Harness, mut rx: UnboundedReceiver<AppEvent>) -> usize {
+    let mut reports = 0;
+    while let Ok(event) = rx.try_recv() {
+        reports += matches!(event, AppEvent::Progress(_)) as usize;
+        harness.send(VecDeque::from([event]));
+    }
+    reports
+}
+
+#[test]
+fn keys_that_change_the_db_wait_for_it_to_load() {
+    let pets: Vec<Pet> = (1..=10_000)
+        .map(|id| pet(id, &format!("Pet {}", id), "dogs"))
+        .collect();
+    let mut harness = Harness::new(pets);
+    let rx = start_loading(&mut harness);
+    harness.press("pjdda");
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        (
+            "still loading the DB, changes can be made once it is read",
+            StatusLevel::Info
+        )
+    );
+    let screen = harness.screen();
+    assert!(screen.contains("loading "), "{}", screen);
+    assert!(screen.contains("q quits"), "{}", screen);
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Home);
+
+    assert!(deliver_loaded(&mut harness, rx) > 0);
+    assert!(harness.app_state.loading.is_none());
+    let database = harness.database();
+    assert_eq!(database.pets.len(), 10_000);
+    assert!(database.pets.iter().all(|pet| pet.archived_at.is_none()));
+    harness.press("pa");
+    assert_eq!(harness.database().pets.len(), 10_001);
+}
+
+#[test]
+fn quitting_while_loading_stops_at_once() {
+    let mut harness = Harness::new(shelter());
+    let _rx = start_loading(&mut harness);
+    assert_eq!(harness.press("q"), ResponseToUserInput::Stop);
+}
+
+#[test]
+fn a_db_that_fails_to_load_opens_the_backups() {
+    let mut harness = Harness::new(shelter());
+    let db = harness.dir.join("db.json");
+    backup::create(&db, 5).unwrap();
+    fs::write(&db, "{\"pets\": [").unwrap();
+    let rx = start_loading(&mut harness);
+    deliver_loaded(&mut harness, rx);
+    assert!(harness.app_state.loading.is_none());
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap().1,
+        StatusLevel::Error
+    );
+    assert!(harness.app_state.restore_preview.is_some());
+}
+
+#[test]
+fn a_search_with_an_unknown_field_is_explained_under_the_prompt() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p/colour:red\n");
+    let prompt = harness.app_state.prompt.as_ref().unwrap();
+    assert!(prompt
+        .error
+        .as_deref()
+        .unwrap()
+        .starts_with("unknown field 'colour'"));
+    assert!(harness.app_state.view.query.is_none());
+    assert!(harness.screen().contains("unknown field 'colour'"));
+}
+
+#[test]
+fn a_search_with_fields_narrows_the_list() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p/category:dogs age:<=1 max\n");
+    let screen = harness.screen();
+    assert!(screen.contains("Max"), "{}", screen);
+    assert!(
+        !screen.contains("Rex") && !screen.contains("Coco"),
+        "{}",
+        screen
+    );
+    assert!(
+        screen.contains("search: category:dogs age:<=1 max"),
+        "{}",
+        screen
+    );
+}
+
+#[test]
+fn the_id_of_a_purged_pet_is_not_given_out_again() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pGddtDy");
+    let database = harness.database();
+    assert!(database.pets.iter().all(|pet| pet.id != 4));
+    harness.press("p");
+    harness.press_ctrl(keys::QUICK_ADD);
+    harness.press("Pip, cats, 3m\n");
+    harness.press("a");
+    let ids: Vec<usize> = harness.database().pets.iter().map(|pet| pet.id).collect();
+    assert_eq!(ids, [1, 2, 3, 5, 6]);
+}
+
+/// The names of the saved views in the harness DB's state file.
+fn stored_views(harness: &Harness) -> Vec<String> {
+    let path = ui_state::state_path(&harness.dir.join("db.json"));
+    ui_state::load(&path)
+        .map(|state| state.saved_views)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|view| view.name)
+        .collect()
+}
+
+#[test]
+fn a_saved_view_is_shown_renamed_and_deleted_from_the_picker() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p/category:dogs\n:save-view dogs\n");
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        ("saved view dogs, V lists the views", StatusLevel::Info)
+    );
+    assert_eq!(stored_views(&harness), ["dogs"]);
+
+    harness.app_state.view = ViewParams::default();
+    harness.press("hV\n");
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Pets);
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        ("showing view dogs", StatusLevel::Info)
+    );
+    let screen = harness.screen();
+    assert!(
+        screen.contains("Rex") && !screen.contains("Coco"),
+        "{}",
+        screen
+    );
+
+    harness.press("Vr\x08\x08\x08\x08pups\n");
+    assert_eq!(stored_views(&harness), ["pups"]);
+    assert_eq!(harness.app_state.active_view.as_deref(), Some("pups"));
+
+    harness.press("Vd");
+    assert!(harness.app_state.view_picker.is_none());
+    assert_eq!(harness.app_state.active_view, None);
+    assert!(stored_views(&harness).is_empty());
+    harness.press("V");
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        (
+            "no saved views, save the Pets tab's with :save-view NAME",
+            StatusLevel::Error
+        )
+    );
+}
+
+#[test]
+fn a_saved_view_that_no_longer_reads_is_skipped_on_load() {
+    let mut harness = Harness::new(shelter());
+    let state = PersistedUiState {
+        active_menu_item: MenuItem::Pets,
+        selected_pet_id: None,
+        tab_selections: Vec::new(),
+        list_width: None,
+        list_columns: None,
+        saved_views: vec![saved_views::StoredView {
+            name: "old".to_owned(),
+            query: Some("colour:red".to_owned()),
+            category: None,
+            status: None,
+            sort: None,
+        }],
+    };
+    load_saved_views(&mut harness.app_state, &state);
+    assert!(harness.app_state.saved_views.views.is_empty());
+    let (message, level) = harness.app_state.status_line.message().unwrap();
+    assert!(message.starts_with("skipped saved view old: unknown field 'colour'"));
+    assert_eq!(level, StatusLevel::Error);
+}
+
+#[test]
+fn a_pet_from_the_yank_ring_is_added_again_with_a_new_id() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjXjX\"");
+    let screen = harness.screen();
+    assert!(
+        screen.contains("Bun") && screen.contains("Rex"),
+        "{}",
+        screen
+    );
+    harness.press("j\n");
+    let database = harness.database();
+    let added = database.pets.last().unwrap();
+    assert_eq!((added.id, added.name.as_str()), (5, "Rex"));
+    assert_eq!(added.category, "dogs");
+    assert!(harness.app_state.yank_picker.is_none());
+}
+
+#[test]
+fn a_pet_from_the_yank_ring_is_validated_like_any_other() {
+    let mut harness = Harness::new(shelter());
+    let nameless = pet(9, " ", "dogs");
+    harness.app_state.yank_ring.push(&nameless, Utc::now());
+    harness.press("p\"\n");
+    let (message, level) = harness.app_state.status_line.message().unwrap();
+    assert!(message.contains("name is empty"), "{}", message);
+    assert_eq!(level, StatusLevel::Error);
+    assert_eq!(harness.database().pets.len(), 4);
+}
+
+#[test]
+fn the_yank_ring_is_empty_until_a_pet_is_yanked() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p\"");
+    assert!(harness.app_state.yank_picker.is_none());
+    assert_eq!(
+        harness.app_state.status_line.message().unwrap(),
+        (
+            "nothing yanked yet, y or X yanks the selected pet",
+            StatusLevel::Error
+        )
+    );
+}
+
+#[test]
+fn a_narrow_list_cuts_long_text_before_dropping_columns() {
+    let mut pets = shelter();
+    pets[1].name = "Sir Reginald Fluffington".to_owned();
+    let mut harness = Harness::new(pets);
+    harness.press("p");
+    harness.app_state.list_columns = vec![PetField::Name, PetField::Category, PetField::Created];
+    harness.app_state.list_width = 30;
+    // The ellipsis is drawn as . on the test screen.
+    assert_eq!(list_row(&mut harness, "Sir"), "Sir R. dogs   2024-01-0.");
+    assert_eq!(list_row(&mut harness, "Coco"), "Coco   cats   2024-01-0.");
+    harness.app_state.list_column_widths = vec![(PetField::Category, 2)];
+    assert_eq!(list_row(&mut harness, "Sir"), "Sir Regin. d. 2024-01-0.");
+}
+
+/// Terminal input that sends `keys` and then ends, as when reading the
+/// terminal fails.
+fn input_ending_after(
+    keys: &str,
+) -> impl Stream<Item = Result<CEvent, crossterm::ErrorKind>> + Unpin {
+    let events: Vec<_> = keys
+        .chars()
+        .map(|key| match key_event(key) {
+            AppEvent::Key(key) => Ok(CEvent::Key(key)),
+            _ => unreachable!("key_event gives keys"),
+        })
+        .collect();
+    futures_util::stream::iter(events)
+}
+
+/// Runs the event loop on the harness with terminal input from `opened`,
+/// one stream each time the loop opens it.
+fn run_with_input(harness: &mut Harness, opened: &[&str]) -> Result<(), AppError> {
+    let (_background, rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut opened = opened.iter();
+    let open_input = || input_ending_after(opened.next().copied().unwrap_or_default());
+    harness.runtime.block_on(run_event_loop(
+        &mut harness.terminal,
+        &mut harness.app_state,
+        rx,
+        Duration::from_secs(60),
+        false,
+        open_input,
+    ))
+}
+
+#[test]
+fn input_that_ends_is_reopened_once() {
+    let mut harness = Harness::new(shelter());
+    let result = run_with_input(&mut harness, &["pj", "jq"]);
+    assert!(result.is_ok());
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Pets);
+    assert!(harness.highlighted().unwrap().starts_with("Bun"));
+    let mut log = harness.app_state.activity_log.entries();
+    assert!(log.any(|entry| entry.text == "terminal input stopped and was reopened"));
+}
+
+#[test]
+fn input_that_ends_twice_stops_after_the_queued_writes() {
+    let mut harness = Harness::new(shelter());
+    let result = run_with_input(&mut harness, &["pa", "a"]);
+    assert!(matches!(result, Err(AppError::EventChannelClosed)));
+    assert!(!harness.app_state.db_writer.is_busy());
+    assert_eq!(harness.database().pets.len(), 6);
+}
+
+#[test]
+fn quitting_as_the_input_ends_is_not_an_error() {
+    let mut harness = Harness::new(shelter());
+    assert!(run_with_input(&mut harness, &["pq"]).is_ok());
+}
+
+#[test]
+fn an_age_typed_in_days_sets_the_birthdate() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pA\x08\x08\x0812d\n");
+    let coco = harness.database().pets.remove(0);
+    let today = Local::now().date_naive();
+    assert_eq!(
+        coco.birthdate,
+        today.checked_sub_days(chrono::Days::new(12))
+    );
+    let screen = harness.screen();
+    assert!(screen.contains("12 d"), "{}", screen);
+}
+
+#[test]
+fn a_demo_says_so_in_the_status_bar() {
+    let mut harness = Harness::new(shelter());
+    assert!(!harness.screen().contains("[demo]"));
+    harness.app_state.demo = true;
+    let screen = harness.screen();
+    assert!(screen.contains("[demo]"), "{}", screen);
+}
+
+#[test]
+fn sorting_by_id_or_age_in_the_list_is_numeric_and_ids_line_up() {
+    let mut pets = vec![pet(10, "Ten", "cats"), pet(9, "Nine", "cats")];
+    pets[0].age.months = 9;
+    pets[1].age.months = 10;
+    let mut harness = Harness::new(pets);
+    harness.app_state.list_columns = vec![PetField::Id, PetField::Name];
+    harness.press("p:sort id\n");
+    let screen = harness.screen();
+    let nine = screen
+        .find("   9 Nine")
+        .unwrap_or_else(|| panic!("{}", screen));
+    let ten = screen
+        .find("  10 Ten")
+        .unwrap_or_else(|| panic!("{}", screen));
+    assert!(nine < ten, "{}", screen);
+    harness.press(":sort age\n");
+    let screen = harness.screen();
+    assert!(
+        screen.find("Ten").unwrap() < screen.find("Nine").unwrap(),
+        "{}",
+        screen
+    );
+}
+
+fn paste(harness: &mut Harness, text: &str) {
+    harness.send(VecDeque::from(vec![AppEvent::Paste(text.to_owned())]));
+}
+
+#[test]
+fn a_long_paste_into_a_name_is_cut_to_the_longest_name() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pr");
+    paste(&mut harness, &"x".repeat(10_000));
+    let prompt = harness
+        .app_state
+        .prompt
+        .as_ref()
+        .expect("the rename prompt");
+    let name = prompt.input.text();
+    assert_eq!(name.chars().count(), validation::MAX_NAME_LENGTH);
+    assert!(name.starts_with("Cocoxxx"), "{}", name);
+    assert_eq!(
+        harness.app_state.status_line.message(),
+        Some(("the paste was cut to 100 characters", StatusLevel::Error))
+    );
+    harness.press("\n");
+    let coco = harness.database().pets.remove(0);
+    assert_eq!(coco.name.chars().count(), validation::MAX_NAME_LENGTH);
+}
+
+#[test]
+fn control_characters_are_left_out_of_a_paste() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pr\x08\x08\x08\x08");
+    paste(&mut harness, "Sir\x1b[31m\tRex\r\n");
+    let prompt = harness
+        .app_state
+        .prompt
+        .as_ref()
+        .expect("the rename prompt");
+    assert_eq!(prompt.input.text(), "Sir[31mRex");
+    assert_eq!(harness.app_state.status_line.message(), None);
+    harness.press("\x1b");
+    harness.press("n");
+    paste(&mut harness, "line one\r\nline\ttwo\n\x07");
+    let editor = harness.app_state.notes_editor.as_ref().expect("the editor");
+    assert_eq!(editor.text, "line one\nlinetwo\n");
+}
+
+#[test]
+fn a_long_paste_into_the_notes_is_cut_to_the_longest_notes() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pn");
+    paste(&mut harness, &"note\n".repeat(3_000));
+    let editor = harness.app_state.notes_editor.as_ref().expect("the editor");
+    assert_eq!(editor.text.chars().count(), validation::MAX_NOTES_LENGTH);
+    assert_eq!(
+        harness.app_state.status_line.message(),
+        Some(("the paste was cut to 10000 characters", StatusLevel::Error))
+    );
+}
+
+#[test]
+fn a_paste_with_no_text_input_open_is_ignored() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    let before = harness.screen();
+    // Keys of a paste would add, delete and quit if they were pressed.
+    paste(&mut harness, "addq");
+    assert_eq!(harness.screen(), before);
+    assert_eq!(harness.database().pets.len(), 4);
+    assert!(harness.app_state.prompt.is_none());
+}
+
+#[test]
+fn characters_arriving_together_in_a_prompt_are_one_paste() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pr\x08\x08\x08\x08");
+    let batch = "Bigcat".chars().map(key_event).collect();
+    harness.send(batch);
+    let prompt = harness
+        .app_state
+        .prompt
+        .as_ref()
+        .expect("the rename prompt");
+    assert_eq!(prompt.input.text(), "Bigcat");
+    // Without a prompt the same keys are keys: `dd` deletes.
+    harness.press("\x1b");
+    harness.send("dd".chars().map(key_event).collect());
+    assert_eq!(
+        harness
+            .database()
+            .pets
+            .iter()
+            .filter(|p| p.archived_at.is_none())
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn f12_shows_and_hides_the_diagnostics() {
+    let mut harness = Harness::new(shelter());
+    harness.press_code(KeyCode::F(12));
+    let screen = harness.screen();
+    assert!(screen.contains("Diagnostics (F12 hides)"), "{}", screen);
+    for label in [
+        "frame",
+        "draws",
+        "DB reads",
+        "DB writes",
+        "queued",
+        "pets read",
+    ] {
+        assert!(screen.contains(label), "{}: {}", label, screen);
+    }
+    assert!(screen.contains("0 writes"), "{}", screen);
+    harness.press_code(KeyCode::F(12));
+    assert!(!harness.screen().contains("Diagnostics"));
+}
+
+#[test]
+fn reset_needs_the_word_typed_and_keeps_the_old_file() {
+    let mut harness = Harness::new(shelter());
+    harness.press(":reset\n");
+    assert!(harness.screen().contains("Type reset to start an empty DB"));
+    harness.press("y\n");
+    let prompt = harness.app_state.prompt.as_ref().expect("still asking");
+    assert_eq!(
+        prompt.error.as_deref(),
+        Some("type reset to go on, or Esc to cancel")
+    );
+    assert_eq!(harness.database().pets.len(), 4);
+    harness.press("\x08reset\n");
+    assert!(harness.app_state.prompt.is_none());
+    let database = harness.database();
+    assert!(database.pets.is_empty());
+    let aside: Vec<_> = fs::read_dir(&harness.dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.starts_with("db.json.reset-"))
+        .collect();
+    assert_eq!(aside.len(), 1, "{:?}", aside);
+    let old = fs::read_to_string(harness.dir.join(&aside[0])).unwrap();
+    assert!(old.contains("Coco"), "{}", old);
+    // Ids carry on from the old DB.
+    harness.press("pa");
+    assert_eq!(harness.database().pets[0].id, 5);
+}
+
+#[test]
+fn reset_is_refused_read_only() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.write_options.read_only = true;
+    harness.press(":reset\n");
+    assert!(harness.app_state.prompt.is_none());
+    assert_eq!(harness.database().pets.len(), 4);
+}
+
+fn prompt(harness: &Harness) -> Option<String> {
+    harness
+        .app_state
+        .pending_confirmation
+        .as_ref()
+        .map(Confirmation::prompt)
+}
+
+fn in_trash(harness: &Harness) -> Vec<usize> {
+    harness
+        .database()
+        .pets
+        .iter()
+        .filter(|pet| pet.is_archived())
+        .map(|pet| pet.id)
+        .collect()
+}
+
+#[test]
+fn deleting_a_pet_already_in_the_trash_asks_to_delete_it_for_good() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p:delete 2\n");
+    assert_eq!(in_trash(&harness), [2]);
+    harness.press(":delete 2\n");
+    assert_eq!(
+        prompt(&harness).as_deref(),
+        Some("Delete Rex permanently? This can't be undone. (y/n)")
+    );
+    harness.press("n");
+    assert_eq!(in_trash(&harness), [2]);
+    harness.press(":delete 2\ny");
+    assert!(harness.database().pets.iter().all(|pet| pet.id != 2));
+}
+
+#[test]
+fn dd_in_the_trash_deletes_for_good_after_asking() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjddt");
+    harness.press("dd");
+    assert_eq!(
+        prompt(&harness).as_deref(),
+        Some("Delete Rex permanently? This can't be undone. (y/n)")
+    );
+    // The Pets tab's selection, Bun by now, is left alone.
+    harness.press("y");
+    let names: Vec<String> = harness
+        .database()
+        .pets
+        .into_iter()
+        .map(|pet| pet.name)
+        .collect();
+    assert_eq!(names, ["Coco", "Bun", "Max"]);
+}
+
+#[test]
+fn a_delete_of_pets_in_and_outside_the_trash_asks_about_each_separately() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p j j ");
+    // Another instance moves Rex to the Trash while he is marked.
+    let mut pets = harness.database().pets;
+    pets[1].archived_at = Some(Utc::now());
+    harness.rewrite_db(pets);
+    harness.press("dd");
+    assert_eq!(
+        prompt(&harness).as_deref(),
+        Some("Move 2 marked pets to the Trash? (y/n)")
+    );
+    // No to the first question still asks the second.
+    harness.press("n");
+    assert_eq!(
+        prompt(&harness).as_deref(),
+        Some("Delete Rex permanently? This can't be undone. (y/n)")
+    );
+    harness.press("y");
+    assert!(harness.database().pets.iter().all(|pet| pet.id != 2));
+    assert!(in_trash(&harness).is_empty());
+    harness.press("dd");
+    harness.press("y");
+    assert_eq!(in_trash(&harness), [1, 3]);
+}
+
+#[test]
+fn restoring_from_the_trash_clears_only_its_date() {
+    let mut pets = shelter();
+    pets[1].notes = Some("shy".to_owned());
+    pets[1].status = PetStatus::Adopted;
+    let before = pets[1].clone();
+    let mut harness = Harness::new(pets);
+    harness.press("pjddtr");
+    let rex = harness.database().pets.remove(1);
+    assert_eq!(
+        serde_json::to_value(&rex).unwrap(),
+        serde_json::to_value(&before).unwrap()
+    );
+}
+
+#[test]
+fn a_pet_restored_before_its_purge_is_written_survives() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p:delete 2\n:delete 2\n");
+    // Another instance restores Rex while the question is open.
+    let mut pets = harness.database().pets;
+    pets[1].archived_at = None;
+    harness.rewrite_db(pets);
+    harness.press("y");
+    while harness.app_state.pending_confirmation.is_some() {
+        harness.press("y");
+    }
+    let database = harness.database();
+    let rex = database.pets.iter().find(|pet| pet.id == 2);
+    assert!(rex.is_some_and(|rex| !rex.is_archived()));
+}
+
+#[test]
+fn borders_are_box_drawing_only_where_the_terminal_has_it() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    let ascii = harness.screen();
+    assert!(ascii.contains("+Pets---"), "{}", ascii);
+    assert!(!ascii.contains('─'), "{}", ascii);
+    harness.app_state.capabilities = Capabilities {
+        unicode_borders: true,
+        truecolor: true,
+        braille: true,
+        images: true,
+    };
+    let unicode = harness.screen();
+    assert!(unicode.contains("┌Pets───"), "{}", unicode);
+}
+
+fn quick_actions(bindings: &[(&str, &str)]) -> Vec<config::QuickAction> {
+    let mut layer = config::Layer::default();
+    for (key, line) in bindings {
+        layer.set(key, *line, config::Source::Flag);
+    }
+    config::resolve(&[layer], String::new())
+        .expect("the bindings parse")
+        .quick_actions
+}
+
+#[test]
+fn a_function_key_does_what_typing_its_command_does() {
+    // What the view args, the selection and the listed pets are after.
+    fn outcome(harness: &mut Harness) -> (String, Option<String>, Vec<String>) {
+        let pets = harness.database().pets;
+        let names = harness
+            .app_state
+            .visible_pets(&pets)
+            .iter()
+            .map(|pet| pet.name.clone())
+            .collect();
+        (
+            harness.app_state.view.to_args(),
+            harness.highlighted(),
+            names,
+        )
+    }
+    let typed = {
+        let mut harness = Harness::new(shelter());
+        harness.press("p:filter dogs\n:sort name desc\n");
+        outcome(&mut harness)
+    };
+    let mut harness = Harness::new(shelter());
+    harness.app_state.quick_actions =
+        quick_actions(&[("F2", ":filter dogs"), ("F3", "sort name desc")]);
+    harness.press("p");
+    harness.press_code(KeyCode::F(2));
+    harness.press_code(KeyCode::F(3));
+    let bound = outcome(&mut harness);
+    assert_eq!(bound, typed);
+    assert_eq!(bound.0, "--sort name:desc --category dogs");
+    assert_eq!(bound.2, ["Rex", "Max"]);
+}
+
+#[test]
+fn a_function_key_says_what_it_ran_and_takes_a_count() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.quick_actions = quick_actions(&[("F4", "add"), ("F5", "theme mono")]);
+    harness.press("p");
+    harness.press_code(KeyCode::F(5));
+    let message = harness
+        .app_state
+        .status_line
+        .message()
+        .map(|(text, _)| text.to_owned());
+    assert_eq!(message.as_deref(), Some("F5: theme mono"));
+    harness.press("2");
+    harness.press_code(KeyCode::F(4));
+    assert_eq!(harness.database().pets.len(), 6);
+    // Unbound keys do nothing.
+    harness.press_code(KeyCode::F(6));
+    assert_eq!(harness.database().pets.len(), 6);
+}
+
+#[test]
+fn the_home_tab_lists_the_quick_actions() {
+    let mut harness = Harness::new(shelter());
+    assert!(!harness.screen().contains("Quick actions"));
+    harness.app_state.quick_actions = quick_actions(&[("F2", ":set status adopted")]);
+    let screen = harness.screen();
+    assert!(screen.contains("Quick actions"), "{}", screen);
+    assert!(screen.contains("F2"), "{}", screen);
+    assert!(screen.contains(":set status adopted"), "{}", screen);
+}
+
+/// `--tutorial` with the steps for the tabs of `harness`.
+fn start_tutorial(harness: &mut Harness) {
+    harness.app_state.tutorial = Some(Tutorial::new(tutorial::steps(&harness.app_state.tabs)));
+}
+
+fn tutorial_step(harness: &Harness) -> usize {
+    harness.app_state.tutorial.as_ref().unwrap().progress().0
+}
+
+#[test]
+fn the_tutorial_moves_on_as_each_step_is_done() {
+    let mut harness = Harness::new(shelter());
+    start_tutorial(&mut harness);
+    assert!(harness.screen().contains("Press p to open the Pets tab."));
+    harness.press(&keys::PETS.to_string());
+    assert_eq!(tutorial_step(&harness), 2);
+    harness.press(&keys::NEXT.to_string());
+    assert_eq!(tutorial_step(&harness), 2);
+    harness.press(&keys::NEXT.to_string());
+    assert_eq!(tutorial_step(&harness), 3);
+    let rest: String = [
+        keys::PREVIOUS,
+        keys::LAST,
+        keys::FIRST,
+        keys::FIRST,
+        keys::CYCLE_SORT,
+        keys::CYCLE_CATEGORY,
+        keys::STATS,
+        keys::HOME,
+    ]
+    .iter()
+    .collect();
+    harness.press(&rest);
+    assert!(harness.app_state.tutorial.as_ref().unwrap().is_over());
+    assert!(harness.screen().contains("You finished the tutorial!"));
+}
+
+#[test]
+fn a_tab_turned_off_does_not_count_for_the_tutorial() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.tabs = vec![MenuItem::Home, MenuItem::Pets];
+    harness.app_state.tutorial = Some(Tutorial::new(vec![tutorial::Step {
+        prompt: "Press t.".to_owned(),
+        expected: Action::ShowTab(MenuItem::Stats),
+        times: 1,
+        target: tutorial::Target::Menu,
+    }]));
+    harness.press(&keys::STATS.to_string());
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Home);
+    assert_eq!(tutorial_step(&harness), 1);
+    assert!(!harness.app_state.tutorial.as_ref().unwrap().is_over());
+}
+
+#[test]
+fn moving_in_an_empty_list_does_not_count_for_the_tutorial() {
+    let mut harness = Harness::new(Vec::new());
+    start_tutorial(&mut harness);
+    harness.press(&format!("{0}{1}{1}", keys::PETS, keys::NEXT));
+    assert_eq!(tutorial_step(&harness), 2);
+    harness.rewrite_db(shelter());
+    harness.press(&format!("{0}{0}", keys::NEXT));
+    assert_eq!(tutorial_step(&harness), 3);
+}
+
+#[test]
+fn the_tutorial_leaves_out_tabs_turned_off() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.tabs = vec![MenuItem::Home, MenuItem::Pets];
+    start_tutorial(&mut harness);
+    let (_, steps) = harness.app_state.tutorial.as_ref().unwrap().progress();
+    assert_eq!(steps, tutorial::steps(&MenuItem::ALL).len() - 1);
+}
+
+#[test]
+fn a_narrow_terminal_still_shows_the_menu_above_the_size_needed() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.terminal = Terminal::new(TestBackend::new(30, 12)).expect("a test terminal");
+    let screen = harness.screen();
+    assert!(screen.contains("h|p|o|u|t|l|%|a|d|e|q"), "{}", screen);
+    assert!(screen.contains("terminal too small"), "{}", screen);
+    harness.terminal = Terminal::new(TestBackend::new(10, 12)).expect("a test terminal");
+    let screen = harness.screen();
+    assert!(
+        screen.lines().nth(1).unwrap().contains(" Pets ("),
+        "{}",
+        screen
+    );
+    harness.terminal = Terminal::new(TestBackend::new(100, 3)).expect("a test terminal");
+    assert!(harness.screen().contains("terminal too"));
+}
+
+/// The rows of `screen`, trimmed at the end.
+fn screen_rows(screen: &str) -> Vec<&str> {
+    screen.lines().map(str::trim_end).collect()
+}
+
+#[test]
+fn the_footer_sits_under_the_status_line() {
+    let rects = create_app_rects(Rect::new(0, 0, 100, 30), true).unwrap();
+    assert_eq!(rects.menu, Rect::new(2, 2, 96, 3));
+    assert_eq!(rects.main_widget, Rect::new(2, 5, 96, 19));
+    assert_eq!(rects.status, Rect::new(2, 24, 96, 1));
+    assert_eq!(rects.footer, Some(Rect::new(2, 25, 96, 3)));
+
+    let mut harness = Harness::new(shelter());
+    let screen = harness.screen();
+    let rows = screen_rows(&screen);
+    assert!(rows[23].ends_with('+'), "{}", screen);
+    assert!(rows[24].starts_with("   4 pets |"), "{}", screen);
+    assert!(rows[25].starts_with("  +Copyright"), "{}", screen);
+    assert!(rows[26].contains("pet-CLI"), "{}", screen);
+    assert!(rows[27].ends_with('+'), "{}", screen);
+}
+
+#[test]
+fn without_the_footer_the_main_view_reaches_the_status_line() {
+    let rects = create_app_rects(Rect::new(0, 0, 100, 30), false).unwrap();
+    assert_eq!(rects.menu, Rect::new(2, 2, 96, 3));
+    assert_eq!(rects.main_widget, Rect::new(2, 5, 96, 22));
+    assert_eq!(rects.status, Rect::new(2, 27, 96, 1));
+    assert_eq!(rects.footer, None);
+
+    let mut harness = Harness::new(shelter());
+    harness.app_state.footer = None;
+    let screen = harness.screen();
+    let rows = screen_rows(&screen);
+    assert!(rows[26].ends_with('+'), "{}", screen);
+    assert!(rows[27].starts_with("   4 pets |"), "{}", screen);
+    assert!(!screen.contains("Copyright"), "{}", screen);
+    // The room the footer had goes to the Home tab, which now fits its stats.
+    assert!(screen.contains("+Stats"), "{}", screen);
+}
+
+#[test]
+fn on_low_terminals_the_footer_gives_way_first() {
+    assert!(create_app_rects(Rect::new(0, 0, 100, MIN_HEIGHT - 1), true).is_none());
+    // (height, main view rows, footer rows)
+    for (height, main, footer) in [(10, 2, 0), (11, 2, 1), (12, 2, 2), (13, 2, 3), (14, 3, 3)] {
+        let rects = create_app_rects(Rect::new(0, 0, 100, height), true).unwrap();
+        assert_eq!(rects.menu, Rect::new(2, 2, 96, MENU_HEIGHT), "{}", height);
+        assert_eq!(rects.main_widget, Rect::new(2, 5, 96, main), "{}", height);
+        assert_eq!(rects.status, Rect::new(2, 5 + main, 96, 1), "{}", height);
+        let below = rects.footer.unwrap();
+        assert_eq!(below, Rect::new(2, 6 + main, 96, footer), "{}", height);
+        assert!(below.bottom() <= height - 2, "{}", height);
+    }
+
+    let mut harness = Harness::new(shelter());
+    harness.terminal = Terminal::new(TestBackend::new(60, 10)).expect("a test terminal");
+    let screen = harness.screen();
+    assert!(
+        screen_rows(&screen)[7].starts_with("   4 pets |"),
+        "{}",
+        screen
+    );
+    assert!(!screen.contains("Copyright"), "{}", screen);
+    harness.terminal = Terminal::new(TestBackend::new(60, 13)).expect("a test terminal");
+    let screen = harness.screen();
+    let rows = screen_rows(&screen);
+    assert!(rows[7].starts_with("   4 pets |"), "{}", screen);
+    assert!(rows[8].starts_with("  +Copyright"), "{}", screen);
+    assert!(rows[9].contains("pet-CLI"), "{}", screen);
+}
+
+#[test]
+fn the_hotkey_of_a_tab_turned_off_says_so() {
+    let mut harness = Harness::new(shelter());
+    harness.app_state.tabs = vec![MenuItem::Pets, MenuItem::Home];
+    harness.app_state.menu = menu::entries(&harness.app_state.tabs);
+    harness.press("p");
+    harness.press(&keys::STATS.to_string());
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Pets);
+    assert!(harness
+        .screen()
+        .contains("the Stats tab is turned off in the config"));
+    harness.press("\t");
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Home);
+    harness.press("\t");
+    assert_eq!(harness.app_state.active_menu_item, MenuItem::Pets);
+}
+
+/// The shelter with every pet in the Trash, the last one trashed most
+/// recently and so listed first.
+fn trashed_shelter() -> Vec<Pet> {
+    let mut pets = shelter();
+    for pet in &mut pets {
+        pet.archived_at = Some(
+            Utc.with_ymd_and_hms(2024, 3, 1, 0, pet.id as u32, 0)
+                .unwrap(),
+        );
+    }
+    pets
+}
+
+/// The first word of the highlighted row, the name on a list of pets.
+fn highlighted_name(harness: &mut Harness) -> String {
+    let row = harness.highlighted().unwrap();
+    row.split_whitespace().next().unwrap().to_owned()
+}
+
+#[test]
+fn the_trash_keeps_its_selection_on_its_pet_while_another_tab_is_shown() {
+    let mut harness = Harness::new(trashed_shelter());
+    harness.press("tjj");
+    assert_eq!(highlighted_name(&mut harness), "Rex");
+    harness.press("p");
+    let mut pets = trashed_shelter();
+    pets.retain(|pet| pet.id != 4);
+    harness.rewrite_db(pets.clone());
+    harness.press("t");
+    assert_eq!(highlighted_name(&mut harness), "Rex");
+    assert_eq!(
+        harness.app_state.tab_views.list(MenuItem::Trash).selected(),
+        Some(1)
+    );
+
+    harness.press("p");
+    let mut newest = pet(5, "Pip", "cats");
+    newest.archived_at = Some(Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap());
+    pets.push(newest);
+    harness.rewrite_db(pets);
+    harness.press("t");
+    assert_eq!(highlighted_name(&mut harness), "Rex");
+    assert_eq!(
+        harness.app_state.tab_views.list(MenuItem::Trash).selected(),
+        Some(2)
+    );
+}
+
+#[test]
+fn a_trash_selection_whose_pet_is_gone_stays_on_its_row() {
+    let mut harness = Harness::new(trashed_shelter());
+    harness.press("tjjj");
+    assert_eq!(highlighted_name(&mut harness), "Coco");
+    harness.press("p");
+    let mut pets = trashed_shelter();
+    pets.retain(|pet| pet.id != 1);
+    harness.rewrite_db(pets.clone());
+    harness.press("t");
+    assert_eq!(highlighted_name(&mut harness), "Rex");
+    harness.press("p");
+    pets.retain(|pet| pet.id == 4);
+    harness.rewrite_db(pets);
+    harness.press("t");
+    assert_eq!(highlighted_name(&mut harness), "Max");
+}
+
+#[test]
+fn the_pets_tab_keeps_its_pet_while_another_tab_is_shown() {
+    let mut harness = Harness::new(shelter());
+    harness.press("pjj");
+    assert_eq!(highlighted_name(&mut harness), "Bun");
+    harness.press("t");
+    let mut pets = shelter();
+    pets.insert(0, pet(5, "Pip", "cats"));
+    pets.retain(|pet| pet.id != 1);
+    harness.rewrite_db(pets);
+    harness.press("p");
+    assert_eq!(highlighted_name(&mut harness), "Bun");
+}
+
+#[test]
+fn each_list_tab_keeps_its_own_selection() {
+    let mut harness = Harness::new(trashed_shelter());
+    harness.press("tj");
+    harness.press("l");
+    assert_eq!(
+        harness.app_state.tab_views.list(MenuItem::Log).selected(),
+        None
+    );
+    harness.press("t");
+    assert_eq!(highlighted_name(&mut harness), "Bun");
+}
+
+#[test]
+fn stored_selections_follow_their_pet_on_the_next_run() {
+    let mut harness = Harness::new(trashed_shelter());
+    harness.press("tjj");
+    assert_eq!(highlighted_name(&mut harness), "Rex");
+    let stored = serde_json::to_string(&harness.app_state.tab_views.store()).unwrap();
+    let stored: Vec<tab_views::StoredSelection> = serde_json::from_str(&stored).unwrap();
+    assert!(stored
+        .iter()
+        .all(|selection| selection.tab != MenuItem::Log));
+
+    let mut pets = trashed_shelter();
+    pets.retain(|pet| pet.id != 3);
+    harness.rewrite_db(pets);
+    harness.app_state.tab_views = TabViews::default();
+    harness.app_state.tab_views.restore(&stored);
+    assert_eq!(highlighted_name(&mut harness), "Rex");
+    assert_eq!(
+        harness.app_state.tab_views.list(MenuItem::Trash).selected(),
+        Some(1)
+    );
+
+    // A tab without a view, as in an edited state file, is left out.
+    let edited = [tab_views::StoredSelection {
+        tab: MenuItem::Pets,
+        row: 3,
+        id: None,
+    }];
+    harness.app_state.tab_views = TabViews::default();
+    harness.app_state.tab_views.restore(&edited);
+    assert_eq!(highlighted_name(&mut harness), "Max");
+}
+
+#[test]
+fn ticks_redraw_nothing_without_animations() {
+    let mut harness = Harness::new(shelter());
+    harness.press("p");
+    harness.screen();
+    harness.app_state.dirty = false;
+    harness.send((0..10).map(|_| AppEvent::Tick).collect());
+    assert!(!harness.app_state.dirty);
+}
@@ -0,0 +1,128 @@
+use crate::age;
+use crate::attributes;
+use crate::microchip::{self, MicrochipIssue};
+use crate::Pet;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// In months.
+pub const AGE_RANGE: RangeInclusive<usize> = 0..=60 * 12;
+/// In characters.
+pub const MAX_NAME_LENGTH: usize = 100;
+pub const MAX_NOTES_LENGTH: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    EmptyName,
+    /// The length, in characters.
+    NameTooLong(usize),
+    AgeOutOfRange(usize),
+    EmptyCategory,
+    Microchip(MicrochipIssue),
+    AttributeKey(String),
+    /// The length, in characters.
+    NotesTooLong(usize),
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::EmptyName => write!(f, "name is empty"),
+            ValidationIssue::NameTooLong(length) => write!(
+                f,
+                "name is {} characters, more than {}",
+                length, MAX_NAME_LENGTH
+            ),
+            ValidationIssue::AgeOutOfRange(months) => write!(
+                f,
+                "age {} is outside {}..={}",
+                age::format(*months),
+                age::format(*AGE_RANGE.start()),
+                age::format(*AGE_RANGE.end())
+            ),
+            ValidationIssue::EmptyCategory => write!(f, "category is empty"),
+            ValidationIssue::Microchip(issue) => write!(f, "{}", issue),
+            ValidationIssue::AttributeKey(key) => {
+                write!(
+                    f,
+                    "attribute key {:?} is empty or has control characters",
+                    key
+                )
+            }
+            ValidationIssue::NotesTooLong(length) => write!(
+                f,
+                "notes are {} characters, more than {}",
+                length, MAX_NOTES_LENGTH
+            ),
+        }
+    }
+}
+
+/// Everything wrong with `pet`, in field order. Empty for a valid pet.
+pub fn validate(pet: &Pet) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    if pet.name.trim().is_empty() {
+        issues.push(ValidationIssue::EmptyName);
+    }
+    let name_length = pet.name.chars().count();
+    if name_length > MAX_NAME_LENGTH {
+        issues.push(ValidationIssue::NameTooLong(name_length));
+    }
+    if !AGE_RANGE.contains(&pet.age.months) {
+        issues.push(ValidationIssue::AgeOutOfRange(pet.age.months));
+    }
+    if pet.category.trim().is_empty() {
+        issues.push(ValidationIssue::EmptyCategory);
+    }
+    if let Some(Err(issue)) = pet.microchip.as_deref().map(microchip::check) {
+        issues.push(ValidationIssue::Microchip(issue));
+    }
+    for key in pet.attributes.keys() {
+        if !attributes::valid_key(key) {
+            issues.push(ValidationIssue::AttributeKey(key.clone()));
+        }
+    }
+    let notes_length = pet
+        .notes
+        .as_deref()
+        .map_or(0, |notes| notes.chars().count());
+    if notes_length > MAX_NOTES_LENGTH {
+        issues.push(ValidationIssue::NotesTooLong(notes_length));
+    }
+    issues
+}
+
+/// `issues` as a single line for the status bar or the detail pane.
+pub fn describe(issues: &[ValidationIssue]) -> String {
+    issues
+        .iter()
+        .map(ValidationIssue::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+
+    #[test]
+    fn bad_attribute_keys_are_issues() {
+        let mut rex = pet(1, "Rex", "dogs");
+        rex.attributes.insert("diet".to_owned(), "raw".to_owned());
+        assert!(validate(&rex).is_empty());
+        rex.attributes.insert(" ".to_owned(), "x".to_owned());
+        rex.attributes.insert("a\tb".to_owned(), "y".to_owned());
+        assert_eq!(
+            validate(&rex),
+            [
+                ValidationIssue::AttributeKey(" ".to_owned()),
+                ValidationIssue::AttributeKey("a\tb".to_owned())
+            ]
+        );
+        assert_eq!(
+            describe(&validate(&rex)[1..]),
+            "attribute key \"a\\tb\" is empty or has control characters"
+        );
+    }
+}
@@ -0,0 +1,462 @@
+//! What the Pets list shows: the pets outside the Trash, narrowed down by
+//! category, status and name and put in order. `pet-cli list` and `report`
+//! can also include the Trash and leave out older pets. The TUI keeps one of these for the
+//! Pets tab, and `pet-cli list` and `report` build one from their flags, so a
+//! view set up interactively can be reproduced from the command line.
+
+use crate::filter::CategoryFilter;
+use crate::pet_status::PetStatus;
+use crate::search::Query;
+use crate::Pet;
+use chrono::{Local, NaiveDate};
+use clap::ValueEnum;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum SortKey {
+    Id,
+    Name,
+    Category,
+    Age,
+    Created,
+}
+
+impl SortKey {
+    /// Names and categories ignore case; ages are as shown on `today`, from
+    /// the birthdate where there is one, with birthdates still to come
+    /// first.
+    fn compare(self, a: &Pet, b: &Pet, today: NaiveDate) -> Ordering {
+        match self {
+            SortKey::Id => a.id.cmp(&b.id),
+            SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortKey::Category => a.category.to_lowercase().cmp(&b.category.to_lowercase()),
+            SortKey::Age => a.age_months(today).cmp(&b.age_months(today)),
+            SortKey::Created => a.created_at.cmp(&b.created_at),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SortKey::Id => "id",
+            SortKey::Name => "name",
+            SortKey::Category => "category",
+            SortKey::Age => "age",
+            SortKey::Created => "created",
+        }
+    }
+}
+
+/// An order for the pets, written `age` or `age:desc` on the command line.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl Sort {
+    /// The next key to sort by after `sort`, in ascending order, or `None`
+    /// for the DB's order after the last key.
+    pub fn cycle(sort: Option<Sort>) -> Option<Sort> {
+        let keys = SortKey::value_variants();
+        let next = match sort {
+            None => keys.first(),
+            Some(sort) => keys.iter().skip_while(|key| **key != sort.key).nth(1),
+        };
+        next.map(|key| Sort {
+            key: *key,
+            descending: false,
+        })
+    }
+}
+
+impl FromStr for Sort {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (key, direction) = input.split_once(':').unwrap_or((input, "asc"));
+        let key = SortKey::from_str(key, true)?;
+        let descending = match direction {
+            "asc" => false,
+            "desc" => true,
+            _ => {
+                return Err(format!(
+                    "invalid direction '{}', expected asc or desc",
+                    direction
+                ))
+            }
+        };
+        Ok(Sort { key, descending })
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = if self.descending { "desc" } else { "asc" };
+        write!(f, "{}:{}", self.key.name(), direction)
+    }
+}
+
+#[derive(Default)]
+pub struct ViewParams {
+    pub category: CategoryFilter,
+    pub status: Option<PetStatus>,
+    /// `None` keeps the order of the DB.
+    pub sort: Option<Sort>,
+    /// Only pets matching this search; see `search`.
+    pub query: Option<Query>,
+    /// Whether pets in the Trash are shown too.
+    pub include_archived: bool,
+    /// Only pets added on this day, in UTC, or later.
+    pub since: Option<NaiveDate>,
+}
+
+impl ViewParams {
+    /// Whether some pets in the DB, besides those in the Trash, may be left
+    /// out.
+    pub fn is_filtered(&self) -> bool {
+        self.category.is_active()
+            || self.status.is_some()
+            || self.query.is_some()
+            || self.since.is_some()
+    }
+
+    pub fn matches(&self, pet: &Pet) -> bool {
+        self.matches_on(pet, Local::now().date_naive())
+    }
+
+    /// Whether `pet` is in the view, with the ages the search compares taken
+    /// on `today`.
+    fn matches_on(&self, pet: &Pet, today: NaiveDate) -> bool {
+        (self.include_archived || !pet.is_archived())
+            && self
+                .since
+                .is_none_or(|since| pet.created_at.date_naive() >= since)
+            && self.category.matches(pet)
+            && self.status.is_none_or(|status| pet.status == status)
+            && self
+                .query
+                .as_ref()
+                .is_none_or(|query| query.matches(pet, today))
+    }
+
+    /// The pets of the view, in its order. Pets that sort the same stay in
+    /// the order of the DB.
+    pub fn apply<'a>(&self, pets: &'a [Pet]) -> Vec<&'a Pet> {
+        let today = Local::now().date_naive();
+        let mut shown: Vec<&Pet> = pets
+            .iter()
+            .filter(|pet| self.matches_on(pet, today))
+            .collect();
+        if let Some(sort) = self.sort {
+            shown.sort_by(|a, b| {
+                let order = sort.key.compare(a, b, today);
+                if sort.descending {
+                    order.reverse()
+                } else {
+                    order
+                }
+            });
+        }
+        shown
+    }
+
+    /// The flags of `pet-cli list` and `report` that give the same view.
+    pub fn to_args(&self) -> String {
+        let mut args = Vec::new();
+        if let Some(sort) = self.sort {
+            args.push(format!("--sort {}", sort));
+        }
+        if let Some(category) = self.category.category() {
+            args.push(format!("--category {}", shell_quote(category)));
+        }
+        if let Some(status) = self.status {
+            args.push(format!("--status {}", status));
+        }
+        if let Some(query) = &self.query {
+            args.push(format!("--query {}", shell_quote(query.text())));
+        }
+        if self.include_archived {
+            args.push("--include-archived".to_owned());
+        }
+        if let Some(since) = self.since {
+            args.push(format!("--since {}", since.format("%Y-%m-%d")));
+        }
+        args.join(" ")
+    }
+}
+
+fn shell_quote(text: &str) -> String {
+    let plain = !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_.,/".contains(c));
+    if plain {
+        text.to_owned()
+    } else {
+        format!("'{}'", text.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command};
+    use crate::ui_tests::pet;
+    use chrono::{TimeZone, Utc};
+    use clap::Parser;
+
+    fn shelter() -> Vec<Pet> {
+        let mut pets = vec![
+            pet(4, "rex", "Dogs"),
+            pet(2, "Coco", "cats"),
+            pet(7, "Bun", "rabbits"),
+            pet(1, "Max", "dogs"),
+            pet(9, "coco", "Cats"),
+            pet(3, "Mia", "cats"),
+        ];
+        for (index, pet) in pets.iter_mut().enumerate() {
+            pet.age.months = [30, 12, 7, 30, 96, 12][index];
+            pet.created_at = Utc
+                .with_ymd_and_hms(2024, 1 + index as u32, 1, 0, 0, 0)
+                .unwrap();
+        }
+        pets[3].status = PetStatus::Adopted;
+        pets[5].archived_at = Some(Utc.with_ymd_and_hms(2024, 8, 1, 0, 0, 0).unwrap());
+        pets
+    }
+
+    fn ids(pets: &[&Pet]) -> Vec<usize> {
+        pets.iter().map(|pet| pet.id).collect()
+    }
+
+    fn sorted(input: &str) -> ViewParams {
+        ViewParams {
+            sort: Some(input.parse().unwrap()),
+            ..ViewParams::default()
+        }
+    }
+
+    /// `args` split as a shell would, for the quoting `to_args` does.
+    fn shell_words(args: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut word: Option<String> = None;
+        let mut quoted = false;
+        let mut chars = args.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' => {
+                    quoted = !quoted;
+                    word.get_or_insert_with(String::new);
+                }
+                '\\' if !quoted => word.get_or_insert_with(String::new).extend(chars.next()),
+                ' ' if !quoted => words.extend(word.take()),
+                c => word.get_or_insert_with(String::new).push(c),
+            }
+        }
+        words.extend(word);
+        words
+    }
+
+    /// The view `pet-cli list` builds from `args`.
+    fn list_view(args: &str) -> ViewParams {
+        let cli = Cli::try_parse_from(
+            vec!["pet-cli".to_owned(), "list".to_owned()]
+                .into_iter()
+                .chain(shell_words(args)),
+        )
+        .unwrap_or_else(|err| panic!("`list {}`: {}", args, err));
+        match cli.command {
+            Some(Command::List { view, .. }) => view.params(),
+            command => panic!("`list {}` is {:?}", args, command),
+        }
+    }
+
+    #[test]
+    fn sorts_ignoring_case_and_keeps_ties_in_db_order() {
+        let pets = shelter();
+        assert_eq!(ids(&sorted("name").apply(&pets)), [7, 2, 9, 1, 4]);
+        assert_eq!(ids(&sorted("name:desc").apply(&pets)), [4, 1, 2, 9, 7]);
+        assert_eq!(ids(&sorted("category").apply(&pets)), [2, 9, 4, 1, 7]);
+        assert_eq!(ids(&sorted("age").apply(&pets)), [7, 2, 4, 1, 9]);
+        assert_eq!(ids(&sorted("id:desc").apply(&pets)), [9, 7, 4, 2, 1]);
+        assert_eq!(ids(&sorted("created:desc").apply(&pets)), [9, 1, 7, 2, 4]);
+        assert_eq!(ids(&ViewParams::default().apply(&pets)), [4, 2, 7, 1, 9]);
+    }
+
+    #[test]
+    fn filters_by_category_status_query_and_date() {
+        let pets = shelter();
+        let view = ViewParams {
+            category: CategoryFilter::only(Some("dogs".to_owned())),
+            ..ViewParams::default()
+        };
+        assert_eq!(ids(&view.apply(&pets)), [1]);
+        let view = ViewParams {
+            status: Some(PetStatus::Available),
+            query: Some("coco".parse().unwrap()),
+            ..ViewParams::default()
+        };
+        assert_eq!(ids(&view.apply(&pets)), [2, 9]);
+        let view = ViewParams {
+            since: NaiveDate::from_ymd_opt(2024, 4, 1),
+            include_archived: true,
+            ..ViewParams::default()
+        };
+        assert_eq!(ids(&view.apply(&pets)), [1, 9, 3]);
+        assert!(view.is_filtered());
+        assert!(!ViewParams::default().is_filtered());
+        assert!(!sorted("age").is_filtered());
+    }
+
+    #[test]
+    fn the_cli_flags_of_a_view_give_the_same_pets_in_the_same_order() {
+        let pets = shelter();
+        let views = [
+            ViewParams::default(),
+            sorted("name:desc"),
+            ViewParams {
+                sort: Some("age".parse().unwrap()),
+                category: CategoryFilter::only(Some("Cats".to_owned())),
+                ..ViewParams::default()
+            },
+            ViewParams {
+                sort: Some("category:desc".parse().unwrap()),
+                status: Some(PetStatus::Available),
+                query: Some("name:\"co co\" age:>1".parse().unwrap()),
+                ..ViewParams::default()
+            },
+            ViewParams {
+                query: Some("it's".parse().unwrap()),
+                include_archived: true,
+                since: NaiveDate::from_ymd_opt(2024, 2, 1),
+                ..ViewParams::default()
+            },
+            ViewParams {
+                sort: Some("created".parse().unwrap()),
+                category: CategoryFilter::only(Some("guinea pigs".to_owned())),
+                ..ViewParams::default()
+            },
+        ];
+        for view in views {
+            let args = view.to_args();
+            let from_cli = list_view(&args);
+            assert_eq!(from_cli.to_args(), args);
+            assert_eq!(
+                ids(&from_cli.apply(&pets)),
+                ids(&view.apply(&pets)),
+                "{}",
+                args
+            );
+        }
+    }
+
+    /// Pets with few distinct names, categories, ages and creation times, so
+    /// that every key has ties, and ids and ages of one and two digits.
+    fn many_pets() -> Vec<Pet> {
+        let names = ["bun", "Bun", "coco", "Max", "max", "Äne", "rex"];
+        let categories = ["cats", "Cats", "dogs", "birds"];
+        let mut pets: Vec<Pet> = (0..60)
+            .map(|n| {
+                let mut pet = pet(
+                    (n * 37) % 61 + 1,
+                    names[n % names.len()],
+                    categories[(n / 2) % categories.len()],
+                );
+                pet.age.months = [9, 10, 2, 100, 10][n % 5];
+                pet.created_at = Utc
+                    .with_ymd_and_hms(2024, 1 + (n % 3) as u32, 1, 0, 0, 0)
+                    .unwrap();
+                if n % 7 == 0 {
+                    pet.birthdate = NaiveDate::from_ymd_opt(2020 + (n % 4) as i32, 3, 1);
+                }
+                pet
+            })
+            .collect();
+        pets.reverse();
+        pets
+    }
+
+    #[test]
+    fn every_order_is_total_and_keeps_ties_in_db_order() {
+        let pets = many_pets();
+        let today = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        for key in SortKey::value_variants() {
+            for a in &pets {
+                assert_eq!(key.compare(a, a, today), Ordering::Equal);
+                for b in &pets {
+                    let ab = key.compare(a, b, today);
+                    assert_eq!(ab, key.compare(b, a, today).reverse(), "{:?}", key);
+                    for c in pets.iter().step_by(3) {
+                        if ab != Ordering::Greater && key.compare(b, c, today) != Ordering::Greater
+                        {
+                            assert_ne!(key.compare(a, c, today), Ordering::Greater, "{:?}", key);
+                        }
+                    }
+                }
+            }
+            for descending in [false, true] {
+                let view = ViewParams {
+                    sort: Some(Sort {
+                        key: *key,
+                        descending,
+                    }),
+                    ..ViewParams::default()
+                };
+                let shown = view.apply(&pets);
+                assert_eq!(shown.len(), pets.len());
+                let position = |pet: &Pet| pets.iter().position(|p| std::ptr::eq(p, pet));
+                let today = Local::now().date_naive();
+                for pair in shown.windows(2) {
+                    let order = key.compare(pair[0], pair[1], today);
+                    let expected = if descending {
+                        Ordering::Greater
+                    } else {
+                        Ordering::Less
+                    };
+                    if order == Ordering::Equal {
+                        assert!(position(pair[0]) < position(pair[1]), "{:?}", key);
+                    } else {
+                        assert_eq!(order, expected, "{:?}", key);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ids_and_ages_sort_as_numbers() {
+        let mut pets = vec![pet(10, "Ten", "cats"), pet(9, "Nine", "cats")];
+        pets[0].age.months = 9;
+        pets[1].age.months = 10;
+        assert_eq!(ids(&sorted("id").apply(&pets)), [9, 10]);
+        assert_eq!(ids(&sorted("age").apply(&pets)), [10, 9]);
+        // A birthdate counts for the age, not the age stored beside it.
+        pets[0].birthdate = Some(Local::now().date_naive() - chrono::Duration::days(2000));
+        assert_eq!(ids(&sorted("age").apply(&pets)), [9, 10]);
+    }
+
+    #[test]
+    fn sorts_read_and_print_the_same() {
+        for input in ["id:asc", "name:desc", "age:asc", "created:desc"] {
+            assert_eq!(input.parse::<Sort>().unwrap().to_string(), input);
+        }
+        assert_eq!("Age".parse::<Sort>().unwrap().to_string(), "age:asc");
+        assert!("age:up".parse::<Sort>().is_err());
+        assert!("weight".parse::<Sort>().is_err());
+    }
+
+    #[test]
+    fn cycling_goes_through_every_key_and_back_to_the_db_order() {
+        let mut sort = None;
+        let mut keys = Vec::new();
+        loop {
+            sort = Sort::cycle(sort);
+            let Some(next) = sort else { break };
+            assert!(!next.descending);
+            keys.push(next.key);
+        }
+        assert_eq!(keys, SortKey::value_variants());
+    }
+}
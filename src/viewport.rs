@@ -0,0 +1,30 @@
+use std::ops::Range;
+
+/// The rows of a long list that are on screen. Only these are turned into
+/// list items, so drawing costs the same for 50 pets as for 50,000.
+///
+/// `tui`'s `ListState` keeps its own scroll offset private, so the offset is
+/// tracked here instead and scrolls the same way: just enough to keep the
+/// selection in view.
+#[derive(Default)]
+pub struct Viewport {
+    offset: usize,
+}
+
+impl Viewport {
+    /// Scrolls so that `selected` is within `height` rows and returns the
+    /// indices of the rows to draw.
+    pub fn window(&mut self, selected: Option<usize>, len: usize, height: usize) -> Range<usize> {
+        let height = height.max(1);
+        if let Some(selected) = selected {
+            if selected < self.offset {
+                self.offset = selected;
+            } else if selected >= self.offset + height {
+                self.offset = selected + 1 - height;
+            }
+        }
+        // Don't leave empty rows at the bottom after pets were removed.
+        self.offset = self.offset.min(len.saturating_sub(height));
+        self.offset..len.min(self.offset + height)
+    }
+}
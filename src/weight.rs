@@ -0,0 +1,74 @@
+//! Weigh-ins of a pet and the points of its weight chart.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WeightEntry {
+    pub date: NaiveDate,
+    pub grams: u32,
+}
+
+/// `850 g` under a kilogram, otherwise kilograms with two decimals.
+pub fn format(grams: u32) -> String {
+    if grams < 1000 {
+        format!("{} g", grams)
+    } else {
+        format!("{:.2} kg", f64::from(grams) / 1000.0)
+    }
+}
+
+/// Reads a weight typed as `4.2kg`, `4,2 kg` or `850g` into grams. The unit
+/// is required, as with ages.
+pub fn parse(input: &str) -> Result<u32, String> {
+    let invalid = || {
+        format!(
+            "invalid weight '{}', expected kilograms or grams like 4.2kg or 850g",
+            input
+        )
+    };
+    let text = input.trim().to_lowercase().replace(',', ".");
+    let (number, grams_per_unit) = if let Some(number) = text.strip_suffix("kg") {
+        (number, 1000.0)
+    } else if let Some(number) = text.strip_suffix('g') {
+        (number, 1.0)
+    } else {
+        return Err(invalid());
+    };
+    let number: f64 = number.trim().parse().map_err(|_| invalid())?;
+    let grams = (number * grams_per_unit).round();
+    if !grams.is_finite() || grams <= 0.0 || grams > f64::from(u32::MAX) {
+        return Err(invalid());
+    }
+    Ok(grams as u32)
+}
+
+/// The entries as chart points, oldest first: days since the first weigh-in
+/// against kilograms.
+pub fn points(entries: &[WeightEntry]) -> Vec<(f64, f64)> {
+    let mut entries: Vec<&WeightEntry> = entries.iter().collect();
+    entries.sort_by_key(|entry| entry.date);
+    let Some(first) = entries.first().map(|entry| entry.date) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .map(|entry| {
+            let days = (entry.date - first).num_days() as f64;
+            (days, f64::from(entry.grams) / 1000.0)
+        })
+        .collect()
+}
+
+/// Axis bounds that fit `values`, padded so a single value or a flat line
+/// sits in the middle rather than on the edge.
+pub fn bounds(values: impl Iterator<Item = f64>, min_span: f64) -> [f64; 2] {
+    let (low, high) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| {
+        (low.min(value), high.max(value))
+    });
+    if !low.is_finite() {
+        return [0.0, min_span];
+    }
+    let padding = ((high - low) * 0.1).max(min_span / 2.0);
+    [low - padding, high + padding]
+}
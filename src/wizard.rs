@@ -0,0 +1,233 @@
+use crate::theme::THEME_NAMES;
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// How many pets the wizard adds for a first look around.
+pub const SAMPLE_PETS: usize = 5;
+
+/// The first-run setup, shown before the UI when there is neither a DB nor a
+/// config file yet. It creates the DB, optionally with sample pets, and
+/// writes the theme chosen to a new config file.
+pub struct Wizard {
+    step: Step,
+    samples: bool,
+    theme: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Step {
+    /// Where the DB will be created.
+    Location,
+    Samples,
+    Theme,
+}
+
+pub enum WizardOutcome {
+    Open,
+    /// Esc: an empty DB and no config file.
+    Skipped,
+    Finished,
+}
+
+impl Default for Wizard {
+    fn default() -> Self {
+        Wizard {
+            step: Step::Location,
+            samples: true,
+            theme: 0,
+        }
+    }
+}
+
+impl Wizard {
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    pub fn samples(&self) -> bool {
+        self.samples
+    }
+
+    pub fn theme(&self) -> &'static str {
+        THEME_NAMES[self.theme]
+    }
+
+    /// The choices of the current step and which is highlighted; none for
+    /// the location, which is only confirmed.
+    pub fn choices(&self) -> (Vec<String>, usize) {
+        match self.step {
+            Step::Location => (Vec::new(), 0),
+            Step::Samples => (
+                vec![
+                    format!("Add {} sample pets", SAMPLE_PETS),
+                    "Start with an empty DB".to_owned(),
+                ],
+                if self.samples { 0 } else { 1 },
+            ),
+            Step::Theme => (
+                THEME_NAMES.iter().map(|name| name.to_string()).collect(),
+                self.theme,
+            ),
+        }
+    }
+
+    /// Up and down choose, Enter goes on to the next step and Left or
+    /// Backspace back to the one before. Esc skips the rest.
+    pub fn handle_key(&mut self, key: KeyEvent) -> WizardOutcome {
+        match key.code {
+            KeyCode::Esc => WizardOutcome::Skipped,
+            KeyCode::Left | KeyCode::Backspace => {
+                self.step = match self.step {
+                    Step::Location | Step::Samples => Step::Location,
+                    Step::Theme => Step::Samples,
+                };
+                WizardOutcome::Open
+            }
+            _ => match self.step {
+                Step::Location => self.location(key),
+                Step::Samples => self.choose_samples(key),
+                Step::Theme => self.choose_theme(key),
+            },
+        }
+    }
+
+    fn location(&mut self, key: KeyEvent) -> WizardOutcome {
+        if key.code == KeyCode::Enter {
+            self.step = Step::Samples;
+        }
+        WizardOutcome::Open
+    }
+
+    fn choose_samples(&mut self, key: KeyEvent) -> WizardOutcome {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.samples = true,
+            KeyCode::Down | KeyCode::Char('j') => self.samples = false,
+            KeyCode::Enter => self.step = Step::Theme,
+            _ => {}
+        }
+        WizardOutcome::Open
+    }
+
+    fn choose_theme(&mut self, key: KeyEvent) -> WizardOutcome {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.theme = self.theme.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.theme = (self.theme + 1).min(THEME_NAMES.len() - 1)
+            }
+            KeyCode::Enter => return WizardOutcome::Finished,
+            _ => {}
+        }
+        WizardOutcome::Open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(wizard: &mut Wizard, codes: &[KeyCode]) -> WizardOutcome {
+        let mut outcome = WizardOutcome::Open;
+        for code in codes {
+            outcome = wizard.handle_key(KeyEvent::new(*code, KeyModifiers::NONE));
+        }
+        outcome
+    }
+
+    #[test]
+    fn starts_at_the_location_with_samples_and_the_first_theme() {
+        let wizard = Wizard::default();
+        assert_eq!(wizard.step(), Step::Location);
+        assert!(wizard.samples());
+        assert_eq!(wizard.theme(), "dark");
+        assert_eq!(wizard.choices(), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn enter_goes_through_every_step_to_finished() {
+        let mut wizard = Wizard::default();
+        assert!(matches!(
+            press(&mut wizard, &[KeyCode::Enter]),
+            WizardOutcome::Open
+        ));
+        assert_eq!(wizard.step(), Step::Samples);
+        assert_eq!(
+            wizard.choices(),
+            (
+                vec![
+                    "Add 5 sample pets".to_owned(),
+                    "Start with an empty DB".to_owned()
+                ],
+                0
+            )
+        );
+        assert!(matches!(
+            press(&mut wizard, &[KeyCode::Enter]),
+            WizardOutcome::Open
+        ));
+        assert_eq!(wizard.step(), Step::Theme);
+        assert_eq!(wizard.choices().0, ["dark", "light", "mono"]);
+        assert!(matches!(
+            press(&mut wizard, &[KeyCode::Enter]),
+            WizardOutcome::Finished
+        ));
+        assert!(wizard.samples());
+        assert_eq!(wizard.theme(), "dark");
+    }
+
+    #[test]
+    fn up_and_down_choose_samples_and_theme() {
+        let mut wizard = Wizard::default();
+        press(&mut wizard, &[KeyCode::Enter, KeyCode::Char('j')]);
+        assert!(!wizard.samples());
+        assert_eq!(wizard.choices().1, 1);
+        press(&mut wizard, &[KeyCode::Up, KeyCode::Down, KeyCode::Enter]);
+        assert!(!wizard.samples());
+
+        press(&mut wizard, &[KeyCode::Down]);
+        assert_eq!((wizard.theme(), wizard.choices().1), ("light", 1));
+        press(&mut wizard, &[KeyCode::Char('j'), KeyCode::Down]);
+        assert_eq!(wizard.theme(), "mono");
+        press(&mut wizard, &[KeyCode::Char('k'), KeyCode::Up, KeyCode::Up]);
+        assert_eq!(wizard.theme(), "dark");
+    }
+
+    #[test]
+    fn other_keys_leave_the_step_alone() {
+        let mut wizard = Wizard::default();
+        press(&mut wizard, &[KeyCode::Char('j'), KeyCode::Char('x')]);
+        assert_eq!(wizard.step(), Step::Location);
+        press(&mut wizard, &[KeyCode::Enter, KeyCode::Char('x')]);
+        assert_eq!(wizard.step(), Step::Samples);
+        assert!(wizard.samples());
+    }
+
+    #[test]
+    fn left_and_backspace_go_back_and_keep_the_choices() {
+        let mut wizard = Wizard::default();
+        press(
+            &mut wizard,
+            &[KeyCode::Enter, KeyCode::Down, KeyCode::Enter, KeyCode::Down],
+        );
+        press(&mut wizard, &[KeyCode::Left]);
+        assert_eq!(wizard.step(), Step::Samples);
+        assert!(!wizard.samples());
+        press(&mut wizard, &[KeyCode::Backspace]);
+        assert_eq!(wizard.step(), Step::Location);
+        press(&mut wizard, &[KeyCode::Left]);
+        assert_eq!(wizard.step(), Step::Location);
+        press(&mut wizard, &[KeyCode::Enter, KeyCode::Enter]);
+        assert_eq!(wizard.theme(), "light");
+    }
+
+    #[test]
+    fn esc_skips_from_any_step() {
+        for enters in 0..3 {
+            let mut wizard = Wizard::default();
+            press(&mut wizard, &vec![KeyCode::Enter; enters]);
+            assert!(matches!(
+                press(&mut wizard, &[KeyCode::Esc]),
+                WizardOutcome::Skipped
+            ));
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Named DBs to switch between, like "home" and "foster", from the
+//! `workspaces` config key. `--workspace` picks one at startup and `W` opens
+//! a picker to switch at runtime.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Workspace {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Parses `home=./data/db.json,foster=../foster/db.json`; empty for none.
+pub fn parse(value: &str) -> Result<Vec<Workspace>, String> {
+    let mut workspaces: Vec<Workspace> = Vec::new();
+    for entry in value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (name, path) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=PATH, got '{}'", entry))?;
+        let (name, path) = (name.trim(), path.trim());
+        if name.is_empty() || path.is_empty() {
+            return Err(format!("expected NAME=PATH, got '{}'", entry));
+        }
+        if workspaces.iter().any(|workspace| workspace.name == name) {
+            return Err(format!("'{}' is named twice", name));
+        }
+        workspaces.push(Workspace {
+            name: name.to_owned(),
+            path: PathBuf::from(path),
+        });
+    }
+    Ok(workspaces)
+}
+
+/// The inverse of `parse`.
+pub fn format(workspaces: &[Workspace]) -> String {
+    workspaces
+        .iter()
+        .map(|workspace| format!("{}={}", workspace.name, workspace.path.display()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The workspace called `name`, or why there is none.
+pub fn find<'w>(workspaces: &'w [Workspace], name: &str) -> Result<&'w Workspace, String> {
+    if let Some(workspace) = workspaces.iter().find(|workspace| workspace.name == name) {
+        return Ok(workspace);
+    }
+    if workspaces.is_empty() {
+        return Err(format!(
+            "no workspace '{}': none are set up, see `workspaces` in the config file",
+            name
+        ));
+    }
+    let names: Vec<_> = workspaces
+        .iter()
+        .map(|workspace| workspace.name.as_str())
+        .collect();
+    Err(format!(
+        "no workspace '{}', the workspaces are: {}",
+        name,
+        names.join(", ")
+    ))
+}
+
+/// A row of the picker.
+pub struct Entry {
+    pub workspace: Workspace,
+    /// The pets the DB has outside the Trash, or why they couldn't be
+    /// counted.
+    pub pet_count: Result<usize, String>,
+    /// Whether this is the DB open now.
+    pub current: bool,
+}
+
+/// The `W` overlay listing the workspaces.
+pub struct WorkspacePicker {
+    entries: Vec<Entry>,
+    selected: usize,
+}
+
+pub enum PickerOutcome {
+    Open,
+    Cancelled,
+    Chosen(Workspace),
+}
+
+impl WorkspacePicker {
+    /// Starts on the current workspace.
+    pub fn new(entries: Vec<Entry>) -> Self {
+        let selected = entries.iter().position(|entry| entry.current).unwrap_or(0);
+        WorkspacePicker { entries, selected }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PickerOutcome {
+        let last = self.entries.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return PickerOutcome::Cancelled,
+            KeyCode::Enter => {
+                return match self.entries.get(self.selected) {
+                    Some(entry) => PickerOutcome::Chosen(entry.workspace.clone()),
+                    None => PickerOutcome::Cancelled,
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') if self.selected < last => self.selected += 1,
+            KeyCode::Up | KeyCode::Char('k') if self.selected > 0 => self.selected -= 1,
+            _ => {}
+        }
+        PickerOutcome::Open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn workspace(name: &str, path: &str) -> Workspace {
+        Workspace {
+            name: name.to_owned(),
+            path: PathBuf::from(path),
+        }
+    }
+
+    fn entry(name: &str, current: bool) -> Entry {
+        Entry {
+            workspace: workspace(name, &format!("{}/db.json", name)),
+            pet_count: Ok(0),
+            current,
+        }
+    }
+
+    fn press(picker: &mut WorkspacePicker, code: KeyCode) -> PickerOutcome {
+        picker.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn parses_names_and_paths() {
+        let workspaces = parse(" home = ./data/db.json, foster=../foster/db.json,").unwrap();
+        assert_eq!(
+            workspaces,
+            [
+                workspace("home", "./data/db.json"),
+                workspace("foster", "../foster/db.json")
+            ]
+        );
+        assert_eq!(parse(&format(&workspaces)).unwrap(), workspaces);
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn refuses_entries_without_both_and_names_used_twice() {
+        for value in ["home", "=db.json", "home=", "home= "] {
+            let err = parse(value).unwrap_err();
+            assert!(err.starts_with("expected NAME=PATH"), "{}", err);
+        }
+        assert_eq!(
+            parse("home=a.json,home=b.json").unwrap_err(),
+            "'home' is named twice"
+        );
+    }
+
+    #[test]
+    fn a_missing_workspace_lists_the_others() {
+        let workspaces = [workspace("home", "a.json"), workspace("foster", "b.json")];
+        assert_eq!(
+            find(&workspaces, "foster").unwrap().path,
+            PathBuf::from("b.json")
+        );
+        assert_eq!(
+            find(&workspaces, "work").unwrap_err(),
+            "no workspace 'work', the workspaces are: home, foster"
+        );
+        assert!(find(&[], "work").unwrap_err().contains("none are set up"));
+    }
+
+    #[test]
+    fn the_picker_starts_on_the_current_workspace() {
+        let picker = WorkspacePicker::new(vec![entry("home", false), entry("foster", true)]);
+        assert_eq!(picker.selected(), 1);
+        let picker = WorkspacePicker::new(vec![entry("home", false), entry("foster", false)]);
+        assert_eq!(picker.selected(), 0);
+    }
+
+    #[test]
+    fn the_picker_moves_within_the_list_and_chooses() {
+        let mut picker = WorkspacePicker::new(vec![
+            entry("home", true),
+            entry("foster", false),
+            entry("default", false),
+        ]);
+        press(&mut picker, KeyCode::Char('k'));
+        assert_eq!(picker.selected(), 0);
+        for _ in 0..3 {
+            press(&mut picker, KeyCode::Down);
+        }
+        assert_eq!(picker.selected(), 2);
+        press(&mut picker, KeyCode::Up);
+        match press(&mut picker, KeyCode::Enter) {
+            PickerOutcome::Chosen(chosen) => assert_eq!(chosen.name, "foster"),
+            _ => panic!("nothing chosen"),
+        }
+        assert!(matches!(
+            press(&mut picker, KeyCode::Esc),
+            PickerOutcome::Cancelled
+        ));
+        let mut empty = WorkspacePicker::new(Vec::new());
+        assert!(matches!(
+            press(&mut empty, KeyCode::Enter),
+            PickerOutcome::Cancelled
+        ));
+    }
+}
@@ -0,0 +1,111 @@
+//! The last pets yanked with `y`/`Y` or cut with `X`, most recent first, as
+//! they were at the time. `"` lists them, and Enter adds the one selected
+//! again as a new pet, even once the original is gone.
+
+use crate::Pet;
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+
+/// The most pets kept; older ones are forgotten.
+pub const CAPACITY: usize = 10;
+
+pub struct Yanked {
+    pub pet: Pet,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct YankRing {
+    entries: VecDeque<Yanked>,
+}
+
+impl YankRing {
+    /// Keeps `pet` as the most recent entry. Yanking the same pet again
+    /// without changing it only updates the time, so that a repeated `y`
+    /// doesn't fill the ring with copies.
+    pub fn push(&mut self, pet: &Pet, at: DateTime<Utc>) {
+        if let Some(latest) = self.entries.front_mut() {
+            if same(&latest.pet, pet) {
+                latest.at = at;
+                return;
+            }
+        }
+        self.entries.push_front(Yanked {
+            pet: pet.clone(),
+            at,
+        });
+        self.entries.truncate(CAPACITY);
+    }
+
+    pub fn entries(&self) -> &VecDeque<Yanked> {
+        &self.entries
+    }
+}
+
+/// Pets have no `PartialEq`, with fields pet-cli doesn't know kept as JSON,
+/// so they are compared as they would be stored.
+fn same(a: &Pet, b: &Pet) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_tests::pet;
+    use chrono::TimeZone;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, minute, 0).unwrap()
+    }
+
+    fn names(ring: &YankRing) -> Vec<&str> {
+        ring.entries()
+            .iter()
+            .map(|yanked| yanked.pet.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn the_most_recent_yank_comes_first() {
+        let mut ring = YankRing::default();
+        ring.push(&pet(1, "Coco", "cats"), at(0));
+        ring.push(&pet(2, "Rex", "dogs"), at(1));
+        assert_eq!(names(&ring), ["Rex", "Coco"]);
+        assert_eq!(ring.entries()[0].at, at(1));
+    }
+
+    #[test]
+    fn yanking_the_same_pet_again_only_updates_the_time() {
+        let mut ring = YankRing::default();
+        let coco = pet(1, "Coco", "cats");
+        ring.push(&coco, at(0));
+        ring.push(&coco, at(5));
+        assert_eq!(names(&ring), ["Coco"]);
+        assert_eq!(ring.entries()[0].at, at(5));
+    }
+
+    #[test]
+    fn a_pet_changed_since_or_yanked_in_between_is_kept_again() {
+        let mut ring = YankRing::default();
+        let coco = pet(1, "Coco", "cats");
+        ring.push(&coco, at(0));
+        ring.push(&pet(2, "Rex", "dogs"), at(1));
+        ring.push(&coco, at(2));
+        assert_eq!(names(&ring), ["Coco", "Rex", "Coco"]);
+        let mut renamed = coco.clone();
+        renamed.name = "Cocoa".to_owned();
+        ring.push(&renamed, at(3));
+        assert_eq!(names(&ring), ["Cocoa", "Coco", "Rex", "Coco"]);
+    }
+
+    #[test]
+    fn the_oldest_yanks_are_forgotten() {
+        let mut ring = YankRing::default();
+        for id in 1..=CAPACITY + 2 {
+            ring.push(&pet(id, &format!("Pet{}", id), "dogs"), at(id as u32));
+        }
+        assert_eq!(ring.entries().len(), CAPACITY);
+        assert_eq!(ring.entries().front().unwrap().pet.id, CAPACITY + 2);
+        assert_eq!(ring.entries().back().unwrap().pet.id, 3);
+    }
+}
@@ -0,0 +1,111 @@
+//! End-to-end tests of the real binary in a pseudo-terminal: what unit tests
+//! can't see, like raw mode and the alternate screen being undone on the way
+//! out. They need a Unix pseudo-terminal, so they only build on Unix. They
+//! type at the speed of a person and take a few seconds, so they are ignored
+//! by default; run them with `cargo test --test e2e -- --ignored`.
+
+#![cfg(unix)]
+
+mod pty;
+
+use pty::Session;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// The names of the pets in the DB outside the Trash, in DB order; none
+/// while the DB is being replaced.
+fn pets(dir: &Path) -> Vec<String> {
+    let Some(db) = fs::read_to_string(dir.join("data/db.json"))
+        .ok()
+        .and_then(|db| serde_json::from_str::<Value>(&db).ok())
+    else {
+        return Vec::new();
+    };
+    let pets = db.get("pets").unwrap_or(&db);
+    pets.as_array()
+        .expect("a list of pets")
+        .iter()
+        .filter(|pet| pet.get("archived_at").is_none_or(Value::is_null))
+        .map(|pet| pet["name"].as_str().expect("a name").to_owned())
+        .collect()
+}
+
+/// Puts an empty DB in `dir`, so that the first-run setup is skipped.
+fn empty_db(dir: &Path) {
+    fs::create_dir_all(dir.join("data")).expect("the data directory");
+    fs::write(dir.join("data/db.json"), "[]").expect("the DB is written");
+}
+
+/// Checks the binary left the terminal as it found it: the modes raw mode
+/// changes back, the alternate screen left and the cursor shown.
+fn assert_terminal_restored(session: &Session) {
+    let (before, after) = (pty::fresh_modes(), session.modes());
+    assert_eq!(after.c_lflag, before.c_lflag, "local modes");
+    assert_eq!(after.c_iflag, before.c_iflag, "input modes");
+    assert_eq!(after.c_oflag, before.c_oflag, "output modes");
+    let output = String::from_utf8_lossy(&session.raw_output()).into_owned();
+    let entered = output
+        .rfind("\x1b[?1049h")
+        .expect("the alternate screen was used");
+    let left = output
+        .rfind("\x1b[?1049l")
+        .expect("the alternate screen was left");
+    assert!(left > entered, "the alternate screen was left last");
+    let shown = output.rfind("\x1b[?25h").expect("the cursor was shown");
+    assert!(shown > output.rfind("\x1b[?25l").unwrap_or(0));
+}
+
+#[test]
+#[ignore]
+fn add_navigate_delete_and_quit() {
+    let mut session = Session::start(&[], empty_db);
+    session.wait_for("Home");
+    session.type_keys("p");
+    session.wait_for("Detail");
+    for (count, pet) in ["Pip, cats, 3m", "Rex, dogs, 2y", "Bun, rabbits, 1y"]
+        .iter()
+        .enumerate()
+    {
+        session.ctrl('a');
+        session.type_keys(pet);
+        session.type_keys("\r");
+        let dir = session.dir().to_owned();
+        session.wait_until(pet, || pets(&dir).len() == count + 1);
+    }
+    session.type_keys("ggjdd");
+    let dir = session.dir().to_owned();
+    session.wait_until("Rex to go to the Trash", || pets(&dir).len() == 2);
+    session.type_keys("q");
+    let status = session.wait();
+    assert!(status.success(), "pet-cli exited with {}", status);
+    assert_eq!(pets(session.dir()), ["Pip", "Bun"]);
+    assert_terminal_restored(&session);
+}
+
+#[test]
+#[ignore]
+fn quitting_straight_away_restores_the_terminal() {
+    let mut session = Session::start(&[], empty_db);
+    session.wait_for("Home");
+    session.type_keys("q");
+    let status = session.wait();
+    assert!(status.success(), "pet-cli exited with {}", status);
+    assert_terminal_restored(&session);
+}
+
+#[test]
+#[ignore]
+fn a_broken_config_file_still_starts_in_safe_mode() {
+    let mut session = Session::start(&["--safe-mode"], |dir| {
+        empty_db(dir);
+        let config = dir.join(".config/pet-cli");
+        fs::create_dir_all(&config).expect("the config directory");
+        fs::write(config.join("config.toml"), "theme = \"plaid\"\n").expect("the config");
+    });
+    session.wait_for("Home");
+    session.type_keys("q");
+    let status = session.wait();
+    assert!(status.success(), "pet-cli exited with {}", status);
+    assert_terminal_restored(&session);
+}
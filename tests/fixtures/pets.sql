@@ -0,0 +1,46 @@
+-- Makes pets.sqlite, the fixture of src/sqlite.rs' tests:
+--   rm -f pets.sqlite && sqlite3 pets.sqlite < pets.sql
+-- Small pages, so that a few hundred rows need interior pages and a long
+-- note overflow pages.
+PRAGMA page_size = 512;
+
+CREATE TABLE pets (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    "pet category" TEXT DEFAULT 'cats',
+    age_months INTEGER CHECK (age_months >= 0),
+    notes TEXT,
+    UNIQUE (name, "pet category")
+);
+
+WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 300)
+INSERT INTO pets (id, name, "pet category", age_months, notes)
+SELECT i, 'pet ' || i, CASE i % 3 WHEN 0 THEN 'cats' WHEN 1 THEN 'dogs' ELSE 'birds' END,
+       i % 150, NULL
+FROM n;
+
+-- Past what fits in a 512-byte page, over several overflow pages.
+UPDATE pets SET notes = (
+    WITH RECURSIVE n(i) AS (SELECT 0 UNION ALL SELECT i + 1 FROM n WHERE i < 399)
+    SELECT group_concat(printf('%04d', i), '') FROM n
+) WHERE id = 150;
+
+-- A value of every serial type, in order, and a row to the side.
+CREATE TABLE [kinds] (`kind` TEXT, value);
+INSERT INTO kinds VALUES
+    ('null', NULL),
+    ('zero', 0),
+    ('one', 1),
+    ('int8', -100),
+    ('int16', 1000),
+    ('int24', -1000000),
+    ('int32', 2000000000),
+    ('int48', 100000000000),
+    ('int64', -9000000000000000000),
+    ('real', 2.5),
+    ('blob', x'00ff10'),
+    ('text', 'héllo'),
+    ('empty text', ''),
+    ('empty blob', x'');
+
+CREATE INDEX pets_by_name ON pets (name);
@@ -0,0 +1,56 @@
+//! `pet-cli list` with its output piped, as a script would run it.
+
+use std::fs;
+use std::process::Command;
+
+/// The output of `pet-cli list` and `args`, piped, on a DB of Coco and Rex.
+fn list(name: &str, args: &[&str]) -> String {
+    let dir = std::env::temp_dir().join(format!("pet-cli-list-{}-{}", std::process::id(), name));
+    fs::create_dir_all(dir.join("data")).expect("a temporary directory");
+    fs::write(
+        dir.join("data/db.json"),
+        r#"[
+            {"id": 1, "name": "Coco", "category": "cats", "age_months": 14, "created_at": "2024-01-01T00:00:00Z"},
+            {"id": 12, "name": "Rex", "category": "dogs", "age_months": 37, "created_at": "2024-02-01T00:00:00Z"}
+        ]"#,
+    )
+    .expect("the DB is written");
+    let output = Command::new(env!("CARGO_BIN_EXE_pet-cli"))
+        .arg("list")
+        .args(args)
+        .current_dir(&dir)
+        .env("HOME", &dir)
+        .env("XDG_CONFIG_HOME", dir.join(".config"))
+        .env("XDG_CACHE_HOME", dir.join(".cache"))
+        .output()
+        .expect("pet-cli runs");
+    fs::remove_dir_all(&dir).expect("the directory is removed");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("UTF-8")
+}
+
+#[test]
+fn piped_output_is_tsv_by_default() {
+    assert_eq!(
+        list("default", &[]),
+        "ID\tName\tCategory\tAge\tCreated\n\
+         1\tCoco\tcats\t1 y 2 m\t2024-01-01\n\
+         12\tRex\tdogs\t3 y 1 m\t2024-02-01\n"
+    );
+}
+
+#[test]
+fn a_format_given_wins_over_the_pipe() {
+    assert_eq!(
+        list("table", &["--format", "table"]),
+        "ID  Name  Category      Age  Created\n \
+         1  Coco  cats      1 y 2 m  2024-01-01\n\
+         12  Rex   dogs      3 y 1 m  2024-02-01\n"
+    );
+    let json = list("json", &["--format", "json"]);
+    assert!(json.starts_with("[{\"id\":1,"), "{}", json);
+}
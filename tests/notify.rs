@@ -0,0 +1,109 @@
+//! `pet-cli notify-daemon`, run as cron or a service manager would.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::{Duration, Instant};
+
+/// A directory with a DB of Rex, who is long overdue a checkup.
+fn setup(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pet-cli-notify-{}-{}", std::process::id(), name));
+    fs::create_dir_all(dir.join("data")).expect("a temporary directory");
+    fs::write(
+        dir.join("data/db.json"),
+        r#"[
+            {"id": 12, "name": "Rex", "category": "dogs", "age_months": 37,
+             "created_at": "2020-01-01T00:00:00Z",
+             "records": [{"date": "2020-01-05", "kind": "checkup", "note": ""}]}
+        ]"#,
+    )
+    .expect("the DB is written");
+    dir
+}
+
+fn pet_cli(dir: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_pet-cli"));
+    command
+        .arg("notify-daemon")
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env("XDG_CONFIG_HOME", dir.join(".config"))
+        .env("XDG_CACHE_HOME", dir.join(".cache"));
+    command
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    pet_cli(dir, args).output().expect("pet-cli runs")
+}
+
+#[test]
+fn once_sends_a_reminder_a_single_time_within_the_snooze() {
+    let dir = setup("once");
+    for _ in 0..2 {
+        let output = run(&dir, &["--once", "--file", "reminders.log"]);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let log = fs::read_to_string(dir.join("reminders.log")).expect("the file channel is written");
+    let sent = fs::read_to_string(dir.join("data/notified.json")).expect("the store is written");
+    fs::remove_dir_all(&dir).expect("the directory is removed");
+    assert_eq!(log.lines().count(), 1, "{}", log);
+    assert!(
+        log.ends_with("\tRex: last medical record on 2020-01-05\n"),
+        "{}",
+        log
+    );
+    assert!(sent.contains("\"12:checkup:2020-01-05\""), "{}", sent);
+}
+
+#[test]
+fn without_a_channel_nothing_runs() {
+    let dir = setup("nowhere");
+    let output = run(&dir, &["--once"]);
+    fs::remove_dir_all(&dir).expect("the directory is removed");
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("there is nowhere to send reminders"),
+        "{}",
+        stderr
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn sigterm_stops_the_daemon_cleanly() {
+    let dir = setup("sigterm");
+    let mut child = pet_cli(&dir, &["--file", "reminders.log"])
+        .spawn()
+        .expect("pet-cli starts");
+    let started = Instant::now();
+    while !dir.join("data/notified.json").exists() {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "no first check"
+        );
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    // SAFETY: kill only sends a signal to the child started above.
+    assert_eq!(unsafe { libc::kill(child.id() as i32, libc::SIGTERM) }, 0);
+    let stopping = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().expect("the child is waited on") {
+            break status;
+        }
+        assert!(
+            stopping.elapsed() < Duration::from_secs(5),
+            "still running after SIGTERM"
+        );
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let log = fs::read_to_string(dir.join("reminders.log")).expect("the file channel is written");
+    fs::remove_dir_all(&dir).expect("the directory is removed");
+    assert!(status.success(), "{:?}", status);
+    assert_eq!(log.lines().count(), 1, "{}", log);
+}
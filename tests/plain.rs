@@ -0,0 +1,178 @@
+//! `pet-cli --plain` driven like a screen reader or a script would: commands
+//! piped into stdin, and what is printed read back from stdout.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const DB: &str = r#"[
+    {"id": 1, "name": "Coco", "category": "cats", "age_months": 14, "created_at": "2024-01-01T00:00:00Z"},
+    {"id": 2, "name": "Rex", "category": "dogs", "age_months": 37, "created_at": "2024-02-01T00:00:00Z"}
+]"#;
+
+/// Runs `pet-cli --plain` on a DB of Coco and Rex with `script` as stdin,
+/// and returns what it printed and the directory it ran in.
+fn run(script: &str) -> (String, PathBuf) {
+    run_with(&[], script)
+}
+
+/// [`run`] with more flags.
+fn run_with(args: &[&str], script: &str) -> (String, PathBuf) {
+    let (output, dir) = spawn(args, script, |_| ());
+    assert!(
+        output.status.success(),
+        "pet-cli exited with {}: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (String::from_utf8(output.stdout).expect("UTF-8"), dir)
+}
+
+/// Runs `pet-cli --plain` and `args` in a directory with the DB and what
+/// `setup` adds, with `script` as stdin, however it exits.
+fn spawn(args: &[&str], script: &str, setup: impl FnOnce(&Path)) -> (Output, PathBuf) {
+    static DIRS: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "pet-cli-plain-{}-{}",
+        std::process::id(),
+        DIRS.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(dir.join("data")).expect("a temporary directory");
+    fs::write(dir.join("data/db.json"), DB).expect("the DB is written");
+    setup(&dir);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pet-cli"))
+        .arg("--plain")
+        .args(args)
+        .current_dir(&dir)
+        .env("HOME", &dir)
+        .env("XDG_CONFIG_HOME", dir.join(".config"))
+        .env("XDG_CACHE_HOME", dir.join(".cache"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("pet-cli starts");
+    // pet-cli may exit before reading it, as it does on a bad config.
+    let _ = child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(script.as_bytes());
+    (child.wait_with_output().expect("pet-cli runs"), dir)
+}
+
+#[test]
+fn prints_the_list_then_the_results_of_each_command() {
+    let (output, dir) = run("show 2\nselect 9\nselect 2\nnonsense\n:delete\nlist\n");
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines[..3],
+        [
+            "2 pets, every pet",
+            "#1 Coco, [available], selected",
+            "#2 Rex, [available]"
+        ]
+    );
+    assert!(lines.contains(&"Name: Rex"), "{}", output);
+    assert!(lines.contains(&"Age: 3 y 1 m"), "{}", output);
+    assert!(lines.contains(&"error: no pet #9"), "{}", output);
+    assert!(
+        lines.contains(&"#2 Rex, [available], selected"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("error: unknown command 'nonsense', expected one of: list, show,"),
+        "{}",
+        output
+    );
+    assert!(lines.contains(&"moved Rex (#2) to the Trash"), "{}", output);
+    let deleted = lines
+        .iter()
+        .position(|line| *line == "moved Rex (#2) to the Trash")
+        .expect("Rex was deleted");
+    assert_eq!(
+        lines[deleted + 1..deleted + 3],
+        ["1 pet, every pet", "#1 Coco, [available], selected"]
+    );
+    // No escape sequences: nothing but text for a screen reader to read.
+    assert!(!output.contains('\x1b'), "{:?}", output);
+    let db = fs::read_to_string(dir.join("data/db.json")).expect("the DB");
+    assert!(db.contains("archived_at"), "{}", db);
+    fs::remove_dir_all(dir).expect("the directory is removed");
+}
+
+#[test]
+fn help_lists_plain_and_command_line_commands() {
+    let (output, dir) = run("help\n");
+    assert!(
+        output.contains("show [ID]: print the fields of a pet, the selected one by default\n"),
+        "{}",
+        output
+    );
+    assert!(output.contains("\nsort [KEY [asc|desc]]\n"), "{}", output);
+    fs::remove_dir_all(dir).expect("the directory is removed");
+}
+
+#[test]
+fn quit_ignores_the_rest_of_the_script() {
+    let (output, dir) = run("quit\n:delete\n");
+    assert!(!output.contains("Trash"), "{}", output);
+    let db = fs::read_to_string(dir.join("data/db.json")).expect("the DB");
+    assert!(!db.contains("archived_at"), "{}", db);
+    fs::remove_dir_all(dir).expect("the directory is removed");
+}
+
+#[test]
+fn what_the_session_changed_is_printed_last_unless_quiet() {
+    let (output, dir) = run(":delete\n");
+    assert_eq!(
+        output.lines().last(),
+        Some("session: 1 pet deleted, db written once, path: ./data/db.json"),
+        "{}",
+        output
+    );
+    fs::remove_dir_all(dir).expect("the directory is removed");
+    let (output, dir) = run("list\n");
+    assert_eq!(
+        output.lines().last(),
+        Some("session: no changes, path: ./data/db.json"),
+        "{}",
+        output
+    );
+    fs::remove_dir_all(dir).expect("the directory is removed");
+    let (output, dir) = run_with(&["--quiet"], ":delete\n");
+    assert!(!output.contains("session:"), "{}", output);
+    fs::remove_dir_all(dir).expect("the directory is removed");
+}
+
+#[test]
+fn a_broken_config_file_still_starts_in_safe_mode() {
+    let broken = |dir: &Path| {
+        let config = dir.join(".config/pet-cli");
+        fs::create_dir_all(&config).expect("the config directory");
+        fs::write(
+            config.join("config.toml"),
+            "theme = \"plaid\"\nlist_width =\n",
+        )
+        .expect("the config is written");
+    };
+    let (output, dir) = spawn(&[], "list\n", broken);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--safe-mode"), "{}", stderr);
+    fs::remove_dir_all(dir).expect("the directory is removed");
+
+    let (output, dir) = spawn(&["--safe-mode"], "list\n", broken);
+    assert!(
+        output.status.success(),
+        "pet-cli exited with {}: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("#2 Rex, [available]"), "{}", stdout);
+    fs::remove_dir_all(dir).expect("the directory is removed");
+}
@@ -0,0 +1,240 @@
+//! Runs pet-cli in a pseudo-terminal, as a user's terminal would, for the
+//! end-to-end tests: a [`Session`] starts the binary in a temporary
+//! directory of its own, types keys and waits for the screen to show text.
+
+#![cfg(unix)]
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long `wait_for` and `wait` give the binary.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Session {
+    child: Child,
+    master: File,
+    /// Kept open to read the terminal's modes after the binary is gone.
+    slave: File,
+    /// Everything the binary wrote to the terminal so far.
+    output: Arc<Mutex<Vec<u8>>>,
+    /// How much of `output` `wait_for` has looked through.
+    seen: usize,
+    dir: PathBuf,
+}
+
+impl Session {
+    /// Starts pet-cli with `args` on an 100x30 terminal, in a directory with
+    /// nothing but what `setup` puts there.
+    pub fn start(args: &[&str], setup: impl FnOnce(&Path)) -> Session {
+        static DIRS: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "pet-cli-e2e-{}-{}",
+            std::process::id(),
+            DIRS.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).expect("a temporary directory");
+        setup(&dir);
+        let (master, slave) = open_pty(100, 30);
+        let mut command = Command::new(env!("CARGO_BIN_EXE_pet-cli"));
+        command
+            .args(args)
+            .current_dir(&dir)
+            .env("HOME", &dir)
+            .env("XDG_CONFIG_HOME", dir.join(".config"))
+            .env("XDG_CACHE_HOME", dir.join(".cache"))
+            .env("TERM", "xterm-256color")
+            .env_remove("NO_COLOR")
+            .stdin(stdio(&slave))
+            .stdout(stdio(&slave))
+            .stderr(stdio(&slave));
+        let slave_fd = slave.as_raw_fd();
+        // SAFETY: only async-signal-safe calls between fork and exec.
+        unsafe {
+            command.pre_exec(move || {
+                if libc::setsid() == -1 || libc::ioctl(slave_fd, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn().expect("pet-cli starts");
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut reader = master.try_clone().expect("the PTY's master");
+        let sink = Arc::clone(&output);
+        thread::spawn(move || {
+            let mut buffer = [0; 4096];
+            // Ends with EIO once the binary and the slave are gone.
+            while let Ok(read @ 1..) = reader.read(&mut buffer) {
+                sink.lock().unwrap().extend_from_slice(&buffer[..read]);
+            }
+        });
+        Session {
+            child,
+            master,
+            slave,
+            output,
+            seen: 0,
+            dir,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Types `keys`, one at a time with a pause in between like a person,
+    /// so each is its own key press rather than a paste.
+    pub fn type_keys(&mut self, keys: &str) {
+        for byte in keys.bytes() {
+            self.master.write_all(&[byte]).expect("the key is sent");
+            thread::sleep(Duration::from_millis(30));
+        }
+    }
+
+    /// Presses Ctrl and `key`.
+    pub fn ctrl(&mut self, key: char) {
+        let byte = key.to_ascii_lowercase() as u8 & 0x1f;
+        self.type_keys(std::str::from_utf8(&[byte]).unwrap());
+    }
+
+    /// Waits until the binary has written `text` since the last call, and
+    /// panics with what it wrote if it doesn't in time. tui only redraws
+    /// the cells that change and moves the cursor over blank ones, so `text`
+    /// has to be new on screen and is best a single word.
+    pub fn wait_for(&mut self, text: &str) {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            {
+                let output = self.output.lock().unwrap();
+                if strip_escapes(&output[self.seen..]).contains(text) {
+                    self.seen = output.len();
+                    return;
+                }
+                assert!(
+                    Instant::now() < deadline,
+                    "pet-cli didn't show {:?}; its output was:\n{}",
+                    text,
+                    strip_escapes(&output[self.seen..])
+                );
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    /// Waits until `done`, like the DB having a pet, and panics naming
+    /// `what` if it isn't in time.
+    pub fn wait_until(&self, what: &str, mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + TIMEOUT;
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    /// Waits for the binary to exit, killing it if it doesn't in time.
+    pub fn wait(&mut self) -> ExitStatus {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            if let Some(status) = self.child.try_wait().expect("the binary's status") {
+                return status;
+            }
+            if Instant::now() >= deadline {
+                let _ = self.child.kill();
+                panic!("pet-cli didn't exit");
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    /// Everything the binary wrote, escape sequences and all.
+    pub fn raw_output(&self) -> Vec<u8> {
+        self.output.lock().unwrap().clone()
+    }
+
+    /// The terminal's modes as the binary left them.
+    pub fn modes(&self) -> libc::termios {
+        termios(self.slave.as_raw_fd())
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// The modes a terminal starts in, before anything changed them.
+pub fn fresh_modes() -> libc::termios {
+    let (_master, slave) = open_pty(100, 30);
+    termios(slave.as_raw_fd())
+}
+
+fn open_pty(cols: u16, rows: u16) -> (File, File) {
+    let mut master: RawFd = -1;
+    let mut slave: RawFd = -1;
+    let size = libc::winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: the pointers are to locals that outlive the call.
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            &size,
+        )
+    };
+    assert_eq!(result, 0, "openpty: {}", std::io::Error::last_os_error());
+    // SAFETY: openpty just opened both and nothing else owns them.
+    unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) }
+}
+
+fn stdio(file: &File) -> Stdio {
+    Stdio::from(file.try_clone().expect("the PTY's slave"))
+}
+
+fn termios(fd: RawFd) -> libc::termios {
+    // SAFETY: termios is plain data, and tcgetattr fills it in.
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::tcgetattr(fd, &mut termios) };
+    assert_eq!(result, 0, "tcgetattr: {}", std::io::Error::last_os_error());
+    termios
+}
+
+/// `output` without its CSI and OSC escape sequences, so that what reads as
+/// one word on screen is one in the text, as far as tui writes it that way.
+pub fn strip_escapes(output: &[u8]) -> String {
+    let text = String::from_utf8_lossy(output);
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            plain.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                chars.find(|c| ('\x40'..='\x7e').contains(c));
+            }
+            Some(']') => {
+                chars.find(|c| *c == '\x07');
+            }
+            _ => {}
+        }
+    }
+    plain
+}